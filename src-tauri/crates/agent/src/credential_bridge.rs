@@ -167,6 +167,13 @@ impl CredentialBridge {
                 base_url.clone(),
             ),
 
+            // 本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM）- API Key 可选
+            CredentialData::LocalOpenAI { base_url, api_key } => (
+                "ollama".to_string(),
+                api_key.clone(),
+                Some(base_url.clone()),
+            ),
+
             // Kiro OAuth - 需要获取 access_token
             CredentialData::KiroOAuth { creds_file_path } => {
                 let token = self
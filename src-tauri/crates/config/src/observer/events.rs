@@ -115,6 +115,9 @@ pub struct EndpointProvidersChangeEvent {
     pub windsurf: Option<String>,
     pub kiro: Option<String>,
     pub other: Option<String>,
+    /// 已停用的客户端类型
+    #[serde(default)]
+    pub disabled: Vec<String>,
     /// 变更来源
     pub source: ConfigChangeSource,
 }
@@ -202,4 +202,58 @@ mod tests {
             .observer_names()
             .contains(&"LoggingObserver".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_switch_profile_updates_router() {
+        use proxycast_core::config::ConfigManager;
+        use proxycast_core::ProviderType;
+
+        let path = PathBuf::from("/tmp/test_config.yaml");
+        let mut config = Config::default();
+        config.routing.default_provider = "kiro".to_string();
+        let manager = GlobalConfigManager::new(config.clone(), path.clone());
+
+        let router = Arc::new(RwLock::new(Router::new(ProviderType::Kiro)));
+        let mapper = Arc::new(RwLock::new(ModelMapper::new()));
+        let injector = Arc::new(RwLock::new(Injector::new()));
+        manager.register_router_observers(router.clone(), mapper.clone(), injector);
+
+        // 工作档案：Kiro；个人档案：Gemini + 模型别名
+        let mut profiles = ConfigManager::with_config(config, path);
+        profiles.create_profile("work").unwrap();
+        profiles.config_mut().routing.default_provider = "gemini".to_string();
+        profiles
+            .config_mut()
+            .routing
+            .model_aliases
+            .insert("fast".to_string(), "gemini-2.5-flash".to_string());
+        profiles.create_profile("personal").unwrap();
+        assert_eq!(profiles.list_profiles(), vec!["personal", "work"]);
+
+        let switched = profiles.switch_profile("work").unwrap().clone();
+        manager
+            .update_config(switched, ConfigChangeSource::FrontendUI)
+            .await;
+        assert_eq!(
+            router.read().await.default_provider(),
+            Some(ProviderType::Kiro)
+        );
+        assert_eq!(mapper.read().await.resolve("fast"), "fast");
+
+        let switched = profiles.switch_profile("personal").unwrap().clone();
+        manager
+            .update_config(switched, ConfigChangeSource::FrontendUI)
+            .await;
+        assert_eq!(
+            router.read().await.default_provider(),
+            Some(ProviderType::Gemini)
+        );
+        assert_eq!(mapper.read().await.resolve("fast"), "gemini-2.5-flash");
+        assert_eq!(manager.config().active_profile.as_deref(), Some("personal"));
+
+        // 当前激活的档案不能删除
+        assert!(profiles.delete_profile("personal").is_err());
+        profiles.delete_profile("work").unwrap();
+        assert_eq!(profiles.list_profiles(), vec!["personal"]);
+    }
 }
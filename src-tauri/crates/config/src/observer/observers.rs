@@ -48,11 +48,10 @@ impl ConfigObserver for RouterObserver {
         _event: &ConfigChangeEvent,
         config: &Config,
     ) -> Result<(), String> {
-        // 更新默认 Provider
+        // 更新默认 Provider（含 routing.provider_protocols 中声明的协议）
         if let Ok(provider_type) = config
             .routing
-            .default_provider
-            .parse::<proxycast_core::ProviderType>()
+            .resolve_provider_type(&config.routing.default_provider)
         {
             let mut router = self.router.write().await;
             router.set_default_provider(provider_type);
@@ -198,6 +197,9 @@ impl ConfigObserver for LoggingObserver {
             config.logging.enabled,
             config.logging.level
         );
+        if let Err(e) = proxycast_core::log_filter::apply_config_level(&config.logging.level) {
+            tracing::warn!("[LoggingObserver] 应用日志级别失败: {}", e);
+        }
         Ok(())
     }
 }
@@ -0,0 +1,91 @@
+//! 构建信息
+//!
+//! 汇总版本号、git commit、构建时间和已编译的 cargo features，
+//! 供 `GET /version` 和 Tauri 命令返回，便于排障时确认用户所用的构建。
+//!
+//! core crate 只能感知自身的 features，主 crate 的 features
+//! 需在启动时通过 [`register_features`] 登记。
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 主 crate 登记的 features
+static REGISTERED_FEATURES: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+/// 构建信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// 版本号
+    pub version: String,
+    /// 构建时的 git commit（无法获取时为 `unknown`）
+    pub git_commit: String,
+    /// 构建时间（RFC 3339）
+    pub build_timestamp: String,
+    /// 已启用的 cargo features（已排序去重）
+    pub features: Vec<String>,
+}
+
+/// 登记主 crate 编译时启用的 features
+///
+/// 只有第一次登记生效，重复调用会被忽略。
+pub fn register_features(features: &[&'static str]) {
+    let _ = REGISTERED_FEATURES.set(features.to_vec());
+}
+
+/// core crate 自身启用的 features
+fn core_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "keychain") {
+        features.push("keychain");
+    }
+    features
+}
+
+/// 获取当前构建信息
+pub fn build_info() -> BuildInfo {
+    let mut features: Vec<String> = core_features()
+        .into_iter()
+        .chain(REGISTERED_FEATURES.get().into_iter().flatten().copied())
+        .map(str::to_string)
+        .collect();
+    features.sort();
+    features.dedup();
+
+    let build_timestamp = env!("PROXYCAST_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    BuildInfo {
+        version: crate::version().to_string(),
+        git_commit: env!("PROXYCAST_GIT_COMMIT").to_string(),
+        build_timestamp,
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_includes_registered_features() {
+        register_features(&["custom-protocol", "local-whisper", "custom-protocol"]);
+
+        let info = build_info();
+        assert_eq!(info.version, crate::version());
+        assert!(!info.git_commit.is_empty());
+        assert!(chrono::DateTime::parse_from_rfc3339(&info.build_timestamp).is_ok());
+        assert!(info.features.contains(&"custom-protocol".to_string()));
+        assert!(info.features.contains(&"local-whisper".to_string()));
+        assert_eq!(
+            info.features
+                .iter()
+                .filter(|f| *f == "custom-protocol")
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! 服务器 API 密钥校验
+//!
+//! 除了单个 `server.api_key`，还可以在 `server.api_keys` 中配置多个密钥：
+//! 轮换时先加入新密钥、客户端切换后再删除旧密钥，不会一次断开所有客户端；
+//! 也可以给每个客户端签发单独的密钥，匹配到的密钥标签会记入请求日志。
+
+use super::types::{ServerApiKey, ServerConfig};
+
+/// 密钥校验结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeyMatch {
+    /// 有效密钥，附带密钥标签（`server.api_key` 或未设置标签时为 `None`）
+    Accepted { label: Option<String> },
+    /// 密钥已停用
+    Disabled,
+    /// 未知密钥
+    Invalid,
+}
+
+/// 服务器接受的 API 密钥集合
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerApiKeys {
+    /// `server.api_key`（向后兼容的单个密钥）
+    primary: String,
+    /// `server.api_keys`
+    keys: Vec<ServerApiKey>,
+}
+
+impl ServerApiKeys {
+    pub fn new(primary: impl Into<String>, keys: Vec<ServerApiKey>) -> Self {
+        Self {
+            primary: primary.into(),
+            keys,
+        }
+    }
+
+    /// 从服务器配置构建
+    pub fn from_config(server: &ServerConfig) -> Self {
+        Self::new(server.api_key.clone(), server.api_keys.clone())
+    }
+
+    /// `server.api_key`
+    pub fn primary(&self) -> &str {
+        &self.primary
+    }
+
+    /// 校验客户端提供的密钥
+    ///
+    /// 先匹配 `api_keys` 中的条目（停用的条目直接拒绝，即使与 `api_key` 相同），
+    /// 再匹配 `api_key`。
+    pub fn check(&self, key: &str) -> ApiKeyMatch {
+        if let Some(entry) = self.keys.iter().find(|entry| entry.key == key) {
+            if !entry.enabled {
+                return ApiKeyMatch::Disabled;
+            }
+            let label = Some(entry.label.clone()).filter(|label| !label.is_empty());
+            return ApiKeyMatch::Accepted { label };
+        }
+        if key == self.primary {
+            ApiKeyMatch::Accepted { label: None }
+        } else {
+            ApiKeyMatch::Invalid
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+
+    fn key(key: &str, label: &str, enabled: bool) -> ServerApiKey {
+        ServerApiKey {
+            key: key.to_string(),
+            label: label.to_string(),
+            enabled,
+        }
+    }
+
+    #[test]
+    fn test_multiple_keys_accepted_with_labels() {
+        let keys = ServerApiKeys::new(
+            "legacy-key",
+            vec![
+                key("new-key", "rotation-2026", true),
+                key("ci-key", "ci", true),
+                key("unlabeled-key", "", true),
+            ],
+        );
+
+        // 旧的单个密钥继续有效
+        assert_eq!(
+            keys.check("legacy-key"),
+            ApiKeyMatch::Accepted { label: None }
+        );
+        assert_eq!(
+            keys.check("new-key"),
+            ApiKeyMatch::Accepted {
+                label: Some("rotation-2026".to_string())
+            }
+        );
+        assert_eq!(
+            keys.check("ci-key"),
+            ApiKeyMatch::Accepted {
+                label: Some("ci".to_string())
+            }
+        );
+        assert_eq!(
+            keys.check("unlabeled-key"),
+            ApiKeyMatch::Accepted { label: None }
+        );
+        assert_eq!(keys.check("unknown"), ApiKeyMatch::Invalid);
+    }
+
+    #[test]
+    fn test_disabled_key_rejected() {
+        let keys = ServerApiKeys::new(
+            "legacy-key",
+            vec![
+                key("old-key", "old", false),
+                key("legacy-key", "legacy", false),
+            ],
+        );
+        assert_eq!(keys.check("old-key"), ApiKeyMatch::Disabled);
+        // 停用条目优先于 `api_key`
+        assert_eq!(keys.check("legacy-key"), ApiKeyMatch::Disabled);
+    }
+
+    #[test]
+    fn test_keys_from_yaml_config() {
+        let config = ConfigManager::parse_yaml(
+            r#"
+server:
+  api_key: legacy-key
+  api_keys:
+    - key: new-key
+      label: laptop
+    - key: old-key
+      label: retired
+      enabled: false
+"#,
+        )
+        .unwrap();
+        let keys = ServerApiKeys::from_config(&config.server);
+        assert_eq!(keys.primary(), "legacy-key");
+        assert_eq!(
+            keys.check("new-key"),
+            ApiKeyMatch::Accepted {
+                label: Some("laptop".to_string())
+            }
+        );
+        assert_eq!(keys.check("old-key"), ApiKeyMatch::Disabled);
+    }
+}
@@ -0,0 +1,177 @@
+//! 生效配置
+//!
+//! 服务器实际使用的配置可能与磁盘上的配置文件不同：热重载只应用部分字段，
+//! 管理 API / 前端还会在运行时修改默认 Provider 等设置而不重启服务器，
+//! 监听地址也可能在启动时被自动切换。本模块把这些运行时覆盖叠加到加载的配置上，
+//! 得到服务器当前真正生效的配置（敏感信息已脱敏），用于排查“设置为什么没生效”。
+
+use super::export::ExportService;
+use super::types::{Config, EndpointProvidersConfig};
+
+/// 运行时覆盖（服务器运行期间生效、可能未写回配置文件的设置）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuntimeOverrides {
+    /// 当前默认 Provider（管理 API / 前端切换后的值）
+    pub default_provider: Option<String>,
+    /// 实际监听的地址
+    pub host: Option<String>,
+    /// 实际监听的端口
+    pub port: Option<u16>,
+    /// 实际使用的 API Key（输出时脱敏）
+    pub api_key: Option<String>,
+    /// 端点 Provider 配置（含管理 API 运行时停用的端点）
+    pub endpoint_providers: Option<EndpointProvidersConfig>,
+}
+
+impl RuntimeOverrides {
+    /// 把运行时覆盖应用到配置
+    pub fn apply_to(&self, config: &mut Config) {
+        if let Some(provider) = &self.default_provider {
+            config.default_provider = provider.clone();
+            config.routing.default_provider = provider.clone();
+        }
+        if let Some(host) = &self.host {
+            config.server.host = host.clone();
+        }
+        if let Some(port) = self.port {
+            config.server.port = port;
+        }
+        if let Some(api_key) = &self.api_key {
+            config.server.api_key = api_key.clone();
+        }
+        if let Some(endpoint_providers) = &self.endpoint_providers {
+            config.endpoint_providers = endpoint_providers.clone();
+        }
+    }
+}
+
+/// 计算生效配置：叠加运行时覆盖后脱敏
+pub fn effective_config(config: &Config, overrides: &RuntimeOverrides) -> Config {
+    let mut effective = config.clone();
+    overrides.apply_to(&mut effective);
+    ExportService::redact_config(&effective)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, REDACTED_PLACEHOLDER};
+
+    #[test]
+    fn test_effective_config_reflects_runtime_overrides() {
+        let loaded = ConfigManager::parse_yaml(
+            r#"
+server:
+  host: 0.0.0.0
+  port: 9000
+  api_key: file-secret-key
+default_provider: kiro
+routing:
+  default_provider: kiro
+remote_management:
+  secret_key: management-secret-key
+"#,
+        )
+        .unwrap();
+        let overrides = RuntimeOverrides {
+            default_provider: Some("gemini".to_string()),
+            host: Some("192.168.1.20".to_string()),
+            port: None,
+            api_key: Some("running-secret-key".to_string()),
+            endpoint_providers: None,
+        };
+
+        let effective = effective_config(&loaded, &overrides);
+
+        // 运行时覆盖优先于配置文件
+        assert_eq!(effective.default_provider, "gemini");
+        assert_eq!(effective.routing.default_provider, "gemini");
+        assert_eq!(effective.server.host, "192.168.1.20");
+        // 未覆盖的字段保持加载时的值
+        assert_eq!(effective.server.port, 9000);
+        // 敏感信息脱敏
+        assert_eq!(effective.server.api_key, REDACTED_PLACEHOLDER);
+        assert_eq!(
+            effective.remote_management.secret_key.as_deref(),
+            Some(REDACTED_PLACEHOLDER)
+        );
+        let json = serde_json::to_string(&effective).unwrap();
+        assert!(!json.contains("secret-key"));
+        // 加载的配置本身不受影响
+        assert_eq!(loaded.default_provider, "kiro");
+        assert_eq!(loaded.server.api_key, "file-secret-key");
+    }
+
+    /// 通过正常的文件加载路径（含环境变量替换）读取配置
+    fn load_with_env(vars: &[(&str, &str)], yaml: &str) -> Config {
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, yaml).unwrap();
+        ConfigManager::load(&path).unwrap().config().clone()
+    }
+
+    #[test]
+    fn test_effective_config_keeps_env_substituted_values() {
+        let loaded = load_with_env(
+            &[("PROXYCAST_EFFECTIVE_TEST_PORT", "9123")],
+            r#"
+server:
+  host: 0.0.0.0
+  port: ${PROXYCAST_EFFECTIVE_TEST_PORT}
+default_provider: kiro
+"#,
+        );
+        assert_eq!(loaded.server.port, 9123);
+
+        let overrides = RuntimeOverrides {
+            default_provider: Some("gemini".to_string()),
+            ..Default::default()
+        };
+        let effective = effective_config(&loaded, &overrides);
+
+        // 环境变量替换的值与运行时覆盖同时生效
+        assert_eq!(effective.server.port, 9123);
+        assert_eq!(effective.default_provider, "gemini");
+    }
+
+    #[test]
+    fn test_runtime_override_wins_over_env_substituted_value() {
+        let loaded = load_with_env(
+            &[
+                ("PROXYCAST_EFFECTIVE_TEST_HOST", "10.0.0.5"),
+                ("PROXYCAST_EFFECTIVE_TEST_KEY", "env-secret-key"),
+            ],
+            r#"
+server:
+  host: ${PROXYCAST_EFFECTIVE_TEST_HOST}
+  api_key: ${PROXYCAST_EFFECTIVE_TEST_KEY}
+"#,
+        );
+        assert_eq!(loaded.server.host, "10.0.0.5");
+        assert_eq!(loaded.server.api_key, "env-secret-key");
+
+        let overrides = RuntimeOverrides {
+            host: Some("127.0.0.1".to_string()),
+            ..Default::default()
+        };
+        let effective = effective_config(&loaded, &overrides);
+
+        assert_eq!(effective.server.host, "127.0.0.1");
+        // 替换进来的密钥同样脱敏
+        assert_eq!(effective.server.api_key, REDACTED_PLACEHOLDER);
+        let json = serde_json::to_string(&effective).unwrap();
+        assert!(!json.contains("env-secret-key"));
+    }
+
+    #[test]
+    fn test_effective_config_without_overrides_matches_loaded() {
+        let loaded = Config::default();
+        let effective = effective_config(&loaded, &RuntimeOverrides::default());
+        assert_eq!(effective.default_provider, loaded.default_provider);
+        assert_eq!(effective.server.host, loaded.server.host);
+        assert_eq!(effective.server.port, loaded.server.port);
+    }
+}
@@ -418,6 +418,20 @@ impl HotReloadManager {
             ));
         }
 
+        // 验证路由别名
+        crate::middleware::validate_route_aliases(&config.server.route_aliases)
+            .map_err(HotReloadError::ValidationError)?;
+
+        // 验证选择器路由前缀
+        crate::router::normalize_selector_prefix(&config.server.selector_prefix)
+            .map_err(HotReloadError::ValidationError)?;
+
+        // 验证响应后处理器
+        crate::response_postprocess::PostProcessorChain::from_config(
+            &config.server.response_post_processors,
+        )
+        .map_err(|e| HotReloadError::ValidationError(e.to_string()))?;
+
         if config.server.tls.enable {
             return Err(HotReloadError::ValidationError(
                 "当前版本暂不支持 TLS，请关闭 TLS 配置".to_string(),
@@ -663,6 +677,32 @@ server:
         }
     }
 
+    #[test]
+    fn test_hot_reload_rejects_invalid_post_processor() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let yaml_content = r#"
+server:
+  host: "127.0.0.1"
+  port: 8999
+  api_key: "test-key"
+  response_post_processors:
+    - type: regex_redact
+      pattern: "("
+"#;
+        temp_file.write_all(yaml_content.as_bytes()).unwrap();
+
+        let config = Config::default();
+        let manager = HotReloadManager::new(config.clone(), temp_file.path().to_path_buf());
+
+        match manager.reload() {
+            ReloadResult::RolledBack { error, .. } => {
+                assert!(error.contains("正则表达式"), "error: {error}");
+                assert_eq!(manager.config(), config);
+            }
+            _ => panic!("Expected RolledBack result"),
+        }
+    }
+
     #[test]
     fn test_config_change_kind_eq() {
         assert_eq!(ConfigChangeKind::Modified, ConfigChangeKind::Modified);
@@ -346,6 +346,9 @@ impl ImportService {
         // 合并日志配置
         merged.logging = imported.logging.clone();
 
+        // 合并遥测配置
+        merged.telemetry = imported.telemetry.clone();
+
         // 合并注入配置
         merged.injection = imported.injection.clone();
 
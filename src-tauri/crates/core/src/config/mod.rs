@@ -5,6 +5,8 @@
 
 #![allow(unused_imports)]
 
+mod api_keys;
+mod effective;
 mod export;
 mod hot_reload;
 mod import;
@@ -12,7 +14,11 @@ mod path_utils;
 mod types;
 mod yaml;
 
-pub use export::{ExportBundle, ExportOptions, ExportService, REDACTED_PLACEHOLDER};
+pub use api_keys::{ApiKeyMatch, ServerApiKeys};
+pub use effective::{effective_config, RuntimeOverrides};
+pub use export::{
+    redact_url_credentials, ExportBundle, ExportOptions, ExportService, REDACTED_PLACEHOLDER,
+};
 pub use hot_reload::{
     ConfigChangeEvent as FileChangeEvent, ConfigChangeKind, FileWatcher, HotReloadManager,
     ReloadResult,
@@ -20,16 +26,22 @@ pub use hot_reload::{
 pub use import::{ImportOptions, ImportService, ValidationResult};
 pub use path_utils::{collapse_tilde, contains_tilde, expand_tilde};
 pub use types::{
-    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, AsrCredentialEntry,
-    AsrProviderType, AssistantConfig, AssistantProfile, BaiduConfig, ChatAppearanceConfig, Config,
-    ContentCreatorConfig, CredentialEntry, CredentialPoolConfig, CustomProviderConfig,
-    EndpointProvidersConfig, ExperimentalFeatures, GeminiApiKeyEntry, ImageGenConfig,
-    InjectionRuleConfig, InjectionSettings, LoggingConfig, MemoryConfig, ModelInfo, ModelsConfig,
-    NativeAgentConfig, NavigationConfig, OpenAIAsrConfig, ProviderConfig, ProviderModelsConfig,
-    ProvidersConfig, QuotaExceededConfig, RemoteManagementConfig, RetrySettings, RoutingConfig,
-    ScreenshotChatConfig, ServerConfig, TlsConfig, UpdateCheckConfig, UserProfile,
-    VertexApiKeyEntry, VertexModelAlias, VoiceConfig, VoiceInputConfig, VoiceInstruction,
-    VoiceOutputConfig, VoiceOutputMode, VoiceProcessorConfig, WhisperLocalConfig, WhisperModelSize,
-    XunfeiConfig, DEFAULT_API_KEY,
+    generate_secure_api_key, AmpConfig, AmpModelMapping, AnthropicConfig, ApiKeyEntry,
+    AsrCredentialEntry, AsrProviderType, AssistantConfig, AssistantProfile, BaiduConfig,
+    ChatAppearanceConfig, CircuitBreakerSettings, ClientDetectionConfig, ClientSignatureRule,
+    Config, ConfigProfile, ConflictPolicy, ContentCreatorConfig, CooldownRecoveryConfig,
+    CredentialEntry, CredentialPoolConfig, CredentialSelectionStrategy, CredentialsConfig,
+    CustomProviderConfig, EndpointProvidersConfig, ExperimentalFeatures, ExternalSyncConfig,
+    ExternalSyncPolicy, FailoverChain, GeminiApiKeyEntry, HealthAlertConfig, HealthProbeConfig,
+    ImageGenConfig, InjectionRuleConfig, InjectionSettings, JitterMode, LoggingConfig,
+    MemoryConfig, ModelInfo, ModelsConfig, NativeAgentConfig, NavigationConfig, OpenAIAsrConfig,
+    PostProcessorConfig, ProviderConfig, ProviderModelsConfig, ProvidersConfig,
+    QuotaExceededConfig, RedactionConfig, RemoteManagementConfig, RetrySettings, RoutingConfig,
+    ScreenshotChatConfig, SecretBackend, SecretStorageConfig, ServerApiKey, ServerConfig,
+    SessionQuotaConfig, StreamCoalesceConfig, StreamCompatConfig, TelemetryConfig, TlsConfig,
+    UpdateCheckConfig, UpstreamPoolConfig, UserProfile, VertexApiKeyEntry, VertexModelAlias,
+    VoiceConfig, VoiceInputConfig, VoiceInstruction, VoiceOutputConfig, VoiceOutputMode,
+    VoiceProcessorConfig, WarmupConfig, WhisperLocalConfig, WhisperModelSize, XunfeiConfig,
+    DEFAULT_API_KEY,
 };
 pub use yaml::{load_config, save_config, ConfigError, ConfigManager, YamlService};
@@ -4,9 +4,10 @@
 
 use crate::config::types::{ContentCreatorConfig, NavigationConfig};
 use crate::config::{
-    collapse_tilde, contains_tilde, expand_tilde, Config, ConfigManager, CustomProviderConfig,
-    HotReloadManager, InjectionSettings, LoggingConfig, ProviderConfig, ProvidersConfig,
-    ReloadResult, RetrySettings, RoutingConfig, ServerConfig, YamlService,
+    collapse_tilde, contains_tilde, expand_tilde, CircuitBreakerSettings, Config, ConfigManager,
+    CustomProviderConfig, HotReloadManager, InjectionSettings, JitterMode, LoggingConfig,
+    ProviderConfig, ProvidersConfig, ReloadResult, RetrySettings, RoutingConfig, ServerConfig,
+    YamlService,
 };
 use proptest::prelude::*;
 use std::io::Write;
@@ -38,7 +39,31 @@ fn arb_server_config() -> impl Strategy<Value = ServerConfig> {
         host,
         port,
         api_key,
+        api_keys: Vec::new(),
         tls: crate::config::TlsConfig::default(),
+        upstream_pool: crate::config::UpstreamPoolConfig::default(),
+        validate_tools: true,
+        warmup: crate::config::WarmupConfig::default(),
+        session_quota: crate::config::SessionQuotaConfig::default(),
+        bind_interface: None,
+        route_aliases: std::collections::HashMap::new(),
+        stream_heartbeat_secs: 15,
+        emulate_multiple_choices: false,
+        max_concurrent_requests: 0,
+        concurrency_wait_ms: 5000,
+        coalesce_tool_calls: false,
+        provider_headers: std::collections::HashMap::new(),
+        response_post_processors: Vec::new(),
+        stream_idle_timeout_ms: 300_000,
+        strict_structured_output: false,
+        allow_upstream_override: false,
+        force_buffer_stream: false,
+        allow_self_upstream: false,
+        dedup_window_ms: 0,
+        stream_compat: crate::config::StreamCompatConfig::default(),
+        shutdown_drain_secs: 10,
+        selector_prefix: String::new(),
+        stream_coalesce: crate::config::StreamCoalesceConfig::default(),
     })
 }
 
@@ -117,6 +142,8 @@ fn arb_routing_config() -> impl Strategy<Value = RoutingConfig> {
         .prop_map(|(default_provider, model_aliases)| RoutingConfig {
             default_provider,
             model_aliases,
+            failover_chains: Vec::new(),
+            provider_protocols: std::collections::HashMap::new(),
         })
 }
 
@@ -133,7 +160,10 @@ fn arb_retry_settings() -> impl Strategy<Value = RetrySettings> {
                 max_retries,
                 base_delay_ms,
                 max_delay_ms,
+                jitter: JitterMode::default(),
                 auto_switch_provider,
+                provider_fallback: Default::default(),
+                circuit_breaker: CircuitBreakerSettings::default(),
             },
         )
 }
@@ -157,6 +187,7 @@ fn arb_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                redaction: crate::config::RedactionConfig::default(),
             },
         )
 }
@@ -356,7 +387,31 @@ fn arb_valid_server_config() -> impl Strategy<Value = ServerConfig> {
         host,
         port,
         api_key,
+        api_keys: Vec::new(),
         tls: crate::config::TlsConfig::default(),
+        upstream_pool: crate::config::UpstreamPoolConfig::default(),
+        validate_tools: true,
+        warmup: crate::config::WarmupConfig::default(),
+        session_quota: crate::config::SessionQuotaConfig::default(),
+        bind_interface: None,
+        route_aliases: std::collections::HashMap::new(),
+        stream_heartbeat_secs: 15,
+        emulate_multiple_choices: false,
+        max_concurrent_requests: 0,
+        concurrency_wait_ms: 5000,
+        coalesce_tool_calls: false,
+        provider_headers: std::collections::HashMap::new(),
+        response_post_processors: Vec::new(),
+        stream_idle_timeout_ms: 300_000,
+        strict_structured_output: false,
+        allow_upstream_override: false,
+        force_buffer_stream: false,
+        allow_self_upstream: false,
+        dedup_window_ms: 0,
+        stream_compat: crate::config::StreamCompatConfig::default(),
+        shutdown_drain_secs: 10,
+        selector_prefix: String::new(),
+        stream_coalesce: crate::config::StreamCoalesceConfig::default(),
     })
 }
 
@@ -373,7 +428,10 @@ fn arb_valid_retry_settings() -> impl Strategy<Value = RetrySettings> {
                 max_retries,
                 base_delay_ms,
                 max_delay_ms,
+                jitter: JitterMode::default(),
                 auto_switch_provider,
+                provider_fallback: Default::default(),
+                circuit_breaker: CircuitBreakerSettings::default(),
             },
         )
 }
@@ -397,6 +455,7 @@ fn arb_valid_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                redaction: crate::config::RedactionConfig::default(),
             },
         )
 }
@@ -2383,6 +2442,7 @@ fn arb_endpoint_providers_config() -> impl Strategy<Value = EndpointProvidersCon
                 windsurf,
                 kiro,
                 other,
+                disabled: Vec::new(),
             }
         })
 }
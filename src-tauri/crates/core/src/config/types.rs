@@ -4,6 +4,8 @@
 //! 保持与旧版 JSON 配置的向后兼容性
 
 use crate::models::injection_types::{InjectionMode, InjectionRule};
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::models::provider_type::{is_custom_provider_id, ProviderType};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -43,6 +45,65 @@ pub struct CredentialPoolConfig {
     pub asr: Vec<AsrCredentialEntry>,
 }
 
+/// 凭证管理行为配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CredentialsConfig {
+    /// 配置热重载时禁用已从 `credential_pool` 中移除的凭证
+    ///
+    /// 被禁用的凭证保留使用统计，但不再参与选择；关闭时（默认）保持不变
+    #[serde(default)]
+    pub prune_on_reload: bool,
+    /// 冷却恢复：因连续错误被标记为不健康的凭证在冷却期结束后自动恢复
+    #[serde(default)]
+    pub cooldown_recovery: CooldownRecoveryConfig,
+    /// 凭证选择策略
+    #[serde(default)]
+    pub selection_strategy: CredentialSelectionStrategy,
+}
+
+/// 凭证选择策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSelectionStrategy {
+    /// 综合评分：健康状态、使用次数、错误率和距上次使用时间（默认）
+    #[default]
+    Score,
+    /// 最快优先：选择响应延迟 EWMA 最低的凭证，定期探索其他凭证以更新延迟统计
+    FastestFirst,
+}
+
+/// 凭证冷却恢复配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CooldownRecoveryConfig {
+    /// 是否自动恢复（关闭时需手动重置健康状态）
+    #[serde(default = "default_cooldown_recovery_enabled")]
+    pub enabled: bool,
+    /// 冷却时间（秒），从最后一次错误开始计算
+    #[serde(default = "default_cooldown_recovery_secs")]
+    pub cooldown_secs: u64,
+    /// 恢复前是否先通过健康检查，未通过时重新冷却
+    #[serde(default)]
+    pub health_check: bool,
+}
+
+fn default_cooldown_recovery_enabled() -> bool {
+    true
+}
+
+fn default_cooldown_recovery_secs() -> u64 {
+    300
+}
+
+impl Default for CooldownRecoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cooldown_recovery_enabled(),
+            cooldown_secs: default_cooldown_recovery_secs(),
+            health_check: false,
+        }
+    }
+}
+
 // ============ ASR 语音服务配置类型 ============
 
 /// ASR Provider 类型
@@ -272,9 +333,42 @@ pub struct EndpointProvidersConfig {
     /// 如果为空，则使用 default_provider
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub other: Option<String>,
+    /// 已停用的客户端类型（配置键名），停用后该客户端的请求返回 503
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<String>,
 }
 
 impl EndpointProvidersConfig {
+    /// 支持的客户端类型配置键名
+    pub const CLIENT_TYPES: [&'static str; 6] = [
+        "cursor",
+        "claude_code",
+        "codex",
+        "windsurf",
+        "kiro",
+        "other",
+    ];
+
+    /// 客户端类型是否启用
+    pub fn is_enabled(&self, client_type: &str) -> bool {
+        !self.disabled.iter().any(|d| d == client_type)
+    }
+
+    /// 启用或停用客户端类型
+    ///
+    /// # 返回
+    /// 如果客户端类型有效，返回 true；否则返回 false
+    pub fn set_enabled(&mut self, client_type: &str, enabled: bool) -> bool {
+        if !Self::CLIENT_TYPES.contains(&client_type) {
+            return false;
+        }
+        self.disabled.retain(|d| d != client_type);
+        if !enabled {
+            self.disabled.push(client_type.to_string());
+        }
+        true
+    }
+
     /// 根据客户端类型获取配置的 Provider
     ///
     /// # 参数
@@ -334,6 +428,42 @@ impl EndpointProvidersConfig {
     }
 }
 
+/// 客户端识别配置
+///
+/// 自定义规则按顺序匹配，优先于内置的 User-Agent 识别；均未命中时按内置规则处理。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ClientDetectionConfig {
+    /// 自定义客户端识别规则
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ClientSignatureRule>,
+}
+
+/// 客户端识别规则（请求头特征 -> 客户端 ID）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClientSignatureRule {
+    /// 客户端 ID（用于日志和遥测，如 "cline"）
+    pub id: String,
+    /// 匹配的请求头名称
+    #[serde(default = "default_client_signature_header")]
+    pub header: String,
+    /// 请求头值中包含的特征字符串（不区分大小写）
+    pub pattern: String,
+    /// 按哪种客户端类型选择端点 Provider（cursor, claude_code, codex, windsurf, kiro, other）
+    /// 为空时若 `id` 本身是客户端类型则使用 `id`，否则为 other
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_type: Option<String>,
+    /// 请求未指定模型（空或 "default"）时使用的模型（可为别名）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// 仅对该客户端应用的参数注入规则 ID，为空时应用全部规则
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub injection_rules: Vec<String>,
+}
+
+fn default_client_signature_header() -> String {
+    "user-agent".to_string()
+}
+
 /// 主配置结构
 ///
 /// 支持两种格式：
@@ -359,6 +489,9 @@ pub struct Config {
     /// 日志配置
     #[serde(default)]
     pub logging: LoggingConfig,
+    /// 遥测配置
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
     /// 参数注入配置
     #[serde(default)]
     pub injection: InjectionSettings,
@@ -368,6 +501,9 @@ pub struct Config {
     /// 凭证池配置
     #[serde(default)]
     pub credential_pool: CredentialPoolConfig,
+    /// 凭证管理行为配置
+    #[serde(default)]
+    pub credentials: CredentialsConfig,
     /// 远程管理配置
     #[serde(default)]
     pub remote_management: RemoteManagementConfig,
@@ -384,6 +520,9 @@ pub struct Config {
     /// 允许为不同的客户端端点（CC/Codex）配置不同的 Provider
     #[serde(default)]
     pub endpoint_providers: EndpointProvidersConfig,
+    /// 客户端识别配置
+    #[serde(default)]
+    pub client_detection: ClientDetectionConfig,
     /// 关闭时最小化到托盘（而不是退出应用）
     #[serde(default = "default_minimize_to_tray")]
     pub minimize_to_tray: bool,
@@ -423,6 +562,204 @@ pub struct Config {
     /// 用户资料
     #[serde(default)]
     pub user_profile: UserProfile,
+    /// 凭证密钥存储配置
+    #[serde(default)]
+    pub secret_storage: SecretStorageConfig,
+    /// 命名配置档案（如工作/个人），共享凭证池
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, ConfigProfile>,
+    /// 当前激活的配置档案名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// 数据库文件路径（为空时使用 `~/.proxycast/proxycast.db`），
+    /// 环境变量 `PROXYCAST_DB_PATH` 优先；修改后需重启生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<String>,
+    /// 外部应用配置（Prompt / MCP）同步配置
+    #[serde(default)]
+    pub external_sync: ExternalSyncConfig,
+    /// 凭证健康告警配置
+    #[serde(default)]
+    pub health_alert: HealthAlertConfig,
+    /// 健康检查探测模型配置
+    #[serde(default)]
+    pub health_probe: HealthProbeConfig,
+    /// Anthropic 协议请求处理配置
+    #[serde(default)]
+    pub anthropic: AnthropicConfig,
+}
+
+// ============ 配置档案 ============
+
+/// 配置档案
+///
+/// 保存可按档案切换的服务器、路由、参数注入和端点 Provider 设置；
+/// 凭证池等其余配置在所有档案之间共享。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigProfile {
+    /// 服务器配置
+    #[serde(default)]
+    pub server: ServerConfig,
+    /// 默认 Provider
+    #[serde(default = "default_provider")]
+    pub default_provider: String,
+    /// 路由配置
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// 参数注入配置
+    #[serde(default)]
+    pub injection: InjectionSettings,
+    /// 端点 Provider 配置
+    #[serde(default)]
+    pub endpoint_providers: EndpointProvidersConfig,
+}
+
+impl ConfigProfile {
+    /// 从当前配置中提取档案设置
+    pub fn capture(config: &Config) -> Self {
+        Self {
+            server: config.server.clone(),
+            default_provider: config.default_provider.clone(),
+            routing: config.routing.clone(),
+            injection: config.injection.clone(),
+            endpoint_providers: config.endpoint_providers.clone(),
+        }
+    }
+
+    /// 将档案设置应用到配置
+    pub fn apply_to(&self, config: &mut Config) {
+        config.server = self.server.clone();
+        config.default_provider = self.default_provider.clone();
+        config.routing = self.routing.clone();
+        config.injection = self.injection.clone();
+        config.endpoint_providers = self.endpoint_providers.clone();
+    }
+}
+
+// ============ 凭证密钥存储配置 ============
+
+/// 凭证密钥存储后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    /// 内联保存在凭证行中（默认，与旧版本兼容）
+    #[default]
+    Inline,
+    /// SQLite 数据库的独立密钥表
+    Sqlite,
+    /// 系统钥匙串（macOS Keychain / Windows 凭据管理器），需启用 `keychain` 特性
+    Keychain,
+}
+
+/// 凭证密钥存储配置
+///
+/// API Key 等密钥字段通过配置的后端保存，凭证的其他元数据仍保存在 SQLite 中。
+/// 默认内联保存；切换后端后，下次启动时会自动把密钥迁移到新后端。
+/// 降级到不支持密钥存储的版本前，需先切回 `inline` 并启动一次，把密钥写回凭证行。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SecretStorageConfig {
+    /// 存储后端
+    #[serde(default)]
+    pub backend: SecretBackend,
+}
+
+// ============ 外部配置同步 ============
+
+/// 外部配置文件被手动修改时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalSyncPolicy {
+    /// 检测到外部修改时放弃写入并报告冲突（默认）
+    #[default]
+    Abort,
+    /// 合并互不重叠的条目，仅在同一条目两侧都修改时放弃写入
+    MergeNonOverlapping,
+}
+
+/// Provider 配置同步到外部配置文件时的字段冲突解决策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// 冲突时以 ProxyCast 的值为准（默认）
+    #[default]
+    PreferLocal,
+    /// 冲突时以外部文件的值为准
+    PreferRemote,
+    /// 冲突时保留外部值，并将冲突列表交由 UI 处理
+    Manual,
+}
+
+/// 外部应用配置（CLAUDE.md、MCP 配置等）同步配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ExternalSyncConfig {
+    /// 冲突处理策略
+    #[serde(default)]
+    pub conflict_policy: ExternalSyncPolicy,
+    /// 切换 Provider 时三方合并的字段冲突策略
+    #[serde(default)]
+    pub provider_conflict_policy: ConflictPolicy,
+}
+
+/// 凭证健康告警配置
+///
+/// 某个 Provider 类型的健康凭证数降为 0 时发出告警，恢复到至少 1 个时发出解除通知。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthAlertConfig {
+    /// 告警 Webhook 地址（为空时仅发送前端事件）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// 同一 Provider 两次通知之间的最小间隔（秒），避免状态抖动时重复告警
+    #[serde(default = "default_health_alert_debounce_secs")]
+    pub debounce_secs: u64,
+}
+
+fn default_health_alert_debounce_secs() -> u64 {
+    300
+}
+
+impl Default for HealthAlertConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            debounce_secs: default_health_alert_debounce_secs(),
+        }
+    }
+}
+
+/// 健康检查探测模型配置
+///
+/// 内置的探测模型下线或账号无权使用时，可按 Provider 类型指定其他模型；
+/// 凭证自身的 `check_model_name` 优先于此配置。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HealthProbeConfig {
+    /// Provider 类型（如 `openai`、`claude`）到探测模型的映射
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub models: HashMap<String, String>,
+}
+
+/// Anthropic 协议请求处理配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnthropicConfig {
+    /// 是否把请求中的 `metadata`（`user_id`）转发给上游；关闭时剥离
+    #[serde(default)]
+    pub forward_user_metadata: bool,
+    /// 转发前校验 `metadata.user_id` 是否为不透明标识，明显包含个人信息
+    /// （邮箱、电话号码等）时拒绝请求
+    #[serde(default = "default_validate_user_metadata")]
+    pub validate_user_metadata: bool,
+}
+
+fn default_validate_user_metadata() -> bool {
+    true
+}
+
+impl Default for AnthropicConfig {
+    fn default() -> Self {
+        Self {
+            forward_user_metadata: false,
+            validate_user_metadata: default_validate_user_metadata(),
+        }
+    }
 }
 
 // ============ Native Agent 配置类型 ============
@@ -905,9 +1242,336 @@ pub struct ServerConfig {
     /// API 密钥
     #[serde(default = "default_api_key")]
     pub api_key: String,
+    /// 额外的 API 密钥（与 `api_key` 同时有效）
+    ///
+    /// 用于不停机轮换（先加新密钥再删旧密钥）和按客户端签发密钥，
+    /// 匹配到的密钥标签会记入请求日志
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_keys: Vec<ServerApiKey>,
     /// TLS 配置
     #[serde(default)]
     pub tls: TlsConfig,
+    /// 上游 HTTP 连接池配置
+    #[serde(default)]
+    pub upstream_pool: UpstreamPoolConfig,
+    /// 是否在转发前校验 Anthropic 工具定义（input_schema / tool_choice）
+    #[serde(default = "default_validate_tools")]
+    pub validate_tools: bool,
+    /// 启动预热配置
+    #[serde(default)]
+    pub warmup: WarmupConfig,
+    /// 按会话（`X-ProxyCast-Session`）限流配置
+    #[serde(default)]
+    pub session_quota: SessionQuotaConfig,
+    /// 绑定的网卡名称（如 `en0`、`eth0`）
+    ///
+    /// 设置后监听该网卡的 IPv4 地址，忽略 `host`
+    #[serde(default)]
+    pub bind_interface: Option<String>,
+    /// 路由别名（额外路径 -> 规范路径），如 `/v1/complete` -> `/v1/messages`
+    ///
+    /// 启动时构建到路由中；不允许覆盖已有的规范路由
+    #[serde(default)]
+    pub route_aliases: HashMap<String, String>,
+    /// 流式响应等待首个上游数据块时的心跳间隔（秒），0 表示不发送
+    #[serde(default = "default_stream_heartbeat_secs")]
+    pub stream_heartbeat_secs: u64,
+    /// Provider 不支持 `n` > 1 时，是否通过并发多次调用模拟多候选回复
+    ///
+    /// 关闭时此类请求返回 400；流式请求始终不模拟
+    #[serde(default)]
+    pub emulate_multiple_choices: bool,
+    /// 全局聊天请求并发上限，0 表示不限制
+    ///
+    /// 超出时请求排队等待 `concurrency_wait_ms`，仍无名额则返回 503
+    #[serde(default)]
+    pub max_concurrent_requests: usize,
+    /// 并发已满时的最长排队时间（毫秒）
+    #[serde(default = "default_concurrency_wait_ms")]
+    pub concurrency_wait_ms: u64,
+    /// OpenAI 流式响应中是否把工具调用参数增量合并为一个完整的工具调用块
+    ///
+    /// 开启后在工具调用结束时一次性发送校验过的完整参数
+    #[serde(default)]
+    pub coalesce_tool_calls: bool,
+    /// 按 Provider 类型配置的默认请求头（如 `User-Agent`），键为 Provider 类型（如 `claude`）
+    ///
+    /// 与内置默认值合并，同名时以配置为准，值为空表示去掉该内置请求头；
+    /// 默认请求头不会覆盖请求本身携带的认证等请求头，Kiro、Codex OAuth 在每个请求上
+    /// 设置的客户端特征请求头（如 Kiro 的设备指纹 `User-Agent`）同样优先
+    #[serde(default)]
+    pub provider_headers: HashMap<String, HashMap<String, String>>,
+    /// 响应后处理器链（按顺序作用于非流式响应的文本内容）
+    #[serde(default)]
+    pub response_post_processors: Vec<PostProcessorConfig>,
+    /// 流式响应空闲超时（毫秒）：连续这么久没有收到上游数据时中止上游并返回错误帧，0 表示不启用
+    #[serde(default = "default_stream_idle_timeout_ms")]
+    pub stream_idle_timeout_ms: u64,
+    /// 结构化输出严格模式
+    ///
+    /// Provider 不支持原生 `response_format` 时，模型输出无法修复为合法 JSON
+    /// 或不符合 Schema 时返回 502；关闭时原样返回
+    #[serde(default)]
+    pub strict_structured_output: bool,
+    /// 是否允许请求级上游地址覆盖（调试用）
+    ///
+    /// 启用后 `X-ProxyCast-Upstream-Base` 请求头可把单个请求指向其他上游地址；
+    /// 存在 SSRF 风险，默认关闭
+    #[serde(default)]
+    pub allow_upstream_override: bool,
+    /// 是否把流式请求在服务端缓冲为非流式响应
+    ///
+    /// 适用于无法消费 SSE 的客户端；单个请求可用 `X-ProxyCast-Buffer` 请求头覆盖
+    #[serde(default)]
+    pub force_buffer_stream: bool,
+    /// 是否允许上游地址指向服务器自身的监听地址
+    ///
+    /// 默认拒绝此类上游，避免误配的凭证导致请求在代理内部循环转发
+    #[serde(default)]
+    pub allow_self_upstream: bool,
+    /// 相同请求合并窗口（毫秒），0 表示不启用
+    ///
+    /// 启用后 API Key、模型和请求体都相同的并发非流式聊天请求共享同一次上游调用，
+    /// 上游调用完成后该窗口内到达的相同请求也复用同一响应
+    #[serde(default)]
+    pub dedup_window_ms: u64,
+    /// 流式响应代理兼容配置
+    #[serde(default)]
+    pub stream_compat: StreamCompatConfig,
+    /// 停止服务器时等待在途请求完成的最长时间（秒），超时后中止剩余请求
+    #[serde(default = "default_shutdown_drain_secs")]
+    pub shutdown_drain_secs: u64,
+    /// 多供应商选择器路由前缀，如 `/p` 时路由为 `/p/{selector}/v1/messages`
+    ///
+    /// 为空时选择器路由挂在根路径下（`/{selector}/v1/messages`）
+    #[serde(default)]
+    pub selector_prefix: String,
+    /// 流式响应合并配置（把细碎的上游增量合并为较少的客户端帧）
+    #[serde(default)]
+    pub stream_coalesce: StreamCoalesceConfig,
+}
+
+/// 服务器 API 密钥条目
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerApiKey {
+    /// 密钥
+    pub key: String,
+    /// 标签（如客户端名称），用于请求日志
+    #[serde(default)]
+    pub label: String,
+    /// 是否启用，停用的密钥会被拒绝
+    #[serde(default = "default_server_api_key_enabled")]
+    pub enabled: bool,
+}
+
+/// 响应后处理器配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostProcessorConfig {
+    /// 将匹配正则的内容替换为 `replacement`（默认 `[REDACTED]`）
+    RegexRedact {
+        /// 正则表达式
+        pattern: String,
+        /// 替换文本，支持 `$1` 等捕获组引用
+        #[serde(default = "default_redact_replacement")]
+        replacement: String,
+    },
+    /// 在内容末尾追加文本（如免责声明）
+    AppendText {
+        /// 追加的文本
+        text: String,
+    },
+    /// 去掉包裹整段内容的 Markdown 代码块标记
+    StripCodeFences,
+}
+
+fn default_redact_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+/// 会话配额配置
+///
+/// 以 `X-ProxyCast-Session` 请求头区分会话，在滑动窗口内限制请求数和 Token 数，
+/// 超出时返回 429。未携带该请求头的请求不受限制
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionQuotaConfig {
+    /// 是否启用会话配额
+    #[serde(default)]
+    pub enabled: bool,
+    /// 窗口内最大请求数（0 表示不限制）
+    #[serde(default = "default_session_quota_max_requests")]
+    pub max_requests: u32,
+    /// 窗口内最大 Token 数（0 表示不限制）
+    #[serde(default)]
+    pub max_tokens: u64,
+    /// 滑动窗口长度（秒）
+    #[serde(default = "default_session_quota_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_session_quota_max_requests() -> u32 {
+    60
+}
+
+fn default_session_quota_window_secs() -> u64 {
+    60
+}
+
+impl Default for SessionQuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_requests: default_session_quota_max_requests(),
+            max_tokens: 0,
+            window_secs: default_session_quota_window_secs(),
+        }
+    }
+}
+
+/// 流式响应代理兼容配置
+///
+/// 部分企业代理会缓冲 SSE，导致客户端迟迟收不到数据。启用后流式响应附带
+/// `X-Accel-Buffering: no`、`Cache-Control: no-cache`，并在响应开头立即发送
+/// 一个 SSE 注释行，可选填充到指定字节数以冲刷中间代理的缓冲区
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StreamCompatConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 首个注释行的填充字节数（如 2048），0 表示不填充
+    #[serde(default)]
+    pub padding_bytes: usize,
+}
+
+/// 流式响应合并配置
+///
+/// 部分 Provider 每个 token 发送一个 SSE 事件，帧开销很大。启用后把连续的文本增量
+/// 合并为一帧，缓冲达到 `max_bytes` 或自首个缓冲事件起超过 `max_ms` 时发出；
+/// 工具调用增量和结束事件立即发出，不会被滞留
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamCoalesceConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓冲字节数上限，达到后立即发出
+    #[serde(default = "default_stream_coalesce_max_bytes")]
+    pub max_bytes: usize,
+    /// 最长缓冲时间（毫秒）
+    #[serde(default = "default_stream_coalesce_max_ms")]
+    pub max_ms: u64,
+}
+
+fn default_stream_coalesce_max_bytes() -> usize {
+    1024
+}
+
+fn default_stream_coalesce_max_ms() -> u64 {
+    50
+}
+
+impl Default for StreamCoalesceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: default_stream_coalesce_max_bytes(),
+            max_ms: default_stream_coalesce_max_ms(),
+        }
+    }
+}
+
+/// Provider 启动预热配置
+///
+/// 凭证加载完成后并发刷新即将过期的 Token，并为 Gemini/Antigravity 凭证解析 project id，
+/// 避免重启后第一个请求承担刷新和发现的延迟
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WarmupConfig {
+    /// 是否启用启动预热
+    #[serde(default)]
+    pub enabled: bool,
+    /// 最多预热的凭证数量
+    #[serde(default = "default_warmup_max_credentials")]
+    pub max_credentials: usize,
+    /// 并发预热数
+    #[serde(default = "default_warmup_concurrency")]
+    pub concurrency: usize,
+    /// Token 在多少分钟内过期时视为需要刷新
+    #[serde(default = "default_warmup_refresh_within_minutes")]
+    pub refresh_within_minutes: i64,
+    /// 每个 Provider 类型至少保持预热（可用且 Token 新鲜）的凭证数，0 表示不维持
+    ///
+    /// 大于 0 时后台任务定期检查，预热凭证不足时主动刷新 Token 补足，不受 `enabled` 影响
+    #[serde(default)]
+    pub min_warm_per_provider: usize,
+    /// 预热凭证数检查间隔（秒）
+    #[serde(default = "default_warm_pool_interval_secs")]
+    pub warm_pool_interval_secs: u64,
+}
+
+fn default_warmup_max_credentials() -> usize {
+    16
+}
+
+fn default_warmup_concurrency() -> usize {
+    4
+}
+
+fn default_warmup_refresh_within_minutes() -> i64 {
+    30
+}
+
+fn default_warm_pool_interval_secs() -> u64 {
+    60
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_credentials: default_warmup_max_credentials(),
+            concurrency: default_warmup_concurrency(),
+            refresh_within_minutes: default_warmup_refresh_within_minutes(),
+            min_warm_per_provider: 0,
+            warm_pool_interval_secs: default_warm_pool_interval_secs(),
+        }
+    }
+}
+
+/// 上游 HTTP 连接池配置
+///
+/// 所有 Provider 共享同一个 HTTP 客户端，通过连接复用减少 TCP/TLS 握手开销
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpstreamPoolConfig {
+    /// 每个 host 保留的最大空闲连接数
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接超时时间（秒）
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// TCP keepalive 间隔（秒）
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    32
+}
+
+fn default_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+fn default_tcp_keepalive_secs() -> u64 {
+    60
+}
+
+impl Default for UpstreamPoolConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+        }
+    }
 }
 
 /// TLS 配置
@@ -929,7 +1593,7 @@ pub struct TlsConfig {
 /// 远程管理配置
 ///
 /// 用于配置远程管理 API 的访问控制
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RemoteManagementConfig {
     /// 是否允许远程访问（非 localhost）
     #[serde(default)]
@@ -940,6 +1604,28 @@ pub struct RemoteManagementConfig {
     /// 是否禁用控制面板
     #[serde(default)]
     pub disable_control_panel: bool,
+    /// 是否要求 HMAC 请求签名（以 secret_key 为共享密钥，代替 Bearer 认证）
+    #[serde(default)]
+    pub require_signature: bool,
+    /// 签名时间戳允许的最大偏差（秒）
+    #[serde(default = "default_signature_max_skew_secs")]
+    pub signature_max_skew_secs: u64,
+}
+
+fn default_signature_max_skew_secs() -> u64 {
+    300
+}
+
+impl Default for RemoteManagementConfig {
+    fn default() -> Self {
+        Self {
+            allow_remote: false,
+            secret_key: None,
+            disable_control_panel: false,
+            require_signature: false,
+            signature_max_skew_secs: default_signature_max_skew_secs(),
+        }
+    }
 }
 
 /// 配额超限配置
@@ -1019,6 +1705,30 @@ fn default_api_key() -> String {
     DEFAULT_API_KEY.to_string()
 }
 
+fn default_validate_tools() -> bool {
+    true
+}
+
+fn default_server_api_key_enabled() -> bool {
+    true
+}
+
+fn default_stream_heartbeat_secs() -> u64 {
+    15
+}
+
+fn default_stream_idle_timeout_ms() -> u64 {
+    300_000
+}
+
+fn default_concurrency_wait_ms() -> u64 {
+    5000
+}
+
+fn default_shutdown_drain_secs() -> u64 {
+    10
+}
+
 /// 生成安全 API Key（32 字节随机）
 pub fn generate_secure_api_key() -> String {
     use rand::distributions::Alphanumeric;
@@ -1043,7 +1753,31 @@ impl Default for ServerConfig {
             host: default_host(),
             port: default_port(),
             api_key: default_api_key(),
+            api_keys: Vec::new(),
             tls: TlsConfig::default(),
+            upstream_pool: UpstreamPoolConfig::default(),
+            validate_tools: default_validate_tools(),
+            warmup: WarmupConfig::default(),
+            session_quota: SessionQuotaConfig::default(),
+            bind_interface: None,
+            route_aliases: HashMap::new(),
+            stream_heartbeat_secs: default_stream_heartbeat_secs(),
+            emulate_multiple_choices: false,
+            max_concurrent_requests: 0,
+            concurrency_wait_ms: default_concurrency_wait_ms(),
+            coalesce_tool_calls: false,
+            provider_headers: HashMap::new(),
+            response_post_processors: Vec::new(),
+            stream_idle_timeout_ms: default_stream_idle_timeout_ms(),
+            strict_structured_output: false,
+            allow_upstream_override: false,
+            force_buffer_stream: false,
+            allow_self_upstream: false,
+            dedup_window_ms: 0,
+            stream_compat: StreamCompatConfig::default(),
+            shutdown_drain_secs: default_shutdown_drain_secs(),
+            selector_prefix: String::new(),
+            stream_coalesce: StreamCoalesceConfig::default(),
         }
     }
 }
@@ -1143,6 +1877,105 @@ pub struct RoutingConfig {
     /// 模型别名映射
     #[serde(default)]
     pub model_aliases: HashMap<String, String>,
+    /// 故障转移链（通过 `/{name}/v1/...` 选择器使用）
+    #[serde(default)]
+    pub failover_chains: Vec<FailoverChain>,
+    /// 自定义 Provider ID → 协议（如 `my-vllm: openai`）
+    ///
+    /// 让自建或第三方 Provider 声明按哪种已知协议处理：路由时据此设置 Router 默认 Provider
+    /// 并按该协议选择降级凭证；内置 Provider 名称不可被覆盖
+    #[serde(default)]
+    pub provider_protocols: HashMap<String, String>,
+}
+
+impl RoutingConfig {
+    /// 按名称查找故障转移链
+    pub fn failover_chain(&self, name: &str) -> Option<&FailoverChain> {
+        self.failover_chains.iter().find(|chain| chain.name == name)
+    }
+
+    /// 为 Provider ID 声明的协议（`provider_protocols`）
+    ///
+    /// 内置 Provider 名称不可被覆盖；协议必须是内置 Provider 类型，无法识别时返回 `None`。
+    pub fn declared_protocol(&self, provider_id: &str) -> Option<ProviderType> {
+        if !is_custom_provider_id(provider_id) && provider_id.parse::<ProviderType>().is_ok() {
+            return None;
+        }
+        self.provider_protocols
+            .iter()
+            .find(|(id, _)| id.eq_ignore_ascii_case(provider_id))
+            .and_then(|(_, protocol)| parse_protocol(protocol))
+    }
+
+    /// 解析 Provider ID 对应的 Provider 类型（路由时调用）
+    ///
+    /// 先查 `provider_protocols` 中的声明，再按内置规则解析（`custom-*` 按 OpenAI 兜底）。
+    pub fn resolve_provider_type(&self, provider_id: &str) -> Result<ProviderType, String> {
+        match self.declared_protocol(provider_id) {
+            Some(protocol) => Ok(protocol),
+            None => provider_id.parse(),
+        }
+    }
+
+    /// 协议无法识别的 `provider_protocols` 条目（Provider ID, 协议）
+    pub fn invalid_provider_protocols(&self) -> Vec<(&str, &str)> {
+        self.provider_protocols
+            .iter()
+            .filter(|(_, protocol)| parse_protocol(protocol).is_none())
+            .map(|(id, protocol)| (id.as_str(), protocol.as_str()))
+            .collect()
+    }
+}
+
+/// 解析声明的协议，不能再指向另一个自定义 Provider
+fn parse_protocol(protocol: &str) -> Option<ProviderType> {
+    if is_custom_provider_id(protocol) {
+        return None;
+    }
+    protocol.parse().ok()
+}
+
+/// 故障转移链
+///
+/// 按顺序使用成员凭证：只有前一个成员不可用（不健康、已禁用或额度耗尽）时
+/// 才会使用下一个，整条链都不可用时请求才失败。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct FailoverChain {
+    /// 链名称（用作路由选择器）
+    pub name: String,
+    /// 成员凭证 UUID 或名称，按优先级排列
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+impl FailoverChain {
+    /// 按链顺序解析成员凭证，忽略找不到的成员
+    pub fn resolve<'a>(
+        &self,
+        credentials: &'a [ProviderCredential],
+    ) -> Vec<&'a ProviderCredential> {
+        self.members
+            .iter()
+            .filter_map(|member| {
+                credentials
+                    .iter()
+                    .find(|c| c.uuid == *member || c.name.as_deref() == Some(member.as_str()))
+            })
+            .collect()
+    }
+
+    /// 返回链中第一个可用的成员
+    ///
+    /// `is_usable` 用于附加检查（如每日额度、是否已在本次请求中失败）。
+    pub fn first_available<'a>(
+        &self,
+        credentials: &'a [ProviderCredential],
+        is_usable: impl Fn(&ProviderCredential) -> bool,
+    ) -> Option<&'a ProviderCredential> {
+        self.resolve(credentials)
+            .into_iter()
+            .find(|c| c.is_available() && is_usable(c))
+    }
 }
 
 fn default_provider() -> String {
@@ -1154,6 +1987,8 @@ impl Default for RoutingConfig {
         Self {
             default_provider: default_provider(),
             model_aliases: HashMap::new(),
+            failover_chains: Vec::new(),
+            provider_protocols: HashMap::new(),
         }
     }
 }
@@ -1170,9 +2005,35 @@ pub struct RetrySettings {
     /// 最大延迟（毫秒）
     #[serde(default = "default_max_delay_ms")]
     pub max_delay_ms: u64,
+    /// 退避抖动方式
+    #[serde(default)]
+    pub jitter: JitterMode,
     /// 是否自动切换 Provider
     #[serde(default = "default_auto_switch")]
     pub auto_switch_provider: bool,
+    /// 按 Provider 类型覆盖是否自动切换（如 `claude: false`），未配置的类型使用 `auto_switch_provider`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub provider_fallback: HashMap<String, bool>,
+    /// 按 Provider 类型熔断
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerSettings,
+}
+
+/// 重试退避的抖动方式
+///
+/// 并发失败的请求退避时间相同就会同时重试、集中冲击上游，抖动把重试时间错开。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterMode {
+    /// 叠加抖动：`min(base * 2^n + [0, base), max)`（默认，与旧版行为一致）
+    #[default]
+    Additive,
+    /// 不加抖动：`min(base * 2^n, max)`
+    None,
+    /// 完全抖动：在 `[0, 退避时间)` 内随机
+    Full,
+    /// 等量抖动：一半固定，另一半在 `[0, 退避时间 / 2)` 内随机
+    Equal,
 }
 
 fn default_max_retries() -> u32 {
@@ -1191,13 +2052,91 @@ fn default_auto_switch() -> bool {
     true
 }
 
+impl RetrySettings {
+    /// 指定 Provider 类型是否允许自动切换（类型名不区分大小写）
+    pub fn fallback_enabled_for(&self, provider: &str) -> bool {
+        self.provider_fallback
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(provider))
+            .map(|(_, enabled)| *enabled)
+            .unwrap_or(self.auto_switch_provider)
+    }
+}
+
 impl Default for RetrySettings {
     fn default() -> Self {
         Self {
             max_retries: default_max_retries(),
             base_delay_ms: default_base_delay_ms(),
             max_delay_ms: default_max_delay_ms(),
+            jitter: JitterMode::default(),
             auto_switch_provider: default_auto_switch(),
+            provider_fallback: HashMap::new(),
+            circuit_breaker: CircuitBreakerSettings::default(),
+        }
+    }
+}
+
+/// 熔断配置
+///
+/// 滚动窗口内某个凭证的上游失败率达到阈值时熔断，冷却期内新请求直接失败
+/// （或立即故障转移）；冷却结束后放行少量探测请求，探测全部成功才恢复。
+/// 默认关闭。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CircuitBreakerSettings {
+    /// 是否启用熔断
+    #[serde(default = "default_circuit_breaker_enabled")]
+    pub enabled: bool,
+    /// 触发熔断的失败率（0.0 - 1.0）
+    #[serde(default = "default_failure_rate_threshold")]
+    pub failure_rate_threshold: f64,
+    /// 窗口内至少有这么多请求才计算失败率
+    #[serde(default = "default_circuit_min_requests")]
+    pub min_requests: u32,
+    /// 滚动窗口长度（秒）
+    #[serde(default = "default_circuit_window_secs")]
+    pub window_secs: u64,
+    /// 熔断冷却时间（秒）
+    #[serde(default = "default_circuit_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// 半开状态下的探测请求数
+    #[serde(default = "default_half_open_probes")]
+    pub half_open_probes: u32,
+}
+
+fn default_circuit_breaker_enabled() -> bool {
+    false
+}
+
+fn default_failure_rate_threshold() -> f64 {
+    0.5
+}
+
+fn default_circuit_min_requests() -> u32 {
+    10
+}
+
+fn default_circuit_window_secs() -> u64 {
+    60
+}
+
+fn default_circuit_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_half_open_probes() -> u32 {
+    1
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_circuit_breaker_enabled(),
+            failure_rate_threshold: default_failure_rate_threshold(),
+            min_requests: default_circuit_min_requests(),
+            window_secs: default_circuit_window_secs(),
+            cooldown_secs: default_circuit_cooldown_secs(),
+            half_open_probes: default_half_open_probes(),
         }
     }
 }
@@ -1217,6 +2156,55 @@ pub struct LoggingConfig {
     /// 是否包含请求体
     #[serde(default)]
     pub include_request_body: bool,
+    /// 请求 / 响应内容写入日志前的脱敏规则
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+}
+
+/// 日志脱敏规则
+///
+/// 匹配 `patterns` 的文本片段和 JSON 中键名属于 `json_keys`（不区分大小写）的值
+/// 会在写入日志存储前替换为 `[REDACTED]`。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedactionConfig {
+    /// 正则表达式列表
+    #[serde(default = "default_redaction_patterns")]
+    pub patterns: Vec<String>,
+    /// 需要脱敏的 JSON 键名
+    #[serde(default = "default_redaction_json_keys")]
+    pub json_keys: Vec<String>,
+}
+
+fn default_redaction_patterns() -> Vec<String> {
+    vec![
+        r"Bearer\s+[A-Za-z0-9._~+/=-]+".to_string(),
+        r"sk-[A-Za-z0-9_-]{8,}".to_string(),
+    ]
+}
+
+fn default_redaction_json_keys() -> Vec<String> {
+    [
+        "api_key",
+        "apiKey",
+        "x-api-key",
+        "authorization",
+        "access_token",
+        "refresh_token",
+        "client_secret",
+        "password",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            patterns: default_redaction_patterns(),
+            json_keys: default_redaction_json_keys(),
+        }
+    }
 }
 
 fn default_logging_enabled() -> bool {
@@ -1238,6 +2226,35 @@ impl Default for LoggingConfig {
             level: default_log_level(),
             retention_days: default_retention_days(),
             include_request_body: false,
+            redaction: RedactionConfig::default(),
+        }
+    }
+}
+
+/// 遥测配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TelemetryConfig {
+    /// 成功请求写入详细请求日志的采样比例（0.0 - 1.0）
+    ///
+    /// 失败请求始终记录；统计聚合不受采样影响，保持精确。
+    #[serde(default = "default_telemetry_sample_rate")]
+    pub sample_rate: f64,
+    /// 请求日志是否记录详细的凭证选择追踪（全部候选及过滤原因）
+    ///
+    /// 关闭时只记录候选数、过滤数和选中的凭证
+    #[serde(default)]
+    pub verbose_selection_trace: bool,
+}
+
+fn default_telemetry_sample_rate() -> f64 {
+    1.0
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: default_telemetry_sample_rate(),
+            verbose_selection_trace: false,
         }
     }
 }
@@ -1699,14 +2716,17 @@ impl Default for Config {
             routing: RoutingConfig::default(),
             retry: RetrySettings::default(),
             logging: LoggingConfig::default(),
+            telemetry: TelemetryConfig::default(),
             injection: InjectionSettings::default(),
             auth_dir: default_auth_dir(),
             credential_pool: CredentialPoolConfig::default(),
+            credentials: CredentialsConfig::default(),
             remote_management: RemoteManagementConfig::default(),
             quota_exceeded: QuotaExceededConfig::default(),
             proxy_url: None,
             ampcode: AmpConfig::default(),
             endpoint_providers: EndpointProvidersConfig::default(),
+            client_detection: ClientDetectionConfig::default(),
             minimize_to_tray: default_minimize_to_tray(),
             language: default_language(),
             models: ModelsConfig::default(),
@@ -1720,6 +2740,14 @@ impl Default for Config {
             image_gen: ImageGenConfig::default(),
             assistant: AssistantConfig::default(),
             user_profile: UserProfile::default(),
+            secret_storage: SecretStorageConfig::default(),
+            profiles: HashMap::new(),
+            active_profile: None,
+            database_path: None,
+            external_sync: ExternalSyncConfig::default(),
+            health_alert: HealthAlertConfig::default(),
+            health_probe: HealthProbeConfig::default(),
+            anthropic: AnthropicConfig::default(),
         }
     }
 }
@@ -2022,6 +3050,24 @@ mod unit_tests {
         assert_eq!(config.base_delay_ms, 1000);
         assert_eq!(config.max_delay_ms, 30000);
         assert!(config.auto_switch_provider);
+        assert!(config.provider_fallback.is_empty());
+    }
+
+    #[test]
+    fn test_retry_settings_provider_fallback_override() {
+        let yaml = "auto_switch_provider: true\nprovider_fallback:\n  claude: false\n";
+        let config: RetrySettings = serde_yaml::from_str(yaml).unwrap();
+        assert!(!config.fallback_enabled_for("claude"));
+        assert!(!config.fallback_enabled_for("Claude"));
+        assert!(config.fallback_enabled_for("kiro"));
+
+        let disabled = RetrySettings {
+            auto_switch_provider: false,
+            provider_fallback: HashMap::from([("kiro".to_string(), true)]),
+            ..Default::default()
+        };
+        assert!(disabled.fallback_enabled_for("kiro"));
+        assert!(!disabled.fallback_enabled_for("openai"));
     }
 
     #[test]
@@ -2060,6 +3106,7 @@ mod unit_tests {
             windsurf: None,
             kiro: Some("gemini".to_string()),
             other: None,
+            disabled: Vec::new(),
         };
 
         assert_eq!(config.get_provider("cursor"), Some(&"qwen".to_string()));
@@ -2110,6 +3157,7 @@ mod unit_tests {
             windsurf: None,
             kiro: None,
             other: None,
+            disabled: Vec::new(),
         };
 
         // 使用 None 清除配置
@@ -2121,6 +3169,28 @@ mod unit_tests {
         assert_eq!(config.claude_code, None);
     }
 
+    #[test]
+    fn test_endpoint_providers_config_set_enabled() {
+        let mut config = EndpointProvidersConfig::default();
+        assert!(config.is_enabled("cursor"));
+
+        assert!(config.set_enabled("cursor", false));
+        assert!(config.set_enabled("cursor", false));
+        assert!(!config.is_enabled("cursor"));
+        assert!(config.is_enabled("codex"));
+        assert_eq!(config.disabled, vec!["cursor".to_string()]);
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let parsed: EndpointProvidersConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert!(!parsed.is_enabled("cursor"));
+
+        assert!(config.set_enabled("cursor", true));
+        assert!(config.is_enabled("cursor"));
+        assert!(config.disabled.is_empty());
+
+        assert!(!config.set_enabled("invalid", false));
+    }
+
     #[test]
     fn test_endpoint_providers_config_serialization() {
         let config = EndpointProvidersConfig {
@@ -2130,6 +3200,7 @@ mod unit_tests {
             windsurf: None,
             kiro: None,
             other: None,
+            disabled: Vec::new(),
         };
 
         let yaml = serde_yaml::to_string(&config).unwrap();
@@ -2154,6 +3225,7 @@ mod unit_tests {
             windsurf: None,
             kiro: None,
             other: Some("openai".to_string()),
+            disabled: Vec::new(),
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -2301,4 +3373,106 @@ mod unit_tests {
         assert_eq!(parsed.asr.len(), 1);
         assert_eq!(parsed.asr[0].provider, AsrProviderType::Xunfei);
     }
+
+    #[test]
+    fn test_failover_chain_walks_members_in_order() {
+        use crate::models::provider_pool_model::{
+            CredentialData, PoolProviderType, ProviderCredential,
+        };
+
+        let credential = |name: &str| {
+            let mut cred = ProviderCredential::new(
+                PoolProviderType::OpenAI,
+                CredentialData::OpenAIKey {
+                    api_key: format!("sk-{name}"),
+                    base_url: None,
+                },
+            );
+            cred.name = Some(name.to_string());
+            cred
+        };
+        let mut credentials = vec![credential("c"), credential("a"), credential("b")];
+
+        let yaml = format!(
+            "default_provider: kiro\nfailover_chains:\n  - name: primary\n    members: [a, {}, missing, c]\n",
+            credentials[2].uuid
+        );
+        let routing: RoutingConfig = serde_yaml::from_str(&yaml).expect("解析路由配置失败");
+        let chain: &FailoverChain = routing.failover_chain("primary").expect("应找到故障转移链");
+        assert!(routing.failover_chain("other").is_none());
+
+        // 按链顺序解析（名称与 UUID 均可），忽略不存在的成员
+        let order: Vec<_> = chain
+            .resolve(&credentials)
+            .iter()
+            .map(|c| c.name.clone().unwrap())
+            .collect();
+        assert_eq!(order, ["a", "b", "c"]);
+        let first = chain.first_available(&credentials, |_| true).unwrap();
+        assert_eq!(first.name.as_deref(), Some("a"));
+
+        // 跳过不健康的成员与额度耗尽的成员
+        credentials[1].is_healthy = false;
+        let exhausted = credentials[2].uuid.clone();
+        let next = chain
+            .first_available(&credentials, |c| c.uuid != exhausted)
+            .unwrap();
+        assert_eq!(next.name.as_deref(), Some("c"));
+
+        // 整条链都不可用时才失败
+        credentials[0].is_disabled = true;
+        assert!(chain
+            .first_available(&credentials, |c| c.uuid != exhausted)
+            .is_none());
+    }
+
+    #[test]
+    fn test_provider_protocols_resolve_at_routing_time() {
+        let yaml = "default_provider: my-vllm\nprovider_protocols:\n  My-VLLM: openai\n  custom-0f6c1d2e-0000-4000-8000-protocol0001: anthropic\n  claude: openai\n  broken-llm: not-a-protocol\n";
+        let routing: RoutingConfig = serde_yaml::from_str(yaml).unwrap();
+
+        // 自定义 ID 按声明的协议解析，默认 Provider 不再落空
+        let provider_type = routing
+            .resolve_provider_type(&routing.default_provider)
+            .unwrap();
+        assert_eq!(provider_type, ProviderType::OpenAI);
+        let mut router = crate::router::Router::new_empty();
+        router.set_default_provider(provider_type);
+        assert_eq!(router.route("gpt-4o").provider, Some(ProviderType::OpenAI));
+
+        // custom-* 可以声明非 OpenAI 协议
+        assert_eq!(
+            routing.declared_protocol("custom-0f6c1d2e-0000-4000-8000-protocol0001"),
+            Some(ProviderType::Anthropic)
+        );
+        // 内置名称不可被覆盖，无法识别的协议被忽略并报告
+        assert_eq!(routing.declared_protocol("claude"), None);
+        assert_eq!(
+            routing.resolve_provider_type("claude").unwrap(),
+            ProviderType::Claude
+        );
+        assert!(routing.resolve_provider_type("broken-llm").is_err());
+        assert_eq!(
+            routing.invalid_provider_protocols(),
+            vec![("broken-llm", "not-a-protocol")]
+        );
+
+        // 声明只在路由时生效，不影响全局解析
+        assert!("my-vllm".parse::<ProviderType>().is_err());
+    }
+
+    #[test]
+    fn test_external_sync_provider_conflict_policy() {
+        // 旧配置缺少该字段时使用默认策略
+        let config: ExternalSyncConfig = serde_yaml::from_str("conflict_policy: abort").unwrap();
+        assert_eq!(config.provider_conflict_policy, ConflictPolicy::PreferLocal);
+
+        let config: ExternalSyncConfig =
+            serde_yaml::from_str("provider_conflict_policy: manual").unwrap();
+        assert_eq!(config.provider_conflict_policy, ConflictPolicy::Manual);
+        assert_eq!(config.conflict_policy, ExternalSyncPolicy::Abort);
+        assert!(serde_yaml::to_string(&config)
+            .unwrap()
+            .contains("provider_conflict_policy: manual"));
+    }
 }
@@ -2,12 +2,15 @@
 //!
 //! 提供 YAML 配置的加载、保存和管理功能
 //! 支持保留注释的配置保存
+//! 加载时把 `${VAR}` 替换为环境变量的值
 
 #![allow(dead_code)]
 
 use super::types::Config;
+use regex::Regex;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 /// 配置错误类型
 #[derive(Debug, Clone)]
@@ -71,9 +74,7 @@ impl ConfigManager {
     /// 如果文件不存在，返回默认配置
     pub fn load(path: &Path) -> Result<Self, ConfigError> {
         let config = if path.exists() {
-            let content =
-                std::fs::read_to_string(path).map_err(|e| ConfigError::ReadError(e.to_string()))?;
-            Self::parse_yaml(&content)?
+            read_config_with_backup(path)?
         } else {
             Config::default()
         };
@@ -85,8 +86,11 @@ impl ConfigManager {
     }
 
     /// 从 YAML 字符串解析配置
+    ///
+    /// 解析前先替换 `${VAR}` 环境变量引用（见 [`substitute_env_vars`]）。
     pub fn parse_yaml(yaml: &str) -> Result<Config, ConfigError> {
-        serde_yaml::from_str(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))
+        serde_yaml::from_str(&substitute_env_vars(yaml))
+            .map_err(|e| ConfigError::ParseError(e.to_string()))
     }
 
     /// 将配置序列化为 YAML 字符串
@@ -101,24 +105,13 @@ impl ConfigManager {
 
     /// 保存配置到指定路径
     pub fn save_to(&self, path: &Path) -> Result<(), ConfigError> {
-        // 确保父目录存在
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| ConfigError::WriteError(e.to_string()))?;
-        }
-
-        if path.exists() {
-            let backup_path = path.with_extension("yaml.backup");
-            let _ = std::fs::copy(path, backup_path);
-        }
         let yaml = Self::to_yaml(&self.config)?;
-        std::fs::write(path, yaml).map_err(|e| ConfigError::WriteError(e.to_string()))
+        write_config_atomic(path, &yaml)
     }
 
     /// 重新加载配置
     pub fn reload(&mut self) -> Result<(), ConfigError> {
-        let content = std::fs::read_to_string(&self.config_path)
-            .map_err(|e| ConfigError::ReadError(e.to_string()))?;
-        self.config = Self::parse_yaml(&content)?;
+        self.config = read_config_with_backup(&self.config_path)?;
         Ok(())
     }
 
@@ -145,19 +138,11 @@ impl ConfigManager {
     /// 导出配置为 YAML 字符串
     ///
     /// # Arguments
-    /// * `redact_secrets` - 是否脱敏敏感信息（API 密钥等）
+    /// * `redact_secrets` - 是否脱敏敏感信息（API 密钥等），脱敏规则见
+    ///   [`ExportService::redact_config`](super::ExportService::redact_config)
     pub fn export(&self, redact_secrets: bool) -> Result<String, ConfigError> {
         if redact_secrets {
-            let mut config = self.config.clone();
-            // 脱敏 API 密钥
-            config.server.api_key = "***REDACTED***".to_string();
-            if config.providers.openai.api_key.is_some() {
-                config.providers.openai.api_key = Some("***REDACTED***".to_string());
-            }
-            if config.providers.claude.api_key.is_some() {
-                config.providers.claude.api_key = Some("***REDACTED***".to_string());
-            }
-            Self::to_yaml(&config)
+            Self::to_yaml(&super::ExportService::redact_config(&self.config))
         } else {
             Self::to_yaml(&self.config)
         }
@@ -226,6 +211,77 @@ impl ConfigManager {
         if other.logging != LoggingConfig::default() {
             self.config.logging = other.logging;
         }
+
+        // 合并遥测配置
+        if other.telemetry != TelemetryConfig::default() {
+            self.config.telemetry = other.telemetry;
+        }
+    }
+
+    /// 列出所有配置档案名称（按名称排序）
+    pub fn list_profiles(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.config.profiles.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 当前激活的配置档案
+    pub fn active_profile(&self) -> Option<&str> {
+        self.config.active_profile.as_deref()
+    }
+
+    /// 以当前的服务器、路由、注入和端点设置创建配置档案
+    pub fn create_profile(&mut self, name: &str) -> Result<(), ConfigError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ConfigError::ValidationError(
+                "配置档案名称不能为空".to_string(),
+            ));
+        }
+        if self.config.profiles.contains_key(name) {
+            return Err(ConfigError::ValidationError(format!(
+                "配置档案已存在: {name}"
+            )));
+        }
+        let profile = ConfigProfile::capture(&self.config);
+        self.config.profiles.insert(name.to_string(), profile);
+        Ok(())
+    }
+
+    /// 删除配置档案，不能删除当前激活的档案
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), ConfigError> {
+        if self.active_profile() == Some(name) {
+            return Err(ConfigError::ValidationError(format!(
+                "不能删除当前激活的配置档案: {name}"
+            )));
+        }
+        self.config
+            .profiles
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| ConfigError::ValidationError(format!("配置档案不存在: {name}")))
+    }
+
+    /// 切换到指定配置档案
+    ///
+    /// 切换前把当前设置保存回正在使用的档案，避免丢失未保存到档案中的修改；
+    /// 凭证池等共享配置保持不变。
+    pub fn switch_profile(&mut self, name: &str) -> Result<&Config, ConfigError> {
+        let profile = self
+            .config
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ConfigError::ValidationError(format!("配置档案不存在: {name}")))?;
+
+        if let Some(current) = self.config.active_profile.clone() {
+            let snapshot = ConfigProfile::capture(&self.config);
+            self.config.profiles.insert(current, snapshot);
+        }
+
+        profile.apply_to(&mut self.config);
+        self.config.active_profile = Some(name.to_string());
+        Ok(&self.config)
     }
 
     /// 获取默认配置文件路径
@@ -237,7 +293,7 @@ impl ConfigManager {
     }
 }
 
-use super::types::{LoggingConfig, RetrySettings, ServerConfig};
+use super::types::{ConfigProfile, LoggingConfig, RetrySettings, ServerConfig, TelemetryConfig};
 
 impl Default for ConfigManager {
     fn default() -> Self {
@@ -291,13 +347,7 @@ impl YamlService {
             new_yaml
         };
 
-        // 确保父目录存在
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| ConfigError::WriteError(e.to_string()))?;
-        }
-
-        // 写入文件
-        std::fs::write(path, final_content).map_err(|e| ConfigError::WriteError(e.to_string()))
+        write_config_atomic(path, &final_content)
     }
 
     /// 合并原文件的注释到新 YAML 内容中
@@ -662,8 +712,7 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
 
     // 优先尝试 YAML 配置
     if yaml_path.exists() {
-        let content = std::fs::read_to_string(&yaml_path)?;
-        let mut config: Config = serde_yaml::from_str(&content)?;
+        let mut config = read_config_with_backup(&yaml_path)?;
         // 如果配置中使用默认 API Key，生成强随机 Key 并保存
         if is_default_api_key(&config.server.api_key) {
             let new_key = generate_secure_api_key();
@@ -712,28 +761,128 @@ pub fn save_config(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     save_config_yaml(config)?;
 
     // 兼容旧版 JSON 配置
-    let path = json_config_path();
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
     let content = serde_json::to_string_pretty(config)?;
-    std::fs::write(&path, content)?;
+    write_file_atomic(&json_config_path(), &content, None)?;
     Ok(())
 }
 
 /// 保存配置为 YAML 格式
 pub fn save_config_yaml(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let path = ConfigManager::default_config_path();
+    let content = serde_yaml::to_string(config)?;
+    write_config_atomic(&ConfigManager::default_config_path(), &content)?;
+    Ok(())
+}
+
+/// 配置备份路径（上一次可正常解析的配置）
+pub fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension("yaml.backup")
+}
+
+/// 在文件名后追加后缀（如 `config.yaml` -> `config.yaml.lock`）
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// 原子写入 YAML 配置文件
+///
+/// 覆盖前若原文件可正常解析，先将其复制为备份，供主文件损坏时回退。
+pub fn write_config_atomic(path: &Path, content: &str) -> Result<(), ConfigError> {
+    write_file_atomic(path, content, Some(&backup_path(path)))
+        .map_err(|e| ConfigError::WriteError(e.to_string()))
+}
+
+/// 原子写入文件
+///
+/// 持有 `<file>.lock` 文件锁串行化并发保存，在同目录写入临时文件并 fsync 后重命名覆盖目标，
+/// 写入中途崩溃只会留下临时文件，目标文件保持原样。
+fn write_file_atomic(path: &Path, content: &str, backup: Option<&Path>) -> std::io::Result<()> {
+    use std::io::Write;
+
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    if path.exists() {
-        let backup_path = path.with_extension("yaml.backup");
-        let _ = std::fs::copy(&path, &backup_path);
+
+    let lock_file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling_path(path, ".lock"))?;
+    lock_file.lock()?;
+
+    if let Some(backup) = backup {
+        let last_good = std::fs::read_to_string(path)
+            .ok()
+            .filter(|existing| ConfigManager::parse_yaml(existing).is_ok());
+        if let Some(existing) = last_good {
+            if let Err(e) = std::fs::write(backup, existing) {
+                tracing::warn!("[CONFIG] 写入配置备份失败: {}", e);
+            }
+        }
+    }
+
+    let temp_path = sibling_path(path, ".tmp");
+    let mut file = std::fs::File::create(&temp_path)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&temp_path, path)?;
+
+    // 确保重命名本身落盘
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = std::fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    lock_file.unlock()
+}
+
+/// 把 `${VAR}` 替换为环境变量的值
+///
+/// 变量名须为字母或下划线开头的标识符，因此正则替换中的 `${1}` 不受影响；
+/// 未设置的变量保留原文并记录警告。保存配置时写入的是替换后的值。
+pub fn substitute_env_vars(yaml: &str) -> String {
+    static ENV_REF: OnceLock<Regex> = OnceLock::new();
+    let env_ref =
+        ENV_REF.get_or_init(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("valid regex"));
+    env_ref
+        .replace_all(yaml, |caps: &regex::Captures| {
+            std::env::var(&caps[1]).unwrap_or_else(|_| {
+                tracing::warn!("[CONFIG] 环境变量 {} 未设置，保留原文", &caps[1]);
+                caps[0].to_string()
+            })
+        })
+        .into_owned()
+}
+
+/// 读取 YAML 配置，主文件无法解析时回退到备份
+pub fn read_config_with_backup(path: &Path) -> Result<Config, ConfigError> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| ConfigError::ReadError(e.to_string()))?;
+    let error = match ConfigManager::parse_yaml(&content) {
+        Ok(config) => return Ok(config),
+        Err(e) => e,
+    };
+
+    let backup = backup_path(path);
+    match std::fs::read_to_string(&backup)
+        .ok()
+        .and_then(|c| ConfigManager::parse_yaml(&c).ok())
+    {
+        Some(config) => {
+            tracing::error!(
+                "[CONFIG] 配置文件 {} 已损坏（{}），已回退到备份 {}",
+                path.display(),
+                error,
+                backup.display()
+            );
+            Ok(config)
+        }
+        None => Err(error),
     }
-    let content = serde_yaml::to_string(config)?;
-    std::fs::write(&path, content)?;
-    Ok(())
 }
 
 #[cfg(test)]
@@ -825,9 +974,20 @@ server:
 
     #[test]
     fn test_export_redacted() {
+        use crate::config::types::{ConfigProfile, ServerApiKey};
+
         let mut config = Config::default();
         config.server.api_key = "secret-key".to_string();
+        config.server.api_keys = vec![ServerApiKey {
+            key: "extra-server-key".to_string(),
+            label: "cursor".to_string(),
+            enabled: true,
+        }];
         config.providers.openai.api_key = Some("openai-secret".to_string());
+        let mut profile = ConfigProfile::capture(&config);
+        profile.server.api_key = "profile-secret".to_string();
+        profile.server.api_keys[0].key = "profile-extra-key".to_string();
+        config.profiles.insert("work".to_string(), profile);
 
         let manager = ConfigManager {
             config,
@@ -838,6 +998,21 @@ server:
         assert!(exported.contains("***REDACTED***"));
         assert!(!exported.contains("secret-key"));
         assert!(!exported.contains("openai-secret"));
+        assert!(!exported.contains("extra-server-key"));
+        assert!(!exported.contains("profile-secret"));
+        assert!(!exported.contains("profile-extra-key"));
+        // 标签不是密钥，保留
+        assert!(exported.contains("cursor"));
+    }
+
+    #[test]
+    fn test_substitute_env_vars() {
+        std::env::set_var("PROXYCAST_YAML_TEST_KEY", "from-env");
+        let yaml = "api_key: ${PROXYCAST_YAML_TEST_KEY}\nreplacement: ${1}\nmissing: ${PROXYCAST_YAML_TEST_UNSET}";
+        assert_eq!(
+            substitute_env_vars(yaml),
+            "api_key: from-env\nreplacement: ${1}\nmissing: ${PROXYCAST_YAML_TEST_UNSET}"
+        );
     }
 
     #[test]
@@ -868,6 +1043,97 @@ server:
         assert_eq!(manager.config.server.port, 5678);
     }
 
+    #[test]
+    fn test_interrupted_save_keeps_previous_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        let mut config = Config::default();
+        config.server.port = 9001;
+        ConfigManager::with_config(config.clone(), path.clone())
+            .save()
+            .unwrap();
+
+        // 模拟写入中途崩溃：临时文件只写了一半，尚未重命名
+        let partial = ConfigManager::to_yaml(&config).unwrap();
+        std::fs::write(sibling_path(&path, ".tmp"), &partial[..partial.len() / 2]).unwrap();
+
+        let loaded = ConfigManager::load(&path).unwrap();
+        assert_eq!(loaded.config().server.port, 9001);
+
+        // 之后的保存不受残留临时文件影响
+        config.server.port = 9002;
+        ConfigManager::with_config(config, path.clone())
+            .save()
+            .unwrap();
+        assert_eq!(
+            ConfigManager::load(&path).unwrap().config().server.port,
+            9002
+        );
+        assert!(!sibling_path(&path, ".tmp").exists());
+    }
+
+    #[test]
+    fn test_corrupt_config_falls_back_to_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        let mut config = Config::default();
+        config.server.port = 9001;
+        let manager = ConfigManager::with_config(config.clone(), path.clone());
+        manager.save().unwrap();
+        config.server.port = 9002;
+        ConfigManager::with_config(config, path.clone())
+            .save()
+            .unwrap();
+
+        // 主文件损坏后读取上一次可解析的备份
+        std::fs::write(&path, "server: [unclosed\n\0garbage").unwrap();
+        let loaded = ConfigManager::load(&path).unwrap();
+        assert_eq!(loaded.config().server.port, 9001);
+
+        // 损坏的主文件不会覆盖备份
+        manager.save().unwrap();
+        let backup = std::fs::read_to_string(backup_path(&path)).unwrap();
+        assert!(ConfigManager::parse_yaml(&backup).is_ok());
+
+        // 没有可用备份时返回解析错误
+        std::fs::remove_file(backup_path(&path)).unwrap();
+        std::fs::write(&path, "server: [unclosed").unwrap();
+        assert!(matches!(
+            ConfigManager::load(&path),
+            Err(ConfigError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_saves_do_not_tear() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        let handles: Vec<_> = (0..8u16)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut config = Config::default();
+                    config.server.port = 9000 + i;
+                    for _ in 0..10 {
+                        ConfigManager::with_config(config.clone(), path.clone())
+                            .save()
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let config = ConfigManager::parse_yaml(&content).unwrap();
+        assert!((9000..9008).contains(&config.server.port));
+    }
+
     #[test]
     fn test_config_error_display() {
         let err = ConfigError::ParseError("invalid yaml".to_string());
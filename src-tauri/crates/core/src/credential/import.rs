@@ -0,0 +1,390 @@
+//! 从 CLI 工具的认证文件批量导入凭证
+//!
+//! 扫描目录下的 JSON 文件，按内容识别凭证格式：
+//! - Kiro：`cache/*.json`（camelCase 的 `refreshToken`）
+//! - Gemini CLI：`oauth_creds.json`（`refresh_token` + Google OAuth 的 `expiry_date`/`scope`）
+//! - Antigravity / Claude OAuth / Codex：ProxyCast 导出的凭证文件（`type` 字段标识）
+//!   以及 Codex 的 `id_token` / `account_id` / `OPENAI_API_KEY`
+//!
+//! 以刷新令牌（或 API Key）的哈希作为指纹去重，已在凭证池中或本次已导入的凭证跳过。
+
+use crate::models::provider_pool_model::{get_oauth_creds_path, CredentialData, PoolProviderType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 识别出的凭证文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedCredential {
+    /// 源文件路径
+    pub path: PathBuf,
+    /// 凭证池 Provider 类型
+    pub provider_type: PoolProviderType,
+    /// 去重指纹
+    pub fingerprint: String,
+}
+
+impl DetectedCredential {
+    /// 构造凭证池使用的凭证数据，`creds_file_path` 为复制到应用目录后的路径
+    pub fn credential_data(&self, creds_file_path: String) -> CredentialData {
+        match self.provider_type {
+            PoolProviderType::Kiro => CredentialData::KiroOAuth { creds_file_path },
+            PoolProviderType::Gemini => CredentialData::GeminiOAuth {
+                creds_file_path,
+                project_id: None,
+            },
+            PoolProviderType::Antigravity => CredentialData::AntigravityOAuth {
+                creds_file_path,
+                project_id: None,
+            },
+            PoolProviderType::Codex => CredentialData::CodexOAuth {
+                creds_file_path,
+                api_base_url: None,
+            },
+            _ => CredentialData::ClaudeOAuth { creds_file_path },
+        }
+    }
+}
+
+/// 单个文件的导入状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    /// 已导入
+    Imported,
+    /// 已跳过（重复或无法识别）
+    Skipped,
+    /// 导入失败
+    Failed,
+}
+
+/// 单个文件的导入结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileImportResult {
+    /// 文件路径
+    pub path: String,
+    /// 导入状态
+    pub status: ImportStatus,
+    /// 识别出的 Provider 类型
+    pub provider_type: Option<PoolProviderType>,
+    /// 跳过或失败的原因
+    pub message: Option<String>,
+}
+
+/// 批量导入汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CredentialImportReport {
+    /// 导入成功数
+    pub imported: usize,
+    /// 跳过数
+    pub skipped: usize,
+    /// 失败数
+    pub failed: usize,
+    /// 每个文件的结果
+    pub files: Vec<FileImportResult>,
+}
+
+impl CredentialImportReport {
+    fn push(
+        &mut self,
+        path: &Path,
+        status: ImportStatus,
+        provider_type: Option<PoolProviderType>,
+        message: Option<String>,
+    ) {
+        match status {
+            ImportStatus::Imported => self.imported += 1,
+            ImportStatus::Skipped => self.skipped += 1,
+            ImportStatus::Failed => self.failed += 1,
+        }
+        self.files.push(FileImportResult {
+            path: path.to_string_lossy().to_string(),
+            status,
+            provider_type,
+            message,
+        });
+    }
+}
+
+/// 计算凭证指纹（刷新令牌或 API Key 的 SHA-256 前 16 字节）
+pub fn credential_fingerprint(secret: &str) -> String {
+    let digest = Sha256::digest(secret.trim().as_bytes());
+    hex::encode(&digest[..16])
+}
+
+/// 按内容识别凭证格式，返回 Provider 类型与用于计算指纹的密钥
+pub fn detect_credential(content: &Value) -> Option<(PoolProviderType, String)> {
+    let obj = content.as_object()?;
+    let field = |key: &str| {
+        obj.get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .map(str::to_string)
+    };
+    let refresh_token = field("refresh_token").or_else(|| field("refreshToken"));
+
+    match field("type").as_deref() {
+        Some("antigravity") => return Some((PoolProviderType::Antigravity, refresh_token?)),
+        Some("claude_oauth") | Some("claude") => {
+            return Some((PoolProviderType::ClaudeOAuth, refresh_token?))
+        }
+        Some("codex") => {
+            let secret = refresh_token.or_else(|| field("api_key"))?;
+            return Some((PoolProviderType::Codex, secret));
+        }
+        _ => {}
+    }
+
+    if let Some(token) = field("refreshToken") {
+        return Some((PoolProviderType::Kiro, token));
+    }
+    if let Some(token) = field("refresh_token") {
+        if obj.contains_key("id_token") || obj.contains_key("account_id") {
+            return Some((PoolProviderType::Codex, token));
+        }
+        let google_scope = field("scope").is_some_and(|s| s.contains("googleapis.com"));
+        if obj.contains_key("expiry_date") || google_scope {
+            return Some((PoolProviderType::Gemini, token));
+        }
+        return None;
+    }
+    field("OPENAI_API_KEY").map(|key| (PoolProviderType::Codex, key))
+}
+
+/// 读取并识别凭证文件，无法识别时返回 `Ok(None)`
+pub fn detect_credential_file(path: &Path) -> Result<Option<DetectedCredential>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {e}"))?;
+    let json: Value = serde_json::from_str(&content).map_err(|e| format!("JSON 格式无效: {e}"))?;
+    Ok(
+        detect_credential(&json).map(|(provider_type, secret)| DetectedCredential {
+            path: path.to_path_buf(),
+            provider_type,
+            fingerprint: credential_fingerprint(&secret),
+        }),
+    )
+}
+
+/// 凭证池中已有凭证的指纹（OAuth 凭证读取其文件，API Key 凭证使用 Key）
+pub fn existing_fingerprint(credential: &CredentialData) -> Option<String> {
+    if let Some(path) = get_oauth_creds_path(credential) {
+        return detect_credential_file(Path::new(&path))
+            .ok()
+            .flatten()
+            .map(|detected| detected.fingerprint);
+    }
+    let api_key = match credential {
+        CredentialData::OpenAIKey { api_key, .. }
+        | CredentialData::ClaudeKey { api_key, .. }
+        | CredentialData::VertexKey { api_key, .. }
+        | CredentialData::GeminiApiKey { api_key, .. }
+        | CredentialData::AnthropicKey { api_key, .. } => api_key,
+        _ => return None,
+    };
+    Some(credential_fingerprint(api_key))
+}
+
+/// 扫描目录并导入识别出的凭证
+///
+/// `existing` 为凭证池中已有凭证的指纹，导入成功的指纹会加入其中；
+/// `insert` 负责把凭证写入凭证池（复制文件、插入数据库）。
+pub fn import_credential_dir<F>(
+    dir: &Path,
+    existing: &mut HashSet<String>,
+    mut insert: F,
+) -> Result<CredentialImportReport, String>
+where
+    F: FnMut(&DetectedCredential) -> Result<(), String>,
+{
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("读取目录失败: {e}"))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        })
+        .collect();
+    files.sort();
+
+    let mut report = CredentialImportReport::default();
+    for path in files {
+        let detected = match detect_credential_file(&path) {
+            Ok(Some(detected)) => detected,
+            Ok(None) => {
+                report.push(
+                    &path,
+                    ImportStatus::Skipped,
+                    None,
+                    Some("无法识别的凭证格式".to_string()),
+                );
+                continue;
+            }
+            Err(e) => {
+                report.push(&path, ImportStatus::Failed, None, Some(e));
+                continue;
+            }
+        };
+
+        let provider_type = Some(detected.provider_type);
+        if existing.contains(&detected.fingerprint) {
+            report.push(
+                &path,
+                ImportStatus::Skipped,
+                provider_type,
+                Some("凭证已存在".to_string()),
+            );
+            continue;
+        }
+        match insert(&detected) {
+            Ok(()) => {
+                existing.insert(detected.fingerprint);
+                report.push(&path, ImportStatus::Imported, provider_type, None);
+            }
+            Err(e) => report.push(&path, ImportStatus::Failed, provider_type, Some(e)),
+        }
+    }
+
+    tracing::info!(
+        "[IMPORT] 扫描 {:?}：导入 {}，跳过 {}，失败 {}",
+        dir,
+        report.imported,
+        report.skipped,
+        report.failed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+
+    fn fixture_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        let kiro = json!({
+            "accessToken": "aoa-access",
+            "refreshToken": "kiro-refresh-1",
+            "expiresAt": "2026-01-01T00:00:00Z",
+            "authMethod": "social",
+            "provider": "Google"
+        });
+        write(path, "kiro-auth-token.json", &kiro.to_string());
+        // 同一账号的另一份拷贝
+        write(path, "kiro-z-copy.json", &kiro.to_string());
+        // Kiro 缓存目录中的 clientIdHash 文件不是凭证
+        write(
+            path,
+            "d1b2c3.json",
+            &json!({"clientId": "cid", "clientSecret": "secret"}).to_string(),
+        );
+        write(
+            path,
+            "oauth_creds.json",
+            &json!({
+                "access_token": "ya29.x",
+                "refresh_token": "gemini-refresh",
+                "scope": "https://www.googleapis.com/auth/cloud-platform",
+                "token_type": "Bearer",
+                "expiry_date": 1760000000000u64
+            })
+            .to_string(),
+        );
+        write(
+            path,
+            "antigravity.json",
+            &json!({"type": "antigravity", "refresh_token": "ag-refresh"}).to_string(),
+        );
+        write(
+            path,
+            "codex.json",
+            &json!({"id_token": "jwt", "refresh_token": "codex-refresh", "account_id": "acc"})
+                .to_string(),
+        );
+        write(
+            path,
+            "claude.json",
+            &json!({"type": "claude_oauth", "refresh_token": "claude-refresh"}).to_string(),
+        );
+        write(path, "broken.json", "{not json");
+        write(path, "notes.txt", "ignored");
+        dir
+    }
+
+    #[test]
+    fn test_detect_credential_formats() {
+        let dir = fixture_dir();
+        let detect = |name: &str| {
+            detect_credential_file(&dir.path().join(name))
+                .unwrap()
+                .map(|d| d.provider_type)
+        };
+        assert_eq!(detect("kiro-auth-token.json"), Some(PoolProviderType::Kiro));
+        assert_eq!(detect("oauth_creds.json"), Some(PoolProviderType::Gemini));
+        assert_eq!(
+            detect("antigravity.json"),
+            Some(PoolProviderType::Antigravity)
+        );
+        assert_eq!(detect("codex.json"), Some(PoolProviderType::Codex));
+        assert_eq!(detect("claude.json"), Some(PoolProviderType::ClaudeOAuth));
+        assert_eq!(detect("d1b2c3.json"), None);
+        assert!(detect_credential_file(&dir.path().join("broken.json")).is_err());
+        assert_eq!(
+            detect_credential(&json!({"OPENAI_API_KEY": "sk-x"})).map(|d| d.0),
+            Some(PoolProviderType::Codex)
+        );
+    }
+
+    #[test]
+    fn test_import_dir_dedups_by_fingerprint() {
+        let dir = fixture_dir();
+        // 凭证池中已有同一个 Gemini 账号
+        let mut existing = HashSet::from([credential_fingerprint("gemini-refresh")]);
+        let mut inserted = Vec::new();
+
+        let report = import_credential_dir(dir.path(), &mut existing, |detected| {
+            if detected.provider_type == PoolProviderType::ClaudeOAuth {
+                return Err("写入数据库失败".to_string());
+            }
+            inserted.push(detected.provider_type);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            inserted,
+            vec![
+                PoolProviderType::Antigravity,
+                PoolProviderType::Codex,
+                PoolProviderType::Kiro,
+            ]
+        );
+        assert_eq!((report.imported, report.skipped, report.failed), (3, 3, 2));
+        assert_eq!(report.files.len(), 8);
+
+        let status_of = |name: &str| {
+            report
+                .files
+                .iter()
+                .find(|f| f.path.ends_with(name))
+                .map(|f| f.status)
+                .unwrap()
+        };
+        assert_eq!(status_of("kiro-auth-token.json"), ImportStatus::Imported);
+        // 同名账号的第二份拷贝按指纹去重
+        assert_eq!(status_of("kiro-z-copy.json"), ImportStatus::Skipped);
+        assert_eq!(status_of("oauth_creds.json"), ImportStatus::Skipped);
+        assert_eq!(status_of("d1b2c3.json"), ImportStatus::Skipped);
+        assert_eq!(status_of("broken.json"), ImportStatus::Failed);
+        assert_eq!(status_of("claude.json"), ImportStatus::Failed);
+        assert!(existing.contains(&credential_fingerprint("kiro-refresh-1")));
+    }
+}
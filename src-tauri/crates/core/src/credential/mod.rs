@@ -5,11 +5,20 @@
 //! 因依赖 infra crate 保留在主 crate 中。
 
 pub mod health;
+pub mod import;
 pub mod pool;
+pub mod refresh_lock;
 pub mod risk;
+pub mod selection_trace;
 pub mod types;
 
 pub use health::{HealthCheckConfig, HealthCheckResult, HealthChecker, HealthStatus};
+pub use import::{
+    credential_fingerprint, existing_fingerprint, import_credential_dir, CredentialImportReport,
+    DetectedCredential, FileImportResult, ImportStatus,
+};
 pub use pool::{CredentialPool, PoolError, PoolStatus};
+pub use refresh_lock::RefreshLocks;
 pub use risk::{CooldownConfig, RateLimitEvent, RateLimitStats, RiskController, RiskLevel};
+pub use selection_trace::{FilterReason, FilteredCandidate, SelectionFactor, SelectionTrace};
 pub use types::{Credential, CredentialData, CredentialStats, CredentialStatus};
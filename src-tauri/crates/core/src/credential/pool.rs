@@ -7,7 +7,8 @@ use crate::ProviderType;
 use chrono::{DateTime, Duration, Utc};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 
 /// 凭证池 - 管理同一 Provider 的多个凭证
 pub struct CredentialPool {
@@ -17,6 +18,8 @@ pub struct CredentialPool {
     pub credentials: DashMap<String, Credential>,
     /// 轮询索引（用于负载均衡）
     round_robin_index: AtomicUsize,
+    /// 各凭证正在处理的请求数（id -> 计数）
+    in_flight: DashMap<String, AtomicU32>,
 }
 
 /// 凭证池状态
@@ -34,6 +37,12 @@ pub struct PoolStatus {
     pub unhealthy: usize,
     /// 已禁用凭证数
     pub disabled: usize,
+    /// 需要轮换的凭证数（仍计入可用）
+    #[serde(default)]
+    pub needs_rotation: usize,
+    /// 各凭证正在处理的请求数（只包含非零项）
+    #[serde(default)]
+    pub in_flight: HashMap<String, u32>,
 }
 
 /// 凭证池错误
@@ -69,6 +78,7 @@ impl CredentialPool {
             provider,
             credentials: DashMap::new(),
             round_robin_index: AtomicUsize::new(0),
+            in_flight: DashMap::new(),
         }
     }
 
@@ -104,6 +114,7 @@ impl CredentialPool {
     /// # 错误
     /// - 如果凭证不存在，返回 `PoolError::CredentialNotFound`
     pub fn remove(&self, id: &str) -> Result<Credential, PoolError> {
+        self.in_flight.remove(id);
         self.credentials
             .remove(id)
             .map(|(_, cred)| cred)
@@ -136,6 +147,7 @@ impl CredentialPool {
         let mut cooldown = 0;
         let mut unhealthy = 0;
         let mut disabled = 0;
+        let mut needs_rotation = 0;
 
         for entry in self.credentials.iter() {
             match &entry.value().status {
@@ -143,6 +155,7 @@ impl CredentialPool {
                 CredentialStatus::Cooldown { .. } => cooldown += 1,
                 CredentialStatus::Unhealthy { .. } => unhealthy += 1,
                 CredentialStatus::Disabled => disabled += 1,
+                CredentialStatus::NeedsRotation => needs_rotation += 1,
             }
         }
 
@@ -153,6 +166,13 @@ impl CredentialPool {
             cooldown,
             unhealthy,
             disabled,
+            needs_rotation,
+            in_flight: self
+                .in_flight
+                .iter()
+                .map(|r| (r.key().clone(), r.value().load(Ordering::SeqCst)))
+                .filter(|(_, count)| *count > 0)
+                .collect(),
         }
     }
 
@@ -212,8 +232,37 @@ impl CredentialPool {
         }
     }
 
+    /// 将超过轮换周期的活跃凭证标记为需要轮换
+    ///
+    /// 返回本次新标记的凭证 ID。冷却、不健康或禁用的凭证保持原状态，
+    /// 恢复为活跃后在下一次检查时再标记。
+    pub fn flag_rotation_due(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut flagged = Vec::new();
+        for mut entry in self.credentials.iter_mut() {
+            if entry.status == CredentialStatus::Active && entry.is_rotation_due_at(now) {
+                entry.status = CredentialStatus::NeedsRotation;
+                flagged.push(entry.id.clone());
+            }
+        }
+        flagged
+    }
+
+    /// 记录凭证已轮换（刷新），重置轮换计时
+    pub fn mark_rotated(&self, id: &str) -> Result<(), PoolError> {
+        let mut entry = self
+            .credentials
+            .get_mut(id)
+            .ok_or_else(|| PoolError::CredentialNotFound(id.to_string()))?;
+
+        entry.mark_rotated();
+        Ok(())
+    }
+
     /// 获取下一个可用凭证（轮询策略）
     ///
+    /// 跳过已达并发上限的凭证；选择本身不占用并发名额，
+    /// 调用方通过 [`Self::try_acquire`] / [`Self::release`] 自行管理。
+    ///
     /// # 错误
     /// - 如果池为空，返回 `PoolError::EmptyPool`
     /// - 如果没有可用凭证，返回 `PoolError::NoAvailableCredential`
@@ -225,14 +274,8 @@ impl CredentialPool {
         // 先刷新冷却状态
         self.refresh_cooldowns();
 
-        // 收集所有活跃凭证
-        let active_creds: Vec<_> = self
-            .credentials
-            .iter()
-            .filter(|r| r.value().is_available())
-            .map(|r| r.value().clone())
-            .collect();
-
+        // 收集所有活跃且未达并发上限的凭证
+        let active_creds = self.selectable();
         if active_creds.is_empty() {
             return Err(PoolError::NoAvailableCredential);
         }
@@ -242,6 +285,56 @@ impl CredentialPool {
         Ok(active_creds[index].clone())
     }
 
+    /// 可供选择的凭证：活跃且未达并发上限
+    pub fn selectable(&self) -> Vec<Credential> {
+        self.credentials
+            .iter()
+            .filter(|r| r.value().is_available() && !self.is_at_capacity(r.value()))
+            .map(|r| r.value().clone())
+            .collect()
+    }
+
+    /// 凭证正在处理的请求数
+    pub fn in_flight(&self, id: &str) -> u32 {
+        self.in_flight
+            .get(id)
+            .map(|count| count.load(Ordering::SeqCst))
+            .unwrap_or(0)
+    }
+
+    /// 凭证是否已达并发上限
+    pub fn is_at_capacity(&self, credential: &Credential) -> bool {
+        credential
+            .max_concurrency
+            .is_some_and(|max| self.in_flight(&credential.id) >= max)
+    }
+
+    /// 为凭证占用一个并发名额，已达上限时返回 false
+    pub fn try_acquire(&self, credential: &Credential) -> bool {
+        let count = self
+            .in_flight
+            .entry(credential.id.clone())
+            .or_insert_with(|| AtomicU32::new(0));
+        match credential.max_concurrency {
+            Some(max) => count
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                    (n < max).then_some(n + 1)
+                })
+                .is_ok(),
+            None => {
+                count.fetch_add(1, Ordering::SeqCst);
+                true
+            }
+        }
+    }
+
+    /// 请求结束，归还凭证的并发名额
+    pub fn release(&self, id: &str) {
+        if let Some(count) = self.in_flight.get(id) {
+            let _ = count.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+        }
+    }
+
     /// 获取最早恢复时间（当所有凭证都在冷却时）
     pub fn earliest_recovery(&self) -> Option<DateTime<Utc>> {
         self.credentials
@@ -350,6 +443,47 @@ mod pool_tests {
         assert!(pool.get("nonexistent").is_none());
     }
 
+    #[test]
+    fn test_pool_concurrency_cap() {
+        let pool = CredentialPool::new(ProviderType::Kiro);
+        pool.add(create_test_credential("capped").with_max_concurrency(Some(2)))
+            .unwrap();
+        pool.add(create_test_credential("unlimited")).unwrap();
+
+        // 选择本身不占用名额
+        for _ in 0..4 {
+            pool.next_available().unwrap();
+        }
+        assert_eq!(pool.in_flight("capped"), 0);
+
+        // capped 占满两个名额后只选择 unlimited
+        let capped = pool.get("capped").unwrap();
+        let unlimited = pool.get("unlimited").unwrap();
+        assert!(pool.try_acquire(&capped));
+        assert!(pool.try_acquire(&capped));
+        assert!(!pool.try_acquire(&capped));
+        assert!(pool.try_acquire(&unlimited));
+        assert!(pool.is_at_capacity(&capped));
+        for _ in 0..3 {
+            assert_eq!(pool.next_available().unwrap().id, "unlimited");
+        }
+
+        let status = pool.status();
+        assert_eq!(status.in_flight.get("capped"), Some(&2));
+        assert_eq!(status.in_flight.get("unlimited"), Some(&1));
+
+        // 归还名额后 capped 重新可选
+        pool.release("capped");
+        assert_eq!(pool.in_flight("capped"), 1);
+        assert!(pool.selectable().iter().any(|c| c.id == "capped"));
+
+        // 多余的 release 不会下溢
+        pool.release("capped");
+        pool.release("capped");
+        assert_eq!(pool.in_flight("capped"), 0);
+        assert!(!pool.status().in_flight.contains_key("capped"));
+    }
+
     #[test]
     fn test_pool_status() {
         let pool = CredentialPool::new(ProviderType::Kiro);
@@ -413,4 +547,40 @@ mod pool_tests {
         assert_eq!(cred.stats.total_requests, 1);
         assert_eq!(cred.stats.consecutive_failures, 1);
     }
+
+    #[test]
+    fn test_pool_rotation_flag_and_reset() {
+        let pool = CredentialPool::new(ProviderType::Kiro);
+        pool.add(create_test_credential("rotating").with_rotation(Some(30)))
+            .unwrap();
+        pool.add(create_test_credential("no-rotation")).unwrap();
+
+        // 轮换周期内不标记
+        assert!(pool
+            .flag_rotation_due(Utc::now() + Duration::days(29))
+            .is_empty());
+
+        // 超过轮换周期后标记，但仍可使用
+        let flagged = pool.flag_rotation_due(Utc::now() + Duration::days(31));
+        assert_eq!(flagged, vec!["rotating".to_string()]);
+        let cred = pool.get("rotating").unwrap();
+        assert_eq!(cred.status, CredentialStatus::NeedsRotation);
+        assert!(cred.is_available());
+        assert_eq!(pool.active_count(), 2);
+        assert_eq!(pool.status().needs_rotation, 1);
+
+        // 刷新后清除标记并重新计时
+        pool.mark_rotated("rotating").unwrap();
+        let cred = pool.get("rotating").unwrap();
+        assert_eq!(cred.status, CredentialStatus::Active);
+        assert!(cred.rotated_at.is_some());
+        assert!(pool
+            .flag_rotation_due(Utc::now() + Duration::days(29))
+            .is_empty());
+        assert_eq!(
+            pool.flag_rotation_due(Utc::now() + Duration::days(31))
+                .len(),
+            1
+        );
+    }
 }
@@ -0,0 +1,122 @@
+//! 按凭证划分的 Token 刷新锁
+//!
+//! 同一凭证的并发请求只允许一个执行刷新，其余等待锁释放后复查 Token（双重检查），
+//! 不同凭证之间互不阻塞，可以并行刷新。
+
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// 按凭证 UUID 划分的刷新锁
+#[derive(Debug, Default)]
+pub struct RefreshLocks {
+    locks: DashMap<String, Arc<Mutex<()>>>,
+}
+
+impl RefreshLocks {
+    /// 创建空的刷新锁表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 获取指定凭证的刷新锁
+    ///
+    /// 持有返回的守卫期间，同一 `key` 的其他调用会等待；
+    /// 拿到锁后应先复查 Token 是否已被其他请求刷新。
+    pub async fn lock(&self, key: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .locks
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+        lock.lock_owned().await
+    }
+
+    /// 已创建锁的凭证数量
+    pub fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    /// 是否没有任何锁
+    pub fn is_empty(&self) -> bool {
+        self.locks.is_empty()
+    }
+
+    /// 移除凭证的锁（凭证删除时调用）
+    pub fn remove(&self, key: &str) {
+        self.locks.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// 模拟带双重检查的刷新：Token 无效时才刷新
+    async fn refresh_if_needed(
+        locks: &RefreshLocks,
+        key: &str,
+        valid: &AtomicBool,
+        refreshes: &AtomicUsize,
+    ) {
+        if valid.load(Ordering::SeqCst) {
+            return;
+        }
+        let _guard = locks.lock(key).await;
+        if valid.load(Ordering::SeqCst) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        refreshes.fetch_add(1, Ordering::SeqCst);
+        valid.store(true, Ordering::SeqCst);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_refresh_once() {
+        let locks = Arc::new(RefreshLocks::new());
+        let valid = Arc::new(AtomicBool::new(false));
+        let refreshes = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let (locks, valid, refreshes) = (locks.clone(), valid.clone(), refreshes.clone());
+                tokio::spawn(async move {
+                    refresh_if_needed(&locks, "cred-a", &valid, &refreshes).await;
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+        assert_eq!(locks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_different_credentials_refresh_in_parallel() {
+        let locks = Arc::new(RefreshLocks::new());
+        // 两个凭证的刷新都要等对方进入临界区才能完成，串行执行会超时
+        let barrier = Arc::new(tokio::sync::Barrier::new(2));
+
+        let tasks: Vec<_> = ["cred-a", "cred-b"]
+            .into_iter()
+            .map(|key| {
+                let (locks, barrier) = (locks.clone(), barrier.clone());
+                tokio::spawn(async move {
+                    let _guard = locks.lock(key).await;
+                    barrier.wait().await;
+                })
+            })
+            .collect();
+        let all = futures::future::join_all(tasks);
+        tokio::time::timeout(Duration::from_secs(2), all)
+            .await
+            .expect("不同凭证的刷新不应互相阻塞");
+
+        locks.remove("cred-a");
+        assert_eq!(locks.len(), 1);
+    }
+}
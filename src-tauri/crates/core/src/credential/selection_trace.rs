@@ -0,0 +1,177 @@
+//! 凭证选择追踪
+//!
+//! 记录单次请求选择凭证的过程：考虑了哪些候选凭证、哪些被过滤（及原因），
+//! 最终选中的凭证和决定因素，写入请求日志，用于回答“为什么选了这个账号”。
+//!
+//! 完整的候选 / 过滤列表开销较大，默认只记录摘要（候选数、过滤数和选中结果），
+//! 配置 `telemetry.verbose_selection_trace` 后才记录详细列表。
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::provider_pool_model::ProviderCredential;
+
+/// 候选凭证被过滤的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterReason {
+    /// 已手动禁用
+    Disabled,
+    /// 不健康，处于冷却中
+    Cooldown,
+    /// 已达每日 Token 上限
+    Quota,
+    /// 不支持请求的模型
+    ModelUnsupported,
+    /// 本次请求已尝试过（重试 / 换用凭证时排除）
+    Excluded,
+    /// 不兼容当前客户端
+    ClientIncompatible,
+    /// 存在偏好该模型的其他凭证
+    NotPreferred,
+    /// 已达最大并发请求数
+    Busy,
+}
+
+/// 被过滤的候选凭证
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilteredCandidate {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub reason: FilterReason,
+}
+
+/// 选中凭证的决定因素
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SelectionFactor {
+    /// 过滤后只剩一个候选
+    OnlyCandidate,
+    /// 综合权重分数最高
+    HighestScore { score: f64 },
+    /// 响应延迟最低（最快优先策略）
+    LowestLatency { latency_ms: f64 },
+    /// 探索延迟样本缺失或最久未使用的凭证（最快优先策略）
+    Explore,
+    /// 凭证池无可用凭证，降级到 API Key Provider
+    ApiKeyFallback,
+}
+
+/// 单次凭证选择的追踪记录
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SelectionTrace {
+    /// 请求的 Provider 类型
+    pub provider_type: String,
+    /// 候选凭证总数
+    pub candidate_count: usize,
+    /// 被过滤的候选数
+    pub filtered_count: usize,
+    /// 候选凭证 UUID（仅详细模式）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<String>,
+    /// 被过滤的候选及原因（仅详细模式）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filtered: Vec<FilteredCandidate>,
+    /// 选中的凭证 UUID
+    pub chosen: Option<String>,
+    /// 决定因素
+    pub factor: Option<SelectionFactor>,
+    /// 是否记录详细列表
+    #[serde(skip)]
+    verbose: bool,
+}
+
+impl SelectionTrace {
+    /// 开始追踪一次选择，`verbose` 为 false 时只记录摘要
+    pub fn new(provider_type: impl Into<String>, verbose: bool) -> Self {
+        Self {
+            provider_type: provider_type.into(),
+            verbose,
+            ..Default::default()
+        }
+    }
+
+    /// 记录考虑的候选凭证
+    pub fn consider(&mut self, credentials: &[ProviderCredential]) {
+        self.candidate_count += credentials.len();
+        if self.verbose {
+            self.candidates
+                .extend(credentials.iter().map(|c| c.uuid.clone()));
+        }
+    }
+
+    /// 只保留满足 `keep` 的候选，其余以 `reason` 记为已过滤
+    pub fn retain<F>(
+        &mut self,
+        credentials: &mut Vec<ProviderCredential>,
+        reason: FilterReason,
+        mut keep: F,
+    ) where
+        F: FnMut(&ProviderCredential) -> bool,
+    {
+        credentials.retain(|c| {
+            let kept = keep(c);
+            if !kept {
+                self.filtered_count += 1;
+                if self.verbose {
+                    self.filtered.push(FilteredCandidate {
+                        uuid: c.uuid.clone(),
+                        name: c.name.clone(),
+                        reason,
+                    });
+                }
+            }
+            kept
+        });
+    }
+
+    /// 记录选中的凭证
+    pub fn choose(&mut self, uuid: &str, factor: SelectionFactor) {
+        self.chosen = Some(uuid.to_string());
+        self.factor = Some(factor);
+    }
+
+    /// 指定凭证被过滤的原因
+    pub fn reason_for(&self, uuid: &str) -> Option<FilterReason> {
+        self.filtered
+            .iter()
+            .find(|f| f.uuid == uuid)
+            .map(|f| f.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+    fn credential(uuid: &str) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: format!("sk-{uuid}"),
+                base_url: None,
+            },
+        );
+        cred.uuid = uuid.to_string();
+        cred.name = Some(uuid.to_string());
+        cred
+    }
+
+    #[test]
+    fn test_compact_trace_keeps_summary_only() {
+        let mut credentials = vec![credential("a"), credential("b")];
+        let mut compact = SelectionTrace::new("claude", false);
+        compact.consider(&credentials);
+        compact.retain(&mut credentials, FilterReason::Excluded, |c| c.uuid != "a");
+        compact.choose("b", SelectionFactor::HighestScore { score: 87.5 });
+
+        assert_eq!(compact.candidate_count, 2);
+        assert_eq!(compact.filtered_count, 1);
+        assert!(compact.candidates.is_empty());
+        assert!(compact.filtered.is_empty());
+        assert_eq!(compact.chosen.as_deref(), Some("b"));
+
+        let json = serde_json::to_value(&compact).unwrap();
+        assert!(json.get("filtered").is_none());
+    }
+}
@@ -3,7 +3,7 @@
 //! 定义凭证、凭证数据、凭证状态等核心类型
 
 use crate::ProviderType;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 /// 凭证 - 表示单个 API 凭证
@@ -26,6 +26,24 @@ pub struct Credential {
     /// Per-Key 代理 URL（覆盖全局代理）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_url: Option<String>,
+    /// 轮换周期（天），超过后标记为需要轮换；None 表示不提醒
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_after_days: Option<u32>,
+    /// 最后一次轮换（刷新）时间，None 时从创建时间开始计算
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// 同时处理的最大请求数，None 表示不限制
+    ///
+    /// 部分账号（如 Kiro）并发请求过多时会被上游严格限流
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<u32>,
+    /// 加权轮询权重（默认 1），权重越大分到的请求越多
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
 }
 
 impl Credential {
@@ -40,9 +58,31 @@ impl Credential {
             status: CredentialStatus::Active,
             stats: CredentialStats::default(),
             proxy_url: None,
+            rotate_after_days: None,
+            rotated_at: None,
+            max_concurrency: None,
+            weight: default_weight(),
         }
     }
 
+    /// 设置加权轮询权重（最小为 1）
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight.max(1);
+        self
+    }
+
+    /// 设置最大并发请求数
+    pub fn with_max_concurrency(mut self, max_concurrency: Option<u32>) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    /// 设置轮换周期（天）
+    pub fn with_rotation(mut self, rotate_after_days: Option<u32>) -> Self {
+        self.rotate_after_days = rotate_after_days;
+        self
+    }
+
     /// 创建带代理的凭证
     pub fn with_proxy(mut self, proxy_url: Option<String>) -> Self {
         self.proxy_url = proxy_url;
@@ -59,9 +99,31 @@ impl Credential {
         self.proxy_url.as_deref()
     }
 
-    /// 检查凭证是否可用（活跃状态）
+    /// 检查凭证是否可用（活跃状态，需要轮换的凭证仍可使用）
     pub fn is_available(&self) -> bool {
-        matches!(self.status, CredentialStatus::Active)
+        matches!(
+            self.status,
+            CredentialStatus::Active | CredentialStatus::NeedsRotation
+        )
+    }
+
+    /// 轮换到期时间（未设置轮换周期时为 None）
+    pub fn rotation_due_at(&self) -> Option<DateTime<Utc>> {
+        let days = self.rotate_after_days?;
+        Some(self.rotated_at.unwrap_or(self.created_at) + Duration::days(days as i64))
+    }
+
+    /// 在指定时间点是否已超过轮换周期
+    pub fn is_rotation_due_at(&self, now: DateTime<Utc>) -> bool {
+        self.rotation_due_at().is_some_and(|due| due <= now)
+    }
+
+    /// 记录一次轮换（刷新），重置轮换计时并清除轮换提醒
+    pub fn mark_rotated(&mut self) {
+        self.rotated_at = Some(Utc::now());
+        if self.status == CredentialStatus::NeedsRotation {
+            self.status = CredentialStatus::Active;
+        }
     }
 
     /// 更新最后使用时间
@@ -105,9 +167,15 @@ pub enum CredentialStatus {
     },
     /// 已禁用
     Disabled,
+    /// 超过轮换周期，需要重新授权/更换（仍可使用）
+    NeedsRotation,
 }
 
 /// 凭证统计信息
+///
+/// 仅供内存中的 `CredentialPool` 负载均衡使用，不落库。最后成功/错误时间和最后错误消息
+/// 由 `ProviderCredential` 的 `last_success_time`、`last_error_time`、`last_error_message`
+/// 记录：请求路径和健康检查都更新它们并通过 `ProviderPoolDao` 持久化，凭证池概览也读取它们。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct CredentialStats {
     /// 总请求数
@@ -0,0 +1,142 @@
+//! 管理 API 审计日志数据访问对象
+//!
+//! 记录每次修改类管理 API 调用（操作、目标、时间、来源 IP、变更内容）。
+//! 日志只追加不修改；写入前会把变更内容中的密钥字段替换为占位符。
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::diagnostics::redact_value;
+
+/// 审计日志条目
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    /// 自增 ID
+    pub id: i64,
+    /// 操作（如 `add_credential`、`update_config`）
+    pub action: String,
+    /// 操作目标（如凭证 ID、配置项）
+    pub target: String,
+    /// 操作时间
+    pub timestamp: DateTime<Utc>,
+    /// 来源 IP
+    pub source_ip: Option<String>,
+    /// 脱敏后的变更内容
+    pub diff: Value,
+}
+
+pub struct AuditLogDao;
+
+impl AuditLogDao {
+    /// 追加一条审计日志，返回条目 ID
+    pub fn append(
+        conn: &Connection,
+        action: &str,
+        target: &str,
+        source_ip: Option<&str>,
+        diff: Value,
+    ) -> Result<i64, rusqlite::Error> {
+        let diff = redact_value(diff);
+        conn.execute(
+            "INSERT INTO management_audit_log (action, target, timestamp, source_ip, diff)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                action,
+                target,
+                Utc::now().to_rfc3339(),
+                source_ip,
+                diff.to_string()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 获取最近的审计日志（按时间倒序）
+    pub fn list(conn: &Connection, limit: usize) -> Result<Vec<AuditEntry>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, action, target, timestamp, source_ip, diff
+             FROM management_audit_log ORDER BY id DESC LIMIT ?1",
+        )?;
+        let entries = stmt.query_map([limit as i64], |row| {
+            let timestamp: String = row.get(3)?;
+            let diff: String = row.get(5)?;
+            Ok(AuditEntry {
+                id: row.get(0)?,
+                action: row.get(1)?,
+                target: row.get(2)?,
+                timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_default(),
+                source_ip: row.get(4)?,
+                diff: serde_json::from_str(&diff).unwrap_or(Value::Null),
+            })
+        })?;
+        entries.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::schema;
+    use crate::models::provider_pool_model::{
+        CredentialData, PoolProviderType, ProviderCredential,
+    };
+
+    #[test]
+    fn test_mutations_are_audited_without_secrets() {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+
+        let credential = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-audit-secret-1234".to_string(),
+                base_url: None,
+            },
+        );
+        AuditLogDao::append(
+            &conn,
+            "add_credential",
+            &credential.uuid,
+            Some("127.0.0.1"),
+            serde_json::json!({ "after": credential }),
+        )
+        .unwrap();
+        AuditLogDao::append(
+            &conn,
+            "update_config",
+            "default_provider",
+            Some("10.0.0.2"),
+            serde_json::json!({
+                "default_provider": { "before": "kiro", "after": "openai" },
+                "secret_key": "mgmt-secret-5678",
+            }),
+        )
+        .unwrap();
+
+        let entries = AuditLogDao::list(&conn, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, "update_config");
+        assert_eq!(entries[0].source_ip.as_deref(), Some("10.0.0.2"));
+        assert_eq!(
+            entries[0].diff["default_provider"]["after"],
+            Value::from("openai")
+        );
+        assert_eq!(entries[1].action, "add_credential");
+        assert_eq!(entries[1].target, credential.uuid);
+
+        let raw: Vec<String> = conn
+            .prepare("SELECT diff FROM management_audit_log")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let raw = raw.join("\n");
+        assert!(!raw.contains("sk-audit-secret-1234"), "{raw}");
+        assert!(!raw.contains("mgmt-secret-5678"), "{raw}");
+    }
+}
@@ -1,12 +1,14 @@
 pub mod a2ui_form_dao;
 pub mod agent;
 pub mod api_key_provider;
+pub mod audit_log;
 pub mod brand_persona_dao;
 pub mod chat;
 pub mod general_chat;
 pub mod installed_plugins;
 pub mod material_dao;
 pub mod mcp;
+pub mod model_registry;
 pub mod orchestrator;
 pub mod persona_dao;
 pub mod poster_material_dao;
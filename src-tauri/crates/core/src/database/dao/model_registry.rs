@@ -0,0 +1,94 @@
+use crate::models::model_registry::ModelLimits;
+use rusqlite::Connection;
+
+pub struct ModelRegistryDao;
+
+impl ModelRegistryDao {
+    /// 获取注册表中声明了最大输出 token 数的模型（模型 ID -> 上限）
+    pub fn get_max_output_tokens(conn: &Connection) -> Result<Vec<(String, u32)>, rusqlite::Error> {
+        let mut stmt = conn.prepare("SELECT id, limits FROM model_registry")?;
+
+        let rows = stmt.query_map([], |row| {
+            let id: String = row.get(0)?;
+            let limits_json: String = row.get(1)?;
+            Ok((id, limits_json))
+        })?;
+
+        let mut ceilings = Vec::new();
+        for row in rows {
+            let (id, limits_json) = row?;
+            let limits: ModelLimits = serde_json::from_str(&limits_json).unwrap_or_default();
+            if let Some(max_output) = limits.max_output_tokens {
+                ceilings.push((id, max_output));
+            }
+        }
+        Ok(ceilings)
+    }
+
+    /// 获取注册表中的所有模型（模型 ID, provider_id），按 Provider 和模型 ID 排序
+    pub fn get_model_providers(
+        conn: &Connection,
+    ) -> Result<Vec<(String, String)>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, provider_id FROM model_registry
+             WHERE status != 'deprecated'
+             ORDER BY provider_id, id",
+        )?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::schema::create_tables;
+
+    #[test]
+    fn test_get_max_output_tokens_skips_models_without_ceiling() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        for (id, limits) in [
+            ("claude-sonnet-4-5", r#"{"max_output_tokens":64000}"#),
+            ("mystery-model", "{}"),
+        ] {
+            conn.execute(
+                "INSERT INTO model_registry (id, display_name, provider_id, provider_name, limits, created_at, updated_at)
+                 VALUES (?1, ?1, 'test', 'Test', ?2, 0, 0)",
+                [id, limits],
+            )
+            .unwrap();
+        }
+
+        let ceilings = ModelRegistryDao::get_max_output_tokens(&conn).unwrap();
+        assert_eq!(ceilings, vec![("claude-sonnet-4-5".to_string(), 64000)]);
+    }
+
+    #[test]
+    fn test_get_model_providers_skips_deprecated_models() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        for (id, provider, status) in [
+            ("gpt-4o", "openai", "active"),
+            ("claude-sonnet-4-5", "anthropic", "active"),
+            ("claude-2", "anthropic", "deprecated"),
+        ] {
+            conn.execute(
+                "INSERT INTO model_registry (id, display_name, provider_id, provider_name, status, created_at, updated_at)
+                 VALUES (?1, ?1, ?2, ?2, ?3, 0, 0)",
+                [id, provider, status],
+            )
+            .unwrap();
+        }
+
+        let models = ModelRegistryDao::get_model_providers(&conn).unwrap();
+        assert_eq!(
+            models,
+            vec![
+                ("claude-sonnet-4-5".to_string(), "anthropic".to_string()),
+                ("gpt-4o".to_string(), "openai".to_string()),
+            ]
+        );
+    }
+}
@@ -2,12 +2,16 @@
 //!
 //! 提供凭证池的 CRUD 操作。
 
+use crate::database::secret_store::{
+    active_secret_store, delete_secret, externalize_secret, hydrate_credential,
+};
 use crate::models::provider_pool_model::{
     CachedTokenInfo, CredentialData, CredentialSource, PoolProviderType, ProviderCredential,
     ProviderPools,
 };
 use chrono::{DateTime, TimeZone, Utc};
 use rusqlite::{params, Connection};
+use std::collections::HashSet;
 
 pub struct ProviderPoolDao;
 
@@ -18,7 +22,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, daily_token_limit,
+                    last_success_time, preferred_models, rotate_after_days, rotated_at, needs_rotation, max_concurrency
              FROM provider_pool_credentials
              ORDER BY provider_type, created_at ASC",
         )?;
@@ -41,7 +46,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, daily_token_limit,
+                    last_success_time, preferred_models, rotate_after_days, rotated_at, needs_rotation, max_concurrency
              FROM provider_pool_credentials
              WHERE provider_type = ?1
              ORDER BY created_at ASC",
@@ -67,7 +73,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, daily_token_limit,
+                    last_success_time, preferred_models, rotate_after_days, rotated_at, needs_rotation, max_concurrency
              FROM provider_pool_credentials
              WHERE uuid = ?1",
         )?;
@@ -89,7 +96,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, daily_token_limit,
+                    last_success_time, preferred_models, rotate_after_days, rotated_at, needs_rotation, max_concurrency
              FROM provider_pool_credentials
              WHERE name = ?1",
         )?;
@@ -116,12 +124,13 @@ impl ProviderPoolDao {
 
     /// 插入新凭证
     pub fn insert(conn: &Connection, cred: &ProviderCredential) -> Result<(), rusqlite::Error> {
-        let credential_json =
-            serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string());
+        let credential_json = Self::credential_json(cred)?;
         let not_supported_models_json =
             serde_json::to_string(&cred.not_supported_models).unwrap_or_else(|_| "[]".to_string());
         let supported_models_json =
             serde_json::to_string(&cred.supported_models).unwrap_or_else(|_| "[]".to_string());
+        let preferred_models_json =
+            serde_json::to_string(&cred.preferred_models).unwrap_or_else(|_| "[]".to_string());
         let source_str = match cred.source {
             CredentialSource::Manual => "manual",
             CredentialSource::Imported => "imported",
@@ -133,8 +142,9 @@ impl ProviderPoolDao {
              (uuid, provider_type, credential_data, name, is_healthy, is_disabled,
               check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
               last_used, last_error_time, last_error_message, last_health_check_time,
-              last_health_check_model, created_at, updated_at, source, proxy_url)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+              last_health_check_model, created_at, updated_at, source, proxy_url, daily_token_limit,
+              last_success_time, preferred_models, rotate_after_days, rotated_at, needs_rotation, max_concurrency)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28)",
             params![
                 cred.uuid,
                 cred.provider_type.to_string(),
@@ -157,6 +167,13 @@ impl ProviderPoolDao {
                 cred.updated_at.timestamp(),
                 source_str,
                 cred.proxy_url,
+                cred.daily_token_limit.map(|v| v as i64),
+                cred.last_success_time.map(|t| t.timestamp()),
+                preferred_models_json,
+                cred.rotate_after_days,
+                cred.rotated_at.map(|t| t.timestamp()),
+                cred.needs_rotation,
+                cred.max_concurrency,
             ],
         )?;
         Ok(())
@@ -164,12 +181,13 @@ impl ProviderPoolDao {
 
     /// 更新凭证
     pub fn update(conn: &Connection, cred: &ProviderCredential) -> Result<(), rusqlite::Error> {
-        let credential_json =
-            serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string());
+        let credential_json = Self::credential_json(cred)?;
         let not_supported_models_json =
             serde_json::to_string(&cred.not_supported_models).unwrap_or_else(|_| "[]".to_string());
         let supported_models_json =
             serde_json::to_string(&cred.supported_models).unwrap_or_else(|_| "[]".to_string());
+        let preferred_models_json =
+            serde_json::to_string(&cred.preferred_models).unwrap_or_else(|_| "[]".to_string());
 
         conn.execute(
             "UPDATE provider_pool_credentials SET
@@ -177,7 +195,10 @@ impl ProviderPoolDao {
              is_disabled = ?6, check_health = ?7, check_model_name = ?8,
              not_supported_models = ?9, supported_models = ?10, usage_count = ?11, error_count = ?12,
              last_used = ?13, last_error_time = ?14, last_error_message = ?15,
-             last_health_check_time = ?16, last_health_check_model = ?17, updated_at = ?18, proxy_url = ?19
+             last_health_check_time = ?16, last_health_check_model = ?17, updated_at = ?18, proxy_url = ?19,
+             daily_token_limit = ?20, last_success_time = ?21, preferred_models = ?22,
+             rotate_after_days = ?23, rotated_at = ?24, needs_rotation = ?25,
+             max_concurrency = ?26
              WHERE uuid = ?1",
             params![
                 cred.uuid,
@@ -199,6 +220,13 @@ impl ProviderPoolDao {
                 cred.last_health_check_model,
                 cred.updated_at.timestamp(),
                 cred.proxy_url,
+                cred.daily_token_limit.map(|v| v as i64),
+                cred.last_success_time.map(|t| t.timestamp()),
+                preferred_models_json,
+                cred.rotate_after_days,
+                cred.rotated_at.map(|t| t.timestamp()),
+                cred.needs_rotation,
+                cred.max_concurrency,
             ],
         )?;
         Ok(())
@@ -210,9 +238,28 @@ impl ProviderPoolDao {
             "DELETE FROM provider_pool_credentials WHERE uuid = ?1",
             [uuid],
         )?;
+        if affected > 0 {
+            if let Some(store) = active_secret_store() {
+                if let Err(e) = delete_secret(store.as_ref(), uuid) {
+                    tracing::warn!("[SECRET_STORE] 删除凭证 {} 的密钥失败: {}", uuid, e);
+                }
+            }
+        }
         Ok(affected > 0)
     }
 
+    /// 序列化凭证数据
+    ///
+    /// 安装了密钥存储时，密钥字段写入存储，凭证行中只保存引用。
+    fn credential_json(cred: &ProviderCredential) -> Result<String, rusqlite::Error> {
+        let credential = match active_secret_store() {
+            Some(store) => externalize_secret(store.as_ref(), &cred.uuid, &cred.credential)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?,
+            None => cred.credential.clone(),
+        };
+        Ok(serde_json::to_string(&credential).unwrap_or_else(|_| "{}".to_string()))
+    }
+
     /// 更新健康状态
     pub fn update_health_status(
         conn: &Connection,
@@ -244,6 +291,31 @@ impl ProviderPoolDao {
         Ok(())
     }
 
+    /// 记录成功（请求成功或健康检查通过）
+    ///
+    /// 恢复健康并清零错误计数，更新最后成功时间；
+    /// 保留最后一次错误的时间和消息，便于排查。
+    pub fn record_success(
+        conn: &Connection,
+        uuid: &str,
+        success_time: DateTime<Utc>,
+        last_health_check_model: Option<&str>,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE provider_pool_credentials SET
+             is_healthy = 1, error_count = 0, last_success_time = ?2,
+             last_health_check_time = ?2, last_health_check_model = ?3, updated_at = ?4
+             WHERE uuid = ?1",
+            params![
+                uuid,
+                success_time.timestamp(),
+                last_health_check_model,
+                Utc::now().timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
     /// 更新使用统计
     pub fn update_usage(
         conn: &Connection,
@@ -277,6 +349,17 @@ impl ProviderPoolDao {
         Ok(())
     }
 
+    /// 恢复凭证健康状态（清零错误计数，最后一次错误的时间和消息保留不变）
+    pub fn mark_recovered(conn: &Connection, uuid: &str) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE provider_pool_credentials SET
+             is_healthy = 1, error_count = 0, updated_at = ?2
+             WHERE uuid = ?1",
+            params![uuid, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
     /// 重置指定类型的所有凭证健康状态
     pub fn reset_health_by_type(
         conn: &Connection,
@@ -292,6 +375,34 @@ impl ProviderPoolDao {
         Ok(affected)
     }
 
+    /// 设置凭证是否需要轮换
+    pub fn set_needs_rotation(
+        conn: &Connection,
+        uuid: &str,
+        needs_rotation: bool,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE provider_pool_credentials SET needs_rotation = ?2, updated_at = ?3
+             WHERE uuid = ?1",
+            params![uuid, needs_rotation, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// 记录凭证已轮换：重置轮换计时并清除轮换提醒
+    pub fn mark_rotated(
+        conn: &Connection,
+        uuid: &str,
+        rotated_at: DateTime<Utc>,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE provider_pool_credentials SET rotated_at = ?2, needs_rotation = 0, updated_at = ?3
+             WHERE uuid = ?1",
+            params![uuid, rotated_at.timestamp(), Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
     /// 从数据库行转换为 ProviderCredential
     fn row_to_credential(row: &rusqlite::Row) -> Result<ProviderCredential, rusqlite::Error> {
         let uuid: String = row.get(0)?;
@@ -315,13 +426,42 @@ impl ProviderPoolDao {
         let updated_at_ts: i64 = row.get(18)?;
         let source_str: Option<String> = row.get(19).ok();
         let proxy_url: Option<String> = row.get(20).ok();
+        let daily_token_limit: Option<u64> = row
+            .get::<_, Option<i64>>(21)
+            .ok()
+            .flatten()
+            .map(|v| v as u64);
+        let last_success_time_ts: Option<i64> = row.get(22).ok().flatten();
+        let preferred_models_json: Option<String> = row.get(23).ok().flatten();
+        let rotate_after_days: Option<u32> = row.get(24).ok().flatten();
+        let rotated_at_ts: Option<i64> = row.get(25).ok().flatten();
+        let needs_rotation: bool = row
+            .get::<_, Option<bool>>(26)
+            .ok()
+            .flatten()
+            .unwrap_or(false);
+        let max_concurrency: Option<u32> = row.get(27).ok().flatten();
 
         let provider_type: PoolProviderType =
             provider_type_str.parse().unwrap_or(PoolProviderType::Kiro);
 
-        let credential: CredentialData = serde_json::from_str(&credential_json).map_err(|e| {
-            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
-        })?;
+        let mut credential: CredentialData =
+            serde_json::from_str(&credential_json).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    2,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?;
+        // 密钥无法取回时该凭证不可用，避免把引用占位符发往上游
+        if let Err(e) = hydrate_credential(&mut credential) {
+            tracing::error!("[SECRET_STORE] 读取凭证 {} 的密钥失败: {}", uuid, e);
+            return Err(rusqlite::Error::FromSqlConversionFailure(
+                2,
+                rusqlite::types::Type::Text,
+                Box::new(e),
+            ));
+        }
 
         let not_supported_models: Vec<String> = not_supported_models_json
             .and_then(|s| serde_json::from_str(&s).ok())
@@ -331,6 +471,10 @@ impl ProviderPoolDao {
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default();
 
+        let preferred_models: Vec<String> = preferred_models_json
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
         let source = match source_str.as_deref() {
             Some("imported") => CredentialSource::Imported,
             Some("private") => CredentialSource::Private,
@@ -351,6 +495,8 @@ impl ProviderPoolDao {
             usage_count,
             error_count,
             last_used: last_used_ts.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+            last_success_time: last_success_time_ts
+                .and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
             last_error_time: last_error_time_ts.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
             last_error_message,
             last_health_check_time: last_health_check_time_ts
@@ -367,6 +513,12 @@ impl ProviderPoolDao {
             cached_token: None, // 从 get_token_cache 单独获取
             source,
             proxy_url,
+            daily_token_limit,
+            preferred_models,
+            rotate_after_days,
+            rotated_at: rotated_at_ts.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+            needs_rotation,
+            max_concurrency,
         })
     }
 
@@ -499,4 +651,216 @@ impl ProviderPoolDao {
         )?;
         Ok(())
     }
+
+    /// 把配置中的凭证同步到凭证池（配置热重载）
+    ///
+    /// 配置中的凭证插入或更新。`previous_ids` 为上次同步时配置中的凭证 ID，
+    /// `prune` 开启时其中已从配置移除的凭证被禁用（不删除，保留使用统计），
+    /// 关闭时保持不变。
+    pub fn sync_from_config(
+        conn: &Connection,
+        credentials: &[ProviderCredential],
+        previous_ids: &HashSet<String>,
+        prune: bool,
+    ) -> Result<ConfigSyncResult, rusqlite::Error> {
+        let mut result = ConfigSyncResult::default();
+
+        for cred in credentials {
+            if Self::get_by_uuid(conn, &cred.uuid)?.is_some() {
+                Self::update(conn, cred)?;
+                tracing::debug!(
+                    "[HOT_RELOAD] 更新凭证: {} ({})",
+                    cred.uuid,
+                    cred.provider_type
+                );
+            } else {
+                Self::insert(conn, cred)?;
+                tracing::debug!(
+                    "[HOT_RELOAD] 添加凭证: {} ({})",
+                    cred.uuid,
+                    cred.provider_type
+                );
+            }
+            result.synced += 1;
+        }
+
+        if !prune {
+            return Ok(result);
+        }
+
+        let current_ids: HashSet<&str> = credentials.iter().map(|c| c.uuid.as_str()).collect();
+        let mut removed: Vec<&String> = previous_ids
+            .iter()
+            .filter(|id| !current_ids.contains(id.as_str()))
+            .collect();
+        removed.sort();
+        for uuid in removed {
+            let affected = conn.execute(
+                "UPDATE provider_pool_credentials SET is_disabled = 1, updated_at = ?2
+                 WHERE uuid = ?1 AND is_disabled = 0",
+                params![uuid, Utc::now().timestamp()],
+            )?;
+            if affected > 0 {
+                tracing::info!("[HOT_RELOAD] 凭证已从配置移除，已禁用: {}", uuid);
+                result.disabled.push(uuid.clone());
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// 配置同步结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigSyncResult {
+    /// 同步（插入或更新）的凭证数
+    pub synced: usize,
+    /// 因已从配置移除而被禁用的凭证 UUID
+    pub disabled: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn setup() -> (Connection, String) {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        let cred = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        ProviderPoolDao::insert(&conn, &cred).unwrap();
+        (conn, cred.uuid)
+    }
+
+    fn load(conn: &Connection, uuid: &str) -> ProviderCredential {
+        ProviderPoolDao::get_by_uuid(conn, uuid).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_success_and_error_timestamps_advance() {
+        let (conn, uuid) = setup();
+        let t0 = Utc::now() - Duration::minutes(10);
+
+        ProviderPoolDao::record_success(&conn, &uuid, t0, Some("gpt-4o")).unwrap();
+        let cred = load(&conn, &uuid);
+        assert_eq!(cred.last_success_time.unwrap().timestamp(), t0.timestamp());
+        assert!(cred.last_error_time.is_none());
+
+        let t1 = t0 + Duration::minutes(1);
+        ProviderPoolDao::update_health_status(
+            &conn,
+            &uuid,
+            true,
+            1,
+            Some(t1),
+            Some("rate limited"),
+            None,
+            None,
+        )
+        .unwrap();
+        let cred = load(&conn, &uuid);
+        assert_eq!(cred.last_error_time.unwrap().timestamp(), t1.timestamp());
+        assert_eq!(cred.last_success_time.unwrap().timestamp(), t0.timestamp());
+
+        let t2 = t1 + Duration::minutes(1);
+        ProviderPoolDao::record_success(&conn, &uuid, t2, None).unwrap();
+        let cred = load(&conn, &uuid);
+        assert_eq!(cred.last_success_time.unwrap().timestamp(), t2.timestamp());
+        assert_eq!(cred.error_count, 0);
+        assert!(cred.is_healthy);
+    }
+
+    #[test]
+    fn test_error_message_retained_after_success() {
+        let (conn, uuid) = setup();
+        let error_time = Utc::now() - Duration::minutes(5);
+        ProviderPoolDao::update_health_status(
+            &conn,
+            &uuid,
+            false,
+            3,
+            Some(error_time),
+            Some("401 Unauthorized"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        ProviderPoolDao::record_success(&conn, &uuid, Utc::now(), None).unwrap();
+        let cred = load(&conn, &uuid);
+        assert!(cred.is_healthy);
+        assert_eq!(cred.last_error_message.as_deref(), Some("401 Unauthorized"));
+        assert_eq!(
+            cred.last_error_time.unwrap().timestamp(),
+            error_time.timestamp()
+        );
+    }
+    fn config_credential(uuid: &str) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: format!("sk-{uuid}"),
+                base_url: None,
+            },
+        );
+        cred.uuid = uuid.to_string();
+        cred
+    }
+
+    /// 首次同步两个配置凭证，再从配置中移除其中一个后重新同步
+    fn resync_after_removal(prune: bool) -> (Connection, ConfigSyncResult) {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        let initial = vec![config_credential("keep"), config_credential("revoked")];
+        ProviderPoolDao::sync_from_config(&conn, &initial, &HashSet::new(), prune).unwrap();
+        ProviderPoolDao::update_usage(&conn, "revoked", 7, Utc::now()).unwrap();
+
+        let previous: HashSet<String> = initial.iter().map(|c| c.uuid.clone()).collect();
+        let result = ProviderPoolDao::sync_from_config(
+            &conn,
+            &[config_credential("keep")],
+            &previous,
+            prune,
+        )
+        .unwrap();
+        (conn, result)
+    }
+
+    #[test]
+    fn test_prune_on_reload_disables_missing_credentials() {
+        let (conn, result) = resync_after_removal(true);
+        assert_eq!(result.synced, 1);
+        assert_eq!(result.disabled, vec!["revoked".to_string()]);
+
+        let revoked = load(&conn, "revoked");
+        assert!(revoked.is_disabled);
+        // 禁用而非删除，保留使用统计
+        assert_eq!(revoked.usage_count, 7);
+        assert!(!load(&conn, "keep").is_disabled);
+
+        // 不影响不由配置管理的凭证
+        let (conn, manual) = setup();
+        let result = ProviderPoolDao::sync_from_config(
+            &conn,
+            &[],
+            &HashSet::from(["keep".to_string()]),
+            true,
+        )
+        .unwrap();
+        assert!(result.disabled.is_empty());
+        assert!(!load(&conn, &manual).is_disabled);
+    }
+
+    #[test]
+    fn test_prune_off_keeps_missing_credentials_active() {
+        let (conn, result) = resync_after_removal(false);
+        assert_eq!(result.synced, 1);
+        assert!(result.disabled.is_empty());
+        assert!(!load(&conn, "revoked").is_disabled);
+    }
 }
@@ -1,4 +1,7 @@
+use crate::database::secret_store::stored_secret;
+use crate::models::provider_pool_model::CredentialData;
 use rusqlite::{params, Connection};
+use std::collections::HashSet;
 
 /// 从旧的 JSON 配置迁移数据到 SQLite
 #[allow(dead_code)]
@@ -45,6 +48,21 @@ pub fn migrate_from_json(conn: &Connection) -> Result<(), String> {
     Ok(())
 }
 
+/// 凭证池中已有的密钥（引用会解析为明文，用于迁移时去重）
+fn existing_credential_keys(conn: &Connection) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT credential_data FROM provider_pool_credentials")
+        .map_err(|e| format!("准备查询语句失败: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("查询凭证失败: {e}"))?;
+    Ok(rows
+        .flatten()
+        .filter_map(|json| serde_json::from_str::<CredentialData>(&json).ok())
+        .filter_map(|credential| stored_secret(conn, &credential))
+        .collect())
+}
+
 /// 将 api_keys 表中的数据迁移到 provider_pool_credentials 表
 ///
 /// 迁移逻辑：
@@ -101,6 +119,7 @@ pub fn migrate_api_keys_to_pool(conn: &Connection) -> Result<usize, String> {
         })
         .map_err(|e| format!("查询 API Keys 失败: {e}"))?;
 
+    let mut existing_keys = existing_credential_keys(conn)?;
     let mut migrated_count = 0;
     let now = chrono::Utc::now().timestamp();
 
@@ -108,16 +127,7 @@ pub fn migrate_api_keys_to_pool(conn: &Connection) -> Result<usize, String> {
         let row = row_result.map_err(|e| format!("读取行数据失败: {e}"))?;
 
         // 检查是否已存在相同的凭证（通过 api_key_encrypted 判断）
-        let exists: bool = conn
-            .query_row(
-                "SELECT COUNT(*) > 0 FROM provider_pool_credentials 
-                 WHERE credential_data LIKE ?1",
-                params![format!("%{}%", row.api_key_encrypted)],
-                |r| r.get(0),
-            )
-            .unwrap_or(false);
-
-        if exists {
+        if !existing_keys.insert(row.api_key_encrypted.clone()) {
             tracing::debug!(
                 "[迁移] 跳过已存在的 API Key: {} (provider: {})",
                 row.alias.as_deref().unwrap_or(&row.id),
@@ -560,7 +570,9 @@ pub fn mark_model_registry_refresh_needed(conn: &Connection) {
 }
 
 /// 检查模型注册表版本，如果版本不匹配则标记需要刷新
-pub fn check_model_registry_version(conn: &Connection) {
+///
+/// 返回本次是否检测到版本变化。
+pub fn check_model_registry_version(conn: &Connection) -> bool {
     let current_version: Option<String> = conn
         .query_row(
             "SELECT value FROM settings WHERE key = 'model_registry_version'",
@@ -582,7 +594,9 @@ pub fn check_model_registry_version(conn: &Connection) {
             "INSERT OR REPLACE INTO settings (key, value) VALUES ('model_registry_version', ?1)",
             params![MODEL_REGISTRY_VERSION],
         );
+        return true;
     }
+    false
 }
 
 /// 检查是否需要刷新模型注册表
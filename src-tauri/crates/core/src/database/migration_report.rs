@@ -0,0 +1,222 @@
+//! 数据迁移报告
+//!
+//! 按 `init_database` 中的顺序执行所有数据迁移，并汇总每一步是否执行及影响的行数。
+//! 预演模式（dry-run）在数据库的内存副本上执行，原数据库不会被修改，
+//! 可用于升级前查看将要发生的变化。
+
+use rusqlite::backup::Backup;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::{migration, migration_v2, migration_v3};
+
+/// 单个迁移步骤的结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationStep {
+    /// 迁移名称
+    pub name: String,
+    /// 迁移说明
+    pub description: String,
+    /// 是否执行（预演模式下表示将会执行）
+    pub applied: bool,
+    /// 影响的记录数
+    pub affected: usize,
+    /// 非致命错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 迁移报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MigrationReport {
+    /// 是否为预演
+    pub dry_run: bool,
+    /// 按执行顺序排列的迁移步骤
+    pub steps: Vec<MigrationStep>,
+}
+
+impl MigrationReport {
+    /// 已执行（或将会执行）的迁移
+    pub fn applied(&self) -> impl Iterator<Item = &MigrationStep> {
+        self.steps.iter().filter(|s| s.applied)
+    }
+
+    /// 是否没有任何迁移需要执行
+    pub fn is_up_to_date(&self) -> bool {
+        self.applied().next().is_none()
+    }
+
+    /// 记录一个步骤，`result` 为（是否执行, 影响的记录数）
+    fn push(&mut self, name: &str, description: &str, result: Result<(bool, usize), String>) {
+        let (applied, affected, error) = match result {
+            Ok((applied, affected)) => (applied, affected, None),
+            Err(e) => {
+                tracing::warn!("[数据库] 迁移 {} 失败（非致命）: {}", name, e);
+                (false, 0, Some(e))
+            }
+        };
+        self.steps.push(MigrationStep {
+            name: name.to_string(),
+            description: description.to_string(),
+            applied,
+            affected,
+            error,
+        });
+    }
+}
+
+/// 执行数据迁移并返回报告
+///
+/// `dry_run` 为 true 时在内存副本上执行，原数据库保持不变。
+/// 旧版 config.json 迁移失败是致命错误，直接返回 `Err`；其他迁移的错误记录在报告中。
+pub fn migrate(conn: &Connection, dry_run: bool) -> Result<MigrationReport, String> {
+    if !dry_run {
+        return run_migrations(conn, false);
+    }
+
+    let mut copy = Connection::open_in_memory().map_err(|e| e.to_string())?;
+    Backup::new(conn, &mut copy)
+        .and_then(|backup| backup.run_to_completion(256, std::time::Duration::ZERO, None))
+        .map_err(|e| format!("复制数据库失败: {e}"))?;
+    run_migrations(&copy, true)
+}
+
+fn run_migrations(conn: &Connection, dry_run: bool) -> Result<MigrationReport, String> {
+    let mut report = MigrationReport {
+        dry_run,
+        steps: Vec::new(),
+    };
+
+    // 旧配置迁移会读取并备份文件，预演时只检查是否已完成
+    let json_pending = !setting_is_true(conn, "migrated_from_json");
+    if !dry_run {
+        migration::migrate_from_json(conn)?;
+    }
+    report.push(
+        "migrate_from_json",
+        "从旧版 config.json 迁移",
+        Ok((json_pending, 0)),
+    );
+
+    let provider_ids = migration::migrate_provider_ids(conn);
+    if matches!(provider_ids, Ok(count) if count > 0) {
+        // 标记需要刷新模型注册表
+        migration::mark_model_registry_refresh_needed(conn);
+    }
+    report.push(
+        "migrate_provider_ids",
+        "修复与模型注册表不匹配的 Provider ID",
+        provider_ids.map(counted),
+    );
+
+    // 检查是否需要刷新模型注册表（版本升级时）
+    let registry_outdated = migration::check_model_registry_version(conn);
+    report.push(
+        "check_model_registry_version",
+        "版本升级后刷新模型注册表",
+        Ok((registry_outdated, 0)),
+    );
+
+    report.push(
+        "migrate_api_keys_to_pool",
+        "将 API Key 迁移到凭证池",
+        migration::migrate_api_keys_to_pool(conn).map(counted),
+    );
+    report.push(
+        "cleanup_legacy_api_key_credentials",
+        "清理旧 API Key 凭证（openai_key, claude_key 类型）",
+        migration::cleanup_legacy_api_key_credentials(conn).map(counted),
+    );
+    report.push(
+        "migrate_mcp_proxycast_enabled",
+        "补齐 MCP 导入数据的 ProxyCast 启用状态",
+        migration::migrate_mcp_proxycast_enabled(conn).map(counted),
+    );
+
+    // 统一内容系统迁移（创建默认项目，迁移话题）
+    let unified = migration_v2::migrate_unified_content_system(conn).map(|result| {
+        let migrated = result.stats.map_or(0, |s| s.migrated_contents_count);
+        (result.executed, migrated)
+    });
+    report.push(
+        "migrate_unified_content_system",
+        "创建默认项目并迁移未归属的内容",
+        unified,
+    );
+
+    let playwright = migration_v3::migrate_playwright_mcp_server(conn)
+        .map(|result| (result.executed, usize::from(result.executed)));
+    report.push(
+        "migrate_playwright_mcp_server",
+        "添加默认 Playwright MCP Server",
+        playwright,
+    );
+
+    Ok(report)
+}
+
+/// 以影响行数判断是否执行
+fn counted(affected: usize) -> (bool, usize) {
+    (affected > 0, affected)
+}
+
+fn setting_is_true(conn: &Connection, key: &str) -> bool {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+        row.get::<_, String>(0)
+    })
+    .map(|v| v == "true")
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::schema;
+
+    fn settings_snapshot(conn: &Connection) -> Vec<(String, String)> {
+        let mut stmt = conn
+            .prepare("SELECT key, value FROM settings ORDER BY key")
+            .unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_dry_run_reports_pending_without_changes() {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        // 已完成旧配置迁移，避免读取主目录下的文件
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES ('migrated_from_json', 'true')",
+            [],
+        )
+        .unwrap();
+        let before = settings_snapshot(&conn);
+
+        let report = migrate(&conn, true).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(settings_snapshot(&conn), before);
+
+        let names: Vec<&str> = report.steps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names.first(), Some(&"migrate_from_json"));
+        assert_eq!(names.len(), 8);
+        let pending: Vec<&str> = report.applied().map(|s| s.name.as_str()).collect();
+        assert!(pending.contains(&"migrate_unified_content_system"));
+        assert!(!pending.contains(&"migrate_from_json"));
+
+        // 实际执行后与预演一致，再次执行时已无待执行迁移
+        let applied = migrate(&conn, false).unwrap();
+        assert_eq!(
+            applied
+                .applied()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            pending
+        );
+        assert_ne!(settings_snapshot(&conn), before);
+        let again = migrate(&conn, true).unwrap();
+        assert!(again.is_up_to_date(), "{again:?}");
+    }
+}
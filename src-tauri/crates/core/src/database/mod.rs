@@ -1,13 +1,17 @@
 pub mod dao;
 pub mod migration;
+pub mod migration_report;
 pub mod migration_v2;
 pub mod migration_v3;
 pub mod schema;
+pub mod secret_store;
 pub mod system_providers;
 
 use rusqlite::Connection;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::config::expand_tilde;
 
 pub type DbConnection = Arc<Mutex<Connection>>;
 
@@ -23,18 +27,64 @@ pub fn lock_db(db: &DbConnection) -> Result<std::sync::MutexGuard<'_, Connection
     }
 }
 
+/// 指定数据库文件路径的环境变量，优先级高于配置中的 `database_path`
+pub const DB_PATH_ENV: &str = "PROXYCAST_DB_PATH";
+
+/// 配置中指定的数据库路径（启动时由配置设置）
+static DB_PATH_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// 设置数据库路径覆盖（来自配置 `database_path`），`None` 表示使用默认路径
+pub fn set_db_path_override(path: Option<PathBuf>) {
+    if let Ok(mut guard) = DB_PATH_OVERRIDE.write() {
+        *guard = path;
+    }
+}
+
+/// 按优先级确定数据库路径：环境变量 > 配置覆盖 > `~/.proxycast/proxycast.db`
+fn resolve_db_path(
+    env_value: Option<String>,
+    config_override: Option<PathBuf>,
+) -> Result<PathBuf, String> {
+    if let Some(path) = env_value.filter(|v| !v.trim().is_empty()) {
+        return Ok(expand_tilde(path.trim()));
+    }
+    if let Some(path) = config_override {
+        return Ok(expand_tilde(path));
+    }
+    let home = dirs::home_dir().ok_or_else(|| "无法获取主目录".to_string())?;
+    Ok(home.join(".proxycast").join("proxycast.db"))
+}
+
 /// 获取数据库文件路径
+///
+/// 可通过环境变量 `PROXYCAST_DB_PATH` 或配置 `database_path` 指定，
+/// 便于 CI 和多个实例并行运行时互相隔离。会自动创建所在目录。
 pub fn get_db_path() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or_else(|| "无法获取主目录".to_string())?;
-    let db_dir = home.join(".proxycast");
-    std::fs::create_dir_all(&db_dir).map_err(|e| format!("无法创建数据库目录 {db_dir:?}: {e}"))?;
-    Ok(db_dir.join("proxycast.db"))
+    let config_override = DB_PATH_OVERRIDE.read().ok().and_then(|p| p.clone());
+    let db_path = resolve_db_path(std::env::var(DB_PATH_ENV).ok(), config_override)?;
+    ensure_db_dir(&db_path)?;
+    Ok(db_path)
+}
+
+/// 创建数据库文件所在目录
+fn ensure_db_dir(db_path: &Path) -> Result<(), String> {
+    match db_path.parent() {
+        Some(db_dir) if !db_dir.as_os_str().is_empty() => std::fs::create_dir_all(db_dir)
+            .map_err(|e| format!("无法创建数据库目录 {db_dir:?}: {e}")),
+        _ => Ok(()),
+    }
 }
 
 /// 初始化数据库连接
 pub fn init_database() -> Result<DbConnection, String> {
-    let db_path = get_db_path()?;
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    init_database_at(&get_db_path()?)
+}
+
+/// 在指定路径初始化数据库连接（建表并执行迁移）
+pub fn init_database_at(db_path: &Path) -> Result<DbConnection, String> {
+    ensure_db_dir(db_path)?;
+    tracing::info!("[数据库] 使用数据库文件: {}", db_path.display());
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     // 设置 busy_timeout 为 5 秒，避免 "database is locked" 错误
     conn.busy_timeout(std::time::Duration::from_secs(5))
@@ -42,96 +92,67 @@ pub fn init_database() -> Result<DbConnection, String> {
 
     // 创建表结构
     schema::create_tables(&conn).map_err(|e| e.to_string())?;
-    migration::migrate_from_json(&conn)?;
-
-    // 执行 Provider ID 迁移（修复旧 ID 与模型注册表不匹配的问题）
-    match migration::migrate_provider_ids(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已迁移 {} 个 Provider ID", count);
-                // 标记需要刷新模型注册表
-                migration::mark_model_registry_refresh_needed(&conn);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] Provider ID 迁移失败（非致命）: {}", e);
-        }
+
+    // 执行数据迁移（旧配置、Provider ID、API Key 入池、统一内容系统等）
+    let report = migration_report::migrate(&conn, false)?;
+    for step in report.applied() {
+        tracing::info!(
+            "[数据库] 已执行迁移 {}: {}（影响 {} 条记录）",
+            step.name,
+            step.description,
+            step.affected
+        );
     }
 
-    // 检查是否需要刷新模型注册表（版本升级时）
-    migration::check_model_registry_version(&conn);
+    Ok(Arc::new(Mutex::new(conn)))
+}
 
-    // 执行 API Keys 到 Provider Pool 的迁移
-    match migration::migrate_api_keys_to_pool(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已将 {} 条 API Key 迁移到凭证池", count);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] API Key 迁移失败（非致命）: {}", e);
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // 清理旧的 API Key 凭证（openai_key, claude_key 类型）
-    match migration::cleanup_legacy_api_key_credentials(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已清理 {} 条旧 API Key 凭证", count);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] 旧 API Key 凭证清理失败（非致命）: {}", e);
-        }
-    }
+    #[test]
+    fn test_resolve_db_path_priority() {
+        let env = resolve_db_path(
+            Some("/tmp/env/a.db".to_string()),
+            Some(PathBuf::from("/tmp/config/b.db")),
+        )
+        .unwrap();
+        assert_eq!(env, PathBuf::from("/tmp/env/a.db"));
 
-    // 修复历史 MCP 导入数据（补齐 enabled_proxycast）
-    match migration::migrate_mcp_proxycast_enabled(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已修复 {} 条 MCP ProxyCast 启用状态", count);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] MCP ProxyCast 启用状态修复失败（非致命）: {}", e);
-        }
-    }
+        let config = resolve_db_path(Some("  ".to_string()), Some(PathBuf::from("/tmp/b.db")));
+        assert_eq!(config.unwrap(), PathBuf::from("/tmp/b.db"));
 
-    // 执行统一内容系统迁移（创建默认项目，迁移话题）
-    // _Requirements: 2.1, 2.2, 2.3, 2.4_
-    match migration_v2::migrate_unified_content_system(&conn) {
-        Ok(result) => {
-            if result.executed {
-                if let Some(stats) = result.stats {
-                    tracing::info!(
-                        "[数据库] 统一内容系统迁移完成: 默认项目={}, 迁移内容数={}",
-                        stats.default_project_id,
-                        stats.migrated_contents_count
-                    );
-                }
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] 统一内容系统迁移失败（非致命）: {}", e);
+        if let Ok(default) = resolve_db_path(None, None) {
+            assert!(default.ends_with(".proxycast/proxycast.db"));
         }
     }
 
-    // 执行 Playwright MCP Server 迁移
-    match migration_v3::migrate_playwright_mcp_server(&conn) {
-        Ok(result) => {
-            if result.executed {
-                if let Some(server_id) = result.server_id {
-                    tracing::info!(
-                        "[数据库] Playwright MCP Server 迁移完成: server_id={}",
-                        server_id
-                    );
-                }
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] Playwright MCP Server 迁移失败（非致命）: {}", e);
-        }
-    }
+    #[test]
+    fn test_init_database_at_custom_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("instance-a").join("nested").join("test.db");
 
-    Ok(Arc::new(Mutex::new(conn)))
+        let db = init_database_at(&db_path).unwrap();
+        assert!(db_path.exists());
+
+        // 迁移在该数据库上执行
+        let conn = lock_db(&db).unwrap();
+        let tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'settings'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 1);
+        let migrated: String = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'migrated_from_json'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(migrated, "true");
+    }
 }
@@ -237,6 +237,44 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     );
 
+    // Migration: 添加每日 Token 上限字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN daily_token_limit INTEGER",
+        [],
+    );
+
+    // Migration: 添加最后成功时间字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN last_success_time INTEGER",
+        [],
+    );
+
+    // Migration: 添加偏好模型字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN preferred_models TEXT",
+        [],
+    );
+
+    // Migration: 添加轮换周期字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN rotate_after_days INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN rotated_at INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN needs_rotation INTEGER DEFAULT 0",
+        [],
+    );
+
+    // Migration: 添加最大并发数字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN max_concurrency INTEGER",
+        [],
+    );
+
     // 已安装插件表
     // _需求: 1.2, 1.3_
     conn.execute(
@@ -979,6 +1017,21 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // ============================================================================
+    // 管理 API 审计日志表（只追加）
+    // ============================================================================
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS management_audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            action TEXT NOT NULL,
+            target TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            source_ip TEXT,
+            diff TEXT NOT NULL DEFAULT '{}'
+        )",
+        [],
+    )?;
+
     Ok(())
 }
 
@@ -0,0 +1,663 @@
+//! 凭证密钥存储
+//!
+//! 凭证的密钥字段（API Key）通过可插拔的 [`SecretStore`] 保存，
+//! 凭证行中只保留形如 `secret-ref:<backend>:<key>` 的引用，其他元数据仍在 SQLite 中。
+//!
+//! - [`SqliteSecretStore`]：保存在数据库的 `credential_secrets` 表（默认）
+//! - `KeychainSecretStore`：保存在系统钥匙串（需启用 `keychain` 特性）
+//! - [`MemorySecretStore`]：内存实现，用于测试
+//!
+//! 默认（`inline` 后端）不使用密钥存储，密钥内联在凭证行中，与旧版本兼容。
+//! 启用其他后端后，启动时通过 [`init_secret_store`] 安装全局存储，并把明文密钥或其他后端的密钥
+//! 迁移到当前后端；切回 `inline` 时把密钥写回凭证行。
+//! 凭证行中的引用无法解析时，读取该凭证直接报错，引用占位符不会被当作密钥发往上游。
+
+use crate::config::{SecretBackend, SecretStorageConfig};
+use crate::models::provider_pool_model::CredentialData;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use thiserror::Error;
+
+/// 密钥引用前缀
+pub const SECRET_REF_PREFIX: &str = "secret-ref:";
+
+/// 钥匙串中使用的服务名
+#[cfg(feature = "keychain")]
+const KEYCHAIN_SERVICE: &str = "proxycast";
+
+static ACTIVE_STORE: RwLock<Option<Arc<dyn SecretStore>>> = RwLock::new(None);
+
+/// 密钥存储错误
+#[derive(Debug, Error)]
+pub enum SecretStoreError {
+    /// 后端读写失败
+    #[error("密钥存储后端错误: {0}")]
+    Backend(String),
+
+    /// 后端不可用
+    #[error("密钥存储后端不可用: {0}")]
+    Unavailable(String),
+}
+
+impl From<rusqlite::Error> for SecretStoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        SecretStoreError::Backend(e.to_string())
+    }
+}
+
+/// 密钥存储后端
+pub trait SecretStore: Send + Sync {
+    /// 后端名称（写入密钥引用，用于迁移）
+    fn name(&self) -> &'static str;
+
+    /// 读取密钥
+    fn get(&self, key: &str) -> Result<Option<String>, SecretStoreError>;
+
+    /// 写入密钥（已存在时覆盖）
+    fn put(&self, key: &str, value: &str) -> Result<(), SecretStoreError>;
+
+    /// 删除密钥（不存在时视为成功）
+    fn delete(&self, key: &str) -> Result<(), SecretStoreError>;
+}
+
+// ============ 后端实现 ============
+
+/// SQLite 密钥存储
+///
+/// 使用独立连接访问 `credential_secrets` 表，避免与持有主连接锁的 DAO 调用互相阻塞。
+pub struct SqliteSecretStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteSecretStore {
+    /// 打开数据库文件中的密钥表
+    pub fn open(path: &Path) -> Result<Self, SecretStoreError> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        Self::with_connection(conn)
+    }
+
+    /// 使用内存数据库（用于测试）
+    pub fn in_memory() -> Result<Self, SecretStoreError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, SecretStoreError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS credential_secrets (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SecretStore for SqliteSecretStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, SecretStoreError> {
+        Ok(self
+            .lock()
+            .query_row(
+                "SELECT value FROM credential_secrets WHERE key = ?1",
+                [key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn put(&self, key: &str, value: &str) -> Result<(), SecretStoreError> {
+        self.lock().execute(
+            "INSERT OR REPLACE INTO credential_secrets (key, value, updated_at)
+             VALUES (?1, ?2, ?3)",
+            params![key, value, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), SecretStoreError> {
+        self.lock()
+            .execute("DELETE FROM credential_secrets WHERE key = ?1", [key])?;
+        Ok(())
+    }
+}
+
+/// 系统钥匙串密钥存储（macOS Keychain / Windows 凭据管理器）
+#[cfg(feature = "keychain")]
+#[derive(Debug, Default)]
+pub struct KeychainSecretStore;
+
+#[cfg(feature = "keychain")]
+impl KeychainSecretStore {
+    fn entry(key: &str) -> Result<keyring::Entry, SecretStoreError> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, key)
+            .map_err(|e| SecretStoreError::Unavailable(e.to_string()))
+    }
+}
+
+#[cfg(feature = "keychain")]
+impl SecretStore for KeychainSecretStore {
+    fn name(&self) -> &'static str {
+        "keychain"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, SecretStoreError> {
+        match Self::entry(key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(SecretStoreError::Backend(e.to_string())),
+        }
+    }
+
+    fn put(&self, key: &str, value: &str) -> Result<(), SecretStoreError> {
+        Self::entry(key)?
+            .set_password(value)
+            .map_err(|e| SecretStoreError::Backend(e.to_string()))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), SecretStoreError> {
+        match Self::entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(SecretStoreError::Backend(e.to_string())),
+        }
+    }
+}
+
+/// 内存密钥存储
+#[derive(Debug, Default)]
+pub struct MemorySecretStore {
+    secrets: Mutex<HashMap<String, String>>,
+}
+
+impl MemorySecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.secrets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SecretStore for MemorySecretStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, SecretStoreError> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    fn put(&self, key: &str, value: &str) -> Result<(), SecretStoreError> {
+        self.lock().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), SecretStoreError> {
+        self.lock().remove(key);
+        Ok(())
+    }
+}
+
+// ============ 全局存储 ============
+
+/// 安装全局密钥存储
+pub fn install_secret_store(store: Arc<dyn SecretStore>) {
+    *ACTIVE_STORE.write().unwrap_or_else(|e| e.into_inner()) = Some(store);
+}
+
+/// 获取全局密钥存储（未安装时返回 `None`，密钥内联保存）
+pub fn active_secret_store() -> Option<Arc<dyn SecretStore>> {
+    ACTIVE_STORE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// 按配置创建密钥存储（`inline` 后端不使用存储，返回 `None`）
+pub fn build_secret_store(
+    config: &SecretStorageConfig,
+    db_path: &Path,
+) -> Result<Option<Arc<dyn SecretStore>>, SecretStoreError> {
+    match config.backend {
+        SecretBackend::Inline => Ok(None),
+        SecretBackend::Sqlite => Ok(Some(Arc::new(SqliteSecretStore::open(db_path)?))),
+        #[cfg(feature = "keychain")]
+        SecretBackend::Keychain => Ok(Some(Arc::new(KeychainSecretStore))),
+        #[cfg(not(feature = "keychain"))]
+        SecretBackend::Keychain => {
+            tracing::warn!("[SECRET_STORE] 当前构建未启用 keychain 特性，回退到 SQLite 存储");
+            Ok(Some(Arc::new(SqliteSecretStore::open(db_path)?)))
+        }
+    }
+}
+
+/// 按名称创建其他后端（用于从旧后端迁移密钥）
+fn open_backend_by_name(name: &str, db_path: &Path) -> Option<Arc<dyn SecretStore>> {
+    match name {
+        "sqlite" => SqliteSecretStore::open(db_path)
+            .ok()
+            .map(|s| Arc::new(s) as Arc<dyn SecretStore>),
+        #[cfg(feature = "keychain")]
+        "keychain" => Some(Arc::new(KeychainSecretStore)),
+        _ => None,
+    }
+}
+
+/// 初始化全局密钥存储并迁移已有凭证
+///
+/// `inline` 后端不安装存储，并把其他后端中的密钥写回凭证行。
+pub fn init_secret_store(
+    conn: &Connection,
+    config: &SecretStorageConfig,
+    db_path: &Path,
+) -> Result<(), String> {
+    let store = build_secret_store(config, db_path).map_err(|e| e.to_string())?;
+    let open_previous = |name: &str| open_backend_by_name(name, db_path);
+    let migrated = relocate_credential_secrets(conn, store.as_deref(), open_previous)?;
+    if migrated > 0 {
+        tracing::info!(
+            "[SECRET_STORE] 已将 {} 个凭证的密钥迁移到 {} 存储",
+            migrated,
+            store.as_ref().map_or("inline", |s| s.name())
+        );
+    }
+    if let Some(store) = store {
+        install_secret_store(store);
+    }
+    Ok(())
+}
+
+// ============ 凭证密钥处理 ============
+
+/// 凭证密钥在存储中的键
+pub fn credential_secret_key(uuid: &str) -> String {
+    format!("provider_pool/{uuid}/api_key")
+}
+
+fn make_ref(backend: &str, key: &str) -> String {
+    format!("{SECRET_REF_PREFIX}{backend}:{key}")
+}
+
+/// 解析密钥引用，返回 (后端名称, 键)
+fn parse_ref(value: &str) -> Option<(&str, &str)> {
+    value.strip_prefix(SECRET_REF_PREFIX)?.split_once(':')
+}
+
+/// 凭证中的密钥字段
+fn secret_field_mut(credential: &mut CredentialData) -> Option<&mut String> {
+    match credential {
+        CredentialData::OpenAIKey { api_key, .. }
+        | CredentialData::ClaudeKey { api_key, .. }
+        | CredentialData::VertexKey { api_key, .. }
+        | CredentialData::GeminiApiKey { api_key, .. }
+        | CredentialData::AnthropicKey { api_key, .. } => Some(api_key),
+        CredentialData::LocalOpenAI {
+            api_key: Some(api_key),
+            ..
+        } => Some(api_key),
+        _ => None,
+    }
+}
+
+/// 将凭证密钥写入存储，返回只含引用的凭证副本
+pub fn externalize_secret(
+    store: &dyn SecretStore,
+    uuid: &str,
+    credential: &CredentialData,
+) -> Result<CredentialData, SecretStoreError> {
+    let mut credential = credential.clone();
+    if let Some(secret) = secret_field_mut(&mut credential) {
+        if !secret.starts_with(SECRET_REF_PREFIX) {
+            let key = credential_secret_key(uuid);
+            store.put(&key, secret)?;
+            *secret = make_ref(store.name(), &key);
+        }
+    }
+    Ok(credential)
+}
+
+/// 从存储中取回凭证密钥，替换引用
+pub fn hydrate_secret(
+    store: &dyn SecretStore,
+    credential: &mut CredentialData,
+) -> Result<(), SecretStoreError> {
+    if let Some(secret) = secret_field_mut(credential) {
+        if let Some((backend, key)) = parse_ref(secret) {
+            if backend != store.name() {
+                return Err(SecretStoreError::Unavailable(format!(
+                    "密钥保存在 {backend} 存储中，当前为 {}",
+                    store.name()
+                )));
+            }
+            let value = store
+                .get(key)?
+                .ok_or_else(|| SecretStoreError::Backend(format!("密钥不存在: {key}")))?;
+            *secret = value;
+        }
+    }
+    Ok(())
+}
+
+/// 使用全局存储取回凭证密钥
+///
+/// 未安装全局存储但凭证行中是引用时同样返回错误，避免把引用当作密钥使用。
+pub fn hydrate_credential(credential: &mut CredentialData) -> Result<(), SecretStoreError> {
+    match active_secret_store() {
+        Some(store) => hydrate_secret(store.as_ref(), credential),
+        None => match secret_field_mut(credential).and_then(|secret| parse_ref(secret)) {
+            Some((backend, _)) => Err(SecretStoreError::Unavailable(format!(
+                "密钥保存在 {backend} 存储中，当前未启用密钥存储"
+            ))),
+            None => Ok(()),
+        },
+    }
+}
+
+/// 读取凭证行中的明文密钥用于比较，引用会通过全局存储或同库的 SQLite 密钥表解析
+pub fn stored_secret(conn: &Connection, credential: &CredentialData) -> Option<String> {
+    let mut credential = credential.clone();
+    let secret = secret_field_mut(&mut credential)?;
+    let Some((backend, key)) = parse_ref(secret) else {
+        return Some(secret.clone());
+    };
+    if let Some(store) = active_secret_store().filter(|s| s.name() == backend) {
+        return store.get(key).ok().flatten();
+    }
+    if backend != "sqlite" {
+        return None;
+    }
+    conn.query_row(
+        "SELECT value FROM credential_secrets WHERE key = ?1",
+        [key],
+        |row| row.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+/// 删除凭证密钥
+pub fn delete_secret(store: &dyn SecretStore, uuid: &str) -> Result<(), SecretStoreError> {
+    store.delete(&credential_secret_key(uuid))
+}
+
+/// 将凭证密钥迁移到指定存储
+///
+/// - 明文密钥写入 `store` 并替换为引用
+/// - 引用其他后端的密钥通过 `open_previous` 打开旧后端读取后写入 `store`
+///
+/// 返回迁移的凭证数。
+pub fn migrate_credential_secrets(
+    conn: &Connection,
+    store: &dyn SecretStore,
+    open_previous: impl Fn(&str) -> Option<Arc<dyn SecretStore>>,
+) -> Result<usize, String> {
+    relocate_credential_secrets(conn, Some(store), open_previous)
+}
+
+/// 将引用其他后端的凭证密钥写回凭证行（切回 `inline` 后端），返回迁移的凭证数
+pub fn inline_credential_secrets(
+    conn: &Connection,
+    open_previous: impl Fn(&str) -> Option<Arc<dyn SecretStore>>,
+) -> Result<usize, String> {
+    relocate_credential_secrets(conn, None, open_previous)
+}
+
+/// 将凭证密钥迁移到 `store`，`None` 表示内联保存在凭证行中
+fn relocate_credential_secrets(
+    conn: &Connection,
+    store: Option<&dyn SecretStore>,
+    open_previous: impl Fn(&str) -> Option<Arc<dyn SecretStore>>,
+) -> Result<usize, String> {
+    let rows: Vec<(String, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT uuid, credential_data FROM provider_pool_credentials")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut migrated = 0;
+    for (uuid, json) in rows {
+        let Ok(mut credential) = serde_json::from_str::<CredentialData>(&json) else {
+            continue;
+        };
+        let Some(secret) = secret_field_mut(&mut credential) else {
+            continue;
+        };
+
+        let mut previous_entry = None;
+        match parse_ref(secret) {
+            Some((backend, _)) if store.is_some_and(|s| s.name() == backend) => continue,
+            Some((backend, key)) => {
+                let Some(previous) = open_previous(backend) else {
+                    tracing::warn!(
+                        "[SECRET_STORE] 凭证 {} 的密钥位于不可用的 {} 存储，跳过迁移",
+                        uuid,
+                        backend
+                    );
+                    continue;
+                };
+                let key = key.to_string();
+                let Some(value) = previous.get(&key).map_err(|e| e.to_string())? else {
+                    tracing::warn!("[SECRET_STORE] 凭证 {} 的密钥在 {} 中不存在", uuid, backend);
+                    continue;
+                };
+                *secret = value;
+                previous_entry = Some((previous, key));
+            }
+            // 明文密钥已经是内联保存
+            None if store.is_none() => continue,
+            None => {}
+        }
+
+        let relocated = match store {
+            Some(store) => {
+                externalize_secret(store, &uuid, &credential).map_err(|e| e.to_string())?
+            }
+            None => credential,
+        };
+        let json = serde_json::to_string(&relocated).map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE provider_pool_credentials SET credential_data = ?1 WHERE uuid = ?2",
+            params![json, uuid],
+        )
+        .map_err(|e| e.to_string())?;
+
+        // 新位置写入成功后再清理旧后端
+        if let Some((previous, key)) = previous_entry {
+            if let Err(e) = previous.delete(&key) {
+                tracing::warn!("[SECRET_STORE] 清理旧存储中的密钥失败: {}", e);
+            }
+        }
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::dao::provider_pool::ProviderPoolDao;
+    use crate::database::schema::create_tables;
+    use crate::models::provider_pool_model::{PoolProviderType, ProviderCredential};
+
+    fn api_key_credential(key: &str) -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: key.to_string(),
+                base_url: None,
+            },
+        )
+    }
+
+    fn api_key_of(credential: &CredentialData) -> &str {
+        match credential {
+            CredentialData::OpenAIKey { api_key, .. } => api_key,
+            _ => panic!("unexpected credential type"),
+        }
+    }
+
+    fn stored_json(conn: &Connection, uuid: &str) -> String {
+        conn.query_row(
+            "SELECT credential_data FROM provider_pool_credentials WHERE uuid = ?1",
+            [uuid],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let stores: Vec<Box<dyn SecretStore>> = vec![
+            Box::new(MemorySecretStore::new()),
+            Box::new(SqliteSecretStore::in_memory().unwrap()),
+        ];
+        for store in stores {
+            assert_eq!(store.get("k").unwrap(), None);
+            store.put("k", "v1").unwrap();
+            store.put("k", "v2").unwrap();
+            assert_eq!(store.get("k").unwrap().as_deref(), Some("v2"));
+            store.delete("k").unwrap();
+            store.delete("k").unwrap();
+            assert_eq!(store.get("k").unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_externalize_and_hydrate() {
+        let store = MemorySecretStore::new();
+        let cred = api_key_credential("sk-secret");
+
+        let externalized = externalize_secret(&store, &cred.uuid, &cred.credential).unwrap();
+        let reference = api_key_of(&externalized);
+        assert!(reference.starts_with("secret-ref:memory:"));
+        assert!(!serde_json::to_string(&externalized)
+            .unwrap()
+            .contains("sk-secret"));
+
+        let mut hydrated = externalized.clone();
+        hydrate_secret(&store, &mut hydrated).unwrap();
+        assert_eq!(api_key_of(&hydrated), "sk-secret");
+
+        // 非密钥类凭证保持不变
+        let oauth = CredentialData::KiroOAuth {
+            creds_file_path: "/tmp/kiro.json".to_string(),
+        };
+        let unchanged = externalize_secret(&store, "x", &oauth).unwrap();
+        assert_eq!(
+            serde_json::to_string(&unchanged).unwrap(),
+            serde_json::to_string(&oauth).unwrap()
+        );
+
+        delete_secret(&store, &cred.uuid).unwrap();
+        assert!(hydrate_secret(&store, &mut externalized.clone()).is_err());
+    }
+
+    #[test]
+    fn test_migrate_inline_and_between_backends() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let cred = api_key_credential("sk-inline");
+        ProviderPoolDao::insert(&conn, &cred).unwrap();
+
+        // 明文密钥迁移到 SQLite 存储
+        let sqlite: Arc<dyn SecretStore> = Arc::new(SqliteSecretStore::in_memory().unwrap());
+        let migrated = migrate_credential_secrets(&conn, sqlite.as_ref(), |_| None).unwrap();
+        assert_eq!(migrated, 1);
+        assert!(!stored_json(&conn, &cred.uuid).contains("sk-inline"));
+        assert_eq!(
+            migrate_credential_secrets(&conn, sqlite.as_ref(), |_| None).unwrap(),
+            0
+        );
+
+        // 切换到另一个后端
+        let memory = MemorySecretStore::new();
+        let previous = sqlite.clone();
+        let migrated =
+            migrate_credential_secrets(&conn, &memory, |_| Some(previous.clone())).unwrap();
+        assert_eq!(migrated, 1);
+        assert_eq!(
+            sqlite.get(&credential_secret_key(&cred.uuid)).unwrap(),
+            None
+        );
+
+        let mut loaded: CredentialData =
+            serde_json::from_str(&stored_json(&conn, &cred.uuid)).unwrap();
+        assert!(api_key_of(&loaded).starts_with("secret-ref:memory:"));
+        hydrate_secret(&memory, &mut loaded).unwrap();
+        assert_eq!(api_key_of(&loaded), "sk-inline");
+    }
+
+    #[test]
+    fn test_switching_back_to_inline_restores_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("proxycast.db");
+        let conn = Connection::open(&db_path).unwrap();
+        create_tables(&conn).unwrap();
+        let cred = api_key_credential("sk-restore");
+        ProviderPoolDao::insert(&conn, &cred).unwrap();
+
+        let sqlite: Arc<dyn SecretStore> = Arc::new(SqliteSecretStore::open(&db_path).unwrap());
+        migrate_credential_secrets(&conn, sqlite.as_ref(), |_| None).unwrap();
+        let stored: CredentialData = serde_json::from_str(&stored_json(&conn, &cred.uuid)).unwrap();
+        assert!(api_key_of(&stored).starts_with("secret-ref:sqlite:"));
+        // 同库的 SQLite 引用可以解析为明文用于比较
+        assert_eq!(stored_secret(&conn, &stored).as_deref(), Some("sk-restore"));
+
+        let previous = sqlite.clone();
+        let restored = inline_credential_secrets(&conn, |_| Some(previous.clone())).unwrap();
+        assert_eq!(restored, 1);
+        let stored: CredentialData = serde_json::from_str(&stored_json(&conn, &cred.uuid)).unwrap();
+        assert_eq!(api_key_of(&stored), "sk-restore");
+        assert_eq!(
+            sqlite.get(&credential_secret_key(&cred.uuid)).unwrap(),
+            None
+        );
+        assert_eq!(
+            inline_credential_secrets(&conn, |_| Some(sqlite.clone())).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_unresolved_reference_fails_closed() {
+        let store = MemorySecretStore::new();
+        let cred = api_key_credential("sk-secret");
+        let mut externalized = externalize_secret(&store, &cred.uuid, &cred.credential).unwrap();
+
+        // 未启用密钥存储时引用不能当作密钥使用
+        assert!(hydrate_credential(&mut externalized).is_err());
+        let mut inline = cred.credential.clone();
+        hydrate_credential(&mut inline).unwrap();
+        assert_eq!(api_key_of(&inline), "sk-secret");
+
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO provider_pool_credentials (uuid, provider_type, credential_data, created_at, updated_at)
+             VALUES (?1, 'openai', ?2, 0, 0)",
+            params![cred.uuid, serde_json::to_string(&externalized).unwrap()],
+        )
+        .unwrap();
+        assert!(ProviderPoolDao::get_by_uuid(&conn, &cred.uuid).is_err());
+        assert!(ProviderPoolDao::get_all(&conn).unwrap().is_empty());
+    }
+}
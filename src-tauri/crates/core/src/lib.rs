@@ -4,9 +4,12 @@
 //!
 //! ## 模块结构
 //! - `app_utils`: 应用通用工具函数
+//! - `build_info`: 构建信息（版本、git commit、features）
 //! - `models`: 核心数据模型定义
 //! - `data`: 静态数据
+//! - `diagnostics`: 诊断包导出
 //! - `logger`: 日志配置
+//! - `log_filter`: 运行时日志级别调整
 //! - `errors`: 错误类型定义
 //! - `backends`: 后端调用层 Trait
 //! - `config`: 配置管理（类型、YAML、热重载、导入导出）
@@ -14,12 +17,16 @@
 //! - `middleware`: HTTP 中间件（认证、限速）
 //! - `orchestrator`: 模型选择编排器
 //! - `plugin`: 插件系统（加载、管理、UI、安装）
+//! - `response_postprocess`: 响应后处理器链
 //! - `session`: 会话管理（限速、粘性路由）
 //! - `session_files`: 会话文件存储
 
 pub mod app_bootstrap;
 pub mod app_utils;
+pub mod build_info;
 pub mod data;
+pub mod diagnostics;
+pub mod log_filter;
 pub mod logger;
 pub mod models;
 pub mod tray_format;
@@ -34,6 +41,7 @@ pub mod errors;
 pub mod middleware;
 pub mod orchestrator;
 pub mod plugin;
+pub mod response_postprocess;
 pub mod session;
 pub mod session_files;
 
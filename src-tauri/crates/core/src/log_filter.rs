@@ -0,0 +1,222 @@
+//! 运行时日志级别调整
+//!
+//! 启动时安装一个可重载的按 target 过滤器，之后可以通过管理 API 或 Tauri 命令
+//! 调整某个模块的日志级别（如 `proxycast_server=debug`），无需重启、不会丢失状态。
+//! 调整只在内存中生效，不会持久化。
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Registry;
+
+/// 默认日志级别
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+
+/// 日志过滤规则环境变量（`target=level` 列表，如 `proxycast_server=debug,info`）
+const RUST_LOG_ENV: &str = "RUST_LOG";
+
+static GLOBAL_HANDLE: OnceLock<LogLevelHandle> = OnceLock::new();
+
+/// 可重载过滤器的句柄
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    handle: reload::Handle<Targets, Registry>,
+}
+
+impl LogLevelHandle {
+    /// 创建可重载过滤层及其句柄
+    pub fn new_layer(default_level: LevelFilter) -> (reload::Layer<Targets, Registry>, Self) {
+        Self::with_filter(Targets::new().with_default(default_level))
+    }
+
+    /// 以指定过滤规则创建可重载过滤层及其句柄
+    pub fn with_filter(filter: Targets) -> (reload::Layer<Targets, Registry>, Self) {
+        let (layer, handle) = reload::Layer::new(filter);
+        (layer, Self { handle })
+    }
+
+    /// 设置日志级别
+    ///
+    /// - `target`: 模块路径前缀（如 `proxycast_server`），为空或 `*` 时设置默认级别
+    /// - `level`: `trace`/`debug`/`info`/`warn`/`error`/`off`
+    pub fn set_level(&self, target: &str, level: &str) -> Result<(), String> {
+        let level =
+            LevelFilter::from_str(level.trim()).map_err(|_| format!("无效的日志级别: {level}"))?;
+        let target = target.trim();
+
+        self.handle
+            .modify(|filter| {
+                let current = std::mem::take(filter);
+                *filter = if target.is_empty() || target == "*" {
+                    current.with_default(level)
+                } else {
+                    current.with_target(target.to_string(), level)
+                };
+            })
+            .map_err(|e| format!("更新日志级别失败: {e}"))?;
+
+        tracing::info!(
+            "[LOG_FILTER] 日志级别已调整: {} = {}",
+            if target.is_empty() { "*" } else { target },
+            level
+        );
+        Ok(())
+    }
+
+    /// 当前过滤规则（`target=level` 形式）
+    pub fn current(&self) -> Result<String, String> {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 初始过滤规则
+///
+/// `RUST_LOG` 优先；未设置或无法解析时使用配置的日志级别（logging.level），都无效时为 INFO。
+fn initial_filter(rust_log: Option<&str>, config_level: Option<&str>) -> Targets {
+    if let Some(filter) = rust_log
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .and_then(|s| Targets::from_str(s).ok())
+    {
+        return filter;
+    }
+    let level = config_level
+        .and_then(|level| LevelFilter::from_str(level.trim()).ok())
+        .unwrap_or(DEFAULT_LEVEL);
+    Targets::new().with_default(level)
+}
+
+fn rust_log() -> Option<String> {
+    std::env::var(RUST_LOG_ENV).ok()
+}
+
+/// 初始化全局 tracing 订阅器
+///
+/// 初始级别取自 `RUST_LOG` 或 `config_level`（见 [`initial_filter`]）。
+/// 已存在全局订阅器时返回 `None`，此时运行时调整不可用。
+pub fn init_tracing(config_level: Option<&str>) -> Option<&'static LogLevelHandle> {
+    let filter = initial_filter(rust_log().as_deref(), config_level);
+    let (filter_layer, handle) = LogLevelHandle::with_filter(filter);
+    let result = tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(tracing_subscriber::fmt::layer())
+        .try_init();
+
+    match result {
+        Ok(()) => Some(GLOBAL_HANDLE.get_or_init(|| handle)),
+        Err(e) => {
+            tracing::warn!(
+                "[LOG_FILTER] tracing 订阅器已存在，运行时日志级别调整不可用: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// 应用配置的日志级别（配置加载后调用）
+///
+/// 设置了 `RUST_LOG` 时以环境变量为准，不做修改。
+pub fn apply_config_level(level: &str) -> Result<(), String> {
+    if rust_log().is_some_and(|s| !s.trim().is_empty()) {
+        return Ok(());
+    }
+    set_log_level("*", level)
+}
+
+/// 获取全局日志级别句柄
+pub fn global_handle() -> Option<&'static LogLevelHandle> {
+    GLOBAL_HANDLE.get()
+}
+
+/// 调整全局日志级别
+pub fn set_log_level(target: &str, level: &str) -> Result<(), String> {
+    global_handle()
+        .ok_or_else(|| "日志系统未初始化".to_string())?
+        .set_level(target, level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl CaptureWriter {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_raise_level_at_runtime() {
+        let writer = CaptureWriter::default();
+        let make_writer = {
+            let writer = writer.clone();
+            move || writer.clone()
+        };
+        let (filter_layer, handle) = LogLevelHandle::new_layer(LevelFilter::INFO);
+        let subscriber = tracing_subscriber::registry().with(filter_layer).with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(make_writer)
+                .with_ansi(false),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "proxycast_server", "before-raise");
+            assert!(!writer.contents().contains("before-raise"));
+
+            handle.set_level("proxycast_server", "debug").unwrap();
+            tracing::debug!(target: "proxycast_server", "after-raise");
+            tracing::debug!(target: "proxycast_providers", "other-target");
+            assert!(handle.current().unwrap().contains("proxycast_server=debug"));
+        });
+
+        let output = writer.contents();
+        assert!(!output.contains("before-raise"));
+        assert!(output.contains("after-raise"));
+        assert!(!output.contains("other-target"));
+    }
+
+    #[test]
+    fn test_initial_filter_prefers_rust_log_then_config() {
+        let filter = initial_filter(Some("proxycast_server=debug,warn"), Some("error"));
+        assert!(filter.would_enable("proxycast_server", &tracing::Level::DEBUG));
+        assert!(!filter.would_enable("proxycast_providers", &tracing::Level::INFO));
+        assert!(filter.would_enable("proxycast_providers", &tracing::Level::WARN));
+
+        let filter = initial_filter(None, Some("debug"));
+        assert!(filter.would_enable("proxycast_server", &tracing::Level::DEBUG));
+
+        // 空的 RUST_LOG 与无效的配置级别回退到 INFO
+        let filter = initial_filter(Some(" "), Some("verbose"));
+        assert!(filter.would_enable("proxycast_server", &tracing::Level::INFO));
+        assert!(!filter.would_enable("proxycast_server", &tracing::Level::DEBUG));
+    }
+
+    #[test]
+    fn test_invalid_level_rejected() {
+        let (_layer, handle) = LogLevelHandle::new_layer(LevelFilter::INFO);
+        assert!(handle.set_level("proxycast_server", "verbose").is_err());
+    }
+}
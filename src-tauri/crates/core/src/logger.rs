@@ -1,9 +1,9 @@
 //! 日志管理模块
-use crate::config::LoggingConfig;
+use crate::config::{LoggingConfig, RedactionConfig};
 use chrono::{Duration, Local, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::fs::{self, OpenOptions};
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -40,6 +40,7 @@ pub struct LogStore {
     max_logs: usize,
     config: LogStoreConfig,
     log_file_path: Option<PathBuf>,
+    redactor: Redactor,
 }
 
 impl Default for LogStore {
@@ -56,6 +57,7 @@ impl Default for LogStore {
             max_logs: config.max_logs,
             config,
             log_file_path: Some(log_file),
+            redactor: Redactor::default(),
         }
     }
 }
@@ -74,8 +76,20 @@ impl LogStore {
         store
     }
 
+    /// 设置内容脱敏规则
+    pub fn set_redactor(&mut self, redactor: Redactor) {
+        self.redactor = redactor;
+    }
+
+    /// 按当前脱敏规则处理内容
+    ///
+    /// 供不经过日志存储的请求 / 响应输出（调试文件、标准错误）复用同一套规则。
+    pub fn redact(&self, content: &str) -> String {
+        self.redactor.redact(content)
+    }
+
     pub fn add(&mut self, level: &str, message: &str) {
-        let sanitized = sanitize_log_message(message);
+        let sanitized = sanitize_log_message(&self.redactor.redact(message));
         let now = Utc::now();
         let entry = LogEntry {
             timestamp: now.to_rfc3339(),
@@ -104,7 +118,7 @@ impl LogStore {
         if let Some(ref log_path) = self.log_file_path {
             let log_dir = log_path.parent().unwrap_or(std::path::Path::new("."));
             let raw_file = log_dir.join(format!("raw_response_{request_id}.txt"));
-            let sanitized = sanitize_log_message(body);
+            let sanitized = sanitize_log_message(&self.redactor.redact(body));
             if let Ok(mut file) = OpenOptions::new()
                 .create(true)
                 .truncate(true)
@@ -240,7 +254,97 @@ impl LogStore {
 pub type SharedLogStore = Arc<parking_lot::RwLock<LogStore>>;
 
 pub fn create_log_store_from_config(logging: &LoggingConfig) -> LogStore {
-    LogStore::with_custom_config(logging.retention_days, logging.enabled)
+    let mut store = LogStore::with_custom_config(logging.retention_days, logging.enabled);
+    store.set_redactor(Redactor::from_config(&logging.redaction));
+    store
+}
+
+/// 脱敏占位符
+const REDACTED: &str = "[REDACTED]";
+
+/// 请求 / 响应内容脱敏器
+///
+/// 由 [`RedactionConfig`] 编译而来：JSON 内容按键名替换值，再对整段文本应用正则规则。
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+    json_keys: HashSet<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::from_config(&RedactionConfig::default())
+    }
+}
+
+impl Redactor {
+    /// 从配置编译脱敏规则，无效的正则会被跳过
+    pub fn from_config(config: &RedactionConfig) -> Self {
+        let patterns = config
+            .patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    tracing::warn!("[LOGGER] 忽略无效的脱敏规则 {}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        let json_keys = config
+            .json_keys
+            .iter()
+            .map(|key| key.to_lowercase())
+            .collect();
+        Self {
+            patterns,
+            json_keys,
+        }
+    }
+
+    /// 对内容脱敏
+    pub fn redact(&self, content: &str) -> String {
+        let trimmed = content.trim_start();
+        let mut redacted = if !self.json_keys.is_empty()
+            && (trimmed.starts_with('{') || trimmed.starts_with('['))
+        {
+            match serde_json::from_str::<serde_json::Value>(content) {
+                Ok(mut value) => {
+                    self.redact_json(&mut value);
+                    value.to_string()
+                }
+                Err(_) => content.to_string(),
+            }
+        } else {
+            content.to_string()
+        };
+        for re in &self.patterns {
+            if re.is_match(&redacted) {
+                redacted = re.replace_all(&redacted, REDACTED).into_owned();
+            }
+        }
+        redacted
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    if self.json_keys.contains(&key.to_lowercase()) {
+                        *child = serde_json::Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(child);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    self.redact_json(item);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 /// P2 安全修复：扩展日志脱敏规则，覆盖更多敏感字段
@@ -285,7 +389,56 @@ pub fn sanitize_log_message(message: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::sanitize_log_message;
+    use super::{sanitize_log_message, LogStore, Redactor};
+    use crate::config::RedactionConfig;
+
+    #[test]
+    fn test_redactor_masks_secrets_in_prompt_before_storage() {
+        let mut store = LogStore::with_custom_config(7, false);
+        let body = serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{
+                "role": "user",
+                "content": "用这个 key 调试：sk-ant-api03-AbCdEf123456，header 是 Bearer eyJhbGciOi.payload.sig"
+            }]
+        });
+        store.add("debug", &format!("[REQUEST] body={body}"));
+
+        let message = &store.get_logs()[0].message;
+        assert!(!message.contains("sk-ant-api03-AbCdEf123456"));
+        assert!(!message.contains("eyJhbGciOi"));
+        assert!(message.contains("[REDACTED]"));
+        assert!(message.contains("claude-sonnet-4-5"));
+    }
+
+    #[test]
+    fn test_set_redactor_replaces_rules() {
+        let mut store = LogStore::with_custom_config(7, false);
+        assert_eq!(store.redact("order 12345"), "order 12345");
+
+        store.set_redactor(Redactor::from_config(&RedactionConfig {
+            patterns: vec![r"\d{5}".to_string()],
+            json_keys: Vec::new(),
+        }));
+        assert_eq!(store.redact("order 12345"), "order [REDACTED]");
+        store.add("info", "order 12345");
+        assert_eq!(store.get_logs()[0].message, "order [REDACTED]");
+    }
+
+    #[test]
+    fn test_redactor_masks_configured_json_keys() {
+        let redactor = Redactor::from_config(&RedactionConfig {
+            patterns: vec![r"\d{3}-\d{4}-\d{4}".to_string(), "(".to_string()],
+            json_keys: vec!["Phone".to_string()],
+        });
+        let body = r#"{"user":{"phone":"13800000000","name":"张三"},"note":"call 138-0000-0000"}"#;
+
+        let output = redactor.redact(body);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["user"]["phone"], "[REDACTED]");
+        assert_eq!(value["user"]["name"], "张三");
+        assert_eq!(value["note"], "call [REDACTED]");
+    }
 
     #[test]
     fn test_sanitize_bearer_token() {
@@ -0,0 +1,282 @@
+//! 全局并发限制中间件
+//!
+//! 在单凭证并发和会话配额之外，为整个服务器设置聊天请求的并发上限，保护宿主机。
+//! 仅作用于 `/v1/messages`、`/v1/chat/completions`（含选择器前缀路径）：
+//! 名额已满时请求最多排队等待 `wait`，仍未获得名额则返回 503。
+//! 名额在响应体发送完毕或被丢弃时释放，流式响应在整个流期间占用一个名额。
+
+use axum::{
+    body::Body,
+    http::{header, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use serde::Serialize;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Layer, Service};
+
+/// 并发使用情况（通过 `/health` 暴露）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ConcurrencyStatus {
+    /// 并发上限，0 表示不限制
+    pub max_concurrent_requests: usize,
+    /// 正在处理的请求数
+    pub in_flight: usize,
+    /// 使用率（0.0 ~ 1.0），不限制时为 0
+    pub utilization: f64,
+}
+
+/// 等待名额超时
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Server is at its concurrency limit ({max} requests), please retry later")]
+pub struct ConcurrencyLimitExceeded {
+    /// 并发上限
+    pub max: usize,
+}
+
+/// 全局并发限制器
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    semaphore: Option<Arc<Semaphore>>,
+    max: usize,
+    wait: Duration,
+}
+
+impl ConcurrencyLimiter {
+    /// 创建限制器，`max` 为 0 时不限制
+    pub fn new(max: usize, wait: Duration) -> Self {
+        Self {
+            semaphore: (max > 0).then(|| Arc::new(Semaphore::new(max))),
+            max,
+            wait,
+        }
+    }
+
+    /// 不限制并发的限制器
+    pub fn unlimited() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.semaphore.is_some()
+    }
+
+    /// 当前使用情况
+    pub fn status(&self) -> ConcurrencyStatus {
+        let in_flight = self
+            .semaphore
+            .as_ref()
+            .map(|s| self.max - s.available_permits())
+            .unwrap_or(0);
+        ConcurrencyStatus {
+            max_concurrent_requests: self.max,
+            in_flight,
+            utilization: if self.max == 0 {
+                0.0
+            } else {
+                in_flight as f64 / self.max as f64
+            },
+        }
+    }
+
+    /// 获取一个名额，最多等待 `wait`
+    ///
+    /// 未启用时返回 `Ok(None)`。
+    pub async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>, ConcurrencyLimitExceeded> {
+        let Some(semaphore) = &self.semaphore else {
+            return Ok(None);
+        };
+        match tokio::time::timeout(self.wait, semaphore.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            // 信号量不会被关闭；超时视为过载
+            Ok(Err(_)) | Err(_) => Err(ConcurrencyLimitExceeded { max: self.max }),
+        }
+    }
+}
+
+impl Default for ConcurrencyLimiter {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// 是否为受并发限制的聊天接口路径
+pub(crate) fn is_chat_path(path: &str) -> bool {
+    path.ends_with("/v1/messages") || path.ends_with("/v1/chat/completions")
+}
+
+/// 并发已满的 503 响应
+fn overloaded_response(error: &ConcurrencyLimitExceeded) -> Response<Body> {
+    let body = serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "overloaded_error",
+            "message": error.to_string()
+        }
+    });
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::RETRY_AFTER, "1")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_default()
+}
+
+/// 全局并发限制层
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitLayer {
+    limiter: Arc<ConcurrencyLimiter>,
+}
+
+impl ConcurrencyLimitLayer {
+    /// 使用共享的限制器创建限制层
+    pub fn new(limiter: Arc<ConcurrencyLimiter>) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S> Layer<S> for ConcurrencyLimitLayer {
+    type Service = ConcurrencyLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConcurrencyLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// 全局并发限制服务
+#[derive(Clone)]
+pub struct ConcurrencyLimitService<S> {
+    inner: S,
+    limiter: Arc<ConcurrencyLimiter>,
+}
+
+impl<S> Service<Request<Body>> for ConcurrencyLimitService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let limited = self.limiter.is_enabled() && is_chat_path(req.uri().path());
+        let limiter = self.limiter.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if !limited {
+                return inner.call(req).await;
+            }
+            let permit = match limiter.acquire().await {
+                Ok(permit) => permit,
+                Err(e) => {
+                    tracing::warn!(
+                        "[CONCURRENCY] 全局并发已满（上限 {}），拒绝请求: {}",
+                        e.max,
+                        req.uri().path()
+                    );
+                    return Ok(overloaded_response(&e));
+                }
+            };
+
+            let response = inner.call(req).await?;
+            // 名额随响应体一起释放
+            let (parts, body) = response.into_parts();
+            let body = Body::from_stream(body.into_data_stream().map(move |chunk| {
+                let _held = &permit;
+                chunk
+            }));
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct OkService;
+
+    impl Service<Request<Body>> for OkService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            Box::pin(async { Ok(Response::new(Body::from("ok"))) })
+        }
+    }
+
+    async fn call(service: &mut ConcurrencyLimitService<OkService>, path: &str) -> Response<Body> {
+        service
+            .call(Request::post(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_overflow_rejected_then_admitted_after_release() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(1, Duration::from_millis(50)));
+        let mut service = ConcurrencyLimitLayer::new(limiter.clone()).layer(OkService);
+
+        // 第一个请求的响应体尚未发送完，占用唯一的名额
+        let first = call(&mut service, "/v1/messages").await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(limiter.status().in_flight, 1);
+        assert_eq!(limiter.status().utilization, 1.0);
+
+        let overflow = call(&mut service, "/kiro/v1/chat/completions").await;
+        assert_eq!(overflow.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // 非聊天接口不受限制
+        assert_eq!(call(&mut service, "/health").await.status(), StatusCode::OK);
+
+        // 响应体发送完毕后名额释放
+        axum::body::to_bytes(first.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(limiter.status().in_flight, 0);
+        assert_eq!(
+            call(&mut service, "/v1/messages").await.status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_waiting_request_admitted_within_bound() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(1, Duration::from_secs(5)));
+        let mut service = ConcurrencyLimitLayer::new(limiter.clone()).layer(OkService);
+
+        let first = call(&mut service, "/v1/messages").await;
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(first);
+        });
+        assert_eq!(
+            call(&mut service, "/v1/messages").await.status(),
+            StatusCode::OK
+        );
+        release.await.unwrap();
+
+        let unlimited = ConcurrencyLimiter::unlimited();
+        assert!(unlimited.acquire().await.unwrap().is_none());
+        assert_eq!(unlimited.status().utilization, 0.0);
+    }
+}
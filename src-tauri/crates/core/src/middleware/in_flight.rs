@@ -0,0 +1,331 @@
+//! 在途请求跟踪与停机排空
+//!
+//! 记录正在处理的请求（含尚未发送完毕的响应体）。停止服务器时：
+//! - 等待在途请求在排空期限内自然完成，计入 `drained_requests`
+//! - 期限到达后仍未完成的请求被中止（尚未返回的请求返回 503，
+//!   流式响应体直接结束），计入 `aborted_requests`
+//!
+//! 停机结果以 [`ShutdownReport`] 返回给 `stop_server`，便于界面如实展示。
+
+use axum::{
+    body::Body,
+    http::{header, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Notify};
+use tower::{Layer, Service};
+
+/// 中止后等待请求收尾的最长时间
+const ABORT_GRACE: Duration = Duration::from_secs(1);
+
+/// 停机结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    /// 是否执行了停止（服务器未运行时为 false）
+    pub stopped: bool,
+    /// 排空期间正常完成的请求数
+    pub drained_requests: u64,
+    /// 排空期限到达时被中止的请求数
+    pub aborted_requests: u64,
+    /// 停机耗时（毫秒）
+    pub duration_ms: u64,
+}
+
+/// 在途请求跟踪器
+#[derive(Debug)]
+pub struct InFlightTracker {
+    active: AtomicUsize,
+    draining: AtomicBool,
+    drained: AtomicU64,
+    aborted: AtomicU64,
+    idle: Notify,
+    abort_tx: watch::Sender<bool>,
+}
+
+impl InFlightTracker {
+    /// 创建跟踪器
+    pub fn new() -> Arc<Self> {
+        let (abort_tx, _) = watch::channel(false);
+        Arc::new(Self {
+            active: AtomicUsize::new(0),
+            draining: AtomicBool::new(false),
+            drained: AtomicU64::new(0),
+            aborted: AtomicU64::new(0),
+            idle: Notify::new(),
+            abort_tx,
+        })
+    }
+
+    /// 当前在途请求数
+    pub fn in_flight(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    fn begin(self: &Arc<Self>) -> InFlightGuard {
+        self.active.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard {
+            tracker: self.clone(),
+            aborted: false,
+        }
+    }
+
+    /// 等待在途请求完成，超过 `timeout` 后中止剩余请求
+    pub async fn drain(&self, timeout: Duration) -> ShutdownReport {
+        let started = Instant::now();
+        self.draining.store(true, Ordering::SeqCst);
+
+        if !self.wait_idle(timeout).await {
+            let remaining = self.in_flight();
+            tracing::warn!("[SHUTDOWN] 排空超时，中止 {} 个在途请求", remaining);
+            self.abort_tx.send_replace(true);
+            if !self.wait_idle(ABORT_GRACE).await {
+                tracing::warn!("[SHUTDOWN] 仍有 {} 个请求未结束", self.in_flight());
+            }
+        }
+
+        ShutdownReport {
+            stopped: true,
+            drained_requests: self.drained.load(Ordering::SeqCst),
+            aborted_requests: self.aborted.load(Ordering::SeqCst),
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// 等待在途请求数归零，超时返回 false
+    async fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // 先注册再检查，避免错过检查与等待之间的通知
+            let notified = self.idle.notified();
+            if self.in_flight() == 0 {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.in_flight() == 0;
+            }
+        }
+    }
+
+    /// 等待中止信号
+    async fn aborted(&self) {
+        let mut rx = self.abort_tx.subscribe();
+        // 发送端由跟踪器持有，不会提前关闭
+        let _ = rx.wait_for(|aborted| *aborted).await;
+    }
+}
+
+/// 在途请求守卫，随响应体一起释放
+struct InFlightGuard {
+    tracker: Arc<InFlightTracker>,
+    aborted: bool,
+}
+
+impl InFlightGuard {
+    /// 标记为被中止，释放时计入 `aborted_requests`
+    fn mark_aborted(&mut self) {
+        self.aborted = true;
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let tracker = &self.tracker;
+        if tracker.draining.load(Ordering::SeqCst) {
+            if self.aborted {
+                tracker.aborted.fetch_add(1, Ordering::SeqCst);
+            } else {
+                tracker.drained.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        if tracker.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            tracker.idle.notify_waiters();
+        }
+    }
+}
+
+/// 停机中止的 503 响应
+fn shutting_down_response() -> Response<Body> {
+    let body = serde_json::json!({
+        "error": {
+            "type": "server_shutting_down",
+            "message": "Server is shutting down"
+        }
+    });
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_default()
+}
+
+/// 在途请求跟踪层
+#[derive(Debug, Clone)]
+pub struct InFlightLayer {
+    tracker: Arc<InFlightTracker>,
+}
+
+impl InFlightLayer {
+    /// 使用共享的跟踪器创建
+    pub fn new(tracker: Arc<InFlightTracker>) -> Self {
+        Self { tracker }
+    }
+}
+
+impl<S> Layer<S> for InFlightLayer {
+    type Service = InFlightService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InFlightService {
+            inner,
+            tracker: self.tracker.clone(),
+        }
+    }
+}
+
+/// 在途请求跟踪服务
+#[derive(Clone)]
+pub struct InFlightService<S> {
+    inner: S,
+    tracker: Arc<InFlightTracker>,
+}
+
+impl<S> Service<Request<Body>> for InFlightService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let tracker = self.tracker.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let mut guard = tracker.begin();
+            let response = tokio::select! {
+                response = inner.call(req) => response?,
+                _ = tracker.aborted() => {
+                    guard.mark_aborted();
+                    return Ok(shutting_down_response());
+                }
+            };
+
+            // 守卫随响应体释放；中止信号到达时结束响应体
+            let (parts, body) = response.into_parts();
+            let body = stream::unfold(
+                (body.into_data_stream(), guard),
+                move |(mut inner, mut guard)| {
+                    let tracker = tracker.clone();
+                    async move {
+                        tokio::select! {
+                            chunk = inner.next() => chunk.map(|chunk| (chunk, (inner, guard))),
+                            _ = tracker.aborted() => {
+                                guard.mark_aborted();
+                                None
+                            }
+                        }
+                    }
+                },
+            );
+            Ok(Response::from_parts(parts, Body::from_stream(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+
+    /// 按路径决定响应耗时：`/fast` 的响应体 50ms 后结束，`/slow` 迟迟不返回
+    #[derive(Clone)]
+    struct DelayedService;
+
+    impl Service<Request<Body>> for DelayedService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<Body>) -> Self::Future {
+            let slow = req.uri().path() == "/slow";
+            Box::pin(async move {
+                if slow {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                }
+                let body = stream::once(async {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok::<_, std::io::Error>(Bytes::from_static(b"done"))
+                });
+                Ok(Response::new(Body::from_stream(body)))
+            })
+        }
+    }
+
+    fn spawn_request(
+        service: &InFlightService<DelayedService>,
+        path: &'static str,
+    ) -> tokio::task::JoinHandle<(StatusCode, Bytes)> {
+        let mut service = service.clone();
+        tokio::spawn(async move {
+            let response = service
+                .call(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap_or_default();
+            (status, body)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_drain_counts_finished_and_aborted_requests() {
+        let tracker = InFlightTracker::new();
+        let service = InFlightLayer::new(tracker.clone()).layer(DelayedService);
+
+        let fast = spawn_request(&service, "/fast");
+        let slow = spawn_request(&service, "/slow");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(tracker.in_flight(), 2);
+
+        let report = tracker.drain(Duration::from_millis(300)).await;
+        assert_eq!(
+            (
+                report.stopped,
+                report.drained_requests,
+                report.aborted_requests
+            ),
+            (true, 1, 1)
+        );
+        assert!(report.duration_ms >= 300);
+        assert_eq!(tracker.in_flight(), 0);
+
+        assert_eq!(fast.await.unwrap(), (StatusCode::OK, Bytes::from("done")));
+        assert_eq!(slow.await.unwrap().0, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_drain_without_in_flight_requests_returns_immediately() {
+        let tracker = InFlightTracker::new();
+        let report = tracker.drain(Duration::from_secs(5)).await;
+        assert_eq!(report.drained_requests + report.aborted_requests, 0);
+        assert!(report.duration_ms < 1000);
+    }
+}
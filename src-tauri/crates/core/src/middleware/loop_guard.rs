@@ -0,0 +1,309 @@
+//! 代理回环检测
+//!
+//! 凭证的上游地址被误配为代理自身时，请求会在代理内部无限转发直到超时。
+//! - 每次向上游发起请求时携带 `X-ProxyCast-Hop` 跳数请求头（入站跳数 + 1），
+//!   跳数达到上限的入站请求直接以 `loop_detected` 错误拒绝
+//! - 跳数请求头只发往可能是 ProxyCast 实例的上游（自定义上游地址），
+//!   发往官方上游（[`is_official_upstream`]）的请求不携带，避免内部请求头泄露给第三方
+//! - [`is_self_target`] 判断上游地址是否指向服务器自身的监听地址，
+//!   调用方据此在未显式允许时拒绝转发
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use std::net::IpAddr;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// 跳数请求头名称
+pub const HOP_HEADER: &str = "x-proxycast-hop";
+
+/// 默认最大跳数（入站跳数达到该值即视为回环）
+pub const DEFAULT_MAX_HOPS: u32 = 3;
+
+tokio::task_local! {
+    /// 当前请求的入站跳数
+    static CURRENT_HOP: u32;
+}
+
+/// 从请求头读取入站跳数（缺失或无法解析时为 0）
+pub fn hop_from_headers(headers: &HeaderMap) -> u32 {
+    headers
+        .get(HOP_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// 当前请求的入站跳数（不在请求处理上下文中时为 0）
+pub fn current_hop() -> u32 {
+    CURRENT_HOP.try_with(|hop| *hop).unwrap_or(0)
+}
+
+/// 向上游发起请求时应携带的跳数
+pub fn outbound_hop() -> u32 {
+    current_hop().saturating_add(1)
+}
+
+/// 检测到回环时的 508 响应
+pub fn loop_detected_response(message: &str) -> Response<Body> {
+    let body = serde_json::json!({
+        "error": {
+            "type": "loop_detected",
+            "code": "loop_detected",
+            "message": message
+        }
+    });
+    Response::builder()
+        .status(StatusCode::LOOP_DETECTED)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_default()
+}
+
+/// 官方上游主机名（含其子域名）
+///
+/// 这些上游不可能是 ProxyCast 实例，发往它们的请求不携带跳数请求头。
+const OFFICIAL_UPSTREAM_HOSTS: &[&str] = &[
+    "anthropic.com",
+    "claude.ai",
+    "openai.com",
+    "chatgpt.com",
+    "googleapis.com",
+    "google.com",
+    "amazonaws.com",
+    "kiro.dev",
+];
+
+/// 上游地址是否为官方上游（无法解析的地址视为非官方）
+pub fn is_official_upstream(url: &str) -> bool {
+    let Some(host) = url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+    else {
+        return false;
+    };
+    OFFICIAL_UPSTREAM_HOSTS.iter().any(|official| {
+        host == *official
+            || host
+                .strip_suffix(official)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+/// 是否为本机回环或未指定地址
+fn is_local_host(host: &str) -> bool {
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .is_ok_and(|ip| ip.is_loopback() || ip.is_unspecified())
+}
+
+/// 上游地址是否指向服务器自身的监听地址
+///
+/// 端口必须一致；主机名相同，或双方都是本机地址（`localhost`、`127.0.0.1`、
+/// `::1`、`0.0.0.0` 视为等价）时判定为指向自身。
+/// 监听 `0.0.0.0` 时，指向本机回环地址的上游同样视为自身。
+pub fn is_self_target(base_url: &str, bind_host: &str, bind_port: u16) -> bool {
+    let Ok(url) = url::Url::parse(base_url) else {
+        return false;
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    if port != bind_port {
+        return false;
+    }
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let bind_host = bind_host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case(bind_host) || (is_local_host(host) && is_local_host(bind_host))
+}
+
+/// 回环检测层
+///
+/// 拒绝跳数达到上限的请求，并在处理期间记录入站跳数供上游客户端读取。
+#[derive(Debug, Clone, Copy)]
+pub struct LoopGuardLayer {
+    max_hops: u32,
+}
+
+impl LoopGuardLayer {
+    /// 创建回环检测层
+    pub fn new(max_hops: u32) -> Self {
+        Self { max_hops }
+    }
+}
+
+impl Default for LoopGuardLayer {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_HOPS)
+    }
+}
+
+impl<S> Layer<S> for LoopGuardLayer {
+    type Service = LoopGuardService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoopGuardService {
+            inner,
+            max_hops: self.max_hops,
+        }
+    }
+}
+
+/// 回环检测服务
+#[derive(Clone)]
+pub struct LoopGuardService<S> {
+    inner: S,
+    max_hops: u32,
+}
+
+impl<S> Service<Request<Body>> for LoopGuardService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let hop = hop_from_headers(req.headers());
+        if hop >= self.max_hops {
+            tracing::warn!(
+                "[LOOP_GUARD] 请求跳数 {} 已达上限 {}，疑似上游地址指向代理自身: {}",
+                hop,
+                self.max_hops,
+                req.uri().path()
+            );
+            let message = format!(
+                "Request loop detected: {HOP_HEADER} reached {hop} (limit {}). \
+                 Check that no upstream base URL points back at this proxy.",
+                self.max_hops
+            );
+            return Box::pin(async move { Ok(loop_detected_response(&message)) });
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(CURRENT_HOP.scope(hop, async move { inner.call(req).await }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 把处理期间看到的出站跳数放入响应体
+    #[derive(Clone)]
+    struct HopEchoService;
+
+    impl Service<Request<Body>> for HopEchoService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            Box::pin(async move { Ok(Response::new(Body::from(outbound_hop().to_string()))) })
+        }
+    }
+
+    async fn call(hop: Option<&str>) -> (StatusCode, String) {
+        let mut builder = Request::builder().uri("/v1/chat/completions");
+        if let Some(hop) = hop {
+            builder = builder.header(HOP_HEADER, hop);
+        }
+        let mut service = LoopGuardLayer::new(3).layer(HopEchoService);
+        let response = service
+            .call(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_hop_count_increments_then_trips() {
+        assert_eq!(call(None).await, (StatusCode::OK, "1".to_string()));
+        assert_eq!(call(Some("2")).await, (StatusCode::OK, "3".to_string()));
+        assert_eq!(
+            call(Some("garbage")).await,
+            (StatusCode::OK, "1".to_string())
+        );
+
+        let (status, body) = call(Some("3")).await;
+        assert_eq!(status, StatusCode::LOOP_DETECTED);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"]["type"], "loop_detected");
+
+        // 不在请求上下文中时视为首跳
+        assert_eq!(outbound_hop(), 1);
+    }
+
+    #[test]
+    fn test_self_target_detection() {
+        assert!(is_self_target(
+            "http://127.0.0.1:8999/v1",
+            "127.0.0.1",
+            8999
+        ));
+        assert!(is_self_target("http://localhost:8999", "0.0.0.0", 8999));
+        assert!(is_self_target("http://[::1]:8999/v1", "localhost", 8999));
+        assert!(is_self_target(
+            "http://192.168.1.5:8999",
+            "192.168.1.5",
+            8999
+        ));
+
+        // 端口不同或主机不同不算自身
+        assert!(!is_self_target(
+            "http://127.0.0.1:9000/v1",
+            "127.0.0.1",
+            8999
+        ));
+        assert!(!is_self_target("https://api.openai.com/v1", "0.0.0.0", 443));
+        assert!(!is_self_target(
+            "http://192.168.1.5:8999",
+            "127.0.0.1",
+            8999
+        ));
+        assert!(!is_self_target("not a url", "127.0.0.1", 8999));
+    }
+
+    #[test]
+    fn test_official_upstream_detection() {
+        assert!(is_official_upstream(
+            "https://api.anthropic.com/v1/messages"
+        ));
+        assert!(is_official_upstream(
+            "https://codewhisperer.us-east-1.amazonaws.com/generateAssistantResponse"
+        ));
+        assert!(is_official_upstream(
+            "https://CHATGPT.com/backend-api/codex"
+        ));
+        assert!(is_official_upstream(
+            "https://cloudcode-pa.googleapis.com/v1internal:generateContent"
+        ));
+
+        // 自定义上游、仿冒的主机名后缀和无法解析的地址都不算官方上游
+        assert!(!is_official_upstream("http://127.0.0.1:8999/v1"));
+        assert!(!is_official_upstream("https://relay.example.com/v1"));
+        assert!(!is_official_upstream("https://notopenai.com/v1"));
+        assert!(!is_official_upstream("not a url"));
+    }
+}
@@ -10,6 +10,15 @@
 //! 1. 如果 secret_key 为空，返回 404 Not Found（禁用管理 API）
 //! 2. 如果 allow_remote 为 false 且请求来自非 localhost，返回 403 Forbidden
 //! 3. 如果请求缺少有效的 secret_key，返回 401 Unauthorized
+//!
+//! # HMAC 请求签名（可选）
+//!
+//! 启用 `require_signature` 后不再接受 Bearer 密钥，客户端以 secret_key 为共享密钥
+//! 对 `METHOD + PATH + TIMESTAMP + BODY` 计算 HMAC-SHA256（PATH 含查询串），
+//! 并通过 `X-Timestamp`（Unix 秒）和 `X-Signature`（十六进制）请求头发送。
+//! 时间戳与服务器时间偏差超过 `signature_max_skew_secs` 的请求视为过期。
+//! 时间窗口内每个签名只接受一次，重放的请求直接拒绝；
+//! 内容完全相同的请求需要间隔至少一秒（时间戳不同）才能重复发送。
 
 use crate::config::RemoteManagementConfig;
 use axum::{
@@ -17,7 +26,10 @@ use axum::{
     http::{Request, Response, StatusCode},
 };
 use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use std::{
+    collections::HashMap,
     net::{IpAddr, SocketAddr},
     sync::Arc,
     sync::Mutex,
@@ -33,6 +45,78 @@ const BLOCK_SECS: u64 = 300;
 // 安全修复：限制 failure_map 最大条目数，防止内存 DoS
 const MAX_FAILURE_ENTRIES: usize = 10000;
 const ENTRY_EXPIRE_SECS: u64 = 3600;
+/// 签名校验时缓冲的最大请求体大小
+const MAX_SIGNED_BODY_BYTES: usize = 10 * 1024 * 1024;
+/// 签名重放缓存的最大条目数
+const MAX_SEEN_SIGNATURES: usize = 10000;
+
+/// 签名请求头
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// 签名时间戳请求头
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+
+/// 计算管理 API 请求签名（十六进制 HMAC-SHA256）
+///
+/// 签名内容为 `METHOD + PATH + TIMESTAMP + BODY`，PATH 包含查询串。
+pub fn sign_request(
+    secret: &str,
+    method: &str,
+    path: &str,
+    timestamp: &str,
+    body: &[u8],
+) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(method.as_bytes());
+    mac.update(path.as_bytes());
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// 签名校验失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignatureError {
+    Missing,
+    Expired,
+    Invalid,
+    Replayed,
+}
+
+/// 已接受的签名 → 过期时间（Unix 秒），过期后时间戳本身已无法通过校验
+#[derive(Default)]
+struct SeenSignatures {
+    entries: Mutex<HashMap<String, i64>>,
+}
+
+impl SeenSignatures {
+    /// 记录签名，签名在有效期内已出现过时返回 `false`
+    fn insert(&self, signature: String, expires_at: i64, now: i64) -> bool {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries
+            .get(&signature)
+            .is_some_and(|&expires_at| expires_at >= now)
+        {
+            return false;
+        }
+
+        // 容量保护：先清理过期条目，仍然超限时淘汰最早过期的条目
+        if entries.len() >= MAX_SEEN_SIGNATURES {
+            entries.retain(|_, &mut expires_at| expires_at >= now);
+        }
+        if entries.len() >= MAX_SEEN_SIGNATURES {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, &expires_at)| expires_at)
+                .map(|(signature, _)| signature.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(signature, expires_at);
+        true
+    }
+}
 
 struct FailureState {
     count: u32,
@@ -67,6 +151,7 @@ pub fn clear_auth_failure_state_for(client_id: &str) {
 #[derive(Clone)]
 pub struct ManagementAuthLayer {
     config: Arc<RemoteManagementConfig>,
+    seen_signatures: Arc<SeenSignatures>,
 }
 
 impl ManagementAuthLayer {
@@ -74,6 +159,7 @@ impl ManagementAuthLayer {
     pub fn new(config: RemoteManagementConfig) -> Self {
         Self {
             config: Arc::new(config),
+            seen_signatures: Arc::new(SeenSignatures::default()),
         }
     }
 }
@@ -85,6 +171,7 @@ impl<S> Layer<S> for ManagementAuthLayer {
         ManagementAuthService {
             inner,
             config: self.config.clone(),
+            seen_signatures: self.seen_signatures.clone(),
         }
     }
 }
@@ -94,6 +181,7 @@ impl<S> Layer<S> for ManagementAuthLayer {
 pub struct ManagementAuthService<S> {
     inner: S,
     config: Arc<RemoteManagementConfig>,
+    seen_signatures: Arc<SeenSignatures>,
 }
 
 impl<S> ManagementAuthService<S> {
@@ -205,6 +293,55 @@ impl<S> ManagementAuthService<S> {
     fn secret_key_matches(provided: &str, expected: &str) -> bool {
         provided.as_bytes().ct_eq(expected.as_bytes()).into()
     }
+
+    fn header_str<'a>(req: &'a Request<Body>, name: &str) -> Option<&'a str> {
+        req.headers().get(name).and_then(|v| v.to_str().ok())
+    }
+
+    /// 校验 HMAC 请求签名
+    ///
+    /// 需要缓冲请求体计算签名，校验后用缓冲的内容重建请求。
+    /// 有效的签名记入重放缓存，时间窗口内再次出现时拒绝。
+    async fn verify_signature(
+        req: Request<Body>,
+        secret: &str,
+        max_skew_secs: u64,
+        seen: &SeenSignatures,
+    ) -> Result<Request<Body>, SignatureError> {
+        let (Some(timestamp), Some(signature)) = (
+            Self::header_str(&req, TIMESTAMP_HEADER).map(str::to_string),
+            Self::header_str(&req, SIGNATURE_HEADER).map(str::to_string),
+        ) else {
+            return Err(SignatureError::Missing);
+        };
+
+        let ts: i64 = timestamp.parse().map_err(|_| SignatureError::Invalid)?;
+        let now = chrono::Utc::now().timestamp();
+        let skew = now.abs_diff(ts);
+        if skew > max_skew_secs {
+            return Err(SignatureError::Expired);
+        }
+
+        let (parts, body) = req.into_parts();
+        let bytes = axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES)
+            .await
+            .map_err(|_| SignatureError::Invalid)?;
+        let path = parts
+            .uri
+            .path_and_query()
+            .map(|pq| pq.as_str())
+            .unwrap_or("/");
+        let expected = sign_request(secret, parts.method.as_str(), path, &timestamp, &bytes);
+        if !Self::secret_key_matches(&signature.to_ascii_lowercase(), &expected) {
+            return Err(SignatureError::Invalid);
+        }
+        let expires_at = ts.saturating_add_unsigned(max_skew_secs);
+        if !seen.insert(expected, expires_at, now) {
+            return Err(SignatureError::Replayed);
+        }
+
+        Ok(Request::from_parts(parts, Body::from(bytes)))
+    }
 }
 
 impl<S> Service<Request<Body>> for ManagementAuthService<S>
@@ -222,6 +359,7 @@ where
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
         let config = self.config.clone();
+        let seen_signatures = self.seen_signatures.clone();
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
@@ -260,7 +398,46 @@ where
                 ));
             }
 
-            // 3. 验证 secret_key
+            // 3. 验证 HMAC 签名（启用时代替 Bearer 认证）
+            if config.require_signature {
+                return match Self::verify_signature(
+                    req,
+                    &secret_key,
+                    config.signature_max_skew_secs,
+                    &seen_signatures,
+                )
+                .await
+                {
+                    Ok(req) => {
+                        tracing::debug!(
+                            "[MANAGEMENT_AUTH] Signature verified from {:?}",
+                            client_addr
+                        );
+                        Self::record_success(&client_id);
+                        inner.call(req).await
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            "[MANAGEMENT_AUTH] Signature rejected ({:?}) from {:?}",
+                            err,
+                            client_addr
+                        );
+                        // 重放的签名本身有效，不计入密钥猜测的失败次数
+                        if err != SignatureError::Replayed {
+                            Self::record_failure(&client_id);
+                        }
+                        let message = match err {
+                            SignatureError::Missing => "Missing request signature",
+                            SignatureError::Expired => "Request timestamp expired",
+                            SignatureError::Invalid => "Invalid request signature",
+                            SignatureError::Replayed => "Request signature already used",
+                        };
+                        Ok(create_error_response(StatusCode::UNAUTHORIZED, message))
+                    }
+                };
+            }
+
+            // 4. 验证 secret_key
             let provided_key = Self::extract_secret_key(&req);
             match provided_key {
                 Some(key) if Self::secret_key_matches(&key, &secret_key) => {
@@ -345,6 +522,7 @@ mod tests {
             allow_remote: false,
             secret_key: Some("test-secret".to_string()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let _layer = ManagementAuthLayer::new(config);
     }
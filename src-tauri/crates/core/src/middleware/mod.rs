@@ -2,9 +2,33 @@
 //!
 //! 提供 HTTP 请求处理的中间件组件
 
+pub mod concurrency_limit;
+pub mod in_flight;
+pub mod loop_guard;
 pub mod management_auth;
+pub mod request_dedup;
+pub mod request_id;
+pub mod request_lease;
+pub mod route_alias;
+pub mod sse_heartbeat;
+pub mod stream_coalesce;
+pub mod stream_compat;
+pub mod stream_idle_timeout;
 
 #[cfg(test)]
 mod tests;
 
-pub use management_auth::ManagementAuthLayer;
+pub use concurrency_limit::{ConcurrencyLimitLayer, ConcurrencyLimiter, ConcurrencyStatus};
+pub use in_flight::{InFlightLayer, InFlightTracker, ShutdownReport};
+pub use loop_guard::{is_self_target, LoopGuardLayer, HOP_HEADER};
+pub use management_auth::{sign_request, ManagementAuthLayer, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+pub use request_dedup::{RequestDedupLayer, RequestDeduplicator, DEDUP_HEADER};
+pub use request_id::{request_id_from_headers, RequestIdLayer, REQUEST_ID_HEADER};
+pub use request_lease::{hold_for_request, RequestLeaseLayer};
+pub use route_alias::{validate_route_aliases, RouteAliasLayer};
+pub use sse_heartbeat::SseHeartbeatLayer;
+pub use stream_coalesce::StreamCoalesceLayer;
+pub use stream_compat::StreamCompatLayer;
+pub use stream_idle_timeout::{
+    StreamIdleTimeoutEvent, StreamIdleTimeoutHook, StreamIdleTimeoutLayer,
+};
@@ -0,0 +1,473 @@
+//! 相同请求合并中间件
+//!
+//! Agent 偶尔会并发发出完全相同的请求（例如重复的工具评估），每个请求都调用上游会白白消耗 token。
+//! 启用后，API Key、模型和请求体都相同的并发聊天请求共享同一次上游调用，
+//! 所有请求收到相同的响应；上游成功响应后 `window` 内到达的相同请求也复用该响应。
+//! - 仅作用于 `/v1/messages`、`/v1/chat/completions`（含选择器前缀路径）
+//! - 流式请求（`"stream": true`）不合并
+//! - 请求体超过 [`MAX_DEDUP_BODY_BYTES`] 或未声明 `Content-Length` 时不合并
+//! - 非 2xx 响应只共享给并发等待的请求，不在窗口内复用
+//! - 复用响应的请求带有 `X-ProxyCast-Deduplicated: true` 响应头
+
+use super::concurrency_limit::is_chat_path;
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue, Request, Response, StatusCode},
+};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+/// 复用响应的标记响应头
+pub const DEDUP_HEADER: &str = "x-proxycast-deduplicated";
+
+/// 参与合并的最大请求体大小
+pub const MAX_DEDUP_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// 可共享的最大响应体大小
+pub const MAX_DEDUP_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// 缓冲后的响应，可在多个请求间共享
+#[derive(Clone)]
+struct SharedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl SharedResponse {
+    fn to_response(&self, deduplicated: bool) -> Response<Body> {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        if deduplicated {
+            response
+                .headers_mut()
+                .insert(DEDUP_HEADER, HeaderValue::from_static("true"));
+        }
+        response
+    }
+}
+
+type SharedCall = Shared<BoxFuture<'static, SharedResponse>>;
+
+/// 进行中或刚完成的上游调用
+struct Entry {
+    id: u64,
+    call: SharedCall,
+}
+
+/// 相同请求合并器
+pub struct RequestDeduplicator {
+    window: Duration,
+    entries: Mutex<HashMap<[u8; 32], Entry>>,
+    next_id: AtomicU64,
+}
+
+impl RequestDeduplicator {
+    /// 创建合并器，`window` 为上游调用完成后继续复用响应的时长
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// 当前记录的请求数
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// 是否没有记录任何请求
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 移除记录（仅移除指定调用创建的记录）
+    fn remove(&self, key: &[u8; 32], id: u64) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.get(key).is_some_and(|entry| entry.id == id) {
+            entries.remove(key);
+        }
+    }
+}
+
+/// 发起上游调用的请求持有的记录守卫
+///
+/// 无论请求正常结束还是 future 被丢弃（客户端断开），记录都会被移除：
+/// 成功响应在窗口结束后移除，其余情况立即移除，避免后续请求复用失效的调用。
+struct LeaderGuard {
+    deduplicator: Arc<RequestDeduplicator>,
+    key: [u8; 32],
+    id: u64,
+    /// 是否在窗口内继续复用响应
+    keep_for_window: bool,
+}
+
+impl Drop for LeaderGuard {
+    fn drop(&mut self) {
+        if self.keep_for_window {
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                let deduplicator = self.deduplicator.clone();
+                let (key, id) = (self.key, self.id);
+                handle.spawn(async move {
+                    tokio::time::sleep(deduplicator.window).await;
+                    deduplicator.remove(&key, id);
+                });
+                return;
+            }
+        }
+        self.deduplicator.remove(&self.key, self.id);
+    }
+}
+
+/// 请求方的 API Key（未携带时为空）
+fn api_key(headers: &HeaderMap) -> &[u8] {
+    [
+        "x-api-key",
+        "x-goog-api-key",
+        header::AUTHORIZATION.as_str(),
+    ]
+    .iter()
+    .find_map(|name| headers.get(*name))
+    .map(|v| v.as_bytes())
+    .unwrap_or_default()
+}
+
+/// 计算合并键；流式请求或无法解析的请求体返回 `None`
+fn dedup_key(path: &str, headers: &HeaderMap, body: &[u8]) -> Option<[u8; 32]> {
+    let json: serde_json::Value = serde_json::from_slice(body).ok()?;
+    if json.get("stream").and_then(|v| v.as_bool()) == Some(true) {
+        return None;
+    }
+    let model = json
+        .get("model")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    for part in [path.as_bytes(), api_key(headers), model.as_bytes(), body] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    Some(hasher.finalize().into())
+}
+
+/// 声明的请求体长度是否允许合并
+fn dedup_eligible(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok())
+        .is_some_and(|len| len <= MAX_DEDUP_BODY_BYTES)
+}
+
+/// 相同请求合并层
+#[derive(Clone)]
+pub struct RequestDedupLayer {
+    deduplicator: Option<Arc<RequestDeduplicator>>,
+}
+
+impl RequestDedupLayer {
+    /// 创建合并层，`window` 为 0 时不启用
+    pub fn new(window: Duration) -> Self {
+        Self {
+            deduplicator: (!window.is_zero()).then(|| Arc::new(RequestDeduplicator::new(window))),
+        }
+    }
+
+    /// 使用共享的合并器创建合并层
+    pub fn with_deduplicator(deduplicator: Arc<RequestDeduplicator>) -> Self {
+        Self {
+            deduplicator: Some(deduplicator),
+        }
+    }
+}
+
+impl<S> Layer<S> for RequestDedupLayer {
+    type Service = RequestDedupService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestDedupService {
+            inner,
+            deduplicator: self.deduplicator.clone(),
+        }
+    }
+}
+
+/// 相同请求合并服务
+#[derive(Clone)]
+pub struct RequestDedupService<S> {
+    inner: S,
+    deduplicator: Option<Arc<RequestDeduplicator>>,
+}
+
+impl<S> Service<Request<Body>> for RequestDedupService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let deduplicator = match &self.deduplicator {
+            Some(d) if is_chat_path(req.uri().path()) && dedup_eligible(req.headers()) => d.clone(),
+            _ => return Box::pin(inner.call(req)),
+        };
+
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let body = match axum::body::to_bytes(body, MAX_DEDUP_BODY_BYTES).await {
+                Ok(body) => body,
+                Err(e) => {
+                    tracing::warn!("[REQUEST_DEDUP] 读取请求体失败: {}", e);
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from(format!("Failed to read request body: {e}")))
+                        .unwrap_or_default());
+                }
+            };
+            let Some(key) = dedup_key(parts.uri.path(), &parts.headers, &body) else {
+                return inner
+                    .call(Request::from_parts(parts, Body::from(body)))
+                    .await;
+            };
+
+            let (call, mut leader) = {
+                let mut entries = deduplicator
+                    .entries
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                match entries.get(&key) {
+                    Some(entry) => (entry.call.clone(), None),
+                    None => {
+                        let id = deduplicator.next_id.fetch_add(1, Ordering::Relaxed);
+                        let req = Request::from_parts(parts, Body::from(body));
+                        let call = async move {
+                            let Ok(response) = inner.call(req).await;
+                            let (parts, body) = response.into_parts();
+                            match axum::body::to_bytes(body, MAX_DEDUP_RESPONSE_BYTES).await {
+                                Ok(body) => SharedResponse {
+                                    status: parts.status,
+                                    headers: parts.headers,
+                                    body,
+                                },
+                                Err(e) => {
+                                    tracing::warn!("[REQUEST_DEDUP] 读取上游响应失败: {}", e);
+                                    SharedResponse {
+                                        status: StatusCode::BAD_GATEWAY,
+                                        headers: HeaderMap::new(),
+                                        body: Bytes::from(format!(
+                                            "Failed to read upstream response: {e}"
+                                        )),
+                                    }
+                                }
+                            }
+                        }
+                        .boxed()
+                        .shared();
+                        entries.insert(
+                            key,
+                            Entry {
+                                id,
+                                call: call.clone(),
+                            },
+                        );
+                        let guard = LeaderGuard {
+                            deduplicator: deduplicator.clone(),
+                            key,
+                            id,
+                            keep_for_window: false,
+                        };
+                        (call, Some(guard))
+                    }
+                }
+            };
+
+            let shared = call.await;
+
+            match leader.as_mut() {
+                // 只有成功响应在窗口内复用
+                Some(guard) => guard.keep_for_window = shared.status.is_success(),
+                None => tracing::info!("[REQUEST_DEDUP] 相同请求复用进行中的上游调用"),
+            }
+
+            Ok(shared.to_response(leader.is_none()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// 统计调用次数、延迟返回的上游
+    #[derive(Clone)]
+    struct CountingService {
+        calls: Arc<AtomicUsize>,
+        status: StatusCode,
+    }
+
+    impl CountingService {
+        fn new(calls: &Arc<AtomicUsize>) -> Self {
+            Self {
+                calls: calls.clone(),
+                status: StatusCode::OK,
+            }
+        }
+    }
+
+    impl Service<Request<Body>> for CountingService {
+        type Response = Response<Body>;
+        type Error = Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let status = self.status;
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let mut response = Response::new(Body::from(format!("response-{n}")));
+                *response.status_mut() = status;
+                Ok(response)
+            })
+        }
+    }
+
+    fn request(body: &str) -> Request<Body> {
+        Request::post("/v1/chat/completions")
+            .header("authorization", "Bearer pc-test")
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn text(response: Response<Body>) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_requests_share_one_upstream_call() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let deduplicator = Arc::new(RequestDeduplicator::new(Duration::from_millis(100)));
+        let service = RequestDedupLayer::with_deduplicator(deduplicator.clone())
+            .layer(CountingService::new(&calls));
+
+        let body = r#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}]}"#;
+        let (first, second) = tokio::join!(
+            service.clone().call(request(body)),
+            service.clone().call(request(body))
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let deduplicated = [&first, &second]
+            .iter()
+            .filter(|r| r.headers().contains_key(DEDUP_HEADER))
+            .count();
+        assert_eq!(deduplicated, 1);
+        assert_eq!(text(first).await, "response-1");
+        assert_eq!(text(second).await, "response-1");
+
+        // 窗口结束后记录被清理，再次请求会调用上游
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(deduplicator.is_empty());
+        service.clone().call(request(body)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_and_different_requests_not_merged() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service =
+            RequestDedupLayer::new(Duration::from_millis(100)).layer(CountingService::new(&calls));
+
+        let stream = r#"{"model":"gpt-4o","stream":true,"messages":[]}"#;
+        let (a, b) = tokio::join!(
+            service.clone().call(request(stream)),
+            service.clone().call(request(stream))
+        );
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let (a, b) = tokio::join!(
+            service
+                .clone()
+                .call(request(r#"{"model":"gpt-4o","messages":[1]}"#)),
+            service
+                .clone()
+                .call(request(r#"{"model":"gpt-4o","messages":[2]}"#))
+        );
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_leader_removes_entry() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let deduplicator = Arc::new(RequestDeduplicator::new(Duration::from_secs(60)));
+        let service = RequestDedupLayer::with_deduplicator(deduplicator.clone())
+            .layer(CountingService::new(&calls));
+
+        let body = r#"{"model":"gpt-4o","messages":[]}"#;
+        // 客户端在上游返回前断开
+        let leader = service.clone().call(request(body));
+        assert!(tokio::time::timeout(Duration::from_millis(10), leader)
+            .await
+            .is_err());
+        assert!(deduplicator.is_empty());
+
+        let response = service.clone().call(request(body)).await.unwrap();
+        assert!(!response.headers().contains_key(DEDUP_HEADER));
+        assert_eq!(text(response).await, "response-2");
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_not_reused_after_completion() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let deduplicator = Arc::new(RequestDeduplicator::new(Duration::from_secs(60)));
+        let service =
+            RequestDedupLayer::with_deduplicator(deduplicator.clone()).layer(CountingService {
+                calls: calls.clone(),
+                status: StatusCode::SERVICE_UNAVAILABLE,
+            });
+
+        let body = r#"{"model":"gpt-4o","messages":[]}"#;
+        let (a, b) = tokio::join!(
+            service.clone().call(request(body)),
+            service.clone().call(request(body))
+        );
+        assert_eq!(a.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(b.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(deduplicator.is_empty());
+
+        service.clone().call(request(body)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,183 @@
+//! 请求 ID 中间件
+//!
+//! 为每个请求确定一个稳定的请求 ID，便于把客户端请求与服务端日志对应起来：
+//! - 客户端传入格式合法的 `X-Request-Id` 时沿用该值，否则生成 UUID
+//! - 确定后的 ID 写回请求头，处理器可通过 [`request_id_from_headers`] 读取
+//! - 响应中回显 `X-Request-Id`
+//! - 请求处理期间的日志都在带 `request_id` 字段的 tracing span 内输出
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Request, Response},
+};
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+/// 请求 ID 头名称
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 客户端请求 ID 的最大长度
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// 校验客户端传入的请求 ID
+///
+/// 只接受 1~128 个字符的 ASCII 字母、数字及 `-` `_` `.` `:`，
+/// 防止日志注入或超长值。
+pub fn normalize_request_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    let well_formed = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'));
+    well_formed.then(|| value.to_string())
+}
+
+/// 从请求头读取请求 ID（格式不合法时返回 `None`）
+pub fn request_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(normalize_request_id)
+}
+
+/// 请求 ID 层
+#[derive(Clone, Copy, Default)]
+pub struct RequestIdLayer;
+
+impl RequestIdLayer {
+    /// 创建请求 ID 层
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService { inner }
+    }
+}
+
+/// 请求 ID 服务
+#[derive(Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for RequestIdService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let request_id = request_id_from_headers(req.headers())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        // 已通过校验或为 UUID，一定是合法的头值
+        let header_value = HeaderValue::from_str(&request_id).expect("valid request id");
+        req.headers_mut()
+            .insert(REQUEST_ID_HEADER, header_value.clone());
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.uri().path()
+        );
+        let mut inner = self.inner.clone();
+
+        Box::pin(
+            async move {
+                let mut response = inner.call(req).await?;
+                response
+                    .headers_mut()
+                    .insert(REQUEST_ID_HEADER, header_value);
+                Ok(response)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 将处理器看到的请求 ID 放入响应体
+    #[derive(Clone)]
+    struct EchoService;
+
+    impl Service<Request<Body>> for EchoService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<Body>) -> Self::Future {
+            let seen = request_id_from_headers(req.headers()).unwrap_or_default();
+            Box::pin(async move { Ok(Response::new(Body::from(seen))) })
+        }
+    }
+
+    async fn call(request_id: Option<&str>) -> (String, String) {
+        let mut builder = Request::builder().uri("/v1/messages");
+        if let Some(id) = request_id {
+            builder = builder.header(REQUEST_ID_HEADER, id);
+        }
+        let mut service = RequestIdLayer::new().layer(EchoService);
+        let response = service
+            .call(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let header = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_client_request_id_round_trips() {
+        let (header, seen) = call(Some("client-req_42")).await;
+        assert_eq!(header, "client-req_42");
+        assert_eq!(seen, "client-req_42");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_when_absent_or_malformed() {
+        let (header, seen) = call(None).await;
+        assert!(uuid::Uuid::parse_str(&header).is_ok());
+        assert_eq!(seen, header);
+
+        let (header, _) = call(Some("bad id\twith spaces")).await;
+        assert!(uuid::Uuid::parse_str(&header).is_ok());
+    }
+
+    #[test]
+    fn test_normalize_request_id() {
+        assert_eq!(
+            normalize_request_id(" abc:1.2 "),
+            Some("abc:1.2".to_string())
+        );
+        assert_eq!(normalize_request_id(""), None);
+        assert_eq!(normalize_request_id(&"a".repeat(129)), None);
+        assert_eq!(normalize_request_id("a\nb"), None);
+    }
+}
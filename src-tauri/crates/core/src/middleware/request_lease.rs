@@ -0,0 +1,214 @@
+//! 请求级租约
+//!
+//! 请求处理过程中占用的资源（如凭证的并发名额）需要在请求真正结束时释放，
+//! 而流式响应在处理函数返回后仍在发送。[`RequestLeaseLayer`] 为每个请求
+//! 提供一个租约容器：
+//! - 处理期间通过 [`hold_for_request`] 把 RAII 守卫交给当前请求
+//! - 响应体发送完毕或被丢弃（客户端断开）时统一释放
+//!
+//! 不在请求处理上下文中（后台任务、WebSocket 等）时守卫原样返回，由调用方自行处理。
+
+use axum::{
+    body::Body,
+    http::{Request, Response},
+};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// 单个请求持有的租约（按键去重）
+#[derive(Default)]
+struct RequestLeases(Mutex<HashMap<String, Box<dyn Any + Send>>>);
+
+tokio::task_local! {
+    /// 当前请求的租约容器
+    static CURRENT_LEASES: Arc<RequestLeases>;
+}
+
+/// 把守卫交给当前请求，请求结束时释放
+///
+/// 同一请求内相同 `key` 只保留第一个守卫，后来的守卫随即释放。
+/// 不在请求处理上下文中时返回 `Err(guard)`。
+pub fn hold_for_request<G: Send + 'static>(key: &str, guard: G) -> Result<(), G> {
+    let Ok(leases) = CURRENT_LEASES.try_with(Arc::clone) else {
+        return Err(guard);
+    };
+    if let Ok(mut leases) = leases.0.lock() {
+        leases
+            .entry(key.to_string())
+            .or_insert_with(|| Box::new(guard));
+    }
+    Ok(())
+}
+
+/// 当前请求是否已持有指定键的租约（不在请求处理上下文中时为 false）
+pub fn is_held(key: &str) -> bool {
+    CURRENT_LEASES
+        .try_with(|leases| {
+            leases
+                .0
+                .lock()
+                .map(|leases| leases.contains_key(key))
+                .unwrap_or(false)
+        })
+        .unwrap_or(false)
+}
+
+/// 请求级租约层
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestLeaseLayer;
+
+impl RequestLeaseLayer {
+    /// 创建请求级租约层
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for RequestLeaseLayer {
+    type Service = RequestLeaseService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestLeaseService { inner }
+    }
+}
+
+/// 请求级租约服务
+#[derive(Clone)]
+pub struct RequestLeaseService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for RequestLeaseService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let leases = Arc::new(RequestLeases::default());
+
+        Box::pin(async move {
+            let response = CURRENT_LEASES
+                .scope(leases.clone(), async move { inner.call(req).await })
+                .await?;
+
+            // 租约随响应体释放
+            let (parts, body) = response.into_parts();
+            let body = body.into_data_stream().map(move |chunk| {
+                let _ = &leases;
+                chunk
+            });
+            Ok(Response::from_parts(parts, Body::from_stream(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use futures::stream;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// 释放时计数减一的守卫
+    struct CountGuard(Arc<AtomicU32>);
+
+    impl CountGuard {
+        fn acquire(count: &Arc<AtomicU32>) -> Self {
+            count.fetch_add(1, Ordering::SeqCst);
+            Self(count.clone())
+        }
+    }
+
+    impl Drop for CountGuard {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// 处理期间占用两次同一键的租约，响应体 50ms 后结束
+    #[derive(Clone)]
+    struct LeasingService(Arc<AtomicU32>);
+
+    impl Service<Request<Body>> for LeasingService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            let count = self.0.clone();
+            Box::pin(async move {
+                assert!(!is_held("cred-1"));
+                assert!(hold_for_request("cred-1", CountGuard::acquire(&count)).is_ok());
+                assert!(is_held("cred-1"));
+                assert!(hold_for_request("cred-1", CountGuard::acquire(&count)).is_ok());
+                let body = stream::once(async {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok::<_, std::io::Error>(Bytes::from_static(b"done"))
+                });
+                Ok(Response::new(Body::from_stream(body)))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_leases_released_when_body_completes() {
+        let count = Arc::new(AtomicU32::new(0));
+        let mut service = RequestLeaseLayer::new().layer(LeasingService(count.clone()));
+
+        let response = service
+            .call(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        // 处理函数已返回，但响应体尚未发送完毕；重复键的守卫已释放
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, Bytes::from("done"));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_leases_released_when_body_dropped() {
+        let count = Arc::new(AtomicU32::new(0));
+        let mut service = RequestLeaseLayer::new().layer(LeasingService(count.clone()));
+
+        let response = service
+            .call(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        drop(response);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_hold_outside_request_returns_guard() {
+        let count = Arc::new(AtomicU32::new(0));
+        let guard = hold_for_request("cred-1", CountGuard::acquire(&count));
+        assert!(guard.is_err());
+        drop(guard);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert!(!is_held("cred-1"));
+    }
+}
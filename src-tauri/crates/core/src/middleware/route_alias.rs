@@ -0,0 +1,232 @@
+//! 路由别名中间件
+//!
+//! 部分客户端会请求旧版或带厂商前缀的路径（如 `/v1/complete`、`/messages`），
+//! 通过配置 `server.route_aliases` 将这些路径映射到规范路由，无需改代码即可接入。
+//!
+//! 别名在路由匹配之前把请求路径改写为规范路径（保留查询参数），
+//! 因此必须包在整个 `Router` 外层，而不是通过 `Router::layer` 挂载。
+
+use axum::{
+    body::Body,
+    http::{uri::PathAndQuery, Request, Uri},
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// 允许作为别名目标的规范路由
+pub const ALIAS_TARGETS: &[&str] = &[
+    "/v1/messages",
+    "/v1/messages/count_tokens",
+    "/v1/chat/completions",
+    "/v1/models",
+];
+
+/// 服务器已注册的固定路由，别名不能覆盖
+const CANONICAL_ROUTES: &[&str] = &[
+    "/health",
+    "/v1/models",
+    "/v1/routes",
+    "/v1/chat/completions",
+    "/v1/messages",
+    "/v1/messages/count_tokens",
+    "/v1/images/generations",
+    "/v1/ws",
+    "/ws",
+];
+
+/// 服务器已注册的路由前缀，别名不能落在其下
+const CANONICAL_PREFIXES: &[&str] = &["/v0/management/", "/api/", "/v1/credentials/"];
+
+/// 多供应商路由 `/{selector}/...` 的后缀，别名不能与之冲突
+const SELECTOR_SUFFIXES: &[&str] = &["/v1/messages", "/v1/chat/completions"];
+
+/// 别名是否会覆盖已有的规范路由
+fn shadows_canonical_route(alias: &str) -> bool {
+    if CANONICAL_ROUTES.contains(&alias) {
+        return true;
+    }
+    if CANONICAL_PREFIXES.iter().any(|p| alias.starts_with(p)) {
+        return true;
+    }
+    // `/{selector}/v1/messages` 等：恰好一段前缀加规范后缀
+    SELECTOR_SUFFIXES.iter().any(|suffix| {
+        alias
+            .strip_suffix(suffix)
+            .and_then(|prefix| prefix.strip_prefix('/'))
+            .is_some_and(|selector| !selector.is_empty() && !selector.contains('/'))
+    })
+}
+
+/// 校验路由别名配置
+///
+/// - 别名必须是以 `/` 开头的合法路径，不含查询参数
+/// - 目标必须是 [`ALIAS_TARGETS`] 中的规范路由
+/// - 别名不能覆盖已有的规范路由
+pub fn validate_route_aliases(aliases: &HashMap<String, String>) -> Result<(), String> {
+    for (alias, target) in aliases {
+        if !alias.starts_with('/') || alias.contains(['?', '#']) {
+            return Err(format!("路由别名必须是以 / 开头的路径: {alias}"));
+        }
+        if alias.parse::<PathAndQuery>().is_err() {
+            return Err(format!("路由别名不是合法路径: {alias}"));
+        }
+        if !ALIAS_TARGETS.contains(&target.as_str()) {
+            return Err(format!(
+                "路由别名 {alias} 的目标 {target} 不受支持，可选: {}",
+                ALIAS_TARGETS.join(", ")
+            ));
+        }
+        if shadows_canonical_route(alias) {
+            return Err(format!("路由别名 {alias} 会覆盖已有的规范路由"));
+        }
+    }
+    Ok(())
+}
+
+/// 路由别名层
+#[derive(Clone, Default)]
+pub struct RouteAliasLayer {
+    aliases: Arc<HashMap<String, String>>,
+}
+
+impl RouteAliasLayer {
+    /// 使用已校验的别名表创建
+    pub fn new(aliases: HashMap<String, String>) -> Self {
+        Self {
+            aliases: Arc::new(aliases),
+        }
+    }
+
+    /// 已配置的别名数量
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    /// 是否没有配置别名
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+impl<S> Layer<S> for RouteAliasLayer {
+    type Service = RouteAliasService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RouteAliasService {
+            inner,
+            aliases: self.aliases.clone(),
+        }
+    }
+}
+
+/// 路由别名服务
+#[derive(Clone)]
+pub struct RouteAliasService<S> {
+    inner: S,
+    aliases: Arc<HashMap<String, String>>,
+}
+
+impl<S> Service<Request<Body>> for RouteAliasService<S>
+where
+    S: Service<Request<Body>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        if let Some(target) = self.aliases.get(req.uri().path()) {
+            if let Some(uri) = rewrite_path(req.uri(), target) {
+                tracing::debug!("[ROUTE_ALIAS] {} -> {}", req.uri().path(), target);
+                *req.uri_mut() = uri;
+            }
+        }
+        self.inner.call(req)
+    }
+}
+
+/// 替换 URI 的路径部分，保留查询参数
+fn rewrite_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post, Router};
+
+    fn aliases(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(a, t)| (a.to_string(), t.to_string()))
+            .collect()
+    }
+
+    async fn call(service: &mut RouteAliasService<Router>, uri: &str) -> (StatusCode, String) {
+        let req = Request::post(uri).body(Body::empty()).unwrap();
+        let response = service.call(req).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_aliased_path_reaches_canonical_handler() {
+        let router = Router::new().route(
+            "/v1/messages",
+            post(|uri: Uri| async move { format!("messages:{}", uri.query().unwrap_or("")) }),
+        );
+        let table = aliases(&[("/v1/complete", "/v1/messages")]);
+        validate_route_aliases(&table).unwrap();
+        let mut service = RouteAliasLayer::new(table).layer(router);
+
+        assert_eq!(
+            call(&mut service, "/v1/complete?beta=true").await,
+            (StatusCode::OK, "messages:beta=true".to_string())
+        );
+        // 规范路由保持可用
+        assert_eq!(
+            call(&mut service, "/v1/messages").await,
+            (StatusCode::OK, "messages:".to_string())
+        );
+        assert_eq!(
+            call(&mut service, "/v1/unknown").await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_validate_route_aliases() {
+        assert!(validate_route_aliases(&aliases(&[
+            ("/messages", "/v1/messages"),
+            ("/openai/chat/completions", "/v1/chat/completions"),
+        ]))
+        .is_ok());
+
+        // 覆盖规范路由
+        assert!(validate_route_aliases(&aliases(&[("/v1/models", "/v1/messages")])).is_err());
+        assert!(validate_route_aliases(&aliases(&[("/api/x", "/v1/messages")])).is_err());
+        assert!(
+            validate_route_aliases(&aliases(&[("/kiro/v1/messages", "/v1/messages")])).is_err()
+        );
+        // 目标不受支持
+        assert!(validate_route_aliases(&aliases(&[("/x", "/health")])).is_err());
+        // 非法路径
+        assert!(validate_route_aliases(&aliases(&[("complete", "/v1/messages")])).is_err());
+        assert!(validate_route_aliases(&aliases(&[("/a?b=1", "/v1/messages")])).is_err());
+    }
+}
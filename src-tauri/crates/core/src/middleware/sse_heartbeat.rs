@@ -0,0 +1,231 @@
+//! 流式响应心跳中间件
+//!
+//! 上游首 token 较慢时，SSE 连接长时间没有数据，客户端侧的代理或负载均衡器
+//! 可能把空闲连接断开。本中间件在等待第一个上游数据块期间按固定间隔发送心跳：
+//! - Anthropic `/v1/messages`：`event: ping`
+//! - OpenAI `/v1/chat/completions`：SSE 注释行 `: keep-alive`
+//!
+//! 收到第一个真实数据块后停止心跳；非 SSE 响应原样返回。
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, Request, Response},
+};
+use futures::future::BoxFuture;
+use futures::{stream, Stream, StreamExt};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+/// Anthropic ping 事件
+const ANTHROPIC_PING: &str = "event: ping\ndata: {\"type\": \"ping\"}\n\n";
+
+/// OpenAI 保活注释
+const OPENAI_KEEP_ALIVE: &str = ": keep-alive\n\n";
+
+/// 心跳格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartbeatKind {
+    /// Anthropic `event: ping`
+    AnthropicPing,
+    /// OpenAI SSE 注释行
+    OpenAiComment,
+}
+
+impl HeartbeatKind {
+    /// 根据请求路径确定心跳格式，非流式 API 路径返回 `None`
+    pub fn for_path(path: &str) -> Option<Self> {
+        if path.ends_with("/v1/messages") {
+            Some(HeartbeatKind::AnthropicPing)
+        } else if path.ends_with("/v1/chat/completions") {
+            Some(HeartbeatKind::OpenAiComment)
+        } else {
+            None
+        }
+    }
+
+    /// 心跳帧内容
+    pub fn frame(&self) -> &'static str {
+        match self {
+            HeartbeatKind::AnthropicPing => ANTHROPIC_PING,
+            HeartbeatKind::OpenAiComment => OPENAI_KEEP_ALIVE,
+        }
+    }
+}
+
+/// 在第一个数据块到达前，每隔 `interval` 插入一个心跳帧
+pub fn heartbeat_until_first_chunk<S, E>(
+    inner: S,
+    interval: Duration,
+    kind: HeartbeatKind,
+) -> impl Stream<Item = Result<Bytes, E>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Send + 'static,
+{
+    stream::unfold(
+        (inner.boxed(), false),
+        move |(mut inner, started)| async move {
+            if started {
+                return inner.next().await.map(|item| (item, (inner, true)));
+            }
+            // `next()` 可安全取消，超时不会丢失数据
+            match tokio::time::timeout(interval, inner.next()).await {
+                Ok(item) => item.map(|item| (item, (inner, true))),
+                Err(_) => Some((
+                    Ok(Bytes::from_static(kind.frame().as_bytes())),
+                    (inner, false),
+                )),
+            }
+        },
+    )
+}
+
+/// 流式响应心跳层
+#[derive(Debug, Clone, Copy)]
+pub struct SseHeartbeatLayer {
+    interval: Duration,
+}
+
+impl SseHeartbeatLayer {
+    /// 创建心跳层，`interval` 为 0 时不发送心跳
+    pub fn new(interval: Duration) -> Self {
+        Self { interval }
+    }
+}
+
+impl<S> Layer<S> for SseHeartbeatLayer {
+    type Service = SseHeartbeatService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SseHeartbeatService {
+            inner,
+            interval: self.interval,
+        }
+    }
+}
+
+/// 流式响应心跳服务
+#[derive(Clone)]
+pub struct SseHeartbeatService<S> {
+    inner: S,
+    interval: Duration,
+}
+
+impl<S> Service<Request<Body>> for SseHeartbeatService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let kind = HeartbeatKind::for_path(req.uri().path()).filter(|_| !self.interval.is_zero());
+        let interval = self.interval;
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            let Some(kind) = kind else {
+                return Ok(response);
+            };
+            let is_sse = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with("text/event-stream"));
+            if !is_sse {
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let body = Body::from_stream(heartbeat_until_first_chunk(
+                body.into_data_stream(),
+                interval,
+                kind,
+            ));
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 延迟 `delay` 后才输出第一个数据块的 SSE 服务
+    #[derive(Clone)]
+    struct SlowSseService {
+        delay: Duration,
+    }
+
+    impl Service<Request<Body>> for SlowSseService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            let delay = self.delay;
+            let chunks = stream::once(async move {
+                tokio::time::sleep(delay).await;
+                Ok::<_, std::io::Error>(Bytes::from_static(b"data: first\n\n"))
+            })
+            .chain(stream::iter(vec![Ok(Bytes::from_static(
+                b"data: second\n\n",
+            ))]));
+            Box::pin(async move {
+                Ok(Response::builder()
+                    .header(header::CONTENT_TYPE, "text/event-stream")
+                    .body(Body::from_stream(chunks))
+                    .unwrap())
+            })
+        }
+    }
+
+    async fn call(path: &str, interval: Duration) -> String {
+        let mut service = SseHeartbeatLayer::new(interval).layer(SlowSseService {
+            delay: Duration::from_millis(250),
+        });
+        let response = service
+            .call(Request::post(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_emitted_before_first_chunk() {
+        let body = call("/v1/messages", Duration::from_millis(50)).await;
+        let (before, after) = body.split_once("data: first").unwrap();
+        assert!(before.matches("event: ping").count() >= 2, "body: {body}");
+        assert_eq!(before.replace(ANTHROPIC_PING, ""), "");
+        // 真实数据开始后不再发送心跳
+        assert!(!after.contains("ping"), "body: {body}");
+
+        let body = call("/kiro/v1/chat/completions", Duration::from_millis(50)).await;
+        assert!(body.starts_with(OPENAI_KEEP_ALIVE), "body: {body}");
+        assert!(body.ends_with("data: first\n\ndata: second\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_disabled_or_not_applicable() {
+        let body = call("/v1/messages", Duration::ZERO).await;
+        assert_eq!(body, "data: first\n\ndata: second\n\n");
+
+        let body = call("/api/batch/tasks/1/events", Duration::from_millis(50)).await;
+        assert_eq!(body, "data: first\n\ndata: second\n\n");
+    }
+}
@@ -0,0 +1,445 @@
+//! 流式响应合并中间件
+//!
+//! 部分 Provider 每个 token 发送一个 SSE 事件，转发给客户端时帧开销很大。启用后
+//! 把连续的文本增量事件合并为一帧发出：
+//! - 缓冲达到 `max_bytes`，或自首个缓冲字节起超过 `max_ms` 时发出
+//! - 只合并已知的文本增量（Anthropic `text_delta` / `thinking_delta`、
+//!   未结束的 OpenAI 内容增量）和 SSE 注释行
+//! - 工具调用增量、结束事件及其他无法识别的事件连同之前缓冲的内容立即发出
+//!
+//! 合并只改变分帧方式，客户端重新拼接得到的字节与上游完全一致。
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, Request, Response},
+};
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{stream, Stream, StreamExt};
+use serde_json::Value;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+use tower::{Layer, Service};
+
+use crate::config::StreamCoalesceConfig;
+
+/// SSE 事件是否可以继续缓冲（文本增量或注释），否则需要立即发出
+pub fn is_coalescable_event(event: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(event);
+    let mut has_data = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(':') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("event:") {
+            if !matches!(name.trim(), "content_block_delta" | "ping") {
+                return false;
+            }
+            continue;
+        }
+        let Some(data) = line.strip_prefix("data:") else {
+            return false;
+        };
+        has_data = true;
+        let Ok(json) = serde_json::from_str::<Value>(data.trim()) else {
+            return false;
+        };
+        if !is_text_delta(&json) {
+            return false;
+        }
+    }
+    // 只有 `event:` 行而没有数据的事件不缓冲
+    has_data || !text.contains("event:")
+}
+
+/// 数据是否为文本增量
+fn is_text_delta(json: &Value) -> bool {
+    // Anthropic
+    if let Some(kind) = json.get("type").and_then(Value::as_str) {
+        return match kind {
+            "ping" => true,
+            "content_block_delta" => matches!(
+                json.pointer("/delta/type").and_then(Value::as_str),
+                Some("text_delta" | "thinking_delta")
+            ),
+            _ => false,
+        };
+    }
+    // OpenAI
+    let Some(choices) = json.get("choices").and_then(Value::as_array) else {
+        return false;
+    };
+    !choices.is_empty()
+        && json.get("usage").is_none_or(Value::is_null)
+        && choices.iter().all(|choice| {
+            choice.get("finish_reason").is_none_or(Value::is_null)
+                && choice
+                    .get("delta")
+                    .and_then(Value::as_object)
+                    .is_some_and(|delta| {
+                        delta.iter().all(|(key, value)| {
+                            matches!(key.as_str(), "role" | "content" | "reasoning_content")
+                                || value.is_null()
+                        })
+                    })
+        })
+}
+
+/// 下一个事件结束位置（含分隔空行），支持 `\n\n` 与 `\r\n\r\n`
+fn next_event_end(buf: &[u8], from: usize) -> Option<usize> {
+    let lf = buf[from..]
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .map(|i| from + i + 2);
+    let crlf = buf[from..]
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| from + i + 4);
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 合并状态
+struct Coalescer<E> {
+    inner: BoxStream<'static, Result<Bytes, E>>,
+    /// 已缓冲的字节
+    buf: Vec<u8>,
+    /// `buf` 中已解析的完整事件的结束位置
+    parsed: usize,
+    /// 缓冲中首个字节的发出截止时间
+    deadline: Option<Instant>,
+    /// 上游错误，先发出缓冲内容再返回
+    error: Option<E>,
+    finished: bool,
+    max_bytes: usize,
+    max_wait: Duration,
+}
+
+impl<E> Coalescer<E> {
+    /// 追加上游数据块，返回需要立即发出的字节数
+    fn push(&mut self, chunk: &[u8]) -> usize {
+        if self.buf.is_empty() {
+            self.deadline = Some(Instant::now() + self.max_wait);
+        }
+        self.buf.extend_from_slice(chunk);
+
+        let mut flush_to = 0;
+        while let Some(end) = next_event_end(&self.buf, self.parsed) {
+            if !is_coalescable_event(&self.buf[self.parsed..end]) {
+                flush_to = end;
+            }
+            self.parsed = end;
+        }
+        if self.buf.len() >= self.max_bytes {
+            // 完整事件全部发出；单个事件超过上限时整体发出
+            flush_to = if self.parsed > 0 {
+                self.parsed
+            } else {
+                self.buf.len()
+            };
+        }
+        flush_to
+    }
+
+    /// 取出前 `len` 个字节
+    fn take(&mut self, len: usize) -> Bytes {
+        let rest = self.buf.split_off(len);
+        let out = std::mem::replace(&mut self.buf, rest);
+        self.parsed = self.parsed.saturating_sub(len);
+        self.deadline = (!self.buf.is_empty()).then(|| Instant::now() + self.max_wait);
+        Bytes::from(out)
+    }
+
+    fn take_all(&mut self) -> Bytes {
+        self.take(self.buf.len())
+    }
+}
+
+/// 合并流式响应体中的文本增量事件
+pub fn coalesce_stream<S, E>(
+    inner: S,
+    max_bytes: usize,
+    max_wait: Duration,
+) -> impl Stream<Item = Result<Bytes, E>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Send + 'static,
+{
+    let state = Coalescer {
+        inner: inner.boxed(),
+        buf: Vec::new(),
+        parsed: 0,
+        deadline: None,
+        error: None,
+        finished: false,
+        max_bytes,
+        max_wait,
+    };
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(error) = state.error.take() {
+                state.finished = true;
+                return Some((Err(error), state));
+            }
+            if state.finished {
+                if state.buf.is_empty() {
+                    return None;
+                }
+                let out = state.take_all();
+                return Some((Ok(out), state));
+            }
+
+            // `next()` 可安全取消，超时不会丢失数据
+            let next = match state.deadline {
+                Some(deadline) => {
+                    match tokio::time::timeout_at(deadline, state.inner.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            let out = state.take_all();
+                            return Some((Ok(out), state));
+                        }
+                    }
+                }
+                None => state.inner.next().await,
+            };
+
+            match next {
+                Some(Ok(chunk)) => {
+                    let flush_to = state.push(&chunk);
+                    if flush_to > 0 {
+                        let out = state.take(flush_to);
+                        return Some((Ok(out), state));
+                    }
+                }
+                Some(Err(error)) => {
+                    state.error = Some(error);
+                    if !state.buf.is_empty() {
+                        let out = state.take_all();
+                        return Some((Ok(out), state));
+                    }
+                }
+                None => state.finished = true,
+            }
+        }
+    })
+}
+
+/// 流式响应合并层
+#[derive(Debug, Clone)]
+pub struct StreamCoalesceLayer {
+    config: StreamCoalesceConfig,
+}
+
+impl StreamCoalesceLayer {
+    /// 创建合并层，`config.enabled` 为 false 时响应原样返回
+    pub fn new(config: StreamCoalesceConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S> Layer<S> for StreamCoalesceLayer {
+    type Service = StreamCoalesceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StreamCoalesceService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// 流式响应合并服务
+#[derive(Clone)]
+pub struct StreamCoalesceService<S> {
+    inner: S,
+    config: StreamCoalesceConfig,
+}
+
+impl<S> Service<Request<Body>> for StreamCoalesceService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let enabled = self.config.enabled && self.config.max_bytes > 0 && self.config.max_ms > 0;
+        let max_bytes = self.config.max_bytes;
+        let max_wait = Duration::from_millis(self.config.max_ms);
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            if !enabled {
+                return Ok(response);
+            }
+            let is_sse = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with("text/event-stream"));
+            if !is_sse {
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let body = Body::from_stream(coalesce_stream(
+                body.into_data_stream(),
+                max_bytes,
+                max_wait,
+            ));
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anthropic_text(text: &str) -> String {
+        format!(
+            "event: content_block_delta\ndata: {{\"type\":\"content_block_delta\",\"index\":0,\"delta\":{{\"type\":\"text_delta\",\"text\":\"{text}\"}}}}\n\n"
+        )
+    }
+
+    fn openai_text(text: &str) -> String {
+        format!(
+            "data: {{\"id\":\"c1\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":\"{text}\"}},\"finish_reason\":null}}]}}\n\n"
+        )
+    }
+
+    /// 逐帧收集合并后的输出
+    async fn collect_frames(chunks: Vec<String>, max_bytes: usize, max_ms: u64) -> Vec<String> {
+        let upstream = stream::iter(
+            chunks
+                .into_iter()
+                .map(|c| Ok::<_, std::io::Error>(Bytes::from(c))),
+        );
+        coalesce_stream(upstream, max_bytes, Duration::from_millis(max_ms))
+            .map(|frame| String::from_utf8(frame.unwrap().to_vec()).unwrap())
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn test_coalescable_event_classification() {
+        assert!(is_coalescable_event(anthropic_text("Hi").as_bytes()));
+        assert!(is_coalescable_event(openai_text("Hi").as_bytes()));
+        assert!(is_coalescable_event(b": keep-alive\n\n"));
+        assert!(is_coalescable_event(
+            b"event: ping\ndata: {\"type\": \"ping\"}\n\n"
+        ));
+
+        // 工具调用增量
+        assert!(!is_coalescable_event(
+            b"event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"a\\\"\"}}\n\n"
+        ));
+        assert!(!is_coalescable_event(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\"}}]},\"finish_reason\":null}]}\n\n"
+        ));
+        // 结束事件
+        assert!(!is_coalescable_event(b"data: [DONE]\n\n"));
+        assert!(!is_coalescable_event(
+            b"event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"
+        ));
+        assert!(!is_coalescable_event(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n"
+        ));
+        // 无法识别的数据
+        assert!(!is_coalescable_event(b"data: not json\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_coalescing_reduces_frames_and_preserves_content() {
+        let mut chunks: Vec<String> = (0..50).map(|i| openai_text(&format!("t{i}"))).collect();
+        chunks.push(
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n"
+                .to_string(),
+        );
+        chunks.push("data: [DONE]\n\n".to_string());
+        let expected: String = chunks.concat();
+
+        let frames = collect_frames(chunks.clone(), 1024, 1000).await;
+        assert!(
+            frames.len() < chunks.len() / 4,
+            "frames: {} chunks: {}",
+            frames.len(),
+            chunks.len()
+        );
+        assert_eq!(frames.concat(), expected);
+        // 每帧都在事件边界上切分，且不超过上限太多
+        for frame in &frames {
+            assert!(frame.ends_with("\n\n"));
+            assert!(frame.len() < 1024 + openai_text("t00").len());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_and_terminal_events_flushed_immediately() {
+        let tool_start = "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"get_weather\",\"input\":{}}}\n\n".to_string();
+        let stop = "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n".to_string();
+        let chunks = vec![
+            anthropic_text("Hel"),
+            anthropic_text("lo"),
+            tool_start.clone(),
+            anthropic_text("after"),
+            stop.clone(),
+        ];
+        let expected: String = chunks.concat();
+
+        let frames = collect_frames(chunks, 64 * 1024, 60_000).await;
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].ends_with(&tool_start));
+        assert!(frames[1].ends_with(&stop));
+        assert_eq!(frames.concat(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_time_threshold_flushes_held_deltas() {
+        // 上游发出两个文本增量后停顿，合并层应在 max_ms 后发出，而不是等到流结束
+        let upstream = stream::iter([anthropic_text("a"), anthropic_text("b")])
+            .map(|c| Ok::<_, std::io::Error>(Bytes::from(c)))
+            .chain(stream::once(async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok(Bytes::from(anthropic_text("c")))
+            }));
+        let started = Instant::now();
+        let mut frames = Box::pin(coalesce_stream(
+            upstream,
+            64 * 1024,
+            Duration::from_millis(30),
+        ));
+
+        let first = frames.next().await.unwrap().unwrap();
+        assert_eq!(
+            first,
+            format!("{}{}", anthropic_text("a"), anthropic_text("b"))
+        );
+        assert!(started.elapsed() < Duration::from_millis(250));
+
+        let second = frames.next().await.unwrap().unwrap();
+        assert_eq!(second, anthropic_text("c"));
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_partial_events_are_reassembled() {
+        let event = openai_text("split");
+        let (a, b) = event.split_at(10);
+        let chunks = vec![a.to_string(), b.to_string(), "data: [DONE]\n\n".to_string()];
+
+        let frames = collect_frames(chunks, 1024, 1000).await;
+        assert_eq!(frames, vec![format!("{event}data: [DONE]\n\n")]);
+    }
+}
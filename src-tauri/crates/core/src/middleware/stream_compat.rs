@@ -0,0 +1,197 @@
+//! 流式响应代理兼容中间件
+//!
+//! 部分企业代理或反向代理会缓冲 SSE 响应，导致客户端迟迟收不到数据甚至超时。
+//! 启用后对所有 `text/event-stream` 响应：
+//! - 设置 `X-Accel-Buffering: no` 和 `Cache-Control: no-cache`
+//! - 在响应开头立即发送一个 SSE 注释行，可选填充到指定字节数，
+//!   用于冲刷中间代理的缓冲区
+//!
+//! SSE 注释行会被所有 SSE 客户端忽略，对 OpenAI / Anthropic / Gemini 格式均安全。
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderValue, Request, Response},
+};
+use futures::future::BoxFuture;
+use futures::{stream, StreamExt};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::config::StreamCompatConfig;
+
+/// 首个注释行的前缀
+const FLUSH_COMMENT: &str = ": proxycast-stream-start";
+
+/// 构造首个注释帧，`padding_bytes` 大于帧长度时用空格填充到该长度
+pub fn leading_flush_frame(padding_bytes: usize) -> Bytes {
+    // 注释内容 + 结尾的 "\n\n"
+    let base_len = FLUSH_COMMENT.len() + 2;
+    let padding = padding_bytes.saturating_sub(base_len);
+    Bytes::from(format!("{FLUSH_COMMENT}{}\n\n", " ".repeat(padding)))
+}
+
+/// 流式响应代理兼容层
+#[derive(Debug, Clone)]
+pub struct StreamCompatLayer {
+    config: StreamCompatConfig,
+}
+
+impl StreamCompatLayer {
+    /// 创建兼容层，`config.enabled` 为 false 时响应原样返回
+    pub fn new(config: StreamCompatConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S> Layer<S> for StreamCompatLayer {
+    type Service = StreamCompatService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StreamCompatService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// 流式响应代理兼容服务
+#[derive(Clone)]
+pub struct StreamCompatService<S> {
+    inner: S,
+    config: StreamCompatConfig,
+}
+
+impl<S> Service<Request<Body>> for StreamCompatService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let enabled = self.config.enabled;
+        let padding_bytes = self.config.padding_bytes;
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            if !enabled {
+                return Ok(response);
+            }
+            let is_sse = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with("text/event-stream"));
+            if !is_sse {
+                return Ok(response);
+            }
+
+            let (mut parts, body) = response.into_parts();
+            parts
+                .headers
+                .insert("x-accel-buffering", HeaderValue::from_static("no"));
+            parts
+                .headers
+                .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+            parts.headers.remove(header::CONTENT_LENGTH);
+
+            let flush = stream::once(async move { Ok(leading_flush_frame(padding_bytes)) });
+            let body = Body::from_stream(flush.chain(body.into_data_stream()));
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct SseService {
+        content_type: &'static str,
+    }
+
+    impl Service<Request<Body>> for SseService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            let content_type = self.content_type;
+            Box::pin(async move {
+                Ok(Response::builder()
+                    .header(header::CONTENT_TYPE, content_type)
+                    .header(header::CACHE_CONTROL, "no-store")
+                    .body(Body::from("data: first\n\n"))
+                    .unwrap())
+            })
+        }
+    }
+
+    async fn call(
+        config: StreamCompatConfig,
+        content_type: &'static str,
+    ) -> (Response<()>, String) {
+        let mut service = StreamCompatLayer::new(config).layer(SseService { content_type });
+        let response = service
+            .call(Request::post("/v1/messages").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (
+            Response::from_parts(parts, ()),
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_compat_headers_and_leading_flush_when_enabled() {
+        let config = StreamCompatConfig {
+            enabled: true,
+            padding_bytes: 2048,
+        };
+        let (response, body) = call(config, "text/event-stream").await;
+
+        let headers = response.headers();
+        assert_eq!(headers[header::CONTENT_TYPE], "text/event-stream");
+        assert_eq!(headers["x-accel-buffering"], "no");
+        assert_eq!(headers[header::CACHE_CONTROL], "no-cache");
+
+        let (flush, rest) = body.split_once("\n\n").unwrap();
+        assert!(flush.starts_with(": proxycast-stream-start"));
+        assert_eq!(flush.len() + 2, 2048);
+        assert_eq!(rest, "data: first\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_or_non_sse_unchanged() {
+        let (response, body) = call(StreamCompatConfig::default(), "text/event-stream").await;
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+        assert!(response.headers().get("x-accel-buffering").is_none());
+        assert_eq!(body, "data: first\n\n");
+
+        let config = StreamCompatConfig {
+            enabled: true,
+            padding_bytes: 0,
+        };
+        let (response, body) = call(config.clone(), "application/json").await;
+        assert!(response.headers().get("x-accel-buffering").is_none());
+        assert_eq!(body, "data: first\n\n");
+
+        // 不填充时只发送注释行
+        let (_, body) = call(config, "text/event-stream").await;
+        assert_eq!(body, ": proxycast-stream-start\n\ndata: first\n\n");
+    }
+}
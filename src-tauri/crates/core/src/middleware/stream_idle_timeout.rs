@@ -0,0 +1,331 @@
+//! 流式响应空闲超时中间件
+//!
+//! 上游在流式输出中途停止发送数据时，客户端会一直挂起。本中间件为 SSE 响应加一个
+//! 无进展看门狗：连续 `idle_timeout` 没有收到任何上游数据就丢弃上游流（中止上游连接），
+//! 向客户端发送一个终止错误帧并结束响应。每收到一个数据块都会重置计时，
+//! 因此缓慢但持续输出的流不受影响。
+//!
+//! - Anthropic `/v1/messages`：`event: error`，错误类型 `timeout_error`
+//! - OpenAI `/v1/chat/completions`：带 `error` 的 `data:` 帧，随后发送 `data: [DONE]`
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, Request, Response},
+};
+use futures::future::BoxFuture;
+use futures::{stream, Stream, StreamExt};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+use super::request_id::request_id_from_headers;
+
+/// 空闲超时事件
+#[derive(Debug, Clone)]
+pub struct StreamIdleTimeoutEvent {
+    /// 请求 ID（来自 `X-Request-Id`）
+    pub request_id: Option<String>,
+    /// 请求路径
+    pub path: String,
+    /// 触发超时的空闲时长
+    pub idle: Duration,
+}
+
+/// 空闲超时回调，用于记录遥测等
+///
+/// 回调在丢弃上游流之前调用，遥测可据此把随后的丢弃归类为超时而非客户端断开。
+pub type StreamIdleTimeoutHook = Arc<dyn Fn(&StreamIdleTimeoutEvent) + Send + Sync>;
+
+/// 流式协议格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Anthropic Messages SSE
+    Anthropic,
+    /// OpenAI Chat Completions SSE
+    OpenAi,
+}
+
+impl StreamFormat {
+    /// 根据请求路径确定流式格式，非流式 API 路径返回 `None`
+    pub fn for_path(path: &str) -> Option<Self> {
+        if path.ends_with("/v1/messages") {
+            Some(StreamFormat::Anthropic)
+        } else if path.ends_with("/v1/chat/completions") {
+            Some(StreamFormat::OpenAi)
+        } else {
+            None
+        }
+    }
+
+    /// 空闲超时的终止错误帧
+    pub fn timeout_frame(&self, idle: Duration) -> String {
+        let message = format!(
+            "Upstream stream stalled: no data received for {} ms",
+            idle.as_millis()
+        );
+        match self {
+            StreamFormat::Anthropic => {
+                let data = serde_json::json!({
+                    "type": "error",
+                    "error": {"type": "timeout_error", "message": message}
+                });
+                format!("event: error\ndata: {data}\n\n")
+            }
+            StreamFormat::OpenAi => {
+                let data = serde_json::json!({
+                    "error": {
+                        "message": message,
+                        "type": "timeout_error",
+                        "code": "stream_idle_timeout"
+                    }
+                });
+                format!("data: {data}\n\ndata: [DONE]\n\n")
+            }
+        }
+    }
+}
+
+/// 为流加上空闲看门狗
+///
+/// 每个数据块到达都会重置计时；连续 `idle_timeout` 没有数据时先调用 `on_timeout`，
+/// 再丢弃内部流，输出错误帧并结束流。
+pub fn idle_timeout_stream<S, E, F>(
+    inner: S,
+    idle_timeout: Duration,
+    format: StreamFormat,
+    on_timeout: F,
+) -> impl Stream<Item = Result<Bytes, E>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Send + 'static,
+    F: FnOnce() + Send + 'static,
+{
+    stream::unfold(Some((inner.boxed(), on_timeout)), move |state| async move {
+        let (mut inner, on_timeout) = state?;
+        match tokio::time::timeout(idle_timeout, inner.next()).await {
+            Ok(item) => item.map(|item| (item, Some((inner, on_timeout)))),
+            Err(_) => {
+                // 先通知再丢弃上游流（中止上游连接），丢弃时触发的遥测才能识别为超时
+                on_timeout();
+                drop(inner);
+                let frame = Bytes::from(format.timeout_frame(idle_timeout));
+                Some((Ok(frame), None))
+            }
+        }
+    })
+}
+
+/// 流式响应空闲超时层
+#[derive(Clone)]
+pub struct StreamIdleTimeoutLayer {
+    idle_timeout: Duration,
+    on_timeout: Option<StreamIdleTimeoutHook>,
+}
+
+impl StreamIdleTimeoutLayer {
+    /// 创建空闲超时层，`idle_timeout` 为 0 时不启用
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            on_timeout: None,
+        }
+    }
+
+    /// 设置超时回调
+    pub fn on_timeout(mut self, hook: StreamIdleTimeoutHook) -> Self {
+        self.on_timeout = Some(hook);
+        self
+    }
+}
+
+impl<S> Layer<S> for StreamIdleTimeoutLayer {
+    type Service = StreamIdleTimeoutService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StreamIdleTimeoutService {
+            inner,
+            idle_timeout: self.idle_timeout,
+            on_timeout: self.on_timeout.clone(),
+        }
+    }
+}
+
+/// 流式响应空闲超时服务
+#[derive(Clone)]
+pub struct StreamIdleTimeoutService<S> {
+    inner: S,
+    idle_timeout: Duration,
+    on_timeout: Option<StreamIdleTimeoutHook>,
+}
+
+impl<S> Service<Request<Body>> for StreamIdleTimeoutService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let path = req.uri().path().to_string();
+        // RequestIdLayer 在外层，已把请求 ID 写入请求头
+        let request_id = request_id_from_headers(req.headers());
+        let format = StreamFormat::for_path(&path).filter(|_| !self.idle_timeout.is_zero());
+        let idle_timeout = self.idle_timeout;
+        let on_timeout = self.on_timeout.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            let Some(format) = format else {
+                return Ok(response);
+            };
+            let is_sse = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with("text/event-stream"));
+            if !is_sse {
+                return Ok(response);
+            }
+
+            let event = StreamIdleTimeoutEvent {
+                request_id,
+                path,
+                idle: idle_timeout,
+            };
+            let (parts, body) = response.into_parts();
+            let body = Body::from_stream(idle_timeout_stream(
+                body.into_data_stream(),
+                idle_timeout,
+                format,
+                move || {
+                    tracing::warn!(
+                        "[STREAM] 上游流 {} ms 无数据，已中止: path={} request_id={:?}",
+                        event.idle.as_millis(),
+                        event.path,
+                        event.request_id
+                    );
+                    if let Some(hook) = on_timeout {
+                        hook(&event);
+                    }
+                },
+            ));
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// 按给定间隔输出数据块的 SSE 服务，`stall` 为真时输出完后不再结束
+    #[derive(Clone)]
+    struct MockSseService {
+        chunks: Vec<&'static str>,
+        interval: Duration,
+        stall: bool,
+    }
+
+    impl Service<Request<Body>> for MockSseService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            let interval = self.interval;
+            let chunks = stream::iter(self.chunks.clone()).then(move |chunk| async move {
+                tokio::time::sleep(interval).await;
+                Ok::<_, std::io::Error>(Bytes::from_static(chunk.as_bytes()))
+            });
+            let chunks = if self.stall {
+                chunks.chain(stream::pending()).boxed()
+            } else {
+                chunks.boxed()
+            };
+            Box::pin(async move {
+                Ok(Response::builder()
+                    .header(header::CONTENT_TYPE, "text/event-stream")
+                    .body(Body::from_stream(chunks))
+                    .unwrap())
+            })
+        }
+    }
+
+    async fn call(path: &str, upstream: MockSseService) -> (String, Vec<StreamIdleTimeoutEvent>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let layer = StreamIdleTimeoutLayer::new(Duration::from_millis(100)).on_timeout(Arc::new(
+            move |event: &StreamIdleTimeoutEvent| recorded.lock().unwrap().push(event.clone()),
+        ));
+        let response = layer
+            .layer(upstream)
+            .call(
+                Request::post(path)
+                    .header("x-request-id", "req-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = tokio::time::timeout(
+            Duration::from_secs(5),
+            axum::body::to_bytes(response.into_body(), usize::MAX),
+        )
+        .await
+        .expect("看门狗应结束挂起的流")
+        .unwrap();
+        let events = events.lock().unwrap().clone();
+        (String::from_utf8(body.to_vec()).unwrap(), events)
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_fires_when_upstream_goes_silent() {
+        let upstream = MockSseService {
+            chunks: vec!["event: message_start\ndata: {}\n\n"],
+            interval: Duration::ZERO,
+            stall: true,
+        };
+        let (body, events) = call("/v1/messages", upstream.clone()).await;
+        let (first, rest) = body.split_once("\n\n").unwrap();
+        assert_eq!(first, "event: message_start\ndata: {}");
+        assert!(rest.starts_with("event: error\n"), "body: {body}");
+        assert!(rest.contains("timeout_error"));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].request_id.as_deref(), Some("req-1"));
+        assert_eq!(events[0].path, "/v1/messages");
+
+        let (body, events) = call("/v1/chat/completions", upstream).await;
+        assert!(
+            body.contains("\"code\":\"stream_idle_timeout\""),
+            "body: {body}"
+        );
+        assert!(body.ends_with("data: [DONE]\n\n"));
+        assert_eq!(events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_trickle_resets_timer() {
+        // 总耗时超过超时时间，但每个数据块间隔都小于超时时间
+        let upstream = MockSseService {
+            chunks: vec!["data: 1\n\n", "data: 2\n\n", "data: 3\n\n", "data: 4\n\n"],
+            interval: Duration::from_millis(50),
+            stall: false,
+        };
+        let (body, events) = call("/v1/chat/completions", upstream).await;
+        assert_eq!(body, "data: 1\n\ndata: 2\n\ndata: 3\n\ndata: 4\n\n");
+        assert!(events.is_empty());
+    }
+}
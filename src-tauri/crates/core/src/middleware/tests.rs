@@ -91,6 +91,7 @@ fn test_management_auth_rate_limit_after_failures() {
         allow_remote: true,
         secret_key: Some("valid_key".to_string()),
         disable_control_panel: false,
+        ..Default::default()
     };
     let layer = ManagementAuthLayer::new(config);
     let mut service = layer.layer(MockService);
@@ -140,6 +141,7 @@ proptest! {
             allow_remote: true,
             secret_key: Some(secret_key),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -158,6 +160,7 @@ proptest! {
             allow_remote: true,
             secret_key: Some(secret_key.clone()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -177,6 +180,7 @@ proptest! {
             allow_remote: true,
             secret_key: Some(secret_key.clone()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -195,6 +199,7 @@ proptest! {
             allow_remote: true,
             secret_key: Some(secret_key.clone()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -213,6 +218,7 @@ proptest! {
             allow_remote: true,
             secret_key: Some(secret_key.clone()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -234,6 +240,7 @@ mod unit_tests {
             allow_remote: true,
             secret_key: Some("test-secret-key".to_string()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -248,6 +255,7 @@ mod unit_tests {
             allow_remote: true,
             secret_key: Some("correct-key".to_string()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -262,6 +270,7 @@ mod unit_tests {
             allow_remote: true,
             secret_key: Some("correct-key".to_string()),
             disable_control_panel: false,
+            ..Default::default()
         };
         let layer = ManagementAuthLayer::new(config);
         let mut service = layer.layer(MockService);
@@ -269,4 +278,110 @@ mod unit_tests {
         let response = service.call(req).await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    /// 构造带 HMAC 签名的请求（来自独立的远程地址，避免与其他测试共享限速状态）
+    fn signed_request(
+        ip: &str,
+        timestamp: i64,
+        signed_body: &str,
+        sent_body: &str,
+    ) -> Request<Body> {
+        use crate::middleware::management_auth::{
+            sign_request, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+        };
+
+        let path = "/v0/management/config?reload=true";
+        let timestamp = timestamp.to_string();
+        let signature = sign_request(
+            "signing-secret",
+            "PUT",
+            path,
+            &timestamp,
+            signed_body.as_bytes(),
+        );
+        let mut req = Request::builder()
+            .method("PUT")
+            .uri(path)
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::from(sent_body.to_string()))
+            .unwrap();
+        let addr: SocketAddr = format!("{ip}:12345").parse().unwrap();
+        req.extensions_mut().insert(ConnectInfo(addr));
+        req
+    }
+
+    fn signing_service(
+    ) -> impl Service<Request<Body>, Response = Response<Body>, Error = std::convert::Infallible>
+    {
+        let config = RemoteManagementConfig {
+            allow_remote: true,
+            secret_key: Some("signing-secret".to_string()),
+            require_signature: true,
+            signature_max_skew_secs: 300,
+            ..Default::default()
+        };
+        ManagementAuthLayer::new(config).layer(MockService)
+    }
+
+    #[tokio::test]
+    async fn test_signature_acceptance_valid() {
+        let mut service = signing_service();
+        let now = chrono::Utc::now().timestamp();
+        let req = signed_request("203.0.113.10", now, r#"{"a":1}"#, r#"{"a":1}"#);
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 启用签名后单独的 Bearer 密钥不再有效
+        let mut req = create_request_with_auth(Some("Bearer signing-secret"));
+        let addr: SocketAddr = "203.0.113.10:12345".parse().unwrap();
+        req.extensions_mut().insert(ConnectInfo(addr));
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_signature_rejection_tampered_body() {
+        let mut service = signing_service();
+        let now = chrono::Utc::now().timestamp();
+        let req = signed_request("203.0.113.11", now, r#"{"a":1}"#, r#"{"a":2}"#);
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_signature_rejection_replayed_request() {
+        let mut service = signing_service();
+        let now = chrono::Utc::now().timestamp();
+        let req = signed_request("203.0.113.13", now, r#"{"a":1}"#, r#"{"a":1}"#);
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 时间窗口内重放同一个签名请求被拒绝
+        let req = signed_request("203.0.113.13", now, r#"{"a":1}"#, r#"{"a":1}"#);
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("already used"));
+
+        // 时间戳不同的新请求不受影响
+        let req = signed_request("203.0.113.13", now - 1, r#"{"a":1}"#, r#"{"a":1}"#);
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_signature_rejection_expired_timestamp() {
+        let mut service = signing_service();
+        let stale = chrono::Utc::now().timestamp() - 301;
+        let req = signed_request("203.0.113.12", stale, r#"{"a":1}"#, r#"{"a":1}"#);
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("expired"));
+    }
 }
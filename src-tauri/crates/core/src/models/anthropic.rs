@@ -28,12 +28,75 @@ pub enum AnthropicContentBlock {
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ImageSource {
     #[serde(rename = "type")]
     pub source_type: String,
+    /// `base64` 来源的 MIME 类型
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub media_type: String,
+    /// `base64` 来源的图片数据
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub data: String,
+    /// `url` 来源的图片地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl ImageSource {
+    /// 转为 OpenAI `image_url` 可用的地址：`url` 来源原样返回，`base64` 来源转为 data URL
+    pub fn to_url(&self) -> String {
+        match &self.url {
+            Some(url) if self.source_type == "url" => url.clone(),
+            _ => format!("data:{};base64,{}", self.media_type, self.data),
+        }
+    }
+}
+
+/// `tool_result` 块的内容
+///
+/// `content` 可以是字符串，也可以是 `text` / `image` 块数组（如浏览器工具返回的截图），
+/// 解析后分别收集文本与图片，供需要转换格式的 Provider 使用。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolResultContent {
+    /// 按换行拼接的文本内容
+    pub text: String,
+    /// 图片内容，按出现顺序排列
+    pub images: Vec<ImageSource>,
+}
+
+impl ToolResultContent {
+    /// 从 `tool_result.content` 解析
+    pub fn from_value(content: Option<&serde_json::Value>) -> Self {
+        let mut result = Self::default();
+        match content {
+            Some(serde_json::Value::String(text)) => result.text = text.clone(),
+            Some(serde_json::Value::Array(blocks)) => {
+                let mut texts = Vec::new();
+                for block in blocks {
+                    match block.get("type").and_then(|t| t.as_str()) {
+                        Some("text") => {
+                            if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                                texts.push(text);
+                            }
+                        }
+                        Some("image") => {
+                            if let Some(source) = block
+                                .get("source")
+                                .and_then(|s| ImageSource::deserialize(s).ok())
+                            {
+                                result.images.push(source);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                result.text = texts.join("\n");
+            }
+            _ => {}
+        }
+        result
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +114,94 @@ pub struct AnthropicTool {
     pub input_schema: Option<serde_json::Value>,
 }
 
+/// System prompt
+///
+/// Anthropic 允许 `system` 为字符串或内容块数组（可带 `cache_control`），
+/// 两种形式都反序列化为此类型，序列化时按原形式输出，转发给 Claude 时保留缓存标记。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AnthropicSystemPrompt {
+    /// 字符串形式
+    Text(String),
+    /// 内容块数组形式
+    Blocks(Vec<AnthropicSystemBlock>),
+}
+
+/// System prompt 内容块
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnthropicSystemBlock {
+    #[serde(rename = "type", default = "default_system_block_type")]
+    pub block_type: String,
+    #[serde(default)]
+    pub text: String,
+    /// 提示缓存标记，如 `{"type": "ephemeral"}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<serde_json::Value>,
+}
+
+fn default_system_block_type() -> String {
+    "text".to_string()
+}
+
+impl AnthropicSystemPrompt {
+    /// 合并后的纯文本（数组形式按换行拼接 `text` 块），用于不支持内容块的 Provider
+    pub fn text(&self) -> String {
+        match self {
+            AnthropicSystemPrompt::Text(text) => text.clone(),
+            AnthropicSystemPrompt::Blocks(blocks) => blocks
+                .iter()
+                .filter(|b| b.block_type == "text")
+                .map(|b| b.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// 统一为内容块数组，字符串形式转为单个无缓存标记的 `text` 块
+    pub fn blocks(&self) -> Vec<AnthropicSystemBlock> {
+        match self {
+            AnthropicSystemPrompt::Text(text) => vec![AnthropicSystemBlock {
+                block_type: default_system_block_type(),
+                text: text.clone(),
+                cache_control: None,
+            }],
+            AnthropicSystemPrompt::Blocks(blocks) => blocks.clone(),
+        }
+    }
+
+    /// 是否有内容块带缓存标记
+    pub fn has_cache_control(&self) -> bool {
+        matches!(self, AnthropicSystemPrompt::Blocks(blocks)
+            if blocks.iter().any(|b| b.cache_control.is_some()))
+    }
+}
+
+impl From<String> for AnthropicSystemPrompt {
+    fn from(text: String) -> Self {
+        AnthropicSystemPrompt::Text(text)
+    }
+}
+
+/// Extended Thinking 配置
+///
+/// 对应请求中的 `"thinking": {"type": "enabled", "budget_tokens": 10000}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnthropicThinkingConfig {
+    /// `enabled` 或 `disabled`
+    #[serde(rename = "type")]
+    pub thinking_type: String,
+    /// 思考 token 预算
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget_tokens: Option<u32>,
+}
+
+impl AnthropicThinkingConfig {
+    /// 是否启用思考
+    pub fn is_enabled(&self) -> bool {
+        self.thinking_type == "enabled"
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicMessagesRequest {
     pub model: String,
@@ -58,7 +209,7 @@ pub struct AnthropicMessagesRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub system: Option<serde_json::Value>,
+    pub system: Option<AnthropicSystemPrompt>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(default)]
@@ -67,8 +218,35 @@ pub struct AnthropicMessagesRequest {
     pub tools: Option<Vec<AnthropicTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<serde_json::Value>,
+    /// Extended Thinking 配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<AnthropicThinkingConfig>,
+    /// 请求元数据（是否转发由配置 anthropic.forward_user_metadata 决定）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<AnthropicMetadata>,
 }
 
+/// Anthropic 请求元数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AnthropicMetadata {
+    /// 终端用户的不透明标识（UUID 或哈希值，不应包含个人信息）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+}
+
+impl AnthropicMessagesRequest {
+    /// 启用思考时的 token 预算
+    pub fn thinking_budget(&self) -> Option<u32> {
+        self.thinking
+            .as_ref()
+            .filter(|t| t.is_enabled())
+            .map(|t| t.budget_tokens.unwrap_or(DEFAULT_THINKING_BUDGET))
+    }
+}
+
+/// 未指定 `budget_tokens` 时的默认思考预算
+pub const DEFAULT_THINKING_BUDGET: u32 = 16000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicUsage {
     pub input_tokens: u32,
@@ -140,3 +318,154 @@ pub enum AnthropicDelta {
 pub struct AnthropicMessageDelta {
     pub stop_reason: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_thinking_request_and_response_roundtrip() {
+        let body = json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 20000,
+            "thinking": {"type": "enabled", "budget_tokens": 10000},
+            "messages": [
+                {"role": "user", "content": "2+2=?"},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "简单加法", "signature": "sig"},
+                    {"type": "text", "text": "4"}
+                ]}
+            ]
+        });
+        let request: AnthropicMessagesRequest = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(request.thinking_budget(), Some(10000));
+        // 请求原样转发给 Claude 时保留 thinking 配置与 thinking 块
+        let forwarded = serde_json::to_value(&request).unwrap();
+        assert_eq!(forwarded["thinking"], body["thinking"]);
+        assert_eq!(forwarded["messages"][1], body["messages"][1]);
+
+        let response: AnthropicMessagesResponse = serde_json::from_value(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": [
+                {"type": "thinking", "thinking": "简单加法", "signature": "sig"},
+                {"type": "text", "text": "4"}
+            ],
+            "model": "claude-sonnet-4-5",
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        }))
+        .unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["content"][0]["type"], "thinking");
+        assert_eq!(json["content"][0]["thinking"], "简单加法");
+        assert_eq!(json["content"][0]["signature"], "sig");
+
+        let disabled: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "thinking": {"type": "disabled"},
+            "messages": []
+        }))
+        .unwrap();
+        assert_eq!(disabled.thinking_budget(), None);
+    }
+
+    #[test]
+    fn test_system_prompt_string_form_roundtrip() {
+        let body = json!({"model": "m", "system": "你是助手", "messages": []});
+        let request: AnthropicMessagesRequest = serde_json::from_value(body.clone()).unwrap();
+        let system = request.system.as_ref().unwrap();
+        assert_eq!(system, &AnthropicSystemPrompt::Text("你是助手".to_string()));
+        assert_eq!(system.text(), "你是助手");
+        assert_eq!(system.blocks()[0].text, "你是助手");
+        assert!(!system.has_cache_control());
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["system"],
+            body["system"]
+        );
+    }
+
+    #[test]
+    fn test_system_prompt_array_form_roundtrip() {
+        let body = json!({
+            "model": "m",
+            "system": [
+                {"type": "text", "text": "第一段"},
+                {"type": "text", "text": "第二段"}
+            ],
+            "messages": []
+        });
+        let request: AnthropicMessagesRequest = serde_json::from_value(body.clone()).unwrap();
+        let system = request.system.as_ref().unwrap();
+        assert_eq!(system.text(), "第一段\n第二段");
+        assert_eq!(system.blocks().len(), 2);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["system"],
+            body["system"]
+        );
+    }
+
+    #[test]
+    fn test_system_prompt_cache_control_preserved() {
+        let body = json!({
+            "model": "m",
+            "system": [
+                {"type": "text", "text": "长上下文", "cache_control": {"type": "ephemeral"}},
+                {"type": "text", "text": "动态部分"}
+            ],
+            "messages": []
+        });
+        let request: AnthropicMessagesRequest = serde_json::from_value(body.clone()).unwrap();
+        let system = request.system.as_ref().unwrap();
+        assert!(system.has_cache_control());
+        assert_eq!(
+            system.blocks()[0].cache_control,
+            Some(json!({"type": "ephemeral"}))
+        );
+        // 转发给 Claude 时按数组形式重建，缓存标记不丢失
+        let forwarded = serde_json::to_value(&request).unwrap();
+        assert_eq!(forwarded["system"], body["system"]);
+        assert!(forwarded["system"][1].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn test_tool_result_image_content_preserved() {
+        let tool_result = json!({
+            "type": "tool_result",
+            "tool_use_id": "toolu_1",
+            "content": [
+                {"type": "text", "text": "截图如下"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0"}}
+            ]
+        });
+        let body = json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": [tool_result.clone()]}]
+        });
+        // 原生路径转发给 Claude 时图片保留在 tool_result 内
+        let request: AnthropicMessagesRequest = serde_json::from_value(body.clone()).unwrap();
+        let forwarded = serde_json::to_value(&request).unwrap();
+        assert_eq!(forwarded["messages"], body["messages"]);
+
+        let block: AnthropicContentBlock = serde_json::from_value(tool_result.clone()).unwrap();
+        let AnthropicContentBlock::ToolResult { content, .. } = block else {
+            panic!("应解析为 tool_result");
+        };
+        let parsed = ToolResultContent::from_value(Some(&content));
+        assert_eq!(parsed.text, "截图如下");
+        assert_eq!(parsed.images.len(), 1);
+        assert_eq!(parsed.images[0].to_url(), "data:image/png;base64,iVBORw0");
+        assert_eq!(
+            serde_json::to_value(&parsed.images[0]).unwrap(),
+            tool_result["content"][1]["source"]
+        );
+
+        let url_image = ToolResultContent::from_value(Some(&json!([
+            {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}}
+        ])));
+        assert_eq!(url_image.images[0].to_url(), "https://example.com/a.png");
+        assert_eq!(ToolResultContent::from_value(Some(&json!("ok"))).text, "ok");
+    }
+}
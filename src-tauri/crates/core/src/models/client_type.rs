@@ -4,6 +4,8 @@
 
 #![allow(dead_code)]
 
+use crate::config::{ClientDetectionConfig, ClientSignatureRule};
+use axum::http::HeaderMap;
 use serde::{Deserialize, Serialize};
 
 /// 客户端类型枚举
@@ -88,6 +90,98 @@ impl std::fmt::Display for ClientType {
     }
 }
 
+/// 客户端识别结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedClient {
+    /// 客户端 ID（自定义规则的 ID，或内置客户端类型的配置键名）
+    pub client_id: String,
+    /// 用于选择端点 Provider 的客户端类型
+    pub client_type: ClientType,
+    /// 请求未指定模型时使用的模型
+    pub default_model: Option<String>,
+    /// 仅应用的参数注入规则 ID（为空表示应用全部规则）
+    pub injection_rules: Vec<String>,
+}
+
+impl DetectedClient {
+    /// 按内置客户端类型构造（无附加行为）
+    pub fn builtin(client_type: ClientType) -> Self {
+        Self {
+            client_id: client_type.config_key().to_string(),
+            client_type,
+            default_model: None,
+            injection_rules: Vec::new(),
+        }
+    }
+
+    /// 请求未指定模型（空或 "default"）时返回应使用的模型
+    pub fn model_fallback(&self, requested: &str) -> Option<&str> {
+        let requested = requested.trim();
+        if requested.is_empty() || requested.eq_ignore_ascii_case("default") {
+            self.default_model.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// 参数注入规则是否适用于该客户端
+    pub fn allows_injection_rule(&self, rule_id: &str) -> bool {
+        self.injection_rules.is_empty() || self.injection_rules.iter().any(|id| id == rule_id)
+    }
+}
+
+/// 可配置的客户端识别器
+///
+/// 先按顺序匹配配置的规则，未命中时回退到 [`ClientType::from_user_agent`]。
+#[derive(Debug, Clone, Default)]
+pub struct ClientDetector {
+    rules: Vec<ClientSignatureRule>,
+}
+
+impl ClientDetector {
+    pub fn from_config(config: &ClientDetectionConfig) -> Self {
+        let rules = config
+            .rules
+            .iter()
+            .filter(|rule| !rule.id.trim().is_empty() && !rule.pattern.is_empty())
+            .map(|rule| ClientSignatureRule {
+                header: rule.header.to_lowercase(),
+                pattern: rule.pattern.to_lowercase(),
+                ..rule.clone()
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// 从请求头识别客户端
+    pub fn detect(&self, headers: &HeaderMap) -> DetectedClient {
+        for rule in &self.rules {
+            let matched = headers
+                .get_all(rule.header.as_str())
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .any(|v| v.to_lowercase().contains(&rule.pattern));
+            if matched {
+                let client_type =
+                    ClientType::from_config_key(rule.client_type.as_deref().unwrap_or(&rule.id))
+                        .unwrap_or(ClientType::Other);
+                return DetectedClient {
+                    client_id: rule.id.clone(),
+                    client_type,
+                    default_model: rule.default_model.clone(),
+                    injection_rules: rule.injection_rules.clone(),
+                };
+            }
+        }
+
+        let user_agent = headers
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        DetectedClient::builtin(ClientType::from_user_agent(user_agent))
+    }
+}
+
 /// 根据客户端类型和端点配置选择 Provider
 pub fn select_provider(
     _client_type: ClientType,
@@ -156,6 +250,76 @@ mod tests {
         assert_eq!(ClientType::all().len(), 6);
     }
 
+    fn detector() -> ClientDetector {
+        ClientDetector::from_config(&ClientDetectionConfig {
+            rules: vec![
+                ClientSignatureRule {
+                    id: "cline".to_string(),
+                    header: "User-Agent".to_string(),
+                    pattern: "Cline/".to_string(),
+                    client_type: None,
+                    default_model: Some("claude-sonnet-4-5".to_string()),
+                    injection_rules: vec!["cline-temperature".to_string()],
+                },
+                // 覆盖内置识别：带特定请求头的 Cursor 请求按 Claude Code 处理
+                ClientSignatureRule {
+                    id: "cursor-agent".to_string(),
+                    header: "x-client-name".to_string(),
+                    pattern: "cursor-agent".to_string(),
+                    client_type: Some("claude_code".to_string()),
+                    default_model: None,
+                    injection_rules: Vec::new(),
+                },
+            ],
+        })
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_detector_maps_configured_signature_to_client_id() {
+        let detector = detector();
+
+        let cline = detector.detect(&headers(&[("user-agent", "cline/3.2 (vscode)")]));
+        assert_eq!(cline.client_id, "cline");
+        assert_eq!(cline.client_type, ClientType::Other);
+        assert_eq!(cline.model_fallback(""), Some("claude-sonnet-4-5"));
+        assert_eq!(cline.model_fallback("default"), Some("claude-sonnet-4-5"));
+        assert_eq!(cline.model_fallback("gpt-4o"), None);
+        assert!(cline.allows_injection_rule("cline-temperature"));
+        assert!(!cline.allows_injection_rule("global-max-tokens"));
+
+        let overridden = detector.detect(&headers(&[
+            ("user-agent", "Cursor/1.0"),
+            ("x-client-name", "Cursor-Agent"),
+        ]));
+        assert_eq!(overridden.client_id, "cursor-agent");
+        assert_eq!(overridden.client_type, ClientType::ClaudeCode);
+    }
+
+    #[test]
+    fn test_detector_falls_back_to_builtin_detection() {
+        let detector = detector();
+
+        let cursor = detector.detect(&headers(&[("user-agent", "Cursor/1.0")]));
+        assert_eq!(cursor, DetectedClient::builtin(ClientType::Cursor));
+
+        let unknown = detector.detect(&headers(&[("user-agent", "curl/8.0")]));
+        assert_eq!(unknown.client_id, "other");
+        assert_eq!(unknown.client_type, ClientType::Other);
+        assert_eq!(unknown.model_fallback(""), None);
+        assert!(unknown.allows_injection_rule("any-rule"));
+
+        let empty = ClientDetector::default().detect(&HeaderMap::new());
+        assert_eq!(empty, DetectedClient::builtin(ClientType::Other));
+    }
+
     #[test]
     fn test_serialization() {
         let json = serde_json::to_string(&ClientType::Cursor).unwrap();
@@ -14,6 +14,7 @@ pub mod model_registry;
 pub mod openai;
 pub mod project_model;
 pub mod prompt_model;
+pub mod provider_capabilities;
 pub mod provider_model;
 pub mod provider_pool_model;
 pub mod provider_type;
@@ -33,6 +34,7 @@ pub use mcp_model::McpServer;
 pub use openai::*;
 pub use project_model::Persona;
 pub use prompt_model::Prompt;
+pub use provider_capabilities::{Capability, CapabilityError, ProviderCapabilities};
 pub use provider_model::Provider;
 #[allow(unused_imports)]
 pub use provider_pool_model::*;
@@ -382,6 +382,68 @@ pub struct ModelSyncState {
     pub last_error: Option<String>,
 }
 
+/// 模型列表读取结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelListSnapshot<T = EnhancedModelMetadata> {
+    pub models: Vec<T>,
+    /// 注册表读取失败时为 true，表示返回的是上次成功读取的缓存
+    pub stale: bool,
+}
+
+/// 最近一次成功读取的模型列表
+///
+/// 注册表读取成功时刷新缓存；读取失败（如数据库被短暂锁住）时返回缓存并标记 `stale`，
+/// 避免模型发现功能随之不可用。从未成功读取过时才返回错误。
+///
+/// 默认缓存完整的模型元数据；只需要部分字段的调用方（如 `/v1/models` 只用
+/// `(模型 ID, Provider ID)`）可以指定其他元素类型。
+#[derive(Debug)]
+pub struct LastKnownModels<T = EnhancedModelMetadata> {
+    models: parking_lot::RwLock<Option<Vec<T>>>,
+}
+
+impl<T> Default for LastKnownModels<T> {
+    fn default() -> Self {
+        Self {
+            models: parking_lot::RwLock::new(None),
+        }
+    }
+}
+
+impl<T: Clone> LastKnownModels<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 直接更新缓存（如从内嵌资源加载成功后）
+    pub fn update(&self, models: Vec<T>) {
+        *self.models.write() = Some(models);
+    }
+
+    /// 根据注册表读取结果返回模型列表
+    pub fn resolve(&self, read: Result<Vec<T>, String>) -> Result<ModelListSnapshot<T>, String> {
+        match read {
+            Ok(models) => {
+                self.update(models.clone());
+                Ok(ModelListSnapshot {
+                    models,
+                    stale: false,
+                })
+            }
+            Err(e) => match self.models.read().as_ref() {
+                Some(models) => {
+                    tracing::warn!("[ModelRegistry] 读取模型列表失败，使用缓存: {}", e);
+                    Ok(ModelListSnapshot {
+                        models: models.clone(),
+                        stale: true,
+                    })
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
 // ============================================================================
 // Provider Alias 相关类型（用于 Kiro、Antigravity 等中转服务）
 // ============================================================================
@@ -630,6 +692,35 @@ fn infer_model_tier(model_id: &str, model_name: &str) -> ModelTier {
 mod tests {
     use super::*;
 
+    fn model(id: &str) -> EnhancedModelMetadata {
+        EnhancedModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "anthropic".to_string(),
+            "Anthropic".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_last_known_models_serves_stale_cache_on_read_error() {
+        let cache = LastKnownModels::new();
+
+        // 从未成功读取时直接返回错误
+        assert!(cache
+            .resolve(Err("database is locked".to_string()))
+            .is_err());
+
+        let fresh = cache.resolve(Ok(vec![model("claude-sonnet-4-5")])).unwrap();
+        assert!(!fresh.stale);
+
+        let stale = cache
+            .resolve(Err("database is locked".to_string()))
+            .unwrap();
+        assert!(stale.stale);
+        assert_eq!(stale.models.len(), 1);
+        assert_eq!(stale.models[0].id, "claude-sonnet-4-5");
+    }
+
     #[test]
     fn test_model_tier_inference() {
         assert_eq!(
@@ -167,6 +167,86 @@ pub struct ChatCompletionRequest {
     /// 思维链强度：none, low, medium, high
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<String>,
+    /// 候选回复数量
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    /// 是否返回 token 对数概率
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    /// 每个位置返回的候选 token 数量（需要 `logprobs: true`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u32>,
+    /// 结构化输出格式（JSON 模式 / JSON Schema）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// 流式选项（`include_usage` 请求在流末尾返回用量）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+}
+
+/// 流式选项（`stream_options`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamOptions {
+    /// 是否在流末尾追加携带 `usage` 的数据块
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+/// 结构化输出格式（`response_format`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// 普通文本
+    Text,
+    /// 输出任意合法 JSON 对象
+    JsonObject,
+    /// 输出符合指定 JSON Schema 的 JSON
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+impl ResponseFormat {
+    /// 是否要求 JSON 输出
+    pub fn is_json(&self) -> bool {
+        !matches!(self, ResponseFormat::Text)
+    }
+
+    /// JSON Schema（仅 `json_schema` 格式且提供了 schema 时）
+    pub fn schema(&self) -> Option<&serde_json::Value> {
+        match self {
+            ResponseFormat::JsonSchema { json_schema } => json_schema.schema.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// `json_schema` 格式的 Schema 定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    /// Schema 名称
+    pub name: String,
+    /// Schema 说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON Schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+    /// 是否要求严格遵循 Schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+impl ChatCompletionRequest {
+    /// 请求的候选回复数量，未设置时为 1
+    pub fn choice_count(&self) -> u32 {
+        self.n.unwrap_or(1).max(1)
+    }
+
+    /// 要求上游在流末尾返回用量（服务端聚合流式响应时需要）
+    pub fn request_stream_usage(&mut self) {
+        self.stream_options
+            .get_or_insert_with(StreamOptions::default)
+            .include_usage = true;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -189,6 +269,9 @@ pub struct ResponseMessage {
 pub struct Choice {
     pub index: u32,
     pub message: ResponseMessage,
+    /// token 对数概率（仅在请求 `logprobs` 且上游支持时返回）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<serde_json::Value>,
     pub finish_reason: String,
 }
 
@@ -0,0 +1,179 @@
+//! Provider 能力清单
+//!
+//! 描述每种 Provider 支持的请求特性（流式、工具调用、图片输入、Embeddings、
+//! 多候选回复、token 对数概率、原生结构化输出），
+//! 路由/处理器层在调用上游之前据此拒绝不支持的组合，
+//! 返回清晰的 400 错误，而不是在调用栈深处失败。
+//! 能力清单同时通过 `/v1/routes` 暴露给客户端。
+
+use serde::{Deserialize, Serialize};
+
+use super::provider_type::ProviderType;
+
+/// Provider 能力
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// 流式响应
+    Streaming,
+    /// 工具调用
+    Tools,
+    /// 图片输入
+    Images,
+    /// Embeddings
+    Embeddings,
+    /// 多候选回复（`n` > 1）
+    MultipleChoices,
+    /// token 对数概率（`logprobs`）
+    Logprobs,
+    /// 原生结构化输出（`response_format`）
+    StructuredOutput,
+}
+
+impl Capability {
+    /// 能力名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Streaming => "streaming",
+            Capability::Tools => "tools",
+            Capability::Images => "images",
+            Capability::Embeddings => "embeddings",
+            Capability::MultipleChoices => "multiple_choices",
+            Capability::Logprobs => "logprobs",
+            Capability::StructuredOutput => "structured_output",
+        }
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// 能力校验错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CapabilityError {
+    /// Provider 不支持请求所需的能力
+    #[error("Provider '{provider}' does not support {capability}")]
+    Unsupported {
+        provider: ProviderType,
+        capability: Capability,
+    },
+}
+
+/// Provider 能力清单
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    /// 是否支持流式响应
+    pub supports_streaming: bool,
+    /// 是否支持工具调用
+    pub supports_tools: bool,
+    /// 是否支持图片输入
+    pub supports_images: bool,
+    /// 是否支持 Embeddings
+    pub supports_embeddings: bool,
+    /// 是否支持多候选回复（`n` > 1）
+    #[serde(default)]
+    pub supports_multiple_choices: bool,
+    /// 是否支持 token 对数概率
+    #[serde(default)]
+    pub supports_logprobs: bool,
+    /// 是否原生支持结构化输出（`response_format`）
+    #[serde(default)]
+    pub supports_structured_output: bool,
+}
+
+impl ProviderCapabilities {
+    /// 是否支持指定能力
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Streaming => self.supports_streaming,
+            Capability::Tools => self.supports_tools,
+            Capability::Images => self.supports_images,
+            Capability::Embeddings => self.supports_embeddings,
+            Capability::MultipleChoices => self.supports_multiple_choices,
+            Capability::Logprobs => self.supports_logprobs,
+            Capability::StructuredOutput => self.supports_structured_output,
+        }
+    }
+}
+
+impl ProviderType {
+    /// 获取 Provider 的能力清单
+    pub fn capabilities(&self) -> ProviderCapabilities {
+        let (supports_images, supports_embeddings) = match self {
+            // OpenAI 兼容上游同时提供 Embeddings 接口；
+            // 本地模型是否支持图片取决于具体模型（如 llava），由上游判断
+            ProviderType::OpenAI
+            | ProviderType::AzureOpenai
+            | ProviderType::Vertex
+            | ProviderType::GeminiApiKey
+            | ProviderType::Ollama => (true, true),
+            ProviderType::Kiro
+            | ProviderType::Gemini
+            | ProviderType::Claude
+            | ProviderType::ClaudeOAuth
+            | ProviderType::AnthropicCompatible
+            | ProviderType::Antigravity
+            | ProviderType::Codex
+            | ProviderType::Anthropic
+            | ProviderType::AwsBedrock => (true, false),
+        };
+        // 只有 OpenAI 原生接口支持 `n`、`logprobs` 与 `response_format`，其余上游会静默忽略
+        let openai_native = matches!(self, ProviderType::OpenAI | ProviderType::AzureOpenai);
+        ProviderCapabilities {
+            supports_streaming: true,
+            supports_tools: true,
+            supports_images,
+            supports_embeddings,
+            supports_multiple_choices: openai_native,
+            supports_logprobs: openai_native,
+            supports_structured_output: openai_native,
+        }
+    }
+
+    /// 校验 Provider 是否支持请求所需的全部能力
+    pub fn ensure_supports(&self, required: &[Capability]) -> Result<(), CapabilityError> {
+        let capabilities = self.capabilities();
+        match required.iter().find(|c| !capabilities.supports(**c)) {
+            Some(capability) => Err(CapabilityError::Unsupported {
+                provider: *self,
+                capability: *capability,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_supports() {
+        assert!(ProviderType::Kiro
+            .ensure_supports(&[Capability::Streaming, Capability::Tools, Capability::Images])
+            .is_ok());
+        // 本地视觉模型（如 llava）可以接收图片
+        assert!(ProviderType::Ollama
+            .ensure_supports(&[Capability::Tools, Capability::Images])
+            .is_ok());
+        assert_eq!(
+            ProviderType::Kiro.ensure_supports(&[Capability::Embeddings]),
+            Err(CapabilityError::Unsupported {
+                provider: ProviderType::Kiro,
+                capability: Capability::Embeddings,
+            })
+        );
+    }
+
+    #[test]
+    fn test_capabilities_serialization() {
+        let json = serde_json::to_value(ProviderType::Ollama.capabilities()).unwrap();
+        assert_eq!(json["supports_streaming"], true);
+        assert_eq!(json["supports_images"], true);
+        assert_eq!(json["supports_embeddings"], true);
+        assert_eq!(json["supports_logprobs"], false);
+    }
+}
@@ -87,6 +87,14 @@ pub enum CredentialData {
         api_key: String,
         base_url: Option<String>,
     },
+
+    /// 本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM），API Key 可选
+    #[serde(rename = "local_openai")]
+    LocalOpenAI {
+        base_url: String,
+        #[serde(default)]
+        api_key: Option<String>,
+    },
 }
 
 impl CredentialData {
@@ -131,9 +139,24 @@ impl CredentialData {
             CredentialData::AnthropicKey { api_key, .. } => {
                 format!("Anthropic: {}", mask_key(api_key))
             }
+            CredentialData::LocalOpenAI { base_url, .. } => {
+                format!("Local: {base_url}")
+            }
         }
     }
 
+    /// 是否依赖需要定期刷新的 OAuth access token
+    pub fn uses_oauth_token(&self) -> bool {
+        matches!(
+            self,
+            CredentialData::KiroOAuth { .. }
+                | CredentialData::GeminiOAuth { .. }
+                | CredentialData::AntigravityOAuth { .. }
+                | CredentialData::CodexOAuth { .. }
+                | CredentialData::ClaudeOAuth { .. }
+        )
+    }
+
     /// 获取 Provider 类型
     pub fn provider_type(&self) -> PoolProviderType {
         match self {
@@ -149,6 +172,7 @@ impl CredentialData {
             CredentialData::ClaudeOAuth { .. } => PoolProviderType::ClaudeOAuth,
 
             CredentialData::AnthropicKey { .. } => PoolProviderType::Anthropic,
+            CredentialData::LocalOpenAI { .. } => PoolProviderType::Ollama,
         }
     }
 }
@@ -176,6 +200,18 @@ pub fn pattern_matches(pattern: &str, model: &str) -> bool {
     }
 }
 
+/// 按模型偏好收窄候选凭证
+///
+/// 存在偏好匹配 `model` 的凭证时只保留这些凭证，否则保持不变，
+/// 保证没有偏好匹配的请求仍能使用任意可用凭证。返回是否发生了收窄。
+pub fn narrow_to_preferred(credentials: &mut Vec<ProviderCredential>, model: &str) -> bool {
+    if !credentials.iter().any(|c| c.prefers_model(model)) {
+        return false;
+    }
+    credentials.retain(|c| c.prefers_model(model));
+    true
+}
+
 /// 单个凭证
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderCredential {
@@ -212,6 +248,9 @@ pub struct ProviderCredential {
     pub error_count: u32,
     /// 最后使用时间
     pub last_used: Option<DateTime<Utc>>,
+    /// 最后成功时间（请求成功或健康检查通过）
+    #[serde(default)]
+    pub last_success_time: Option<DateTime<Utc>>,
     /// 最后错误时间
     pub last_error_time: Option<DateTime<Utc>>,
     /// 最后错误消息
@@ -232,6 +271,24 @@ pub struct ProviderCredential {
     pub source: CredentialSource,
     /// 代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 每日 Token 上限（按 UTC 日累计，None 表示不限制）
+    #[serde(default)]
+    pub daily_token_limit: Option<u64>,
+    /// 偏好的模型（支持通配符），请求匹配的模型时优先选择该凭证
+    #[serde(default)]
+    pub preferred_models: Vec<String>,
+    /// 轮换周期（天），超过后提醒更换凭证；None 表示不提醒
+    #[serde(default)]
+    pub rotate_after_days: Option<u32>,
+    /// 最后一次轮换时间，None 时从创建时间开始计算
+    #[serde(default)]
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// 是否已超过轮换周期（由后台轮换检查设置，凭证仍可使用）
+    #[serde(default)]
+    pub needs_rotation: bool,
+    /// 最大并发请求数，None 表示不限制
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
 }
 
 fn default_true() -> bool {
@@ -256,6 +313,7 @@ impl ProviderCredential {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -265,6 +323,12 @@ impl ProviderCredential {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         }
     }
 
@@ -284,6 +348,21 @@ impl ProviderCredential {
         self.is_healthy && !self.is_disabled
     }
 
+    /// 是否处于预热状态（可用，且 OAuth Token 在 [`WARM_TOKEN_MARGIN_MINUTES`] 内不会过期）
+    ///
+    /// API Key 类凭证不需要刷新 Token，可用即视为预热。
+    pub fn is_warm(&self, cache: Option<&CachedTokenInfo>) -> bool {
+        if !self.is_available() {
+            return false;
+        }
+        if !self.credential.uses_oauth_token() {
+            return true;
+        }
+        cache.is_some_and(|cache| {
+            cache.is_valid() && !cache.is_expiring_within_minutes(WARM_TOKEN_MARGIN_MINUTES)
+        })
+    }
+
     /// 是否支持指定模型
     ///
     /// 检查两个来源的排除列表：
@@ -318,6 +397,30 @@ impl ProviderCredential {
         true
     }
 
+    /// 轮换到期时间（未设置轮换周期时为 None）
+    pub fn rotation_due_at(&self) -> Option<DateTime<Utc>> {
+        let days = self.rotate_after_days?;
+        Some(self.rotated_at.unwrap_or(self.created_at) + chrono::Duration::days(days as i64))
+    }
+
+    /// 在指定时间点是否已超过轮换周期
+    pub fn is_rotation_due_at(&self, now: DateTime<Utc>) -> bool {
+        self.rotation_due_at().is_some_and(|due| due <= now)
+    }
+
+    /// 记录一次轮换（重新授权或更换密钥），重置轮换计时并清除轮换提醒
+    pub fn mark_rotated(&mut self, now: DateTime<Utc>) {
+        self.rotated_at = Some(now);
+        self.needs_rotation = false;
+    }
+
+    /// 是否偏好指定模型（匹配 `preferred_models` 中的任一模式）
+    pub fn prefers_model(&self, model: &str) -> bool {
+        self.preferred_models
+            .iter()
+            .any(|pattern| pattern_matches(pattern, model))
+    }
+
     /// 标记为健康
     pub fn mark_healthy(&mut self, check_model: Option<String>) {
         self.is_healthy = true;
@@ -357,6 +460,9 @@ impl ProviderCredential {
     }
 }
 
+/// Token 剩余有效期不少于该分钟数的 OAuth 凭证视为预热
+pub const WARM_TOKEN_MARGIN_MINUTES: i64 = 10;
+
 /// 凭证池统计信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolStats {
@@ -370,6 +476,9 @@ pub struct PoolStats {
     pub total_usage: u64,
     /// 总错误次数
     pub total_errors: u64,
+    /// 预热凭证数（可用且 Token 新鲜，见 [`ProviderCredential::is_warm`]）
+    #[serde(default)]
+    pub warm_count: usize,
     /// 最后更新时间
     pub last_update: DateTime<Utc>,
 }
@@ -382,6 +491,10 @@ impl PoolStats {
             disabled_count: credentials.iter().filter(|c| c.is_disabled).count(),
             total_usage: credentials.iter().map(|c| c.usage_count).sum(),
             total_errors: credentials.iter().map(|c| c.error_count as u64).sum(),
+            warm_count: credentials
+                .iter()
+                .filter(|c| c.is_warm(c.cached_token.as_ref()))
+                .count(),
             last_update: Utc::now(),
         }
     }
@@ -395,6 +508,138 @@ pub struct HealthCheckResult {
     pub model: Option<String>,
     pub message: Option<String>,
     pub duration_ms: u64,
+    /// 失败原因（检查失败时）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<HealthReason>,
+}
+
+/// 凭证不健康的原因
+///
+/// 由健康检查或请求失败的 HTTP 状态码 / 错误信息推断，供前端展示可操作的提示
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthReason {
+    /// 认证失效，需要重新授权
+    AuthExpired,
+    /// 额度耗尽或被限流
+    QuotaExceeded,
+    /// 网络错误（连接失败、超时等）
+    NetworkError,
+    /// 无权访问
+    Forbidden,
+    /// 模型不存在或暂不可用
+    ModelUnavailable,
+    /// 未知原因
+    Unknown,
+}
+
+impl HealthReason {
+    /// 根据上游 HTTP 状态码推断原因
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => Self::AuthExpired,
+            403 => Self::Forbidden,
+            429 => Self::QuotaExceeded,
+            404 | 503 | 529 => Self::ModelUnavailable,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// 根据错误信息推断原因
+    ///
+    /// 优先解析其中的 `HTTP <status>`，否则按关键字判断
+    pub fn from_error(message: &str) -> Self {
+        if let Some(status) = parse_http_status(message) {
+            let reason = Self::from_status(status);
+            if reason != Self::Unknown {
+                return reason;
+            }
+        }
+
+        let lower = message.to_lowercase();
+        let contains_any = |keywords: &[&str]| keywords.iter().any(|k| lower.contains(k));
+        if contains_any(&[
+            "请求失败",
+            "error sending request",
+            "connection",
+            "timed out",
+            "timeout",
+            "dns",
+        ]) {
+            Self::NetworkError
+        } else if contains_any(&[
+            "unauthorized",
+            "invalid_grant",
+            "token expired",
+            "重新授权",
+            "凭证已过期",
+        ]) {
+            Self::AuthExpired
+        } else if contains_any(&["quota", "rate limit", "too many requests", "额度"]) {
+            Self::QuotaExceeded
+        } else if contains_any(&["forbidden", "permission"]) {
+            Self::Forbidden
+        } else if mentions_unavailable_model(&lower) {
+            Self::ModelUnavailable
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// 前端展示的提示文本
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::AuthExpired => "需要重新授权",
+            Self::QuotaExceeded => "额度耗尽或被限流",
+            Self::NetworkError => "网络连接失败",
+            Self::Forbidden => "无权访问",
+            Self::ModelUnavailable => "模型不可用",
+            Self::Unknown => "未知错误",
+        }
+    }
+}
+
+/// 错误信息（小写）是否表示模型不存在或不可用
+///
+/// "does not exist"、"not supported" 等通用短语只有出现在同一句的 "model" 之后才算，
+/// 避免把 "config file does not exist" 之类的错误归为模型问题。
+fn mentions_unavailable_model(lower: &str) -> bool {
+    const MODEL_MARKERS: &[&str] = &[
+        "model_not_found",
+        "model not found",
+        "unknown model",
+        "no such model",
+        "invalid model:",
+        "invalid model name",
+        "invalid model id",
+    ];
+    const GENERIC_PHRASES: &[&str] = &[
+        "does not exist",
+        "is not found",
+        "not supported",
+        "is not available",
+    ];
+
+    if MODEL_MARKERS.iter().any(|m| lower.contains(m)) {
+        return true;
+    }
+    lower
+        .lines()
+        .flat_map(|line| line.split(". "))
+        .any(|sentence| {
+            sentence.find("model").is_some_and(|pos| {
+                GENERIC_PHRASES
+                    .iter()
+                    .any(|phrase| sentence[pos..].contains(phrase))
+            })
+        })
+}
+
+/// 从错误信息中提取 `HTTP <status>` 状态码
+fn parse_http_status(message: &str) -> Option<u16> {
+    let rest = &message[message.find("HTTP ")? + 5..];
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
 }
 
 /// OAuth 凭证状态
@@ -513,6 +758,29 @@ pub fn get_default_check_model(provider_type: PoolProviderType) -> &'static str
     }
 }
 
+/// 确定健康检查使用的探测模型
+///
+/// 优先级：凭证的 `check_model_name` > 配置 `health_probe.models` 中该 Provider 类型的模型
+/// > [`get_default_check_model`] 内置默认值。空字符串视为未配置。
+pub fn resolve_check_model(
+    provider_type: PoolProviderType,
+    credential_model: Option<&str>,
+    configured: &HashMap<String, String>,
+) -> String {
+    let non_empty = |model: &str| {
+        let model = model.trim();
+        (!model.is_empty()).then(|| model.to_string())
+    };
+    credential_model
+        .and_then(non_empty)
+        .or_else(|| {
+            configured
+                .get(&provider_type.to_string())
+                .and_then(|model| non_empty(model))
+        })
+        .unwrap_or_else(|| get_default_check_model(provider_type).to_string())
+}
+
 /// 凭证池前端展示数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CredentialDisplay {
@@ -527,11 +795,16 @@ pub struct CredentialDisplay {
     pub check_model_name: Option<String>,
     pub not_supported_models: Vec<String>,
     pub supported_models: Vec<String>,
+    /// 偏好的模型（支持通配符）
+    pub preferred_models: Vec<String>,
     pub usage_count: u64,
     pub error_count: u32,
     pub last_used: Option<String>,
+    pub last_success_time: Option<String>,
     pub last_error_time: Option<String>,
     pub last_error_message: Option<String>,
+    /// 不健康的原因（由最后一次错误推断）
+    pub health_reason: Option<HealthReason>,
     pub last_health_check_time: Option<String>,
     pub last_health_check_model: Option<String>,
     pub oauth_status: Option<OAuthStatus>,
@@ -546,6 +819,14 @@ pub struct CredentialDisplay {
     pub api_key: Option<String>,
     /// 凭证级代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 轮换周期（天）
+    pub rotate_after_days: Option<u32>,
+    /// 轮换到期时间（RFC3339 格式）
+    pub rotation_due_at: Option<String>,
+    /// 是否已超过轮换周期
+    pub needs_rotation: bool,
+    /// 最大并发请求数
+    pub max_concurrency: Option<u32>,
 }
 
 /// 获取凭证类型字符串
@@ -561,6 +842,7 @@ fn get_credential_type(cred: &CredentialData) -> String {
         CredentialData::CodexOAuth { .. } => "codex_oauth".to_string(),
         CredentialData::ClaudeOAuth { .. } => "claude_oauth".to_string(),
         CredentialData::AnthropicKey { .. } => "anthropic_key".to_string(),
+        CredentialData::LocalOpenAI { .. } => "local_openai".to_string(),
     }
 }
 
@@ -588,6 +870,7 @@ fn get_base_url(cred: &CredentialData) -> Option<String> {
         CredentialData::OpenAIKey { base_url, .. } => base_url.clone(),
         CredentialData::ClaudeKey { base_url, .. } => base_url.clone(),
         CredentialData::AnthropicKey { base_url, .. } => base_url.clone(),
+        CredentialData::LocalOpenAI { base_url, .. } => Some(base_url.clone()),
         _ => None,
     }
 }
@@ -598,6 +881,7 @@ fn get_api_key(cred: &CredentialData) -> Option<String> {
         CredentialData::OpenAIKey { api_key, .. } => Some(api_key.clone()),
         CredentialData::ClaudeKey { api_key, .. } => Some(api_key.clone()),
         CredentialData::AnthropicKey { api_key, .. } => Some(api_key.clone()),
+        CredentialData::LocalOpenAI { api_key, .. } => api_key.clone(),
         _ => None,
     }
 }
@@ -627,11 +911,18 @@ impl From<&ProviderCredential> for CredentialDisplay {
             check_model_name: cred.check_model_name.clone(),
             not_supported_models: cred.not_supported_models.clone(),
             supported_models: cred.supported_models.clone(),
+            preferred_models: cred.preferred_models.clone(),
             usage_count: cred.usage_count,
             error_count: cred.error_count,
             last_used: cred.last_used.map(|t| t.to_rfc3339()),
+            last_success_time: cred.last_success_time.map(|t| t.to_rfc3339()),
             last_error_time: cred.last_error_time.map(|t| t.to_rfc3339()),
             last_error_message: cred.last_error_message.clone(),
+            health_reason: cred
+                .last_error_message
+                .as_deref()
+                .filter(|_| !cred.is_healthy)
+                .map(HealthReason::from_error),
             last_health_check_time: cred.last_health_check_time.map(|t| t.to_rfc3339()),
             last_health_check_model: cred.last_health_check_model.clone(),
             oauth_status: None, // 需要单独调用获取
@@ -642,6 +933,10 @@ impl From<&ProviderCredential> for CredentialDisplay {
             base_url: get_base_url(&cred.credential),
             api_key: get_api_key(&cred.credential),
             proxy_url: cred.proxy_url.clone(),
+            rotate_after_days: cred.rotate_after_days,
+            rotation_due_at: cred.rotation_due_at().map(|t| t.to_rfc3339()),
+            needs_rotation: cred.needs_rotation,
+            max_concurrency: cred.max_concurrency,
         }
     }
 }
@@ -691,6 +986,9 @@ pub struct UpdateCredentialRequest {
     pub check_health: Option<bool>,
     pub check_model_name: Option<String>,
     pub not_supported_models: Option<Vec<String>>,
+    /// 偏好的模型（支持通配符），空列表表示清除
+    #[serde(default)]
+    pub preferred_models: Option<Vec<String>>,
     /// 新的凭证文件路径（仅适用于OAuth凭证，用于重新上传文件）
     pub new_creds_file_path: Option<String>,
     /// OAuth相关：新的project_id（仅适用于Gemini）
@@ -701,6 +999,12 @@ pub struct UpdateCredentialRequest {
     pub new_api_key: Option<String>,
     /// 新的代理 URL（可覆盖全局代理设置）
     pub new_proxy_url: Option<String>,
+    /// 轮换周期（天），0 表示清除
+    #[serde(default)]
+    pub rotate_after_days: Option<u32>,
+    /// 最大并发请求数，0 表示不限制
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
 }
 
 pub type ProviderPools = HashMap<PoolProviderType, Vec<ProviderCredential>>;
@@ -761,6 +1065,7 @@ mod tests {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -770,6 +1075,12 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         };
 
         assert!(!cred.supports_model("claude-opus"));
@@ -796,6 +1107,7 @@ mod tests {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -805,6 +1117,12 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         };
 
         // Exact match exclusion
@@ -833,6 +1151,7 @@ mod tests {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -842,6 +1161,12 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         };
 
         // Prefix wildcard exclusion
@@ -874,6 +1199,7 @@ mod tests {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -883,6 +1209,12 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         };
 
         // Contains wildcard exclusion
@@ -912,6 +1244,7 @@ mod tests {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -921,6 +1254,12 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         };
 
         // Excluded by not_supported_models (exact match)
@@ -951,6 +1290,7 @@ mod tests {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -960,6 +1300,12 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         };
 
         // All models should be supported since not_supported_models is empty
@@ -967,6 +1313,148 @@ mod tests {
         assert!(cred.supports_model("claude-opus"));
     }
 
+    fn claude_key(name: &str, preferred_models: &[&str]) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: format!("sk-{name}"),
+                base_url: None,
+            },
+        );
+        cred.name = Some(name.to_string());
+        cred.preferred_models = preferred_models.iter().map(|m| m.to_string()).collect();
+        cred
+    }
+
+    #[test]
+    fn test_narrow_to_preferred_picks_matching_credential() {
+        let mut available = vec![
+            claude_key("free", &[]),
+            claude_key("paid", &["claude-opus-*"]),
+            claude_key("chat", &["*-haiku-*"]),
+        ];
+        assert!(narrow_to_preferred(&mut available, "claude-opus-4-1"));
+        assert_eq!(available.len(), 1);
+        assert_eq!(available[0].name.as_deref(), Some("paid"));
+    }
+
+    #[test]
+    fn test_narrow_to_preferred_keeps_all_without_match() {
+        let mut available = vec![
+            claude_key("free", &[]),
+            claude_key("paid", &["claude-opus-*"]),
+        ];
+        // 没有凭证偏好该模型时，任意可用凭证都能处理请求
+        assert!(!narrow_to_preferred(&mut available, "claude-sonnet-4-5"));
+        assert_eq!(available.len(), 2);
+    }
+
+    #[test]
+    fn test_health_reason_from_status_and_error() {
+        assert_eq!(HealthReason::from_status(401), HealthReason::AuthExpired);
+        assert_eq!(HealthReason::from_status(403), HealthReason::Forbidden);
+        assert_eq!(HealthReason::from_status(429), HealthReason::QuotaExceeded);
+        assert_eq!(
+            HealthReason::from_status(404),
+            HealthReason::ModelUnavailable
+        );
+        assert_eq!(HealthReason::from_status(500), HealthReason::Unknown);
+
+        // 健康检查产生的错误信息
+        let cases = [
+            ("HTTP 401 Unauthorized - {}", HealthReason::AuthExpired),
+            ("HTTP 429 Too Many Requests", HealthReason::QuotaExceeded),
+            (
+                "请求失败: error sending request for url (https://api.openai.com/v1/chat/completions)",
+                HealthReason::NetworkError,
+            ),
+            ("HTTP 403 Forbidden", HealthReason::Forbidden),
+            ("invalid_grant: 请重新授权", HealthReason::AuthExpired),
+            ("HTTP 500 Internal Server Error", HealthReason::Unknown),
+            // 探测模型已下线或账号无权使用
+            (
+                "HTTP 404 Not Found - {\"type\":\"not_found_error\",\"message\":\"model: claude-2\"}",
+                HealthReason::ModelUnavailable,
+            ),
+            (
+                "HTTP 400 Bad Request - The model `gpt-3.5-turbo` does not exist",
+                HealthReason::ModelUnavailable,
+            ),
+            (
+                "models/gemini-1.0-pro is not found for API version v1beta",
+                HealthReason::ModelUnavailable,
+            ),
+            ("Invalid model: claude-x", HealthReason::ModelUnavailable),
+            // 与模型无关的通用短语不视为模型不可用
+            ("Credential file does not exist", HealthReason::Unknown),
+            ("Streaming not supported. Check model settings", HealthReason::Unknown),
+            ("invalid model parameters: max_tokens", HealthReason::Unknown),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(HealthReason::from_error(message), expected, "{message}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_check_model_prefers_configured_probe() {
+        let configured = HashMap::from([
+            ("openai".to_string(), "gpt-4o-mini".to_string()),
+            ("gemini".to_string(), "  ".to_string()),
+        ]);
+        assert_eq!(
+            resolve_check_model(PoolProviderType::OpenAI, None, &configured),
+            "gpt-4o-mini"
+        );
+        // 凭证级配置优先
+        assert_eq!(
+            resolve_check_model(PoolProviderType::OpenAI, Some("gpt-4.1"), &configured),
+            "gpt-4.1"
+        );
+        // 未配置或配置为空时使用内置默认值
+        assert_eq!(
+            resolve_check_model(PoolProviderType::Gemini, None, &configured),
+            get_default_check_model(PoolProviderType::Gemini)
+        );
+        assert_eq!(
+            resolve_check_model(PoolProviderType::Claude, None, &configured),
+            get_default_check_model(PoolProviderType::Claude)
+        );
+    }
+
+    #[test]
+    fn test_credential_display_exposes_health_reason() {
+        let mut cred = claude_key("limited", &[]);
+        for _ in 0..3 {
+            cred.mark_unhealthy(Some("HTTP 429 Too Many Requests".to_string()));
+        }
+        let display = CredentialDisplay::from(&cred);
+        assert_eq!(display.health_reason, Some(HealthReason::QuotaExceeded));
+
+        cred.mark_healthy(None);
+        assert_eq!(CredentialDisplay::from(&cred).health_reason, None);
+    }
+
+    #[test]
+    fn test_local_openai_credential_without_api_key() {
+        let credential: CredentialData = serde_json::from_value(serde_json::json!({
+            "type": "local_openai",
+            "base_url": "http://localhost:11434"
+        }))
+        .unwrap();
+        assert!(matches!(
+            &credential,
+            CredentialData::LocalOpenAI { api_key: None, base_url } if base_url == "http://localhost:11434"
+        ));
+        assert_eq!(credential.provider_type(), PoolProviderType::Ollama);
+        assert!(!credential.uses_oauth_token());
+
+        let cred = ProviderCredential::new(PoolProviderType::Ollama, credential);
+        let display = CredentialDisplay::from(&cred);
+        assert_eq!(display.credential_type, "local_openai");
+        assert_eq!(display.base_url.as_deref(), Some("http://localhost:11434"));
+        assert_eq!(display.api_key, None);
+    }
+
     // ========================================================================
     // Property-Based Tests for Token Expiration Check
     // ========================================================================
@@ -4,6 +4,30 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::provider_capabilities::ProviderCapabilities;
+use super::provider_type::ProviderType;
+
+/// 请求的路由路径（遥测中的 `route` 标签）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestRoute {
+    /// 默认路由（`/v1/messages`、`/v1/chat/completions`）
+    Default,
+    /// 选择器路由，按凭证名称 / UUID 或故障转移链选择
+    Selector(String),
+    /// 选择器路由，按 Provider 类型从凭证池选择
+    Pool(String),
+}
+
+impl std::fmt::Display for RequestRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestRoute::Default => write!(f, "default"),
+            RequestRoute::Selector(name) => write!(f, "selector:{name}"),
+            RequestRoute::Pool(provider_type) => write!(f, "pool:{provider_type}"),
+        }
+    }
+}
+
 /// 单个路由信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RouteInfo {
@@ -19,6 +43,9 @@ pub struct RouteInfo {
     pub tags: Vec<String>,
     /// 是否启用
     pub enabled: bool,
+    /// Provider 能力清单
+    #[serde(default)]
+    pub capabilities: ProviderCapabilities,
 }
 
 /// 路由端点
@@ -43,6 +70,14 @@ pub struct RouteListResponse {
     pub routes: Vec<RouteInfo>,
 }
 
+/// 按 Provider 类型名称获取能力清单（无法识别的类型视为不支持任何能力）
+pub fn provider_capabilities(provider_type: &str) -> ProviderCapabilities {
+    provider_type
+        .parse::<ProviderType>()
+        .map(|p| p.capabilities())
+        .unwrap_or_default()
+}
+
 /// curl 示例
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CurlExample {
@@ -55,6 +90,7 @@ pub struct CurlExample {
 impl RouteInfo {
     /// 创建新的路由信息
     pub fn new(selector: String, provider_type: String) -> Self {
+        let capabilities = provider_capabilities(&provider_type);
         Self {
             selector,
             provider_type,
@@ -62,16 +98,20 @@ impl RouteInfo {
             endpoints: Vec::new(),
             tags: Vec::new(),
             enabled: true,
+            capabilities,
         }
     }
 
     /// 添加端点
-    pub fn add_endpoint(&mut self, base_url: &str, protocol: &str) {
-        let path = match protocol {
-            "claude" => format!("/{}/v1/messages", self.selector),
-            "openai" => format!("/{}/v1/chat/completions", self.selector),
+    ///
+    /// `selector_prefix` 为规范化后的选择器路由前缀（配置 `server.selector_prefix`），可为空
+    pub fn add_endpoint(&mut self, base_url: &str, selector_prefix: &str, protocol: &str) {
+        let suffix = match protocol {
+            "claude" => "/v1/messages",
+            "openai" => "/v1/chat/completions",
             _ => return,
         };
+        let path = crate::router::selector_route_path(selector_prefix, &self.selector, suffix);
         let url = format!("{base_url}{path}");
         self.endpoints.push(RouteEndpoint {
             path,
@@ -4,7 +4,7 @@
 //! 从主 crate 的 commands/network_cmd.rs 迁移而来。
 
 use serde::Serialize;
-use std::net::{IpAddr, UdpSocket};
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
 
 /// 网络接口信息
 #[derive(Debug, Clone, Serialize)]
@@ -89,6 +89,86 @@ fn get_all_local_ips() -> Vec<String> {
     ips
 }
 
+/// 网卡地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceAddr {
+    /// 网卡名称（如 `en0`、`eth0`）
+    pub name: String,
+    /// 网卡上的 IP 地址
+    pub ip: IpAddr,
+}
+
+/// 列出所有网卡地址
+pub fn list_interface_addrs() -> Result<Vec<InterfaceAddr>, String> {
+    let interfaces = if_addrs::get_if_addrs().map_err(|e| format!("读取网卡信息失败: {e}"))?;
+    Ok(interfaces
+        .into_iter()
+        .map(|iface| InterfaceAddr {
+            ip: iface.ip(),
+            name: iface.name,
+        })
+        .collect())
+}
+
+/// 在给定的网卡地址中查找指定网卡的 IPv4 地址
+///
+/// 优先返回非链路本地（169.254.x.x）地址。
+/// 网卡不存在，或存在但没有 IPv4 地址（通常是未启用）时返回错误。
+pub fn find_interface_ipv4(name: &str, addrs: &[InterfaceAddr]) -> Result<Ipv4Addr, String> {
+    let mut found = false;
+    let mut link_local = None;
+    for addr in addrs.iter().filter(|a| a.name == name) {
+        found = true;
+        if let IpAddr::V4(ipv4) = addr.ip {
+            if !ipv4.is_link_local() {
+                return Ok(ipv4);
+            }
+            link_local.get_or_insert(ipv4);
+        }
+    }
+
+    match (found, link_local) {
+        (_, Some(ipv4)) => Ok(ipv4),
+        (true, None) => Err(format!(
+            "网卡 {name} 没有可用的 IPv4 地址，请确认网卡已启用"
+        )),
+        (false, None) => {
+            let mut names: Vec<&str> = addrs.iter().map(|a| a.name.as_str()).collect();
+            names.sort_unstable();
+            names.dedup();
+            Err(format!("未找到网卡 {name}，可用网卡: {}", names.join(", ")))
+        }
+    }
+}
+
+/// 根据配置确定监听地址
+///
+/// 配置了 `bind_interface` 时绑定到该网卡的 IPv4 地址，否则使用配置的 host。
+pub fn resolve_bind_host(
+    configured_host: &str,
+    bind_interface: Option<&str>,
+) -> Result<String, String> {
+    match bind_interface
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        Some(name) => resolve_bind_host_with(configured_host, Some(name), &list_interface_addrs()?),
+        None => Ok(configured_host.to_string()),
+    }
+}
+
+/// [`resolve_bind_host`] 的实现，网卡地址由调用方提供
+pub fn resolve_bind_host_with(
+    configured_host: &str,
+    bind_interface: Option<&str>,
+    addrs: &[InterfaceAddr],
+) -> Result<String, String> {
+    match bind_interface {
+        Some(name) => find_interface_ipv4(name, addrs).map(|ip| ip.to_string()),
+        None => Ok(configured_host.to_string()),
+    }
+}
+
 /// 根据监听地址生成可访问的 host
 pub fn get_accessible_host(listen_host: &str) -> String {
     match listen_host {
@@ -144,6 +224,59 @@ mod tests {
         );
     }
 
+    fn addr(name: &str, ip: &str) -> InterfaceAddr {
+        InterfaceAddr {
+            name: name.to_string(),
+            ip: ip.parse().unwrap(),
+        }
+    }
+
+    fn mock_interfaces() -> Vec<InterfaceAddr> {
+        vec![
+            addr("lo", "127.0.0.1"),
+            addr("eth0", "fe80::1"),
+            addr("eth0", "169.254.10.2"),
+            addr("eth0", "192.168.1.20"),
+            addr("wlan0", "fe80::2"),
+        ]
+    }
+
+    #[test]
+    fn test_bind_interface_found() {
+        assert_eq!(
+            resolve_bind_host_with("127.0.0.1", Some("eth0"), &mock_interfaces()).unwrap(),
+            "192.168.1.20"
+        );
+        assert_eq!(
+            find_interface_ipv4("lo", &mock_interfaces()).unwrap(),
+            Ipv4Addr::LOCALHOST
+        );
+    }
+
+    #[test]
+    fn test_bind_interface_missing_or_down() {
+        let err = resolve_bind_host_with("127.0.0.1", Some("en9"), &mock_interfaces()).unwrap_err();
+        assert!(err.contains("未找到网卡 en9"));
+        assert!(err.contains("eth0"));
+
+        // 只有 IPv6 地址，视为未启用
+        let err = find_interface_ipv4("wlan0", &mock_interfaces()).unwrap_err();
+        assert!(err.contains("没有可用的 IPv4 地址"));
+    }
+
+    #[test]
+    fn test_bind_interface_unset_uses_host() {
+        assert_eq!(
+            resolve_bind_host_with("0.0.0.0", None, &mock_interfaces()).unwrap(),
+            "0.0.0.0"
+        );
+        assert_eq!(resolve_bind_host("127.0.0.1", None).unwrap(), "127.0.0.1");
+        assert_eq!(
+            resolve_bind_host("127.0.0.1", Some("  ")).unwrap(),
+            "127.0.0.1"
+        );
+    }
+
     #[test]
     fn test_get_accessible_url_specific_ip() {
         assert_eq!(
@@ -5,7 +5,7 @@
 use super::fallback::{FallbackHandler, FallbackPolicy};
 use super::pool_builder::{CredentialInfo, DynamicPoolBuilder};
 use super::selector::{ModelSelector, SelectionResult};
-use super::strategies::create_default_registry;
+use super::strategies::{create_default_registry, SelectionWeights};
 use super::strategy::{SelectionContext, StrategyInfo, StrategyResult, TaskHint};
 use super::tier::{AvailableModel, ServiceTier, TierPool};
 use serde::{Deserialize, Serialize};
@@ -26,6 +26,11 @@ pub struct OrchestratorConfig {
     pub load_balancing: bool,
     /// 模型池刷新间隔（秒）
     pub pool_refresh_interval: u64,
+    /// 选择权重（成本/速度/能力/可用性）
+    ///
+    /// 设置后所有等级统一按权重加权评分选择模型，未设置时使用各等级的默认策略
+    #[serde(default)]
+    pub weights: Option<SelectionWeights>,
 }
 
 impl Default for OrchestratorConfig {
@@ -36,6 +41,7 @@ impl Default for OrchestratorConfig {
             fallback_policy: FallbackPolicy::NextTier,
             load_balancing: true,
             pool_refresh_interval: 60,
+            weights: None,
         }
     }
 }
@@ -73,23 +79,40 @@ impl ModelOrchestrator {
     }
 
     /// 使用自定义配置创建
-    pub fn with_config(config: OrchestratorConfig) -> Self {
+    pub fn with_config(mut config: OrchestratorConfig) -> Self {
         let registry = create_default_registry();
+        let selector = ModelSelector::new(registry);
+
+        if let Some(weights) = config.weights {
+            match weights.validate() {
+                Ok(()) => selector.set_weights(Some(weights)),
+                Err(e) => {
+                    warn!("忽略无效的选择权重: {}", e);
+                    config.weights = None;
+                }
+            }
+        }
 
         Self {
             fallback_handler: FallbackHandler::new(config.fallback_policy),
             config: RwLock::new(config),
-            selector: ModelSelector::new(registry),
+            selector,
             pool_builder: DynamicPoolBuilder::new(),
             credentials: RwLock::new(Vec::new()),
         }
     }
 
     /// 更新配置
-    pub async fn update_config(&self, config: OrchestratorConfig) {
+    pub async fn update_config(&self, config: OrchestratorConfig) -> StrategyResult<()> {
+        if let Some(weights) = &config.weights {
+            weights.validate()?;
+        }
+        self.selector.set_weights(config.weights);
+
         let mut current = self.config.write().await;
         *current = config;
         info!("编排器配置已更新");
+        Ok(())
     }
 
     /// 获取配置
@@ -370,4 +393,28 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_update_config_validates_weights() {
+        let orchestrator = ModelOrchestrator::new();
+
+        let mut config = OrchestratorConfig {
+            weights: Some(SelectionWeights {
+                cost: 0.0,
+                latency: 0.0,
+                capability: 0.0,
+                availability: 0.0,
+            }),
+            ..Default::default()
+        };
+        assert!(orchestrator.update_config(config.clone()).await.is_err());
+        assert!(orchestrator.get_config().await.weights.is_none());
+
+        config.weights = Some(SelectionWeights::default());
+        orchestrator.update_config(config).await.unwrap();
+        assert_eq!(
+            orchestrator.get_config().await.weights,
+            Some(SelectionWeights::default())
+        );
+    }
 }
@@ -2,7 +2,10 @@
 //!
 //! 提供统一的模型选择接口，整合策略和模型池。
 
-use super::strategy::{SelectionContext, StrategyError, StrategyRegistry, StrategyResult};
+use super::strategies::{SelectionWeights, WeightedStrategy};
+use super::strategy::{
+    SelectionContext, SelectionStrategy, StrategyError, StrategyRegistry, StrategyResult,
+};
 use super::tier::{AvailableModel, ServiceTier, TierConfig, TierPool};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -37,6 +40,8 @@ pub struct ModelSelector {
     tier_configs: HashMap<ServiceTier, TierConfig>,
     /// 模型池
     pool: Arc<RwLock<TierPool>>,
+    /// 加权选择权重，设置后所有等级统一使用加权评分策略
+    weights: std::sync::RwLock<Option<SelectionWeights>>,
 }
 
 impl ModelSelector {
@@ -46,6 +51,7 @@ impl ModelSelector {
             registry: Arc::new(RwLock::new(registry)),
             tier_configs: TierConfig::defaults(),
             pool: Arc::new(RwLock::new(TierPool::new())),
+            weights: std::sync::RwLock::new(None),
         }
     }
 
@@ -58,6 +64,7 @@ impl ModelSelector {
             registry: Arc::new(RwLock::new(registry)),
             tier_configs: configs,
             pool: Arc::new(RwLock::new(TierPool::new())),
+            weights: std::sync::RwLock::new(None),
         }
     }
 
@@ -88,24 +95,18 @@ impl ModelSelector {
             return self.select_with_fallback(ctx).await;
         }
 
-        // 获取等级配置
-        let config = self
-            .tier_configs
-            .get(&ctx.tier)
-            .cloned()
-            .unwrap_or_else(TierConfig::pro);
+        let candidates = Self::within_budget(models, ctx);
+        if candidates.is_empty() {
+            warn!("等级 {} 的模型均超出成本预算，尝试降级", ctx.tier);
+            return self.select_with_fallback(ctx).await;
+        }
 
-        // 获取策略
-        let registry = self.registry.read().await;
-        let strategy = registry
-            .get(&config.default_strategy)
-            .or_else(|| registry.get_default())
-            .ok_or_else(|| StrategyError::StrategyNotFound(config.default_strategy.clone()))?;
+        let strategy = self.resolve_strategy(ctx.tier).await?;
 
         debug!("使用策略 {} 选择模型 (等级: {})", strategy.id(), ctx.tier);
 
         // 执行选择
-        let selection = strategy.select(models, ctx).await?;
+        let selection = strategy.select(&candidates, ctx).await?;
 
         Ok(SelectionResult {
             model: selection.model,
@@ -131,12 +132,17 @@ impl ModelSelector {
             return Err(StrategyError::NoAvailableModels);
         }
 
+        let candidates = Self::within_budget(models, ctx);
+        if let (true, Some(budget)) = (candidates.is_empty(), ctx.max_cost_usd) {
+            return Err(StrategyError::BudgetExceeded(budget));
+        }
+
         let registry = self.registry.read().await;
         let strategy = registry
             .get(strategy_id)
             .ok_or_else(|| StrategyError::StrategyNotFound(strategy_id.to_string()))?;
 
-        let selection = strategy.select(models, ctx).await?;
+        let selection = strategy.select(&candidates, ctx).await?;
 
         Ok(SelectionResult {
             model: selection.model,
@@ -155,6 +161,8 @@ impl ModelSelector {
         ctx: &SelectionContext,
     ) -> StrategyResult<SelectionResult> {
         let pool = self.pool.read().await;
+        // 请求等级有模型却进入降级，说明均超出成本预算
+        let mut over_budget = !pool.get(ctx.tier).is_empty();
 
         // 尝试降级到更低等级
         let fallback_tiers = match ctx.tier {
@@ -164,26 +172,17 @@ impl ModelSelector {
         };
 
         for fallback_tier in fallback_tiers {
-            let models = pool.get(fallback_tier);
+            let models = Self::within_budget(pool.get(fallback_tier), ctx);
+            if models.len() < pool.get(fallback_tier).len() {
+                over_budget = true;
+            }
             if !models.is_empty() {
                 let mut fallback_ctx = ctx.clone();
                 fallback_ctx.tier = fallback_tier;
 
-                let config = self
-                    .tier_configs
-                    .get(&fallback_tier)
-                    .cloned()
-                    .unwrap_or_else(TierConfig::pro);
+                let strategy = self.resolve_strategy(fallback_tier).await?;
 
-                let registry = self.registry.read().await;
-                let strategy = registry
-                    .get(&config.default_strategy)
-                    .or_else(|| registry.get_default())
-                    .ok_or_else(|| {
-                        StrategyError::StrategyNotFound(config.default_strategy.clone())
-                    })?;
-
-                let selection = strategy.select(models, &fallback_ctx).await?;
+                let selection = strategy.select(&models, &fallback_ctx).await?;
 
                 info!(
                     "降级选择: {} -> {} (模型: {})",
@@ -205,7 +204,57 @@ impl ModelSelector {
             }
         }
 
-        Err(StrategyError::NoAvailableModels)
+        match ctx.max_cost_usd {
+            Some(budget) if over_budget => Err(StrategyError::BudgetExceeded(budget)),
+            _ => Err(StrategyError::NoAvailableModels),
+        }
+    }
+
+    /// 确定等级使用的策略：配置了权重时使用加权评分，否则使用等级默认策略
+    async fn resolve_strategy(
+        &self,
+        tier: ServiceTier,
+    ) -> StrategyResult<Arc<dyn SelectionStrategy>> {
+        if let Some(weights) = *self.weights.read().unwrap() {
+            return Ok(Arc::new(WeightedStrategy::new(weights)));
+        }
+
+        let config = self
+            .tier_configs
+            .get(&tier)
+            .cloned()
+            .unwrap_or_else(TierConfig::pro);
+
+        let registry = self.registry.read().await;
+        registry
+            .get(&config.default_strategy)
+            .or_else(|| registry.get_default())
+            .ok_or_else(|| StrategyError::StrategyNotFound(config.default_strategy.clone()))
+    }
+
+    /// 设置加权选择权重，`None` 恢复为各等级的默认策略
+    pub fn set_weights(&self, weights: Option<SelectionWeights>) {
+        *self.weights.write().unwrap() = weights;
+    }
+
+    /// 过滤出预估成本在预算内的模型
+    fn within_budget(models: &[AvailableModel], ctx: &SelectionContext) -> Vec<AvailableModel> {
+        models
+            .iter()
+            .filter(|model| {
+                let fits = ctx.within_budget(model);
+                if !fits {
+                    debug!(
+                        "模型 {} 预估成本 {:?} 超出预算 {:?}，跳过",
+                        model.id,
+                        ctx.estimate_cost(model),
+                        ctx.max_cost_usd
+                    );
+                }
+                fits
+            })
+            .cloned()
+            .collect()
     }
 
     /// 获取策略注册表
@@ -322,4 +371,66 @@ mod tests {
         assert!(result.is_fallback);
         assert!(result.fallback_reason.is_some());
     }
+
+    fn priced_model(id: &str, input_cost: f64, output_cost: f64) -> AvailableModel {
+        AvailableModel {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            provider_type: "anthropic".to_string(),
+            family: None,
+            credential_id: format!("cred-{id}"),
+            context_length: None,
+            supports_vision: false,
+            supports_tools: false,
+            input_cost_per_million: Some(input_cost),
+            output_cost_per_million: Some(output_cost),
+            is_healthy: true,
+            current_load: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_budget_skips_expensive_model() {
+        let selector = ModelSelector::new(create_default_registry());
+        let mut pool = TierPool::new();
+        pool.add(ServiceTier::Max, priced_model("opus", 15.0, 75.0));
+        pool.add(ServiceTier::Max, priced_model("sonnet", 3.0, 15.0));
+        selector.update_pool(pool).await;
+
+        // 100k 输入 + 10k 输出：opus ≈ $2.25，sonnet ≈ $0.45
+        let tight = SelectionContext::new(ServiceTier::Max)
+            .with_token_estimate(100_000, 10_000)
+            .with_max_cost(1.0);
+        for _ in 0..4 {
+            let result = selector.select(&tight).await.unwrap();
+            assert_eq!(result.model.id, "sonnet");
+        }
+        let result = selector
+            .select_with_strategy("round_robin", &tight)
+            .await
+            .unwrap();
+        assert_eq!(result.model.id, "sonnet");
+
+        // 没有模型在预算内时报错
+        let too_tight = tight.clone().with_max_cost(0.1);
+        assert!(matches!(
+            selector.select(&too_tight).await,
+            Err(StrategyError::BudgetExceeded(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generous_budget_allows_expensive_model() {
+        let selector = ModelSelector::new(create_default_registry());
+        let mut pool = TierPool::new();
+        pool.add(ServiceTier::Max, priced_model("opus", 15.0, 75.0));
+        selector.update_pool(pool).await;
+
+        let generous = SelectionContext::new(ServiceTier::Max)
+            .with_token_estimate(100_000, 10_000)
+            .with_max_cost(5.0);
+        let result = selector.select(&generous).await.unwrap();
+        assert_eq!(result.model.id, "opus");
+        assert!(!result.is_fallback);
+    }
 }
@@ -7,12 +7,14 @@ mod load_balanced;
 mod round_robin;
 mod speed_optimized;
 mod task_based;
+mod weighted;
 
 pub use cost_optimized::CostOptimizedStrategy;
 pub use load_balanced::LoadBalancedStrategy;
 pub use round_robin::RoundRobinStrategy;
 pub use speed_optimized::SpeedOptimizedStrategy;
 pub use task_based::TaskBasedStrategy;
+pub use weighted::{SelectionWeights, WeightedStrategy, WEIGHTED_STRATEGY_ID};
 
 use super::strategy::StrategyRegistry;
 use std::sync::Arc;
@@ -24,6 +26,7 @@ pub fn register_builtin_strategies(registry: &mut StrategyRegistry) {
     registry.register(Arc::new(CostOptimizedStrategy::new()));
     registry.register(Arc::new(SpeedOptimizedStrategy::new()));
     registry.register(Arc::new(LoadBalancedStrategy::new()));
+    registry.register(Arc::new(WeightedStrategy::default()));
 }
 
 /// 创建带有内置策略的注册表
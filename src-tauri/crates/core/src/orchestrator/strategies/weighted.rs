@@ -0,0 +1,296 @@
+//! 加权评分策略
+//!
+//! 按可配置权重对成本、速度、能力、可用性四个归一化分量加权求和，
+//! 得分最高的模型胜出。权重通过 `OrchestratorConfig.weights` 配置。
+
+use crate::orchestrator::strategy::{
+    ModelSelection, SelectionContext, SelectionStrategy, StrategyError, StrategyResult,
+};
+use crate::orchestrator::tier::AvailableModel;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// 加权策略 ID
+pub const WEIGHTED_STRATEGY_ID: &str = "weighted";
+
+/// 选择权重
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SelectionWeights {
+    /// 成本权重（越便宜得分越高）
+    #[serde(default = "default_weight")]
+    pub cost: f64,
+    /// 速度权重（响应越快得分越高）
+    #[serde(default = "default_weight")]
+    pub latency: f64,
+    /// 能力权重（模型等级、工具/视觉支持、上下文长度）
+    #[serde(default = "default_weight")]
+    pub capability: f64,
+    /// 可用性权重（负载越低得分越高）
+    #[serde(default = "default_weight")]
+    pub availability: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl Default for SelectionWeights {
+    fn default() -> Self {
+        Self {
+            cost: default_weight(),
+            latency: default_weight(),
+            capability: default_weight(),
+            availability: default_weight(),
+        }
+    }
+}
+
+impl SelectionWeights {
+    /// 校验权重：必须为非负有限数，且不能全为 0
+    pub fn validate(&self) -> StrategyResult<()> {
+        let weights = [
+            ("cost", self.cost),
+            ("latency", self.latency),
+            ("capability", self.capability),
+            ("availability", self.availability),
+        ];
+        for (name, weight) in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(StrategyError::ConfigError(format!(
+                    "weights.{name} 必须为非负数，当前为 {weight}"
+                )));
+            }
+        }
+        if self.total() == 0.0 {
+            return Err(StrategyError::ConfigError("weights 不能全为 0".to_string()));
+        }
+        Ok(())
+    }
+
+    fn total(&self) -> f64 {
+        self.cost + self.latency + self.capability + self.availability
+    }
+}
+
+/// 加权评分策略
+pub struct WeightedStrategy {
+    weights: SelectionWeights,
+}
+
+impl WeightedStrategy {
+    /// 使用指定权重创建
+    pub fn new(weights: SelectionWeights) -> Self {
+        Self { weights }
+    }
+
+    /// 每百万 tokens 的输入 + 输出价格，缺少定价时返回 None
+    fn unit_price(model: &AvailableModel) -> Option<f64> {
+        Some(model.input_cost_per_million? + model.output_cost_per_million?)
+    }
+
+    /// 速度分量（0-1），按模型家族估算
+    fn latency_component(model: &AvailableModel) -> f64 {
+        let family = model.family.as_deref().unwrap_or("").to_lowercase();
+        if family.contains("haiku") || family.contains("flash") {
+            1.0
+        } else if family.contains("gpt-3.5") {
+            0.8
+        } else if family.contains("sonnet") || family.contains("pro") {
+            0.6
+        } else if family.contains("gpt-4") {
+            0.5
+        } else if family.contains("opus") || family.contains("ultra") || family.contains("o1") {
+            0.2
+        } else {
+            0.5
+        }
+    }
+
+    /// 能力分量（0-1）
+    fn capability_component(model: &AvailableModel) -> f64 {
+        let family = model.family.as_deref().unwrap_or("").to_lowercase();
+        let family_score = if family.contains("opus")
+            || family.contains("ultra")
+            || family.contains("o1")
+        {
+            1.0
+        } else if family.contains("sonnet") || family.contains("pro") || family.contains("gpt-4") {
+            0.7
+        } else if family.contains("haiku") || family.contains("flash") || family.contains("gpt-3.5")
+        {
+            0.4
+        } else {
+            0.5
+        };
+        let context_score = model
+            .context_length
+            .map(|len| (len as f64 / 200_000.0).min(1.0))
+            .unwrap_or(0.5);
+
+        family_score * 0.6
+            + if model.supports_tools { 0.15 } else { 0.0 }
+            + if model.supports_vision { 0.15 } else { 0.0 }
+            + context_score * 0.1
+    }
+
+    /// 可用性分量（0-1），负载未知时取中间值
+    fn availability_component(model: &AvailableModel) -> f64 {
+        model
+            .current_load
+            .map(|load| (100 - load.min(100)) as f64 / 100.0)
+            .unwrap_or(0.5)
+    }
+
+    /// 在候选集内计算各模型的加权得分
+    ///
+    /// 成本按候选集内的最低/最高价格做 min-max 归一化，缺少定价的模型取中间值
+    pub fn score_all(&self, candidates: &[AvailableModel]) -> Vec<f64> {
+        let prices: Vec<f64> = candidates.iter().filter_map(Self::unit_price).collect();
+        let min_price = prices.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_price = prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        let total = self.weights.total().max(f64::EPSILON);
+        candidates
+            .iter()
+            .map(|model| {
+                let cost = match Self::unit_price(model) {
+                    Some(_) if max_price <= min_price => 1.0,
+                    Some(price) => (max_price - price) / (max_price - min_price),
+                    None => 0.5,
+                };
+                (self.weights.cost * cost
+                    + self.weights.latency * Self::latency_component(model)
+                    + self.weights.capability * Self::capability_component(model)
+                    + self.weights.availability * Self::availability_component(model))
+                    / total
+            })
+            .collect()
+    }
+}
+
+impl Default for WeightedStrategy {
+    fn default() -> Self {
+        Self::new(SelectionWeights::default())
+    }
+}
+
+#[async_trait]
+impl SelectionStrategy for WeightedStrategy {
+    fn id(&self) -> &str {
+        WEIGHTED_STRATEGY_ID
+    }
+
+    fn display_name(&self) -> &str {
+        "加权评分"
+    }
+
+    fn description(&self) -> &str {
+        "按成本、速度、能力、可用性的权重综合评分"
+    }
+
+    async fn select(
+        &self,
+        pool: &[AvailableModel],
+        ctx: &SelectionContext,
+    ) -> StrategyResult<ModelSelection> {
+        self.weights.validate()?;
+
+        let available: Vec<_> = pool
+            .iter()
+            .filter(|m| {
+                m.is_healthy
+                    && !ctx.excluded_models.contains(&m.id)
+                    && (!ctx.requires_vision || m.supports_vision)
+                    && (!ctx.requires_tools || m.supports_tools)
+            })
+            .cloned()
+            .collect();
+
+        if available.is_empty() {
+            return Err(StrategyError::NoAvailableModels);
+        }
+
+        let scores = self.score_all(&available);
+        let mut ranked: Vec<(AvailableModel, f64)> = available.into_iter().zip(scores).collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let (selected, score) = ranked.remove(0);
+
+        Ok(ModelSelection {
+            model: selected,
+            reason: format!("加权评分最高 ({score:.2})"),
+            confidence: (score * 100.0).round().clamp(0.0, 100.0) as u8,
+            alternatives: ranked.into_iter().map(|(model, _)| model).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::tier::ServiceTier;
+
+    fn model(id: &str, family: &str, input: f64, output: f64) -> AvailableModel {
+        AvailableModel {
+            id: id.to_string(),
+            display_name: id.to_string(),
+            provider_type: "anthropic".to_string(),
+            family: Some(family.to_string()),
+            credential_id: format!("cred-{id}"),
+            context_length: Some(200000),
+            supports_vision: true,
+            supports_tools: true,
+            input_cost_per_million: Some(input),
+            output_cost_per_million: Some(output),
+            is_healthy: true,
+            current_load: Some(20),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cost_weight_favors_cheaper_candidate() {
+        let candidates = vec![
+            model("claude-opus", "opus", 15.0, 75.0),
+            model("claude-sonnet", "sonnet", 3.0, 15.0),
+        ];
+        let ctx = SelectionContext::new(ServiceTier::Max);
+
+        let capability_first = WeightedStrategy::new(SelectionWeights {
+            cost: 0.1,
+            latency: 0.0,
+            capability: 1.0,
+            availability: 0.0,
+        });
+        let result = capability_first.select(&candidates, &ctx).await.unwrap();
+        assert_eq!(result.model.id, "claude-opus");
+
+        let cost_first = WeightedStrategy::new(SelectionWeights {
+            cost: 5.0,
+            latency: 0.0,
+            capability: 1.0,
+            availability: 0.0,
+        });
+        let result = cost_first.select(&candidates, &ctx).await.unwrap();
+        assert_eq!(result.model.id, "claude-sonnet");
+        assert_eq!(result.alternatives[0].id, "claude-opus");
+    }
+
+    #[test]
+    fn test_weights_validation() {
+        assert!(SelectionWeights::default().validate().is_ok());
+
+        let negative = SelectionWeights {
+            cost: -1.0,
+            ..Default::default()
+        };
+        assert!(negative.validate().is_err());
+
+        let all_zero = SelectionWeights {
+            cost: 0.0,
+            latency: 0.0,
+            capability: 0.0,
+            availability: 0.0,
+        };
+        assert!(all_zero.validate().is_err());
+    }
+}
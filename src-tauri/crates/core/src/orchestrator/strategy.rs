@@ -23,6 +23,9 @@ pub enum StrategyError {
 
     #[error("配置错误: {0}")]
     ConfigError(String),
+
+    #[error("没有预估成本在预算 ${0} 以内的模型")]
+    BudgetExceeded(f64),
 }
 
 pub type StrategyResult<T> = Result<T, StrategyError>;
@@ -50,6 +53,11 @@ pub struct SelectionContext {
     pub preferred_provider: Option<String>,
     /// 排除的模型 ID 列表
     pub excluded_models: Vec<String>,
+    /// 成本预算（美元），按预估 tokens × 模型定价排除超出预算的模型
+    ///
+    /// 按会话限额时由调用方传入会话剩余预算
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
     /// 额外元数据
     pub metadata: HashMap<String, serde_json::Value>,
 }
@@ -66,6 +74,7 @@ impl Default for SelectionContext {
             estimated_output_tokens: None,
             preferred_provider: None,
             excluded_models: Vec::new(),
+            max_cost_usd: None,
             metadata: HashMap::new(),
         }
     }
@@ -109,6 +118,35 @@ impl SelectionContext {
         self.excluded_models.push(model_id.to_string());
         self
     }
+
+    /// 设置成本预算（美元）
+    pub fn with_max_cost(mut self, max_cost_usd: f64) -> Self {
+        self.max_cost_usd = Some(max_cost_usd);
+        self
+    }
+
+    /// 设置预估的输入/输出 tokens
+    pub fn with_token_estimate(mut self, input_tokens: u32, output_tokens: u32) -> Self {
+        self.estimated_input_tokens = Some(input_tokens);
+        self.estimated_output_tokens = Some(output_tokens);
+        self
+    }
+
+    /// 按预估 tokens 估算模型的请求成本（美元）
+    pub fn estimate_cost(&self, model: &AvailableModel) -> Option<f64> {
+        model.estimate_cost_usd(
+            self.estimated_input_tokens.unwrap_or(0),
+            self.estimated_output_tokens.unwrap_or(0),
+        )
+    }
+
+    /// 模型是否在成本预算内（未设置预算或缺少定价时视为在预算内）
+    pub fn within_budget(&self, model: &AvailableModel) -> bool {
+        match (self.max_cost_usd, self.estimate_cost(model)) {
+            (Some(budget), Some(cost)) => cost <= budget,
+            _ => true,
+        }
+    }
 }
 
 /// 任务类型提示
@@ -162,6 +162,13 @@ pub struct AvailableModel {
 }
 
 impl AvailableModel {
+    /// 按定价估算一次请求的成本（美元），缺少定价时返回 None
+    pub fn estimate_cost_usd(&self, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        let input = self.input_cost_per_million?;
+        let output = self.output_cost_per_million?;
+        Some((input * input_tokens as f64 + output * output_tokens as f64) / 1_000_000.0)
+    }
+
     /// 计算模型的综合评分
     pub fn score(&self, tier: ServiceTier) -> f64 {
         let mut score = 0.0;
@@ -2,10 +2,44 @@
 //!
 //! 定义请求处理过程中的上下文信息
 
+use crate::credential::SelectionTrace;
 use crate::models::provider_type::ProviderType;
+use crate::models::route_model::RequestRoute;
 use crate::plugin::PluginContext;
 use chrono::{DateTime, Utc};
-use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// 请求处理阶段（用于分阶段计时）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestStage {
+    /// 凭证选择
+    Selection,
+    /// Token 刷新
+    Refresh,
+    /// 上游调用
+    Upstream,
+}
+
+/// 请求分阶段耗时（毫秒）
+///
+/// 重试时同一阶段的耗时会累加；`total_ms` 为端到端耗时，
+/// 与各阶段之和的差值即排队、转换等代理自身开销。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestTiming {
+    pub selection_ms: u64,
+    pub refresh_ms: u64,
+    pub upstream_ms: u64,
+    pub total_ms: u64,
+}
+
+impl RequestTiming {
+    /// 代理自身开销（端到端耗时减去上游耗时）
+    pub fn proxy_overhead_ms(&self) -> u64 {
+        self.total_ms.saturating_sub(self.upstream_ms)
+    }
+}
 
 /// 请求上下文
 ///
@@ -26,6 +60,8 @@ pub struct RequestContext {
     pub provider: Option<ProviderType>,
     /// 使用的凭证 ID
     pub credential_id: Option<String>,
+    /// 本次请求已尝试过的凭证 ID（重试时优先避开）
+    pub tried_credentials: Vec<String>,
     /// 重试次数
     pub retry_count: u32,
     /// 是否为流式请求
@@ -34,6 +70,22 @@ pub struct RequestContext {
     pub plugin_ctx: Option<PluginContext>,
     /// 元数据
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    /// 分阶段耗时
+    pub timing: RequestTiming,
+    /// 识别到的客户端 ID
+    pub client_id: Option<String>,
+    /// 会话 ID（来自 `X-ProxyCast-Session` 请求头）
+    pub session_id: Option<String>,
+    /// 流式请求的首 token 耗时（毫秒）
+    pub first_token_ms: Option<u64>,
+    /// 路由标签（如 `default`、`selector:<name>`、`pool:<type>`）
+    pub route: Option<String>,
+    /// 凭证选择追踪
+    pub selection_trace: Option<SelectionTrace>,
+    /// 客户端使用的服务器 API 密钥标签（`server.api_keys` 中的 `label`）
+    pub api_key_label: Option<String>,
+    /// 取消令牌（客户端断开时触发，克隆的上下文共享同一令牌）
+    pub cancel_token: CancellationToken,
 }
 
 impl RequestContext {
@@ -48,13 +100,28 @@ impl RequestContext {
             resolved_model: model,
             provider: None,
             credential_id: None,
+            tried_credentials: Vec::new(),
             retry_count: 0,
             is_stream: false,
             plugin_ctx: None,
             metadata: std::collections::HashMap::new(),
+            timing: RequestTiming::default(),
+            client_id: None,
+            session_id: None,
+            first_token_ms: None,
+            route: None,
+            selection_trace: None,
+            api_key_label: None,
+            cancel_token: CancellationToken::new(),
         }
     }
 
+    /// 使用指定的请求 ID（如中间件确定的 `X-Request-Id`）
+    pub fn with_request_id(mut self, request_id: String) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
     /// 设置流式请求标志
     pub fn with_stream(mut self, is_stream: bool) -> Self {
         self.is_stream = is_stream;
@@ -66,11 +133,49 @@ impl RequestContext {
         self.provider = Some(provider);
     }
 
-    /// 设置凭证 ID
+    /// 设置客户端 ID
+    pub fn set_client_id(&mut self, client_id: String) {
+        self.client_id = Some(client_id);
+    }
+
+    /// 设置会话 ID
+    pub fn set_session_id(&mut self, session_id: String) {
+        self.session_id = Some(session_id);
+    }
+
+    /// 设置路由标签
+    pub fn set_route(&mut self, route: RequestRoute) {
+        self.route = Some(route.to_string());
+    }
+
+    /// 设置凭证选择追踪
+    pub fn set_selection_trace(&mut self, trace: SelectionTrace) {
+        self.selection_trace = Some(trace);
+    }
+
+    /// 设置 API 密钥标签
+    pub fn set_api_key_label(&mut self, label: Option<String>) {
+        self.api_key_label = label;
+    }
+
+    /// 设置凭证 ID（同时记为已尝试）
     pub fn set_credential_id(&mut self, credential_id: String) {
+        if !self.has_tried_credential(&credential_id) {
+            self.tried_credentials.push(credential_id.clone());
+        }
         self.credential_id = Some(credential_id);
     }
 
+    /// 凭证是否已在本次请求中尝试过
+    pub fn has_tried_credential(&self, credential_id: &str) -> bool {
+        self.tried_credentials.iter().any(|id| id == credential_id)
+    }
+
+    /// 已尝试过的凭证 ID（用作重试选择的排除集合）
+    pub fn tried_credential_ids(&self) -> Vec<&str> {
+        self.tried_credentials.iter().map(String::as_str).collect()
+    }
+
     /// 设置解析后的模型名称
     pub fn set_resolved_model(&mut self, model: String) {
         self.resolved_model = model;
@@ -86,6 +191,39 @@ impl RequestContext {
         self.start_time.elapsed().as_millis() as u64
     }
 
+    /// 取消请求（客户端断开），等待该令牌的上游调用随之中止
+    pub fn cancel(&self) {
+        self.cancel_token.cancel();
+    }
+
+    /// 请求是否已取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_token.is_cancelled()
+    }
+
+    /// 记录首 token 耗时
+    pub fn set_first_token_ms(&mut self, ms: u64) {
+        self.first_token_ms = Some(ms);
+    }
+
+    /// 累加某个阶段的耗时
+    pub fn record_stage(&mut self, stage: RequestStage, elapsed: Duration) {
+        let ms = elapsed.as_millis() as u64;
+        match stage {
+            RequestStage::Selection => self.timing.selection_ms += ms,
+            RequestStage::Refresh => self.timing.refresh_ms += ms,
+            RequestStage::Upstream => self.timing.upstream_ms += ms,
+        }
+    }
+
+    /// 获取分阶段耗时（`total_ms` 取当前端到端耗时）
+    pub fn timing(&self) -> RequestTiming {
+        RequestTiming {
+            total_ms: self.elapsed_ms(),
+            ..self.timing
+        }
+    }
+
     /// 初始化插件上下文
     pub fn init_plugin_context(&mut self, provider: ProviderType) {
         self.plugin_ctx = Some(PluginContext::new(
@@ -147,6 +285,32 @@ mod tests {
         assert_eq!(ctx.provider, Some(ProviderType::Kiro));
     }
 
+    #[test]
+    fn test_request_context_tracks_tried_credentials() {
+        let mut ctx = RequestContext::new("model".to_string());
+        ctx.set_credential_id("cred-a".to_string());
+        ctx.set_credential_id("cred-b".to_string());
+        ctx.set_credential_id("cred-a".to_string());
+
+        assert_eq!(ctx.credential_id.as_deref(), Some("cred-a"));
+        assert_eq!(ctx.tried_credential_ids(), vec!["cred-a", "cred-b"]);
+        assert!(ctx.has_tried_credential("cred-b"));
+        assert!(!ctx.has_tried_credential("cred-c"));
+    }
+
+    #[test]
+    fn test_request_context_route_tag() {
+        let mut ctx = RequestContext::new("claude-sonnet-4-5".to_string());
+        assert_eq!(ctx.route, None);
+
+        ctx.set_route(RequestRoute::Default);
+        assert_eq!(ctx.route.as_deref(), Some("default"));
+        ctx.set_route(RequestRoute::Selector("my-kiro".to_string()));
+        assert_eq!(ctx.route.as_deref(), Some("selector:my-kiro"));
+        ctx.set_route(RequestRoute::Pool("gemini".to_string()));
+        assert_eq!(ctx.route.as_deref(), Some("pool:gemini"));
+    }
+
     #[test]
     fn test_request_context_increment_retry() {
         let mut ctx = RequestContext::new("model".to_string());
@@ -157,6 +321,39 @@ mod tests {
         assert_eq!(ctx.retry_count, 2);
     }
 
+    #[test]
+    fn test_request_context_timing_breakdown() {
+        let mut ctx = RequestContext::new("model".to_string());
+        let stage = |ms| {
+            let started = Instant::now();
+            std::thread::sleep(Duration::from_millis(ms));
+            started.elapsed()
+        };
+
+        let selection = stage(20);
+        ctx.record_stage(RequestStage::Selection, selection);
+        let refresh = stage(20);
+        ctx.record_stage(RequestStage::Refresh, refresh);
+        // 重试时上游耗时累加
+        let first = stage(30);
+        ctx.record_stage(RequestStage::Upstream, first);
+        let second = stage(30);
+        ctx.record_stage(RequestStage::Upstream, second);
+
+        let timing = ctx.timing();
+        assert!(timing.selection_ms >= 20);
+        assert!(timing.refresh_ms >= 20);
+        assert!(timing.upstream_ms >= 60);
+        let stages = timing.selection_ms + timing.refresh_ms + timing.upstream_ms;
+        assert!(stages <= timing.total_ms);
+        // 阶段之外几乎没有额外开销
+        assert!(timing.total_ms - stages < 100);
+        assert_eq!(
+            timing.proxy_overhead_ms(),
+            timing.total_ms - timing.upstream_ms
+        );
+    }
+
     #[test]
     fn test_request_context_metadata() {
         let mut ctx = RequestContext::new("model".to_string());
@@ -166,4 +363,16 @@ mod tests {
         assert!(value.is_some());
         assert_eq!(value.unwrap(), &serde_json::json!("value"));
     }
+
+    #[test]
+    fn test_cancel_shared_with_cloned_context() {
+        let ctx = RequestContext::new("model".to_string());
+        let cloned = ctx.clone();
+        let token = ctx.cancel_token.clone();
+        assert!(!cloned.is_cancelled());
+
+        cloned.cancel();
+        assert!(ctx.is_cancelled());
+        assert!(token.is_cancelled());
+    }
 }
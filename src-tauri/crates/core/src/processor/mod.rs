@@ -6,5 +6,5 @@
 pub mod context;
 pub mod error;
 
-pub use context::RequestContext;
+pub use context::{RequestContext, RequestStage, RequestTiming};
 pub use error::ProcessError;
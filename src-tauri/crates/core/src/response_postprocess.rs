@@ -0,0 +1,348 @@
+//! 响应后处理器
+//!
+//! 按配置（`server.response_post_processors`）顺序对响应文本做变换，
+//! 例如脱敏、去掉代码块标记、追加免责声明，无需修改代码。
+//!
+//! 作用于成功的非流式 JSON 响应：OpenAI 格式的 `choices[].message.content`
+//! （字符串或 `text` 片段数组）与 Anthropic 格式 `content[]` 中的 `text` 块。
+//! 流式响应的文本被拆分在多个事件中，正则和追加类处理无法逐块正确执行，
+//! 由服务端先聚合为非流式响应、处理后再转换为合成流。
+
+use axum::{
+    body::Body,
+    http::{header, Response},
+};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::config::PostProcessorConfig;
+
+/// 后处理器构建错误
+#[derive(Debug, thiserror::Error)]
+pub enum PostProcessError {
+    /// 正则表达式无效
+    #[error("无效的正则表达式 '{pattern}': {message}")]
+    InvalidPattern { pattern: String, message: String },
+}
+
+/// 响应后处理器
+///
+/// 接收一段响应文本，返回变换后的文本。
+pub trait ResponsePostProcessor: Send + Sync {
+    /// 处理器名称（用于日志）
+    fn name(&self) -> &str;
+
+    /// 变换响应文本
+    fn process(&self, content: &str) -> String;
+}
+
+/// 正则脱敏处理器
+pub struct RegexRedactProcessor {
+    regex: Regex,
+    replacement: String,
+}
+
+impl RegexRedactProcessor {
+    /// 创建脱敏处理器，`replacement` 支持 `$1` 等捕获组引用
+    pub fn new(pattern: &str, replacement: impl Into<String>) -> Result<Self, PostProcessError> {
+        let regex = Regex::new(pattern).map_err(|e| PostProcessError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(Self {
+            regex,
+            replacement: replacement.into(),
+        })
+    }
+}
+
+impl ResponsePostProcessor for RegexRedactProcessor {
+    fn name(&self) -> &str {
+        "regex_redact"
+    }
+
+    fn process(&self, content: &str) -> String {
+        self.regex
+            .replace_all(content, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+/// 追加文本处理器
+pub struct AppendTextProcessor {
+    text: String,
+}
+
+impl AppendTextProcessor {
+    /// 创建追加文本处理器
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+}
+
+impl ResponsePostProcessor for AppendTextProcessor {
+    fn name(&self) -> &str {
+        "append_text"
+    }
+
+    fn process(&self, content: &str) -> String {
+        format!("{content}{}", self.text)
+    }
+}
+
+/// 去掉代码块标记处理器
+///
+/// 仅当整段内容被一个 ```` ``` ```` 代码块包裹时去掉首尾标记（含语言标识）。
+pub struct StripCodeFencesProcessor;
+
+impl ResponsePostProcessor for StripCodeFencesProcessor {
+    fn name(&self) -> &str {
+        "strip_code_fences"
+    }
+
+    fn process(&self, content: &str) -> String {
+        let trimmed = content.trim();
+        let Some(rest) = trimmed.strip_prefix("```") else {
+            return content.to_string();
+        };
+        let Some(body) = rest.strip_suffix("```") else {
+            return content.to_string();
+        };
+        // 第一行是语言标识
+        match body.split_once('\n') {
+            Some((_, inner)) if !inner.contains("```") => inner.trim_end().to_string(),
+            _ => content.to_string(),
+        }
+    }
+}
+
+/// 后处理器链
+#[derive(Default)]
+pub struct PostProcessorChain {
+    processors: Vec<Box<dyn ResponsePostProcessor>>,
+}
+
+impl PostProcessorChain {
+    /// 创建空的后处理器链
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按配置顺序构建后处理器链
+    pub fn from_config(configs: &[PostProcessorConfig]) -> Result<Self, PostProcessError> {
+        let mut chain = Self::new();
+        for config in configs {
+            let processor: Box<dyn ResponsePostProcessor> = match config {
+                PostProcessorConfig::RegexRedact {
+                    pattern,
+                    replacement,
+                } => Box::new(RegexRedactProcessor::new(pattern, replacement.clone())?),
+                PostProcessorConfig::AppendText { text } => {
+                    Box::new(AppendTextProcessor::new(text.clone()))
+                }
+                PostProcessorConfig::StripCodeFences => Box::new(StripCodeFencesProcessor),
+            };
+            chain = chain.with(processor);
+        }
+        Ok(chain)
+    }
+
+    /// 在链尾追加处理器
+    pub fn with(mut self, processor: Box<dyn ResponsePostProcessor>) -> Self {
+        self.processors.push(processor);
+        self
+    }
+
+    /// 是否没有任何处理器
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// 处理器名称（按执行顺序）
+    pub fn names(&self) -> Vec<&str> {
+        self.processors.iter().map(|p| p.name()).collect()
+    }
+
+    /// 按顺序执行所有处理器
+    pub fn process(&self, content: &str) -> String {
+        self.processors
+            .iter()
+            .fold(content.to_string(), |content, processor| {
+                processor.process(&content)
+            })
+    }
+
+    /// 处理响应 JSON 中的文本内容，返回是否有内容被处理
+    ///
+    /// 支持 OpenAI（`choices[].message.content`，字符串或片段数组）和
+    /// Anthropic（`content[]` 的 `text` 块）格式。
+    pub fn apply_to_json(&self, body: &mut Value) -> bool {
+        let mut applied = false;
+        if let Some(choices) = body.get_mut("choices").and_then(Value::as_array_mut) {
+            for choice in choices {
+                match choice.pointer_mut("/message/content") {
+                    Some(Value::String(content)) => {
+                        *content = self.process(content);
+                        applied = true;
+                    }
+                    Some(Value::Array(parts)) => applied |= self.apply_to_text_blocks(parts),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(blocks) = body.get_mut("content").and_then(Value::as_array_mut) {
+            applied |= self.apply_to_text_blocks(blocks);
+        }
+        applied
+    }
+
+    /// 处理 `{"type": "text", "text": ...}` 块，其他类型的块保持不变
+    fn apply_to_text_blocks(&self, blocks: &mut [Value]) -> bool {
+        let mut applied = false;
+        for block in blocks {
+            if block.get("type").and_then(Value::as_str) != Some("text") {
+                continue;
+            }
+            if let Some(Value::String(text)) = block.get_mut("text") {
+                *text = self.process(text);
+                applied = true;
+            }
+        }
+        applied
+    }
+
+    /// 处理成功的非流式 JSON 响应，其他响应原样返回
+    pub async fn apply_to_response(&self, response: Response<Body>) -> Response<Body> {
+        let is_json = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/json"));
+        if self.is_empty() || !response.status().is_success() || !is_json {
+            return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(_) => return Response::from_parts(parts, Body::empty()),
+        };
+        let mut value: Value = match serde_json::from_slice(&bytes) {
+            Ok(value) => value,
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        };
+        if !self.apply_to_json(&mut value) {
+            return Response::from_parts(parts, Body::from(bytes));
+        }
+
+        let bytes = serde_json::to_vec(&value).unwrap_or_else(|_| bytes.to_vec());
+        parts.headers.remove(header::CONTENT_LENGTH);
+        Response::from_parts(parts, Body::from(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex_redact() {
+        let chain = PostProcessorChain::from_config(&[PostProcessorConfig::RegexRedact {
+            pattern: r"[\w.+-]+@[\w-]+\.[\w.]+".to_string(),
+            replacement: "[EMAIL]".to_string(),
+        }])
+        .unwrap();
+        assert_eq!(
+            chain.process("联系 alice@example.com 或 bob@test.org"),
+            "联系 [EMAIL] 或 [EMAIL]"
+        );
+
+        assert!(
+            PostProcessorChain::from_config(&[PostProcessorConfig::RegexRedact {
+                pattern: "(".to_string(),
+                replacement: String::new(),
+            }])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_chain_applied_in_order() {
+        let chain = PostProcessorChain::from_config(&[
+            PostProcessorConfig::StripCodeFences,
+            PostProcessorConfig::AppendText {
+                text: "\n-- AI generated".to_string(),
+            },
+            PostProcessorConfig::RegexRedact {
+                pattern: "AI".to_string(),
+                replacement: "bot".to_string(),
+            },
+        ])
+        .unwrap();
+        assert_eq!(
+            chain.names(),
+            vec!["strip_code_fences", "append_text", "regex_redact"]
+        );
+        // 追加的文本也会被后续的脱敏处理
+        assert_eq!(
+            chain.process("```json\n{\"a\":1}\n```"),
+            "{\"a\":1}\n-- bot generated"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_to_responses() {
+        let chain = PostProcessorChain::new().with(Box::new(AppendTextProcessor::new("!")));
+
+        let mut openai = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": "hi"}}]
+        });
+        assert!(chain.apply_to_json(&mut openai));
+        assert_eq!(openai["choices"][0]["message"]["content"], "hi!");
+
+        // OpenAI 片段数组形式的 content
+        let mut parts = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": [
+                {"type": "text", "text": "a"},
+                {"type": "image_url", "image_url": {"url": "data:"}},
+                {"type": "text", "text": "b"}
+            ]}}]
+        });
+        assert!(chain.apply_to_json(&mut parts));
+        assert_eq!(parts["choices"][0]["message"]["content"][0]["text"], "a!");
+        assert_eq!(
+            parts["choices"][0]["message"]["content"][1]["image_url"]["url"],
+            "data:"
+        );
+        assert_eq!(parts["choices"][0]["message"]["content"][2]["text"], "b!");
+
+        let anthropic = serde_json::json!({
+            "content": [
+                {"type": "text", "text": "hello"},
+                {"type": "tool_use", "id": "t1", "name": "f", "input": {}}
+            ]
+        });
+        let response = Response::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(anthropic.to_string()))
+            .unwrap();
+        let response = chain.apply_to_response(response).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["content"][0]["text"], "hello!");
+        assert_eq!(body["content"][1]["name"], "f");
+
+        // 流式响应原样透传
+        let sse = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from("data: hi\n\n"))
+            .unwrap();
+        let sse = chain.apply_to_response(sse).await;
+        let body = axum::body::to_bytes(sse.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"data: hi\n\n");
+    }
+}
@@ -4,7 +4,7 @@
 //!
 //! 路由格式：
 //! - `/{provider-name}/v1/messages` - Provider 命名空间路由
-//! - `/{selector}/v1/messages` - 凭证选择器路由（向后兼容）
+//! - `/{selector}/v1/messages` - 凭证选择器路由（向后兼容，可配置前缀，如 `/p/{selector}/v1/messages`）
 //! - `/v1/messages` - 默认路由
 //! - `/api/provider/{provider}/v1/*` - Amp CLI 路由
 //!
@@ -16,7 +16,11 @@ mod mapper;
 mod provider_router;
 mod route_registry;
 mod rules;
+pub mod selector_prefix;
 
 pub use amp_router::AmpRouter;
 pub use mapper::ModelMapper;
 pub use rules::Router;
+pub use selector_prefix::{
+    normalize_selector_prefix, selector_route_path, selector_route_pattern, SELECTOR_ROUTE_SUFFIXES,
+};
@@ -0,0 +1,168 @@
+//! 多供应商选择器路由前缀
+//!
+//! 选择器路由默认挂在根路径下（`/{selector}/v1/messages`），可能与自带路径前缀的
+//! 客户端冲突。配置 `server.selector_prefix`（如 `/p`）后，选择器路由改为
+//! `/p/{selector}/v1/messages`；前缀为空时保持原有路径。
+
+/// 选择器路由的规范后缀
+pub const SELECTOR_ROUTE_SUFFIXES: &[&str] = &["/v1/messages", "/v1/chat/completions"];
+
+/// 服务器固定路由的首段路径，前缀不能以其开头
+const RESERVED_SEGMENTS: &[&str] = &["v0", "v1", "api", "health", "version", "ws"];
+
+/// 规范化并校验选择器路由前缀
+///
+/// 空字符串（或 `/`）表示不使用前缀；否则返回以 `/` 开头、不以 `/` 结尾的前缀。
+pub fn normalize_selector_prefix(prefix: &str) -> Result<String, String> {
+    let trimmed = prefix.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+
+    for segment in trimmed.split('/') {
+        if segment.is_empty() {
+            return Err(format!("选择器路由前缀不能包含空路径段: {prefix}"));
+        }
+        if !segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(format!(
+                "选择器路由前缀只能包含字母、数字、`-`、`_`、`.`: {prefix}"
+            ));
+        }
+    }
+
+    let first = trimmed.split('/').next().unwrap_or_default();
+    if RESERVED_SEGMENTS.contains(&first) {
+        return Err(format!(
+            "选择器路由前缀不能以 /{first} 开头，会与已有路由冲突: {prefix}"
+        ));
+    }
+
+    Ok(format!("/{trimmed}"))
+}
+
+/// 选择器路由的 axum 路由模式，如 `/p/:selector/v1/messages`
+///
+/// `prefix` 需先经过 [`normalize_selector_prefix`] 规范化。
+pub fn selector_route_pattern(prefix: &str, suffix: &str) -> String {
+    format!("{prefix}/:selector{suffix}")
+}
+
+/// 指定选择器的请求路径，如 `/p/my-kiro/v1/messages`
+pub fn selector_route_path(prefix: &str, selector: &str, suffix: &str) -> String {
+    format!("{prefix}/{selector}{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        extract::Path,
+        http::{Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tower::Service;
+
+    fn router(prefix: &str) -> Router {
+        let mut router = Router::new()
+            .route(
+                "/v1/messages",
+                post(|| async { "default:messages".to_string() }),
+            )
+            .route(
+                "/v1/chat/completions",
+                post(|| async { "default:chat".to_string() }),
+            );
+        for suffix in SELECTOR_ROUTE_SUFFIXES {
+            let handler = post(move |Path(selector): Path<String>| async move {
+                format!("{selector}:{suffix}")
+            });
+            router = router.route(&selector_route_pattern(prefix, suffix), handler);
+        }
+        router
+    }
+
+    async fn call(router: &mut Router, uri: &str) -> (StatusCode, String) {
+        let req = Request::post(uri).body(Body::empty()).unwrap();
+        let response = router.call(req).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_normalize_selector_prefix() {
+        assert_eq!(normalize_selector_prefix("").unwrap(), "");
+        assert_eq!(normalize_selector_prefix(" / ").unwrap(), "");
+        assert_eq!(normalize_selector_prefix("p").unwrap(), "/p");
+        assert_eq!(normalize_selector_prefix("/p/").unwrap(), "/p");
+        assert_eq!(
+            normalize_selector_prefix("/proxy/route").unwrap(),
+            "/proxy/route"
+        );
+
+        assert!(normalize_selector_prefix("/v1").is_err());
+        assert!(normalize_selector_prefix("/api/x").is_err());
+        assert!(normalize_selector_prefix("/p//x").is_err());
+        assert!(normalize_selector_prefix("/:p").is_err());
+        assert!(normalize_selector_prefix("/p?x=1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_routes_resolve_under_configured_prefix() {
+        let prefix = normalize_selector_prefix("/p").unwrap();
+        let mut router = router(&prefix);
+
+        assert_eq!(
+            call(
+                &mut router,
+                &selector_route_path(&prefix, "my-kiro", "/v1/messages")
+            )
+            .await,
+            (StatusCode::OK, "my-kiro:/v1/messages".to_string())
+        );
+        assert_eq!(
+            call(&mut router, "/p/my-kiro/v1/chat/completions").await,
+            (StatusCode::OK, "my-kiro:/v1/chat/completions".to_string())
+        );
+        // 配置前缀后，根路径下的选择器路由不再生效
+        assert_eq!(
+            call(&mut router, "/my-kiro/v1/messages").await.0,
+            StatusCode::NOT_FOUND
+        );
+        // 默认路由不受影响
+        assert_eq!(
+            call(&mut router, "/v1/messages").await,
+            (StatusCode::OK, "default:messages".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_prefix_keeps_root_selector_routes() {
+        let prefix = normalize_selector_prefix("").unwrap();
+        assert_eq!(
+            selector_route_path(&prefix, "my-kiro", "/v1/messages"),
+            "/my-kiro/v1/messages"
+        );
+        let mut router = router(&prefix);
+
+        assert_eq!(
+            call(&mut router, "/my-kiro/v1/messages").await,
+            (StatusCode::OK, "my-kiro:/v1/messages".to_string())
+        );
+        assert_eq!(
+            call(&mut router, "/my-kiro/v1/chat/completions").await,
+            (StatusCode::OK, "my-kiro:/v1/chat/completions".to_string())
+        );
+        assert_eq!(
+            call(&mut router, "/v1/chat/completions").await,
+            (StatusCode::OK, "default:chat".to_string())
+        );
+    }
+}
@@ -4,11 +4,14 @@
 //! - 增强的限流处理（Duration 解析、指数退避）
 //! - 会话粘性管理（会话与账号映射）
 //! - 调度模式配置
+//! - 会话配额（按会话限制请求数与 Token 数）
 
+pub mod quota;
 pub mod rate_limit;
 pub mod sticky_config;
 pub mod sticky_manager;
 
+pub use quota::{session_id_from_headers, QuotaStatus, SessionQuotaLimiter, SESSION_HEADER};
 pub use rate_limit::{
     extract_retry_delay, parse_duration_string, RateLimitReason, RateLimitRecord, RateLimitTracker,
 };
@@ -0,0 +1,262 @@
+//! 会话配额模块
+//!
+//! 按 `X-ProxyCast-Session` 请求头区分会话，在滑动窗口内统计请求数与 Token 数：
+//! - 请求在进入 Provider 选择前检查并计数
+//! - Token 在请求完成后补记（使用估算或上游返回的用量）
+//! - 超出任一限制时拒绝请求，并返回剩余额度与重置时间
+
+use crate::config::SessionQuotaConfig;
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// 会话请求头名称
+pub const SESSION_HEADER: &str = "x-proxycast-session";
+
+/// 从请求头读取会话 ID（空值视为未提供）
+pub fn session_id_from_headers(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// 超过该会话数时顺带清理空闲会话
+const CLEANUP_THRESHOLD: usize = 1024;
+
+/// 配额状态（用于生成 `x-ratelimit-*` 响应头）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaStatus {
+    /// 窗口内请求上限（0 表示不限制）
+    pub limit_requests: u32,
+    /// 窗口内剩余请求数
+    pub remaining_requests: u32,
+    /// 窗口内 Token 上限（0 表示不限制）
+    pub limit_tokens: u64,
+    /// 窗口内剩余 Token 数
+    pub remaining_tokens: u64,
+    /// 最早一条记录滑出窗口前的秒数
+    pub reset_after_secs: u64,
+}
+
+/// 单个会话的滑动窗口
+#[derive(Debug, Default)]
+struct SessionWindow {
+    requests: VecDeque<Instant>,
+    tokens: VecDeque<(Instant, u64)>,
+}
+
+impl SessionWindow {
+    /// 移除窗口外的记录
+    fn prune(&mut self, now: Instant, window: Duration) {
+        while matches!(self.requests.front(), Some(t) if now.duration_since(*t) >= window) {
+            self.requests.pop_front();
+        }
+        while matches!(self.tokens.front(), Some((t, _)) if now.duration_since(*t) >= window) {
+            self.tokens.pop_front();
+        }
+    }
+
+    fn used_tokens(&self) -> u64 {
+        self.tokens.iter().map(|(_, n)| n).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.requests.is_empty() && self.tokens.is_empty()
+    }
+
+    /// 最早记录滑出窗口前的秒数（向上取整）
+    fn reset_after(&self, now: Instant, window: Duration) -> u64 {
+        let oldest = [
+            self.requests.front().copied(),
+            self.tokens.front().map(|(t, _)| *t),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        match oldest {
+            Some(t) => {
+                let remaining = window.saturating_sub(now.duration_since(t));
+                remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+            }
+            None => 0,
+        }
+    }
+}
+
+/// 会话配额限流器
+#[derive(Debug)]
+pub struct SessionQuotaLimiter {
+    config: SessionQuotaConfig,
+    sessions: DashMap<String, SessionWindow>,
+}
+
+impl SessionQuotaLimiter {
+    /// 根据配置创建限流器
+    pub fn new(config: SessionQuotaConfig) -> Self {
+        Self {
+            config,
+            sessions: DashMap::new(),
+        }
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_secs.max(1))
+    }
+
+    /// 检查会话配额并计入本次请求
+    ///
+    /// 未超限时返回 `Ok`（已扣除本次请求），超限时返回 `Err` 且不计数。
+    pub fn check(&self, session: &str) -> Result<QuotaStatus, QuotaStatus> {
+        self.check_at(session, Instant::now())
+    }
+
+    /// 以指定时间检查会话配额
+    pub fn check_at(&self, session: &str, now: Instant) -> Result<QuotaStatus, QuotaStatus> {
+        let window = self.window();
+        if self.sessions.len() > CLEANUP_THRESHOLD {
+            self.cleanup_at(now);
+        }
+
+        let mut entry = self.sessions.entry(session.to_string()).or_default();
+        entry.prune(now, window);
+
+        let used_requests = entry.requests.len() as u64;
+        let used_tokens = entry.used_tokens();
+        let over_requests =
+            self.config.max_requests > 0 && used_requests >= u64::from(self.config.max_requests);
+        let over_tokens = self.config.max_tokens > 0 && used_tokens >= self.config.max_tokens;
+
+        if over_requests || over_tokens {
+            let status = self.status(&entry, now, window);
+            tracing::warn!(
+                "[SESSION_QUOTA] 会话 {} 超出配额: requests={}/{}, tokens={}/{}",
+                session,
+                used_requests,
+                self.config.max_requests,
+                used_tokens,
+                self.config.max_tokens
+            );
+            return Err(status);
+        }
+
+        entry.requests.push_back(now);
+        Ok(self.status(&entry, now, window))
+    }
+
+    /// 补记会话消耗的 Token 数
+    pub fn record_tokens(&self, session: &str, tokens: u64) {
+        self.record_tokens_at(session, tokens, Instant::now());
+    }
+
+    /// 以指定时间补记 Token 数
+    pub fn record_tokens_at(&self, session: &str, tokens: u64, now: Instant) {
+        if tokens == 0 {
+            return;
+        }
+        let mut entry = self.sessions.entry(session.to_string()).or_default();
+        entry.tokens.push_back((now, tokens));
+    }
+
+    /// 清理窗口内已无记录的会话
+    pub fn cleanup_at(&self, now: Instant) {
+        let window = self.window();
+        self.sessions.retain(|_, entry| {
+            entry.prune(now, window);
+            !entry.is_empty()
+        });
+    }
+
+    fn status(&self, entry: &SessionWindow, now: Instant, window: Duration) -> QuotaStatus {
+        let used_requests = u32::try_from(entry.requests.len()).unwrap_or(u32::MAX);
+        QuotaStatus {
+            limit_requests: self.config.max_requests,
+            remaining_requests: self.config.max_requests.saturating_sub(used_requests),
+            limit_tokens: self.config.max_tokens,
+            remaining_tokens: self.config.max_tokens.saturating_sub(entry.used_tokens()),
+            reset_after_secs: entry.reset_after(now, window),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_requests: u32, max_tokens: u64) -> SessionQuotaLimiter {
+        SessionQuotaLimiter::new(SessionQuotaConfig {
+            enabled: true,
+            max_requests,
+            max_tokens,
+            window_secs: 60,
+        })
+    }
+
+    #[test]
+    fn test_within_quota() {
+        let limiter = limiter(3, 0);
+        let now = Instant::now();
+
+        let status = limiter.check_at("s1", now).unwrap();
+        assert_eq!(status.limit_requests, 3);
+        assert_eq!(status.remaining_requests, 2);
+        assert_eq!(status.reset_after_secs, 60);
+
+        let status = limiter.check_at("s1", now).unwrap();
+        assert_eq!(status.remaining_requests, 1);
+
+        // 其他会话互不影响
+        assert_eq!(limiter.check_at("s2", now).unwrap().remaining_requests, 2);
+    }
+
+    #[test]
+    fn test_over_quota() {
+        let request_limiter = limiter(2, 0);
+        let token_limiter = limiter(0, 100);
+        let now = Instant::now();
+
+        request_limiter.check_at("s1", now).unwrap();
+        request_limiter
+            .check_at("s1", now + Duration::from_secs(10))
+            .unwrap();
+        let status = request_limiter
+            .check_at("s1", now + Duration::from_secs(20))
+            .unwrap_err();
+        assert_eq!(status.remaining_requests, 0);
+        assert_eq!(status.reset_after_secs, 40);
+
+        // Token 配额
+        token_limiter.check_at("s1", now).unwrap();
+        token_limiter.record_tokens_at("s1", 120, now);
+        let status = token_limiter.check_at("s1", now).unwrap_err();
+        assert_eq!(status.remaining_tokens, 0);
+    }
+
+    #[test]
+    fn test_window_reset() {
+        let limiter = limiter(2, 100);
+        let now = Instant::now();
+
+        limiter.check_at("s1", now).unwrap();
+        limiter.record_tokens_at("s1", 100, now);
+        limiter
+            .check_at("s1", now + Duration::from_secs(30))
+            .unwrap_err();
+
+        // 第一条记录滑出窗口后恢复额度
+        let later = now + Duration::from_secs(61);
+        let status = limiter.check_at("s1", later).unwrap();
+        assert_eq!(status.remaining_requests, 1);
+        assert_eq!(status.remaining_tokens, 100);
+
+        limiter.cleanup_at(later + Duration::from_secs(120));
+        assert!(limiter.sessions.is_empty());
+    }
+}
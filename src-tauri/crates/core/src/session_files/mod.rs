@@ -6,7 +6,7 @@
 //! ```text
 //! ~/.proxycast/sessions/
 //! ├── {session-id}/
-//! │   ├── .meta.json          # 会话元数据
+//! │   ├── .meta.jsonl         # 会话元数据（只追加日志）
 //! │   ├── files/              # 生成的文件
 //! │   │   ├── article.md
 //! │   │   ├── song-spec.md
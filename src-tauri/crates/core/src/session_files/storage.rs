@@ -1,14 +1,22 @@
 //! 会话文件存储服务
 //!
 //! 提供会话文件的 CRUD 操作和生命周期管理。
+//!
+//! 会话元数据以只追加的 JSONL 日志保存（每次更新追加一行快照），
+//! 单次更新是 O(1) 追加，写入中途崩溃最多丢失最后一行。
+//! 日志超过阈值后通过临时文件 + rename 原子地压缩为一行。
 
-use std::fs;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
 use chrono::Utc;
 
 use super::types::{SessionDetail, SessionFile, SessionMeta, SessionSummary};
 
+/// 元数据日志超过该大小（字节）时自动压缩
+const META_LOG_COMPACT_BYTES: u64 = 64 * 1024;
+
 /// 会话文件存储服务
 pub struct SessionFileStorage {
     /// 存储根目录
@@ -42,11 +50,16 @@ impl SessionFileStorage {
         self.base_dir.join(session_id)
     }
 
-    /// 获取会话元数据文件路径
-    fn get_meta_path(&self, session_id: &str) -> PathBuf {
+    /// 获取旧版会话元数据文件路径（整体覆盖写入的 JSON）
+    fn get_legacy_meta_path(&self, session_id: &str) -> PathBuf {
         self.get_session_dir(session_id).join(".meta.json")
     }
 
+    /// 获取会话元数据日志路径
+    fn get_meta_log_path(&self, session_id: &str) -> PathBuf {
+        self.get_session_dir(session_id).join(".meta.jsonl")
+    }
+
     /// 获取会话文件目录路径
     fn get_files_dir(&self, session_id: &str) -> PathBuf {
         self.get_session_dir(session_id).join("files")
@@ -137,18 +150,82 @@ impl SessionFileStorage {
     // ========================================================================
 
     /// 读取会话元数据
+    ///
+    /// 从日志重建，取最后一条完整记录；无法解析的行（如崩溃时写了一半的末行）会被跳过。
+    /// 没有日志时回退读取旧版 `.meta.json`。
     pub fn get_meta(&self, session_id: &str) -> Result<SessionMeta, String> {
-        let meta_path = self.get_meta_path(session_id);
+        let log_path = self.get_meta_log_path(session_id);
+        if log_path.exists() {
+            let content =
+                fs::read_to_string(&log_path).map_err(|e| format!("读取元数据失败: {e}"))?;
+            return content
+                .lines()
+                .rev()
+                .find_map(|line| serde_json::from_str(line).ok())
+                .ok_or_else(|| "解析元数据失败: 日志中没有完整记录".to_string());
+        }
+
+        let meta_path = self.get_legacy_meta_path(session_id);
         let content = fs::read_to_string(&meta_path).map_err(|e| format!("读取元数据失败: {e}"))?;
         serde_json::from_str(&content).map_err(|e| format!("解析元数据失败: {e}"))
     }
 
-    /// 保存会话元数据
+    /// 保存会话元数据（追加到日志）
     pub fn save_meta(&self, session_id: &str, meta: &SessionMeta) -> Result<(), String> {
-        let meta_path = self.get_meta_path(session_id);
-        let content =
-            serde_json::to_string_pretty(meta).map_err(|e| format!("序列化元数据失败: {e}"))?;
-        fs::write(&meta_path, content).map_err(|e| format!("写入元数据失败: {e}"))
+        let log_path = self.get_meta_log_path(session_id);
+        let mut line = serde_json::to_string(meta).map_err(|e| format!("序列化元数据失败: {e}"))?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(|e| format!("写入元数据失败: {e}"))?;
+        // 上次写入中断时末行没有换行符，先补上，避免与新记录拼成一行
+        let len = file
+            .metadata()
+            .map_err(|e| format!("写入元数据失败: {e}"))?
+            .len();
+        if len > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::Start(len - 1))
+                .and_then(|_| file.read_exact(&mut last))
+                .map_err(|e| format!("写入元数据失败: {e}"))?;
+            if last[0] != b'\n' {
+                line.insert(0, '\n');
+            }
+        }
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("写入元数据失败: {e}"))?;
+
+        if len + line.len() as u64 > META_LOG_COMPACT_BYTES {
+            self.compact_meta(session_id)?;
+        }
+        Ok(())
+    }
+
+    /// 压缩元数据日志
+    ///
+    /// 把日志重写为只包含最新元数据的一行：先写入临时文件再 rename，
+    /// 压缩过程中崩溃不会破坏原日志。同时清理旧版 `.meta.json`。
+    pub fn compact_meta(&self, session_id: &str) -> Result<(), String> {
+        let meta = self.get_meta(session_id)?;
+        let log_path = self.get_meta_log_path(session_id);
+        let tmp_path = log_path.with_extension("jsonl.tmp");
+
+        let mut line =
+            serde_json::to_string(&meta).map_err(|e| format!("序列化元数据失败: {e}"))?;
+        line.push('\n');
+        fs::write(&tmp_path, line).map_err(|e| format!("压缩元数据失败: {e}"))?;
+        fs::rename(&tmp_path, &log_path).map_err(|e| format!("压缩元数据失败: {e}"))?;
+
+        let legacy_path = self.get_legacy_meta_path(session_id);
+        if legacy_path.exists() {
+            let _ = fs::remove_file(&legacy_path);
+        }
+        tracing::debug!("[SessionFileStorage] 压缩元数据日志: {}", session_id);
+        Ok(())
     }
 
     /// 更新会话元数据
@@ -426,4 +503,106 @@ mod tests {
         storage.delete_session("test-session-4").unwrap();
         assert!(!storage.session_exists("test-session-4"));
     }
+
+    fn meta_log_lines(storage: &SessionFileStorage, session_id: &str) -> usize {
+        fs::read_to_string(storage.get_meta_log_path(session_id))
+            .unwrap()
+            .lines()
+            .count()
+    }
+
+    #[test]
+    fn test_meta_updates_are_appended() {
+        let (storage, _temp) = create_test_storage();
+        storage.create_session("append").unwrap();
+        storage
+            .update_meta("append", Some("标题".to_string()), None, None)
+            .unwrap();
+        storage
+            .update_meta("append", None, Some("music".to_string()), None)
+            .unwrap();
+
+        assert_eq!(meta_log_lines(&storage, "append"), 3);
+        let meta = storage.get_meta("append").unwrap();
+        assert_eq!(meta.title.as_deref(), Some("标题"));
+        assert_eq!(meta.theme.as_deref(), Some("music"));
+    }
+
+    #[test]
+    fn test_truncated_last_line_is_tolerated() {
+        let (storage, _temp) = create_test_storage();
+        storage.create_session("crash").unwrap();
+        storage
+            .update_meta("crash", Some("完整".to_string()), None, None)
+            .unwrap();
+
+        // 模拟写入中途崩溃：末行只写了一半
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(storage.get_meta_log_path("crash"))
+            .unwrap();
+        file.write_all(r#"{"sessionId":"crash","title":"半"#.as_bytes())
+            .unwrap();
+
+        assert_eq!(
+            storage.get_meta("crash").unwrap().title.as_deref(),
+            Some("完整")
+        );
+
+        // 后续追加不受残行影响
+        storage
+            .update_meta("crash", Some("恢复".to_string()), None, None)
+            .unwrap();
+        assert_eq!(
+            storage.get_meta("crash").unwrap().title.as_deref(),
+            Some("恢复")
+        );
+    }
+
+    #[test]
+    fn test_compaction_keeps_latest_meta() {
+        let (storage, _temp) = create_test_storage();
+        storage.create_session("compact").unwrap();
+        for i in 0..5 {
+            storage
+                .update_meta("compact", Some(format!("v{i}")), None, None)
+                .unwrap();
+        }
+        storage
+            .save_file("compact", "article.md", "content")
+            .unwrap();
+        let before = storage.get_meta("compact").unwrap();
+
+        storage.compact_meta("compact").unwrap();
+        assert_eq!(meta_log_lines(&storage, "compact"), 1);
+        let after = storage.get_meta("compact").unwrap();
+        assert_eq!(after.title.as_deref(), Some("v4"));
+        assert_eq!(after.file_count, 1);
+        assert_eq!(after.updated_at, before.updated_at);
+
+        // 超过阈值时自动压缩
+        let big_title = "x".repeat(META_LOG_COMPACT_BYTES as usize);
+        storage
+            .update_meta("compact", Some(big_title.clone()), None, None)
+            .unwrap();
+        assert_eq!(meta_log_lines(&storage, "compact"), 1);
+        assert_eq!(storage.get_meta("compact").unwrap().title, Some(big_title));
+    }
+
+    #[test]
+    fn test_legacy_meta_file_is_migrated() {
+        let (storage, _temp) = create_test_storage();
+        let meta = SessionMeta::new("legacy".to_string());
+        fs::create_dir_all(storage.get_files_dir("legacy")).unwrap();
+        fs::write(
+            storage.get_legacy_meta_path("legacy"),
+            serde_json::to_string_pretty(&meta).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(storage.get_meta("legacy").unwrap().session_id, "legacy");
+        storage.compact_meta("legacy").unwrap();
+        assert!(!storage.get_legacy_meta_path("legacy").exists());
+        assert_eq!(storage.get_meta("legacy").unwrap().session_id, "legacy");
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! 定义托盘图标状态和状态快照结构
 
+use crate::models::provider_pool_model::ProviderCredential;
 use serde::{Deserialize, Serialize};
 
 /// 托盘图标状态枚举
@@ -10,7 +11,7 @@ use serde::{Deserialize, Serialize};
 pub enum TrayIconStatus {
     /// 正常运行（绿色）- 服务器运行且凭证健康
     Running,
-    /// 警告状态（黄色）- 有凭证即将过期或余额不足
+    /// 警告状态（黄色）- 有凭证即将过期、余额不足或需要轮换
     Warning,
     /// 错误状态（红色）- 服务器停止或所有凭证无效
     Error,
@@ -33,6 +34,8 @@ pub struct CredentialHealth {
     pub is_expiring_soon: bool,
     /// 是否余额不足
     pub is_low_balance: bool,
+    /// 是否超过轮换周期
+    pub needs_rotation: bool,
 }
 
 impl CredentialHealth {
@@ -42,6 +45,7 @@ impl CredentialHealth {
             is_valid: true,
             is_expiring_soon: false,
             is_low_balance: false,
+            needs_rotation: false,
         }
     }
 
@@ -51,12 +55,26 @@ impl CredentialHealth {
             is_valid: false,
             is_expiring_soon: false,
             is_low_balance: false,
+            needs_rotation: false,
+        }
+    }
+
+    /// 从凭证池中的凭证生成健康状态（缓存的 OAuth Token 5 分钟内过期视为即将过期）
+    pub fn from_pool_credential(credential: &ProviderCredential) -> Self {
+        Self {
+            is_valid: credential.is_available(),
+            is_expiring_soon: credential
+                .cached_token
+                .as_ref()
+                .is_some_and(|cache| cache.is_expiring_soon()),
+            is_low_balance: false,
+            needs_rotation: credential.needs_rotation,
         }
     }
 
     /// 检查凭证是否有警告
     pub fn has_warning(&self) -> bool {
-        self.is_valid && (self.is_expiring_soon || self.is_low_balance)
+        self.is_valid && (self.is_expiring_soon || self.is_low_balance || self.needs_rotation)
     }
 }
 
@@ -136,6 +154,7 @@ pub fn get_credential_health_from_pool(
                 is_valid: *is_valid,
                 is_expiring_soon: *is_expiring_soon,
                 is_low_balance: *is_low_balance,
+                needs_rotation: false,
             },
         )
         .collect()
@@ -147,11 +166,12 @@ mod tests {
     use proptest::prelude::*;
 
     fn arb_credential_health() -> impl Strategy<Value = CredentialHealth> {
-        (any::<bool>(), any::<bool>(), any::<bool>()).prop_map(
-            |(is_valid, is_expiring_soon, is_low_balance)| CredentialHealth {
+        (any::<bool>(), any::<bool>(), any::<bool>(), any::<bool>()).prop_map(
+            |(is_valid, is_expiring_soon, is_low_balance, needs_rotation)| CredentialHealth {
                 is_valid,
                 is_expiring_soon,
                 is_low_balance,
+                needs_rotation,
             },
         )
     }
@@ -217,6 +237,30 @@ mod tests {
         let mut health2 = CredentialHealth::healthy();
         health2.is_low_balance = true;
         assert!(health2.has_warning());
+
+        let mut health3 = CredentialHealth::healthy();
+        health3.needs_rotation = true;
+        assert!(health3.has_warning());
+    }
+
+    #[test]
+    fn test_credential_health_from_pool_credential() {
+        use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+        let mut credential = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "key".to_string(),
+                base_url: None,
+            },
+        );
+        assert!(!CredentialHealth::from_pool_credential(&credential).has_warning());
+
+        credential.needs_rotation = true;
+        let health = CredentialHealth::from_pool_credential(&credential);
+        assert!(health.is_valid);
+        assert!(health.needs_rotation);
+        assert!(health.has_warning());
     }
 
     #[test]
@@ -248,6 +292,7 @@ mod tests {
                 is_valid: true,
                 is_expiring_soon: true,
                 is_low_balance: false,
+                needs_rotation: false,
             },
         ];
         let status = calculate_icon_status(true, &credentials);
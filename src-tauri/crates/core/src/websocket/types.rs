@@ -19,6 +19,9 @@ pub struct WsConnection {
     pub request_count: u64,
     /// 连接状态
     pub status: WsConnectionStatus,
+    /// 认证身份（API Key 掩码），用于按身份统计和限制连接
+    #[serde(default)]
+    pub identity: Option<String>,
 }
 
 impl WsConnection {
@@ -30,15 +33,27 @@ impl WsConnection {
             client_info,
             request_count: 0,
             status: WsConnectionStatus::Connected,
+            identity: None,
         }
     }
 
+    /// 设置认证身份
+    pub fn with_identity(mut self, identity: Option<String>) -> Self {
+        self.identity = identity;
+        self
+    }
+
     /// 增加请求计数
     pub fn increment_request_count(&mut self) {
         self.request_count += 1;
     }
 }
 
+/// 由 API Key 生成连接的认证身份（不含完整密钥）
+pub fn api_key_identity(api_key: &str) -> String {
+    format!("api_key:{}", crate::app_utils::mask_token(api_key))
+}
+
 /// WebSocket 连接状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -75,6 +90,8 @@ pub enum WsMessage {
     UnsubscribeKiroEvents,
     /// Kiro 凭证状态事件通知
     KiroCredentialEvent(WsKiroEvent),
+    /// 认证消息（升级时声明 `auth=frame` 的连接必须以此作为第一条消息）
+    Auth { api_key: String },
 }
 
 /// WebSocket API 请求
@@ -7,6 +7,7 @@
 //! - Workspace CRUD 操作
 //! - 与 Aster Session 通过 working_dir 关联
 //! - Workspace 级别的配置管理
+//! - 文件访问路径沙箱（`Workspace::resolve_safe`）
 //!
 //! ## 设计原则
 //! - 读共享，写隔离
@@ -14,7 +15,9 @@
 //! - Workspace = 边界（文件系统 + context + 配置）
 
 mod manager;
+mod sandbox;
 mod types;
 
 pub use manager::WorkspaceManager;
+pub use sandbox::{resolve_within, WorkspaceError};
 pub use types::{Workspace, WorkspaceId, WorkspaceSettings, WorkspaceType, WorkspaceUpdate};
@@ -0,0 +1,154 @@
+//! Workspace 路径沙箱
+//!
+//! 文件读写类工具只允许访问当前 workspace 根目录内的路径。
+//! 路径会先规范化（解析 `..` 与符号链接）再与根目录比较，
+//! 因此 `../../.ssh/id_rsa` 或指向根目录外的符号链接都会被拒绝。
+
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+use super::Workspace;
+
+/// Workspace 路径错误
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WorkspaceError {
+    /// 路径超出 workspace 根目录
+    #[error("路径超出 workspace 范围: {0}")]
+    PathEscapesRoot(String),
+    /// 根目录不存在或无法访问
+    #[error("workspace 根目录不可用: {0}")]
+    RootUnavailable(String),
+    /// 路径解析失败
+    #[error("路径解析失败: {0}")]
+    Io(String),
+}
+
+impl Workspace {
+    /// 将路径解析为 workspace 内的绝对路径
+    ///
+    /// 相对路径相对于 `root_path` 解析；解析结果不在根目录内时返回
+    /// `WorkspaceError::PathEscapesRoot`。
+    pub fn resolve_safe(&self, path: impl AsRef<Path>) -> Result<PathBuf, WorkspaceError> {
+        resolve_within(&self.root_path, path.as_ref())
+    }
+}
+
+/// 在根目录内解析路径
+///
+/// 逐段解析路径：已存在的部分通过 `canonicalize` 解析符号链接，
+/// 尚不存在的部分（如待创建的文件）直接拼接，`..` 作用于已解析的父目录。
+pub fn resolve_within(root: &Path, path: &Path) -> Result<PathBuf, WorkspaceError> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| WorkspaceError::RootUnavailable(format!("{}: {e}", root.display())))?;
+
+    let mut resolved = if path.is_absolute() {
+        PathBuf::new()
+    } else {
+        root.clone()
+    };
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => resolved.push(component),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                if resolved.exists() {
+                    resolved = resolved
+                        .canonicalize()
+                        .map_err(|e| WorkspaceError::Io(format!("{}: {e}", resolved.display())))?;
+                } else if resolved.is_symlink() {
+                    // 悬空符号链接的目标无法确认，直接拒绝
+                    return Err(WorkspaceError::PathEscapesRoot(path.display().to_string()));
+                }
+            }
+        }
+    }
+
+    if resolved.starts_with(&root) {
+        Ok(resolved)
+    } else {
+        tracing::warn!(
+            "[WORKSPACE] 拒绝访问 workspace 之外的路径: {} (root: {})",
+            path.display(),
+            root.display()
+        );
+        Err(WorkspaceError::PathEscapesRoot(path.display().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("workspace");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(temp.path().join("secret.txt"), "secret").unwrap();
+        (temp, root)
+    }
+
+    #[test]
+    fn test_in_root_access() {
+        let (_temp, root) = setup();
+        let canonical_root = root.canonicalize().unwrap();
+
+        let resolved = resolve_within(&root, Path::new("src/main.rs")).unwrap();
+        assert_eq!(resolved, canonical_root.join("src/main.rs"));
+
+        // 绝对路径与尚不存在的文件
+        let resolved = resolve_within(&root, &root.join("src/new/lib.rs")).unwrap();
+        assert_eq!(resolved, canonical_root.join("src/new/lib.rs"));
+
+        // 根目录内部的 `..` 允许
+        let resolved = resolve_within(&root, Path::new("src/../src/main.rs")).unwrap();
+        assert_eq!(resolved, canonical_root.join("src/main.rs"));
+    }
+
+    #[test]
+    fn test_parent_traversal_rejected() {
+        let (_temp, root) = setup();
+
+        assert!(matches!(
+            resolve_within(&root, Path::new("../secret.txt")),
+            Err(WorkspaceError::PathEscapesRoot(_))
+        ));
+        assert!(matches!(
+            resolve_within(&root, Path::new("src/../../secret.txt")),
+            Err(WorkspaceError::PathEscapesRoot(_))
+        ));
+        // 不存在的路径中的 `..` 同样拒绝
+        assert!(matches!(
+            resolve_within(&root, Path::new("missing/../../secret.txt")),
+            Err(WorkspaceError::PathEscapesRoot(_))
+        ));
+        assert!(matches!(
+            resolve_within(&root, Path::new("/etc/passwd")),
+            Err(WorkspaceError::PathEscapesRoot(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_rejected() {
+        let (temp, root) = setup();
+        std::os::unix::fs::symlink(temp.path(), root.join("escape")).unwrap();
+
+        assert!(matches!(
+            resolve_within(&root, Path::new("escape/secret.txt")),
+            Err(WorkspaceError::PathEscapesRoot(_))
+        ));
+        // 通过符号链接写入尚不存在的文件也拒绝
+        assert!(matches!(
+            resolve_within(&root, Path::new("escape/new.txt")),
+            Err(WorkspaceError::PathEscapesRoot(_))
+        ));
+    }
+}
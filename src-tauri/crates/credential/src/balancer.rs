@@ -1,6 +1,6 @@
 //! 负载均衡器实现
 //!
-//! 提供轮询负载均衡策略，支持凭证冷却和自动恢复
+//! 提供轮询、加权轮询、最少使用和随机负载均衡策略，支持凭证冷却和自动恢复
 
 use chrono::{DateTime, Duration, Utc};
 use dashmap::DashMap;
@@ -11,6 +11,7 @@ use proxycast_core::ProviderType;
 use proxycast_infra::ProxyClientFactory;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
@@ -25,6 +26,8 @@ pub enum BalanceStrategy {
     LeastUsed,
     /// 随机策略
     Random,
+    /// 加权轮询策略（平滑加权轮询，按凭证 `weight` 成比例分配）
+    Weighted,
 }
 
 /// 冷却信息
@@ -53,6 +56,8 @@ pub struct LoadBalancer {
     pools: DashMap<ProviderType, Arc<CredentialPool>>,
     /// 轮询索引（每个 Provider 独立）
     round_robin_indices: DashMap<ProviderType, AtomicUsize>,
+    /// 加权轮询的当前权重（每个 Provider 独立，凭证 ID -> 当前权重）
+    weighted_currents: DashMap<ProviderType, HashMap<String, i64>>,
     /// 健康检查器
     health_checker: HealthChecker,
     /// 代理客户端工厂
@@ -66,6 +71,7 @@ impl LoadBalancer {
             strategy,
             pools: DashMap::new(),
             round_robin_indices: DashMap::new(),
+            weighted_currents: DashMap::new(),
             health_checker: HealthChecker::with_defaults(),
             proxy_factory: ProxyClientFactory::new(),
         }
@@ -82,6 +88,7 @@ impl LoadBalancer {
             strategy,
             pools: DashMap::new(),
             round_robin_indices: DashMap::new(),
+            weighted_currents: DashMap::new(),
             health_checker: HealthChecker::new(health_config),
             proxy_factory: ProxyClientFactory::new(),
         }
@@ -134,6 +141,7 @@ impl LoadBalancer {
     /// 移除凭证池
     pub fn remove_pool(&self, provider: ProviderType) -> Option<Arc<CredentialPool>> {
         self.round_robin_indices.remove(&provider);
+        self.weighted_currents.remove(&provider);
         self.pools.remove(&provider).map(|(_, pool)| pool)
     }
 
@@ -143,6 +151,8 @@ impl LoadBalancer {
     }
 
     /// 选择下一个可用凭证（使用当前策略）
+    ///
+    /// 跳过已达并发上限的凭证；选择本身不占用并发名额。
     pub fn select(&self, provider: ProviderType) -> Result<Credential, PoolError> {
         let pool = self.pools.get(&provider).ok_or(PoolError::EmptyPool)?;
         pool.refresh_cooldowns();
@@ -150,6 +160,7 @@ impl LoadBalancer {
             BalanceStrategy::RoundRobin => self.select_round_robin(&pool, provider),
             BalanceStrategy::LeastUsed => self.select_least_used(&pool),
             BalanceStrategy::Random => self.select_random(&pool),
+            BalanceStrategy::Weighted => self.select_weighted(&pool, provider),
         }
     }
 
@@ -251,11 +262,7 @@ impl LoadBalancer {
         pool: &CredentialPool,
         provider: ProviderType,
     ) -> Result<Credential, PoolError> {
-        let active_creds: Vec<Credential> = pool
-            .all()
-            .into_iter()
-            .filter(|c| c.is_available())
-            .collect();
+        let active_creds = pool.selectable();
 
         if active_creds.is_empty() {
             return Err(PoolError::NoAvailableCredential);
@@ -270,22 +277,55 @@ impl LoadBalancer {
         Ok(active_creds[index].clone())
     }
 
+    /// 平滑加权轮询选择凭证（与 nginx 的算法相同）
+    ///
+    /// 每次选择时所有候选的当前权重加上各自的权重，选中当前权重最大的凭证，
+    /// 再从它的当前权重中减去候选权重总和。权重为 5:1:1 时选择序列为
+    /// `a a b a c a a`，高权重凭证不会被连续集中选中。候选按 ID 排序，
+    /// 相同的权重和调用次数总是得到相同的结果。
+    fn select_weighted(
+        &self,
+        pool: &CredentialPool,
+        provider: ProviderType,
+    ) -> Result<Credential, PoolError> {
+        let mut active_creds = pool.selectable();
+        if active_creds.is_empty() {
+            return Err(PoolError::NoAvailableCredential);
+        }
+        active_creds.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut currents = self.weighted_currents.entry(provider).or_default();
+        let mut total = 0i64;
+        let mut selected: Option<(usize, i64)> = None;
+        for (index, cred) in active_creds.iter().enumerate() {
+            let weight = i64::from(cred.weight.max(1));
+            total += weight;
+            let current = currents.entry(cred.id.clone()).or_insert(0);
+            *current += weight;
+            if selected.is_none_or(|(_, best)| *current > best) {
+                selected = Some((index, *current));
+            }
+        }
+
+        let (index, _) = selected.ok_or(PoolError::NoAvailableCredential)?;
+        let chosen = active_creds.swap_remove(index);
+        if let Some(current) = currents.get_mut(&chosen.id) {
+            *current -= total;
+        }
+        Ok(chosen)
+    }
+
     /// 最少使用选择凭证
     fn select_least_used(&self, pool: &CredentialPool) -> Result<Credential, PoolError> {
-        pool.all()
+        pool.selectable()
             .into_iter()
-            .filter(|c| c.is_available())
             .min_by_key(|c| c.stats.total_requests)
             .ok_or(PoolError::NoAvailableCredential)
     }
 
     /// 随机选择凭证
     fn select_random(&self, pool: &CredentialPool) -> Result<Credential, PoolError> {
-        let active_creds: Vec<Credential> = pool
-            .all()
-            .into_iter()
-            .filter(|c| c.is_available())
-            .collect();
+        let active_creds = pool.selectable();
 
         if active_creds.is_empty() {
             return Err(PoolError::NoAvailableCredential);
@@ -424,6 +464,77 @@ mod balancer_tests {
         assert_eq!(ids.len(), 3);
     }
 
+    #[test]
+    fn test_load_balancer_skips_credentials_at_concurrency_cap() {
+        for strategy in [
+            BalanceStrategy::RoundRobin,
+            BalanceStrategy::LeastUsed,
+            BalanceStrategy::Random,
+            BalanceStrategy::Weighted,
+        ] {
+            let lb = LoadBalancer::new(strategy);
+            let pool = Arc::new(CredentialPool::new(ProviderType::Kiro));
+            pool.add(
+                create_test_credential("kiro-1", ProviderType::Kiro).with_max_concurrency(Some(2)),
+            )
+            .unwrap();
+            lb.register_pool(pool.clone());
+
+            // 选择本身不占用名额
+            assert_eq!(lb.select(ProviderType::Kiro).unwrap().id, "kiro-1");
+            assert_eq!(lb.select(ProviderType::Kiro).unwrap().id, "kiro-1");
+            assert_eq!(pool.in_flight("kiro-1"), 0);
+
+            // 两个请求占满名额后，第三个请求没有可用凭证
+            let cred = pool.get("kiro-1").unwrap();
+            assert!(pool.try_acquire(&cred));
+            assert!(pool.try_acquire(&cred));
+            assert!(matches!(
+                lb.select(ProviderType::Kiro),
+                Err(PoolError::NoAvailableCredential)
+            ));
+
+            pool.release("kiro-1");
+            assert_eq!(lb.select(ProviderType::Kiro).unwrap().id, "kiro-1");
+        }
+    }
+
+    #[test]
+    fn test_load_balancer_weighted_round_robin() {
+        let lb = LoadBalancer::new(BalanceStrategy::Weighted);
+        let pool = Arc::new(CredentialPool::new(ProviderType::Kiro));
+        pool.add(create_test_credential("a-paid", ProviderType::Kiro).with_weight(5))
+            .unwrap();
+        pool.add(create_test_credential("b-free", ProviderType::Kiro))
+            .unwrap();
+        pool.add(create_test_credential("c-free", ProviderType::Kiro))
+            .unwrap();
+        lb.register_pool(pool.clone());
+
+        let select = || lb.select(ProviderType::Kiro).unwrap().id;
+
+        // 平滑加权轮询：5:1:1 的一个周期
+        let cycle: Vec<String> = (0..7).map(|_| select()).collect();
+        assert_eq!(
+            cycle,
+            vec!["a-paid", "a-paid", "b-free", "a-paid", "c-free", "a-paid", "a-paid"]
+        );
+        // 下一个周期重复同样的序列
+        let next: Vec<String> = (0..7).map(|_| select()).collect();
+        assert_eq!(next, cycle);
+
+        // 低权重凭证不会被完全禁用
+        let counts = (0..70)
+            .map(|_| select())
+            .fold(HashMap::new(), |mut acc, id| {
+                *acc.entry(id).or_insert(0) += 1;
+                acc
+            });
+        assert_eq!(counts["a-paid"], 50);
+        assert_eq!(counts["b-free"], 10);
+        assert_eq!(counts["c-free"], 10);
+    }
+
     #[test]
     fn test_load_balancer_select_empty_pool() {
         let lb = LoadBalancer::round_robin();
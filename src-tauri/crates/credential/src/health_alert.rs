@@ -0,0 +1,399 @@
+//! 凭证健康告警
+//!
+//! 跟踪每个 Provider 类型的健康凭证数：降为 0 时发出告警（`firing`），
+//! 恢复到至少 1 个时发出解除通知（`resolved`）。通知通过前端事件
+//! `provider-health-alert` 发送，并在配置了 Webhook 时 POST 到该地址。
+//!
+//! 同一 Provider 两次通知之间至少间隔 `debounce_secs`，间隔内的状态变化
+//! 不会立即通知，而是记为待定，在间隔结束时按最后一次观测到的状态补发；
+//! 若状态在间隔内回到已通知的状态，则取消待定通知。
+
+use chrono::{DateTime, Utc};
+use proxycast_core::config::HealthAlertConfig;
+use proxycast_core::DynEmitter;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 健康告警前端事件名
+pub const HEALTH_ALERT_EVENT: &str = "provider-health-alert";
+
+/// 告警状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthAlertStatus {
+    /// 所有凭证均不健康
+    Firing,
+    /// 已恢复至少一个健康凭证
+    Resolved,
+}
+
+/// 健康告警通知
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthAlert {
+    /// Provider 类型
+    pub provider_type: String,
+    /// 告警状态
+    pub status: HealthAlertStatus,
+    /// 健康凭证数
+    pub healthy: usize,
+    /// 启用的凭证总数
+    pub total: usize,
+    /// 触发原因（最后一次错误信息）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// 通知时间
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 防抖间隔内被推迟的观测
+#[derive(Debug, Clone)]
+struct PendingObservation {
+    healthy: usize,
+    total: usize,
+    reason: Option<String>,
+}
+
+/// 单个 Provider 的告警状态
+#[derive(Debug, Default)]
+struct ProviderAlertState {
+    /// 最近一次通知是否为告警
+    firing: bool,
+    /// 最近一次通知时间
+    last_notified: Option<Instant>,
+    /// 防抖间隔内尚未通知的状态变化
+    pending: Option<PendingObservation>,
+    /// 是否已安排间隔结束时的补发
+    flush_scheduled: bool,
+}
+
+/// 凭证健康告警监视器
+pub struct HealthAlertMonitor {
+    config: RwLock<HealthAlertConfig>,
+    states: Mutex<HashMap<String, ProviderAlertState>>,
+    emitter: RwLock<Option<DynEmitter>>,
+    client: reqwest::Client,
+}
+
+impl Default for HealthAlertMonitor {
+    fn default() -> Self {
+        Self::new(HealthAlertConfig::default())
+    }
+}
+
+impl HealthAlertMonitor {
+    pub fn new(config: HealthAlertConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            states: Mutex::new(HashMap::new()),
+            emitter: RwLock::new(None),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 更新配置
+    pub fn set_config(&self, config: HealthAlertConfig) {
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+    }
+
+    /// 设置前端事件发射器
+    pub fn set_emitter(&self, emitter: DynEmitter) {
+        if let Ok(mut current) = self.emitter.write() {
+            *current = Some(emitter);
+        }
+    }
+
+    fn debounce(&self) -> Duration {
+        self.config
+            .read()
+            .map(|c| Duration::from_secs(c.debounce_secs))
+            .unwrap_or_default()
+    }
+
+    /// 记录一次健康状态观测，状态变化需要通知时发送并返回通知内容
+    ///
+    /// 处于防抖间隔内的状态变化会在间隔结束时补发（需要 Tokio 运行时）。
+    pub fn observe(
+        self: &Arc<Self>,
+        provider_type: &str,
+        healthy: usize,
+        total: usize,
+        reason: Option<&str>,
+    ) -> Option<HealthAlert> {
+        let now = Instant::now();
+        let alert = self.evaluate(provider_type, healthy, total, reason, now);
+        match &alert {
+            Some(alert) => self.notify(alert),
+            None => self.schedule_flush(provider_type, now),
+        }
+        alert
+    }
+
+    /// 为待定的状态变化安排间隔结束时的补发
+    fn schedule_flush(self: &Arc<Self>, provider_type: &str, now: Instant) {
+        let Some(delay) = self.flush_delay(provider_type, now) else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let monitor = Arc::clone(self);
+        let provider_type = provider_type.to_string();
+        runtime.spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Some(alert) = monitor.flush_pending(&provider_type, Instant::now()) {
+                monitor.notify(&alert);
+            }
+            // 仍有待定变化（如期间又产生了新通知）时继续等待下一个间隔
+            monitor.schedule_flush(&provider_type, Instant::now());
+        });
+    }
+
+    /// 有待定变化且尚未安排补发时，返回距间隔结束的时长并标记为已安排
+    fn flush_delay(&self, provider_type: &str, now: Instant) -> Option<Duration> {
+        let debounce = self.debounce();
+        let mut states = self.states.lock().ok()?;
+        let state = states.get_mut(provider_type)?;
+        if state.pending.is_none() || state.flush_scheduled {
+            return None;
+        }
+        state.flush_scheduled = true;
+        let deadline = state.last_notified? + debounce;
+        Some(deadline.saturating_duration_since(now))
+    }
+
+    /// 防抖间隔结束后按待定的观测重新计算通知
+    fn flush_pending(&self, provider_type: &str, now: Instant) -> Option<HealthAlert> {
+        let pending = {
+            let mut states = self.states.lock().ok()?;
+            let state = states.get_mut(provider_type)?;
+            state.flush_scheduled = false;
+            state.pending.take()?
+        };
+        self.evaluate(
+            provider_type,
+            pending.healthy,
+            pending.total,
+            pending.reason.as_deref(),
+            now,
+        )
+    }
+
+    /// 计算是否需要通知（不发送）
+    fn evaluate(
+        &self,
+        provider_type: &str,
+        healthy: usize,
+        total: usize,
+        reason: Option<&str>,
+        now: Instant,
+    ) -> Option<HealthAlert> {
+        let debounce = self.debounce();
+        let mut states = self.states.lock().ok()?;
+        let state = states.entry(provider_type.to_string()).or_default();
+
+        let firing = total > 0 && healthy == 0;
+        if firing == state.firing {
+            // 状态回到已通知的状态，取消待定通知
+            state.pending = None;
+            return None;
+        }
+        if let Some(last) = state.last_notified {
+            if now.saturating_duration_since(last) < debounce {
+                tracing::debug!(
+                    "[HEALTH_ALERT] {} 状态变化处于防抖间隔内，间隔结束后补发",
+                    provider_type
+                );
+                state.pending = Some(PendingObservation {
+                    healthy,
+                    total,
+                    reason: reason.map(str::to_string),
+                });
+                return None;
+            }
+        }
+
+        state.firing = firing;
+        state.last_notified = Some(now);
+        state.pending = None;
+        Some(HealthAlert {
+            provider_type: provider_type.to_string(),
+            status: if firing {
+                HealthAlertStatus::Firing
+            } else {
+                HealthAlertStatus::Resolved
+            },
+            healthy,
+            total,
+            reason: if firing {
+                reason.map(str::to_string)
+            } else {
+                None
+            },
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// 发送前端事件和 Webhook
+    fn notify(&self, alert: &HealthAlert) {
+        match alert.status {
+            HealthAlertStatus::Firing => tracing::warn!(
+                "[HEALTH_ALERT] {} 已无健康凭证（共 {} 个）: {}",
+                alert.provider_type,
+                alert.total,
+                alert.reason.as_deref().unwrap_or("-")
+            ),
+            HealthAlertStatus::Resolved => tracing::info!(
+                "[HEALTH_ALERT] {} 已恢复，健康凭证 {}/{}",
+                alert.provider_type,
+                alert.healthy,
+                alert.total
+            ),
+        }
+
+        let payload = serde_json::to_value(alert).unwrap_or_default();
+        if let Some(emitter) = self.emitter.read().ok().and_then(|e| e.clone()) {
+            if let Err(e) = emitter.emit_event(HEALTH_ALERT_EVENT, &payload) {
+                tracing::warn!("[HEALTH_ALERT] 发送告警事件失败: {}", e);
+            }
+        }
+
+        let webhook_url = self
+            .config
+            .read()
+            .ok()
+            .and_then(|c| c.webhook_url.clone())
+            .filter(|url| !url.trim().is_empty());
+        let (Some(url), Ok(runtime)) = (webhook_url, tokio::runtime::Handle::try_current()) else {
+            return;
+        };
+        let client = self.client.clone();
+        runtime.spawn(async move {
+            match client.post(&url).json(&payload).send().await {
+                Ok(resp) if !resp.status().is_success() => {
+                    tracing::warn!("[HEALTH_ALERT] Webhook 返回 {}: {}", resp.status(), url);
+                }
+                Err(e) => tracing::warn!("[HEALTH_ALERT] Webhook 发送失败: {}", e),
+                Ok(_) => {}
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::event_emit::EventEmit;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct RecordingEmitter(Arc<Mutex<Vec<serde_json::Value>>>);
+
+    impl EventEmit for RecordingEmitter {
+        fn emit_event(&self, event: &str, payload: &serde_json::Value) -> Result<(), String> {
+            assert_eq!(event, HEALTH_ALERT_EVENT);
+            self.0.lock().unwrap().push(payload.clone());
+            Ok(())
+        }
+    }
+
+    fn monitor(debounce_secs: u64) -> Arc<HealthAlertMonitor> {
+        Arc::new(HealthAlertMonitor::new(HealthAlertConfig {
+            webhook_url: None,
+            debounce_secs,
+        }))
+    }
+
+    #[test]
+    fn test_alert_fires_on_zero_healthy_and_resolves_on_recovery() {
+        let monitor = monitor(0);
+        let events = RecordingEmitter::default();
+        monitor.set_emitter(DynEmitter::new(events.clone()));
+
+        // 仍有健康凭证时不告警
+        assert!(monitor.observe("kiro", 1, 2, Some("429")).is_none());
+
+        let alert = monitor
+            .observe("kiro", 0, 2, Some("401 Unauthorized"))
+            .unwrap();
+        assert_eq!(alert.status, HealthAlertStatus::Firing);
+        assert_eq!(alert.reason.as_deref(), Some("401 Unauthorized"));
+        // 持续不健康不重复告警
+        assert!(monitor.observe("kiro", 0, 2, Some("401")).is_none());
+        // 其他 Provider 互不影响
+        assert!(monitor.observe("gemini", 1, 1, None).is_none());
+
+        let resolved = monitor.observe("kiro", 1, 2, None).unwrap();
+        assert_eq!(resolved.status, HealthAlertStatus::Resolved);
+        assert!(resolved.reason.is_none());
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["status"], "firing");
+        assert_eq!(events[0]["provider_type"], "kiro");
+        assert_eq!(events[1]["status"], "resolved");
+    }
+
+    #[test]
+    fn test_alert_debounces_flapping() {
+        let monitor = monitor(60);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let alert = monitor.evaluate("kiro", 0, 1, Some("err"), at(0)).unwrap();
+        assert_eq!(alert.status, HealthAlertStatus::Firing);
+
+        // 防抖间隔内的恢复和再次失效都不通知
+        assert!(monitor.evaluate("kiro", 1, 1, None, at(10)).is_none());
+        assert!(monitor
+            .evaluate("kiro", 0, 1, Some("err"), at(20))
+            .is_none());
+        assert!(monitor.evaluate("kiro", 1, 1, None, at(30)).is_none());
+
+        // 间隔结束后按最后一次观测补发解除通知，无需新的观测
+        let resolved = monitor.flush_pending("kiro", at(61)).unwrap();
+        assert_eq!(resolved.status, HealthAlertStatus::Resolved);
+        assert!(monitor.flush_pending("kiro", at(62)).is_none());
+
+        // 没有凭证时不视为告警
+        assert!(monitor.evaluate("gemini", 0, 0, None, at(0)).is_none());
+    }
+
+    #[test]
+    fn test_debounced_change_cancelled_when_state_settles_back() {
+        let monitor = monitor(60);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(monitor.evaluate("kiro", 0, 1, Some("err"), at(0)).is_some());
+        // 间隔内短暂恢复后又失效，最终状态与已通知的一致，不补发
+        assert!(monitor.evaluate("kiro", 1, 1, None, at(10)).is_none());
+        assert!(monitor
+            .evaluate("kiro", 0, 1, Some("err"), at(20))
+            .is_none());
+        assert!(monitor.flush_pending("kiro", at(61)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_observe_flushes_pending_change_after_debounce() {
+        let monitor = monitor(1);
+        let events = RecordingEmitter::default();
+        monitor.set_emitter(DynEmitter::new(events.clone()));
+
+        assert!(monitor.observe("kiro", 0, 1, Some("err")).is_some());
+        // 防抖间隔内恢复后不再有新的观测
+        assert!(monitor.observe("kiro", 1, 1, None).is_none());
+        assert_eq!(events.0.lock().unwrap().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1]["status"], "resolved");
+    }
+}
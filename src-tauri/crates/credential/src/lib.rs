@@ -4,16 +4,20 @@
 //!
 //! ## 模块结构
 //!
-//! - `balancer` - 负载均衡策略（轮询、最少使用、随机）
+//! - `health_alert` - 凭证健康告警（Provider 无健康凭证时通知）
+//! - `balancer` - 负载均衡策略（轮询、最少使用、随机、加权轮询）；按延迟的最快优先选择由
+//!   `proxycast_services` 的 `ProviderPoolService::select_fastest` 实现
 //! - `quota` - 配额超限检测、自动切换和冷却恢复
 //! - `sync` - 凭证与 YAML 配置文件的同步
 
 mod balancer;
+mod health_alert;
 mod quota;
 mod sync;
 
 // 重新导出
 pub use balancer::{BalanceStrategy, CooldownInfo, CredentialSelection, LoadBalancer};
+pub use health_alert::{HealthAlert, HealthAlertMonitor, HealthAlertStatus, HEALTH_ALERT_EVENT};
 pub use quota::{
     create_shared_quota_manager, start_quota_cleanup_task, AllCredentialsExhaustedError,
     QuotaAutoSwitchResult, QuotaExceededRecord, QuotaManager,
@@ -2,7 +2,7 @@
 //!
 //! 提供配额超限检测、自动切换和冷却恢复功能
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use dashmap::DashMap;
 use proxycast_core::config::QuotaExceededConfig;
 use proxycast_infra::resilience::{QUOTA_EXCEEDED_KEYWORDS, QUOTA_EXCEEDED_STATUS_CODES};
@@ -22,6 +22,13 @@ pub struct QuotaExceededRecord {
     pub reason: String,
 }
 
+/// 凭证当日 Token 用量（按 UTC 日期）
+#[derive(Debug, Clone, Copy)]
+struct DailyTokenUsage {
+    day: NaiveDate,
+    tokens: u64,
+}
+
 /// 配额管理器
 #[derive(Debug)]
 pub struct QuotaManager {
@@ -29,6 +36,8 @@ pub struct QuotaManager {
     config: QuotaExceededConfig,
     /// 超限凭证记录（credential_id -> record）
     exceeded_credentials: DashMap<String, QuotaExceededRecord>,
+    /// 当日 Token 用量（credential_id -> usage）
+    daily_usage: DashMap<String, DailyTokenUsage>,
 }
 
 impl QuotaManager {
@@ -37,6 +46,7 @@ impl QuotaManager {
         Self {
             config,
             exceeded_credentials: DashMap::new(),
+            daily_usage: DashMap::new(),
         }
     }
 
@@ -87,9 +97,13 @@ impl QuotaManager {
 
     /// 检查凭证是否可用（未超限或已过冷却期）
     pub fn is_available(&self, credential_id: &str) -> bool {
+        self.is_available_at(credential_id, Utc::now())
+    }
+
+    /// 以指定时间检查凭证是否可用
+    pub fn is_available_at(&self, credential_id: &str, now: DateTime<Utc>) -> bool {
         match self.exceeded_credentials.get(credential_id) {
             Some(record) => {
-                let now = Utc::now();
                 if now >= record.cooldown_until {
                     drop(record);
                     self.exceeded_credentials.remove(credential_id);
@@ -102,6 +116,79 @@ impl QuotaManager {
         }
     }
 
+    /// 记录凭证消耗的 Token 数（按 UTC 日累计）
+    ///
+    /// 设置了 `daily_token_limit` 且当日累计达到上限时，凭证被标记为超限，
+    /// 冷却至下一个 UTC 零点；返回新产生的超限记录。
+    pub fn record_daily_tokens(
+        &self,
+        credential_id: &str,
+        tokens: u64,
+        daily_token_limit: Option<u64>,
+    ) -> Option<QuotaExceededRecord> {
+        self.record_daily_tokens_at(credential_id, tokens, daily_token_limit, Utc::now())
+    }
+
+    /// 以指定时间记录凭证消耗的 Token 数
+    pub fn record_daily_tokens_at(
+        &self,
+        credential_id: &str,
+        tokens: u64,
+        daily_token_limit: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> Option<QuotaExceededRecord> {
+        let today = now.date_naive();
+        let used = {
+            let mut usage =
+                self.daily_usage
+                    .entry(credential_id.to_string())
+                    .or_insert(DailyTokenUsage {
+                        day: today,
+                        tokens: 0,
+                    });
+            if usage.day != today {
+                *usage = DailyTokenUsage {
+                    day: today,
+                    tokens: 0,
+                };
+            }
+            usage.tokens = usage.tokens.saturating_add(tokens);
+            usage.tokens
+        };
+
+        let limit = daily_token_limit.filter(|limit| *limit > 0)?;
+        if used < limit || self.exceeded_credentials.contains_key(credential_id) {
+            return None;
+        }
+
+        let record = QuotaExceededRecord {
+            credential_id: credential_id.to_string(),
+            exceeded_at: now,
+            cooldown_until: next_utc_midnight(now),
+            reason: format!("daily token limit reached: {used}/{limit}"),
+        };
+        self.exceeded_credentials
+            .insert(credential_id.to_string(), record.clone());
+        tracing::info!(
+            credential_id = %credential_id,
+            used = used,
+            limit = limit,
+            cooldown_until = %record.cooldown_until,
+            "凭证达到每日 Token 上限，暂停至 UTC 零点"
+        );
+        Some(record)
+    }
+
+    /// 获取凭证当日已消耗的 Token 数
+    pub fn daily_tokens_used(&self, credential_id: &str) -> u64 {
+        let today = Utc::now().date_naive();
+        self.daily_usage
+            .get(credential_id)
+            .filter(|usage| usage.day == today)
+            .map(|usage| usage.tokens)
+            .unwrap_or(0)
+    }
+
     /// 获取凭证的冷却结束时间
     pub fn get_cooldown_until(&self, credential_id: &str) -> Option<DateTime<Utc>> {
         self.exceeded_credentials
@@ -241,6 +328,15 @@ impl Default for QuotaManager {
     }
 }
 
+/// 下一个 UTC 零点
+fn next_utc_midnight(now: DateTime<Utc>) -> DateTime<Utc> {
+    let tomorrow = now.date_naive().succ_opt().unwrap_or(now.date_naive());
+    tomorrow
+        .and_hms_opt(0, 0, 0)
+        .map(|t| t.and_utc())
+        .unwrap_or(now)
+}
+
 /// 创建共享的配额管理器
 pub fn create_shared_quota_manager(config: QuotaExceededConfig) -> Arc<QuotaManager> {
     Arc::new(QuotaManager::new(config))
@@ -497,6 +593,7 @@ impl QuotaManager {
 #[cfg(test)]
 mod unit_tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_quota_auto_switch_result_switched() {
@@ -634,6 +731,48 @@ mod unit_tests {
         assert_eq!(available, vec!["cred-2".to_string(), "cred-4".to_string()]);
     }
 
+    #[test]
+    fn test_daily_token_limit_drops_and_reinstates_credential() {
+        let manager = QuotaManager::with_defaults();
+        let all = vec!["cred-1".to_string(), "cred-2".to_string()];
+        let morning = Utc.with_ymd_and_hms(2025, 1, 10, 8, 0, 0).unwrap();
+
+        assert!(manager
+            .record_daily_tokens_at("cred-1", 600, Some(1000), morning)
+            .is_none());
+        let record = manager
+            .record_daily_tokens_at("cred-1", 500, Some(1000), morning + Duration::hours(2))
+            .expect("should exceed daily limit");
+        assert_eq!(
+            record.cooldown_until,
+            Utc.with_ymd_and_hms(2025, 1, 11, 0, 0, 0).unwrap()
+        );
+
+        // 超限后当日不再被选中
+        let evening = morning + Duration::hours(12);
+        assert!(!manager.is_available_at("cred-1", evening));
+        assert!(manager.is_available_at("cred-2", evening));
+
+        // 跨过 UTC 零点后恢复，用量重新计数
+        let next_day = Utc.with_ymd_and_hms(2025, 1, 11, 0, 0, 1).unwrap();
+        assert!(manager.is_available_at("cred-1", next_day));
+        assert!(manager
+            .record_daily_tokens_at("cred-1", 600, Some(1000), next_day)
+            .is_none());
+        assert_eq!(manager.filter_available_credentials(&all), all);
+    }
+
+    #[test]
+    fn test_daily_token_usage_without_limit() {
+        let manager = QuotaManager::with_defaults();
+        assert!(manager.record_daily_tokens("cred-1", 5_000, None).is_none());
+        assert!(manager
+            .record_daily_tokens("cred-1", 5_000, Some(0))
+            .is_none());
+        assert_eq!(manager.daily_tokens_used("cred-1"), 10_000);
+        assert!(manager.is_available("cred-1"));
+    }
+
     #[test]
     fn test_all_credentials_exhausted_error() {
         let error = AllCredentialsExhaustedError::new(None);
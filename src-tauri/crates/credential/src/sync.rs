@@ -210,6 +210,11 @@ impl CredentialSyncService {
                 };
                 config.credential_pool.claude.push(entry);
             }
+            CredentialData::LocalOpenAI { .. } => {
+                return Err(SyncError::InvalidCredentialType(
+                    "本地服务凭证暂不支持同步到配置".to_string(),
+                ));
+            }
         }
 
         self.update_config(config)
@@ -495,6 +500,11 @@ impl CredentialSyncService {
                     found = true;
                 }
             }
+            CredentialData::LocalOpenAI { .. } => {
+                return Err(SyncError::InvalidCredentialType(
+                    "本地服务凭证暂不支持同步到配置".to_string(),
+                ));
+            }
         }
 
         if !found {
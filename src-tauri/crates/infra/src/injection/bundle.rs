@@ -0,0 +1,161 @@
+//! 注入规则包
+//!
+//! 将注入规则导出为可移植的 JSON 包，或从 JSON 包导入：
+//! - 导入前逐条校验（匹配模式可用、参数在白名单内、ID 不重复）
+//! - 任一规则无效时整包拒绝，不会出现部分导入
+//! - 支持替换当前规则集或按 ID 合并
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+use super::types::{ALLOWED_INJECTION_PARAMS, BLOCKED_OVERRIDE_PARAMS};
+use super::{InjectionMode, InjectionRule, Injector};
+
+/// 当前规则包格式版本
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// 注入规则包
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InjectionBundle {
+    /// 格式版本
+    pub version: u32,
+    /// 规则列表
+    pub rules: Vec<InjectionRule>,
+}
+
+/// 规则包错误
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BundleError {
+    /// JSON 解析失败
+    #[error("规则包解析失败: {0}")]
+    Parse(String),
+
+    /// 不支持的版本
+    #[error("不支持的规则包版本: {0}")]
+    UnsupportedVersion(u32),
+
+    /// 规则 ID 重复
+    #[error("规则 ID 重复: {0}")]
+    DuplicateId(String),
+
+    /// 规则无效
+    #[error("规则 '{id}' 无效: {reason}")]
+    InvalidRule { id: String, reason: String },
+}
+
+/// 校验单条规则
+pub fn validate_rule(rule: &InjectionRule) -> Result<(), String> {
+    if rule.id.trim().is_empty() {
+        return Err("规则 ID 不能为空".to_string());
+    }
+    validate_pattern(&rule.pattern)?;
+
+    let params = rule
+        .parameters
+        .as_object()
+        .ok_or_else(|| "parameters 必须是 JSON 对象".to_string())?;
+    if params.is_empty() {
+        return Err("parameters 不能为空".to_string());
+    }
+    for key in params.keys() {
+        if !ALLOWED_INJECTION_PARAMS.contains(&key.as_str()) {
+            return Err(format!("参数 {key} 不在白名单中"));
+        }
+        if rule.mode == InjectionMode::Override && BLOCKED_OVERRIDE_PARAMS.contains(&key.as_str()) {
+            return Err(format!("参数 {key} 禁止使用 Override 模式"));
+        }
+    }
+    Ok(())
+}
+
+/// 校验匹配模式是否为支持的通配符形式
+///
+/// 支持精确、`prefix*`、`*suffix`、`*middle*`、`prefix*suffix`，
+/// 其他形式（如多个中间通配符）永远不会匹配，视为无效。
+fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if pattern.trim().is_empty() {
+        return Err("匹配模式不能为空".to_string());
+    }
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let valid = match parts.as_slice() {
+        [_] | [_, _] => true,
+        ["", middle, ""] => !middle.is_empty(),
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("不支持的匹配模式: {pattern}"))
+    }
+}
+
+/// 导出规则为 JSON 规则包
+pub fn export_rules(rules: &[InjectionRule]) -> String {
+    let bundle = InjectionBundle {
+        version: BUNDLE_VERSION,
+        rules: rules.to_vec(),
+    };
+    serde_json::to_string_pretty(&bundle).unwrap_or_default()
+}
+
+/// 解析并校验规则包
+pub fn parse_bundle(json: &str) -> Result<Vec<InjectionRule>, BundleError> {
+    let bundle: InjectionBundle =
+        serde_json::from_str(json).map_err(|e| BundleError::Parse(e.to_string()))?;
+    if bundle.version != BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(bundle.version));
+    }
+
+    let mut ids = HashSet::with_capacity(bundle.rules.len());
+    for rule in &bundle.rules {
+        validate_rule(rule).map_err(|reason| BundleError::InvalidRule {
+            id: rule.id.clone(),
+            reason,
+        })?;
+        if !ids.insert(rule.id.as_str()) {
+            return Err(BundleError::DuplicateId(rule.id.clone()));
+        }
+    }
+    Ok(bundle.rules)
+}
+
+/// 合并导入的规则
+///
+/// - `merge = false`：以导入的规则替换当前规则集
+/// - `merge = true`：保留当前规则，同 ID 的规则被导入版本覆盖
+pub fn merge_rules(
+    current: &[InjectionRule],
+    imported: Vec<InjectionRule>,
+    merge: bool,
+) -> Vec<InjectionRule> {
+    if !merge {
+        return imported;
+    }
+    let imported_ids: HashSet<&str> = imported.iter().map(|r| r.id.as_str()).collect();
+    let mut rules: Vec<InjectionRule> = current
+        .iter()
+        .filter(|r| !imported_ids.contains(r.id.as_str()))
+        .cloned()
+        .collect();
+    rules.extend(imported);
+    rules
+}
+
+impl Injector {
+    /// 导出当前规则为 JSON 规则包
+    pub fn export_bundle(&self) -> String {
+        export_rules(self.rules())
+    }
+
+    /// 导入 JSON 规则包
+    ///
+    /// 先完整校验再一次性替换规则集，校验失败时注入器保持不变。
+    /// 返回导入的规则数。
+    pub fn import_bundle(&mut self, json: &str, merge: bool) -> Result<usize, BundleError> {
+        let imported = parse_bundle(json)?;
+        let count = imported.len();
+        *self = Injector::with_rules(merge_rules(self.rules(), imported, merge));
+        Ok(count)
+    }
+}
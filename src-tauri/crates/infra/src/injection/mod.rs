@@ -4,9 +4,15 @@
 //! - 模型通配符匹配规则
 //! - merge 和 override 两种注入模式
 //! - 规则优先级排序
+//! - 规则包导入/导出
 
+mod bundle;
 mod types;
 
+pub use bundle::{
+    export_rules, merge_rules, parse_bundle, validate_rule, BundleError, InjectionBundle,
+    BUNDLE_VERSION,
+};
 pub use types::{InjectionConfig, InjectionMode, InjectionResult, InjectionRule, Injector};
 
 #[cfg(test)]
@@ -130,6 +130,24 @@ mod injector_tests {
         assert_eq!(payload["temperature"], 0.7);
     }
 
+    #[test]
+    fn test_inject_filtered_skips_disallowed_rules() {
+        let mut injector = Injector::new();
+        injector.add_rule(InjectionRule::new(
+            "r1",
+            "claude-*",
+            json!({"temperature": 0.7}),
+        ));
+        injector.add_rule(InjectionRule::new("r2", "claude-*", json!({"top_p": 0.9})));
+
+        let mut payload = json!({"model": "claude-sonnet-4-5", "messages": []});
+        let result = injector.inject_filtered("claude-sonnet-4-5", &mut payload, |id| id == "r2");
+
+        assert_eq!(result.applied_rules, vec!["r2"]);
+        assert!(payload.get("temperature").is_none());
+        assert_eq!(payload["top_p"], 0.9);
+    }
+
     #[test]
     fn test_inject_no_match() {
         let mut injector = Injector::new();
@@ -224,3 +242,76 @@ mod injector_tests {
         assert!(matches.iter().any(|r| r.id == "r3"));
     }
 }
+
+#[cfg(test)]
+mod bundle_tests {
+    use super::*;
+
+    fn sample_rules() -> Vec<InjectionRule> {
+        vec![
+            InjectionRule::new("exact", "claude-sonnet-4-5", json!({"temperature": 0.3}))
+                .with_priority(5),
+            InjectionRule::new("flash", "*flash*", json!({"max_tokens": 4096}))
+                .with_mode(InjectionMode::Override),
+        ]
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let injector = Injector::with_rules(sample_rules());
+        let json = injector.export_bundle();
+
+        let mut imported = Injector::new();
+        assert_eq!(imported.import_bundle(&json, false).unwrap(), 2);
+        assert_eq!(imported.rules(), injector.rules());
+        assert_eq!(parse_bundle(&json).unwrap(), injector.rules().to_vec());
+    }
+
+    #[test]
+    fn test_invalid_rule_rejects_whole_bundle() {
+        let mut injector = Injector::with_rules(sample_rules());
+        let before = injector.rules().to_vec();
+
+        let mut rules = sample_rules();
+        rules[0].id = "new".to_string();
+        rules.push(InjectionRule::new(
+            "bad",
+            "claude-*",
+            json!({"model": "gpt-4"}),
+        ));
+        let json = export_rules(&rules);
+
+        let err = injector.import_bundle(&json, true).unwrap_err();
+        assert!(matches!(err, BundleError::InvalidRule { ref id, .. } if id == "bad"));
+        assert_eq!(injector.rules(), before.as_slice());
+
+        // 不支持的匹配模式与重复 ID
+        let rules = vec![InjectionRule::new("p", "a*b*c", json!({"top_p": 0.9}))];
+        assert!(parse_bundle(&export_rules(&rules)).is_err());
+        let rules = vec![
+            InjectionRule::new("dup", "a*", json!({"top_p": 0.9})),
+            InjectionRule::new("dup", "b*", json!({"top_p": 0.9})),
+        ];
+        assert_eq!(
+            parse_bundle(&export_rules(&rules)).unwrap_err(),
+            BundleError::DuplicateId("dup".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge_overrides_same_id() {
+        let mut injector = Injector::with_rules(sample_rules());
+        let json = export_rules(&[
+            InjectionRule::new("flash", "*flash*", json!({"max_tokens": 1024})),
+            InjectionRule::new("gemini", "gemini-*", json!({"top_k": 40})),
+        ]);
+
+        assert_eq!(injector.import_bundle(&json, true).unwrap(), 2);
+        assert_eq!(injector.rules().len(), 3);
+        let flash = injector.rules().iter().find(|r| r.id == "flash").unwrap();
+        assert_eq!(flash.parameters, json!({"max_tokens": 1024}));
+
+        injector.import_bundle(&json, false).unwrap();
+        assert_eq!(injector.rules().len(), 2);
+    }
+}
@@ -10,7 +10,7 @@ pub use proxycast_core::models::injection_types::{InjectionMode, InjectionRule};
 
 /// 允许注入的参数白名单
 /// 这些参数是安全的，不会影响请求的核心行为
-const ALLOWED_INJECTION_PARAMS: &[&str] = &[
+pub(super) const ALLOWED_INJECTION_PARAMS: &[&str] = &[
     "temperature",
     "max_tokens",
     "top_p",
@@ -23,7 +23,7 @@ const ALLOWED_INJECTION_PARAMS: &[&str] = &[
 ];
 
 /// 禁止注入的参数黑名单（即使在白名单中也不允许 Override 模式）
-const BLOCKED_OVERRIDE_PARAMS: &[&str] = &[
+pub(super) const BLOCKED_OVERRIDE_PARAMS: &[&str] = &[
     "model",
     "messages",
     "tools",
@@ -123,6 +123,21 @@ impl Injector {
     /// - Merge 模式：不覆盖已有参数
     /// - Override 模式：覆盖已有参数
     pub fn inject(&self, model: &str, payload: &mut serde_json::Value) -> InjectionResult {
+        self.inject_filtered(model, payload, |_| true)
+    }
+
+    /// 注入参数到请求（仅应用 `allow` 允许的规则）
+    ///
+    /// 用于按客户端限定参与注入的规则 ID。
+    pub fn inject_filtered<F>(
+        &self,
+        model: &str,
+        payload: &mut serde_json::Value,
+        allow: F,
+    ) -> InjectionResult
+    where
+        F: Fn(&str) -> bool,
+    {
         let mut result = InjectionResult::new();
 
         // 确保 payload 是对象
@@ -133,6 +148,9 @@ impl Injector {
 
         // 按优先级顺序应用匹配的规则
         for rule in self.matching_rules(model) {
+            if !allow(&rule.id) {
+                continue;
+            }
             let params = match rule.parameters.as_object() {
                 Some(params) => params,
                 None => continue,
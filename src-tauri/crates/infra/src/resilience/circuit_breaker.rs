@@ -0,0 +1,596 @@
+//! 熔断器实现
+//!
+//! 按熔断键（凭证 UUID 或上游地址）统计滚动窗口内的请求结果，
+//! 单个上游故障不会影响同类型的其他凭证：
+//! - 关闭（Closed）：正常放行，失败率达到阈值时熔断
+//! - 打开（Open）：冷却期内直接拒绝，避免在上游整体故障时继续重试放大负载
+//! - 半开（HalfOpen）：冷却结束后放行少量探测请求，全部成功则恢复，任一失败则重新熔断
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use proxycast_core::config::CircuitBreakerSettings;
+use proxycast_core::ProviderType;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 保留的状态切换记录条数
+const MAX_TRANSITIONS: usize = 100;
+
+/// 熔断状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half_open"),
+        }
+    }
+}
+
+/// 熔断状态切换记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitTransition {
+    pub provider: ProviderType,
+    /// 熔断键（凭证 UUID 或上游地址）
+    pub key: String,
+    pub from: CircuitState,
+    pub to: CircuitState,
+    /// 切换时窗口内的失败率
+    pub failure_rate: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 熔断打开时的拒绝信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitOpenError {
+    pub provider: ProviderType,
+    pub key: String,
+    /// 距离允许探测的剩余时间
+    pub retry_after: Duration,
+}
+
+impl std::fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Provider {} 已熔断，{} 秒后重试",
+            self.provider,
+            self.retry_after.as_secs().max(1)
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpenError {}
+
+/// 单个熔断键的熔断状态
+#[derive(Debug)]
+struct Circuit {
+    provider: ProviderType,
+    state: CircuitState,
+    /// 每次状态切换递增，用于识别切换前发出的许可
+    generation: u64,
+    /// 窗口内的请求结果（时间, 是否成功）
+    outcomes: VecDeque<(Instant, bool)>,
+    opened_at: Option<Instant>,
+    /// 半开状态下已放行的探测数
+    probes_in_flight: u32,
+    /// 半开状态下已成功的探测数
+    probe_successes: u32,
+}
+
+impl Circuit {
+    fn new(provider: ProviderType) -> Self {
+        Self {
+            provider,
+            state: CircuitState::Closed,
+            generation: 0,
+            outcomes: VecDeque::new(),
+            opened_at: None,
+            probes_in_flight: 0,
+            probe_successes: 0,
+        }
+    }
+
+    fn prune(&mut self, now: Instant, window: Duration) {
+        while let Some((at, _)) = self.outcomes.front() {
+            if now.duration_since(*at) <= window {
+                break;
+            }
+            self.outcomes.pop_front();
+        }
+    }
+
+    fn failure_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        let failures = self.outcomes.iter().filter(|(_, ok)| !ok).count();
+        failures as f64 / self.outcomes.len() as f64
+    }
+}
+
+/// 放行许可
+///
+/// 通过 [`record_status`](Self::record_status) 等方法记录请求结果；未记录结果就被丢弃时
+/// （例如客户端断开导致请求 future 被取消），半开探测名额会被释放，不计入失败率。
+#[derive(Debug)]
+#[must_use = "请求结束后应记录结果"]
+pub struct CircuitPermit {
+    breaker: Option<Arc<CircuitBreaker>>,
+    key: String,
+    /// 半开状态下发出的探测许可所属的状态代次
+    probe_generation: Option<u64>,
+}
+
+impl CircuitPermit {
+    /// 记录成功
+    pub fn record_success(self) {
+        self.finish(true);
+    }
+
+    /// 记录失败
+    pub fn record_failure(self) {
+        self.finish(false);
+    }
+
+    /// 按上游响应状态码记录结果
+    ///
+    /// 只有 5xx（含 529 过载）视为上游故障；4xx（含 429 配额）
+    /// 属于请求或单个凭证的问题，不计入失败率。
+    pub fn record_status(self, status_code: u16) {
+        self.finish(status_code < 500);
+    }
+
+    /// 请求未到达上游（例如本地生成的错误响应），释放许可但不记录结果
+    pub fn release(self) {}
+
+    fn finish(mut self, success: bool) {
+        if let Some(breaker) = self.breaker.take() {
+            breaker.record_at(&self.key, self.probe_generation, success, Instant::now());
+        }
+    }
+}
+
+impl Drop for CircuitPermit {
+    fn drop(&mut self) {
+        if let (Some(breaker), Some(generation)) = (self.breaker.take(), self.probe_generation) {
+            breaker.release_probe(&self.key, generation);
+        }
+    }
+}
+
+/// 按熔断键熔断的熔断器
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    settings: Mutex<CircuitBreakerSettings>,
+    circuits: Mutex<HashMap<String, Circuit>>,
+    transitions: Mutex<VecDeque<CircuitTransition>>,
+}
+
+impl CircuitBreaker {
+    /// 创建熔断器
+    pub fn new(settings: CircuitBreakerSettings) -> Self {
+        Self {
+            settings: Mutex::new(settings),
+            circuits: Mutex::new(HashMap::new()),
+            transitions: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 更新配置（热重载），已有的熔断状态保留
+    pub fn set_settings(&self, settings: CircuitBreakerSettings) {
+        *self.settings.lock() = settings;
+    }
+
+    /// 当前配置
+    pub fn settings(&self) -> CircuitBreakerSettings {
+        self.settings.lock().clone()
+    }
+
+    /// 请求前检查是否放行
+    ///
+    /// `key` 为熔断键，通常是凭证 UUID；`provider` 仅用于日志和切换记录。
+    pub fn try_acquire(
+        self: &Arc<Self>,
+        provider: ProviderType,
+        key: &str,
+    ) -> Result<CircuitPermit, CircuitOpenError> {
+        self.try_acquire_at(provider, key, Instant::now())
+    }
+
+    /// 当前熔断状态
+    pub fn state(&self, key: &str) -> CircuitState {
+        self.circuits
+            .lock()
+            .get(key)
+            .map(|c| c.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    /// 最近的状态切换记录（按时间先后）
+    pub fn transitions(&self) -> Vec<CircuitTransition> {
+        self.transitions.lock().iter().cloned().collect()
+    }
+
+    /// 清除状态切换记录
+    pub fn clear_transitions(&self) {
+        self.transitions.lock().clear();
+    }
+
+    fn try_acquire_at(
+        self: &Arc<Self>,
+        provider: ProviderType,
+        key: &str,
+        now: Instant,
+    ) -> Result<CircuitPermit, CircuitOpenError> {
+        let settings = self.settings();
+        if !settings.enabled {
+            return Ok(CircuitPermit {
+                breaker: None,
+                key: key.to_string(),
+                probe_generation: None,
+            });
+        }
+        let cooldown = Duration::from_secs(settings.cooldown_secs);
+        let permit = |probe_generation| CircuitPermit {
+            breaker: Some(self.clone()),
+            key: key.to_string(),
+            probe_generation,
+        };
+
+        let mut circuits = self.circuits.lock();
+        let circuit = circuits
+            .entry(key.to_string())
+            .or_insert_with(|| Circuit::new(provider));
+        match circuit.state {
+            CircuitState::Closed => Ok(permit(None)),
+            CircuitState::Open => {
+                let elapsed = circuit
+                    .opened_at
+                    .map(|at| now.duration_since(at))
+                    .unwrap_or(cooldown);
+                if elapsed < cooldown {
+                    return Err(CircuitOpenError {
+                        provider,
+                        key: key.to_string(),
+                        retry_after: cooldown - elapsed,
+                    });
+                }
+                self.transition(key, circuit, CircuitState::HalfOpen);
+                circuit.probes_in_flight = 1;
+                Ok(permit(Some(circuit.generation)))
+            }
+            CircuitState::HalfOpen => {
+                if circuit.probes_in_flight + circuit.probe_successes
+                    >= settings.half_open_probes.max(1)
+                {
+                    return Err(CircuitOpenError {
+                        provider,
+                        key: key.to_string(),
+                        retry_after: Duration::from_secs(1),
+                    });
+                }
+                circuit.probes_in_flight += 1;
+                Ok(permit(Some(circuit.generation)))
+            }
+        }
+    }
+
+    /// 释放未记录结果的半开探测名额
+    fn release_probe(&self, key: &str, generation: u64) {
+        let mut circuits = self.circuits.lock();
+        if let Some(circuit) = circuits.get_mut(key) {
+            if circuit.state == CircuitState::HalfOpen && circuit.generation == generation {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+            }
+        }
+    }
+
+    fn record_at(&self, key: &str, probe_generation: Option<u64>, success: bool, now: Instant) {
+        let settings = self.settings();
+        if !settings.enabled {
+            return;
+        }
+        let window = Duration::from_secs(settings.window_secs);
+
+        let mut circuits = self.circuits.lock();
+        let Some(circuit) = circuits.get_mut(key) else {
+            return;
+        };
+        match circuit.state {
+            CircuitState::Closed => {
+                circuit.outcomes.push_back((now, success));
+                circuit.prune(now, window);
+                if circuit.outcomes.len() >= settings.min_requests.max(1) as usize
+                    && circuit.failure_rate() >= settings.failure_rate_threshold
+                {
+                    circuit.opened_at = Some(now);
+                    self.transition(key, circuit, CircuitState::Open);
+                }
+            }
+            // 只有本轮半开发出的探测结果才影响状态，熔断前放行的请求晚到的结果忽略
+            CircuitState::HalfOpen if probe_generation == Some(circuit.generation) => {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+                if !success {
+                    circuit.opened_at = Some(now);
+                    self.transition(key, circuit, CircuitState::Open);
+                } else {
+                    circuit.probe_successes += 1;
+                    if circuit.probe_successes >= settings.half_open_probes.max(1) {
+                        self.transition(key, circuit, CircuitState::Closed);
+                    }
+                }
+            }
+            CircuitState::HalfOpen => {}
+            // 熔断前已放行的请求在熔断后返回，不影响状态
+            CircuitState::Open => {}
+        }
+    }
+
+    fn transition(&self, key: &str, circuit: &mut Circuit, to: CircuitState) {
+        let from = circuit.state;
+        let provider = circuit.provider;
+        let failure_rate = circuit.failure_rate();
+        circuit.state = to;
+        circuit.generation += 1;
+        circuit.probes_in_flight = 0;
+        circuit.probe_successes = 0;
+        if to == CircuitState::Closed {
+            circuit.outcomes.clear();
+            circuit.opened_at = None;
+        }
+
+        match to {
+            CircuitState::Open => tracing::warn!(
+                "[CIRCUIT] Provider {} ({}) 熔断: {} -> {}，失败率 {:.0}%",
+                provider,
+                key,
+                from,
+                to,
+                failure_rate * 100.0
+            ),
+            _ => tracing::info!(
+                "[CIRCUIT] Provider {} ({}) 熔断状态: {} -> {}",
+                provider,
+                key,
+                from,
+                to
+            ),
+        }
+
+        let mut transitions = self.transitions.lock();
+        transitions.push_back(CircuitTransition {
+            provider,
+            key: key.to_string(),
+            from,
+            to,
+            failure_rate,
+            timestamp: Utc::now(),
+        });
+        if transitions.len() > MAX_TRANSITIONS {
+            transitions.pop_front();
+        }
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(CircuitBreakerSettings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> Arc<CircuitBreaker> {
+        Arc::new(CircuitBreaker::new(CircuitBreakerSettings {
+            enabled: true,
+            failure_rate_threshold: 0.5,
+            min_requests: 4,
+            window_secs: 60,
+            cooldown_secs: 30,
+            half_open_probes: 2,
+        }))
+    }
+
+    fn fail(breaker: &Arc<CircuitBreaker>, key: &str, at: Instant) {
+        breaker
+            .try_acquire_at(ProviderType::Kiro, key, at)
+            .unwrap()
+            .finish_at(false, at);
+    }
+
+    impl CircuitPermit {
+        fn finish_at(mut self, success: bool, now: Instant) {
+            if let Some(breaker) = self.breaker.take() {
+                breaker.record_at(&self.key, self.probe_generation, success, now);
+            }
+        }
+    }
+
+    #[test]
+    fn test_burst_of_failures_opens_circuit_and_fast_fails() {
+        let breaker = breaker();
+        let start = Instant::now();
+
+        // 样本不足时不熔断
+        for i in 0..3 {
+            fail(&breaker, "cred-a", start + Duration::from_millis(i));
+        }
+        assert_eq!(breaker.state("cred-a"), CircuitState::Closed);
+
+        fail(&breaker, "cred-a", start + Duration::from_millis(3));
+        assert_eq!(breaker.state("cred-a"), CircuitState::Open);
+
+        // 冷却期内直接拒绝，同类型的其他凭证不受影响
+        let err = breaker
+            .try_acquire_at(
+                ProviderType::Kiro,
+                "cred-a",
+                start + Duration::from_secs(10),
+            )
+            .unwrap_err();
+        assert_eq!(err.provider, ProviderType::Kiro);
+        assert_eq!(err.key, "cred-a");
+        assert_eq!(err.retry_after, Duration::from_millis(20_003));
+        assert!(breaker
+            .try_acquire_at(
+                ProviderType::Kiro,
+                "cred-b",
+                start + Duration::from_secs(10)
+            )
+            .is_ok());
+
+        let transitions = breaker.transitions();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].key, "cred-a");
+        assert_eq!(transitions[0].from, CircuitState::Closed);
+        assert_eq!(transitions[0].to, CircuitState::Open);
+        assert_eq!(transitions[0].failure_rate, 1.0);
+    }
+
+    #[test]
+    fn test_half_open_probes_recover_or_reopen() {
+        let breaker = breaker();
+        let start = Instant::now();
+        let key = "cred-gemini";
+        for _ in 0..4 {
+            fail(&breaker, key, start);
+        }
+        assert_eq!(breaker.state(key), CircuitState::Open);
+
+        // 冷却结束后进入半开，只放行配置数量的探测请求
+        let after_cooldown = start + Duration::from_secs(31);
+        let probe = breaker
+            .try_acquire_at(ProviderType::Gemini, key, after_cooldown)
+            .unwrap();
+        assert_eq!(breaker.state(key), CircuitState::HalfOpen);
+        let _second = breaker
+            .try_acquire_at(ProviderType::Gemini, key, after_cooldown)
+            .unwrap();
+        assert!(breaker
+            .try_acquire_at(ProviderType::Gemini, key, after_cooldown)
+            .is_err());
+
+        // 探测失败重新熔断
+        probe.finish_at(false, after_cooldown);
+        assert_eq!(breaker.state(key), CircuitState::Open);
+        assert!(breaker
+            .try_acquire_at(ProviderType::Gemini, key, after_cooldown)
+            .is_err());
+
+        // 再次冷却后探测全部成功，恢复正常
+        let later = after_cooldown + Duration::from_secs(31);
+        let first = breaker
+            .try_acquire_at(ProviderType::Gemini, key, later)
+            .unwrap();
+        let second = breaker
+            .try_acquire_at(ProviderType::Gemini, key, later)
+            .unwrap();
+        first.finish_at(true, later);
+        assert_eq!(breaker.state(key), CircuitState::HalfOpen);
+        second.finish_at(true, later);
+        assert_eq!(breaker.state(key), CircuitState::Closed);
+        assert!(breaker
+            .try_acquire_at(ProviderType::Gemini, key, later)
+            .is_ok());
+
+        let states: Vec<_> = breaker.transitions().iter().map(|t| t.to).collect();
+        assert_eq!(
+            states,
+            vec![
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Closed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dropped_probe_releases_slot() {
+        let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerSettings {
+            enabled: true,
+            min_requests: 1,
+            half_open_probes: 1,
+            ..CircuitBreakerSettings::default()
+        }));
+        let start = Instant::now();
+        fail(&breaker, "cred", start);
+        assert_eq!(breaker.state("cred"), CircuitState::Open);
+
+        // 探测请求被取消（future 被丢弃），名额释放后下一个请求仍可探测
+        let after_cooldown = start + Duration::from_secs(31);
+        let probe = breaker
+            .try_acquire_at(ProviderType::OpenAI, "cred", after_cooldown)
+            .unwrap();
+        assert!(breaker
+            .try_acquire_at(ProviderType::OpenAI, "cred", after_cooldown)
+            .is_err());
+        drop(probe);
+        assert_eq!(breaker.state("cred"), CircuitState::HalfOpen);
+        breaker
+            .try_acquire_at(ProviderType::OpenAI, "cred", after_cooldown)
+            .unwrap()
+            .finish_at(true, after_cooldown);
+        assert_eq!(breaker.state("cred"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_only_server_errors_count_as_failures() {
+        let breaker = breaker();
+        for status in [429, 400, 401, 404] {
+            breaker
+                .try_acquire(ProviderType::Claude, "cred")
+                .unwrap()
+                .record_status(status);
+        }
+        assert_eq!(breaker.state("cred"), CircuitState::Closed);
+
+        for status in [500, 503, 529, 502] {
+            breaker
+                .try_acquire(ProviderType::Claude, "cred")
+                .unwrap()
+                .record_status(status);
+        }
+        assert_eq!(breaker.state("cred"), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_released_permits_are_not_counted() {
+        let breaker = breaker();
+        for _ in 0..10 {
+            breaker
+                .try_acquire(ProviderType::Claude, "cred")
+                .unwrap()
+                .release();
+        }
+        assert_eq!(breaker.state("cred"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_disabled_by_default_and_never_opens() {
+        assert!(!CircuitBreakerSettings::default().enabled);
+        let breaker = Arc::new(CircuitBreaker::default());
+        let now = Instant::now();
+        for _ in 0..50 {
+            fail(&breaker, "cred", now);
+        }
+        assert!(breaker
+            .try_acquire_at(ProviderType::Kiro, "cred", now)
+            .is_ok());
+        assert_eq!(breaker.state("cred"), CircuitState::Closed);
+    }
+}
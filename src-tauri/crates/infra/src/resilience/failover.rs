@@ -2,9 +2,14 @@
 //!
 //! 提供 Provider 故障转移和自动切换功能
 
+use chrono::{DateTime, Utc};
 use proxycast_core::ProviderType;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::circuit_breaker::{CircuitBreaker, CircuitState, CircuitTransition};
+use super::retry::OVERLOADED_STATUS_CODE;
 
 /// 配额超限相关的 HTTP 状态码
 pub const QUOTA_EXCEEDED_STATUS_CODES: &[u16] = &[429];
@@ -65,6 +70,8 @@ pub enum FailureType {
     AuthenticationFailed,
     /// 服务不可用
     ServiceUnavailable,
+    /// 上游过载（529）
+    Overloaded,
     /// 其他错误
     Other,
 }
@@ -74,6 +81,11 @@ impl FailureType {
     pub fn detect(status_code: Option<u16>, error_message: &str) -> Self {
         let error_lower = error_message.to_lowercase();
 
+        // 检查上游过载（优先于配额关键词，过载消息中也可能含有 "exceeded"）
+        if status_code == Some(OVERLOADED_STATUS_CODE) || error_lower.contains("overloaded_error") {
+            return FailureType::Overloaded;
+        }
+
         // 检查配额超限
         if let Some(code) = status_code {
             if QUOTA_EXCEEDED_STATUS_CODES.contains(&code) {
@@ -109,6 +121,11 @@ impl FailureType {
     pub fn is_quota_exceeded(&self) -> bool {
         matches!(self, FailureType::QuotaExceeded)
     }
+
+    /// 是否为上游过载
+    pub fn is_overloaded(&self) -> bool {
+        matches!(self, FailureType::Overloaded)
+    }
 }
 
 /// 故障转移结果
@@ -199,6 +216,8 @@ impl Failover {
         let should_switch = match &failure_type {
             FailureType::QuotaExceeded => self.config.switch_on_quota,
             FailureType::ServiceUnavailable => true,
+            // 过载时换用其他 Provider，避免继续压垮同一上游
+            FailureType::Overloaded => true,
             FailureType::AuthenticationFailed => false, // 认证失败通常不应切换
             FailureType::Other => false,
         };
@@ -268,6 +287,8 @@ pub struct FailoverManager {
     failed_providers: HashSet<ProviderType>,
     /// 切换日志
     switch_log: Vec<SwitchEvent>,
+    /// 熔断器，其状态切换记录合并进切换日志
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
 }
 
 /// 切换事件
@@ -279,8 +300,27 @@ pub struct SwitchEvent {
     pub to_provider: ProviderType,
     /// 故障类型
     pub failure_type: FailureType,
+    /// 熔断状态切换（熔断器产生的事件），故障转移切换为 None
+    pub circuit: Option<CircuitTransition>,
     /// 时间戳
-    pub timestamp: std::time::Instant,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl From<&CircuitTransition> for SwitchEvent {
+    /// 熔断器只统计 5xx，熔断打开记为服务不可用，其余切换记为其他
+    fn from(transition: &CircuitTransition) -> Self {
+        let failure_type = match transition.to {
+            CircuitState::Open => FailureType::ServiceUnavailable,
+            CircuitState::HalfOpen | CircuitState::Closed => FailureType::Other,
+        };
+        Self {
+            from_provider: transition.provider,
+            to_provider: transition.provider,
+            failure_type,
+            circuit: Some(transition.clone()),
+            timestamp: transition.timestamp,
+        }
+    }
 }
 
 impl FailoverManager {
@@ -290,9 +330,16 @@ impl FailoverManager {
             failover: Failover::new(config),
             failed_providers: HashSet::new(),
             switch_log: Vec::new(),
+            circuit_breaker: None,
         }
     }
 
+    /// 关联熔断器，切换日志同时包含其状态切换记录
+    pub fn with_circuit_breaker(mut self, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
     /// 使用默认配置创建
     pub fn with_defaults() -> Self {
         Self::new(FailoverConfig::default())
@@ -340,6 +387,8 @@ impl FailoverManager {
         let should_switch = match &failure_type {
             FailureType::QuotaExceeded => self.failover.config().switch_on_quota,
             FailureType::ServiceUnavailable => true,
+            // 过载时换用其他 Provider，避免继续压垮同一上游
+            FailureType::Overloaded => true,
             FailureType::AuthenticationFailed => false,
             FailureType::Other => false,
         };
@@ -362,7 +411,8 @@ impl FailoverManager {
                     from_provider: failed_provider,
                     to_provider: new_provider,
                     failure_type,
-                    timestamp: std::time::Instant::now(),
+                    circuit: None,
+                    timestamp: Utc::now(),
                 });
 
                 FailoverResult::switched(new_provider, failure_type)
@@ -377,13 +427,23 @@ impl FailoverManager {
     }
 
     /// 获取切换日志
-    pub fn switch_log(&self) -> &[SwitchEvent] {
-        &self.switch_log
+    ///
+    /// 合并关联熔断器的状态切换记录，按时间先后排列。
+    pub fn switch_log(&self) -> Vec<SwitchEvent> {
+        let mut log = self.switch_log.clone();
+        if let Some(breaker) = &self.circuit_breaker {
+            log.extend(breaker.transitions().iter().map(SwitchEvent::from));
+            log.sort_by_key(|event| event.timestamp);
+        }
+        log
     }
 
-    /// 清除切换日志
+    /// 清除切换日志（含关联熔断器的状态切换记录）
     pub fn clear_switch_log(&mut self) {
         self.switch_log.clear();
+        if let Some(breaker) = &self.circuit_breaker {
+            breaker.clear_transitions();
+        }
     }
 
     /// 检查 Provider 是否已失败
@@ -391,7 +451,7 @@ impl FailoverManager {
         self.failed_providers.contains(&provider)
     }
 
-    /// 获取切换次数
+    /// 获取故障转移切换次数（不含熔断状态切换）
     pub fn switch_count(&self) -> usize {
         self.switch_log.len()
     }
@@ -475,6 +535,22 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn test_failure_type_detect_overloaded() {
+        assert_eq!(
+            FailureType::detect(Some(529), "Overloaded"),
+            FailureType::Overloaded
+        );
+        assert_eq!(
+            FailureType::detect(
+                None,
+                r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+            ),
+            FailureType::Overloaded
+        );
+        assert!(!FailureType::detect(Some(500), "Internal error").is_overloaded());
+    }
+
     #[test]
     fn test_failure_type_detect_other() {
         assert_eq!(
@@ -570,6 +646,23 @@ mod unit_tests {
         assert_eq!(result.failure_type, FailureType::ServiceUnavailable);
     }
 
+    #[test]
+    fn test_handle_failure_overloaded_switches() {
+        let mut manager = FailoverManager::with_defaults();
+        let available = vec![ProviderType::Claude, ProviderType::Kiro];
+
+        let result =
+            manager.handle_failure_and_switch(ProviderType::Claude, Some(529), "", &available);
+
+        assert!(result.switched);
+        assert_eq!(result.new_provider, Some(ProviderType::Kiro));
+        assert_eq!(result.failure_type, FailureType::Overloaded);
+        assert_eq!(
+            manager.switch_log()[0].failure_type,
+            FailureType::Overloaded
+        );
+    }
+
     #[test]
     fn test_select_alternative() {
         let failover = Failover::with_defaults();
@@ -732,6 +825,45 @@ mod manager_tests {
         assert_eq!(manager.switch_count(), 0);
     }
 
+    #[test]
+    fn test_failover_manager_switch_log_includes_circuit_transitions() {
+        let breaker = Arc::new(CircuitBreaker::new(
+            proxycast_core::config::CircuitBreakerSettings {
+                enabled: true,
+                failure_rate_threshold: 0.5,
+                min_requests: 2,
+                window_secs: 60,
+                cooldown_secs: 30,
+                half_open_probes: 1,
+            },
+        ));
+        let mut manager = FailoverManager::with_defaults().with_circuit_breaker(breaker.clone());
+        let available = vec![ProviderType::Kiro, ProviderType::Gemini];
+
+        manager.handle_failure_and_switch(ProviderType::Kiro, Some(503), "", &available);
+        for _ in 0..2 {
+            breaker
+                .try_acquire(ProviderType::Kiro, "cred-1")
+                .unwrap()
+                .record_status(503);
+        }
+
+        let log = manager.switch_log();
+        assert_eq!(log.len(), 2);
+        assert!(log[0].circuit.is_none());
+        let circuit = log[1].circuit.as_ref().unwrap();
+        assert_eq!(circuit.key, "cred-1");
+        assert_eq!(circuit.from, CircuitState::Closed);
+        assert_eq!(circuit.to, CircuitState::Open);
+        assert_eq!(log[1].from_provider, ProviderType::Kiro);
+        assert_eq!(log[1].failure_type, FailureType::ServiceUnavailable);
+        assert_eq!(manager.switch_count(), 1);
+
+        manager.clear_switch_log();
+        assert!(manager.switch_log().is_empty());
+        assert!(breaker.transitions().is_empty());
+    }
+
     #[test]
     fn test_failover_manager_disabled() {
         let mut manager = FailoverManager::new(FailoverConfig::disabled());
@@ -1,16 +1,22 @@
 //! 容错机制模块
 //!
-//! 提供重试、故障转移和超时控制功能
+//! 提供重试、熔断、故障转移和超时控制功能
 
+mod circuit_breaker;
 mod failover;
 mod retry;
 mod timeout;
 
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitOpenError, CircuitPermit, CircuitState, CircuitTransition,
+};
 pub use failover::{
     Failover, FailoverConfig, FailoverManager, FailoverResult, FailureType, SwitchEvent,
     QUOTA_EXCEEDED_KEYWORDS, QUOTA_EXCEEDED_STATUS_CODES,
 };
-pub use retry::{Retrier, RetryConfig, RetryError};
+pub use retry::{
+    Retrier, RetryConfig, RetryError, OVERLOADED_BACKOFF_MULTIPLIER, OVERLOADED_STATUS_CODE,
+};
 pub use timeout::{
     CancellationToken, StreamIdleDetector, StreamWithIdleTimeout, TimeoutConfig, TimeoutController,
     TimeoutError,
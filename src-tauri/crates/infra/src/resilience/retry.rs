@@ -2,12 +2,20 @@
 //!
 //! 提供带指数退避和抖动的重试逻辑
 
+use parking_lot::RwLock;
+use proxycast_core::config::{JitterMode, RetrySettings};
 use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::time::Duration;
 
 /// 可重试的 HTTP 状态码
-pub const RETRYABLE_STATUS_CODES: &[u16] = &[408, 429, 500, 502, 503, 504];
+pub const RETRYABLE_STATUS_CODES: &[u16] = &[408, 429, 500, 502, 503, 504, 529];
+
+/// 上游过载状态码（Anthropic `overloaded_error`）
+pub const OVERLOADED_STATUS_CODE: u16 = 529;
+
+/// 过载时退避时间相对普通错误的倍数
+pub const OVERLOADED_BACKOFF_MULTIPLIER: f64 = 4.0;
 
 /// 重试配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -21,6 +29,9 @@ pub struct RetryConfig {
     /// 可重试的状态码
     #[serde(default = "default_retryable_codes")]
     pub retryable_codes: Vec<u16>,
+    /// 退避抖动方式
+    #[serde(default)]
+    pub jitter: JitterMode,
 }
 
 fn default_retryable_codes() -> Vec<u16> {
@@ -34,6 +45,7 @@ impl Default for RetryConfig {
             base_delay_ms: 1000,
             max_delay_ms: 30000,
             retryable_codes: default_retryable_codes(),
+            jitter: JitterMode::default(),
         }
     }
 }
@@ -46,9 +58,26 @@ impl RetryConfig {
             base_delay_ms,
             max_delay_ms,
             retryable_codes: default_retryable_codes(),
+            jitter: JitterMode::default(),
         }
     }
 
+    /// 从配置文件的重试设置创建
+    pub fn from_settings(settings: &RetrySettings) -> Self {
+        Self::new(
+            settings.max_retries,
+            settings.base_delay_ms,
+            settings.max_delay_ms,
+        )
+        .with_jitter(settings.jitter)
+    }
+
+    /// 设置退避抖动方式
+    pub fn with_jitter(mut self, jitter: JitterMode) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
     /// 检查状态码是否可重试
     pub fn is_retryable(&self, status_code: u16) -> bool {
         self.retryable_codes.contains(&status_code)
@@ -79,15 +108,19 @@ impl std::fmt::Display for RetryError {
 impl std::error::Error for RetryError {}
 
 /// 重试器
-#[derive(Debug, Clone)]
+///
+/// 配置可在热重载时替换（[`Retrier::set_config`]），进行中的请求下一次退避即使用新配置。
+#[derive(Debug)]
 pub struct Retrier {
-    config: RetryConfig,
+    config: RwLock<RetryConfig>,
 }
 
 impl Retrier {
     /// 创建新的重试器
     pub fn new(config: RetryConfig) -> Self {
-        Self { config }
+        Self {
+            config: RwLock::new(config),
+        }
     }
 
     /// 使用默认配置创建重试器
@@ -96,37 +129,73 @@ impl Retrier {
     }
 
     /// 获取配置
-    pub fn config(&self) -> &RetryConfig {
-        &self.config
+    pub fn config(&self) -> RetryConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新配置（热重载）
+    pub fn set_config(&self, config: RetryConfig) {
+        *self.config.write() = config;
     }
 
     /// 计算第 N 次重试的退避时间（指数退避 + 抖动）
     ///
-    /// 公式: min(base_delay * 2^attempt + jitter, max_delay)
-    /// 其中 jitter 是 [0, base_delay) 范围内的随机值
+    /// 退避上限为 `min(base_delay * 2^attempt, max_delay)`，再按配置的 [`JitterMode`] 抖动
     pub fn backoff_delay(&self, attempt: u32) -> Duration {
         self.backoff_delay_with_jitter(attempt, rand_jitter_factor())
     }
 
     /// 计算退避时间（可指定抖动因子，用于测试）
     ///
-    /// jitter_factor 应在 [0.0, 1.0) 范围内
+    /// jitter_factor 应在 [0.0, 1.0) 范围内：
+    /// - `Additive`: `min(base * 2^attempt + base * jitter_factor, max)`
+    /// - `None`: 忽略抖动因子
+    /// - `Full`: `ceiling * jitter_factor`
+    /// - `Equal`: `ceiling / 2 + ceiling / 2 * jitter_factor`
     pub fn backoff_delay_with_jitter(&self, attempt: u32, jitter_factor: f64) -> Duration {
-        let base = self.config.base_delay_ms as f64;
-        let max = self.config.max_delay_ms as f64;
+        let config = self.config.read();
+        let base = config.base_delay_ms as f64;
+        let max = config.max_delay_ms as f64;
 
-        // 指数退避: base * 2^attempt
-        let exponential = base * 2_f64.powi(attempt as i32);
+        // 指数退避: base * 2^attempt，不超过最大值
+        let ceiling = (base * 2_f64.powi(attempt as i32)).min(max);
 
-        // 抖动: [0, base) 范围内的随机值
-        let jitter = base * jitter_factor.clamp(0.0, 1.0);
-
-        // 总延迟，不超过最大值
-        let delay = (exponential + jitter).min(max);
+        let jitter_factor = jitter_factor.clamp(0.0, 1.0);
+        let delay = match config.jitter {
+            JitterMode::Additive => {
+                (base * 2_f64.powi(attempt as i32) + base * jitter_factor).min(max)
+            }
+            JitterMode::None => ceiling,
+            JitterMode::Full => ceiling * jitter_factor,
+            JitterMode::Equal => ceiling / 2.0 + ceiling / 2.0 * jitter_factor,
+        };
 
         Duration::from_millis(delay as u64)
     }
 
+    /// 按状态码计算退避时间
+    ///
+    /// 529（过载）使用 `OVERLOADED_BACKOFF_MULTIPLIER` 倍的退避，给上游留出恢复时间
+    pub fn backoff_delay_for_status(&self, attempt: u32, status_code: u16) -> Duration {
+        self.backoff_delay_for_status_with_jitter(attempt, status_code, rand_jitter_factor())
+    }
+
+    /// 按状态码计算退避时间（可指定抖动因子，用于测试）
+    pub fn backoff_delay_for_status_with_jitter(
+        &self,
+        attempt: u32,
+        status_code: u16,
+        jitter_factor: f64,
+    ) -> Duration {
+        let delay = self.backoff_delay_with_jitter(attempt, jitter_factor);
+        if status_code != OVERLOADED_STATUS_CODE {
+            return delay;
+        }
+        delay
+            .mul_f64(OVERLOADED_BACKOFF_MULTIPLIER)
+            .min(Duration::from_millis(self.config.read().max_delay_ms))
+    }
+
     /// 带重试执行异步操作
     ///
     /// 操作函数返回 `Result<T, (String, Option<u16>)>`，
@@ -148,17 +217,18 @@ impl Retrier {
                 Err((error, status_code)) => {
                     last_error = error;
                     last_status_code = status_code;
+                    let config = self.config();
 
                     // 检查是否应该重试
                     let should_retry = if let Some(code) = status_code {
-                        self.config.is_retryable(code)
+                        config.is_retryable(code)
                     } else {
                         // 没有状态码的错误（如网络错误）默认可重试
                         true
                     };
 
                     // 检查是否还有重试次数
-                    if !should_retry || attempts > self.config.max_retries {
+                    if !should_retry || attempts > config.max_retries {
                         return Err(RetryError {
                             attempts,
                             last_error,
@@ -167,7 +237,10 @@ impl Retrier {
                     }
 
                     // 等待退避时间
-                    let delay = self.backoff_delay(attempts - 1);
+                    let delay = match status_code {
+                        Some(code) => self.backoff_delay_for_status(attempts - 1, code),
+                        None => self.backoff_delay(attempts - 1),
+                    };
                     tokio::time::sleep(delay).await;
                 }
             }
@@ -176,7 +249,7 @@ impl Retrier {
 
     /// 同步计算重试序列的所有退避时间（用于测试）
     pub fn compute_backoff_sequence(&self, jitter_factor: f64) -> Vec<Duration> {
-        (0..self.config.max_retries)
+        (0..self.config().max_retries)
             .map(|attempt| self.backoff_delay_with_jitter(attempt, jitter_factor))
             .collect()
     }
@@ -226,6 +299,7 @@ mod unit_tests {
         assert!(config.is_retryable(502));
         assert!(config.is_retryable(503));
         assert!(config.is_retryable(504));
+        assert!(config.is_retryable(529));
 
         // 不可重试的状态码
         assert!(!config.is_retryable(200));
@@ -266,17 +340,93 @@ mod unit_tests {
     #[test]
     fn test_backoff_delay_with_jitter() {
         let config = RetryConfig::new(5, 1000, 30000);
-        let retrier = Retrier::new(config);
 
-        // 50% 抖动
+        // 默认叠加抖动：额外加 [0, base)
+        let additive = Retrier::new(config.clone());
         assert_eq!(
-            retrier.backoff_delay_with_jitter(0, 0.5),
+            additive.backoff_delay_with_jitter(0, 0.5),
             Duration::from_millis(1500)
         );
         assert_eq!(
-            retrier.backoff_delay_with_jitter(1, 0.5),
+            additive.backoff_delay_with_jitter(1, 0.5),
             Duration::from_millis(2500)
         );
+
+        // 不加抖动：忽略抖动因子
+        let none = Retrier::new(config.clone().with_jitter(JitterMode::None));
+        assert_eq!(
+            none.backoff_delay_with_jitter(1, 0.5),
+            Duration::from_millis(2000)
+        );
+
+        // 完全抖动：[0, 上限)
+        let full = Retrier::new(config.clone().with_jitter(JitterMode::Full));
+        assert_eq!(full.backoff_delay_with_jitter(1, 0.0), Duration::ZERO);
+        assert_eq!(
+            full.backoff_delay_with_jitter(1, 0.5),
+            Duration::from_millis(1000)
+        );
+
+        // 等量抖动：[上限 / 2, 上限)
+        let equal = Retrier::new(config.with_jitter(JitterMode::Equal));
+        assert_eq!(
+            equal.backoff_delay_with_jitter(1, 0.0),
+            Duration::from_millis(1000)
+        );
+        assert_eq!(
+            equal.backoff_delay_with_jitter(1, 0.5),
+            Duration::from_millis(1500)
+        );
+        // 上限仍受 max_delay_ms 限制
+        assert_eq!(
+            equal.backoff_delay_with_jitter(10, 0.5),
+            Duration::from_millis(22500)
+        );
+    }
+
+    #[test]
+    fn test_random_backoff_within_jitter_bounds() {
+        // 第 3 次重试的上限为 4000ms，叠加抖动额外加 [0, 1000)
+        for (jitter, min_ms, max_ms) in [
+            (JitterMode::Additive, 4000, 5000),
+            (JitterMode::None, 4000, 4000),
+            (JitterMode::Full, 0, 4000),
+            (JitterMode::Equal, 2000, 4000),
+        ] {
+            let retrier = Retrier::new(RetryConfig::new(5, 1000, 30000).with_jitter(jitter));
+            for _ in 0..100 {
+                let delay = retrier.backoff_delay(2);
+                assert!(
+                    delay >= Duration::from_millis(min_ms)
+                        && delay <= Duration::from_millis(max_ms),
+                    "{jitter:?}: {delay:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_config_rebuilds_backoff() {
+        let retrier = Retrier::with_defaults();
+        assert_eq!(
+            retrier.backoff_delay_with_jitter(0, 0.5),
+            Duration::from_millis(1500)
+        );
+
+        // 热重载后新的重试次数和抖动方式立即生效
+        let settings = RetrySettings {
+            max_retries: 1,
+            base_delay_ms: 200,
+            jitter: JitterMode::Full,
+            ..Default::default()
+        };
+        retrier.set_config(RetryConfig::from_settings(&settings));
+        assert_eq!(retrier.config().max_retries, 1);
+        assert_eq!(retrier.config().jitter, JitterMode::Full);
+        assert_eq!(
+            retrier.backoff_delay_with_jitter(0, 0.5),
+            Duration::from_millis(100)
+        );
     }
 
     #[test]
@@ -295,6 +445,22 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn test_overloaded_backoff_is_longer() {
+        let retrier = Retrier::new(RetryConfig::new(5, 1000, 30000));
+
+        let server_error = retrier.backoff_delay_for_status_with_jitter(1, 500, 0.0);
+        let overloaded = retrier.backoff_delay_for_status_with_jitter(1, 529, 0.0);
+        assert_eq!(server_error, Duration::from_millis(2000));
+        assert_eq!(overloaded, Duration::from_millis(8000));
+
+        // 同样受 max_delay_ms 限制
+        assert_eq!(
+            retrier.backoff_delay_for_status_with_jitter(4, 529, 0.0),
+            Duration::from_millis(30000)
+        );
+    }
+
     #[test]
     fn test_compute_backoff_sequence() {
         let config = RetryConfig::new(3, 1000, 30000);
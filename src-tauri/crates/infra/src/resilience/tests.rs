@@ -4,6 +4,7 @@
 
 use crate::resilience::{Retrier, RetryConfig};
 use proptest::prelude::*;
+use proxycast_core::config::JitterMode;
 use std::time::Duration;
 
 /// 生成有效的重试配置（用于属性测试，使用较短的延迟）
@@ -284,31 +285,44 @@ proptest! {
     }
 
     /// **Feature: enhancement-roadmap, Property 10: 退避时间递增（抖动范围）**
-    /// *对于任意* 重试配置，抖动应在 [0, base_delay) 范围内
+    /// *对于任意* 重试配置和抖动方式，退避时间应在该方式的范围内：
+    /// `Additive` 在 [上限, 上限 + base]（不超过 max），`None` 等于上限，
+    /// `Full` 在 [0, 上限]，`Equal` 在 [上限 / 2, 上限]
     /// **Validates: Requirements 3.1 (验收标准 2)**
     #[test]
     fn prop_backoff_jitter_range(
         config in arb_retry_config(),
         attempt in 0u32..=5u32,
-        jitter_factor in 0.0f64..1.0f64
+        jitter_factor in 0.0f64..1.0f64,
+        jitter in prop_oneof![
+            Just(JitterMode::Additive),
+            Just(JitterMode::None),
+            Just(JitterMode::Full),
+            Just(JitterMode::Equal),
+        ]
     ) {
-        let retrier = Retrier::new(config.clone());
-
-        let delay_no_jitter = retrier.backoff_delay_with_jitter(attempt, 0.0);
-        let delay_with_jitter = retrier.backoff_delay_with_jitter(attempt, jitter_factor);
-
-        // 抖动应该使延迟增加，但增加量不超过 base_delay
-        let max_jitter = Duration::from_millis(config.base_delay_ms);
-        let actual_jitter = delay_with_jitter.saturating_sub(delay_no_jitter);
-
-        // 如果没有被 max_delay 截断，抖动应该在范围内
-        if delay_no_jitter < Duration::from_millis(config.max_delay_ms) {
-            prop_assert!(
-                actual_jitter <= max_jitter,
-                "抖动 {:?} 不应超过 base_delay {:?}",
-                actual_jitter,
-                max_jitter
-            );
-        }
+        let ceiling = Retrier::new(config.clone().with_jitter(JitterMode::None))
+            .backoff_delay_with_jitter(attempt, 0.0);
+        let retrier = Retrier::new(config.clone().with_jitter(jitter));
+        let delay = retrier.backoff_delay_with_jitter(attempt, jitter_factor);
+
+        let (min, max) = match jitter {
+            JitterMode::Additive => (
+                ceiling,
+                (ceiling + Duration::from_millis(config.base_delay_ms))
+                    .min(Duration::from_millis(config.max_delay_ms)),
+            ),
+            JitterMode::None => (ceiling, ceiling),
+            JitterMode::Full => (Duration::ZERO, ceiling),
+            JitterMode::Equal => (Duration::from_millis(ceiling.as_millis() as u64 / 2), ceiling),
+        };
+        prop_assert!(
+            delay >= min && delay <= max,
+            "{:?} 抖动的退避时间 {:?} 应在 [{:?}, {:?}] 内",
+            jitter,
+            delay,
+            min,
+            max
+        );
     }
 }
@@ -12,6 +12,7 @@ use std::fs::{self, File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// 日志记录器错误
 #[derive(Debug)]
@@ -70,6 +71,13 @@ pub struct LogRotationConfig {
     pub max_file_size: u64,
     /// 是否启用文件日志
     pub enable_file_logging: bool,
+    /// 成功请求的采样比例（0.0 - 1.0），失败请求始终记录
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
 }
 
 impl Default for LogRotationConfig {
@@ -79,6 +87,7 @@ impl Default for LogRotationConfig {
             retention_days: 7,
             max_file_size: 10 * 1024 * 1024, // 10MB
             enable_file_logging: true,
+            sample_rate: default_sample_rate(),
         }
     }
 }
@@ -95,6 +104,8 @@ pub struct RequestLogger {
     log_dir: PathBuf,
     /// 当前日志文件路径
     current_log_file: RwLock<Option<PathBuf>>,
+    /// 已参与采样的成功请求数
+    sampled_successes: AtomicU64,
 }
 
 impl RequestLogger {
@@ -115,6 +126,7 @@ impl RequestLogger {
             config,
             log_dir,
             current_log_file: RwLock::new(None),
+            sampled_successes: AtomicU64::new(0),
         };
 
         // 初始化日志文件
@@ -131,7 +143,14 @@ impl RequestLogger {
     }
 
     /// 记录请求日志
+    ///
+    /// 成功请求按 `sample_rate` 采样，未被采样的日志直接丢弃；
+    /// 非成功请求始终记录。
     pub fn record(&self, log: RequestLog) -> Result<(), LoggerError> {
+        if log.status == RequestStatus::Success && !self.sample_success() {
+            return Ok(());
+        }
+
         // 写入内存
         {
             let mut logs = self.logs.write();
@@ -151,6 +170,19 @@ impl RequestLogger {
         Ok(())
     }
 
+    /// 成功请求是否被采样
+    ///
+    /// 按计数确定性采样：第 k 个成功请求在 `floor(k * rate)` 增加时保留，
+    /// 因此 N 个成功请求中恰好保留约 `rate * N` 条，且分布均匀。
+    fn sample_success(&self) -> bool {
+        let rate = self.config.sample_rate.clamp(0.0, 1.0);
+        if rate >= 1.0 {
+            return true;
+        }
+        let k = self.sampled_successes.fetch_add(1, Ordering::Relaxed) as f64;
+        ((k + 1.0) * rate).floor() > (k * rate).floor()
+    }
+
     /// 获取所有内存中的日志
     pub fn get_all(&self) -> Vec<RequestLog> {
         self.logs.read().iter().cloned().collect()
@@ -186,6 +218,16 @@ impl RequestLogger {
             .collect()
     }
 
+    /// 按路由过滤日志（见 [`RequestLog::matches_route`]）
+    pub fn get_by_route(&self, route: &str) -> Vec<RequestLog> {
+        self.logs
+            .read()
+            .iter()
+            .filter(|log| log.matches_route(route))
+            .cloned()
+            .collect()
+    }
+
     /// 按状态过滤日志
     pub fn get_by_status(&self, status: RequestStatus) -> Vec<RequestLog> {
         self.logs
@@ -4,11 +4,13 @@
 
 mod logger;
 mod stats;
+mod stream_timeouts;
 mod tokens;
 mod types;
 
 pub use logger::{LogRotationConfig, LoggerError, RequestLogger};
 pub use stats::StatsAggregator;
+pub use stream_timeouts::StreamTimeouts;
 pub use tokens::{
     ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenSource, TokenStatsSummary,
     TokenTracker, TokenUsageRecord,
@@ -1,19 +1,32 @@
 //! 统计聚合器
 //!
 //! 提供请求统计的聚合、分组和查询功能
+//!
+//! 日志按顺序号轮流写入多个分片，每个分片独立加锁，并发记录时几乎不会互相阻塞；
+//! 统计查询直接合并所有分片，计数与分位数均基于完整日志计算，与写入顺序无关；
+//! 只有 [`StatsAggregator::get_all`] 按顺序号还原写入顺序。
 
 use super::types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
 use chrono::{Duration, Utc};
 use parking_lot::RwLock;
 use proxycast_core::ProviderType;
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 最大分片数
+const MAX_SHARDS: usize = 16;
+
+/// 日志分片，元素为（写入顺序号, 日志）
+type Shard = RwLock<VecDeque<(u64, RequestLog)>>;
 
 /// 统计聚合器
 ///
 /// 管理请求日志的统计聚合，支持按时间范围、Provider 和模型分组统计
 pub struct StatsAggregator {
-    /// 日志队列
-    logs: RwLock<VecDeque<RequestLog>>,
+    /// 日志分片
+    shards: Box<[Shard]>,
+    /// 下一条日志的顺序号（同时决定写入的分片）
+    next_seq: AtomicU64,
     /// 日志保留时长
     retention: Duration,
     /// 最大日志条数
@@ -27,8 +40,19 @@ impl StatsAggregator {
     /// * `retention` - 日志保留时长
     /// * `max_logs` - 最大日志条数
     pub fn new(retention: Duration, max_logs: usize) -> Self {
+        let shard_count = MAX_SHARDS.min(max_logs).max(1);
+        let shards = (0..shard_count)
+            .map(|i| {
+                RwLock::new(VecDeque::with_capacity(Self::shard_capacity(
+                    max_logs,
+                    shard_count,
+                    i,
+                )))
+            })
+            .collect();
         Self {
-            logs: RwLock::new(VecDeque::with_capacity(max_logs)),
+            shards,
+            next_seq: AtomicU64::new(0),
             retention,
             max_logs,
         }
@@ -39,27 +63,41 @@ impl StatsAggregator {
         Self::new(Duration::days(7), 10000)
     }
 
+    /// 分片容量：`max_logs` 均分到各分片，余数分给前面的分片，合计恰好为 `max_logs`
+    fn shard_capacity(max_logs: usize, shard_count: usize, index: usize) -> usize {
+        max_logs / shard_count + usize::from(index < max_logs % shard_count)
+    }
+
     /// 记录请求日志
     ///
-    /// 将日志添加到聚合器中，并自动清理过期日志
+    /// 将日志添加到聚合器中，并自动清理过期日志。
+    /// 只锁定日志所在的分片，可以通过共享引用并发调用。
     pub fn record(&self, log: RequestLog) {
-        let mut logs = self.logs.write();
-        logs.push_back(log);
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.insert(seq, log);
+    }
+
+    /// 把日志写入顺序号对应的分片
+    ///
+    /// 顺序号在加锁前分配，同一分片的两个写入者可能以相反的顺序拿到锁。
+    /// 按顺序号插入保持分片有序，超出容量时淘汰的始终是最早写入的日志。
+    fn insert(&self, seq: u64, log: RequestLog) {
+        let index = (seq % self.shards.len() as u64) as usize;
+        let capacity = Self::shard_capacity(self.max_logs, self.shards.len(), index);
+
+        let mut logs = self.shards[index].write();
+        // 绝大多数情况下插入位置就在末尾
+        let pos = logs.partition_point(|(s, _)| *s < seq);
+        logs.insert(pos, (seq, log));
 
         // 清理超出数量限制的日志
-        while logs.len() > self.max_logs {
+        while logs.len() > capacity {
             logs.pop_front();
         }
 
         // 清理过期日志
         let cutoff = Utc::now() - self.retention;
-        while let Some(front) = logs.front() {
-            if front.timestamp < cutoff {
-                logs.pop_front();
-            } else {
-                break;
-            }
-        }
+        Self::drop_expired(&mut logs, cutoff);
     }
 
     /// 获取统计摘要
@@ -71,55 +109,72 @@ impl StatsAggregator {
         StatsSummary::from_logs(&logs)
     }
 
-    /// 获取指定时间范围内的日志
+    /// 获取指定时间范围内的日志（不保证顺序）
     fn get_logs_in_range(&self, range: Option<TimeRange>) -> Vec<RequestLog> {
-        let logs = self.logs.read();
-        match range {
-            Some(r) => logs
-                .iter()
-                .filter(|l| r.contains(&l.timestamp))
-                .cloned()
-                .collect(),
-            None => logs.iter().cloned().collect(),
+        let mut merged = Vec::new();
+        for shard in self.shards.iter() {
+            let logs = shard.read();
+            merged.extend(
+                logs.iter()
+                    .filter(|(_, l)| range.as_ref().is_none_or(|r| r.contains(&l.timestamp)))
+                    .map(|(_, log)| log.clone()),
+            );
         }
+        merged
     }
 
-    /// 获取所有日志
+    /// 获取所有日志（按写入顺序）
     pub fn get_all(&self) -> Vec<RequestLog> {
-        self.logs.read().iter().cloned().collect()
+        let mut merged: Vec<(u64, RequestLog)> = Vec::with_capacity(self.len());
+        for shard in self.shards.iter() {
+            merged.extend(shard.read().iter().cloned());
+        }
+        // 各分片内部已有序，稳定排序只需合并有序段
+        merged.sort_by_key(|(seq, _)| *seq);
+        merged.into_iter().map(|(_, log)| log).collect()
     }
 
     /// 获取日志数量
     pub fn len(&self) -> usize {
-        self.logs.read().len()
+        self.shards.iter().map(|shard| shard.read().len()).sum()
     }
 
     /// 检查是否为空
     pub fn is_empty(&self) -> bool {
-        self.logs.read().is_empty()
+        self.shards.iter().all(|shard| shard.read().is_empty())
     }
 
     /// 清空所有日志
     pub fn clear(&self) {
-        self.logs.write().clear();
+        for shard in self.shards.iter() {
+            shard.write().clear();
+        }
     }
 
     /// 清理过期日志
     ///
     /// 返回清理的日志数量
     pub fn cleanup_expired(&self) -> usize {
-        let mut logs = self.logs.write();
         let cutoff = Utc::now() - self.retention;
-        let initial_len = logs.len();
+        self.shards
+            .iter()
+            .map(|shard| Self::drop_expired(&mut shard.write(), cutoff))
+            .sum()
+    }
 
-        while let Some(front) = logs.front() {
+    /// 从分片头部移除早于 `cutoff` 的日志，返回移除数量
+    fn drop_expired(
+        logs: &mut VecDeque<(u64, RequestLog)>,
+        cutoff: chrono::DateTime<Utc>,
+    ) -> usize {
+        let initial_len = logs.len();
+        while let Some((_, front)) = logs.front() {
             if front.timestamp < cutoff {
                 logs.pop_front();
             } else {
                 break;
             }
         }
-
         initial_len - logs.len()
     }
 }
@@ -258,3 +313,112 @@ impl StatsAggregator {
         ModelStats::from_logs(model.to_string(), &filtered)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::sync::Arc;
+
+    fn log(id: String, provider: ProviderType) -> RequestLog {
+        let mut log = RequestLog::new(id, provider, "model".to_string(), false);
+        log.mark_success(100, 200);
+        log
+    }
+
+    #[test]
+    fn test_concurrent_records_are_exact() {
+        let aggregator = Arc::new(StatsAggregator::with_defaults());
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let aggregator = aggregator.clone();
+                std::thread::spawn(move || {
+                    let provider = if t % 2 == 0 {
+                        ProviderType::Kiro
+                    } else {
+                        ProviderType::OpenAI
+                    };
+                    for i in 0..1000 {
+                        aggregator.record(log(format!("{t}-{i}"), provider));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(aggregator.len(), 8000);
+        let summary = aggregator.summary(None);
+        assert_eq!(summary.total_requests, 8000);
+        assert_eq!(summary.successful_requests, 8000);
+        let by_provider = aggregator.by_provider(None);
+        assert_eq!(
+            by_provider[&ProviderType::Kiro].summary.total_requests,
+            4000
+        );
+        assert_eq!(
+            by_provider[&ProviderType::OpenAI].summary.total_requests,
+            4000
+        );
+    }
+
+    #[test]
+    fn test_busy_shard_does_not_block_other_writers() {
+        let aggregator = Arc::new(StatsAggregator::with_defaults());
+        let shard_count = aggregator.shards.len();
+
+        // 占住第一个分片（模拟一次耗时的读取），其余分片的写入不受影响
+        let guard = aggregator.shards[0].write();
+        aggregator.next_seq.store(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        let writer = {
+            let aggregator = aggregator.clone();
+            std::thread::spawn(move || {
+                for i in 1..shard_count {
+                    aggregator.record(log(format!("req-{i}"), ProviderType::Kiro));
+                }
+                tx.send(()).unwrap();
+            })
+        };
+        rx.recv_timeout(std::time::Duration::from_secs(5))
+            .expect("写入其他分片时不应被阻塞");
+        drop(guard);
+        writer.join().unwrap();
+
+        aggregator.record(log("req-0".to_string(), ProviderType::Kiro));
+        assert_eq!(aggregator.len(), shard_count);
+        // 合并后保持写入顺序
+        let ids: Vec<_> = aggregator.get_all().into_iter().map(|l| l.id).collect();
+        assert_eq!(ids.first().map(String::as_str), Some("req-1"));
+        assert_eq!(ids.last().map(String::as_str), Some("req-0"));
+    }
+
+    #[test]
+    fn test_out_of_order_writers_evict_oldest() {
+        // 每个分片容量为 1
+        let aggregator = StatsAggregator::new(Duration::days(7), MAX_SHARDS);
+        let shard_count = aggregator.shards.len() as u64;
+
+        // 同一分片的两个写入者以相反的顺序拿到锁
+        aggregator.insert(shard_count, log("newer".to_string(), ProviderType::Kiro));
+        aggregator.insert(0, log("older".to_string(), ProviderType::Kiro));
+
+        let ids: Vec<_> = aggregator.get_all().into_iter().map(|l| l.id).collect();
+        assert_eq!(ids, vec!["newer".to_string()]);
+    }
+
+    #[test]
+    fn test_shard_capacities_sum_to_max_logs() {
+        for max_logs in [1, 10, 20, 10000, 10001] {
+            let aggregator = StatsAggregator::new(Duration::days(7), max_logs);
+            for i in 0..max_logs * 2 + 3 {
+                aggregator.record(log(format!("req-{i}"), ProviderType::Kiro));
+            }
+            assert_eq!(aggregator.len(), max_logs);
+            // 保留的是最近写入的日志
+            let last = aggregator.get_all().pop().unwrap();
+            assert_eq!(last.id, format!("req-{}", max_logs * 2 + 2));
+        }
+    }
+}
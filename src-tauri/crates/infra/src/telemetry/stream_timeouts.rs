@@ -0,0 +1,60 @@
+//! 空闲看门狗中止的流式请求标记
+//!
+//! 看门狗中止流式响应时会丢弃上游流，流式遥测看到的是"响应体未读完就被丢弃"，
+//! 与客户端断开无法区分。看门狗在丢弃上游流之前标记请求 ID，
+//! 流式遥测在响应体结束时据此把请求记为超时。
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+use super::types::RequestStatus;
+
+/// 未被取出的标记最多保留的条数（正常情况下标记随即被取出）
+const MAX_PENDING: usize = 256;
+
+/// 被空闲看门狗中止的流式请求
+#[derive(Debug, Default)]
+pub struct StreamTimeouts {
+    ids: Mutex<VecDeque<String>>,
+}
+
+impl StreamTimeouts {
+    /// 创建空的标记表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 标记请求已被看门狗中止
+    pub fn mark(&self, id: &str) {
+        let mut ids = self.ids.lock();
+        if ids.len() >= MAX_PENDING {
+            ids.pop_front();
+        }
+        ids.push_back(id.to_string());
+    }
+
+    /// 取出请求的超时标记，返回请求是否被看门狗中止
+    pub fn take(&self, id: &str) -> bool {
+        let mut ids = self.ids.lock();
+        match ids.iter().position(|pending| pending == id) {
+            Some(index) => {
+                ids.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 流式响应体结束时的请求状态
+    ///
+    /// 被看门狗中止的记为超时，其余提前丢弃的记为已取消（客户端断开）。
+    pub fn end_status(&self, id: &str, client_disconnected: bool) -> RequestStatus {
+        if self.take(id) {
+            RequestStatus::Timeout
+        } else if client_disconnected {
+            RequestStatus::Cancelled
+        } else {
+            RequestStatus::Success
+        }
+    }
+}
@@ -3,12 +3,14 @@
 //! 使用 proptest 进行属性测试
 
 use super::{
-    LogRotationConfig, RequestLog, RequestLogger, RequestStatus, StatsAggregator, TimeRange,
+    LogRotationConfig, RequestLog, RequestLogger, RequestStatus, StatsAggregator, StreamTimeouts,
+    TimeRange,
 };
 use chrono::{Duration, Utc};
 use proptest::prelude::*;
 use proxycast_core::ProviderType;
 use std::collections::HashSet;
+use std::sync::Arc;
 
 /// 生成随机的 ProviderType
 fn arb_provider_type() -> impl Strategy<Value = ProviderType> {
@@ -110,6 +112,7 @@ fn create_test_logger() -> RequestLogger {
         retention_days: 7,
         max_file_size: 10 * 1024 * 1024,
         enable_file_logging: false, // 测试时禁用文件日志
+        sample_rate: 1.0,
     };
     RequestLogger::new(config).expect("Failed to create test logger")
 }
@@ -342,6 +345,7 @@ proptest! {
             retention_days: 7,
             max_file_size: 10 * 1024 * 1024,
             enable_file_logging: false,
+            sample_rate: 1.0,
         };
         let logger = RequestLogger::new(config).expect("Failed to create logger");
 
@@ -419,6 +423,81 @@ fn test_logger_basic_operations() {
     assert_eq!(retrieved.unwrap().status, RequestStatus::Success);
 }
 
+#[test]
+fn test_logger_filter_by_route() {
+    let logger = create_test_logger();
+
+    // 默认路由与选择器路由的请求分别打上不同的路由标签
+    let routes = [
+        "default",
+        "selector:my-kiro",
+        "default",
+        "pool:gemini",
+        "selector:backup",
+    ];
+    for (i, route) in routes.iter().enumerate() {
+        let mut log = RequestLog::new(
+            format!("req-{i}"),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            false,
+        );
+        log.set_route(route.to_string());
+        log.mark_success(100, 200);
+        logger.record(log).expect("Failed to record log");
+    }
+    // 未打标签的旧日志不匹配任何路由
+    logger
+        .record(RequestLog::new(
+            "untagged".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            false,
+        ))
+        .expect("Failed to record log");
+
+    let ids = |route: &str| -> Vec<String> {
+        let mut ids: Vec<String> = logger
+            .get_by_route(route)
+            .into_iter()
+            .map(|log| log.id)
+            .collect();
+        ids.sort();
+        ids
+    };
+    assert_eq!(ids("default"), vec!["req-0", "req-2"]);
+    assert_eq!(ids("selector"), vec!["req-1", "req-4"]);
+    assert_eq!(ids("selector:my-kiro"), vec!["req-1"]);
+    assert_eq!(ids("pool"), vec!["req-3"]);
+    assert!(ids("pool:kiro").is_empty());
+}
+
+#[test]
+fn test_logger_records_api_key_label() {
+    let logger = create_test_logger();
+
+    // 不同客户端使用不同的服务器 API 密钥，旧的单个密钥没有标签
+    for (id, label) in [("req-ci", Some("ci")), ("req-legacy", None)] {
+        let mut log = RequestLog::new(
+            id.to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            false,
+        );
+        if let Some(label) = label {
+            log.set_api_key_label(label.to_string());
+        }
+        log.mark_success(100, 200);
+        logger.record(log).expect("Failed to record log");
+    }
+
+    let ci = logger.get_by_id("req-ci").unwrap();
+    assert_eq!(ci.api_key_label.as_deref(), Some("ci"));
+    let json = serde_json::to_value(&ci).unwrap();
+    assert_eq!(json["api_key_label"], "ci");
+    assert_eq!(logger.get_by_id("req-legacy").unwrap().api_key_label, None);
+}
+
 #[test]
 fn test_logger_clear() {
     let logger = create_test_logger();
@@ -472,13 +551,14 @@ fn test_stats_by_model() {
     let logger = create_test_logger();
 
     // 记录不同模型的日志
-    for model in ["model-a", "model-b", "model-a"] {
+    for (model, ttft) in [("model-a", 50), ("model-b", 300), ("model-a", 80)] {
         let mut log = RequestLog::new(
             uuid::Uuid::new_v4().to_string(),
             ProviderType::Kiro,
             model.to_string(),
-            false,
+            true,
         );
+        log.set_ttft(ttft);
         log.mark_success(100, 200);
         logger.record(log).expect("Failed to record log");
     }
@@ -489,6 +569,9 @@ fn test_stats_by_model() {
     assert!(stats.contains_key("model-b"));
     assert_eq!(stats["model-a"].summary.total_requests, 2);
     assert_eq!(stats["model-b"].summary.total_requests, 1);
+    // 首 token 耗时按模型分别统计
+    assert_eq!(stats["model-a"].summary.p95_ttft_ms, Some(80));
+    assert_eq!(stats["model-b"].summary.p95_ttft_ms, Some(300));
 }
 
 // ========== StatsAggregator 属性测试 ==========
@@ -726,6 +809,116 @@ fn test_stats_aggregator_basic_operations() {
     assert_eq!(summary.successful_requests, 1);
 }
 
+/// 响应体释放时按 [`StreamTimeouts`] 分类并写入请求日志（与流式遥测一致）
+struct StreamEndRecorder {
+    logger: Arc<RequestLogger>,
+    timeouts: Arc<StreamTimeouts>,
+    id: &'static str,
+}
+
+impl Drop for StreamEndRecorder {
+    fn drop(&mut self) {
+        // 上游流从未读完：不是超时就是客户端断开
+        let mut log = RequestLog::new(
+            self.id.to_string(),
+            ProviderType::Claude,
+            "claude-sonnet".to_string(),
+            true,
+        );
+        match self.timeouts.end_status(self.id, true) {
+            RequestStatus::Timeout => log.mark_timeout(100),
+            RequestStatus::Cancelled => log.mark_cancelled(100),
+            _ => log.mark_success(100, 200),
+        }
+        self.logger.record(log).unwrap();
+    }
+}
+
+/// 输出一个数据块后挂起的 SSE 服务，响应体释放时记录请求日志
+fn stalled_sse_app(logger: Arc<RequestLogger>, timeouts: Arc<StreamTimeouts>) -> axum::Router {
+    use futures::StreamExt;
+
+    axum::Router::new().route(
+        "/v1/messages",
+        axum::routing::post(move |headers: axum::http::HeaderMap| {
+            let recorder = StreamEndRecorder {
+                logger: logger.clone(),
+                timeouts: timeouts.clone(),
+                id: if headers.contains_key("x-request-id") {
+                    "stream-timeout"
+                } else {
+                    "stream-cancel"
+                },
+            };
+            async move {
+                let body = futures::stream::once(async {
+                    Ok::<_, std::io::Error>("event: message_start\ndata: {}\n\n")
+                })
+                .chain(futures::stream::pending())
+                .map(move |chunk| {
+                    let _ = &recorder;
+                    chunk
+                });
+                axum::response::Response::builder()
+                    .header("content-type", "text/event-stream")
+                    .body(axum::body::Body::from_stream(body))
+                    .unwrap()
+            }
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_watchdog_abort_is_logged_as_timeout() {
+    use proxycast_core::middleware::{StreamIdleTimeoutEvent, StreamIdleTimeoutLayer};
+    use tower::ServiceExt;
+
+    let logger = Arc::new(create_test_logger());
+    let timeouts = Arc::new(StreamTimeouts::new());
+    let hook_timeouts = timeouts.clone();
+    let app = stalled_sse_app(logger.clone(), timeouts.clone()).layer(
+        StreamIdleTimeoutLayer::new(std::time::Duration::from_millis(50)).on_timeout(Arc::new(
+            move |event: &StreamIdleTimeoutEvent| {
+                if let Some(request_id) = &event.request_id {
+                    hook_timeouts.mark(request_id);
+                }
+            },
+        )),
+    );
+
+    let request = |id: Option<&str>| {
+        let mut builder = axum::http::Request::post("/v1/messages");
+        if let Some(id) = id {
+            builder = builder.header("x-request-id", id);
+        }
+        builder.body(axum::body::Body::empty()).unwrap()
+    };
+
+    // 上游挂起：看门狗中止，请求日志记为超时
+    let response = app
+        .clone()
+        .oneshot(request(Some("stream-timeout")))
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("timeout_error"));
+    assert_eq!(
+        logger.get_by_id("stream-timeout").unwrap().status,
+        RequestStatus::Timeout
+    );
+    assert!(!timeouts.take("stream-timeout"));
+
+    // 客户端在超时前断开：仍记为已取消
+    let response = app.oneshot(request(None)).await.unwrap();
+    drop(response);
+    assert_eq!(
+        logger.get_by_id("stream-cancel").unwrap().status,
+        RequestStatus::Cancelled
+    );
+}
+
 #[test]
 fn test_stats_aggregator_clear() {
     let aggregator = create_test_aggregator();
@@ -840,3 +1033,44 @@ fn test_stats_aggregator_max_logs_limit() {
     // 验证日志数量不超过限制
     assert_eq!(aggregator.len(), 10);
 }
+
+#[test]
+fn test_sampling_keeps_aggregates_exact() {
+    let aggregator = StatsAggregator::new(Duration::days(7), 10000);
+    let logger = RequestLogger::new(LogRotationConfig {
+        max_memory_logs: 10000,
+        retention_days: 7,
+        max_file_size: 10 * 1024 * 1024,
+        enable_file_logging: false,
+        sample_rate: 0.1,
+    })
+    .expect("Failed to create logger");
+
+    // 1000 个成功请求，每 10 个穿插一个失败请求
+    for i in 0..1100 {
+        let mut log = RequestLog::new(
+            format!("req-{i}"),
+            ProviderType::Kiro,
+            "claude-sonnet-4".to_string(),
+            false,
+        );
+        if i % 11 == 10 {
+            log.mark_failed(100, Some(500), "upstream error".to_string());
+        } else {
+            log.mark_success(100, 200);
+        }
+        aggregator.record(log.clone());
+        logger.record(log).unwrap();
+    }
+
+    // 聚合统计保持精确
+    let summary = aggregator.summary(None);
+    assert_eq!(summary.total_requests, 1100);
+    assert_eq!(summary.successful_requests, 1000);
+    assert_eq!(summary.failed_requests, 100);
+
+    // 失败请求全部保留，成功请求约保留 sample_rate * N 条
+    assert_eq!(logger.get_by_status(RequestStatus::Failed).len(), 100);
+    let sampled = logger.get_by_status(RequestStatus::Success).len();
+    assert!((90..=110).contains(&sampled), "sampled: {sampled}");
+}
@@ -31,6 +31,12 @@ pub struct TokenUsageRecord {
     pub source: TokenSource,
     /// 关联的请求 ID
     pub request_id: Option<String>,
+    /// 会话 ID（来自 `X-ProxyCast-Session` 请求头）
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// 客户端 ID
+    #[serde(default)]
+    pub client_id: Option<String>,
 }
 
 impl TokenUsageRecord {
@@ -53,6 +59,8 @@ impl TokenUsageRecord {
             total_tokens: input_tokens + output_tokens,
             source,
             request_id: None,
+            session_id: None,
+            client_id: None,
         }
     }
 
@@ -61,6 +69,17 @@ impl TokenUsageRecord {
         self.request_id = Some(request_id);
         self
     }
+
+    /// 设置归属的会话与客户端（用于按使用方统计）
+    pub fn with_attribution(
+        mut self,
+        session_id: Option<String>,
+        client_id: Option<String>,
+    ) -> Self {
+        self.session_id = session_id;
+        self.client_id = client_id;
+        self
+    }
 }
 
 /// Token 来源
@@ -389,6 +408,51 @@ impl TokenTracker {
             .collect()
     }
 
+    /// 按会话分组统计（未关联会话的记录不计入）
+    pub fn by_session(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> HashMap<String, TokenStatsSummary> {
+        self.summarize_by(start, end, |r| r.session_id.as_deref())
+    }
+
+    /// 按客户端分组统计（未识别客户端的记录不计入）
+    pub fn by_client(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> HashMap<String, TokenStatsSummary> {
+        self.summarize_by(start, end, |r| r.client_id.as_deref())
+    }
+
+    fn summarize_by<F>(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        key: F,
+    ) -> HashMap<String, TokenStatsSummary>
+    where
+        F: Fn(&TokenUsageRecord) -> Option<&str>,
+    {
+        let records = match (start, end) {
+            (Some(s), Some(e)) => self.get_by_time_range(s, e),
+            _ => self.get_all(),
+        };
+
+        let mut grouped: HashMap<String, Vec<TokenUsageRecord>> = HashMap::new();
+        for record in records {
+            if let Some(k) = key(&record) {
+                grouped.entry(k.to_string()).or_default().push(record);
+            }
+        }
+
+        grouped
+            .into_iter()
+            .map(|(k, records)| (k, TokenStatsSummary::from_records(&records)))
+            .collect()
+    }
+
     /// 按时间段汇总（按天）
     pub fn by_day(&self, days: i64) -> Vec<PeriodTokenStats> {
         let now = Utc::now();
@@ -601,6 +665,43 @@ mod token_tests {
         assert_eq!(record.request_id, Some("req-123".to_string()));
     }
 
+    #[test]
+    fn test_token_stats_by_session_and_client() {
+        let tracker = TokenTracker::with_defaults();
+        let record = |input, output, session: Option<&str>, client: Option<&str>| {
+            TokenUsageRecord::new(
+                uuid::Uuid::new_v4().to_string(),
+                ProviderType::Kiro,
+                "claude-sonnet".to_string(),
+                input,
+                output,
+                TokenSource::Actual,
+            )
+            .with_attribution(session.map(String::from), client.map(String::from))
+        };
+        tracker.record(record(100, 50, Some("alice"), Some("cline")));
+        tracker.record(record(200, 20, Some("alice"), Some("claude_code")));
+        tracker.record(record(10, 5, Some("bob"), Some("cline")));
+        tracker.record(record(1, 1, None, None));
+
+        let tagged = tracker.get_all();
+        assert_eq!(tagged[0].session_id.as_deref(), Some("alice"));
+        assert_eq!(tagged[0].client_id.as_deref(), Some("cline"));
+
+        let by_session = tracker.by_session(None, None);
+        assert_eq!(by_session.len(), 2);
+        let alice = &by_session["alice"];
+        assert_eq!(alice.record_count, 2);
+        assert_eq!(alice.total_input_tokens, 300);
+        assert_eq!(alice.total_output_tokens, 70);
+        assert_eq!(alice.total_tokens, 370);
+        assert_eq!(by_session["bob"].total_tokens, 15);
+
+        let by_client = tracker.by_client(None, None);
+        assert_eq!(by_client["cline"].total_tokens, 165);
+        assert_eq!(by_client["claude_code"].total_tokens, 220);
+    }
+
     #[test]
     fn test_token_stats_summary_from_records() {
         let records = vec![
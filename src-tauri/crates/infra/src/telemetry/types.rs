@@ -3,6 +3,8 @@
 //! 定义请求日志、统计数据等核心类型
 
 use chrono::{DateTime, Utc};
+use proxycast_core::credential::SelectionTrace;
+use proxycast_core::processor::RequestTiming;
 use proxycast_core::ProviderType;
 use serde::{Deserialize, Serialize};
 
@@ -67,6 +69,24 @@ pub struct RequestLog {
     pub credential_id: Option<String>,
     /// 重试次数
     pub retry_count: u32,
+    /// 分阶段耗时（选择 / 刷新 / 上游 / 总计）
+    #[serde(default)]
+    pub timing: Option<RequestTiming>,
+    /// 识别到的客户端 ID
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// 流式请求的首 token 耗时（毫秒，从收到请求到发出首个内容字节）
+    #[serde(default)]
+    pub ttft_ms: Option<u64>,
+    /// 路由标签（`default`、`selector:<name>`、`pool:<type>`）
+    #[serde(default)]
+    pub route: Option<String>,
+    /// 凭证选择追踪
+    #[serde(default)]
+    pub selection_trace: Option<SelectionTrace>,
+    /// 客户端使用的服务器 API 密钥标签
+    #[serde(default)]
+    pub api_key_label: Option<String>,
 }
 
 impl RequestLog {
@@ -87,6 +107,12 @@ impl RequestLog {
             is_streaming,
             credential_id: None,
             retry_count: 0,
+            timing: None,
+            client_id: None,
+            ttft_ms: None,
+            route: None,
+            selection_trace: None,
+            api_key_label: None,
         }
     }
 
@@ -133,6 +159,51 @@ impl RequestLog {
         self.credential_id = Some(id);
     }
 
+    /// 设置分阶段耗时
+    pub fn set_timing(&mut self, timing: RequestTiming) {
+        self.timing = Some(timing);
+    }
+
+    /// 设置客户端 ID
+    pub fn set_client_id(&mut self, client_id: String) {
+        self.client_id = Some(client_id);
+    }
+
+    /// 设置首 token 耗时
+    pub fn set_ttft(&mut self, ttft_ms: u64) {
+        self.ttft_ms = Some(ttft_ms);
+    }
+
+    /// 设置路由标签
+    pub fn set_route(&mut self, route: String) {
+        self.route = Some(route);
+    }
+
+    /// 设置凭证选择追踪
+    pub fn set_selection_trace(&mut self, trace: SelectionTrace) {
+        self.selection_trace = Some(trace);
+    }
+
+    /// 设置 API 密钥标签
+    pub fn set_api_key_label(&mut self, label: String) {
+        self.api_key_label = Some(label);
+    }
+
+    /// 是否匹配路由过滤条件
+    ///
+    /// 过滤条件含 `:` 时精确匹配（如 `selector:my-kiro`）；
+    /// 否则按路由类型匹配（如 `selector` 匹配所有选择器路由）。
+    pub fn matches_route(&self, filter: &str) -> bool {
+        let Some(route) = self.route.as_deref() else {
+            return false;
+        };
+        if filter.contains(':') {
+            route == filter
+        } else {
+            route.split(':').next() == Some(filter)
+        }
+    }
+
     /// 增加重试次数
     pub fn increment_retry(&mut self) {
         self.retry_count += 1;
@@ -198,6 +269,12 @@ pub struct StatsSummary {
     pub min_latency_ms: Option<u64>,
     /// 最大延迟（毫秒）
     pub max_latency_ms: Option<u64>,
+    /// 平均首 token 耗时（毫秒，仅统计记录了首 token 的流式请求）
+    #[serde(default)]
+    pub avg_ttft_ms: Option<f64>,
+    /// 首 token 耗时 P95（毫秒）
+    #[serde(default)]
+    pub p95_ttft_ms: Option<u64>,
     /// 总输入 Token 数
     pub total_input_tokens: u64,
     /// 总输出 Token 数
@@ -240,6 +317,12 @@ impl StatsSummary {
         let min_latency_ms = latencies.iter().min().copied();
         let max_latency_ms = latencies.iter().max().copied();
 
+        let mut ttfts: Vec<u64> = logs.iter().filter_map(|l| l.ttft_ms).collect();
+        ttfts.sort_unstable();
+        let avg_ttft_ms =
+            (!ttfts.is_empty()).then(|| ttfts.iter().sum::<u64>() as f64 / ttfts.len() as f64);
+        let p95_ttft_ms = percentile(&ttfts, 0.95);
+
         let total_input_tokens: u64 = logs
             .iter()
             .filter_map(|l| l.input_tokens)
@@ -261,6 +344,8 @@ impl StatsSummary {
             avg_latency_ms,
             min_latency_ms,
             max_latency_ms,
+            avg_ttft_ms,
+            p95_ttft_ms,
             total_input_tokens,
             total_output_tokens,
             total_tokens,
@@ -268,6 +353,15 @@ impl StatsSummary {
     }
 }
 
+/// 已排序样本的百分位数（最近秩法）
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
 /// Provider 统计
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProviderStats {
@@ -329,6 +423,24 @@ mod type_tests {
         assert_eq!(log.retry_count, 0);
     }
 
+    #[test]
+    fn test_request_log_matches_route() {
+        let mut log = RequestLog::new(
+            "test-id".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            false,
+        );
+        assert!(!log.matches_route("default"));
+
+        log.set_route("selector:my-kiro".to_string());
+        assert!(log.matches_route("selector"));
+        assert!(log.matches_route("selector:my-kiro"));
+        assert!(!log.matches_route("selector:my"));
+        assert!(!log.matches_route("default"));
+        assert!(!log.matches_route("pool"));
+    }
+
     #[test]
     fn test_request_log_mark_success() {
         let mut log = RequestLog::new(
@@ -442,5 +554,30 @@ mod type_tests {
         assert_eq!(summary.max_latency_ms, Some(300));
         assert_eq!(summary.total_input_tokens, 150);
         assert_eq!(summary.total_output_tokens, 75);
+        assert_eq!(summary.avg_ttft_ms, None);
+        assert_eq!(summary.p95_ttft_ms, None);
+    }
+
+    #[test]
+    fn test_stats_summary_ttft_percentile() {
+        let logs: Vec<RequestLog> = (1..=20)
+            .map(|i| {
+                let mut log =
+                    RequestLog::new(i.to_string(), ProviderType::Kiro, "model".to_string(), true);
+                log.mark_success(i * 1000, 200);
+                log.set_ttft(i * 10);
+                log
+            })
+            .chain(std::iter::once(RequestLog::new(
+                "non-stream".to_string(),
+                ProviderType::Kiro,
+                "model".to_string(),
+                false,
+            )))
+            .collect();
+
+        let summary = StatsSummary::from_logs(&logs);
+        assert_eq!(summary.avg_ttft_ms, Some(105.0));
+        assert_eq!(summary.p95_ttft_ms, Some(190));
     }
 }
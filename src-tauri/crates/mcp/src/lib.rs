@@ -5,16 +5,18 @@
 
 pub mod client;
 pub mod manager;
+pub mod secret_ref;
 pub mod tool_converter;
 pub mod types;
 
 pub use client::{McpClientWrapper, ProxyCastMcpClient};
 pub use manager::McpClientManager;
+pub use secret_ref::{CredentialResolver, DbCredentialResolver, DynCredentialResolver};
 pub use tool_converter::ToolConverter;
 pub use types::{
     McpContent, McpError, McpManagerState, McpPromptArgument, McpPromptDefinition,
-    McpPromptMessage, McpPromptResult, McpResourceContent, McpResourceDefinition,
+    McpPromptMessage, McpPromptResult, McpResourceChunk, McpResourceContent, McpResourceDefinition,
     McpServerCapabilities, McpServerConfig, McpServerErrorPayload, McpServerInfo,
     McpServerStartedPayload, McpServerStoppedPayload, McpToolCall, McpToolDefinition,
-    McpToolResult, McpToolsUpdatedPayload,
+    McpToolResult, McpToolsUpdatedPayload, ResourceChunks,
 };
@@ -26,6 +26,7 @@
 
 #![allow(dead_code)]
 
+use futures::stream::{BoxStream, StreamExt};
 use proxycast_core::DynEmitter;
 use std::collections::HashMap;
 use std::process::Stdio;
@@ -40,8 +41,15 @@ use rmcp::transport::TokioChildProcess;
 use rmcp::ServiceExt;
 
 use crate::client::McpClientWrapper;
+use crate::secret_ref::{resolve_env, DynCredentialResolver};
 use crate::types::*;
 
+/// `read_resource` 默认允许缓冲的最大资源大小（8 MiB）
+pub const DEFAULT_MAX_BUFFERED_RESOURCE_BYTES: usize = 8 * 1024 * 1024;
+
+/// `read_resource_stream` 的分块大小（64 KiB）
+pub const RESOURCE_CHUNK_SIZE: usize = 64 * 1024;
+
 /// MCP 客户端管理器
 ///
 /// 负责管理所有 MCP 服务器的连接和生命周期。
@@ -97,6 +105,16 @@ pub struct McpClientManager {
     /// - mcp:server_error
     /// - mcp:tools_updated
     emitter: Option<DynEmitter>,
+
+    /// 凭证解析器
+    ///
+    /// 用于在启动时解析 env 中的 `${credential:uuid:field}` 引用。
+    credential_resolver: Option<DynCredentialResolver>,
+
+    /// `read_resource` 允许缓冲的最大资源大小（字节）
+    ///
+    /// 超过时返回 `McpError::ResourceTooLarge`，大资源需使用 `read_resource_stream`。
+    max_buffered_resource_bytes: usize,
 }
 
 impl McpClientManager {
@@ -116,6 +134,8 @@ impl McpClientManager {
             clients: Arc::new(RwLock::new(HashMap::new())),
             tool_cache: Arc::new(RwLock::new(None)),
             emitter,
+            credential_resolver: None,
+            max_buffered_resource_bytes: DEFAULT_MAX_BUFFERED_RESOURCE_BYTES,
         }
     }
 
@@ -124,6 +144,16 @@ impl McpClientManager {
         self.emitter = Some(emitter);
     }
 
+    /// 设置凭证解析器
+    pub fn set_credential_resolver(&mut self, resolver: DynCredentialResolver) {
+        self.credential_resolver = Some(resolver);
+    }
+
+    /// 设置 `read_resource` 允许缓冲的最大资源大小（字节）
+    pub fn set_max_buffered_resource_bytes(&mut self, limit: usize) {
+        self.max_buffered_resource_bytes = limit;
+    }
+
     // ========================================================================
     // 连接池管理方法
     // ========================================================================
@@ -383,67 +413,16 @@ impl McpClientManager {
             return Err(McpError::ServerAlreadyRunning(name.to_string()));
         }
 
-        // 2. 构建命令
-        let mut command = Command::new(&config.command);
-        command.args(&config.args);
-
-        // 设置环境变量
-        for (key, value) in &config.env {
-            command.env(key, value);
-        }
-
-        // macOS GUI 应用的 PATH 通常不完整，需要补充常见的命令路径
-        // 确保 npx/node/uvx 等命令可被找到
-        if !config.env.contains_key("PATH") {
-            let current_path = std::env::var("PATH").unwrap_or_default();
-            let home = std::env::var("HOME").unwrap_or_else(|_| "/Users/unknown".to_string());
-            let extra_paths = [
-                format!("{home}/.nvm/versions/node/*/bin"),
-                format!("{home}/.local/bin"),
-                format!("{home}/.cargo/bin"),
-                format!("{home}/Library/pnpm"),
-                format!("{home}/.bun/bin"),
-                "/usr/local/bin".to_string(),
-                "/opt/homebrew/bin".to_string(),
-                "/opt/homebrew/sbin".to_string(),
-            ];
-            // 用 glob 展开 nvm 路径，取最新版本
-            let mut resolved_paths: Vec<String> = Vec::new();
-            for p in &extra_paths {
-                if p.contains('*') {
-                    if let Ok(entries) = glob::glob(p) {
-                        let mut matched: Vec<String> = entries
-                            .filter_map(|e| e.ok())
-                            .map(|e| e.to_string_lossy().to_string())
-                            .collect();
-                        matched.sort();
-                        if let Some(last) = matched.last() {
-                            resolved_paths.push(last.clone());
-                        }
-                    }
-                } else if std::path::Path::new(p).exists() {
-                    resolved_paths.push(p.clone());
-                }
-            }
-            if !resolved_paths.is_empty() {
-                let merged = if current_path.is_empty() {
-                    resolved_paths.join(":")
-                } else {
-                    format!("{}:{}", resolved_paths.join(":"), current_path)
-                };
-                command.env("PATH", &merged);
-                debug!(server_name = %name, "补充 PATH: {}", merged);
+        // 2. 解析凭证引用并构建命令
+        let env = match resolve_env(&config.env, self.credential_resolver.as_deref()) {
+            Ok(env) => env,
+            Err(e) => {
+                error!(server_name = %name, error = %e, "解析 MCP 环境变量失败");
+                self.emit_server_error(name, &e.to_string());
+                return Err(e);
             }
-        }
-
-        // 设置工作目录
-        if let Some(ref cwd) = config.cwd {
-            command.current_dir(cwd);
-        }
-
-        // Unix 系统设置进程组（使子进程独立于父进程组）
-        #[cfg(unix)]
-        command.process_group(0);
+        };
+        let command = Self::build_command(name, config, &env);
 
         // 3. 启动子进程并建立 stdio 连接
         let spawn_result = TokioChildProcess::builder(command)
@@ -563,6 +542,78 @@ impl McpClientManager {
         Ok(())
     }
 
+    /// 构建 MCP 服务器子进程命令
+    ///
+    /// `env` 为已解析凭证引用后的环境变量。
+    fn build_command(
+        name: &str,
+        config: &McpServerConfig,
+        env: &HashMap<String, String>,
+    ) -> Command {
+        let mut command = Command::new(&config.command);
+        command.args(&config.args);
+
+        // 设置环境变量
+        for (key, value) in env {
+            command.env(key, value);
+        }
+
+        // macOS GUI 应用的 PATH 通常不完整，需要补充常见的命令路径
+        // 确保 npx/node/uvx 等命令可被找到
+        if !env.contains_key("PATH") {
+            let current_path = std::env::var("PATH").unwrap_or_default();
+            let home = std::env::var("HOME").unwrap_or_else(|_| "/Users/unknown".to_string());
+            let extra_paths = [
+                format!("{home}/.nvm/versions/node/*/bin"),
+                format!("{home}/.local/bin"),
+                format!("{home}/.cargo/bin"),
+                format!("{home}/Library/pnpm"),
+                format!("{home}/.bun/bin"),
+                "/usr/local/bin".to_string(),
+                "/opt/homebrew/bin".to_string(),
+                "/opt/homebrew/sbin".to_string(),
+            ];
+            // 用 glob 展开 nvm 路径，取最新版本
+            let mut resolved_paths: Vec<String> = Vec::new();
+            for p in &extra_paths {
+                if p.contains('*') {
+                    if let Ok(entries) = glob::glob(p) {
+                        let mut matched: Vec<String> = entries
+                            .filter_map(|e| e.ok())
+                            .map(|e| e.to_string_lossy().to_string())
+                            .collect();
+                        matched.sort();
+                        if let Some(last) = matched.last() {
+                            resolved_paths.push(last.clone());
+                        }
+                    }
+                } else if std::path::Path::new(p).exists() {
+                    resolved_paths.push(p.clone());
+                }
+            }
+            if !resolved_paths.is_empty() {
+                let merged = if current_path.is_empty() {
+                    resolved_paths.join(":")
+                } else {
+                    format!("{}:{}", resolved_paths.join(":"), current_path)
+                };
+                command.env("PATH", &merged);
+                debug!(server_name = %name, "补充 PATH: {}", merged);
+            }
+        }
+
+        // 设置工作目录
+        if let Some(ref cwd) = config.cwd {
+            command.current_dir(cwd);
+        }
+
+        // Unix 系统设置进程组（使子进程独立于父进程组）
+        #[cfg(unix)]
+        command.process_group(0);
+
+        command
+    }
+
     /// 停止 MCP 服务器
     ///
     /// # Arguments
@@ -1353,7 +1404,8 @@ impl McpClientManager {
     ///
     /// # Returns
     ///
-    /// 返回资源内容。
+    /// 返回资源内容。资源超过 `max_buffered_resource_bytes` 时返回
+    /// `McpError::ResourceTooLarge`，此时应改用 `read_resource_stream`。
     ///
     /// # 实现步骤（Task 4.5）
     ///
@@ -1364,8 +1416,53 @@ impl McpClientManager {
     pub async fn read_resource(&self, uri: &str) -> Result<McpResourceContent, McpError> {
         info!(uri = %uri, "读取 MCP 资源");
 
+        let content = self
+            .fetch_resource(uri, Some(self.max_buffered_resource_bytes))
+            .await?;
+        Self::check_resource_size(&content, self.max_buffered_resource_bytes)?;
+        Ok(content)
+    }
+
+    /// 分块读取资源内容
+    ///
+    /// 适用于能够增量消费的调用方（如把大文件分段推送给前端）。MCP 的
+    /// `resources/read` 响应是一条完整消息，接收时只能整体读入；之后按
+    /// `RESOURCE_CHUNK_SIZE` 惰性切分，已产出的部分逐步释放，调用方和 IPC
+    /// 层都无需一次性持有或复制整个资源。
+    pub async fn read_resource_stream(
+        &self,
+        uri: &str,
+    ) -> Result<BoxStream<'static, McpResourceChunk>, McpError> {
+        info!(uri = %uri, "分块读取 MCP 资源");
+
+        let content = self.fetch_resource(uri, None).await?;
+        Ok(futures::stream::iter(content.into_chunks(RESOURCE_CHUNK_SIZE)).boxed())
+    }
+
+    /// 检查资源大小是否超过缓冲上限
+    fn check_resource_size(content: &McpResourceContent, limit: usize) -> Result<(), McpError> {
+        let size = content.size();
+        if size > limit {
+            warn!(uri = %content.uri, size, limit, "资源超过缓冲上限");
+            return Err(McpError::ResourceTooLarge {
+                uri: content.uri.clone(),
+                size,
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// 从目标服务器读取资源
+    ///
+    /// `limit` 不为空且资源列表声明的大小已超出时，不发起读取直接报错。
+    async fn fetch_resource(
+        &self,
+        uri: &str,
+        limit: Option<usize>,
+    ) -> Result<McpResourceContent, McpError> {
         // 1. 解析资源 URI，确定目标服务器
-        let (server_name, _) = self.resolve_resource_target(uri).await?;
+        let (server_name, declared_size) = self.resolve_resource_target(uri).await?;
 
         debug!(
             uri = %uri,
@@ -1373,6 +1470,16 @@ impl McpClientManager {
             "解析资源目标"
         );
 
+        if let (Some(limit), Some(size)) = (limit, declared_size) {
+            if size > limit {
+                return Err(McpError::ResourceTooLarge {
+                    uri: uri.to_string(),
+                    size,
+                    limit,
+                });
+            }
+        }
+
         // 2. 获取目标服务器的客户端
         let clients = self.clients.read().await;
         let wrapper = clients
@@ -1419,12 +1526,15 @@ impl McpClientManager {
     ///
     /// # Returns
     ///
-    /// 返回 (服务器名称, 资源 URI) 元组。
+    /// 返回 (服务器名称, 资源声明的大小) 元组。
     ///
     /// # 解析逻辑
     ///
     /// 遍历所有运行中的服务器，查找提供该资源的服务器。
-    async fn resolve_resource_target(&self, uri: &str) -> Result<(String, String), McpError> {
+    async fn resolve_resource_target(
+        &self,
+        uri: &str,
+    ) -> Result<(String, Option<usize>), McpError> {
         let clients = self.clients.read().await;
 
         // 在所有服务器中查找该资源
@@ -1439,8 +1549,8 @@ impl McpClientManager {
             if let Some(service) = wrapper.running_service() {
                 // 尝试获取资源列表并查找
                 if let Ok(resources) = service.list_all_resources().await {
-                    if resources.iter().any(|r| r.uri == uri) {
-                        return Ok((server_name.clone(), uri.to_string()));
+                    if let Some(resource) = resources.iter().find(|r| r.uri == uri) {
+                        return Ok((server_name.clone(), resource.size.map(|s| s as usize)));
                     }
                 }
             }
@@ -2428,4 +2538,150 @@ mod tests {
         assert!(mcp_content.text.is_none());
         assert!(mcp_content.blob.is_none());
     }
+
+    #[test]
+    fn test_resource_chunks_reassemble_text_and_blob() {
+        // 多字节字符不能被拆开
+        let text = "你好, MCP! ".repeat(100);
+        let content = McpResourceContent {
+            uri: "file:///large.txt".to_string(),
+            mime_type: Some("text/plain".to_string()),
+            text: Some(text.clone()),
+            blob: None,
+        };
+        let chunks: Vec<_> = content.into_chunks(64).collect();
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.text.as_ref().unwrap().len() <= 64));
+        assert!(chunks.iter().enumerate().all(|(i, c)| c.index == i));
+        assert!(chunks.last().unwrap().is_last);
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| !c.is_last));
+        let joined: String = chunks.iter().map(|c| c.text.clone().unwrap()).collect();
+        assert_eq!(joined, text);
+
+        // 二进制分块按 4 字符对齐
+        let blob = "QUJD".repeat(50);
+        let content = McpResourceContent {
+            uri: "file:///large.bin".to_string(),
+            mime_type: None,
+            text: None,
+            blob: Some(blob.clone()),
+        };
+        let chunks: Vec<_> = content.into_chunks(30).collect();
+        assert!(chunks
+            .iter()
+            .all(|c| c.text.is_none() && c.blob.as_ref().unwrap().len() % 4 == 0));
+        let joined: String = chunks.iter().map(|c| c.blob.clone().unwrap()).collect();
+        assert_eq!(joined, blob);
+    }
+
+    #[test]
+    fn test_resource_chunks_release_consumed_data() {
+        let content = McpResourceContent {
+            uri: "file:///large.txt".to_string(),
+            mime_type: None,
+            text: Some("x".repeat(64 * 1024)),
+            blob: None,
+        };
+        let mut chunks = content.into_chunks(1024);
+        let initial = chunks.buffered_bytes();
+
+        let consumed: usize = chunks
+            .by_ref()
+            .take(48)
+            .map(|c| c.text.unwrap().len())
+            .sum();
+        assert_eq!(consumed, 48 * 1024);
+        // 已产出的内容逐步释放，缓冲区不超过剩余内容的两倍
+        let remaining = initial - consumed;
+        assert!(chunks.buffered_bytes() <= 2 * remaining);
+
+        let rest: usize = chunks.map(|c| c.text.unwrap().len()).sum();
+        assert_eq!(consumed + rest, 64 * 1024);
+    }
+
+    #[test]
+    fn test_buffered_resource_size_guard() {
+        let content = McpResourceContent {
+            uri: "file:///large.txt".to_string(),
+            mime_type: None,
+            text: Some("x".repeat(2048)),
+            blob: None,
+        };
+        assert!(McpClientManager::check_resource_size(&content, 4096).is_ok());
+
+        let err = McpClientManager::check_resource_size(&content, 1024).unwrap_err();
+        assert!(matches!(
+            err,
+            McpError::ResourceTooLarge {
+                size: 2048,
+                limit: 1024,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("read_resource_stream"));
+    }
+
+    #[tokio::test]
+    async fn test_read_resource_stream_not_found() {
+        let manager = McpClientManager::new(None);
+        let result = manager
+            .read_resource_stream("file:///nonexistent/resource")
+            .await;
+        assert!(matches!(result, Err(McpError::ToolNotFound(_))));
+    }
+
+    /// 测试用的凭证解析器
+    struct StaticResolver;
+
+    impl crate::secret_ref::CredentialResolver for StaticResolver {
+        fn resolve(&self, uuid: &str, field: &str) -> Result<String, String> {
+            match (uuid, field) {
+                ("cred-1", "api_key") => Ok("sk-secret".to_string()),
+                _ => Err(format!("凭证不存在: {}", uuid)),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_credential_ref_injected_into_child_env() {
+        let mut env = HashMap::new();
+        env.insert(
+            "API_KEY".to_string(),
+            "${credential:cred-1:api_key}".to_string(),
+        );
+        let config = McpServerConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), "printf %s \"$API_KEY\"".to_string()],
+            env,
+            cwd: None,
+            timeout: 5,
+        };
+
+        let resolved = resolve_env(&config.env, Some(&StaticResolver)).unwrap();
+        let mut command = McpClientManager::build_command("test-server", &config, &resolved);
+        let output = command.output().await.unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "sk-secret");
+    }
+
+    #[tokio::test]
+    async fn test_start_server_unresolvable_credential_ref() {
+        let mut manager = McpClientManager::new(None);
+        manager.set_credential_resolver(Arc::new(StaticResolver));
+
+        let mut config = create_test_config();
+        config.env.insert(
+            "API_KEY".to_string(),
+            "${credential:missing:api_key}".to_string(),
+        );
+
+        let result = manager.start_server("test-server", &config).await;
+        match result {
+            Err(McpError::SecretResolveFailed(msg)) => assert!(msg.contains("API_KEY")),
+            Err(e) => panic!("Expected SecretResolveFailed error, got: {:?}", e),
+            Ok(_) => panic!("Expected error, but got Ok"),
+        }
+        assert!(!manager.is_server_running("test-server").await);
+    }
 }
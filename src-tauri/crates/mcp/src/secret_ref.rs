@@ -0,0 +1,204 @@
+//! MCP 环境变量密钥引用
+//!
+//! 支持在 `McpServerConfig.env` 中使用 `${credential:<uuid>:<field>}` 形式的值，
+//! 在服务器启动时从凭证池解析为实际密钥，而不是将密钥明文写入 MCP 配置。
+//! 轮换凭证后只需重启 MCP 服务器即可生效，无需修改配置。
+//!
+//! # 示例
+//!
+//! ```json
+//! {
+//!   "command": "npx",
+//!   "args": ["-y", "some-mcp-server"],
+//!   "env": { "OPENAI_API_KEY": "${credential:2f1c...:api_key}" }
+//! }
+//! ```
+
+use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::{lock_db, DbConnection};
+use proxycast_core::models::provider_pool_model::ProviderCredential;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::types::McpError;
+
+/// 引用前缀
+const CREDENTIAL_REF_PREFIX: &str = "${credential:";
+/// 引用后缀
+const CREDENTIAL_REF_SUFFIX: &str = "}";
+
+/// 凭证解析 trait
+///
+/// 由宿主注入，将 `(uuid, field)` 解析为实际的密钥值。
+pub trait CredentialResolver: Send + Sync + 'static {
+    /// 解析凭证字段
+    ///
+    /// # Arguments
+    /// * `uuid` - 凭证 UUID
+    /// * `field` - 字段名（如 `api_key`、`base_url`、`access_token`）
+    fn resolve(&self, uuid: &str, field: &str) -> Result<String, String>;
+}
+
+/// 动态凭证解析器
+pub type DynCredentialResolver = Arc<dyn CredentialResolver>;
+
+/// 解析 `${credential:<uuid>:<field>}` 引用
+///
+/// 只有整个值都是引用时才会被识别，返回 `(uuid, field)`。
+pub fn parse_credential_ref(value: &str) -> Option<(&str, &str)> {
+    let inner = value
+        .trim()
+        .strip_prefix(CREDENTIAL_REF_PREFIX)?
+        .strip_suffix(CREDENTIAL_REF_SUFFIX)?;
+    let (uuid, field) = inner.split_once(':')?;
+    if uuid.is_empty() || field.is_empty() {
+        return None;
+    }
+    Some((uuid, field))
+}
+
+/// 解析环境变量中的凭证引用
+///
+/// 普通值原样保留；引用值通过 `resolver` 解析。
+/// 任一引用无法解析（或未配置解析器）时返回 `McpError::SecretResolveFailed`。
+pub fn resolve_env(
+    env: &HashMap<String, String>,
+    resolver: Option<&dyn CredentialResolver>,
+) -> Result<HashMap<String, String>, McpError> {
+    let mut resolved = HashMap::with_capacity(env.len());
+    for (key, value) in env {
+        let Some((uuid, field)) = parse_credential_ref(value) else {
+            resolved.insert(key.clone(), value.clone());
+            continue;
+        };
+
+        let resolver = resolver.ok_or_else(|| {
+            McpError::SecretResolveFailed(format!("环境变量 {key} 引用了凭证，但未配置凭证解析器"))
+        })?;
+        let secret = resolver.resolve(uuid, field).map_err(|e| {
+            McpError::SecretResolveFailed(format!(
+                "环境变量 {key} 的凭证引用 {uuid}:{field} 无法解析: {e}"
+            ))
+        })?;
+        resolved.insert(key.clone(), secret);
+    }
+    Ok(resolved)
+}
+
+/// 从凭证中读取字段值
+///
+/// 优先读取凭证数据中的字段（如 `api_key`、`base_url`、`project_id`），
+/// 其次读取缓存的 OAuth Token（`access_token`、`refresh_token`）。
+pub fn credential_field(cred: &ProviderCredential, field: &str) -> Option<String> {
+    if field != "type" {
+        let data = serde_json::to_value(&cred.credential).ok()?;
+        if let Some(value) = data.get(field).and_then(|v| v.as_str()) {
+            return Some(value.to_string());
+        }
+    }
+
+    let token = cred.cached_token.as_ref()?;
+    match field {
+        "access_token" => token.access_token.clone(),
+        "refresh_token" => token.refresh_token.clone(),
+        _ => None,
+    }
+}
+
+/// 基于数据库凭证池的解析器
+pub struct DbCredentialResolver {
+    db: DbConnection,
+}
+
+impl DbCredentialResolver {
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+}
+
+impl CredentialResolver for DbCredentialResolver {
+    fn resolve(&self, uuid: &str, field: &str) -> Result<String, String> {
+        let conn = lock_db(&self.db)?;
+        let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("凭证不存在: {uuid}"))?;
+        if cred.cached_token.is_none() {
+            cred.cached_token =
+                ProviderPoolDao::get_token_cache(&conn, uuid).map_err(|e| e.to_string())?;
+        }
+        credential_field(&cred, field).ok_or_else(|| format!("凭证 {uuid} 不包含字段 {field}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+    struct MapResolver(HashMap<(String, String), String>);
+
+    impl CredentialResolver for MapResolver {
+        fn resolve(&self, uuid: &str, field: &str) -> Result<String, String> {
+            self.0
+                .get(&(uuid.to_string(), field.to_string()))
+                .cloned()
+                .ok_or_else(|| "not found".to_string())
+        }
+    }
+
+    #[test]
+    fn test_parse_credential_ref() {
+        assert_eq!(
+            parse_credential_ref("${credential:abc-123:api_key}"),
+            Some(("abc-123", "api_key"))
+        );
+        assert_eq!(parse_credential_ref("plain-value"), None);
+        assert_eq!(parse_credential_ref("${credential:abc}"), None);
+        assert_eq!(parse_credential_ref("${credential::api_key}"), None);
+        assert_eq!(parse_credential_ref("prefix ${credential:a:b}"), None);
+    }
+
+    #[test]
+    fn test_resolve_env_keeps_plain_values() {
+        let mut env = HashMap::new();
+        env.insert("PLAIN".to_string(), "value".to_string());
+
+        let resolved = resolve_env(&env, None).unwrap();
+        assert_eq!(resolved.get("PLAIN").map(String::as_str), Some("value"));
+    }
+
+    #[test]
+    fn test_resolve_env_missing_reference_errors() {
+        let resolver = MapResolver(HashMap::new());
+        let mut env = HashMap::new();
+        env.insert(
+            "API_KEY".to_string(),
+            "${credential:missing:api_key}".to_string(),
+        );
+
+        let result = resolve_env(&env, Some(&resolver));
+        assert!(matches!(result, Err(McpError::SecretResolveFailed(_))));
+
+        // 未配置解析器同样报错
+        let result = resolve_env(&env, None);
+        assert!(matches!(result, Err(McpError::SecretResolveFailed(_))));
+    }
+
+    #[test]
+    fn test_credential_field() {
+        let cred = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        assert_eq!(
+            credential_field(&cred, "api_key").as_deref(),
+            Some("sk-test")
+        );
+        assert_eq!(credential_field(&cred, "base_url"), None);
+        assert_eq!(credential_field(&cred, "type"), None);
+        assert_eq!(credential_field(&cred, "access_token"), None);
+    }
+}
@@ -163,6 +163,114 @@ pub struct McpResourceContent {
     pub blob: Option<String>,
 }
 
+impl McpResourceContent {
+    /// 内容大小（字节）：文本按 UTF-8 字节数，二进制按 base64 解码后的字节数估算
+    pub fn size(&self) -> usize {
+        match (&self.text, &self.blob) {
+            (Some(text), _) => text.len(),
+            (None, Some(blob)) => blob.len() / 4 * 3,
+            (None, None) => 0,
+        }
+    }
+
+    /// 按 `chunk_size` 字节拆分为分块
+    ///
+    /// 文本在字符边界处切分；二进制按 4 字符对齐切分，每个分块都是独立可解码的 base64。
+    pub fn into_chunks(self, chunk_size: usize) -> ResourceChunks {
+        let (data, is_blob) = match (self.text, self.blob) {
+            (Some(text), _) => (text, false),
+            (None, Some(blob)) => (blob, true),
+            (None, None) => (String::new(), false),
+        };
+        let chunk_size = if is_blob {
+            (chunk_size / 4).max(1) * 4
+        } else {
+            chunk_size.max(4)
+        };
+        ResourceChunks {
+            uri: self.uri,
+            mime_type: self.mime_type,
+            data,
+            is_blob,
+            chunk_size,
+            pos: 0,
+            index: 0,
+        }
+    }
+}
+
+impl ResourceChunks {
+    /// 尚未产出的内容仍占用的内存（字节）
+    pub fn buffered_bytes(&self) -> usize {
+        self.data.capacity()
+    }
+}
+
+/// MCP 资源内容分块（`read_resource_stream` 的产出单元）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResourceChunk {
+    pub uri: String,
+    pub mime_type: Option<String>,
+    /// 分块序号（从 0 开始）
+    pub index: usize,
+    pub text: Option<String>,
+    pub blob: Option<String>,
+    /// 是否为最后一个分块
+    pub is_last: bool,
+}
+
+/// 资源内容分块迭代器
+///
+/// 已产出的部分超过剩余部分时压缩缓冲区，逐步释放已产出内容占用的内存。
+#[derive(Debug)]
+pub struct ResourceChunks {
+    uri: String,
+    mime_type: Option<String>,
+    data: String,
+    is_blob: bool,
+    chunk_size: usize,
+    pos: usize,
+    index: usize,
+}
+
+impl Iterator for ResourceChunks {
+    type Item = McpResourceChunk;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let mut end = (self.pos + self.chunk_size).min(self.data.len());
+        while !self.data.is_char_boundary(end) {
+            end -= 1;
+        }
+        let piece = self.data[self.pos..end].to_string();
+        let (text, blob) = if self.is_blob {
+            (None, Some(piece))
+        } else {
+            (Some(piece), None)
+        };
+        let chunk = McpResourceChunk {
+            uri: self.uri.clone(),
+            mime_type: self.mime_type.clone(),
+            index: self.index,
+            text,
+            blob,
+            is_last: end == self.data.len(),
+        };
+        self.pos = end;
+        self.index += 1;
+
+        // 每次压缩复制的剩余内容不超过已产出的内容，总复制量与资源大小成正比
+        if self.pos > self.data.len() - self.pos {
+            self.data.drain(..self.pos);
+            self.data.shrink_to_fit();
+            self.pos = 0;
+        }
+        Some(chunk)
+    }
+}
+
 // ============================================================================
 // 错误类型
 // ============================================================================
@@ -199,6 +307,16 @@ pub enum McpError {
 
     #[error("协议错误: {0}")]
     ProtocolError(String),
+
+    #[error("凭证引用解析失败: {0}")]
+    SecretResolveFailed(String),
+
+    #[error("资源过大: {uri}（{size} 字节，超过 {limit} 字节上限），请使用 read_resource_stream 分块读取")]
+    ResourceTooLarge {
+        uri: String,
+        size: usize,
+        limit: usize,
+    },
 }
 
 // ============================================================================
@@ -10,5 +10,5 @@ pub mod processor;
 pub mod steps;
 
 pub use processor::RequestProcessor;
-pub use proxycast_core::processor::RequestContext;
+pub use proxycast_core::processor::{RequestContext, RequestStage, RequestTiming};
 pub use steps::*;
@@ -20,7 +20,7 @@ use proxycast_core::plugin::PluginManager;
 use proxycast_core::router::{ModelMapper, Router};
 use proxycast_core::ProviderType;
 use proxycast_infra::{
-    Failover, Injector, Retrier, StatsAggregator, TimeoutController, TokenTracker,
+    CircuitBreaker, Failover, Injector, Retrier, StatsAggregator, TimeoutController, TokenTracker,
 };
 use proxycast_services::provider_pool_service::ProviderPoolService;
 use std::sync::Arc;
@@ -40,6 +40,11 @@ pub struct RequestProcessor {
     pub retrier: Arc<Retrier>,
     /// 故障转移器
     pub failover: Arc<Failover>,
+    /// 熔断器
+    ///
+    /// 按凭证 UUID 熔断而非按 Provider 类型：同类型下单个凭证或自定义上游故障时，
+    /// 不应让该类型的其他凭证一起快速失败。
+    pub circuit_breaker: Arc<CircuitBreaker>,
     /// 超时控制器
     pub timeout: Arc<TimeoutController>,
     /// 插件管理器
@@ -74,6 +79,7 @@ impl RequestProcessor {
             injector,
             retrier,
             failover,
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
             timeout,
             plugins,
             stats,
@@ -91,6 +97,7 @@ impl RequestProcessor {
             injector: Arc::new(RwLock::new(Injector::new())),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
             timeout: Arc::new(TimeoutController::with_defaults()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats: Arc::new(ParkingLotRwLock::new(StatsAggregator::with_defaults())),
@@ -121,6 +128,7 @@ impl RequestProcessor {
             injector: Arc::new(RwLock::new(Injector::new())),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
             timeout: Arc::new(TimeoutController::with_defaults()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats,
@@ -52,8 +52,20 @@ impl TelemetryStep {
             log.set_credential_id(cred_id.clone());
         }
         log.retry_count = ctx.retry_count;
-        let stats = self.stats.write();
-        stats.record(log);
+        log.set_timing(ctx.timing());
+        if let Some(client_id) = &ctx.client_id {
+            log.set_client_id(client_id.clone());
+        }
+        if let Some(route) = &ctx.route {
+            log.set_route(route.clone());
+        }
+        if let Some(trace) = &ctx.selection_trace {
+            log.set_selection_trace(trace.clone());
+        }
+        if let Some(label) = &ctx.api_key_label {
+            log.set_api_key_label(label.clone());
+        }
+        self.stats.read().record(log);
     }
 
     /// 记录 Token 使用
@@ -74,7 +86,8 @@ impl TelemetryStep {
                 output_tokens.unwrap_or(0),
                 source,
             )
-            .with_request_id(ctx.request_id.clone());
+            .with_request_id(ctx.request_id.clone())
+            .with_attribution(ctx.session_id.clone(), ctx.client_id.clone());
             let tokens = self.tokens.write();
             tokens.record(record);
         }
@@ -3,13 +3,15 @@ use proxycast_core::models::anthropic::*;
 use proxycast_core::models::openai::*;
 use uuid::Uuid;
 
+use super::reasoning_handler::ReasoningHandler;
+
 /// 将 Anthropic MessagesRequest 转换为 OpenAI ChatCompletionRequest
 pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCompletionRequest {
     let mut openai_messages: Vec<ChatMessage> = Vec::new();
 
     // 处理 system prompt
     if let Some(system) = &request.system {
-        let system_text = extract_system_text(system);
+        let system_text = system.text();
         if !system_text.is_empty() {
             openai_messages.push(ChatMessage {
                 role: "system".to_string(),
@@ -50,27 +52,14 @@ pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCo
         stream: request.stream,
         tools,
         tool_choice: request.tool_choice.clone(),
-        reasoning_effort: None,
-    }
-}
-
-fn extract_system_text(system: &serde_json::Value) -> String {
-    match system {
-        serde_json::Value::String(s) => s.clone(),
-        serde_json::Value::Array(arr) => arr
-            .iter()
-            .filter_map(|item| {
-                if item.get("type") == Some(&serde_json::Value::String("text".to_string())) {
-                    item.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| s.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
+        reasoning_effort: request
+            .thinking_budget()
+            .map(|budget| ReasoningHandler::budget_to_effort(budget).to_string()),
+        n: None,
+        logprobs: None,
+        top_logprobs: None,
+        response_format: None,
+        stream_options: None,
     }
 }
 
@@ -90,7 +79,7 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
         serde_json::Value::Array(parts) => {
             let mut text_parts: Vec<String> = Vec::new();
             let mut tool_calls: Vec<ToolCall> = Vec::new();
-            let mut tool_results: Vec<(String, String)> = Vec::new(); // (tool_use_id, content)
+            let mut tool_results: Vec<(String, ToolResultContent)> = Vec::new();
 
             for part in parts {
                 let part_type = part.get("type").and_then(|t| t.as_str()).unwrap_or("");
@@ -124,7 +113,7 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                             .get("tool_use_id")
                             .and_then(|i| i.as_str())
                             .unwrap_or("");
-                        let content = extract_tool_result_content(part.get("content"));
+                        let content = ToolResultContent::from_value(part.get("content"));
                         tool_results.push((tool_use_id.to_string(), content));
                     }
                     _ => {}
@@ -149,21 +138,38 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                     content,
                     tool_calls: tc,
                     tool_call_id: None,
-                    reasoning_content: None,
+                    reasoning_content: ReasoningHandler::thinking_to_reasoning_content(parts),
                 });
             }
             // 处理 user 消息
             else if msg.role == "user" {
                 // 先添加 tool results 作为 tool 角色消息
+                // OpenAI 的 tool 消息只支持文本，结果中的图片放到紧随其后的 user 消息里
+                let mut tool_images: Vec<ContentPart> = Vec::new();
                 for (tool_use_id, content) in tool_results {
+                    tool_images.extend(content.images.iter().map(|image| ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: image.to_url(),
+                            detail: None,
+                        },
+                    }));
                     result.push(ChatMessage {
                         role: "tool".to_string(),
-                        content: Some(MessageContent::Text(content)),
+                        content: Some(MessageContent::Text(content.text)),
                         tool_calls: None,
                         tool_call_id: Some(tool_use_id),
                         reasoning_content: None,
                     });
                 }
+                if !tool_images.is_empty() {
+                    result.push(ChatMessage {
+                        role: "user".to_string(),
+                        content: Some(MessageContent::Parts(tool_images)),
+                        tool_calls: None,
+                        tool_call_id: None,
+                        reasoning_content: None,
+                    });
+                }
 
                 // 添加文本内容
                 if !text_parts.is_empty() {
@@ -183,22 +189,80 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
     result
 }
 
-fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
-    match content {
-        Some(serde_json::Value::String(s)) => s.clone(),
-        Some(serde_json::Value::Array(arr)) => arr
-            .iter()
-            .filter_map(|item| {
-                if item.get("type") == Some(&serde_json::Value::String("text".to_string())) {
-                    item.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| s.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_thinking_maps_to_reasoning() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "thinking": {"type": "enabled", "budget_tokens": 10000},
+            "messages": [
+                {"role": "user", "content": "2+2=?"},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "简单加法", "signature": "sig"},
+                    {"type": "text", "text": "4"}
+                ]},
+                {"role": "user", "content": "再加 1 呢？"}
+            ]
+        }))
+        .unwrap();
+
+        let openai = convert_anthropic_to_openai(&request);
+        assert_eq!(openai.reasoning_effort.as_deref(), Some("medium"));
+        let assistant = &openai.messages[1];
+        assert_eq!(assistant.role, "assistant");
+        assert_eq!(assistant.reasoning_content.as_deref(), Some("简单加法"));
+        assert!(matches!(&assistant.content, Some(MessageContent::Text(t)) if t == "4"));
+
+        // 不接受 reasoning_effort 的 OpenAI 兼容上游不转发该参数
+        let mut stripped = openai.clone();
+        ReasoningHandler::strip_unsupported_effort(&mut stripped);
+        assert_eq!(stripped.reasoning_effort, None);
+        let mut o3 = openai;
+        o3.model = "o3-mini".to_string();
+        ReasoningHandler::strip_unsupported_effort(&mut o3);
+        assert_eq!(o3.reasoning_effort.as_deref(), Some("medium"));
+    }
+
+    #[test]
+    fn test_tool_result_image_converted_to_user_image_part() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [
+                {"role": "user", "content": "打开首页并截图"},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "toolu_1", "name": "screenshot", "input": {}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [
+                        {"type": "text", "text": "已截图"},
+                        {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0"}}
+                    ]}
+                ]}
+            ]
+        }))
+        .unwrap();
+
+        let openai = convert_anthropic_to_openai(&request);
+        assert_eq!(openai.messages.len(), 4);
+
+        let tool = &openai.messages[2];
+        assert_eq!(tool.role, "tool");
+        assert_eq!(tool.tool_call_id.as_deref(), Some("toolu_1"));
+        assert!(matches!(&tool.content, Some(MessageContent::Text(t)) if t == "已截图"));
+
+        let image = &openai.messages[3];
+        assert_eq!(image.role, "user");
+        match &image.content {
+            Some(MessageContent::Parts(parts)) => {
+                assert_eq!(parts.len(), 1);
+                assert!(matches!(&parts[0], ContentPart::ImageUrl { image_url }
+                    if image_url.url == "data:image/png;base64,iVBORw0"));
+            }
+            other => panic!("应为图片内容，实际为 {other:?}"),
+        }
     }
 }
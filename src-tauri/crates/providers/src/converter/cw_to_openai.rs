@@ -103,6 +103,7 @@ pub fn create_openai_response(
                 },
                 tool_calls,
             },
+            logprobs: None,
             finish_reason: finish_reason.to_string(),
         }],
         usage: Usage {
@@ -15,6 +15,7 @@
 //! - 2025-12-28: 修复请求格式，对齐 CLIProxyAPI 实现
 
 use crate::session::{get_thought_signature, SessionManager};
+use crate::stream::StopReason;
 use proxycast_core::models::openai::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -957,13 +958,7 @@ pub fn convert_antigravity_to_openai_response(
             let finish_reason = candidate
                 .get("finishReason")
                 .and_then(|r| r.as_str())
-                .map(|r| match r.to_uppercase().as_str() {
-                    "STOP" => "stop",
-                    "MAX_TOKENS" => "length",
-                    "SAFETY" => "content_filter",
-                    "RECITATION" => "content_filter",
-                    _ => "stop",
-                })
+                .map(|r| StopReason::from_gemini(r).to_openai_str())
                 .unwrap_or(if !tool_calls.is_empty() {
                     "tool_calls"
                 } else {
@@ -217,6 +217,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_declared_protocol_selects_openai_conversion_path() {
+        let routing = proxycast_core::config::RoutingConfig {
+            provider_protocols: [("my-vllm".to_string(), "openai".to_string())].into(),
+            ..Default::default()
+        };
+
+        // 路由时解析出声明的协议，降级查找按 OpenAI 凭证进行
+        let declared = routing.declared_protocol("my-vllm").unwrap();
+        let pool_type: PoolProviderType = declared.to_string().parse().unwrap();
+        assert_eq!(pool_type, PoolProviderType::OpenAI);
+
+        // Anthropic 请求经 Anthropic → OpenAI 转换后发往上游
+        let path = ProtocolSelector::select_path(Protocol::Anthropic, pool_type);
+        assert!(path.needs_conversion);
+        assert_eq!(path.target, Protocol::OpenAI);
+    }
+
     #[test]
     fn test_select_path_no_conversion() {
         let path = ProtocolSelector::select_path(Protocol::OpenAI, PoolProviderType::OpenAI);
@@ -8,6 +8,13 @@
 //! |------|--------|--------------|
 //! | DeepSeek R1/Reasoner | `reasoning_content` | 丢弃，只保留 `content` |
 //! | OpenAI o1/o3/o4 | `reasoning` | 通过 `previous_response_id` 引用 |
+//! | Claude Extended Thinking | `thinking` 块 | 与 `reasoning_content` 互相映射 |
+//!
+//! # 跨协议映射
+//!
+//! Anthropic 与 OpenAI 之间转换时：
+//! - 请求 `thinking.budget_tokens` ↔ `reasoning_effort`（按预算分档）
+//! - assistant 消息中的 `thinking` 块 ↔ `reasoning_content`
 //!
 //! # 设计原则
 //!
@@ -24,7 +31,14 @@
 // 预留功能模块，暂未在主流程中调用
 #![allow(dead_code)]
 
-use proxycast_core::models::openai::ChatMessage;
+use proxycast_core::models::openai::{ChatCompletionRequest, ChatMessage};
+
+/// `reasoning_effort` 为 `low` 时对应的思考预算
+const LOW_EFFORT_BUDGET: u32 = 4096;
+/// `reasoning_effort` 为 `medium` 时对应的思考预算
+const MEDIUM_EFFORT_BUDGET: u32 = 16000;
+/// `reasoning_effort` 为 `high` 时对应的思考预算
+const HIGH_EFFORT_BUDGET: u32 = 32000;
 
 /// 模型类型，用于确定推理内容处理策略
 #[derive(Debug, Clone, PartialEq)]
@@ -135,6 +149,72 @@ impl ReasoningHandler {
         !matches!(model_type, ReasoningModelType::Other)
     }
 
+    /// 检查模型是否接受 `reasoning_effort` 参数（OpenAI o 系列与 GPT-5）
+    ///
+    /// 部分 OpenAI 兼容上游会拒绝未知参数，由思考预算映射得到的
+    /// `reasoning_effort` 只应发给这些模型。
+    pub fn accepts_reasoning_effort(model: &str) -> bool {
+        ReasoningModelType::from_model_name(model) == ReasoningModelType::OpenAI
+            || model.to_lowercase().starts_with("gpt-5")
+    }
+
+    /// 目标模型不接受 `reasoning_effort` 时移除该参数
+    pub fn strip_unsupported_effort(request: &mut ChatCompletionRequest) {
+        if request.reasoning_effort.is_some() && !Self::accepts_reasoning_effort(&request.model) {
+            request.reasoning_effort = None;
+        }
+    }
+
+    /// 将 Anthropic 思考预算映射为 OpenAI `reasoning_effort`
+    pub fn budget_to_effort(budget_tokens: u32) -> &'static str {
+        if budget_tokens <= LOW_EFFORT_BUDGET {
+            "low"
+        } else if budget_tokens <= MEDIUM_EFFORT_BUDGET {
+            "medium"
+        } else {
+            "high"
+        }
+    }
+
+    /// 将 OpenAI `reasoning_effort` 映射为 Anthropic 思考预算
+    pub fn effort_to_budget(effort: &str) -> Option<u32> {
+        match effort {
+            "low" | "minimal" => Some(LOW_EFFORT_BUDGET),
+            "medium" => Some(MEDIUM_EFFORT_BUDGET),
+            "high" => Some(HIGH_EFFORT_BUDGET),
+            _ => None,
+        }
+    }
+
+    /// 提取 Anthropic 内容块中的思考内容，作为 `reasoning_content`
+    ///
+    /// 多个 `thinking` 块按顺序拼接；没有思考内容时返回 `None`。
+    pub fn thinking_to_reasoning_content(blocks: &[serde_json::Value]) -> Option<String> {
+        let thinking: Vec<&str> = blocks
+            .iter()
+            .filter(|b| b["type"] == "thinking")
+            .filter_map(|b| b["thinking"].as_str())
+            .collect();
+        if thinking.is_empty() {
+            None
+        } else {
+            Some(thinking.join("\n"))
+        }
+    }
+
+    /// 将 `reasoning_content` 转换为 Anthropic `thinking` 内容块
+    ///
+    /// OpenAI 兼容上游不提供签名，因此不输出 `signature` 字段。
+    pub fn reasoning_content_to_thinking(reasoning_content: &str) -> Option<serde_json::Value> {
+        if reasoning_content.is_empty() {
+            return None;
+        }
+        Some(serde_json::json!({
+            "type": "thinking",
+            "thinking": reasoning_content
+        }))
+    }
+
     /// 检查模型是否需要清理历史 reasoning_content
     pub fn needs_reasoning_cleanup(model: &str) -> bool {
         matches!(
@@ -221,4 +301,45 @@ mod tests {
         // 最后一条 assistant 消息的 reasoning_content 应该保留
         assert!(processed[3].reasoning_content.is_some());
     }
+
+    #[test]
+    fn test_thinking_reasoning_mapping() {
+        assert_eq!(ReasoningHandler::budget_to_effort(1024), "low");
+        assert_eq!(ReasoningHandler::budget_to_effort(10000), "medium");
+        assert_eq!(ReasoningHandler::budget_to_effort(32000), "high");
+        assert_eq!(ReasoningHandler::effort_to_budget("medium"), Some(16000));
+        assert_eq!(ReasoningHandler::effort_to_budget("unknown"), None);
+
+        let blocks = vec![
+            serde_json::json!({"type": "thinking", "thinking": "先想一想", "signature": "sig"}),
+            serde_json::json!({"type": "text", "text": "答案"}),
+        ];
+        assert_eq!(
+            ReasoningHandler::thinking_to_reasoning_content(&blocks).as_deref(),
+            Some("先想一想")
+        );
+        assert_eq!(
+            ReasoningHandler::thinking_to_reasoning_content(&blocks[1..]),
+            None
+        );
+
+        let block = ReasoningHandler::reasoning_content_to_thinking("先想一想").unwrap();
+        assert_eq!(block["type"], "thinking");
+        assert_eq!(block["thinking"], "先想一想");
+        assert!(block.get("signature").is_none());
+        assert!(ReasoningHandler::reasoning_content_to_thinking("").is_none());
+    }
+
+    #[test]
+    fn test_accepts_reasoning_effort() {
+        assert!(ReasoningHandler::accepts_reasoning_effort("o3-mini"));
+        assert!(ReasoningHandler::accepts_reasoning_effort("gpt-5-codex"));
+        assert!(!ReasoningHandler::accepts_reasoning_effort("gpt-4o"));
+        assert!(!ReasoningHandler::accepts_reasoning_effort(
+            "deepseek-reasoner"
+        ));
+        assert!(!ReasoningHandler::accepts_reasoning_effort(
+            "claude-sonnet-4-5"
+        ));
+    }
 }
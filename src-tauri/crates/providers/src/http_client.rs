@@ -0,0 +1,386 @@
+//! 共享上游 HTTP 客户端
+//!
+//! `reqwest::Client` 内部维护连接池，克隆开销很小且共享同一个连接池。
+//! 服务器启动时按 `server.upstream_pool` 配置构建一次，Provider 通过
+//! `with_client` 借用，避免每次请求新建客户端导致的重复 TCP/TLS 握手。
+//!
+//! 部分上游会根据 `User-Agent` 等客户端标识区别对待请求，
+//! [`ProviderClients`] 按 Provider 类型构建带默认请求头的客户端。
+//! 默认请求头由 reqwest 在发送时补充，只填充请求中没有的请求头，
+//! 因此不会覆盖 Provider 自己设置的认证请求头。
+//!
+//! 为检测代理回环，Provider 发往上游的请求通过 [`with_hop_header`] 附加
+//! `X-ProxyCast-Hop` 跳数请求头，取值为当前入站请求的跳数 + 1；
+//! 发往官方上游的请求不附加。
+//!
+//! 不经过服务器状态创建的 Provider（Token 刷新、预热、命令等）通过
+//! [`default_provider_client`] 共享一个按默认连接池配置构建的客户端集合。
+
+use once_cell::sync::Lazy;
+use proxycast_core::config::UpstreamPoolConfig;
+use proxycast_core::middleware::loop_guard;
+use proxycast_core::ProviderType;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, RequestBuilder};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// 连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// 总超时（流式响应可能很长）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 按连接池配置构建共享 HTTP 客户端
+pub fn build_shared_client(config: &UpstreamPoolConfig) -> Client {
+    build_client_with_headers(config, HeaderMap::new())
+}
+
+/// 按连接池配置和默认请求头构建 HTTP 客户端
+pub fn build_client_with_headers(config: &UpstreamPoolConfig, headers: HeaderMap) -> Client {
+    Client::builder()
+        .default_headers(headers)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+        .tcp_keepalive(Duration::from_secs(config.tcp_keepalive_secs))
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+/// 按默认连接池配置构建的进程级客户端集合
+static DEFAULT_PROVIDER_CLIENTS: Lazy<ProviderClients> =
+    Lazy::new(|| ProviderClients::new(UpstreamPoolConfig::default(), HashMap::new()));
+
+/// 获取指定 Provider 类型的进程级共享客户端（带内置默认请求头）
+///
+/// 供 Provider 的 `Default` 实现使用；服务器处理请求时应通过 `with_client`
+/// 传入按配置构建的 [`ProviderClients`] 客户端。
+pub fn default_provider_client(provider: ProviderType) -> Client {
+    DEFAULT_PROVIDER_CLIENTS.get(provider)
+}
+
+/// 默认 `User-Agent`
+const PROXYCAST_USER_AGENT: &str = concat!("proxycast/", env!("CARGO_PKG_VERSION"));
+
+/// 内置的各 Provider 默认请求头（名称须为小写）
+///
+/// 只有依赖官方客户端标识的 OAuth 类 Provider 使用官方客户端的 `User-Agent`；
+/// API Key 和自定义端点一律使用 ProxyCast 自己的标识。Codex、Kiro 的客户端特征
+/// 请求头由 Provider 按认证方式在每个请求上设置。
+pub fn builtin_default_headers(provider: ProviderType) -> &'static [(&'static str, &'static str)] {
+    match provider {
+        ProviderType::ClaudeOAuth => &[("user-agent", "claude-cli/1.0.56 (external, cli)")],
+        ProviderType::Antigravity => &[("user-agent", "antigravity/1.11.9 windows/amd64")],
+        _ => &[("user-agent", PROXYCAST_USER_AGENT)],
+    }
+}
+
+/// 内置默认请求头（未应用配置），供 Provider 自建客户端使用
+pub fn builtin_header_map(provider: ProviderType) -> HeaderMap {
+    resolve_default_headers(provider, &HashMap::new())
+}
+
+/// 合并内置默认请求头与配置的请求头
+///
+/// 同名请求头以配置为准，配置值为空时去掉该请求头；无法解析的请求头会被忽略。
+pub fn resolve_default_headers(
+    provider: ProviderType,
+    configured: &HashMap<String, HashMap<String, String>>,
+) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in builtin_default_headers(provider) {
+        headers.insert(
+            HeaderName::from_static(name),
+            HeaderValue::from_static(value),
+        );
+    }
+
+    let Some(overrides) = configured.get(&provider.to_string()) else {
+        return headers;
+    };
+    for (name, value) in overrides {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            tracing::warn!("[HTTP_CLIENT] 忽略无效的请求头名称: {}", name);
+            continue;
+        };
+        if value.is_empty() {
+            headers.remove(&name);
+            continue;
+        }
+        match HeaderValue::from_str(value) {
+            Ok(value) => {
+                headers.insert(name, value);
+            }
+            Err(_) => tracing::warn!("[HTTP_CLIENT] 忽略无效的请求头值: {}", name),
+        }
+    }
+    headers
+}
+
+/// 为发往上游的请求附加跳数请求头
+///
+/// 跳数在发送时从当前请求上下文读取。官方上游不可能是 ProxyCast 实例，
+/// 发往官方上游的请求不附加。
+pub fn with_hop_header(builder: RequestBuilder, url: &str) -> RequestBuilder {
+    if loop_guard::is_official_upstream(url) {
+        return builder;
+    }
+    builder.header(loop_guard::HOP_HEADER, loop_guard::outbound_hop())
+}
+
+/// 按 Provider 类型区分的上游 HTTP 客户端
+///
+/// 每个 Provider 类型首次使用时构建一个带默认请求头的客户端并缓存，
+/// 同一 Provider 类型的请求共享连接池。
+#[derive(Debug)]
+pub struct ProviderClients {
+    pool: UpstreamPoolConfig,
+    headers: HashMap<String, HashMap<String, String>>,
+    clients: RwLock<HashMap<ProviderType, Client>>,
+}
+
+impl ProviderClients {
+    /// 创建客户端集合
+    pub fn new(
+        pool: UpstreamPoolConfig,
+        headers: HashMap<String, HashMap<String, String>>,
+    ) -> Self {
+        Self {
+            pool,
+            headers,
+            clients: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 获取指定 Provider 类型的客户端
+    pub fn get(&self, provider: ProviderType) -> Client {
+        if let Some(client) = self
+            .clients
+            .read()
+            .ok()
+            .and_then(|clients| clients.get(&provider).cloned())
+        {
+            return client;
+        }
+
+        let client =
+            build_client_with_headers(&self.pool, resolve_default_headers(provider, &self.headers));
+        if let Ok(mut clients) = self.clients.write() {
+            clients.entry(provider).or_insert_with(|| client.clone());
+        }
+        client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 启动一个统计 TCP 连接数的 keep-alive HTTP 服务器
+    async fn spawn_counting_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let mut pending = Vec::new();
+                    loop {
+                        let n = match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => n,
+                        };
+                        pending.extend_from_slice(&buf[..n]);
+                        while let Some(pos) = pending.windows(4).position(|w| w == b"\r\n\r\n") {
+                            pending.drain(..pos + 4);
+                            let response =
+                                b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: keep-alive\r\n\r\nok";
+                            if socket.write_all(response).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        (format!("http://{addr}/"), accepted)
+    }
+
+    #[tokio::test]
+    async fn test_shared_client_reuses_connections() {
+        let (url, accepted) = spawn_counting_server().await;
+        let client = build_shared_client(&UpstreamPoolConfig::default());
+
+        for _ in 0..5 {
+            let resp = client.get(&url).send().await.unwrap();
+            assert_eq!(resp.text().await.unwrap(), "ok");
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ad_hoc_clients_open_new_connections() {
+        let (url, accepted) = spawn_counting_server().await;
+
+        for _ in 0..5 {
+            let client = build_shared_client(&UpstreamPoolConfig::default());
+            let resp = client.get(&url).send().await.unwrap();
+            assert_eq!(resp.text().await.unwrap(), "ok");
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_default_provider_clients_share_connections() {
+        let (url, accepted) = spawn_counting_server().await;
+
+        for _ in 0..3 {
+            let kiro = crate::providers::kiro::KiroProvider::new();
+            let resp = kiro.client.get(&url).send().await.unwrap();
+            assert_eq!(resp.text().await.unwrap(), "ok");
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_zero_idle_pool_disables_reuse() {
+        let (url, accepted) = spawn_counting_server().await;
+        let client = build_shared_client(&UpstreamPoolConfig {
+            pool_max_idle_per_host: 0,
+            ..Default::default()
+        });
+
+        for _ in 0..3 {
+            let resp = client.get(&url).send().await.unwrap();
+            assert_eq!(resp.text().await.unwrap(), "ok");
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    }
+
+    /// 启动一个记录请求头的 HTTP 服务器，响应空模型列表
+    async fn spawn_header_capturing_server() -> (String, tokio::sync::mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 8192];
+                    let mut pending = Vec::new();
+                    while !pending.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => pending.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let _ = tx
+                        .send(String::from_utf8_lossy(&pending).to_lowercase())
+                        .await;
+                    let body = br#"{"object":"list","data":[]}"#;
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = socket.write_all(head.as_bytes()).await;
+                    let _ = socket.write_all(body).await;
+                });
+            }
+        });
+
+        (format!("http://{addr}"), rx)
+    }
+
+    #[test]
+    fn test_resolve_default_headers() {
+        let configured = HashMap::from([(
+            "claude".to_string(),
+            HashMap::from([
+                ("X-Client-Name".to_string(), "my-cli".to_string()),
+                ("User-Agent".to_string(), String::new()),
+            ]),
+        )]);
+
+        // 内置默认值：官方 CLI 标识只用于 OAuth 凭证，自定义端点使用 ProxyCast 标识
+        let headers = resolve_default_headers(ProviderType::ClaudeOAuth, &configured);
+        assert_eq!(headers["user-agent"], "claude-cli/1.0.56 (external, cli)");
+        let headers = resolve_default_headers(ProviderType::AnthropicCompatible, &configured);
+        assert_eq!(headers["user-agent"], PROXYCAST_USER_AGENT);
+
+        // 配置追加请求头，空值去掉内置的 User-Agent
+        let headers = resolve_default_headers(ProviderType::Claude, &configured);
+        assert_eq!(headers["x-client-name"], "my-cli");
+        assert!(headers.get("user-agent").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_configured_user_agent_sent_without_clobbering_auth() {
+        let (base_url, mut requests) = spawn_header_capturing_server().await;
+        let clients = ProviderClients::new(
+            UpstreamPoolConfig::default(),
+            HashMap::from([(
+                "openai".to_string(),
+                HashMap::from([
+                    ("User-Agent".to_string(), "codex_cli_rs/0.60.0".to_string()),
+                    (
+                        "Authorization".to_string(),
+                        "Bearer default-key".to_string(),
+                    ),
+                ]),
+            )]),
+        );
+
+        let provider = crate::providers::openai_custom::OpenAICustomProvider::with_client(
+            "sk-real-key".to_string(),
+            Some(base_url),
+            clients.get(ProviderType::OpenAI),
+        );
+        provider.list_models().await.unwrap();
+
+        let request = requests.recv().await.unwrap();
+        assert!(request.contains("user-agent: codex_cli_rs/0.60.0"));
+        assert!(request.contains("authorization: bearer sk-real-key"));
+        assert!(!request.contains("default-key"));
+        // 自定义上游可能是另一个 ProxyCast 实例，携带跳数请求头
+        assert!(request.contains("x-proxycast-hop: 1"));
+    }
+
+    #[test]
+    fn test_hop_header_stripped_for_official_upstreams() {
+        let client = Client::new();
+        let hop = |url: &str| {
+            with_hop_header(client.post(url), url)
+                .build()
+                .unwrap()
+                .headers()
+                .get(loop_guard::HOP_HEADER)
+                .cloned()
+        };
+
+        assert!(hop("https://api.anthropic.com/v1/messages").is_none());
+        assert!(hop("https://generativelanguage.googleapis.com/v1beta/models").is_none());
+        assert_eq!(
+            hop("http://127.0.0.1:8999/v1/chat/completions").unwrap(),
+            "1"
+        );
+    }
+}
@@ -5,12 +5,14 @@
 //! ## 模块结构
 //! - `providers`: Provider 实现（Kiro、Gemini、Claude、OpenAI、Vertex 等）
 //! - `converter`: 协议转换（OpenAI ↔ CW、OpenAI ↔ Antigravity 等）
+//! - `http_client`: 共享上游 HTTP 客户端（连接池复用）
 //! - `streaming`: 流式传输管理
 //! - `translator`: 请求/响应翻译层
 //! - `stream`: 流事件解析和生成
 //! - `session`: 会话管理（签名存储、会话 ID 生成）
 
 pub mod converter;
+pub mod http_client;
 pub mod providers;
 pub mod session;
 pub mod stream;
@@ -4,8 +4,13 @@
 
 #![allow(dead_code)]
 
+use super::project_id::{
+    generate_project_id, is_valid_project_id, resolve_project_id, validate_project_id,
+};
 use super::traits::{CredentialProvider, ProviderResult};
+use crate::http_client::{default_provider_client, with_hop_header};
 use async_trait::async_trait;
+use proxycast_core::ProviderType;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -288,18 +293,6 @@ fn generate_session_id() -> String {
     format!("-{n}")
 }
 
-/// 生成随机项目 ID
-fn generate_project_id() -> String {
-    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
-    let nouns = ["fuze", "wave", "spark", "flow", "core"];
-    let uuid = Uuid::new_v4();
-    let bytes = uuid.as_bytes();
-    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
-    let noun = nouns[(bytes[1] as usize) % nouns.len()];
-    let random_part: String = uuid.to_string()[..5].to_lowercase();
-    format!("{adj}-{noun}-{random_part}")
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AntigravityCredentials {
     pub access_token: Option<String>,
@@ -372,10 +365,7 @@ impl Default for AntigravityProvider {
         Self {
             credentials: AntigravityCredentials::default(),
             project_id: None,
-            client: Client::builder()
-                .timeout(std::time::Duration::from_secs(120))
-                .build()
-                .unwrap_or_else(|_| Client::new()),
+            client: default_provider_client(ProviderType::Antigravity),
             // 只使用生产环境和 daily 环境（参考 Antigravity-Manager）
             // 沙盒环境（autopush）需要特殊许可证，不适合普通用户
             base_urls: vec![
@@ -395,6 +385,16 @@ impl AntigravityProvider {
         Self::default()
     }
 
+    /// 使用自定义 HTTP 客户端创建（如按 Provider 配置了默认请求头的共享客户端）
+    ///
+    /// 客户端需自带 `User-Agent` 默认请求头，见 [`crate::http_client::builtin_header_map`]。
+    pub fn with_client(client: Client) -> Self {
+        Self {
+            client,
+            ..Self::default()
+        }
+    }
+
     pub fn default_creds_path() -> PathBuf {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -865,12 +865,9 @@ impl AntigravityProvider {
             serde_json::to_string_pretty(body).unwrap_or_default()
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
-            .header("User-Agent", "antigravity/1.11.9 windows/amd64")
             .json(body)
             .send()
             .await
@@ -952,10 +949,86 @@ impl AntigravityProvider {
             .unwrap_or_else(|| AntigravityApiError::new(503, "All Antigravity base URLs failed")))
     }
 
+    /// 以 SSE 方式调用 `streamGenerateContent`，返回上游响应供调用方转发
+    ///
+    /// 请求体为 Gemini 原生格式（与 [`Self::call_api`] 相同）；
+    /// 降级规则与 [`Self::call_api`] 一致，流建立之后不再降级。
+    pub async fn call_api_sse(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, AntigravityApiError> {
+        let token = self
+            .credentials
+            .access_token
+            .as_ref()
+            .ok_or_else(|| AntigravityApiError::new(401, "No access token"))?;
+        let mut last_error: Option<AntigravityApiError> = None;
+
+        for (idx, base_url) in self.base_urls.iter().enumerate() {
+            let url = format!("{base_url}/{ANTIGRAVITY_API_VERSION}:streamGenerateContent?alt=sse");
+            let error = match with_hop_header(self.client.post(&url), &url)
+                .header("Authorization", format!("Bearer {token}"))
+                .header("Content-Type", "application/json")
+                .header("Accept", "text/event-stream")
+                .json(body)
+                .send()
+                .await
+            {
+                Ok(resp) if resp.status().is_success() => return Ok(resp),
+                Ok(resp) => {
+                    let status = resp.status();
+                    let body_text = resp.text().await.unwrap_or_default();
+                    AntigravityApiError::with_body(
+                        status.as_u16(),
+                        format!("API call failed: {status}"),
+                        body_text,
+                    )
+                }
+                Err(e) => AntigravityApiError::new(503, format!("Network error: {e}")),
+            };
+
+            if error.is_retryable() && idx + 1 < self.base_urls.len() {
+                tracing::warn!(
+                    "[Antigravity] {} 流式请求返回可重试错误 (HTTP {}), 尝试下一个端点",
+                    base_url,
+                    error.status_code
+                );
+                last_error = Some(error);
+                continue;
+            }
+            return Err(error);
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| AntigravityApiError::new(503, "All Antigravity base URLs failed")))
+    }
+
+    /// 确定本次请求使用的项目 ID
+    ///
+    /// 依次尝试 `stored`（凭证中配置的 id）、已缓存的 id 和上游发现，
+    /// 都不可用时才随机生成，结果会缓存到 provider。
+    pub async fn ensure_project_id(&mut self, stored: Option<String>) -> String {
+        let stored = stored.or_else(|| self.project_id.take());
+        self.project_id = None;
+        let (project_id, _) =
+            resolve_project_id("Antigravity", stored.as_deref(), self.discover_project()).await;
+        self.project_id = Some(project_id.clone());
+        project_id
+    }
+
     /// 发现项目 ID
+    ///
+    /// 已缓存的 id 不合法时重新发现；发现失败或结果不合法时返回错误，不会随机生成。
     pub async fn discover_project(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
-        if let Some(ref project_id) = self.project_id {
-            return Ok(project_id.clone());
+        if let Some(project_id) = self.project_id.clone() {
+            if is_valid_project_id(&project_id) {
+                return Ok(project_id);
+            }
+            tracing::warn!(
+                "[Antigravity] 已缓存的 project id 无效，重新发现: {}",
+                project_id
+            );
+            self.project_id = None;
         }
 
         let body = serde_json::json!({
@@ -972,6 +1045,7 @@ impl AntigravityProvider {
 
         if let Some(project) = resp["cloudaicompanionProject"].as_str() {
             if !project.is_empty() {
+                validate_project_id(project)?;
                 self.project_id = Some(project.to_string());
                 return Ok(project.to_string());
             }
@@ -1006,11 +1080,9 @@ impl AntigravityProvider {
             .to_string();
 
         if project_id.is_empty() {
-            // 生成一个随机项目 ID 作为后备
-            let fallback = generate_project_id();
-            self.project_id = Some(fallback.clone());
-            return Ok(fallback);
+            return Err("Failed to discover project ID".into());
         }
+        validate_project_id(&project_id)?;
 
         self.project_id = Some(project_id.clone());
         Ok(project_id)
@@ -2175,13 +2247,10 @@ impl StreamingProvider for AntigravityProvider {
                 actual_model
             );
 
-            let result = self
-                .client
-                .post(&url)
+            let result = with_hop_header(self.client.post(&url), &url)
                 .header("Authorization", format!("Bearer {token}"))
                 .header("Content-Type", "application/json")
                 .header("Accept", "text/event-stream")
-                .header("User-Agent", "antigravity/1.11.9 windows/amd64")
                 .json(&payload)
                 .send()
                 .await;
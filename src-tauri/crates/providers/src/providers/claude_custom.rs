@@ -1,4 +1,5 @@
 //! Claude Custom Provider (自定义 Claude API)
+use crate::http_client::with_hop_header;
 use proxycast_core::models::anthropic::AnthropicMessagesRequest;
 use proxycast_core::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
 use reqwest::Client;
@@ -62,6 +63,18 @@ impl ClaudeCustomProvider {
         }
     }
 
+    /// 使用 API key、base_url 和共享 HTTP 客户端创建 Provider
+    pub fn with_client(api_key: String, base_url: Option<String>, client: Client) -> Self {
+        Self {
+            config: ClaudeCustomConfig {
+                api_key: Some(api_key),
+                base_url,
+                enabled: true,
+            },
+            client,
+        }
+    }
+
     pub fn get_base_url(&self) -> String {
         self.config
             .base_url
@@ -152,9 +165,7 @@ impl ClaudeCustomProvider {
             request.stream
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
@@ -261,9 +272,7 @@ impl ClaudeCustomProvider {
             request.stream
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
@@ -356,9 +365,7 @@ impl ClaudeCustomProvider {
             stream
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
@@ -388,9 +395,7 @@ impl ClaudeCustomProvider {
 
         let url = self.build_url("messages/count_tokens");
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
@@ -630,9 +635,7 @@ impl StreamingProvider for ClaudeCustomProvider {
             request.model
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
@@ -19,6 +19,8 @@
 use super::error::{
     create_auth_error, create_config_error, create_token_refresh_error, ProviderError,
 };
+use crate::http_client::default_provider_client;
+use proxycast_core::ProviderType;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -125,7 +127,7 @@ impl Default for ClaudeOAuthProvider {
     fn default() -> Self {
         Self {
             credentials: ClaudeOAuthCredentials::default(),
-            client: Client::new(),
+            client: default_provider_client(ProviderType::ClaudeOAuth),
             creds_path: None,
         }
     }
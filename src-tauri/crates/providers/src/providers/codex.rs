@@ -8,6 +8,8 @@
 use super::error::{
     create_auth_error, create_config_error, create_token_refresh_error, ProviderError,
 };
+use crate::http_client::{default_provider_client, with_hop_header};
+use proxycast_core::ProviderType;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -388,15 +390,18 @@ pub struct CodexProvider {
     pub creds_path: Option<PathBuf>,
     /// OAuth callback port
     pub callback_port: u16,
+    /// 本次请求使用的 API Base URL（优先于凭证中的 `api_base_url`，不写入凭证文件）
+    pub request_base_url: Option<String>,
 }
 
 impl Default for CodexProvider {
     fn default() -> Self {
         Self {
             credentials: CodexCredentials::default(),
-            client: Client::new(),
+            client: default_provider_client(ProviderType::Codex),
             creds_path: None,
             callback_port: DEFAULT_CALLBACK_PORT,
+            request_base_url: None,
         }
     }
 }
@@ -457,6 +462,15 @@ impl CodexProvider {
             .filter(|s| !s.is_empty())
     }
 
+    /// 自定义 API Base URL（trim 后的非空值，请求级地址优先）
+    fn custom_base_url(&self) -> Option<&str> {
+        self.request_base_url
+            .as_deref()
+            .or(self.credentials.api_base_url.as_deref())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+    }
+
     pub fn build_responses_url(base_url: &str) -> String {
         let base = base_url.trim_end_matches('/');
 
@@ -1063,21 +1077,8 @@ impl CodexProvider {
         // Build the Codex API URL
         let url = match mode {
             AuthMode::ApiKey => {
-                let has_custom_base_url = self
-                    .credentials
-                    .api_base_url
-                    .as_deref()
-                    .map(|s| s.trim())
-                    .filter(|s| !s.is_empty())
-                    .is_some();
-
-                let base_url = self
-                    .credentials
-                    .api_base_url
-                    .as_deref()
-                    .map(|s| s.trim())
-                    .filter(|s| !s.is_empty())
-                    .unwrap_or(DEFAULT_API_BASE_URL);
+                let has_custom_base_url = self.custom_base_url().is_some();
+                let base_url = self.custom_base_url().unwrap_or(DEFAULT_API_BASE_URL);
 
                 // Warn if API key doesn't look like OpenAI format but no custom base URL is set
                 if !has_custom_base_url && !token.starts_with("sk-") {
@@ -1097,9 +1098,7 @@ impl CodexProvider {
         // Transform OpenAI chat completion request to Codex format
         let codex_request = transform_to_codex_format(request)?;
 
-        let mut req = self
-            .client
-            .post(&url)
+        let mut req = with_hop_header(self.client.post(&url), &url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
             .header("Accept", "text/event-stream")
@@ -1110,13 +1109,7 @@ impl CodexProvider {
         // 部分三方 Codex 代理（如 Yunyi）会依赖 Codex CLI 的特征 headers；
         // 仅在 OAuth 模式或显式配置了自定义 base_url 时附加，避免影响 OpenAI 官方 Key 模式。
         let should_add_codex_cli_headers = matches!(mode, AuthMode::OAuth)
-            || (matches!(mode, AuthMode::ApiKey)
-                && self
-                    .credentials
-                    .api_base_url
-                    .as_deref()
-                    .map(|s| !s.trim().is_empty())
-                    .unwrap_or(false));
+            || (matches!(mode, AuthMode::ApiKey) && self.custom_base_url().is_some());
 
         if should_add_codex_cli_headers {
             req = req
@@ -2020,6 +2013,24 @@ mod tests {
         // 应该返回 access_token（即使已过期，由上层处理）
         assert_eq!(result.unwrap(), "expired_access_token");
     }
+
+    #[tokio::test]
+    async fn test_request_base_url_not_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut provider = CodexProvider::new();
+        provider.creds_path = Some(dir.path().join("auth.json"));
+        provider.credentials.api_key = Some("sk-test-api-key".to_string());
+        provider.credentials.api_base_url = Some("https://relay.example.com/v1".to_string());
+        provider.request_base_url = Some("http://localhost:9000/v1".to_string());
+
+        assert_eq!(provider.custom_base_url(), Some("http://localhost:9000/v1"));
+
+        // 保存凭证（如刷新后）时只写入凭证自身的地址
+        provider.save_credentials().await.unwrap();
+        let saved = std::fs::read_to_string(dir.path().join("auth.json")).unwrap();
+        assert!(saved.contains("https://relay.example.com/v1"));
+        assert!(!saved.contains("localhost:9000"));
+    }
 }
 
 // ============================================================================
@@ -8,8 +8,11 @@
 use super::error::{
     create_auth_error, create_config_error, create_token_refresh_error, ProviderError,
 };
+use super::project_id::{is_valid_project_id, resolve_project_id, validate_project_id};
 use super::traits::{CredentialProvider, ProviderResult};
+use crate::http_client::{default_provider_client, with_hop_header};
 use async_trait::async_trait;
+use proxycast_core::ProviderType;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -191,7 +194,7 @@ impl Default for GeminiProvider {
         Self {
             credentials: GeminiCredentials::default(),
             project_id: None,
-            client: Client::new(),
+            client: default_provider_client(ProviderType::Gemini),
         }
     }
 }
@@ -389,9 +392,7 @@ impl GeminiProvider {
 
         let url = self.get_api_url(action);
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
             .json(body)
@@ -408,9 +409,62 @@ impl GeminiProvider {
         Ok(data)
     }
 
+    /// 以 SSE 方式调用 `streamGenerateContent`，返回上游响应供调用方转发
+    pub async fn call_api_sse(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let token = self
+            .credentials
+            .access_token
+            .as_ref()
+            .ok_or("No access token")?;
+
+        let url = format!("{}?alt=sse", self.get_api_url("streamGenerateContent"));
+
+        let resp = with_hop_header(self.client.post(&url), &url)
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json")
+            .header("Accept", "text/event-stream")
+            .json(body)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("API call failed: {status} - {body}").into());
+        }
+
+        Ok(resp)
+    }
+
+    /// 确定本次请求使用的项目 ID
+    ///
+    /// 依次尝试 `stored`（凭证中配置的 id）、已缓存的 id 和上游发现，
+    /// 都不可用时才随机生成，结果会缓存到 provider。
+    pub async fn ensure_project_id(&mut self, stored: Option<String>) -> String {
+        let stored = stored.or_else(|| self.project_id.take());
+        self.project_id = None;
+        let (project_id, _) =
+            resolve_project_id("Gemini CLI", stored.as_deref(), self.discover_project()).await;
+        self.project_id = Some(project_id.clone());
+        project_id
+    }
+
+    /// 发现项目 ID
+    ///
+    /// 已缓存的 id 不合法时重新发现；发现失败或结果不合法时返回错误。
     pub async fn discover_project(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
-        if let Some(ref project_id) = self.project_id {
-            return Ok(project_id.clone());
+        if let Some(project_id) = self.project_id.clone() {
+            if is_valid_project_id(&project_id) {
+                return Ok(project_id);
+            }
+            tracing::warn!(
+                "[Gemini CLI] 已缓存的 project id 无效，重新发现: {}",
+                project_id
+            );
+            self.project_id = None;
         }
 
         let body = serde_json::json!({
@@ -427,6 +481,7 @@ impl GeminiProvider {
 
         if let Some(project) = resp["cloudaicompanionProject"].as_str() {
             if !project.is_empty() {
+                validate_project_id(project)?;
                 self.project_id = Some(project.to_string());
                 return Ok(project.to_string());
             }
@@ -463,6 +518,7 @@ impl GeminiProvider {
         if project_id.is_empty() {
             return Err("Failed to discover project ID".into());
         }
+        validate_project_id(&project_id)?;
 
         self.project_id = Some(project_id.clone());
         Ok(project_id)
@@ -584,7 +640,7 @@ impl GeminiApiKeyProvider {
     /// Create a new Gemini API Key provider
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: default_provider_client(ProviderType::GeminiApiKey),
         }
     }
 
@@ -602,9 +658,7 @@ impl GeminiApiKeyProvider {
     ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
         let url = credential.build_api_url(model, "generateContent");
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-goog-api-key", &credential.api_key)
             .header("Content-Type", "application/json")
             .json(body)
@@ -633,9 +687,7 @@ impl GeminiApiKeyProvider {
             credential.build_api_url(model, "streamGenerateContent")
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-goog-api-key", &credential.api_key)
             .header("Content-Type", "application/json")
             .json(body)
@@ -658,9 +710,7 @@ impl GeminiApiKeyProvider {
     ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
         let url = format!("{}/v1beta/models", credential.get_base_url());
 
-        let resp = self
-            .client
-            .get(&url)
+        let resp = with_hop_header(self.client.get(&url), &url)
             .header("x-goog-api-key", &credential.api_key)
             .send()
             .await?;
@@ -3,12 +3,14 @@
 #![allow(dead_code)]
 
 // 使用新的 translator 模块替代旧的 converter
+use crate::http_client::{default_provider_client, with_hop_header};
 use crate::providers::traits::{CredentialProvider, ProviderResult};
 use crate::translator::kiro::anthropic::request::convert_anthropic_to_codewhisperer;
 use crate::translator::kiro::openai::request::convert_openai_to_codewhisperer;
 use async_trait::async_trait;
 use proxycast_core::models::anthropic::AnthropicMessagesRequest;
 use proxycast_core::models::openai::*;
+use proxycast_core::ProviderType;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -253,17 +255,9 @@ pub struct KiroProvider {
 
 impl Default for KiroProvider {
     fn default() -> Self {
-        // 创建带超时配置的 HTTP 客户端
-        // 参考 AIClient-2-API: AXIOS_TIMEOUT: 300000 (5分钟)
-        let client = Client::builder()
-            .connect_timeout(std::time::Duration::from_secs(30)) // 连接超时 30 秒
-            .timeout(std::time::Duration::from_secs(300)) // 总超时 5 分钟
-            .build()
-            .unwrap_or_else(|_| Client::new());
-
         Self {
             credentials: KiroCredentials::default(),
-            client,
+            client: default_provider_client(ProviderType::Kiro),
             creds_path: None,
         }
     }
@@ -284,6 +278,14 @@ impl KiroProvider {
         Self::default()
     }
 
+    /// 使用自定义 HTTP 客户端创建（如按 Provider 配置了默认请求头的共享客户端）
+    pub fn with_client(client: Client) -> Self {
+        Self {
+            client,
+            ..Self::default()
+        }
+    }
+
     pub fn default_creds_path() -> PathBuf {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -1022,9 +1024,7 @@ impl KiroProvider {
             self.credentials.client_id.is_some()
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
             .header("Accept", "application/json")
@@ -1176,9 +1176,7 @@ impl StreamingProvider for KiroProvider {
             &machine_id[..16]
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
             .header("Accept", "application/vnd.amazon.eventstream")
@@ -1276,9 +1274,7 @@ impl KiroProvider {
             &machine_id[..16]
         );
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
             .header("Accept", "application/vnd.amazon.eventstream")
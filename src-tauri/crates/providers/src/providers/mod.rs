@@ -6,6 +6,7 @@ pub mod error;
 pub mod gemini;
 pub mod kiro;
 pub mod openai_custom;
+pub mod project_id;
 pub mod traits;
 pub mod vertex;
 
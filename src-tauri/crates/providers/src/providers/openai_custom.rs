@@ -1,6 +1,8 @@
 //! OpenAI Custom Provider (自定义 OpenAI 兼容 API)
+use crate::http_client::with_hop_header;
 use proxycast_core::models::openai::ChatCompletionRequest;
 use reqwest::Client;
+use reqwest::RequestBuilder;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -12,6 +14,9 @@ pub struct OpenAICustomConfig {
     pub api_key: Option<String>,
     pub base_url: Option<String>,
     pub enabled: bool,
+    /// 本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM），未配置 API Key 时不发送认证头
+    #[serde(default)]
+    pub local: bool,
 }
 
 pub struct OpenAICustomProvider {
@@ -64,11 +69,40 @@ impl OpenAICustomProvider {
                 api_key: Some(api_key),
                 base_url,
                 enabled: true,
+                local: false,
             },
             client: create_http_client(),
         }
     }
 
+    /// 使用 API key、base_url 和共享 HTTP 客户端创建 Provider
+    pub fn with_client(api_key: String, base_url: Option<String>, client: Client) -> Self {
+        Self {
+            config: OpenAICustomConfig {
+                api_key: Some(api_key),
+                base_url,
+                enabled: true,
+                local: false,
+            },
+            client,
+        }
+    }
+
+    /// 创建本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM）的 Provider
+    ///
+    /// 本地服务通常不校验 API Key，`api_key` 为空时请求不带 Authorization 头。
+    pub fn with_local_server(base_url: String, api_key: Option<String>, client: Client) -> Self {
+        Self {
+            config: OpenAICustomConfig {
+                api_key,
+                base_url: Some(base_url),
+                enabled: true,
+                local: true,
+            },
+            client,
+        }
+    }
+
     pub fn get_base_url(&self) -> String {
         self.config
             .base_url
@@ -77,7 +111,24 @@ impl OpenAICustomProvider {
     }
 
     pub fn is_configured(&self) -> bool {
-        self.config.api_key.is_some() && self.config.enabled
+        (self.config.api_key.is_some() || self.config.local) && self.config.enabled
+    }
+
+    /// 请求使用的 API Key；本地服务未配置 API Key 时为 `None`
+    fn bearer_token(&self) -> Result<Option<&str>, &'static str> {
+        match self.config.api_key.as_deref() {
+            Some(key) if !key.is_empty() || !self.config.local => Ok(Some(key)),
+            _ if self.config.local => Ok(None),
+            _ => Err("OpenAI API key not configured"),
+        }
+    }
+
+    /// 添加 Authorization 头（无 API Key 时不添加）
+    fn authorize(builder: RequestBuilder, api_key: Option<&str>) -> RequestBuilder {
+        match api_key {
+            Some(key) => builder.header("Authorization", format!("Bearer {key}")),
+            None => builder,
+        }
     }
 
     /// 构建完整的 API URL
@@ -208,11 +259,7 @@ impl OpenAICustomProvider {
         &self,
         request: &ChatCompletionRequest,
     ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        let api_key = self
-            .config
-            .api_key
-            .as_ref()
-            .ok_or("OpenAI API key not configured")?;
+        let api_key = self.bearer_token()?;
 
         let urls = self.build_urls_with_fallbacks("chat/completions");
         let mut last_resp: Option<reqwest::Response> = None;
@@ -224,10 +271,7 @@ impl OpenAICustomProvider {
 
         for url in &urls {
             eprintln!("[OPENAI_CUSTOM] call_api trying URL: {url}");
-            let resp = self
-                .client
-                .post(url)
-                .header("Authorization", format!("Bearer {api_key}"))
+            let resp = Self::authorize(with_hop_header(self.client.post(url), url), api_key)
                 .header("Content-Type", "application/json")
                 .json(request)
                 .send()
@@ -248,11 +292,7 @@ impl OpenAICustomProvider {
         &self,
         request: &serde_json::Value,
     ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        let api_key = self
-            .config
-            .api_key
-            .as_ref()
-            .ok_or("OpenAI API key not configured")?;
+        let api_key = self.bearer_token()?;
 
         let url = self.build_url("chat/completions");
 
@@ -262,10 +302,7 @@ impl OpenAICustomProvider {
             self.get_base_url()
         );
 
-        let resp = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {api_key}"))
+        let resp = Self::authorize(with_hop_header(self.client.post(&url), &url), api_key)
             .header("Content-Type", "application/json")
             .json(request)
             .send()
@@ -276,14 +313,14 @@ impl OpenAICustomProvider {
         if resp.status() == StatusCode::NOT_FOUND {
             if let Some(fallback_url) = self.build_url_fallback_without_v1("chat/completions") {
                 if fallback_url != url {
-                    let resp2 = self
-                        .client
-                        .post(&fallback_url)
-                        .header("Authorization", format!("Bearer {api_key}"))
-                        .header("Content-Type", "application/json")
-                        .json(request)
-                        .send()
-                        .await?;
+                    let resp2 = Self::authorize(
+                        with_hop_header(self.client.post(&fallback_url), &fallback_url),
+                        api_key,
+                    )
+                    .header("Content-Type", "application/json")
+                    .json(request)
+                    .send()
+                    .await?;
                     Self::maybe_log_protocol_mismatch_hint(&fallback_url, resp2.status());
                     return Ok(resp2);
                 }
@@ -294,11 +331,7 @@ impl OpenAICustomProvider {
     }
 
     pub async fn list_models(&self) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
-        let api_key = self
-            .config
-            .api_key
-            .as_ref()
-            .ok_or("OpenAI API key not configured")?;
+        let api_key = self.bearer_token()?;
 
         let urls = self.build_urls_with_fallbacks("models");
         let mut tried_urls: Vec<String> = Vec::new();
@@ -307,10 +340,7 @@ impl OpenAICustomProvider {
         for url in urls {
             eprintln!("[OPENAI_CUSTOM] list_models URL: {url}");
             tried_urls.push(url.clone());
-            let r = self
-                .client
-                .get(&url)
-                .header("Authorization", format!("Bearer {api_key}"))
+            let r = Self::authorize(with_hop_header(self.client.get(&url), &url), api_key)
                 .send()
                 .await?;
             Self::maybe_log_protocol_mismatch_hint(&url, r.status());
@@ -337,6 +367,23 @@ impl OpenAICustomProvider {
         let data: serde_json::Value = resp.json().await?;
         Ok(data)
     }
+
+    /// 查询 `/v1/models` 返回模型 ID 列表（用于本地服务的模型发现）
+    pub async fn list_model_ids(&self) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let data = self.list_models().await?;
+        let ids = data
+            .get("data")
+            .and_then(|d| d.as_array())
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m.get("id").and_then(|id| id.as_str()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(ids)
+    }
 }
 
 // ============================================================================
@@ -362,9 +409,9 @@ impl StreamingProvider for OpenAICustomProvider {
         &self,
         request: &ChatCompletionRequest,
     ) -> Result<StreamResponse, ProviderError> {
-        let api_key = self.config.api_key.as_ref().ok_or_else(|| {
-            ProviderError::ConfigurationError("OpenAI API key not configured".to_string())
-        })?;
+        let api_key = self
+            .bearer_token()
+            .map_err(|e| ProviderError::ConfigurationError(e.to_string()))?;
 
         // 确保请求启用流式
         let mut stream_request = request.clone();
@@ -378,10 +425,7 @@ impl StreamingProvider for OpenAICustomProvider {
             request.model
         );
 
-        let resp = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {api_key}"))
+        let resp = Self::authorize(with_hop_header(self.client.post(&url), &url), api_key)
             .header("Content-Type", "application/json")
             .header("Accept", "text/event-stream")
             .json(&stream_request)
@@ -392,15 +436,16 @@ impl StreamingProvider for OpenAICustomProvider {
         let resp = if resp.status() == StatusCode::NOT_FOUND {
             if let Some(fallback_url) = self.build_url_fallback_without_v1("chat/completions") {
                 if fallback_url != url {
-                    self.client
-                        .post(&fallback_url)
-                        .header("Authorization", format!("Bearer {api_key}"))
-                        .header("Content-Type", "application/json")
-                        .header("Accept", "text/event-stream")
-                        .json(&stream_request)
-                        .send()
-                        .await
-                        .map_err(|e| ProviderError::from_reqwest_error(&e))?
+                    Self::authorize(
+                        with_hop_header(self.client.post(&fallback_url), &fallback_url),
+                        api_key,
+                    )
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "text/event-stream")
+                    .json(&stream_request)
+                    .send()
+                    .await
+                    .map_err(|e| ProviderError::from_reqwest_error(&e))?
                 } else {
                     resp
                 }
@@ -437,3 +482,130 @@ impl StreamingProvider for OpenAICustomProvider {
         StreamFormat::OpenAiSse
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderMap;
+    use axum::response::IntoResponse;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// 启动模拟的本地 OpenAI 兼容服务（统计带 Authorization 头的请求数），返回 base_url
+    async fn spawn_local_server(authorized: Arc<AtomicUsize>) -> String {
+        let check = move |headers: &HeaderMap| {
+            if headers.contains_key("authorization") {
+                authorized.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        let models_check = check.clone();
+        let app = Router::new()
+            .route(
+                "/v1/models",
+                get(move |headers: HeaderMap| async move {
+                    models_check(&headers);
+                    Json(serde_json::json!({
+                        "object": "list",
+                        "data": [
+                            {"id": "llama3.2", "object": "model"},
+                            {"id": "qwen2.5-coder", "object": "model"}
+                        ]
+                    }))
+                }),
+            )
+            .route(
+                "/v1/chat/completions",
+                post(
+                    move |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                        check(&headers);
+                        if body["stream"].as_bool() == Some(true) {
+                            let sse = "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+                                       data: [DONE]\n\n";
+                            ([("content-type", "text/event-stream")], sse).into_response()
+                        } else {
+                            Json(serde_json::json!({
+                                "id": "chatcmpl-local",
+                                "object": "chat.completion",
+                                "model": body["model"],
+                                "choices": [{
+                                    "index": 0,
+                                    "message": {"role": "assistant", "content": "Hi"},
+                                    "finish_reason": "stop"
+                                }]
+                            }))
+                            .into_response()
+                        }
+                    },
+                ),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        format!("http://{addr}")
+    }
+
+    fn chat_request(stream: bool) -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "llama3.2",
+            "messages": [{"role": "user", "content": "Hello"}],
+            "stream": stream
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_local_server_works_without_api_key() {
+        let authorized = Arc::new(AtomicUsize::new(0));
+        let base_url = spawn_local_server(authorized.clone()).await;
+        let provider = OpenAICustomProvider::with_local_server(base_url, None, Client::new());
+        assert!(provider.is_configured());
+
+        // 模型发现
+        let models = provider.list_model_ids().await.unwrap();
+        assert_eq!(models, vec!["llama3.2", "qwen2.5-coder"]);
+
+        // 非流式对话
+        let resp = provider.call_api(&chat_request(false)).await.unwrap();
+        assert!(resp.status().is_success());
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], "Hi");
+
+        // 流式对话
+        assert!(provider.supports_streaming());
+        let mut stream = provider.call_api_stream(&chat_request(true)).await.unwrap();
+        let mut sse = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            sse.extend_from_slice(&chunk.unwrap());
+        }
+        let sse = String::from_utf8(sse).unwrap();
+        assert!(sse.contains("\"content\":\"Hi\""));
+        assert!(sse.contains("[DONE]"));
+
+        // 未配置 API Key 时不发送认证头
+        assert_eq!(authorized.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_still_required_for_remote_provider() {
+        let provider = OpenAICustomProvider::new();
+        assert!(!provider.is_configured());
+        let err = provider.call_api(&chat_request(false)).await.unwrap_err();
+        assert!(err.to_string().contains("API key not configured"));
+
+        // 本地服务配置了 API Key 时照常发送
+        let authorized = Arc::new(AtomicUsize::new(0));
+        let base_url = spawn_local_server(authorized.clone()).await;
+        let provider = OpenAICustomProvider::with_local_server(
+            base_url,
+            Some("sk-local".to_string()),
+            Client::new(),
+        );
+        provider.list_model_ids().await.unwrap();
+        assert_eq!(authorized.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,187 @@
+//! Cloud Code project id 处理
+//!
+//! Gemini CLI 与 Antigravity 请求都需要 GCP project id。确定顺序：
+//! 1. 已配置（凭证中保存）的 id，格式合法时原样使用
+//! 2. 通过 loadCodeAssist / onboardUser 发现
+//! 3. 仅在发现失败时随机生成
+//!
+//! 格式校验遵循 GCP project id 规则，避免把上游必然拒绝的 id 发出去。
+
+use std::fmt::Display;
+use std::future::Future;
+use uuid::Uuid;
+
+/// project id 最小长度
+const MIN_PROJECT_ID_LEN: usize = 6;
+/// project id 最大长度
+const MAX_PROJECT_ID_LEN: usize = 30;
+
+/// project id 的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectIdSource {
+    /// 凭证中已配置
+    Stored,
+    /// 通过上游接口发现
+    Discovered,
+    /// 发现失败后随机生成
+    Generated,
+}
+
+impl ProjectIdSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            ProjectIdSource::Stored => "stored",
+            ProjectIdSource::Discovered => "discovered",
+            ProjectIdSource::Generated => "generated",
+        }
+    }
+}
+
+/// 校验 project id 是否符合 GCP 规则
+///
+/// 6~30 个字符，仅含小写字母、数字和连字符，以字母开头，不以连字符结尾。
+pub fn validate_project_id(project_id: &str) -> Result<(), String> {
+    let len = project_id.len();
+    if !(MIN_PROJECT_ID_LEN..=MAX_PROJECT_ID_LEN).contains(&len) {
+        return Err(format!(
+            "project id 长度必须在 {MIN_PROJECT_ID_LEN}~{MAX_PROJECT_ID_LEN} 之间: {project_id}"
+        ));
+    }
+    if !project_id.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Err(format!("project id 必须以小写字母开头: {project_id}"));
+    }
+    if project_id.ends_with('-') {
+        return Err(format!("project id 不能以连字符结尾: {project_id}"));
+    }
+    if !project_id
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+    {
+        return Err(format!(
+            "project id 只能包含小写字母、数字和连字符: {project_id}"
+        ));
+    }
+    Ok(())
+}
+
+/// project id 是否合法
+pub fn is_valid_project_id(project_id: &str) -> bool {
+    validate_project_id(project_id).is_ok()
+}
+
+/// 生成随机项目 ID（形如 `swift-wave-3fa9c`）
+pub fn generate_project_id() -> String {
+    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
+    let nouns = ["fuze", "wave", "spark", "flow", "core"];
+    let uuid = Uuid::new_v4();
+    let bytes = uuid.as_bytes();
+    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
+    let noun = nouns[(bytes[1] as usize) % nouns.len()];
+    let random_part: String = uuid.to_string()[..5].to_lowercase();
+    format!("{adj}-{noun}-{random_part}")
+}
+
+/// 按"已配置 -> 发现 -> 生成"的顺序确定 project id
+///
+/// `discover` 只有在没有合法的已配置 id 时才会被执行；
+/// 发现失败或发现的 id 不合法时才随机生成。
+pub async fn resolve_project_id<Fut, E>(
+    provider: &str,
+    stored: Option<&str>,
+    discover: Fut,
+) -> (String, ProjectIdSource)
+where
+    Fut: Future<Output = Result<String, E>>,
+    E: Display,
+{
+    let (project_id, source) = match stored.map(|id| (id, validate_project_id(id))) {
+        Some((id, Ok(()))) => (id.to_string(), ProjectIdSource::Stored),
+        stored => {
+            if let Some((_, Err(reason))) = stored {
+                tracing::warn!("[{}] 忽略已配置的 project id: {}", provider, reason);
+            }
+            match discover.await {
+                Ok(id) => match validate_project_id(&id) {
+                    Ok(()) => (id, ProjectIdSource::Discovered),
+                    Err(reason) => {
+                        tracing::warn!("[{}] 发现的 project id 无效: {}", provider, reason);
+                        (generate_project_id(), ProjectIdSource::Generated)
+                    }
+                },
+                Err(e) => {
+                    tracing::warn!("[{}] 获取 project id 失败: {}", provider, e);
+                    (generate_project_id(), ProjectIdSource::Generated)
+                }
+            }
+        }
+    };
+
+    tracing::info!(
+        "[{}] 使用 project id: {} (source={})",
+        provider,
+        project_id,
+        source.as_str()
+    );
+    (project_id, source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_validate_project_id() {
+        assert!(is_valid_project_id("my-project-123"));
+        assert!(is_valid_project_id(&generate_project_id()));
+
+        assert!(!is_valid_project_id("short"));
+        assert!(!is_valid_project_id("1-starts-with-digit"));
+        assert!(!is_valid_project_id("Upper-Case-Id"));
+        assert!(!is_valid_project_id("ends-with-hyphen-"));
+        assert!(!is_valid_project_id("has_underscore_id"));
+        assert!(!is_valid_project_id(&"a".repeat(31)));
+    }
+
+    #[tokio::test]
+    async fn test_valid_stored_id_used_verbatim() {
+        let discovered = Cell::new(false);
+        let (id, source) = resolve_project_id("Test", Some("stored-project-1"), async {
+            discovered.set(true);
+            Ok::<_, String>("discovered-project".to_string())
+        })
+        .await;
+
+        assert_eq!(id, "stored-project-1");
+        assert_eq!(source, ProjectIdSource::Stored);
+        assert!(!discovered.get());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_stored_id_rejected() {
+        let (id, source) = resolve_project_id("Test", Some("Bad_Project"), async {
+            Ok::<_, String>("discovered-project".to_string())
+        })
+        .await;
+
+        assert_eq!(id, "discovered-project");
+        assert_eq!(source, ProjectIdSource::Discovered);
+    }
+
+    #[tokio::test]
+    async fn test_generation_only_on_discovery_failure() {
+        let (id, source) = resolve_project_id("Test", None, async {
+            Ok::<_, String>("discovered-project".to_string())
+        })
+        .await;
+        assert_eq!(source, ProjectIdSource::Discovered);
+        assert_eq!(id, "discovered-project");
+
+        let (id, source) = resolve_project_id("Test", None, async {
+            Err::<String, _>("loadCodeAssist 请求失败")
+        })
+        .await;
+        assert_eq!(source, ProjectIdSource::Generated);
+        assert!(is_valid_project_id(&id));
+    }
+}
@@ -5,6 +5,7 @@
 
 #![allow(dead_code)]
 
+use crate::http_client::with_hop_header;
 use proxycast_core::models::vertex_model::VertexApiKeyEntry;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -186,9 +187,7 @@ impl VertexProvider {
         // Vertex AI uses a different URL pattern
         let url = format!("{base_url}/models/{model}:generateContent");
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-goog-api-key", api_key)
             .header("Content-Type", "application/json")
             .json(&request)
@@ -225,9 +224,7 @@ impl VertexProvider {
         // Streaming endpoint
         let url = format!("{base_url}/models/{model}:streamGenerateContent");
 
-        let resp = self
-            .client
-            .post(&url)
+        let resp = with_hop_header(self.client.post(&url), &url)
             .header("x-goog-api-key", api_key)
             .header("Content-Type", "application/json")
             .json(&request)
@@ -248,9 +245,7 @@ impl VertexProvider {
         let base_url = self.get_base_url();
         let url = format!("{base_url}/models");
 
-        let resp = self
-            .client
-            .get(&url)
+        let resp = with_hop_header(self.client.get(&url), &url)
             .header("x-goog-api-key", api_key)
             .send()
             .await?;
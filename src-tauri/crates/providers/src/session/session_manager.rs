@@ -177,6 +177,11 @@ mod tests {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream_options: None,
         };
 
         let sid1 = SessionManager::extract_session_id(&request);
@@ -209,6 +214,11 @@ mod tests {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream_options: None,
         };
 
         let request2 = ChatCompletionRequest {
@@ -229,6 +239,11 @@ mod tests {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream_options: None,
         };
 
         let sid1 = SessionManager::extract_session_id(&request1);
@@ -31,7 +31,7 @@ pub enum StreamEvent {
 
     /// 内容块开始
     ///
-    /// 表示一个新的内容块开始（文本或工具调用）
+    /// 表示一个新的内容块开始（文本、思考或工具调用）
     ContentBlockStart {
         /// 内容块索引
         index: u32,
@@ -47,6 +47,14 @@ pub enum StreamEvent {
         text: String,
     },
 
+    /// 思考内容增量
+    ///
+    /// 对应 Extended Thinking 思考内容的增量输出
+    ThinkingDelta {
+        /// 思考内容
+        thinking: String,
+    },
+
     /// 工具调用开始
     ///
     /// 表示一个新的工具调用开始
@@ -136,6 +144,8 @@ pub enum StreamEvent {
 pub enum ContentBlockType {
     /// 文本内容
     Text,
+    /// 思考内容
+    Thinking,
     /// 工具调用
     ToolUse {
         /// 工具调用 ID
@@ -146,6 +156,9 @@ pub enum ContentBlockType {
 }
 
 /// 停止原因
+///
+/// 不同上游的结束原因词汇不同（OpenAI `stop`/`length`，Anthropic `end_turn`/`max_tokens`，
+/// Gemini `STOP`/`MAX_TOKENS` 等），统一解析到这里，只在输出时映射回目标协议的取值。
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StopReason {
     /// 正常结束
@@ -156,6 +169,8 @@ pub enum StopReason {
     ToolUse,
     /// 用户停止
     StopSequence,
+    /// 被内容安全策略拦截
+    ContentFilter,
     /// 其他原因
     Other(String),
 }
@@ -167,36 +182,72 @@ impl Default for StopReason {
 }
 
 impl StopReason {
-    /// 从字符串解析停止原因
+    /// 从字符串解析停止原因（来源协议未知时使用）
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "end_turn" | "stop" => Self::EndTurn,
-            "max_tokens" | "length" => Self::MaxTokens,
-            "tool_use" | "tool_calls" => Self::ToolUse,
+            "max_tokens" | "length" | "model_context_window_exceeded" => Self::MaxTokens,
+            "tool_use" | "tool_calls" | "function_call" => Self::ToolUse,
             "stop_sequence" => Self::StopSequence,
+            "content_filter" | "refusal" | "safety" | "recitation" | "blocklist"
+            | "prohibited_content" | "spii" | "image_safety" => Self::ContentFilter,
             _ => Self::Other(s.to_string()),
         }
     }
 
+    /// 解析 OpenAI 的 `finish_reason`
+    pub fn from_openai(s: &str) -> Self {
+        match s {
+            "length" => Self::MaxTokens,
+            "tool_calls" | "function_call" => Self::ToolUse,
+            "content_filter" => Self::ContentFilter,
+            _ => Self::EndTurn,
+        }
+    }
+
+    /// 解析 Anthropic 的 `stop_reason`
+    pub fn from_anthropic(s: &str) -> Self {
+        match s {
+            "max_tokens" | "model_context_window_exceeded" => Self::MaxTokens,
+            "tool_use" => Self::ToolUse,
+            "stop_sequence" => Self::StopSequence,
+            "refusal" => Self::ContentFilter,
+            _ => Self::EndTurn,
+        }
+    }
+
+    /// 解析 Gemini 的 `finishReason`
+    pub fn from_gemini(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "MAX_TOKENS" => Self::MaxTokens,
+            "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII"
+            | "IMAGE_SAFETY" => Self::ContentFilter,
+            _ => Self::EndTurn,
+        }
+    }
+
     /// 转换为 OpenAI 格式的字符串
-    pub fn to_openai_str(&self) -> &str {
+    pub fn to_openai_str(&self) -> &'static str {
         match self {
             Self::EndTurn => "stop",
             Self::MaxTokens => "length",
             Self::ToolUse => "tool_calls",
             Self::StopSequence => "stop",
+            Self::ContentFilter => "content_filter",
+            // 未知原因不透传给客户端
             Self::Other(_) => "stop",
         }
     }
 
     /// 转换为 Anthropic 格式的字符串
-    pub fn to_anthropic_str(&self) -> &str {
+    pub fn to_anthropic_str(&self) -> &'static str {
         match self {
             Self::EndTurn => "end_turn",
             Self::MaxTokens => "max_tokens",
             Self::ToolUse => "tool_use",
             Self::StopSequence => "stop_sequence",
-            Self::Other(s) => s,
+            Self::ContentFilter => "refusal",
+            Self::Other(_) => "end_turn",
         }
     }
 }
@@ -286,6 +337,63 @@ mod tests {
         assert_eq!(StopReason::EndTurn.to_anthropic_str(), "end_turn");
         assert_eq!(StopReason::MaxTokens.to_anthropic_str(), "max_tokens");
         assert_eq!(StopReason::ToolUse.to_anthropic_str(), "tool_use");
+        assert_eq!(
+            StopReason::from_str("MAX_TOKENS").to_anthropic_str(),
+            "max_tokens"
+        );
+        assert_eq!(StopReason::from_str("SAFETY").to_anthropic_str(), "refusal");
+        assert_eq!(
+            StopReason::from_str("SAFETY").to_openai_str(),
+            "content_filter"
+        );
+        assert_eq!(
+            StopReason::Other("something_new".to_string()).to_anthropic_str(),
+            "end_turn"
+        );
+    }
+
+    #[test]
+    fn test_stop_reason_provider_vocabularies() {
+        let cases = [
+            (StopReason::from_openai("length"), StopReason::MaxTokens),
+            (
+                StopReason::from_openai("function_call"),
+                StopReason::ToolUse,
+            ),
+            (
+                StopReason::from_openai("content_filter"),
+                StopReason::ContentFilter,
+            ),
+            (StopReason::from_anthropic("end_turn"), StopReason::EndTurn),
+            (
+                StopReason::from_anthropic("stop_sequence"),
+                StopReason::StopSequence,
+            ),
+            (
+                StopReason::from_anthropic("refusal"),
+                StopReason::ContentFilter,
+            ),
+            (StopReason::from_gemini("STOP"), StopReason::EndTurn),
+            (StopReason::from_gemini("max_tokens"), StopReason::MaxTokens),
+            (
+                StopReason::from_gemini("RECITATION"),
+                StopReason::ContentFilter,
+            ),
+            (StopReason::from_gemini("OTHER"), StopReason::EndTurn),
+        ];
+        for (actual, expected) in cases {
+            assert_eq!(actual, expected);
+        }
+
+        // 跨协议往返
+        assert_eq!(
+            StopReason::from_gemini("MAX_TOKENS").to_anthropic_str(),
+            "max_tokens"
+        );
+        assert_eq!(
+            StopReason::from_anthropic("end_turn").to_openai_str(),
+            "stop"
+        );
     }
 
     #[test]
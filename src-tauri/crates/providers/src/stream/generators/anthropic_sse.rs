@@ -64,6 +64,10 @@ pub struct AnthropicSseGenerator {
     cache_creation_input_tokens: u32,
     /// 累积的停止原因
     stop_reason: Option<StopReason>,
+    /// 当前文本块索引
+    text_block_index: u32,
+    /// 当前思考块索引
+    thinking_block_index: u32,
 }
 
 impl Default for AnthropicSseGenerator {
@@ -85,6 +89,8 @@ impl AnthropicSseGenerator {
             cache_read_input_tokens: 0,
             cache_creation_input_tokens: 0,
             stop_reason: None,
+            text_block_index: 0,
+            thinking_block_index: 0,
         }
     }
 
@@ -100,6 +106,8 @@ impl AnthropicSseGenerator {
             cache_read_input_tokens: 0,
             cache_creation_input_tokens: 0,
             stop_reason: None,
+            text_block_index: 0,
+            thinking_block_index: 0,
         }
     }
 
@@ -129,8 +137,13 @@ impl AnthropicSseGenerator {
             StreamEvent::ContentBlockStart { index, block_type } => {
                 match block_type {
                     ContentBlockType::Text => {
+                        self.text_block_index = *index;
                         sse_events.push(self.create_content_block_start_text(*index));
                     }
+                    ContentBlockType::Thinking => {
+                        self.thinking_block_index = *index;
+                        sse_events.push(self.create_content_block_start_thinking(*index));
+                    }
                     ContentBlockType::ToolUse { id, name } => {
                         // 记录工具调用状态
                         self.tool_calls.insert(
@@ -148,8 +161,11 @@ impl AnthropicSseGenerator {
             }
 
             StreamEvent::TextDelta { text } => {
-                // 假设文本块索引为 0（大多数情况下）
-                sse_events.push(self.create_text_delta(0, text));
+                sse_events.push(self.create_text_delta(self.text_block_index, text));
+            }
+
+            StreamEvent::ThinkingDelta { thinking } => {
+                sse_events.push(self.create_thinking_delta(self.thinking_block_index, thinking));
             }
 
             StreamEvent::ToolUseStart { id, name } => {
@@ -278,6 +294,18 @@ impl AnthropicSseGenerator {
         format!("event: content_block_start\ndata: {event}\n\n")
     }
 
+    fn create_content_block_start_thinking(&self, index: u32) -> String {
+        let event = serde_json::json!({
+            "type": "content_block_start",
+            "index": index,
+            "content_block": {
+                "type": "thinking",
+                "thinking": ""
+            }
+        });
+        format!("event: content_block_start\ndata: {event}\n\n")
+    }
+
     fn create_content_block_start_tool(&self, index: u32, id: &str, name: &str) -> String {
         let event = serde_json::json!({
             "type": "content_block_start",
@@ -304,6 +332,18 @@ impl AnthropicSseGenerator {
         format!("event: content_block_delta\ndata: {event}\n\n")
     }
 
+    fn create_thinking_delta(&self, index: u32, thinking: &str) -> String {
+        let event = serde_json::json!({
+            "type": "content_block_delta",
+            "index": index,
+            "delta": {
+                "type": "thinking_delta",
+                "thinking": thinking
+            }
+        });
+        format!("event: content_block_delta\ndata: {event}\n\n")
+    }
+
     fn create_input_json_delta(&self, index: u32, partial_json: &str) -> String {
         let event = serde_json::json!({
             "type": "content_block_delta",
@@ -409,6 +449,37 @@ mod tests {
         assert!(sse[0].contains("Hello"));
     }
 
+    #[test]
+    fn test_generate_thinking_then_text() {
+        let mut generator = AnthropicSseGenerator::new("claude-sonnet-4-5".to_string());
+        let events = [
+            StreamEvent::ContentBlockStart {
+                index: 0,
+                block_type: ContentBlockType::Thinking,
+            },
+            StreamEvent::ThinkingDelta {
+                thinking: "先想一想".to_string(),
+            },
+            StreamEvent::ContentBlockStop { index: 0 },
+            StreamEvent::ContentBlockStart {
+                index: 1,
+                block_type: ContentBlockType::Text,
+            },
+            StreamEvent::TextDelta {
+                text: "答案".to_string(),
+            },
+        ];
+        let sse: Vec<String> = events.iter().flat_map(|e| generator.generate(e)).collect();
+
+        assert!(sse[1].contains(r#""content_block":{"thinking":"","type":"thinking"}"#));
+        assert!(sse[2].contains(r#""index":0"#));
+        assert!(sse[2].contains(r#""type":"thinking_delta""#));
+        assert!(sse[2].contains("先想一想"));
+        // 文本增量使用文本块自己的索引
+        assert!(sse[5].contains(r#""index":1"#));
+        assert!(sse[5].contains(r#""type":"text_delta""#));
+    }
+
     #[test]
     fn test_generate_tool_use() {
         let mut generator = AnthropicSseGenerator::new("claude-3-sonnet".to_string());
@@ -10,6 +10,13 @@
 //!
 //! data: [DONE]
 //! ```
+//!
+//! # 工具调用合并
+//!
+//! 上游可能把工具调用参数拆成很多碎片，部分 OpenAI 客户端拼接碎片时会出错。
+//! 开启 `coalesce_tool_calls` 后，参数增量只在内部累积，工具调用结束时
+//! 校验完整 JSON 并一次性发送包含 `id`、`name`、`arguments` 的完整块。
+//! 无论是否合并，结束时参数不是合法 JSON 都会发送错误帧，而不是静默截断。
 
 use crate::stream::events::{ContentBlockType, StreamEvent};
 use serde::Serialize;
@@ -29,10 +36,11 @@ pub struct OpenAiSseGenerator {
     tool_calls: HashMap<String, ToolCallState>,
     /// 下一个工具调用索引
     next_tool_index: usize,
+    /// 是否把工具调用参数增量合并为一个完整块
+    coalesce_tool_calls: bool,
 }
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 struct ToolCallState {
     /// 工具调用在 tool_calls 数组中的索引
     index: usize,
@@ -62,6 +70,7 @@ impl OpenAiSseGenerator {
             created,
             tool_calls: HashMap::new(),
             next_tool_index: 0,
+            coalesce_tool_calls: false,
         }
     }
 
@@ -78,9 +87,16 @@ impl OpenAiSseGenerator {
             created,
             tool_calls: HashMap::new(),
             next_tool_index: 0,
+            coalesce_tool_calls: false,
         }
     }
 
+    /// 设置是否合并工具调用参数增量
+    pub fn with_coalesce_tool_calls(mut self, enabled: bool) -> Self {
+        self.coalesce_tool_calls = enabled;
+        self
+    }
+
     /// 将 StreamEvent 转换为 OpenAI SSE 字符串
     ///
     /// # 返回
@@ -125,6 +141,28 @@ impl OpenAiSseGenerator {
                         delta: OpenAiDelta {
                             role: None,
                             content: Some(text.as_str()),
+                            reasoning_content: None,
+                            tool_calls: None,
+                        },
+                        finish_reason: None,
+                    }],
+                };
+                Some(format!("data: {}\n\n", serde_json::to_string(&chunk).ok()?))
+            }
+
+            StreamEvent::ThinkingDelta { thinking } => {
+                // 思考内容映射为 reasoning_content
+                let chunk = OpenAiStreamChunk {
+                    id: &self.response_id,
+                    object: "chat.completion.chunk",
+                    created: self.created,
+                    model: &self.model,
+                    choices: vec![OpenAiChoice {
+                        index: 0,
+                        delta: OpenAiDelta {
+                            role: None,
+                            content: None,
+                            reasoning_content: Some(thinking.as_str()),
                             tool_calls: None,
                         },
                         finish_reason: None,
@@ -150,6 +188,10 @@ impl OpenAiSseGenerator {
                     );
                     index
                 };
+                if self.coalesce_tool_calls {
+                    // 合并模式下等到工具调用结束再发送
+                    return None;
+                }
 
                 let chunk = OpenAiStreamChunk {
                     id: &self.response_id,
@@ -161,6 +203,7 @@ impl OpenAiSseGenerator {
                         delta: OpenAiDelta {
                             role: None,
                             content: None,
+                            reasoning_content: None,
                             tool_calls: Some(vec![OpenAiToolCallDelta {
                                 index,
                                 id: Some(id.as_str()),
@@ -184,6 +227,9 @@ impl OpenAiSseGenerator {
                 if let Some(state) = self.tool_calls.get_mut(id) {
                     state.arguments.push_str(partial_json);
                 }
+                if self.coalesce_tool_calls {
+                    return None;
+                }
 
                 let chunk = OpenAiStreamChunk {
                     id: &self.response_id,
@@ -195,6 +241,7 @@ impl OpenAiSseGenerator {
                         delta: OpenAiDelta {
                             role: None,
                             content: None,
+                            reasoning_content: None,
                             tool_calls: Some(vec![OpenAiToolCallDelta {
                                 index,
                                 id: None,
@@ -212,9 +259,10 @@ impl OpenAiSseGenerator {
             }
 
             StreamEvent::ToolUseStop { id } => {
-                // OpenAI 格式不需要单独的工具调用结束事件
-                self.tool_calls.remove(id);
-                None
+                // OpenAI 格式不需要单独的工具调用结束事件，
+                // 只在合并模式下发送完整块或在参数非法时发送错误帧
+                let state = self.tool_calls.remove(id)?;
+                self.finish_tool_call(id, &state)
             }
 
             StreamEvent::ContentBlockStop { .. } => {
@@ -225,6 +273,14 @@ impl OpenAiSseGenerator {
             StreamEvent::MessageStop { stop_reason } => {
                 let finish_reason = stop_reason.to_openai_str();
 
+                // 未收到结束事件的工具调用按索引顺序收尾
+                let mut pending: Vec<(String, ToolCallState)> = self.tool_calls.drain().collect();
+                pending.sort_by_key(|(_, state)| state.index);
+                let pending: String = pending
+                    .iter()
+                    .filter_map(|(id, state)| self.finish_tool_call(id, state))
+                    .collect();
+
                 let chunk = OpenAiStreamChunk {
                     id: &self.response_id,
                     object: "chat.completion.chunk",
@@ -235,6 +291,7 @@ impl OpenAiSseGenerator {
                         delta: OpenAiDelta {
                             role: None,
                             content: None,
+                            reasoning_content: None,
                             tool_calls: None,
                         },
                         finish_reason: Some(finish_reason),
@@ -242,7 +299,7 @@ impl OpenAiSseGenerator {
                 };
 
                 let chunk_str = format!("data: {}\n\n", serde_json::to_string(&chunk).ok()?);
-                Some(format!("{chunk_str}data: [DONE]\n\n"))
+                Some(format!("{pending}{chunk_str}data: [DONE]\n\n"))
             }
 
             StreamEvent::Usage {
@@ -282,6 +339,64 @@ impl OpenAiSseGenerator {
         }
     }
 
+    /// 工具调用结束：校验参数，合并模式下生成完整的工具调用块
+    ///
+    /// 参数不是合法 JSON 时返回错误帧。
+    fn finish_tool_call(&self, id: &str, state: &ToolCallState) -> Option<String> {
+        let arguments = if state.arguments.trim().is_empty() {
+            "{}"
+        } else {
+            state.arguments.as_str()
+        };
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(arguments) {
+            tracing::warn!(
+                "[OPENAI_SSE] 工具调用 {} ({}) 参数不是合法 JSON: {}",
+                state.name,
+                id,
+                e
+            );
+            let error_obj = serde_json::json!({
+                "error": {
+                    "type": "invalid_tool_arguments",
+                    "message": format!(
+                        "Tool call '{}' ({id}) produced invalid JSON arguments: {e}",
+                        state.name
+                    ),
+                }
+            });
+            return Some(format!("data: {error_obj}\n\n"));
+        }
+        if !self.coalesce_tool_calls {
+            return None;
+        }
+
+        let chunk = OpenAiStreamChunk {
+            id: &self.response_id,
+            object: "chat.completion.chunk",
+            created: self.created,
+            model: &self.model,
+            choices: vec![OpenAiChoice {
+                index: 0,
+                delta: OpenAiDelta {
+                    role: None,
+                    content: None,
+                    reasoning_content: None,
+                    tool_calls: Some(vec![OpenAiToolCallDelta {
+                        index: state.index,
+                        id: Some(id),
+                        r#type: Some("function"),
+                        function: Some(OpenAiFunctionDelta {
+                            name: Some(state.name.as_str()),
+                            arguments: Some(arguments),
+                        }),
+                    }]),
+                },
+                finish_reason: None,
+            }],
+        };
+        Some(format!("data: {}\n\n", serde_json::to_string(&chunk).ok()?))
+    }
+
     /// 生成 [DONE] 事件
     pub fn generate_done(&self) -> String {
         "data: [DONE]\n\n".to_string()
@@ -321,6 +436,8 @@ struct OpenAiDelta<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     content: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_content: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tool_calls: Option<Vec<OpenAiToolCallDelta<'a>>>,
 }
 
@@ -362,6 +479,18 @@ mod tests {
         assert!(sse.contains("\"content\":\"Hello\""));
     }
 
+    #[test]
+    fn test_generate_thinking_delta() {
+        let mut generator = OpenAiSseGenerator::new("claude-sonnet-4-5".to_string());
+        let sse = generator
+            .generate(&StreamEvent::ThinkingDelta {
+                thinking: "先想一想".to_string(),
+            })
+            .unwrap();
+        assert!(sse.contains(r#""reasoning_content":"先想一想""#));
+        assert!(!sse.contains(r#""content""#));
+    }
+
     #[test]
     fn test_generate_tool_call() {
         let mut generator = OpenAiSseGenerator::new("gpt-4".to_string());
@@ -401,4 +530,107 @@ mod tests {
         assert!(sse.contains("\"finish_reason\":\"stop\""));
         assert!(sse.contains("[DONE]"));
     }
+
+    /// 把参数拆成碎片的工具调用事件序列
+    fn fragmented_tool_call(fragments: &[&str]) -> Vec<StreamEvent> {
+        let mut events = vec![StreamEvent::ToolUseStart {
+            id: "call_1".to_string(),
+            name: "read_file".to_string(),
+        }];
+        events.extend(fragments.iter().map(|f| StreamEvent::ToolUseInputDelta {
+            id: "call_1".to_string(),
+            partial_json: f.to_string(),
+        }));
+        events.push(StreamEvent::ToolUseStop {
+            id: "call_1".to_string(),
+        });
+        events.push(StreamEvent::MessageStop {
+            stop_reason: StopReason::ToolUse,
+        });
+        events
+    }
+
+    /// 生成全部 SSE 帧并解析 data 部分
+    fn frames(
+        generator: &mut OpenAiSseGenerator,
+        events: &[StreamEvent],
+    ) -> Vec<serde_json::Value> {
+        events
+            .iter()
+            .filter_map(|e| generator.generate(e))
+            .flat_map(|sse| {
+                sse.split("\n\n")
+                    .filter_map(|frame| frame.strip_prefix("data: "))
+                    .filter(|data| *data != "[DONE]")
+                    .map(|data| serde_json::from_str(data).unwrap())
+                    .collect::<Vec<serde_json::Value>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fragmented_arguments_reassemble() {
+        let events = fragmented_tool_call(&["{\"pa", "th\":\"/tmp", "/a.txt\",\"lim", "it\":10}"]);
+        let mut generator = OpenAiSseGenerator::new("gpt-4".to_string());
+        let frames = frames(&mut generator, &events);
+
+        // 逐片段透传，客户端拼接后得到完整参数
+        let arguments: String = frames
+            .iter()
+            .filter_map(|f| {
+                f["choices"][0]["delta"]["tool_calls"][0]["function"]["arguments"].as_str()
+            })
+            .collect();
+        let parsed: serde_json::Value = serde_json::from_str(&arguments).unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!({"path": "/tmp/a.txt", "limit": 10})
+        );
+        assert!(frames.iter().all(|f| f.get("error").is_none()));
+    }
+
+    #[test]
+    fn test_coalesced_tool_call_emitted_once() {
+        let events = fragmented_tool_call(&["{\"pa", "th\":\"/tmp", "/a.txt\"}"]);
+        let mut generator =
+            OpenAiSseGenerator::new("gpt-4".to_string()).with_coalesce_tool_calls(true);
+        let frames = frames(&mut generator, &events);
+
+        let tool_frames: Vec<&serde_json::Value> = frames
+            .iter()
+            .filter(|f| f["choices"][0]["delta"].get("tool_calls").is_some())
+            .collect();
+        assert_eq!(tool_frames.len(), 1);
+        let call = &tool_frames[0]["choices"][0]["delta"]["tool_calls"][0];
+        assert_eq!(call["index"], 0);
+        assert_eq!(call["id"], "call_1");
+        assert_eq!(call["type"], "function");
+        assert_eq!(call["function"]["name"], "read_file");
+        assert_eq!(call["function"]["arguments"], "{\"path\":\"/tmp/a.txt\"}");
+        assert_eq!(
+            frames.last().unwrap()["choices"][0]["finish_reason"],
+            "tool_calls"
+        );
+    }
+
+    #[test]
+    fn test_invalid_final_arguments_emit_error_frame() {
+        for coalesce in [false, true] {
+            let events = fragmented_tool_call(&["{\"path\":", "\"/tmp/a"]);
+            let mut generator =
+                OpenAiSseGenerator::new("gpt-4".to_string()).with_coalesce_tool_calls(coalesce);
+            let frames = frames(&mut generator, &events);
+
+            let errors: Vec<&serde_json::Value> =
+                frames.iter().filter(|f| f.get("error").is_some()).collect();
+            assert_eq!(errors.len(), 1, "coalesce={coalesce}");
+            assert_eq!(errors[0]["error"]["type"], "invalid_tool_arguments");
+            if coalesce {
+                // 合并模式下不发送截断的工具调用
+                assert!(frames
+                    .iter()
+                    .all(|f| f["choices"][0]["delta"].get("tool_calls").is_none()));
+            }
+        }
+    }
 }
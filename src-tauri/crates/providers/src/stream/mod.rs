@@ -4,6 +4,7 @@
 //! - 事件类型定义 (events)
 //! - 后端流格式解析 (parsers)
 //! - 前端流格式生成 (generators)
+//! - 思考内容拆分 (thinking)
 //!
 //! # 架构设计
 //!
@@ -23,14 +24,17 @@
 //! - `generators`: 前端流格式生成器
 //!   - `openai_sse`: OpenAI SSE 格式生成器
 //!   - `anthropic_sse`: Anthropic SSE 格式生成器
+//! - `thinking`: 从文本增量中拆分 `<thinking>` 思考内容
 
 pub mod events;
 pub mod generators;
 pub mod parsers;
 pub mod pipeline;
+pub mod thinking;
 
 // 重新导出核心类型
 pub use events::{ContentBlockType, StopReason, StreamContext, StreamEvent};
 pub use generators::{AnthropicSseGenerator, OpenAiSseGenerator};
 pub use parsers::{AwsEventStreamParser, ParserState};
 pub use pipeline::{create_sse_stream, BackendType, FrontendType, PipelineConfig, StreamPipeline};
+pub use thinking::{ThinkingSegment, ThinkingSplitter};
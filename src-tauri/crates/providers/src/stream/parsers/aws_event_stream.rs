@@ -6,7 +6,7 @@
 //! # 协议格式
 //!
 //! CodeWhisperer 使用 AWS Event Stream 二进制格式，每个事件包含：
-//! - `{"content": "文本内容"}` - 文本增量
+//! - `{"content": "文本内容"}` - 文本增量（启用思考时，开头的 `<thinking>...</thinking>` 拆分为思考内容）
 //! - `{"toolUseId": "id", "name": "tool_name"}` - 工具调用开始
 //! - `{"toolUseId": "id", "input": "部分JSON"}` - 工具参数增量
 //! - `{"toolUseId": "id", "stop": true}` - 工具调用结束
@@ -15,6 +15,7 @@
 //! - `{"contextUsagePercentage": 54.36}` - 上下文使用百分比
 
 use crate::stream::events::{ContentBlockType, StopReason, StreamContext, StreamEvent};
+use crate::stream::thinking::{ThinkingSegment, ThinkingSplitter};
 use std::collections::HashMap;
 
 /// 解析器状态
@@ -72,6 +73,12 @@ pub struct AwsEventStreamParser {
     in_text_block: bool,
     /// 当前文本块索引
     text_block_index: Option<u32>,
+    /// 思考内容拆分器
+    thinking_splitter: ThinkingSplitter,
+    /// 当前思考块索引
+    thinking_block_index: Option<u32>,
+    /// 是否拆分思考内容（仅请求启用思考时）
+    extract_thinking: bool,
 }
 
 impl Default for AwsEventStreamParser {
@@ -97,6 +104,9 @@ impl AwsEventStreamParser {
             message_stopped: false,
             in_text_block: false,
             text_block_index: None,
+            thinking_splitter: ThinkingSplitter::new(),
+            thinking_block_index: None,
+            extract_thinking: false,
         }
     }
 
@@ -107,6 +117,14 @@ impl AwsEventStreamParser {
         parser
     }
 
+    /// 设置是否拆分开头的 `<thinking>` 思考内容
+    ///
+    /// 只有请求启用思考时才应开启，否则模型原样输出的标签会被误当作思考内容。
+    pub fn with_thinking(mut self, enabled: bool) -> Self {
+        self.extract_thinking = enabled;
+        self
+    }
+
     /// 获取当前状态
     pub fn state(&self) -> &ParserState {
         &self.state
@@ -133,6 +151,8 @@ impl AwsEventStreamParser {
         self.message_stopped = false;
         self.in_text_block = false;
         self.text_block_index = None;
+        self.thinking_splitter = ThinkingSplitter::new();
+        self.thinking_block_index = None;
     }
 
     /// 处理接收到的字节
@@ -195,6 +215,7 @@ impl AwsEventStreamParser {
 
         // 尝试解析缓冲区中剩余的数据
         events.extend(self.parse_buffer());
+        self.flush_text(&mut events);
 
         // 完成所有未完成的工具调用
         let has_tool_calls = !self.tool_accumulators.is_empty();
@@ -207,7 +228,10 @@ impl AwsEventStreamParser {
             }
         }
 
-        // 关闭文本块（如果有）
+        // 关闭思考块和文本块（如果有）
+        if let Some(index) = self.thinking_block_index.take() {
+            events.push(StreamEvent::ContentBlockStop { index });
+        }
         if let Some(index) = self.text_block_index.take() {
             events.push(StreamEvent::ContentBlockStop { index });
         }
@@ -404,29 +428,19 @@ impl AwsEventStreamParser {
         if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
             // 跳过 followupPrompt
             if value.get("followupPrompt").is_none() {
-                // 如果还没有文本块，创建一个
-                if !self.in_text_block {
-                    self.in_text_block = true;
-                    let index = self.context.next_block_index();
-                    self.text_block_index = Some(index);
-                    events.push(StreamEvent::ContentBlockStart {
-                        index,
-                        block_type: ContentBlockType::Text,
-                    });
+                if self.extract_thinking {
+                    for segment in self.thinking_splitter.push(content) {
+                        self.push_segment(segment, &mut events);
+                    }
+                } else {
+                    self.push_segment(ThinkingSegment::Text(content.to_string()), &mut events);
                 }
-
-                events.push(StreamEvent::TextDelta {
-                    text: content.to_string(),
-                });
             }
         }
         // 处理 tool use 事件 (包含 toolUseId)
         else if let Some(tool_use_id) = value.get("toolUseId").and_then(|v| v.as_str()) {
             // 如果有文本块，先关闭它
-            if let Some(index) = self.text_block_index.take() {
-                self.in_text_block = false;
-                events.push(StreamEvent::ContentBlockStop { index });
-            }
+            self.close_text_blocks(&mut events);
 
             let name = value
                 .get("name")
@@ -525,10 +539,7 @@ impl AwsEventStreamParser {
             }
 
             // 关闭文本块（如果有）
-            if let Some(index) = self.text_block_index.take() {
-                self.in_text_block = false;
-                events.push(StreamEvent::ContentBlockStop { index });
-            }
+            self.close_text_blocks(&mut events);
 
             // 确定停止原因
             let stop_reason = if self.context.has_active_tool_calls() {
@@ -558,6 +569,59 @@ impl AwsEventStreamParser {
 
         Ok(events)
     }
+
+    /// 输出一个拆分后的文本片段，按需开启思考块或文本块
+    fn push_segment(&mut self, segment: ThinkingSegment, events: &mut Vec<StreamEvent>) {
+        match segment {
+            ThinkingSegment::Thinking(thinking) => {
+                if self.thinking_block_index.is_none() {
+                    let index = self.context.next_block_index();
+                    self.thinking_block_index = Some(index);
+                    events.push(StreamEvent::ContentBlockStart {
+                        index,
+                        block_type: ContentBlockType::Thinking,
+                    });
+                }
+                events.push(StreamEvent::ThinkingDelta { thinking });
+            }
+            ThinkingSegment::Text(text) => {
+                // 正文开始意味着思考结束
+                if let Some(index) = self.thinking_block_index.take() {
+                    events.push(StreamEvent::ContentBlockStop { index });
+                }
+                // 如果还没有文本块，创建一个
+                if !self.in_text_block {
+                    self.in_text_block = true;
+                    let index = self.context.next_block_index();
+                    self.text_block_index = Some(index);
+                    events.push(StreamEvent::ContentBlockStart {
+                        index,
+                        block_type: ContentBlockType::Text,
+                    });
+                }
+                events.push(StreamEvent::TextDelta { text });
+            }
+        }
+    }
+
+    /// 输出拆分器中暂存的文本
+    fn flush_text(&mut self, events: &mut Vec<StreamEvent>) {
+        for segment in self.thinking_splitter.flush() {
+            self.push_segment(segment, events);
+        }
+    }
+
+    /// 输出暂存文本并关闭思考块、文本块
+    fn close_text_blocks(&mut self, events: &mut Vec<StreamEvent>) {
+        self.flush_text(events);
+        if let Some(index) = self.thinking_block_index.take() {
+            events.push(StreamEvent::ContentBlockStop { index });
+        }
+        if let Some(index) = self.text_block_index.take() {
+            self.in_text_block = false;
+            events.push(StreamEvent::ContentBlockStop { index });
+        }
+    }
 }
 
 #[cfg(test)]
@@ -631,4 +695,62 @@ mod tests {
         let events2 = parser.process(br#"tent":"Hello"}"#);
         assert!(!events2.is_empty()); // 现在有事件了
     }
+
+    #[test]
+    fn test_parse_thinking_content() {
+        let mut parser = AwsEventStreamParser::new().with_thinking(true);
+        let mut events = parser.process(br#"{"content":"<thinking>2+2"}"#);
+        events.extend(parser.process(r#"{"content":" 等于 4</thinking>\n\n"}"#.as_bytes()));
+        events.extend(parser.process(r#"{"content":"答案是 4"}"#.as_bytes()));
+        events.extend(parser.process(br#"{"stop":true}"#));
+
+        let blocks: Vec<&StreamEvent> = events
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e,
+                    StreamEvent::ContentBlockStart { .. }
+                        | StreamEvent::ContentBlockStop { .. }
+                        | StreamEvent::ThinkingDelta { .. }
+                        | StreamEvent::TextDelta { .. }
+                )
+            })
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![
+                &StreamEvent::ContentBlockStart {
+                    index: 0,
+                    block_type: ContentBlockType::Thinking,
+                },
+                &StreamEvent::ThinkingDelta {
+                    thinking: "2+2".to_string(),
+                },
+                &StreamEvent::ThinkingDelta {
+                    thinking: " 等于 4".to_string(),
+                },
+                &StreamEvent::ContentBlockStop { index: 0 },
+                &StreamEvent::ContentBlockStart {
+                    index: 1,
+                    block_type: ContentBlockType::Text,
+                },
+                &StreamEvent::TextDelta {
+                    text: "答案是 4".to_string(),
+                },
+                &StreamEvent::ContentBlockStop { index: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thinking_tags_kept_when_disabled() {
+        let mut parser = AwsEventStreamParser::new();
+        let events = parser.process(br#"{"content":"<thinking>2+2</thinking>"}"#);
+        assert!(events
+            .iter()
+            .all(|e| !matches!(e, StreamEvent::ThinkingDelta { .. })));
+        assert!(events.contains(&StreamEvent::TextDelta {
+            text: "<thinking>2+2</thinking>".to_string(),
+        }));
+    }
 }
@@ -51,6 +51,10 @@ pub struct PipelineConfig {
     pub model: String,
     /// 消息 ID（可选）
     pub message_id: Option<String>,
+    /// 是否合并 OpenAI 工具调用参数增量（仅 OpenAI 前端）
+    pub coalesce_tool_calls: bool,
+    /// 是否拆分 Kiro 响应开头的思考内容（仅请求启用思考时）
+    pub extract_thinking: bool,
 }
 
 impl PipelineConfig {
@@ -61,6 +65,8 @@ impl PipelineConfig {
             frontend: FrontendType::Anthropic,
             model,
             message_id: None,
+            coalesce_tool_calls: false,
+            extract_thinking: false,
         }
     }
 
@@ -71,6 +77,8 @@ impl PipelineConfig {
             frontend: FrontendType::OpenAi,
             model,
             message_id: None,
+            coalesce_tool_calls: false,
+            extract_thinking: false,
         }
     }
 
@@ -79,6 +87,18 @@ impl PipelineConfig {
         self.message_id = Some(id);
         self
     }
+
+    /// 设置是否合并 OpenAI 工具调用参数增量
+    pub fn with_coalesce_tool_calls(mut self, enabled: bool) -> Self {
+        self.coalesce_tool_calls = enabled;
+        self
+    }
+
+    /// 设置是否拆分思考内容
+    pub fn with_extract_thinking(mut self, enabled: bool) -> Self {
+        self.extract_thinking = enabled;
+        self
+    }
 }
 
 /// SSE 生成器封装
@@ -112,7 +132,10 @@ impl StreamPipeline {
     /// 创建新的管道
     pub fn new(config: PipelineConfig) -> Self {
         let aws_parser = match config.backend {
-            BackendType::Kiro => Some(AwsEventStreamParser::with_model(config.model.clone())),
+            BackendType::Kiro => Some(
+                AwsEventStreamParser::with_model(config.model.clone())
+                    .with_thinking(config.extract_thinking),
+            ),
             _ => None,
         };
 
@@ -128,14 +151,12 @@ impl StreamPipeline {
                 }
             }
             FrontendType::OpenAi => {
-                if let Some(id) = &config.message_id {
-                    SseGenerator::OpenAi(OpenAiSseGenerator::with_id(
-                        id.clone(),
-                        config.model.clone(),
-                    ))
+                let generator = if let Some(id) = &config.message_id {
+                    OpenAiSseGenerator::with_id(id.clone(), config.model.clone())
                 } else {
-                    SseGenerator::OpenAi(OpenAiSseGenerator::new(config.model.clone()))
-                }
+                    OpenAiSseGenerator::new(config.model.clone())
+                };
+                SseGenerator::OpenAi(generator.with_coalesce_tool_calls(config.coalesce_tool_calls))
             }
         };
 
@@ -206,9 +227,10 @@ impl StreamPipeline {
             FrontendType::Anthropic => {
                 SseGenerator::Anthropic(AnthropicSseGenerator::new(self.config.model.clone()))
             }
-            FrontendType::OpenAi => {
-                SseGenerator::OpenAi(OpenAiSseGenerator::new(self.config.model.clone()))
-            }
+            FrontendType::OpenAi => SseGenerator::OpenAi(
+                OpenAiSseGenerator::new(self.config.model.clone())
+                    .with_coalesce_tool_calls(self.config.coalesce_tool_calls),
+            ),
         };
     }
 }
@@ -0,0 +1,192 @@
+//! 思考内容拆分
+//!
+//! Kiro/CodeWhisperer 开启思考模式后，思考内容以 `<thinking>...</thinking>`
+//! 的形式出现在文本开头。本模块把流式文本增量拆分为思考片段与正文片段，
+//! 标签可能被拆散在多个 chunk 中，未能确定归属的尾部会暂存到下一个 chunk。
+
+/// 开始标签
+const OPEN_TAG: &str = "<thinking>";
+/// 结束标签
+const CLOSE_TAG: &str = "</thinking>";
+
+/// 拆分后的文本片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThinkingSegment {
+    /// 思考内容
+    Thinking(String),
+    /// 正文内容
+    Text(String),
+}
+
+/// 拆分状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SplitState {
+    /// 尚未确定文本开头是否为思考标签
+    #[default]
+    Start,
+    /// 位于思考标签内
+    Thinking,
+    /// 正文（思考结束后会跳过紧随的换行）
+    Text { skip_newlines: bool },
+}
+
+/// 流式思考内容拆分器
+#[derive(Debug, Default)]
+pub struct ThinkingSplitter {
+    state: SplitState,
+    pending: String,
+}
+
+impl ThinkingSplitter {
+    /// 创建拆分器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一个文本增量
+    pub fn push(&mut self, chunk: &str) -> Vec<ThinkingSegment> {
+        self.pending.push_str(chunk);
+        let mut segments = Vec::new();
+
+        loop {
+            match self.state {
+                SplitState::Start => {
+                    let trimmed = self.pending.trim_start();
+                    if let Some(rest) = trimmed.strip_prefix(OPEN_TAG) {
+                        self.pending = rest.to_string();
+                        self.state = SplitState::Thinking;
+                    } else if OPEN_TAG.starts_with(trimmed) {
+                        // 可能是被拆散的开始标签，等待更多数据
+                        break;
+                    } else {
+                        self.state = SplitState::Text {
+                            skip_newlines: false,
+                        };
+                    }
+                }
+                SplitState::Thinking => {
+                    if let Some(pos) = self.pending.find(CLOSE_TAG) {
+                        let thinking = self.pending[..pos].to_string();
+                        self.pending.drain(..pos + CLOSE_TAG.len());
+                        if !thinking.is_empty() {
+                            segments.push(ThinkingSegment::Thinking(thinking));
+                        }
+                        self.state = SplitState::Text {
+                            skip_newlines: true,
+                        };
+                        continue;
+                    }
+                    // 保留可能是结束标签前缀的尾部
+                    let emit_len = self.pending.len() - partial_tag_suffix(&self.pending);
+                    if emit_len > 0 {
+                        let thinking: String = self.pending.drain(..emit_len).collect();
+                        segments.push(ThinkingSegment::Thinking(thinking));
+                    }
+                    break;
+                }
+                SplitState::Text { skip_newlines } => {
+                    if skip_newlines {
+                        let kept = self.pending.trim_start_matches('\n').len();
+                        self.pending.drain(..self.pending.len() - kept);
+                        if self.pending.is_empty() {
+                            break;
+                        }
+                        self.state = SplitState::Text {
+                            skip_newlines: false,
+                        };
+                    }
+                    if !self.pending.is_empty() {
+                        segments.push(ThinkingSegment::Text(std::mem::take(&mut self.pending)));
+                    }
+                    break;
+                }
+            }
+        }
+
+        segments
+    }
+
+    /// 输出暂存的内容
+    ///
+    /// 在工具调用或流结束前调用；未闭合的思考标签内容按思考内容输出。
+    pub fn flush(&mut self) -> Vec<ThinkingSegment> {
+        let pending = std::mem::take(&mut self.pending);
+        match self.state {
+            SplitState::Thinking if !pending.is_empty() => {
+                vec![ThinkingSegment::Thinking(pending)]
+            }
+            SplitState::Thinking => Vec::new(),
+            SplitState::Start | SplitState::Text { .. } => {
+                self.state = SplitState::Text {
+                    skip_newlines: false,
+                };
+                if pending.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![ThinkingSegment::Text(pending)]
+                }
+            }
+        }
+    }
+}
+
+/// 文本末尾与结束标签前缀重合的长度
+fn partial_tag_suffix(text: &str) -> usize {
+    (1..CLOSE_TAG.len())
+        .rev()
+        .find(|&len| text.ends_with(&CLOSE_TAG[..len]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(chunks: &[&str]) -> Vec<ThinkingSegment> {
+        let mut splitter = ThinkingSplitter::new();
+        let mut segments: Vec<ThinkingSegment> = Vec::new();
+        for chunk in chunks {
+            segments.extend(splitter.push(chunk));
+        }
+        segments.extend(splitter.flush());
+        // 合并相邻的同类片段，便于断言
+        segments.into_iter().fold(Vec::new(), |mut acc, seg| {
+            match (acc.last_mut(), seg) {
+                (Some(ThinkingSegment::Thinking(a)), ThinkingSegment::Thinking(b))
+                | (Some(ThinkingSegment::Text(a)), ThinkingSegment::Text(b)) => a.push_str(&b),
+                (_, seg) => acc.push(seg),
+            }
+            acc
+        })
+    }
+
+    #[test]
+    fn test_split_thinking_across_chunks() {
+        assert_eq!(
+            split(&["<thin", "king>先想", "一想</thi", "nking>\n\n答案", "是 4"]),
+            vec![
+                ThinkingSegment::Thinking("先想一想".to_string()),
+                ThinkingSegment::Text("答案是 4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plain_text_passes_through() {
+        assert_eq!(
+            split(&["Hello", ", <thinking> is a tag"]),
+            vec![ThinkingSegment::Text(
+                "Hello, <thinking> is a tag".to_string()
+            )]
+        );
+        assert_eq!(split(&["<"]), vec![ThinkingSegment::Text("<".to_string())]);
+    }
+
+    #[test]
+    fn test_unclosed_thinking_flushed_as_thinking() {
+        assert_eq!(
+            split(&["<thinking>还没想完</thin"]),
+            vec![ThinkingSegment::Thinking("还没想完</thin".to_string())]
+        );
+    }
+}
@@ -62,6 +62,8 @@ struct ToolCallAccumulator {
     started: bool,
     /// 内容块索引（用于 Anthropic 格式）
     index: u32,
+    /// 是否已完成（已校验参数并发送合并块）
+    finished: bool,
 }
 
 /// 部分 JSON 累积器
@@ -164,6 +166,8 @@ pub struct StreamConverter {
     message_started: bool,
     /// 累积的内容（用于重建完整响应）
     accumulated_content: String,
+    /// 是否合并工具调用参数增量（OpenAI 格式）
+    coalesce_tool_calls: bool,
 }
 
 impl StreamConverter {
@@ -186,6 +190,7 @@ impl StreamConverter {
             next_content_block_index: 0,
             message_started: false,
             accumulated_content: String::new(),
+            coalesce_tool_calls: false,
         }
     }
 
@@ -196,6 +201,15 @@ impl StreamConverter {
         converter
     }
 
+    /// 设置是否合并工具调用参数增量
+    ///
+    /// 开启后输出 OpenAI 格式时，参数增量只在内部累积，工具调用结束时
+    /// 校验完整 JSON 并一次性发送包含 `id`、`name`、`arguments` 的完整块。
+    pub fn with_coalesce_tool_calls(mut self, enabled: bool) -> Self {
+        self.coalesce_tool_calls = enabled;
+        self
+    }
+
     /// 获取当前状态
     pub fn state(&self) -> &ConverterState {
         &self.state
@@ -342,6 +356,7 @@ impl StreamConverter {
                         input: String::new(),
                         started: true,
                         index,
+                        finished: false,
                     },
                 );
 
@@ -409,10 +424,13 @@ impl StreamConverter {
                         input: String::new(),
                         started: true,
                         index,
+                        finished: false,
                     },
                 );
-                // 发送工具调用开始 chunk
-                sse_events.push(self.create_openai_tool_call_chunk(index, id, name, "", true));
+                if !self.coalesce_tool_calls {
+                    // 发送工具调用开始 chunk
+                    sse_events.push(self.create_openai_tool_call_chunk(index, id, name, "", true));
+                }
             }
             AwsEvent::ToolUseInput { id, input } => {
                 let (index, tool_id, tool_name) =
@@ -422,6 +440,10 @@ impl StreamConverter {
                     } else {
                         return sse_events;
                     };
+                if self.coalesce_tool_calls {
+                    // 合并模式下等到工具调用结束再发送
+                    return sse_events;
+                }
                 // 发送工具调用参数增量
                 sse_events.push(
                     self.create_openai_tool_call_chunk(index, &tool_id, &tool_name, input, false),
@@ -429,7 +451,9 @@ impl StreamConverter {
             }
             AwsEvent::ToolUseStop { id } => {
                 // OpenAI 格式不需要显式的工具调用结束事件
-                self.tool_accumulators.remove(id);
+                if let Some(acc) = self.tool_accumulators.remove(id) {
+                    sse_events.extend(self.finish_openai_tool_call(&acc));
+                }
             }
             AwsEvent::Stop => {
                 // 结束事件在 finish() 中处理
@@ -508,6 +532,10 @@ impl StreamConverter {
                                                 acc.input.push_str(partial_json);
                                                 (acc.index, acc.id.clone(), acc.name.clone())
                                             });
+                                        if self.coalesce_tool_calls {
+                                            // 合并模式下等到内容块结束再发送
+                                            continue;
+                                        }
                                         if let Some((idx, tool_id, tool_name)) = tool_info {
                                             sse_events.push(self.create_openai_tool_call_chunk(
                                                 idx,
@@ -546,14 +574,32 @@ impl StreamConverter {
                                                 input: String::new(),
                                                 started: true,
                                                 index,
+                                                finished: false,
                                             },
                                         );
-                                        sse_events.push(self.create_openai_tool_call_chunk(
-                                            index, id, name, "", true,
-                                        ));
+                                        if !self.coalesce_tool_calls {
+                                            sse_events.push(self.create_openai_tool_call_chunk(
+                                                index, id, name, "", true,
+                                            ));
+                                        }
                                     }
                                 }
                             }
+                            "content_block_stop" => {
+                                let index =
+                                    event.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as u32;
+                                let finished = self
+                                    .tool_accumulators
+                                    .values_mut()
+                                    .find(|a| a.index == index && !a.finished)
+                                    .map(|acc| {
+                                        acc.finished = true;
+                                        acc.clone()
+                                    });
+                                if let Some(acc) = finished {
+                                    sse_events.extend(self.finish_openai_tool_call(&acc));
+                                }
+                            }
                             "message_stop" => {
                                 sse_events.push(self.create_openai_finish_chunk("stop"));
                                 sse_events.push("data: [DONE]\n\n".to_string());
@@ -588,15 +634,29 @@ impl StreamConverter {
                 events
             }
             StreamFormat::OpenAiSse => {
+                // 上游未发送结束事件的工具调用在这里收尾
+                let mut pending: Vec<ToolCallAccumulator> = self
+                    .tool_accumulators
+                    .values_mut()
+                    .filter(|a| !a.finished)
+                    .map(|acc| {
+                        acc.finished = true;
+                        acc.clone()
+                    })
+                    .collect();
+                pending.sort_by_key(|a| a.index);
+                let mut events: Vec<String> = pending
+                    .iter()
+                    .flat_map(|acc| self.finish_openai_tool_call(acc))
+                    .collect();
                 let finish_reason = if self.tool_accumulators.is_empty() {
                     "stop"
                 } else {
                     "tool_calls"
                 };
-                vec![
-                    self.create_openai_finish_chunk(finish_reason),
-                    "data: [DONE]\n\n".to_string(),
-                ]
+                events.push(self.create_openai_finish_chunk(finish_reason));
+                events.push("data: [DONE]\n\n".to_string());
+                events
             }
             StreamFormat::AwsEventStream => {
                 vec![]
@@ -784,6 +844,39 @@ impl StreamConverter {
         format!("data: {chunk}\n\n")
     }
 
+    /// 工具调用结束：校验参数，合并模式下生成完整的工具调用块
+    ///
+    /// 参数不是合法 JSON 时返回错误帧。
+    fn finish_openai_tool_call(&self, acc: &ToolCallAccumulator) -> Option<String> {
+        let arguments = if acc.input.trim().is_empty() {
+            "{}"
+        } else {
+            acc.input.as_str()
+        };
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(arguments) {
+            tracing::warn!(
+                "[STREAM_CONVERTER] 工具调用 {} ({}) 参数不是合法 JSON: {}",
+                acc.name,
+                acc.id,
+                e
+            );
+            let error_obj = serde_json::json!({
+                "error": {
+                    "type": "invalid_tool_arguments",
+                    "message": format!(
+                        "Tool call '{}' ({}) produced invalid JSON arguments: {e}",
+                        acc.name, acc.id
+                    ),
+                }
+            });
+            return Some(format!("data: {error_obj}\n\n"));
+        }
+        if !self.coalesce_tool_calls {
+            return None;
+        }
+        Some(self.create_openai_tool_call_chunk(acc.index, &acc.id, &acc.name, arguments, true))
+    }
+
     fn create_openai_finish_chunk(&self, finish_reason: &str) -> String {
         let chunk = serde_json::json!({
             "id": self.response_id,
@@ -1026,6 +1119,68 @@ mod tests {
         assert!(has_tool_call);
     }
 
+    #[test]
+    fn test_aws_to_openai_coalesced_tool_call() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AwsEventStream,
+            StreamFormat::OpenAiSse,
+            "test-model",
+        )
+        .with_coalesce_tool_calls(true);
+
+        let mut events = converter.convert(b"{\"toolUseId\":\"tool_1\",\"name\":\"read_file\"}");
+        for part in ["{\\\"pa", "th\\\":\\\"/tmp", "/a.txt\\\"}"] {
+            let chunk = format!("{{\"toolUseId\":\"tool_1\",\"input\":\"{part}\"}}");
+            events.extend(converter.convert(chunk.as_bytes()));
+        }
+        events.extend(converter.convert(b"{\"toolUseId\":\"tool_1\",\"stop\":true}"));
+
+        let tool_events: Vec<_> = events
+            .iter()
+            .filter(|e| e.contains("\"tool_calls\":["))
+            .collect();
+        assert_eq!(tool_events.len(), 1);
+        let chunk: serde_json::Value =
+            serde_json::from_str(tool_events[0].trim().trim_start_matches("data: ")).unwrap();
+        let tool_call = &chunk["choices"][0]["delta"]["tool_calls"][0];
+        assert_eq!(tool_call["id"], "tool_1");
+        assert_eq!(tool_call["function"]["name"], "read_file");
+        assert_eq!(
+            tool_call["function"]["arguments"],
+            "{\"path\":\"/tmp/a.txt\"}"
+        );
+    }
+
+    #[test]
+    fn test_anthropic_to_openai_invalid_tool_arguments() {
+        for coalesce in [false, true] {
+            let mut converter = StreamConverter::with_model(
+                StreamFormat::AnthropicSse,
+                StreamFormat::OpenAiSse,
+                "test-model",
+            )
+            .with_coalesce_tool_calls(coalesce);
+
+            let data = concat!(
+                "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"tool_use\",\"id\":\"tool_1\",\"name\":\"read_file\"}}\n\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"path\\\":\"}}\n\n",
+                "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            );
+            let mut events = converter.convert(data.as_bytes());
+            events.extend(converter.finish());
+
+            let errors: Vec<_> = events
+                .iter()
+                .filter(|e| e.contains("invalid_tool_arguments"))
+                .collect();
+            assert_eq!(errors.len(), 1, "coalesce={coalesce}");
+            if coalesce {
+                // 合并模式下不发送截断的工具调用
+                assert!(!events.iter().any(|e| e.contains("\"tool_calls\":[")));
+            }
+        }
+    }
+
     #[test]
     fn test_aws_to_anthropic_content() {
         let mut converter = StreamConverter::with_model(
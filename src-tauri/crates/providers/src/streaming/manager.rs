@@ -64,6 +64,12 @@ pub struct StreamConfig {
     /// 两个 chunk 之间的最大等待时间。
     #[serde(default = "default_chunk_timeout_ms")]
     pub chunk_timeout_ms: u64,
+
+    /// 是否合并工具调用参数增量
+    ///
+    /// 输出 OpenAI 格式时，工具调用结束后一次性发送完整参数。
+    #[serde(default)]
+    pub coalesce_tool_calls: bool,
 }
 
 fn default_buffer_size() -> usize {
@@ -89,6 +95,7 @@ impl Default for StreamConfig {
             timeout_ms: default_timeout_ms(),
             throttle_ms: default_throttle_ms(),
             chunk_timeout_ms: default_chunk_timeout_ms(),
+            coalesce_tool_calls: false,
         }
     }
 }
@@ -123,6 +130,12 @@ impl StreamConfig {
         self
     }
 
+    /// 设置是否合并工具调用参数增量
+    pub fn with_coalesce_tool_calls(mut self, enabled: bool) -> Self {
+        self.coalesce_tool_calls = enabled;
+        self
+    }
+
     /// 获取超时 Duration
     pub fn timeout_duration(&self) -> Duration {
         Duration::from_millis(self.timeout_ms)
@@ -419,7 +432,8 @@ impl ManagedStream {
             context.source_format,
             context.target_format,
             &context.model,
-        );
+        )
+        .with_coalesce_tool_calls(config.coalesce_tool_calls);
 
         Self {
             context,
@@ -4,6 +4,7 @@
 //! 无需经过 OpenAI 中间格式，减少转换开销。
 
 use crate::translator::kiro::openai::request::{get_model_map, DEFAULT_MODEL};
+use crate::translator::kiro::thinking_prompt;
 use crate::translator::traits::{RequestTranslator, TranslateError};
 use proxycast_core::models::anthropic::*;
 use proxycast_core::models::codewhisperer::*;
@@ -83,6 +84,11 @@ pub fn convert_anthropic_to_codewhisperer(
     // 提取 system prompt
     let mut system_prompt = extract_system_text(&request.system);
 
+    // 处理 thinking: CodeWhisperer 通过 system prompt 标签开启思考模式
+    if let Some(budget) = request.thinking_budget() {
+        system_prompt = format!("{}\n{system_prompt}", thinking_prompt(budget));
+    }
+
     // 处理 tool_choice: required - CodeWhisperer 不支持此参数，通过 prompt 注入强制
     if is_tool_choice_required(&request.tool_choice) && request.tools.is_some() {
         let tool_instruction = "\n\n[CRITICAL INSTRUCTION] You MUST use one of the provided tools to respond. Do NOT respond with plain text. Call a tool function immediately.";
@@ -250,24 +256,13 @@ pub fn convert_anthropic_to_codewhisperer(
 }
 
 /// 提取 system prompt 文本
-fn extract_system_text(system: &Option<serde_json::Value>) -> String {
-    match system {
-        Some(serde_json::Value::String(s)) => s.clone(),
-        Some(serde_json::Value::Array(arr)) => arr
-            .iter()
-            .filter_map(|item| {
-                if item.get("type") == Some(&serde_json::Value::String("text".to_string())) {
-                    item.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| s.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
-    }
+///
+/// CodeWhisperer 只接受纯文本，数组形式按换行拼接（`cache_control` 无对应字段，丢弃）
+fn extract_system_text(system: &Option<AnthropicSystemPrompt>) -> String {
+    system
+        .as_ref()
+        .map(AnthropicSystemPrompt::text)
+        .unwrap_or_default()
 }
 
 /// 预处理 Anthropic 消息
@@ -282,6 +277,8 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
     // 合并连续的 user 消息中的 tool_results
     let mut merged: Vec<ProcessedMessage> = Vec::new();
     let mut pending_tool_results: Vec<CWToolResult> = Vec::new();
+    // tool_result 中的图片随 tool_results 一起合并
+    let mut pending_images: Vec<CWImage> = Vec::new();
 
     for msg in result {
         if msg.role == "user" {
@@ -289,6 +286,7 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
                 pending_tool_results.extend(tr.clone());
             }
             if !msg.content.is_empty() || msg.tool_results.is_none() {
+                pending_images.extend(msg.images.unwrap_or_default());
                 // 去重 tool_results
                 let mut seen_ids = HashSet::new();
                 pending_tool_results.retain(|tr| seen_ids.insert(tr.tool_use_id.clone()));
@@ -306,9 +304,11 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
                     } else {
                         Some(pending_tool_results.clone())
                     },
-                    images: msg.images, // 保留图片
+                    images: take_images(&mut pending_images), // 保留图片
                 });
                 pending_tool_results.clear();
+            } else {
+                pending_images.extend(msg.images.unwrap_or_default());
             }
         } else {
             // 如果有待处理的 tool_results，先创建 user 消息
@@ -321,7 +321,7 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
                     content: "Tool results provided.".to_string(),
                     tool_uses: None,
                     tool_results: Some(pending_tool_results.clone()),
-                    images: None,
+                    images: take_images(&mut pending_images),
                 });
                 pending_tool_results.clear();
             }
@@ -339,13 +339,46 @@ fn preprocess_anthropic_messages(messages: &[AnthropicMessage]) -> Vec<Processed
             content: "Tool results provided.".to_string(),
             tool_uses: None,
             tool_results: Some(pending_tool_results),
-            images: None,
+            images: take_images(&mut pending_images),
         });
     }
 
     merged
 }
 
+/// 取出待合并的图片，为空时返回 None
+fn take_images(images: &mut Vec<CWImage>) -> Option<Vec<CWImage>> {
+    if images.is_empty() {
+        None
+    } else {
+        Some(std::mem::take(images))
+    }
+}
+
+/// 将 Anthropic base64 图片来源转换为 CodeWhisperer 图片，其他来源返回 None
+fn convert_image_source(source: &ImageSource) -> Option<CWImage> {
+    if source.source_type != "base64" || source.data.is_empty() {
+        return None;
+    }
+    let media_type = if source.media_type.is_empty() {
+        "image/jpeg"
+    } else {
+        source.media_type.as_str()
+    };
+    // 从 media_type 提取格式 (image/jpeg -> jpeg)
+    let format = media_type.split('/').nth(1).unwrap_or("jpeg").to_string();
+    tracing::debug!(
+        "[KIRO_TRANSLATE] Converted image: media_type={}",
+        media_type
+    );
+    Some(CWImage {
+        format,
+        source: CWImageSource {
+            bytes: source.data.clone(),
+        },
+    })
+}
+
 /// 转换单条 Anthropic 消息
 fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
     let mut result: Vec<ProcessedMessage> = Vec::new();
@@ -365,6 +398,7 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
             let mut tool_uses: Vec<CWToolUse> = Vec::new();
             let mut tool_results: Vec<CWToolResult> = Vec::new();
             let mut images: Vec<CWImage> = Vec::new();
+            let mut tool_result_images: Vec<CWImage> = Vec::new();
 
             for part in parts {
                 let part_type = part.get("type").and_then(|t| t.as_str()).unwrap_or("");
@@ -378,34 +412,12 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
                     "image" => {
                         // 处理 Anthropic 格式的图片
                         // { "type": "image", "source": { "type": "base64", "media_type": "image/jpeg", "data": "..." } }
-                        if let Some(source) = part.get("source") {
-                            let source_type =
-                                source.get("type").and_then(|t| t.as_str()).unwrap_or("");
-                            if source_type == "base64" {
-                                let media_type = source
-                                    .get("media_type")
-                                    .and_then(|m| m.as_str())
-                                    .unwrap_or("image/jpeg");
-                                let data =
-                                    source.get("data").and_then(|d| d.as_str()).unwrap_or("");
-
-                                if !data.is_empty() {
-                                    // 从 media_type 提取格式 (image/jpeg -> jpeg)
-                                    let format =
-                                        media_type.split('/').nth(1).unwrap_or("jpeg").to_string();
-
-                                    images.push(CWImage {
-                                        format,
-                                        source: CWImageSource {
-                                            bytes: data.to_string(),
-                                        },
-                                    });
-                                    tracing::debug!(
-                                        "[KIRO_TRANSLATE] Converted image: media_type={}",
-                                        media_type
-                                    );
-                                }
-                            }
+                        if let Some(image) = part
+                            .get("source")
+                            .and_then(|s| serde_json::from_value::<ImageSource>(s.clone()).ok())
+                            .and_then(|s| convert_image_source(&s))
+                        {
+                            images.push(image);
                         }
                     }
                     "tool_use" => {
@@ -428,7 +440,9 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
                             .get("tool_use_id")
                             .and_then(|i| i.as_str())
                             .unwrap_or("");
-                        let content_text = extract_tool_result_content(part.get("content"));
+                        let content = ToolResultContent::from_value(part.get("content"));
+                        tool_result_images
+                            .extend(content.images.iter().filter_map(convert_image_source));
                         let is_error = part
                             .get("is_error")
                             .and_then(|e| e.as_bool())
@@ -436,7 +450,7 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
 
                         tool_results.push(CWToolResult {
                             tool_use_id: tool_use_id.to_string(),
-                            content: vec![CWTextContent { text: content_text }],
+                            content: vec![CWTextContent { text: content.text }],
                             status: if is_error {
                                 "error".to_string()
                             } else {
@@ -471,7 +485,11 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
                         content: String::new(),
                         tool_uses: None,
                         tool_results: Some(tool_results),
-                        images: None,
+                        images: if tool_result_images.is_empty() {
+                            None
+                        } else {
+                            Some(tool_result_images)
+                        },
                     });
                 }
 
@@ -497,27 +515,6 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
     result
 }
 
-/// 提取 tool_result 内容
-fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
-    match content {
-        Some(serde_json::Value::String(s)) => s.clone(),
-        Some(serde_json::Value::Array(arr)) => arr
-            .iter()
-            .filter_map(|item| {
-                if item.get("type") == Some(&serde_json::Value::String("text".to_string())) {
-                    item.get("text")
-                        .and_then(|t| t.as_str())
-                        .map(|s| s.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        _ => String::new(),
-    }
-}
-
 /// 转换 Anthropic tools 为 CodeWhisperer tools
 fn convert_anthropic_tools(tools: &Option<Vec<AnthropicTool>>) -> Option<Vec<CWToolItem>> {
     tools.as_ref().map(|tools| {
@@ -700,6 +697,8 @@ mod tests {
             temperature: None,
             tools: None,
             tool_choice: None,
+            thinking: None,
+            metadata: None,
         };
 
         let translator = AnthropicRequestTranslator::new();
@@ -717,20 +716,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_thinking_config_forwarded_as_prompt() {
+        let mut request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "system": "You are a helpful assistant.",
+            "thinking": {"type": "enabled", "budget_tokens": 10000},
+            "messages": [{"role": "user", "content": "Hello"}]
+        }))
+        .unwrap();
+
+        let cw_request = convert_anthropic_to_codewhisperer(&request, None);
+        let json = serde_json::to_string(&cw_request).unwrap();
+        assert!(json.contains(
+            "<thinking_mode>enabled</thinking_mode><max_thinking_length>10000</max_thinking_length>\\nYou are a helpful assistant."
+        ));
+
+        request.thinking = None;
+        let json =
+            serde_json::to_string(&convert_anthropic_to_codewhisperer(&request, None)).unwrap();
+        assert!(!json.contains("thinking_mode"));
+    }
+
     #[test]
     fn test_extract_system_text_string() {
-        let system = Some(serde_json::json!("You are a helpful assistant."));
+        let system = serde_json::from_value(serde_json::json!("You are a helpful assistant.")).ok();
         let text = extract_system_text(&system);
         assert_eq!(text, "You are a helpful assistant.");
     }
 
     #[test]
     fn test_extract_system_text_array() {
-        let system = Some(serde_json::json!([
-            {"type": "text", "text": "Line 1"},
+        let system = serde_json::from_value(serde_json::json!([
+            {"type": "text", "text": "Line 1", "cache_control": {"type": "ephemeral"}},
             {"type": "text", "text": "Line 2"}
-        ]));
+        ]))
+        .ok();
         let text = extract_system_text(&system);
         assert_eq!(text, "Line 1\nLine 2");
     }
+
+    #[test]
+    fn test_tool_result_image_forwarded() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [
+                {"role": "user", "content": "打开首页并截图"},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "toolu_1", "name": "screenshot", "input": {}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [
+                        {"type": "text", "text": "已截图"},
+                        {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0"}}
+                    ]}
+                ]}
+            ]
+        }))
+        .unwrap();
+
+        let cw_request = convert_anthropic_to_codewhisperer(&request, None);
+        let current = &cw_request
+            .conversation_state
+            .current_message
+            .user_input_message;
+        let images = current
+            .images
+            .as_ref()
+            .expect("tool_result 图片应随当前消息转发");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].format, "png");
+        assert_eq!(images[0].source.bytes, "iVBORw0");
+
+        let tool_results = current
+            .user_input_message_context
+            .as_ref()
+            .and_then(|ctx| ctx.tool_results.as_ref())
+            .unwrap();
+        assert_eq!(tool_results[0].content[0].text, "已截图");
+    }
 }
@@ -41,3 +41,38 @@ pub mod openai;
 // 重新导出常用类型
 pub use anthropic::{AnthropicRequestTranslator, AnthropicResponseTranslator};
 pub use openai::{OpenAiRequestTranslator, OpenAiResponseTranslator};
+
+use proxycast_core::models::openai::{ChatCompletionRequest, ChatMessage, MessageContent};
+
+/// 构建 Kiro 思考模式提示
+///
+/// CodeWhisperer 没有 `thinking` 参数，通过 system prompt 中的标签开启思考，
+/// 思考内容以 `<thinking>...</thinking>` 形式出现在响应文本开头。
+pub fn thinking_prompt(budget_tokens: u32) -> String {
+    format!(
+        "<thinking_mode>enabled</thinking_mode><max_thinking_length>{budget_tokens}</max_thinking_length>"
+    )
+}
+
+/// 在 OpenAI 格式请求的 system prompt 前注入思考模式提示
+///
+/// 用于 Anthropic 请求经 OpenAI 格式转发到 Kiro 的非流式路径。
+pub fn inject_thinking_prompt(request: &mut ChatCompletionRequest, budget_tokens: u32) {
+    let prompt = thinking_prompt(budget_tokens);
+    match request.messages.iter_mut().find(|m| m.role == "system") {
+        Some(system) => {
+            let text = system.get_content_text();
+            system.content = Some(MessageContent::Text(format!("{prompt}\n{text}")));
+        }
+        None => request.messages.insert(
+            0,
+            ChatMessage {
+                role: "system".to_string(),
+                content: Some(MessageContent::Text(prompt)),
+                tool_calls: None,
+                tool_call_id: None,
+                reasoning_content: None,
+            },
+        ),
+    }
+}
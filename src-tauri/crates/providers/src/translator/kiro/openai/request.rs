@@ -643,6 +643,11 @@ mod tests {
             top_p: None,
             tool_choice: None,
             reasoning_effort: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream_options: None,
         };
 
         let translator = OpenAiRequestTranslator::new();
@@ -14,9 +14,15 @@ pub struct BatchOptions {
     pub concurrency: usize,
 
     /// 失败后是否继续 (默认为 true)
+    ///
+    /// 未设置 `on_error` 时生效：false 等同于 `OnErrorPolicy::StopBatch`
     #[serde(default = "default_continue_on_error")]
     pub continue_on_error: bool,
 
+    /// 子任务出现终止性错误时的处理策略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<OnErrorPolicy>,
+
     /// 重试次数 (默认为 0)
     #[serde(default)]
     pub retry_count: usize,
@@ -43,12 +49,40 @@ impl Default for BatchOptions {
         Self {
             concurrency: default_concurrency(),
             continue_on_error: default_continue_on_error(),
+            on_error: None,
             retry_count: 0,
             timeout_seconds: default_timeout(),
         }
     }
 }
 
+impl BatchOptions {
+    /// 实际生效的错误处理策略
+    pub fn error_policy(&self) -> OnErrorPolicy {
+        self.on_error.unwrap_or(if self.continue_on_error {
+            OnErrorPolicy::ContinueTask
+        } else {
+            OnErrorPolicy::StopBatch
+        })
+    }
+}
+
+/// 子任务出现终止性错误时的处理策略
+///
+/// 终止性错误指不可重试的错误（如认证失败、请求无效）；
+/// 可重试错误（限流、超时、5xx）耗尽重试次数后只记为该任务失败，不触发策略。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnErrorPolicy {
+    /// 终止整个批次，取消所有未完成的任务（包括正在执行的）
+    StopBatch,
+    /// 继续执行其余任务
+    #[default]
+    ContinueTask,
+    /// 不再启动尚未开始的任务，正在执行的任务继续完成
+    SkipRemaining,
+}
+
 /// 单个任务定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskDefinition {
@@ -85,6 +119,10 @@ pub struct TaskResult {
     #[serde(default)]
     pub usage: TokenUsage,
 
+    /// 完整响应（OpenAI 格式，如果成功），保留工具调用和结束原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<serde_json::Value>,
+
     /// 开始时间
     pub started_at: chrono::DateTime<chrono::Utc>,
 
@@ -201,6 +239,8 @@ pub enum BatchTaskStatus {
 
 impl BatchTask {
     /// 创建新的批量任务
+    ///
+    /// 未指定 ID 的任务在创建时分配 ID 并随批量任务保存，执行结果按 ID 与任务对应。
     pub fn new(
         name: String,
         template_id: Uuid,
@@ -208,6 +248,13 @@ impl BatchTask {
         options: BatchOptions,
     ) -> Self {
         let now = chrono::Utc::now();
+        let tasks = tasks
+            .into_iter()
+            .map(|mut task| {
+                task.id.get_or_insert_with(Uuid::new_v4);
+                task
+            })
+            .collect();
         Self {
             id: Uuid::new_v4(),
             name,
@@ -299,10 +346,29 @@ mod tests {
         let options = BatchOptions::default();
         assert_eq!(options.concurrency, 3);
         assert_eq!(options.continue_on_error, true);
+        assert_eq!(options.error_policy(), OnErrorPolicy::ContinueTask);
         assert_eq!(options.retry_count, 0);
         assert_eq!(options.timeout_seconds, 120);
     }
 
+    #[test]
+    fn test_error_policy_resolution() {
+        let options: BatchOptions =
+            serde_json::from_str(r#"{"on_error": "skip_remaining"}"#).unwrap();
+        assert_eq!(options.error_policy(), OnErrorPolicy::SkipRemaining);
+
+        // 兼容旧字段
+        let options: BatchOptions =
+            serde_json::from_str(r#"{"continue_on_error": false}"#).unwrap();
+        assert_eq!(options.error_policy(), OnErrorPolicy::StopBatch);
+
+        // on_error 优先于 continue_on_error
+        let options: BatchOptions =
+            serde_json::from_str(r#"{"continue_on_error": false, "on_error": "continue_task"}"#)
+                .unwrap();
+        assert_eq!(options.error_policy(), OnErrorPolicy::ContinueTask);
+    }
+
     #[test]
     fn test_batch_task_creation() {
         let tasks = vec![
@@ -370,6 +436,7 @@ mod tests {
             content: Some("完成".to_string()),
             error: None,
             usage: TokenUsage::default(),
+            response: None,
             started_at: chrono::Utc::now(),
             completed_at: Some(chrono::Utc::now()),
         });
@@ -380,6 +447,7 @@ mod tests {
             content: None,
             error: Some("失败".to_string()),
             usage: TokenUsage::default(),
+            response: None,
             started_at: chrono::Utc::now(),
             completed_at: Some(chrono::Utc::now()),
         });
@@ -3,6 +3,7 @@
 //! 提供批量任务和模板的数据库操作
 
 use super::batch::{BatchTask, BatchTaskStatus};
+use super::batch_export::{render_batch_results, BatchExportFormat};
 use super::template::TaskTemplate;
 use anyhow::{Context, Result};
 use proxycast_core::database::DbConnection;
@@ -183,6 +184,15 @@ impl BatchTaskDao {
         }
     }
 
+    /// 导出批量任务结果（CSV 或 JSONL），任务不存在时返回 `None`
+    pub fn export_batch_results(
+        db: &DbConnection,
+        id: &Uuid,
+        format: BatchExportFormat,
+    ) -> Result<Option<String>> {
+        Ok(Self::get_by_id(db, id)?.map(|batch| render_batch_results(&batch, format)))
+    }
+
     /// 查询所有批量任务
     pub fn list_all(db: &DbConnection, limit: usize) -> Result<Vec<BatchTask>> {
         let conn = db.lock().unwrap();
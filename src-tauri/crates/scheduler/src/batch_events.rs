@@ -0,0 +1,246 @@
+//! 批量任务进度事件
+//!
+//! 批量任务执行过程中推送实时事件，前端无需轮询任务状态：
+//! - `batch:task_started`：子任务开始执行
+//! - `batch:task_completed`：子任务结束（成功、失败或取消），携带结果
+//! - `batch:progress`：已结束的子任务数 / 总数
+//! - `batch:finished`：批量任务结束，携带最终状态
+//!
+//! 所有事件都带有 `batch_id`，订阅者可按批量任务过滤。
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::batch::{BatchTaskStatus, TaskResult};
+
+/// 事件通道容量
+const EVENT_CHANNEL_CAPACITY: usize = 1000;
+
+/// 批量任务事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum BatchEvent {
+    /// 子任务开始执行
+    #[serde(rename = "batch:task_started")]
+    TaskStarted { batch_id: Uuid, task_id: Uuid },
+
+    /// 子任务结束
+    #[serde(rename = "batch:task_completed")]
+    TaskCompleted { batch_id: Uuid, result: TaskResult },
+
+    /// 进度更新
+    #[serde(rename = "batch:progress")]
+    Progress {
+        batch_id: Uuid,
+        done: usize,
+        total: usize,
+    },
+
+    /// 批量任务结束
+    #[serde(rename = "batch:finished")]
+    Finished {
+        batch_id: Uuid,
+        status: BatchTaskStatus,
+    },
+}
+
+impl BatchEvent {
+    /// 事件名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchEvent::TaskStarted { .. } => "batch:task_started",
+            BatchEvent::TaskCompleted { .. } => "batch:task_completed",
+            BatchEvent::Progress { .. } => "batch:progress",
+            BatchEvent::Finished { .. } => "batch:finished",
+        }
+    }
+
+    /// 所属批量任务 ID
+    pub fn batch_id(&self) -> Uuid {
+        match self {
+            BatchEvent::TaskStarted { batch_id, .. }
+            | BatchEvent::TaskCompleted { batch_id, .. }
+            | BatchEvent::Progress { batch_id, .. }
+            | BatchEvent::Finished { batch_id, .. } => *batch_id,
+        }
+    }
+}
+
+/// 批量任务事件发送器
+///
+/// 基于 broadcast 通道，可被多个订阅者同时消费；没有订阅者时事件直接丢弃。
+#[derive(Debug, Clone)]
+pub struct BatchEventEmitter {
+    sender: broadcast::Sender<BatchEvent>,
+}
+
+impl Default for BatchEventEmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchEventEmitter {
+    /// 创建事件发送器
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// 订阅批量任务事件
+    pub fn subscribe(&self) -> broadcast::Receiver<BatchEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 发送事件
+    pub fn emit(&self, event: BatchEvent) {
+        // 没有订阅者时发送失败，属于正常情况
+        let _ = self.sender.send(event);
+    }
+
+    /// 为一次批量任务执行创建进度跟踪器
+    pub fn progress(&self, batch_id: Uuid, total: usize) -> BatchProgress {
+        BatchProgress {
+            batch_id,
+            total,
+            done: AtomicUsize::new(0),
+            emitter: self.clone(),
+        }
+    }
+}
+
+/// 单次批量任务执行的进度跟踪器
+///
+/// 子任务并发执行时共享同一个跟踪器，`done` 计数保证进度单调递增。
+#[derive(Debug)]
+pub struct BatchProgress {
+    batch_id: Uuid,
+    total: usize,
+    done: AtomicUsize,
+    emitter: BatchEventEmitter,
+}
+
+impl BatchProgress {
+    /// 子任务开始
+    pub fn task_started(&self, task_id: Uuid) {
+        self.emitter.emit(BatchEvent::TaskStarted {
+            batch_id: self.batch_id,
+            task_id,
+        });
+    }
+
+    /// 子任务结束（无论成功与否），随后推送进度
+    pub fn task_completed(&self, result: TaskResult) {
+        self.emitter.emit(BatchEvent::TaskCompleted {
+            batch_id: self.batch_id,
+            result,
+        });
+        let done = self.done.fetch_add(1, Ordering::SeqCst) + 1;
+        self.emitter.emit(BatchEvent::Progress {
+            batch_id: self.batch_id,
+            done,
+            total: self.total,
+        });
+    }
+
+    /// 批量任务结束
+    pub fn finished(&self, status: BatchTaskStatus) {
+        self.emitter.emit(BatchEvent::Finished {
+            batch_id: self.batch_id,
+            status,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::{TaskStatus, TokenUsage};
+
+    fn result(task_id: Uuid, status: TaskStatus) -> TaskResult {
+        TaskResult {
+            task_id,
+            status,
+            content: None,
+            error: (status == TaskStatus::Failed).then(|| "LLM 调用失败".to_string()),
+            usage: TokenUsage::default(),
+            response: None,
+            started_at: chrono::Utc::now(),
+            completed_at: Some(chrono::Utc::now()),
+        }
+    }
+
+    #[test]
+    fn test_small_batch_emits_full_sequence() {
+        let emitter = BatchEventEmitter::new();
+        let mut rx = emitter.subscribe();
+        let batch_id = Uuid::new_v4();
+        let (ok_task, failed_task) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let progress = emitter.progress(batch_id, 2);
+        progress.task_started(ok_task);
+        progress.task_completed(result(ok_task, TaskStatus::Completed));
+        progress.task_started(failed_task);
+        progress.task_completed(result(failed_task, TaskStatus::Failed));
+        progress.finished(BatchTaskStatus::PartiallyCompleted);
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            assert_eq!(event.batch_id(), batch_id);
+            events.push(event);
+        }
+        let names: Vec<&str> = events.iter().map(BatchEvent::name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "batch:task_started",
+                "batch:task_completed",
+                "batch:progress",
+                "batch:task_started",
+                "batch:task_completed",
+                "batch:progress",
+                "batch:finished",
+            ]
+        );
+
+        // 失败的子任务同样推送结果和进度
+        match &events[4] {
+            BatchEvent::TaskCompleted { result, .. } => {
+                assert_eq!(result.task_id, failed_task);
+                assert_eq!(result.status, TaskStatus::Failed);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert!(matches!(
+            events[5],
+            BatchEvent::Progress {
+                done: 2,
+                total: 2,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[6],
+            BatchEvent::Finished {
+                status: BatchTaskStatus::PartiallyCompleted,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_event_serialization_tag() {
+        let event = BatchEvent::Progress {
+            batch_id: Uuid::nil(),
+            done: 1,
+            total: 3,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "batch:progress");
+        assert_eq!(json["done"], 1);
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["batch_id"], Uuid::nil().to_string());
+    }
+}
@@ -0,0 +1,268 @@
+//! 批量任务结果导出
+//!
+//! 将批量任务的结果导出为 CSV 或 JSONL，每个任务一行，
+//! 包含任务 ID、状态、Token 用量、耗时和截断后的结果预览。
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::batch::{BatchTask, TaskStatus};
+
+/// 结果预览的最大字符数
+pub const RESULT_PREVIEW_CHARS: usize = 200;
+
+/// CSV 表头
+const CSV_HEADER: [&str; 7] = [
+    "task_id",
+    "status",
+    "prompt_tokens",
+    "completion_tokens",
+    "total_tokens",
+    "latency_ms",
+    "preview",
+];
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchExportFormat {
+    Csv,
+    Jsonl,
+}
+
+/// 导出的单行记录
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchExportRow {
+    pub task_id: Option<Uuid>,
+    pub status: TaskStatus,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+    /// 执行耗时（毫秒），未完成的任务为空
+    pub latency_ms: Option<i64>,
+    /// 结果预览（成功时为响应内容，失败时为错误信息）
+    pub preview: String,
+}
+
+/// 截断到指定字符数，超出部分以 `...` 结尾
+fn truncate_preview(text: &str) -> String {
+    if text.chars().count() <= RESULT_PREVIEW_CHARS {
+        return text.to_string();
+    }
+    let mut preview: String = text.chars().take(RESULT_PREVIEW_CHARS).collect();
+    preview.push_str("...");
+    preview
+}
+
+/// 按任务定义顺序生成导出行，尚无结果的任务记为等待中
+pub fn export_rows(batch: &BatchTask) -> Vec<BatchExportRow> {
+    batch
+        .tasks
+        .iter()
+        .map(|task| {
+            let result = task
+                .id
+                .and_then(|id| batch.results.iter().find(|r| r.task_id == id));
+            match result {
+                Some(result) => BatchExportRow {
+                    task_id: Some(result.task_id),
+                    status: result.status,
+                    prompt_tokens: result.usage.prompt_tokens,
+                    completion_tokens: result.usage.completion_tokens,
+                    total_tokens: result.usage.total_tokens,
+                    latency_ms: result
+                        .completed_at
+                        .map(|end| (end - result.started_at).num_milliseconds()),
+                    preview: truncate_preview(
+                        result
+                            .content
+                            .as_deref()
+                            .or(result.error.as_deref())
+                            .unwrap_or_default(),
+                    ),
+                },
+                None => BatchExportRow {
+                    task_id: task.id,
+                    status: TaskStatus::Pending,
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                    total_tokens: 0,
+                    latency_ms: None,
+                    preview: String::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+/// 按 RFC 4180 转义 CSV 字段：包含逗号、引号或换行时加引号，引号双写
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 以 `=`、`+`、`-`、`@` 等开头的文本会被电子表格当作公式执行，前置 `'` 使其按文本显示
+fn neutralize_formula(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// 将批量任务结果渲染为指定格式
+pub fn render_batch_results(batch: &BatchTask, format: BatchExportFormat) -> String {
+    let rows = export_rows(batch);
+    match format {
+        BatchExportFormat::Csv => {
+            let mut out = CSV_HEADER.join(",");
+            out.push_str("\r\n");
+            for row in rows {
+                let status = serde_json::to_value(row.status)
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default();
+                let fields = [
+                    row.task_id.map(|id| id.to_string()).unwrap_or_default(),
+                    status,
+                    row.prompt_tokens.to_string(),
+                    row.completion_tokens.to_string(),
+                    row.total_tokens.to_string(),
+                    row.latency_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+                    neutralize_formula(&row.preview),
+                ];
+                let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                out.push_str(&line.join(","));
+                out.push_str("\r\n");
+            }
+            out
+        }
+        BatchExportFormat::Jsonl => rows
+            .iter()
+            .filter_map(|row| serde_json::to_string(row).ok())
+            .map(|line| line + "\n")
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::{BatchOptions, TaskDefinition, TaskResult, TokenUsage};
+    use std::collections::HashMap;
+
+    fn batch_with_results() -> BatchTask {
+        let tasks: Vec<TaskDefinition> = (0..3)
+            .map(|_| TaskDefinition {
+                id: Some(Uuid::new_v4()),
+                variables: HashMap::new(),
+                metadata: HashMap::new(),
+            })
+            .collect();
+        let mut batch = BatchTask::new(
+            "导出测试".to_string(),
+            Uuid::new_v4(),
+            tasks,
+            BatchOptions::default(),
+        );
+        let started_at = chrono::Utc::now();
+        batch.results.push(TaskResult {
+            task_id: batch.tasks[0].id.unwrap(),
+            status: TaskStatus::Completed,
+            content: Some("Hello, \"world\"\nsecond line".to_string()),
+            error: None,
+            usage: TokenUsage::new(10, 5),
+            response: None,
+            started_at,
+            completed_at: Some(started_at + chrono::Duration::milliseconds(1500)),
+        });
+        batch.results.push(TaskResult {
+            task_id: batch.tasks[1].id.unwrap(),
+            status: TaskStatus::Failed,
+            content: None,
+            error: Some("x".repeat(RESULT_PREVIEW_CHARS + 50)),
+            usage: TokenUsage::default(),
+            response: None,
+            started_at,
+            completed_at: Some(started_at),
+        });
+        batch
+    }
+
+    #[test]
+    fn test_csv_escapes_commas_and_quotes() {
+        let batch = batch_with_results();
+        let csv = render_batch_results(&batch, BatchExportFormat::Csv);
+
+        assert!(csv.starts_with("task_id,status,prompt_tokens,"));
+        let expected = format!(
+            "{},completed,10,5,15,1500,\"Hello, \"\"world\"\"\nsecond line\"\r\n",
+            batch.tasks[0].id.unwrap()
+        );
+        assert!(csv.contains(&expected), "{csv}");
+
+        // 超长错误信息被截断
+        let preview = format!("{}...", "x".repeat(RESULT_PREVIEW_CHARS));
+        assert!(csv.contains(&format!(",failed,0,0,0,0,{preview}\r\n")));
+        // 尚未执行的任务记为 pending
+        assert!(csv.ends_with(&format!(
+            "{},pending,0,0,0,,\r\n",
+            batch.tasks[2].id.unwrap()
+        )));
+    }
+
+    #[test]
+    fn test_tasks_without_ids_join_results() {
+        let tasks = vec![TaskDefinition {
+            id: None,
+            variables: HashMap::new(),
+            metadata: HashMap::new(),
+        }];
+        let mut batch = BatchTask::new(
+            "无 ID 任务".to_string(),
+            Uuid::new_v4(),
+            tasks,
+            BatchOptions::default(),
+        );
+        let task_id = batch.tasks[0].id.expect("创建时应分配任务 ID");
+        let started_at = chrono::Utc::now();
+        batch.results.push(TaskResult {
+            task_id,
+            status: TaskStatus::Completed,
+            content: Some("=HYPERLINK(\"http://evil\")".to_string()),
+            error: None,
+            usage: TokenUsage::new(1, 1),
+            response: None,
+            started_at,
+            completed_at: Some(started_at),
+        });
+
+        let rows = export_rows(&batch);
+        assert_eq!(rows[0].task_id, Some(task_id));
+        assert_eq!(rows[0].status, TaskStatus::Completed);
+
+        // CSV 中的公式被中和为文本
+        let csv = render_batch_results(&batch, BatchExportFormat::Csv);
+        assert!(
+            csv.contains(",\"'=HYPERLINK(\"\"http://evil\"\")\"\r\n"),
+            "{csv}"
+        );
+    }
+
+    #[test]
+    fn test_jsonl_has_one_line_per_task() {
+        let batch = batch_with_results();
+        let jsonl = render_batch_results(&batch, BatchExportFormat::Jsonl);
+
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), batch.tasks.len());
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["status"], "completed");
+        assert_eq!(first["total_tokens"], 15);
+        assert_eq!(first["latency_ms"], 1500);
+        assert_eq!(first["preview"], "Hello, \"world\"\nsecond line");
+    }
+}
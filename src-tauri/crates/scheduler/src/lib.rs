@@ -9,6 +9,8 @@
 //! - 任务状态跟踪
 //! - 失败重试机制
 //! - 批量任务支持
+//! - 批量任务进度事件
+//! - 批量任务结果导出（CSV / JSONL）
 //!
 //! ## 使用示例
 //!
@@ -41,6 +43,8 @@
 
 pub mod batch;
 pub mod batch_dao;
+pub mod batch_events;
+pub mod batch_export;
 pub mod dao;
 pub mod executor;
 pub mod scheduler;
@@ -48,10 +52,12 @@ pub mod template;
 pub mod types;
 
 pub use batch::{
-    BatchOptions, BatchTask, BatchTaskStatistics, BatchTaskStatus, TaskDefinition, TaskResult,
-    TaskStatus as BatchTaskStatus2, TokenUsage,
+    BatchOptions, BatchTask, BatchTaskStatistics, BatchTaskStatus, OnErrorPolicy, TaskDefinition,
+    TaskResult, TaskStatus as BatchTaskStatus2, TokenUsage,
 };
 pub use batch_dao::{BatchTaskDao, TemplateDao};
+pub use batch_events::{BatchEvent, BatchEventEmitter, BatchProgress};
+pub use batch_export::{render_batch_results, BatchExportFormat, BatchExportRow};
 pub use dao::SchedulerDao;
 pub use executor::{AgentExecutor, TaskExecutor};
 pub use scheduler::{AgentScheduler, SchedulerTrait};
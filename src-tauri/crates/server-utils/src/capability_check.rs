@@ -0,0 +1,230 @@
+//! 请求能力校验
+//!
+//! 根据请求内容推断所需的 Provider 能力（流式、工具调用、图片输入、多候选、logprobs），
+//! 在选定凭证之后、调用上游之前对照 Provider 能力清单校验，
+//! 不支持的组合直接返回 400。
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use proxycast_core::models::anthropic::AnthropicMessagesRequest;
+use proxycast_core::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
+use proxycast_core::models::provider_capabilities::{Capability, CapabilityError};
+use proxycast_core::ProviderType;
+
+/// OpenAI 格式请求所需的能力
+pub fn required_capabilities_openai(request: &ChatCompletionRequest) -> Vec<Capability> {
+    let has_images = request.messages.iter().any(|m| {
+        matches!(&m.content, Some(MessageContent::Parts(parts))
+            if parts.iter().any(|p| matches!(p, ContentPart::ImageUrl { .. })))
+    });
+    let mut required = collect_required(
+        request.stream,
+        request.tools.as_ref().is_some_and(|t| !t.is_empty()),
+        has_images,
+    );
+    if request.choice_count() > 1 {
+        required.push(Capability::MultipleChoices);
+    }
+    if request.logprobs == Some(true) {
+        required.push(Capability::Logprobs);
+    }
+    required
+}
+
+/// Anthropic 格式请求所需的能力
+pub fn required_capabilities_anthropic(request: &AnthropicMessagesRequest) -> Vec<Capability> {
+    let has_images = request
+        .messages
+        .iter()
+        .any(|m| contains_image_block(&m.content));
+    collect_required(
+        request.stream,
+        request.tools.as_ref().is_some_and(|t| !t.is_empty()),
+        has_images,
+    )
+}
+
+fn collect_required(stream: bool, tools: bool, images: bool) -> Vec<Capability> {
+    [
+        (stream, Capability::Streaming),
+        (tools, Capability::Tools),
+        (images, Capability::Images),
+    ]
+    .into_iter()
+    .filter_map(|(required, capability)| required.then_some(capability))
+    .collect()
+}
+
+/// 内容块数组中是否包含图片（包括 `tool_result` 内嵌的图片）
+fn contains_image_block(content: &serde_json::Value) -> bool {
+    content.as_array().is_some_and(|blocks| {
+        blocks.iter().any(|block| match block["type"].as_str() {
+            Some("image") => true,
+            Some("tool_result") => contains_image_block(&block["content"]),
+            _ => false,
+        })
+    })
+}
+
+/// 校验 Provider 是否支持 OpenAI 格式请求
+pub fn check_openai_capabilities(
+    provider: ProviderType,
+    request: &ChatCompletionRequest,
+) -> Result<(), CapabilityError> {
+    provider.ensure_supports(&required_capabilities_openai(request))
+}
+
+/// 校验 Provider 是否支持 Anthropic 格式请求
+pub fn check_anthropic_capabilities(
+    provider: ProviderType,
+    request: &AnthropicMessagesRequest,
+) -> Result<(), CapabilityError> {
+    provider.ensure_supports(&required_capabilities_anthropic(request))
+}
+
+/// 构建能力不支持的 400 响应
+///
+/// 同时带有 Anthropic 的 `type: "error"` 与 OpenAI 的 `error.code`，两种客户端都能解析。
+pub fn unsupported_capability_response(error: &CapabilityError) -> Response {
+    let CapabilityError::Unsupported { capability, .. } = error;
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "invalid_request_error",
+                "code": "unsupported_capability",
+                "param": capability.as_str(),
+                "message": error.to_string()
+            }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::models::openai::ChatCompletionResponse;
+    use serde_json::json;
+
+    fn openai_request(body: serde_json::Value) -> ChatCompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn anthropic_request(body: serde_json::Value) -> AnthropicMessagesRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_required_capabilities() {
+        let request = openai_request(json!({
+            "model": "gpt-4o",
+            "stream": true,
+            "messages": [{"role": "user", "content": "hi"}]
+        }));
+        assert_eq!(
+            required_capabilities_openai(&request),
+            vec![Capability::Streaming]
+        );
+
+        let request = anthropic_request(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": [{
+                "type": "tool_result",
+                "tool_use_id": "toolu_1",
+                "content": [{"type": "image", "source": {
+                    "type": "base64", "media_type": "image/png", "data": "AAAA"
+                }}]
+            }]}],
+            "tools": [{"name": "screenshot", "input_schema": {"type": "object"}}]
+        }));
+        assert_eq!(
+            required_capabilities_anthropic(&request),
+            vec![Capability::Tools, Capability::Images]
+        );
+    }
+
+    #[test]
+    fn test_logprobs_forwarded_to_supporting_provider() {
+        let request = openai_request(json!({
+            "model": "gpt-4o",
+            "logprobs": true,
+            "top_logprobs": 5,
+            "messages": [{"role": "user", "content": "hi"}]
+        }));
+        assert!(check_openai_capabilities(ProviderType::OpenAI, &request).is_ok());
+        assert_eq!(
+            check_openai_capabilities(ProviderType::Kiro, &request),
+            Err(CapabilityError::Unsupported {
+                provider: ProviderType::Kiro,
+                capability: Capability::Logprobs,
+            })
+        );
+
+        // OpenAI Provider 直接序列化请求体转发
+        let forwarded = serde_json::to_value(&request).unwrap();
+        assert_eq!(forwarded["logprobs"], true);
+        assert_eq!(forwarded["top_logprobs"], 5);
+        assert!(forwarded.get("n").is_none());
+
+        // 响应中的 logprobs 保留
+        let response: ChatCompletionResponse = serde_json::from_value(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hi"},
+                "logprobs": {"content": [{"token": "Hi", "logprob": -0.1, "top_logprobs": []}]},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+        }))
+        .unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["choices"][0]["logprobs"]["content"][0]["token"], "Hi");
+    }
+
+    #[tokio::test]
+    async fn test_image_request_passed_to_local_models() {
+        let request = openai_request(json!({
+            "model": "llava",
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "这是什么？"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+            ]}]
+        }));
+
+        // 本地视觉模型（如 llava）可以接收图片，是否支持由上游判断
+        assert!(check_openai_capabilities(ProviderType::OpenAI, &request).is_ok());
+        assert!(check_openai_capabilities(ProviderType::Ollama, &request).is_ok());
+
+        let request = openai_request(json!({
+            "model": "llama3",
+            "logprobs": true,
+            "messages": [{"role": "user", "content": "hi"}]
+        }));
+        let error = check_openai_capabilities(ProviderType::Ollama, &request).unwrap_err();
+        assert_eq!(
+            error,
+            CapabilityError::Unsupported {
+                provider: ProviderType::Ollama,
+                capability: Capability::Logprobs,
+            }
+        );
+
+        let response = unsupported_capability_response(&error);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "unsupported_capability");
+        assert_eq!(body["error"]["param"], "logprobs");
+    }
+}
@@ -0,0 +1,269 @@
+//! Anthropic `count_tokens` 计数
+//!
+//! 选中的 Provider 支持原生计数（Anthropic `/v1/messages/count_tokens`）时转发到上游，
+//! 返回真实的 Token 数；否则（或上游失败时）使用本地估算。
+//! 原生计数结果按请求体哈希短暂缓存，避免客户端重复计数同一请求时反复调用上游。
+
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 原生计数结果默认缓存时间
+pub const DEFAULT_COUNT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// 缓存条目上限
+const MAX_CACHE_ENTRIES: usize = 1024;
+
+/// 每条消息的格式化开销估算
+const TOKENS_PER_MESSAGE: u64 = 4;
+
+/// Token 计数来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountSource {
+    /// 上游原生计数
+    Native,
+    /// 缓存的原生计数
+    Cached,
+    /// 本地估算
+    Estimated,
+}
+
+/// Token 计数结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCount {
+    pub input_tokens: u64,
+    pub source: CountSource,
+}
+
+/// 原生计数结果缓存（按请求体哈希）
+pub struct CountTokensCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<u64, (u64, Instant)>>,
+}
+
+impl Default for CountTokensCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_COUNT_CACHE_TTL)
+    }
+}
+
+impl CountTokensCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 计算请求的输入 Token 数
+    ///
+    /// `native` 为上游原生计数调用（无支持原生计数的凭证时传 `None`），
+    /// 返回上游响应体（含 `input_tokens`）；调用失败时回退到本地估算。
+    pub async fn count<F, Fut>(&self, request: &Value, native: Option<F>) -> TokenCount
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, String>>,
+    {
+        let Some(native) = native else {
+            return estimated(request);
+        };
+
+        let key = body_hash(request);
+        if let Some(input_tokens) = self.get(key) {
+            return TokenCount {
+                input_tokens,
+                source: CountSource::Cached,
+            };
+        }
+
+        match native().await {
+            Ok(body) => match body.get("input_tokens").and_then(Value::as_u64) {
+                Some(input_tokens) => {
+                    self.insert(key, input_tokens);
+                    TokenCount {
+                        input_tokens,
+                        source: CountSource::Native,
+                    }
+                }
+                None => {
+                    tracing::warn!("[COUNT_TOKENS] 上游响应缺少 input_tokens，改用本地估算");
+                    estimated(request)
+                }
+            },
+            Err(e) => {
+                tracing::warn!("[COUNT_TOKENS] 上游计数失败，改用本地估算: {}", e);
+                estimated(request)
+            }
+        }
+    }
+
+    fn get(&self, key: u64) -> Option<u64> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(&key)
+            .filter(|(_, at)| at.elapsed() < self.ttl)
+            .map(|(count, _)| *count)
+    }
+
+    fn insert(&self, key: u64, count: u64) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_CACHE_ENTRIES {
+            entries.retain(|_, (_, at)| at.elapsed() < self.ttl);
+        }
+        if entries.len() >= MAX_CACHE_ENTRIES {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, (_, at))| *at)
+                .map(|(key, _)| *key)
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (count, Instant::now()));
+    }
+}
+
+fn estimated(request: &Value) -> TokenCount {
+    TokenCount {
+        input_tokens: estimate_input_tokens(request),
+        source: CountSource::Estimated,
+    }
+}
+
+fn body_hash(request: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    request.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 本地估算 Anthropic 请求的输入 Token 数（约 4 个字符一个 Token）
+pub fn estimate_input_tokens(request: &Value) -> u64 {
+    let mut chars = 0usize;
+    if let Some(system) = request.get("system") {
+        chars += content_chars(system);
+    }
+    let messages = request
+        .get("messages")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for message in messages {
+        if let Some(content) = message.get("content") {
+            chars += content_chars(content);
+        }
+    }
+    if let Some(tools) = request.get("tools").and_then(Value::as_array) {
+        chars += tools
+            .iter()
+            .map(|tool| tool.to_string().len())
+            .sum::<usize>();
+    }
+
+    (chars as u64).div_ceil(4) + messages.len() as u64 * TOKENS_PER_MESSAGE
+}
+
+/// 内容的字符数：文本取文本本身，其余块（工具调用、图片等）按 JSON 长度计
+fn content_chars(content: &Value) -> usize {
+    match content {
+        Value::String(text) => text.chars().count(),
+        Value::Array(blocks) => blocks.iter().map(content_chars).sum(),
+        Value::Object(block) => match block.get("text").and_then(Value::as_str) {
+            Some(text) => text.chars().count(),
+            None => match block.get("content") {
+                Some(inner) => content_chars(inner),
+                None => content.to_string().len(),
+            },
+        },
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type NativeCount = fn() -> std::future::Ready<Result<Value, String>>;
+
+    fn request() -> Value {
+        json!({
+            "model": "claude-sonnet-4-5",
+            "system": "You are a helpful assistant.",
+            "messages": [{"role": "user", "content": "Hello, how are you today?"}]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_native_count_delegated_and_cached() {
+        let cache = CountTokensCache::default();
+        let calls = AtomicUsize::new(0);
+        let native = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(json!({"input_tokens": 21}))
+        };
+
+        let first = cache.count(&request(), Some(native)).await;
+        assert_eq!(
+            first,
+            TokenCount {
+                input_tokens: 21,
+                source: CountSource::Native
+            }
+        );
+
+        // 相同请求体命中缓存，不再调用上游
+        let second = cache.count(&request(), Some(native)).await;
+        assert_eq!(second.input_tokens, 21);
+        assert_eq!(second.source, CountSource::Cached);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 缓存过期后重新调用上游
+        let cache = CountTokensCache::new(Duration::ZERO);
+        cache.count(&request(), Some(native)).await;
+        cache.count(&request(), Some(native)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_estimator_fallback() {
+        let cache = CountTokensCache::default();
+        let expected = estimate_input_tokens(&request());
+        assert!(expected > TOKENS_PER_MESSAGE);
+
+        // 没有支持原生计数的凭证
+        let none: Option<NativeCount> = None;
+        let count = cache.count(&request(), none).await;
+        assert_eq!(count.source, CountSource::Estimated);
+        assert_eq!(count.input_tokens, expected);
+
+        // 上游失败时回退估算，且不缓存
+        let failing = || async { Err("upstream 500".to_string()) };
+        let count = cache.count(&request(), Some(failing)).await;
+        assert_eq!(count.source, CountSource::Estimated);
+        assert_eq!(count.input_tokens, expected);
+        let native = || async { Ok(json!({"input_tokens": 21})) };
+        assert_eq!(
+            cache.count(&request(), Some(native)).await.source,
+            CountSource::Native
+        );
+    }
+
+    #[test]
+    fn test_estimate_counts_blocks_and_tools() {
+        let plain = estimate_input_tokens(&request());
+        let with_tools = estimate_input_tokens(&json!({
+            "system": [{"type": "text", "text": "You are a helpful assistant."}],
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "Hello, how are you today?"}
+            ]}],
+            "tools": [{"name": "get_weather", "input_schema": {"type": "object"}}]
+        }));
+        assert!(with_tools > plain);
+        assert_eq!(estimate_input_tokens(&json!({})), 0);
+    }
+}
@@ -0,0 +1,120 @@
+//! Provider 自动降级策略
+//!
+//! 是否允许在当前 Provider 无可用凭证或上游过载时切换到其他凭证/Provider，
+//! 由 `retry.auto_switch_provider` 决定，并可通过 `retry.provider_fallback`
+//! 按 Provider 类型覆盖（例如对按量计费的 Provider 严格禁用）。
+//! 单个请求还可以携带 `X-ProxyCast-No-Fallback: true` 临时禁用降级。
+
+use crate::stream_buffer::BufferFormat;
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use proxycast_core::config::RetrySettings;
+use serde_json::json;
+
+/// 请求级禁用降级请求头（`true`/`1` 禁用）
+pub const NO_FALLBACK_HEADER: &str = "x-proxycast-no-fallback";
+
+/// 本次请求对指定 Provider 类型是否允许降级
+///
+/// 请求头只能禁用降级，不能开启配置中已禁用的降级。
+pub fn fallback_allowed(settings: &RetrySettings, provider: &str, headers: &HeaderMap) -> bool {
+    settings.fallback_enabled_for(provider) && !no_fallback_requested(headers)
+}
+
+/// 请求是否携带了禁用降级的请求头
+pub fn no_fallback_requested(headers: &HeaderMap) -> bool {
+    headers
+        .get(NO_FALLBACK_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .is_some_and(|v| matches!(v.as_str(), "1" | "true" | "yes" | "on"))
+}
+
+/// 凭证池中没有可用凭证且不进行降级时的 503 响应（按客户端协议格式构建）
+pub fn no_credential_response(
+    format: BufferFormat,
+    selected_provider: &str,
+    fallback_allowed: bool,
+) -> Response {
+    let message = if fallback_allowed {
+        format!(
+            "没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。",
+            selected_provider
+        )
+    } else {
+        format!(
+            "没有找到可用的 '{}' 凭证（已禁用自动降级）。请在凭证池中添加对应的凭证。",
+            selected_provider
+        )
+    };
+    let body = match format {
+        BufferFormat::OpenAi => json!({
+            "error": {
+                "message": message,
+                "type": "no_credential_error",
+                "code": "no_credential"
+            }
+        }),
+        BufferFormat::Anthropic => json!({
+            "type": "error",
+            "error": {
+                "type": "no_credential_error",
+                "message": message
+            }
+        }),
+    };
+    (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn settings() -> RetrySettings {
+        RetrySettings {
+            provider_fallback: HashMap::from([("Claude".to_string(), false)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_provider_with_fallback_disabled_does_not_switch() {
+        let settings = settings();
+        let headers = HeaderMap::new();
+        assert!(!fallback_allowed(&settings, "claude", &headers));
+        // 其他 Provider 仍按全局配置故障转移
+        assert!(fallback_allowed(&settings, "kiro", &headers));
+        assert!(fallback_allowed(&settings, "openai", &headers));
+
+        let response = no_credential_response(
+            BufferFormat::Anthropic,
+            "claude",
+            fallback_allowed(&settings, "claude", &headers),
+        );
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_no_fallback_header_overrides_per_request() {
+        let settings = settings();
+        let mut headers = HeaderMap::new();
+        headers.insert(NO_FALLBACK_HEADER, "true".parse().unwrap());
+        assert!(!fallback_allowed(&settings, "kiro", &headers));
+
+        headers.insert(NO_FALLBACK_HEADER, "false".parse().unwrap());
+        assert!(fallback_allowed(&settings, "kiro", &headers));
+
+        // 请求头不能开启配置中已禁用的降级
+        assert!(!fallback_allowed(&settings, "claude", &headers));
+
+        let disabled = RetrySettings {
+            auto_switch_provider: false,
+            ..Default::default()
+        };
+        assert!(!fallback_allowed(&disabled, "kiro", &HeaderMap::new()));
+    }
+}
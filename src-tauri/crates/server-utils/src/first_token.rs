@@ -0,0 +1,306 @@
+//! 流式响应首 token 耗时
+//!
+//! 流式请求的总耗时包含整个生成过程，无法反映“开始慢”的问题。包装响应体后，
+//! 在发出首个内容字节时记录首 token 耗时（TTFT），响应体结束（或客户端断开）时
+//! 通过回调上报，由调用方一并写入遥测。
+//!
+//! 客户端中途断开时 axum 会直接丢弃响应体，上游流还没读完；回调据此区分
+//! 正常结束与客户端断开，调用方可以取消请求并记为已取消。
+//!
+//! 首 token 指第一个真正的文本、推理或工具调用增量：SSE 注释（心跳）、
+//! Anthropic 的 `message_start`/`ping`、OpenAI 只含 role 的 delta 等
+//! 元数据事件都不计入。事件可能跨数据块，按行缓冲后逐条解析。
+
+use axum::{body::Body, response::Response};
+use futures::{stream, StreamExt};
+use serde_json::Value;
+use std::time::Instant;
+
+/// 流式响应体结束时的统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamEnd {
+    /// 首 token 耗时（毫秒），响应体没有任何内容时为 `None`
+    pub first_token_ms: Option<u64>,
+    /// 上游流读完之前响应体就被丢弃（客户端断开）
+    pub client_disconnected: bool,
+}
+
+/// 响应体计时器，释放时调用完成回调
+struct FirstTokenTimer<F: FnOnce(StreamEnd)> {
+    started: Instant,
+    first_token_ms: Option<u64>,
+    /// 尚未读到换行的不完整行
+    partial_line: Vec<u8>,
+    /// 上游流已结束（读完或出错）
+    finished: bool,
+    on_complete: Option<F>,
+}
+
+impl<F: FnOnce(StreamEnd)> FirstTokenTimer<F> {
+    fn observe(&mut self, chunk: &[u8]) {
+        if self.first_token_ms.is_some() {
+            return;
+        }
+        self.partial_line.extend_from_slice(chunk);
+        let Some(end) = self.partial_line.iter().rposition(|b| *b == b'\n') else {
+            return;
+        };
+        let complete: Vec<u8> = self.partial_line.drain(..=end).collect();
+        if String::from_utf8_lossy(&complete)
+            .lines()
+            .any(is_content_line)
+        {
+            self.first_token_ms = Some(self.started.elapsed().as_millis() as u64);
+            self.partial_line = Vec::new();
+        }
+    }
+
+    /// 上游流已结束（读完或出错）
+    fn finish(&mut self) {
+        self.finished = true;
+    }
+}
+
+impl<F: FnOnce(StreamEnd)> Drop for FirstTokenTimer<F> {
+    fn drop(&mut self) {
+        if let Some(on_complete) = self.on_complete.take() {
+            on_complete(StreamEnd {
+                first_token_ms: self.first_token_ms,
+                client_disconnected: !self.finished,
+            });
+        }
+    }
+}
+
+/// SSE 行是否携带首 token（文本、推理或工具调用增量）
+fn is_content_line(line: &str) -> bool {
+    let Some(data) = line.trim().strip_prefix("data:") else {
+        // 注释、event:/id: 等字段行不含内容
+        return false;
+    };
+    let data = data.trim();
+    if data.is_empty() || data == "[DONE]" {
+        return false;
+    }
+    match serde_json::from_str::<Value>(data) {
+        Ok(event) => is_content_event(&event),
+        // 非 JSON 的 data 行按原样视为内容
+        Err(_) => true,
+    }
+}
+
+/// 解析后的事件是否为内容增量（支持 Anthropic、OpenAI Chat/Responses、Gemini）
+fn is_content_event(event: &Value) -> bool {
+    let non_empty = |v: Option<&Value>| match v {
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::Null) | None => false,
+        Some(_) => true,
+    };
+
+    if let Some(kind) = event.get("type").and_then(Value::as_str) {
+        return match kind {
+            // Anthropic：文本、推理与工具参数增量，以及工具调用块开始
+            "content_block_delta" => true,
+            "content_block_start" => event
+                .pointer("/content_block/type")
+                .and_then(Value::as_str)
+                .is_some_and(|t| t != "text" && t != "thinking"),
+            // OpenAI Responses：各类输出增量
+            kind => kind.starts_with("response.") && kind.ends_with(".delta"),
+        };
+    }
+
+    if let Some(choices) = event.get("choices").and_then(Value::as_array) {
+        return choices.iter().any(|choice| {
+            let delta = choice.get("delta");
+            non_empty(delta.and_then(|d| d.get("content")))
+                || non_empty(delta.and_then(|d| d.get("reasoning_content")))
+                || non_empty(delta.and_then(|d| d.get("tool_calls")))
+                || non_empty(choice.get("text"))
+        });
+    }
+
+    if let Some(candidates) = event.get("candidates").and_then(Value::as_array) {
+        return candidates.iter().any(|candidate| {
+            candidate
+                .pointer("/content/parts")
+                .and_then(Value::as_array)
+                .is_some_and(|parts| {
+                    parts.iter().any(|part| {
+                        non_empty(part.get("text")) || part.get("functionCall").is_some()
+                    })
+                })
+        });
+    }
+
+    false
+}
+
+/// 包装流式响应体，响应体结束或被丢弃时以 [`StreamEnd`] 调用 `on_complete`
+///
+/// `started` 为请求开始时间。
+pub fn track_first_token<F>(response: Response, started: Instant, on_complete: F) -> Response
+where
+    F: FnOnce(StreamEnd) + Send + 'static,
+{
+    let (parts, body) = response.into_parts();
+    let timer = FirstTokenTimer {
+        started,
+        first_token_ms: None,
+        partial_line: Vec::new(),
+        finished: false,
+        on_complete: Some(on_complete),
+    };
+    let body = stream::unfold(
+        (body.into_data_stream(), timer),
+        |(mut inner, mut timer)| async move {
+            let Some(chunk) = inner.next().await else {
+                timer.finish();
+                return None;
+            };
+            match &chunk {
+                Ok(bytes) => timer.observe(bytes),
+                // 出错后 axum 不再轮询响应体，不算客户端断开
+                Err(_) => timer.finish(),
+            }
+            Some((chunk, (inner, timer)))
+        },
+    );
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_ttft_recorded_and_less_than_total_duration() {
+        // 先发心跳注释和只含 role 的 delta，200ms 后才发出首个 token（跨数据块），
+        // 之后继续生成 200ms
+        let chunks = stream::iter([
+            (0, ": ping\n\n"),
+            (
+                0,
+                "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            ),
+            (200, "data: {\"choices\":[{\"delta\":"),
+            (0, "{\"content\":\"Hi\"}}]}\n\n"),
+            (200, "data: [DONE]\n\n"),
+        ])
+        .then(|(delay, chunk)| async move {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            Ok::<_, std::io::Error>(Bytes::from(chunk))
+        });
+        let response = Response::new(Body::from_stream(chunks));
+
+        let started = Instant::now();
+        let recorded = Arc::new(Mutex::new(None));
+        let sink = recorded.clone();
+        let response = track_first_token(response, started, move |end| {
+            *sink.lock().unwrap() = Some((end, started.elapsed().as_millis() as u64));
+        });
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.ends_with(b"data: [DONE]\n\n"));
+
+        let (end, total) = recorded.lock().unwrap().take().expect("响应体结束时应回调");
+        assert!(!end.client_disconnected);
+        let ttft = end.first_token_ms.expect("应记录首 token 耗时");
+        assert!(ttft >= 200, "ttft={ttft}");
+        assert!(ttft < total, "ttft={ttft} total={total}");
+        assert!(total >= 400);
+    }
+
+    #[tokio::test]
+    async fn test_no_content_reports_none() {
+        let recorded = Arc::new(Mutex::new(None));
+        let sink = recorded.clone();
+        let response = track_first_token(
+            Response::new(Body::from(": keep-alive\n\n")),
+            Instant::now(),
+            move |end| *sink.lock().unwrap() = Some(end),
+        );
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            Some(StreamEnd {
+                first_token_ms: None,
+                client_disconnected: false,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_body_reports_client_disconnect() {
+        // 上游还在生成时客户端断开：读到第一个数据块后丢弃响应体
+        let upstream_dropped = Arc::new(Mutex::new(false));
+        let guard = DropFlag(upstream_dropped.clone());
+        let chunks = stream::iter([
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"Hi\"}}\n\n",
+        ])
+        .map(|chunk| Ok::<_, std::io::Error>(Bytes::from(chunk)))
+        .chain(stream::pending())
+        .map(move |chunk| {
+            let _ = &guard;
+            chunk
+        });
+
+        let recorded = Arc::new(Mutex::new(None));
+        let sink = recorded.clone();
+        let response = track_first_token(
+            Response::new(Body::from_stream(chunks)),
+            Instant::now(),
+            move |end| *sink.lock().unwrap() = Some(end),
+        );
+        let mut body = response.into_body().into_data_stream();
+        body.next().await.unwrap().unwrap();
+        drop(body);
+
+        let end = recorded.lock().unwrap().expect("丢弃响应体时应回调");
+        assert!(end.client_disconnected);
+        assert!(end.first_token_ms.is_some());
+        // 上游流随响应体一起释放
+        assert!(*upstream_dropped.lock().unwrap());
+    }
+
+    #[test]
+    fn test_metadata_events_are_not_content() {
+        for line in [
+            ": ping",
+            "event: message_start",
+            "data: [DONE]",
+            r#"data: {"type":"message_start","message":{"id":"msg_1"}}"#,
+            r#"data: {"type":"ping"}"#,
+            r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"data: {"choices":[{"index":0,"delta":{"role":"assistant","content":""}}]}"#,
+            r#"data: {"type":"response.created","response":{}}"#,
+        ] {
+            assert!(!is_content_line(line), "{line}");
+        }
+        for line in [
+            r#"data: {"type":"content_block_delta","delta":{"type":"text_delta","text":"Hi"}}"#,
+            r#"data: {"type":"content_block_start","content_block":{"type":"tool_use","name":"f"}}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0}]}}]}"#,
+            r#"data: {"type":"response.output_text.delta","delta":"Hi"}"#,
+            r#"data: {"candidates":[{"content":{"parts":[{"text":"Hi"}]}}]}"#,
+        ] {
+            assert!(is_content_line(line), "{line}");
+        }
+    }
+
+    struct DropFlag(Arc<Mutex<bool>>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            *self.0.lock().unwrap() = true;
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Gemini 原生协议流式响应
+//!
+//! `streamGenerateContent?alt=sse` 的上游响应是 `data: {...}` 格式的 SSE，
+//! 这里按行重新分帧后转发给客户端（上游数据块可能在任意位置被切开），
+//! 每个事件只输出一个完整的 JSON 数据行。
+//!
+//! 上游连接中途断开，或流结束时还没有任何候选返回 `finishReason` 时，
+//! 追加一个 Gemini 格式的错误事件，避免客户端把截断的输出当成完整回复。
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::Response,
+};
+use futures::{stream, Stream, StreamExt};
+use serde_json::{json, Value};
+use std::fmt::Display;
+
+/// 把上游 Gemini SSE 字节流转换为客户端流式响应
+pub fn build_gemini_native_stream_response<S, B, E>(upstream: S) -> Response
+where
+    S: Stream<Item = Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]> + Send + 'static,
+    E: Display + Send + 'static,
+{
+    let state = GeminiSseState {
+        upstream: Box::pin(upstream),
+        buffer: Vec::new(),
+        finished: false,
+        done: false,
+    };
+    let body = stream::unfold(state, |mut state| async move {
+        let events = state.next_events().await?;
+        Some((Ok::<_, std::convert::Infallible>(events), state))
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .body(Body::from_stream(body))
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to build SSE response: {}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap_or_default()
+        })
+}
+
+/// 重新分帧的状态
+struct GeminiSseState<S> {
+    upstream: std::pin::Pin<Box<S>>,
+    /// 尚未遇到换行的上游字节
+    buffer: Vec<u8>,
+    /// 是否已收到 `finishReason`（或上游错误对象）
+    finished: bool,
+    /// 是否已输出最后一个事件
+    done: bool,
+}
+
+impl<S, B, E> GeminiSseState<S>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    /// 读取上游直到产出至少一个事件，流结束时返回 `None`
+    async fn next_events(&mut self) -> Option<String> {
+        while !self.done {
+            match self.upstream.next().await {
+                Some(Ok(chunk)) => {
+                    self.buffer.extend_from_slice(chunk.as_ref());
+                    let events = self.drain_lines(false);
+                    if !events.is_empty() {
+                        return Some(events);
+                    }
+                }
+                Some(Err(e)) => {
+                    tracing::warn!("[GEMINI] 上游流中断: {}", e);
+                    self.done = true;
+                    let mut events = self.drain_lines(true);
+                    events.push_str(&error_event(&format!("上游连接中断: {e}")));
+                    return Some(events);
+                }
+                None => {
+                    self.done = true;
+                    let mut events = self.drain_lines(true);
+                    if !self.finished {
+                        tracing::warn!("[GEMINI] 上游流在返回 finishReason 前结束");
+                        events.push_str(&error_event("上游连接在响应完成前断开"));
+                    }
+                    return (!events.is_empty()).then_some(events);
+                }
+            }
+        }
+        None
+    }
+
+    /// 取出缓冲区中的完整行并转换为事件；`flush` 时连同最后一个不完整的行
+    fn drain_lines(&mut self, flush: bool) -> String {
+        let mut events = String::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            self.push_line(&line, &mut events);
+        }
+        if flush && !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.push_line(&line, &mut events);
+        }
+        events
+    }
+
+    fn push_line(&mut self, line: &[u8], events: &mut String) {
+        let Ok(line) = std::str::from_utf8(line) else {
+            return;
+        };
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            return;
+        };
+        let Ok(chunk) = serde_json::from_str::<Value>(data.trim()) else {
+            return;
+        };
+        if is_final_chunk(&chunk) {
+            self.finished = true;
+        }
+        events.push_str(&format!("data: {chunk}\n\n"));
+    }
+}
+
+/// 数据块是否表示响应已结束（有候选返回 `finishReason`，或是上游错误对象）
+///
+/// Cloud Code Assist 端点把响应包在 `response` 字段中
+fn is_final_chunk(chunk: &Value) -> bool {
+    let body = chunk.get("response").unwrap_or(chunk);
+    if body.get("error").is_some() {
+        return true;
+    }
+    body.get("candidates")
+        .and_then(Value::as_array)
+        .is_some_and(|candidates| {
+            candidates
+                .iter()
+                .any(|candidate| candidate.get("finishReason").is_some())
+        })
+}
+
+/// Gemini 格式的错误事件
+fn error_event(message: &str) -> String {
+    let error = json!({
+        "error": {
+            "code": 502,
+            "message": message,
+            "status": "UNAVAILABLE"
+        }
+    });
+    format!("data: {error}\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn collect<I>(chunks: I) -> Vec<Value>
+    where
+        I: IntoIterator<Item = Result<&'static str, &'static str>>,
+        I::IntoIter: Send + 'static,
+    {
+        let response = build_gemini_native_stream_response(stream::iter(chunks));
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        body.split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                let data = event
+                    .strip_prefix("data: ")
+                    .expect("每个事件只有一个数据行");
+                serde_json::from_str(data).unwrap()
+            })
+            .collect()
+    }
+
+    fn is_error(event: &Value) -> bool {
+        event.get("error").is_some()
+    }
+
+    #[tokio::test]
+    async fn test_chunks_reframed_across_boundaries() {
+        let events = collect([
+            Ok("data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hel"),
+            Ok("lo\"}]}}]}\r\n\r\ndata: {\"candidates\":[{\"content\":"),
+            Ok("{\"parts\":[{\"text\":\" world\"}]},\"finishReason\":\"STOP\"}]}\n\n"),
+        ])
+        .await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0]["candidates"][0]["content"]["parts"][0]["text"],
+            "Hello"
+        );
+        assert_eq!(events[1]["candidates"][0]["finishReason"], "STOP");
+    }
+
+    #[tokio::test]
+    async fn test_empty_candidates_forwarded_without_panic() {
+        let events = collect([
+            Ok("data: {\"candidates\":[]}\n\n"),
+            Ok("data: {\"usageMetadata\":{\"totalTokenCount\":3}}\n\n"),
+            Ok("data: {\"response\":{\"candidates\":[{\"finishReason\":\"STOP\"}]}}"),
+        ])
+        .await;
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|event| !is_error(event)));
+        assert_eq!(
+            events[2]["response"]["candidates"][0]["finishReason"],
+            "STOP"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upstream_drop_emits_final_error() {
+        let events = collect([
+            Ok("data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hi\"}]}}]}\n\n"),
+            Err("connection reset"),
+            Ok("data: {\"candidates\":[{\"finishReason\":\"STOP\"}]}\n\n"),
+        ])
+        .await;
+        assert_eq!(events.len(), 2);
+        assert!(!is_error(&events[0]));
+        assert!(events[1]["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("connection reset"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_ending_without_finish_reason_emits_error() {
+        let events = collect([Ok(
+            "data: {\"candidates\":[{\"content\":{\"parts\":[]}}]}\n\n",
+        )])
+        .await;
+        assert_eq!(events.len(), 2);
+        assert!(is_error(&events[1]));
+
+        // 只有错误对象的上游流不再追加错误
+        let events = collect([Ok("data: {\"error\":{\"code\":429}}\n\n")]).await;
+        assert_eq!(events.len(), 1);
+    }
+}
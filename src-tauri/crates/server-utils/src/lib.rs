@@ -9,9 +9,53 @@ use axum::{
     Json,
 };
 use futures::stream;
+use proxycast_core::middleware::ConcurrencyStatus;
 use proxycast_core::models::openai::{ContentPart, FunctionCall, MessageContent, ToolCall};
 use std::collections::HashMap;
 
+pub mod capability_check;
+pub mod count_tokens;
+pub mod fallback;
+pub mod first_token;
+pub mod gemini_stream;
+pub mod model_suggest;
+pub mod multi_choice;
+pub mod output_clamp;
+pub mod pool_models;
+pub mod stream_buffer;
+pub mod structured_output;
+pub mod tool_validation;
+pub mod upstream_body;
+pub mod upstream_override;
+pub mod user_metadata;
+
+pub use capability_check::{
+    check_anthropic_capabilities, check_openai_capabilities, unsupported_capability_response,
+};
+pub use count_tokens::{estimate_input_tokens, CountSource, CountTokensCache, TokenCount};
+pub use fallback::{
+    fallback_allowed, no_credential_response, no_fallback_requested, NO_FALLBACK_HEADER,
+};
+pub use first_token::{track_first_token, StreamEnd};
+pub use gemini_stream::build_gemini_native_stream_response;
+pub use model_suggest::{model_not_found_response, suggest_models, suggest_on_model_not_found};
+pub use multi_choice::{emulate_multiple_choices, plan_openai_request};
+pub use output_clamp::{
+    annotate_output_clamp, ModelOutputLimits, OutputClamp, OutputLimitsCache, OUTPUT_LIMITS_TTL,
+};
+pub use pool_models::{build_pool_model_list, PoolModelsCache, POOL_MODELS_TTL};
+pub use stream_buffer::{
+    deferred_stream_response, ensure_response_mode, should_buffer_stream, BufferFormat,
+};
+pub use structured_output::{
+    apply_structured_output, enforce_structured_response, handle_structured_response,
+    take_pending_structured_output, PendingStructuredOutput,
+};
+pub use tool_validation::validate_anthropic_tools;
+pub use upstream_body::{display_text, json_or_passthrough, passthrough_response, UpstreamBody};
+pub use upstream_override::{apply_upstream_override, reject_self_upstream};
+pub use user_metadata::apply_user_metadata_policy;
+
 /// 从错误信息中解析 HTTP 状态码
 pub fn parse_error_status_code(error_message: &str) -> StatusCode {
     if error_message.contains("429") {
@@ -63,10 +107,39 @@ pub fn build_error_response_with_status(status_code: u16, error_message: &str) -
         .into_response()
 }
 
+/// 本地生成的错误响应标记
+///
+/// 请求没有到达上游（数据库不可用、凭证加载失败等）时附加在响应扩展上，
+/// 熔断器据此不把这类 5xx 记为上游故障。
+#[derive(Debug, Clone, Copy)]
+pub struct LocalError;
+
+/// 构建本地错误响应（附带 [`LocalError`] 标记）
+pub fn local_error_response(status: StatusCode, error_message: &str) -> Response {
+    let mut response = (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "message": error_message
+            }
+        })),
+    )
+        .into_response();
+    response.extensions_mut().insert(LocalError);
+    response
+}
+
+/// 判断响应是否为本地生成的错误
+pub fn is_local_error(response: &Response) -> bool {
+    response.extensions().get::<LocalError>().is_some()
+}
+
 /// CodeWhisperer 响应解析结果
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct CWParsedResponse {
     pub content: String,
+    /// 开启思考模式时，文本开头 `<thinking>` 标签内的思考内容
+    pub thinking: String,
     pub tool_calls: Vec<ToolCall>,
     pub usage_credits: f64,
     pub context_usage_percentage: f64,
@@ -76,13 +149,74 @@ impl CWParsedResponse {
     /// 估算 Token 使用量
     #[allow(dead_code)]
     pub fn estimate_tokens(&self) -> (u32, u32) {
-        let mut output_tokens: u32 = (self.content.len() / 4) as u32;
+        let mut output_tokens: u32 = ((self.content.len() + self.thinking.len()) / 4) as u32;
         for tc in &self.tool_calls {
             output_tokens += (tc.function.arguments.len() / 4) as u32;
         }
         let input_tokens = ((self.context_usage_percentage / 100.0) * 200000.0) as u32;
         (input_tokens, output_tokens)
     }
+
+    /// 是否解析出了文本或工具调用
+    pub fn has_output(&self) -> bool {
+        !self.content.is_empty() || !self.tool_calls.is_empty()
+    }
+}
+
+/// CodeWhisperer 响应解析错误
+#[derive(Debug, Clone)]
+pub enum CWParseError {
+    /// 响应不完整（如网络中断导致截断），`parsed` 为截断前已解析出的内容
+    Partial {
+        parsed: CWParsedResponse,
+        reason: String,
+    },
+    /// 响应中没有任何可识别的事件
+    Unparseable(String),
+}
+
+impl std::fmt::Display for CWParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CWParseError::Partial { reason, .. } => write!(f, "上游响应不完整: {reason}"),
+            CWParseError::Unparseable(reason) => write!(f, "无法解析上游响应: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CWParseError {}
+
+impl CWParseError {
+    /// 尝试使用部分内容恢复
+    ///
+    /// 不完整但已有文本或工具调用的响应视为可恢复，记录警告后返回已解析的内容；
+    /// 其余情况原样返回错误，由调用方重试或返回错误响应。
+    pub fn recover(self) -> Result<CWParsedResponse, CWParseError> {
+        match self {
+            CWParseError::Partial { parsed, reason } if parsed.has_output() => {
+                tracing::warn!(
+                    "[CW_PARSE] 上游响应不完整，使用已解析的部分内容: {}",
+                    reason
+                );
+                Ok(parsed)
+            }
+            other => Err(other),
+        }
+    }
+}
+
+/// 构建上游响应无法解析的 502 响应
+pub fn cw_parse_error_response(error: &CWParseError) -> Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(serde_json::json!({
+            "error": {
+                "type": "upstream_error",
+                "message": error.to_string()
+            }
+        })),
+    )
+        .into_response()
 }
 
 /// 安全截断字符串到指定字符数，避免 UTF-8 边界问题
@@ -185,10 +319,22 @@ pub fn extract_json_object(s: &str) -> Option<&str> {
 }
 
 /// 解析 CodeWhisperer AWS Event Stream 响应
-pub fn parse_cw_response(body: &str) -> CWParsedResponse {
+///
+/// - 完整响应返回 `Ok`
+/// - 末尾事件被截断或事件 JSON 损坏时返回 [`CWParseError::Partial`]，携带已解析的内容
+/// - 找不到任何可识别的事件时返回 [`CWParseError::Unparseable`]
+pub fn parse_cw_response(body: &str) -> Result<CWParsedResponse, CWParseError> {
+    parse_cw_response_bytes(body.as_bytes())
+}
+
+/// 直接解析上游原始字节
+///
+/// Event Stream 帧头是二进制数据，按字节扫描事件 JSON，无需先有损转换为字符串。
+pub fn parse_cw_response_bytes(bytes: &[u8]) -> Result<CWParsedResponse, CWParseError> {
     let mut result = CWParsedResponse::default();
+    let mut events = 0usize;
+    let mut damaged: Option<String> = None;
     let mut tool_map: HashMap<String, (String, String)> = HashMap::new();
-    let bytes = body.as_bytes();
 
     let json_patterns: &[&[u8]] = &[
         b"{\"content\":",
@@ -220,7 +366,12 @@ pub fn parse_cw_response(body: &str) -> CWParsedResponse {
         };
 
         if let Some(json_str) = extract_json_from_bytes(&bytes[start..]) {
-            if let Ok(value) = serde_json::from_str::<serde_json::Value>(&json_str) {
+            let parsed = serde_json::from_str::<serde_json::Value>(&json_str);
+            if let Err(e) = &parsed {
+                damaged.get_or_insert_with(|| format!("事件 JSON 损坏（偏移 {start}）: {e}"));
+            }
+            if let Ok(value) = parsed {
+                events += 1;
                 if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
                     if value.get("followupPrompt").is_none() {
                         result.content.push_str(content);
@@ -272,6 +423,7 @@ pub fn parse_cw_response(body: &str) -> CWParsedResponse {
             }
             pos = start + json_str.len();
         } else {
+            damaged.get_or_insert_with(|| format!("事件 JSON 在偏移 {start} 处被截断"));
             pos = start + 1;
         }
     }
@@ -291,7 +443,24 @@ pub fn parse_cw_response(body: &str) -> CWParsedResponse {
     }
 
     parse_bracket_tool_calls(&mut result);
-    result
+
+    if events == 0 {
+        let reason = damaged.unwrap_or_else(|| {
+            if bytes.is_empty() {
+                "响应为空".to_string()
+            } else {
+                format!("未找到事件（{} 字节）", bytes.len())
+            }
+        });
+        return Err(CWParseError::Unparseable(reason));
+    }
+    match damaged {
+        Some(reason) => Err(CWParseError::Partial {
+            parsed: result,
+            reason,
+        }),
+        None => Ok(result),
+    }
 }
 
 /// 解析 bracket 格式的 tool calls: [Called xxx with args: {...}]
@@ -328,11 +497,35 @@ pub fn parse_bracket_tool_calls(result: &mut CWParsedResponse) {
     }
 }
 
+/// 提取文本开头 `<thinking>...</thinking>` 中的思考内容
+///
+/// 仅在请求启用了思考时调用，否则模型原样输出的标签会被误当作思考内容。
+/// 缺少结束标签时（如输出被截断），剩余文本全部视为思考内容。
+pub fn extract_thinking(result: &mut CWParsedResponse) {
+    let Some(rest) = result.content.trim_start().strip_prefix("<thinking>") else {
+        return;
+    };
+    let (thinking, text) = match rest.split_once("</thinking>") {
+        Some((thinking, text)) => (thinking.to_string(), text.trim_start().to_string()),
+        None => (rest.to_string(), String::new()),
+    };
+    result.thinking = thinking;
+    result.content = text;
+}
+
 /// 构建 Anthropic 非流式响应
 pub fn build_anthropic_response(model: &str, parsed: &CWParsedResponse) -> Response {
     let has_tool_calls = !parsed.tool_calls.is_empty();
     let mut content_array: Vec<serde_json::Value> = Vec::new();
 
+    if !parsed.thinking.is_empty() {
+        // 思考内容由上游文本标签提取，没有签名，不输出空的 `signature`
+        content_array.push(serde_json::json!({
+            "type": "thinking",
+            "thinking": parsed.thinking
+        }));
+    }
+
     if !parsed.content.is_empty() {
         content_array.push(serde_json::json!({
             "type": "text",
@@ -355,7 +548,7 @@ pub fn build_anthropic_response(model: &str, parsed: &CWParsedResponse) -> Respo
         content_array.push(serde_json::json!({"type": "text", "text": ""}));
     }
 
-    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
+    let mut output_tokens: u32 = ((parsed.content.len() + parsed.thinking.len()) / 4) as u32;
     for tc in &parsed.tool_calls {
         output_tokens += (tc.function.arguments.len() / 4) as u32;
     }
@@ -383,9 +576,10 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
     let message_id = format!("msg_{}", uuid::Uuid::new_v4());
     let model = model.to_string();
     let content = parsed.content.clone();
+    let thinking = parsed.thinking.clone();
     let tool_calls = parsed.tool_calls.clone();
 
-    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
+    let mut output_tokens: u32 = ((parsed.content.len() + parsed.thinking.len()) / 4) as u32;
     for tc in &parsed.tool_calls {
         output_tokens += (tc.function.arguments.len() / 4) as u32;
     }
@@ -407,7 +601,28 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
 
     let mut block_index = 0;
 
-    // 2. 文本内容块
+    // 2. 思考内容块
+    if !thinking.is_empty() {
+        let block_start = serde_json::json!({
+            "type": "content_block_start", "index": block_index,
+            "content_block": {"type": "thinking", "thinking": ""}
+        });
+        events.push(format!(
+            "event: content_block_start\ndata: {block_start}\n\n"
+        ));
+        let block_delta = serde_json::json!({
+            "type": "content_block_delta", "index": block_index,
+            "delta": {"type": "thinking_delta", "thinking": thinking}
+        });
+        events.push(format!(
+            "event: content_block_delta\ndata: {block_delta}\n\n"
+        ));
+        let block_stop = serde_json::json!({"type": "content_block_stop", "index": block_index});
+        events.push(format!("event: content_block_stop\ndata: {block_stop}\n\n"));
+        block_index += 1;
+    }
+
+    // 3. 文本内容块
     let block_start = serde_json::json!({
         "type": "content_block_start", "index": block_index,
         "content_block": {"type": "text", "text": ""}
@@ -430,7 +645,7 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
     events.push(format!("event: content_block_stop\ndata: {block_stop}\n\n"));
     block_index += 1;
 
-    // 3. Tool use 块
+    // 4. Tool use 块
     for tc in &tool_calls {
         let block_start = serde_json::json!({
             "type": "content_block_start", "index": block_index,
@@ -461,7 +676,7 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
         block_index += 1;
     }
 
-    // 4. message_delta
+    // 5. message_delta
     let message_delta = serde_json::json!({
         "type": "message_delta",
         "delta": {
@@ -472,7 +687,7 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
     });
     events.push(format!("event: message_delta\ndata: {message_delta}\n\n"));
 
-    // 5. message_stop
+    // 6. message_stop
     let message_stop = serde_json::json!({"type": "message_stop"});
     events.push(format!("event: message_stop\ndata: {message_stop}\n\n"));
 
@@ -607,30 +822,68 @@ pub fn build_gemini_native_request(
 }
 
 /// 健康检查端点响应
-pub async fn health() -> impl IntoResponse {
+///
+/// 附带全局并发使用情况（`server.max_concurrent_requests`）
+pub fn health(concurrency: ConcurrencyStatus) -> Response {
     Json(serde_json::json!({
         "status": "healthy",
-        "version": env!("CARGO_PKG_VERSION")
+        "version": env!("CARGO_PKG_VERSION"),
+        "concurrency": concurrency
     }))
+    .into_response()
+}
+
+/// 版本信息端点响应
+///
+/// 返回版本号、git commit、构建时间和已启用的 cargo features
+pub fn version_info() -> Response {
+    Json(proxycast_core::build_info::build_info()).into_response()
 }
 
-/// 模型列表端点响应
+/// 内置模型列表（模型 ID, 所属厂商）
+pub const BUILTIN_MODELS: &[(&str, &str)] = &[
+    ("claude-sonnet-4-5", "anthropic"),
+    ("claude-sonnet-4-5-20250929", "anthropic"),
+    ("gemini-3-pro-preview", "google"),
+    ("gemini-3-pro-image-preview", "google"),
+    ("gemini-3-flash-preview", "google"),
+    ("gemini-2.5-computer-use-preview-10-2025", "google"),
+    ("gemini-claude-sonnet-4-5", "google"),
+    ("gemini-claude-sonnet-4-5-thinking", "google"),
+    ("gemini-claude-opus-4-5-thinking", "google"),
+    ("qwen3-coder-plus", "alibaba"),
+    ("qwen3-coder-flash", "alibaba"),
+];
+
+/// 已知的模型名（内置模型列表 + Antigravity 模型）
+///
+/// 模型注册表不可用时作为未知模型名称建议的候选，调用方可再追加模型别名。
+pub fn known_model_ids() -> Vec<String> {
+    let mut ids: Vec<String> = BUILTIN_MODELS
+        .iter()
+        .map(|(id, _)| id.to_string())
+        .chain(
+            proxycast_core::models::provider_type::ANTIGRAVITY_MODELS_FALLBACK
+                .iter()
+                .map(|id| id.to_string()),
+        )
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// 模型列表端点响应（内置模型列表，凭证池不可用时使用）
 pub async fn models() -> impl IntoResponse {
+    let data: Vec<serde_json::Value> = BUILTIN_MODELS
+        .iter()
+        .map(
+            |(id, owned_by)| serde_json::json!({"id": id, "object": "model", "owned_by": owned_by}),
+        )
+        .collect();
     Json(serde_json::json!({
         "object": "list",
-        "data": [
-            {"id": "claude-sonnet-4-5", "object": "model", "owned_by": "anthropic"},
-            {"id": "claude-sonnet-4-5-20250929", "object": "model", "owned_by": "anthropic"},
-            {"id": "gemini-3-pro-preview", "object": "model", "owned_by": "google"},
-            {"id": "gemini-3-pro-image-preview", "object": "model", "owned_by": "google"},
-            {"id": "gemini-3-flash-preview", "object": "model", "owned_by": "google"},
-            {"id": "gemini-2.5-computer-use-preview-10-2025", "object": "model", "owned_by": "google"},
-            {"id": "gemini-claude-sonnet-4-5", "object": "model", "owned_by": "google"},
-            {"id": "gemini-claude-sonnet-4-5-thinking", "object": "model", "owned_by": "google"},
-            {"id": "gemini-claude-opus-4-5-thinking", "object": "model", "owned_by": "google"},
-            {"id": "qwen3-coder-plus", "object": "model", "owned_by": "alibaba"},
-            {"id": "qwen3-coder-flash", "object": "model", "owned_by": "alibaba"}
-        ]
+        "data": data
     }))
 }
 
@@ -638,6 +891,21 @@ pub async fn models() -> impl IntoResponse {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_version_endpoint_reports_build_info() {
+        proxycast_core::build_info::register_features(&["custom-protocol"]);
+
+        let response = version_info();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["version"], proxycast_core::version());
+        assert!(body["git_commit"].as_str().is_some_and(|c| !c.is_empty()));
+        assert!(body["features"].as_array().is_some_and(|f| !f.is_empty()));
+    }
+
     #[test]
     fn test_safe_truncate() {
         assert_eq!(safe_truncate("hello", 10), "hello");
@@ -666,6 +934,119 @@ mod tests {
         );
         assert_eq!(extract_json_from_bytes(b"not json"), None);
     }
+
+    /// 模拟 AWS Event Stream：事件 JSON 之间夹杂二进制帧头
+    fn cw_body(events: &[&str]) -> String {
+        events
+            .iter()
+            .map(|e| format!("\u{0}\u{0}\u{1}:event-type\u{7}{e}"))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_cw_response_outcomes() {
+        let complete = cw_body(&[
+            r#"{"content":"Hello"}"#,
+            r#"{"content":", world"}"#,
+            r#"{"name":"get_weather","toolUseId":"tool_1","input":"{\"city\":"}"#,
+            r#"{"toolUseId":"tool_1","input":"\"Paris\"}","stop":true}"#,
+            r#"{"contextUsagePercentage":1.5}"#,
+        ]);
+        let parsed = parse_cw_response(&complete).unwrap();
+        assert_eq!(parsed.content, "Hello, world");
+        assert_eq!(parsed.tool_calls.len(), 1);
+        assert_eq!(
+            parsed.tool_calls[0].function.arguments,
+            r#"{"city":"Paris"}"#
+        );
+        assert_eq!(parsed.context_usage_percentage, 1.5);
+
+        // 网络中断：最后一个事件被截断，已解析内容可恢复
+        let truncated = &complete[..complete.find("world").unwrap() + 3];
+        match parse_cw_response(truncated) {
+            Err(CWParseError::Partial { parsed, reason }) => {
+                assert_eq!(parsed.content, "Hello");
+                assert!(reason.contains("截断"), "reason: {reason}");
+                assert_eq!(
+                    CWParseError::Partial { parsed, reason }
+                        .recover()
+                        .unwrap()
+                        .content,
+                    "Hello"
+                );
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        // 无法识别的内容
+        let garbage = parse_cw_response("<html>502 Bad Gateway</html>").unwrap_err();
+        assert!(matches!(garbage, CWParseError::Unparseable(_)));
+        assert!(garbage.clone().recover().is_err());
+        assert_eq!(
+            cw_parse_error_response(&garbage).status(),
+            StatusCode::BAD_GATEWAY
+        );
+        assert!(matches!(
+            parse_cw_response(""),
+            Err(CWParseError::Unparseable(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_cw_response_bytes_skips_binary_frames() {
+        // Event Stream 帧头包含非 UTF-8 字节，事件 JSON 中的多字节字符须原样保留
+        let mut body = vec![0x00, 0x00, 0x01, 0x2c, 0xff, 0xfe];
+        body.extend_from_slice(r#"{"content":"你好"}"#.as_bytes());
+        body.extend_from_slice(&[0x8f, 0xa3, 0xc3]);
+        body.extend_from_slice(r#"{"content":"，世界"}"#.as_bytes());
+
+        let parsed = parse_cw_response_bytes(&body).unwrap();
+        assert_eq!(parsed.content, "你好，世界");
+    }
+
+    #[tokio::test]
+    async fn test_thinking_extracted_into_anthropic_blocks() {
+        let body = cw_body(&[
+            r#"{"content":"<thinking>先算 2+2"}"#,
+            r#"{"content":"</thinking>\n\n答案是 4"}"#,
+        ]);
+        let mut parsed = parse_cw_response(&body).unwrap();
+        // 请求未启用思考时保留原文
+        assert!(parsed.thinking.is_empty());
+        assert!(parsed.content.starts_with("<thinking>"));
+
+        extract_thinking(&mut parsed);
+        assert_eq!(parsed.thinking, "先算 2+2");
+        assert_eq!(parsed.content, "答案是 4");
+
+        let response = build_anthropic_response("claude-sonnet-4-5", &parsed);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["content"][0]["type"], "thinking");
+        assert_eq!(body["content"][0]["thinking"], "先算 2+2");
+        assert!(body["content"][0].get("signature").is_none());
+        assert_eq!(body["content"][1]["text"], "答案是 4");
+
+        let response = build_anthropic_stream_response("claude-sonnet-4-5", &parsed);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let thinking = body.find("thinking_delta").unwrap();
+        let text = body.find("text_delta").unwrap();
+        assert!(thinking < text);
+
+        // 未闭合的思考标签：剩余内容全部视为思考
+        let mut truncated = CWParsedResponse {
+            content: "<thinking>还没想完".to_string(),
+            ..Default::default()
+        };
+        extract_thinking(&mut truncated);
+        assert_eq!(truncated.thinking, "还没想完");
+        assert!(truncated.content.is_empty());
+    }
 }
 
 #[cfg(test)]
@@ -724,6 +1105,7 @@ mod property_tests {
             .prop_map(
                 |(content, tool_calls, usage_credits, context_usage_percentage)| CWParsedResponse {
                     content,
+                    thinking: String::new(),
                     tool_calls,
                     usage_credits,
                     context_usage_percentage,
@@ -749,7 +1131,7 @@ mod property_tests {
         #[test]
         fn prop_non_streaming_response_empty_content(model in arb_model_name()) {
             let parsed = CWParsedResponse {
-                content: String::new(), tool_calls: Vec::new(),
+                content: String::new(), thinking: String::new(), tool_calls: Vec::new(),
                 usage_credits: 0.0, context_usage_percentage: 0.0,
             };
             let response = build_anthropic_response(&model, &parsed);
@@ -763,7 +1145,7 @@ mod property_tests {
             tool_calls in prop::collection::vec(arb_tool_call(), 1..3)
         ) {
             let parsed = CWParsedResponse {
-                content: String::new(), tool_calls,
+                content: String::new(), thinking: String::new(), tool_calls,
                 usage_credits: 0.0, context_usage_percentage: 50.0,
             };
             let response = build_anthropic_response(&model, &parsed);
@@ -778,7 +1160,7 @@ mod property_tests {
             context_percentage in 0.0f64..100.0f64
         ) {
             let parsed = CWParsedResponse {
-                content: content.clone(), tool_calls: Vec::new(),
+                content: content.clone(), thinking: String::new(), tool_calls: Vec::new(),
                 usage_credits: 0.0, context_usage_percentage: context_percentage,
             };
             let (input_tokens, output_tokens) = parsed.estimate_tokens();
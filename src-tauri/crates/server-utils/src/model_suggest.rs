@@ -0,0 +1,211 @@
+//! 未知模型的名称建议
+//!
+//! 请求的模型不在已知模型（模型注册表或内置模型列表）和别名中、且上游返回“模型不存在”时，
+//! 把上游的通用错误替换为 404，并附带编辑距离最近的已知模型名（`suggestions`），
+//! 方便 CLI 用户发现拼写错误。
+
+use axum::{
+    body::Body,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// 最多返回的建议数量
+const MAX_SUGGESTIONS: usize = 3;
+
+/// 上游错误信息中表示模型不存在的片段（小写）
+const MODEL_NOT_FOUND_MARKERS: &[&str] = &[
+    "model_not_found",
+    "not_found_error",
+    "model not found",
+    "does not exist",
+    "unknown model",
+    "invalid model",
+    "unsupported model",
+    "no such model",
+];
+
+/// 两个字符串的 Levenshtein 编辑距离（按字符计算）
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// 返回与 `model` 最接近的已知模型名
+///
+/// 只保留编辑距离不超过模型名长度三分之一（至少 2）的候选，按距离和名称排序。
+pub fn suggest_models<'a, I>(model: &str, known: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let model = model.to_lowercase();
+    let max_distance = (model.chars().count() / 3).max(2);
+
+    let mut candidates: Vec<(usize, &str)> = known
+        .into_iter()
+        .filter(|candidate| !candidate.is_empty())
+        .map(|candidate| (levenshtein(&model, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+    candidates.dedup_by(|a, b| a.1 == b.1);
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// 上游错误是否表示模型不存在
+///
+/// 404 也要求错误内容提到模型：base_url 配置错误等导致的 404 不是模型名的问题。
+pub fn is_model_not_found_error(status: StatusCode, body: &str) -> bool {
+    if !matches!(status, StatusCode::NOT_FOUND | StatusCode::BAD_REQUEST) {
+        return false;
+    }
+    let body = body.to_lowercase();
+    body.contains("model") && MODEL_NOT_FOUND_MARKERS.iter().any(|m| body.contains(m))
+}
+
+/// 构建模型不存在的 404 响应
+///
+/// 同时带有 Anthropic 的 `type: "error"` 与 OpenAI 的 `error.code`，两种客户端都能解析。
+pub fn model_not_found_response(model: &str, suggestions: &[String]) -> Response {
+    let message = if suggestions.is_empty() {
+        format!("Model '{model}' was not found")
+    } else {
+        format!(
+            "Model '{model}' was not found. Did you mean: {}?",
+            suggestions.join(", ")
+        )
+    };
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "not_found_error",
+                "code": "model_not_found",
+                "param": "model",
+                "message": message,
+                "suggestions": suggestions
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 上游返回“模型不存在”时，替换为带建议的 404 响应
+///
+/// `known` 为已知模型名（模型注册表或内置模型列表，以及别名）。请求的模型本身已知时不做替换，
+/// 此时错误来自上游凭证或账号，而不是拼写错误。
+pub async fn suggest_on_model_not_found(
+    response: Response,
+    model: &str,
+    known: &[String],
+) -> Response {
+    let status = response.status();
+    if !matches!(status, StatusCode::NOT_FOUND | StatusCode::BAD_REQUEST)
+        || known.iter().any(|k| k.eq_ignore_ascii_case(model))
+    {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    if !is_model_not_found_error(status, &String::from_utf8_lossy(&bytes)) {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    let suggestions = suggest_models(model, known.iter().map(String::as_str));
+    tracing::info!("[MODEL] 未知模型 {}，建议: {:?}", model, suggestions);
+    model_not_found_response(model, &suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("模型", "模型"), 0);
+    }
+
+    #[test]
+    fn test_typo_suggests_closest_model() {
+        let known = crate::known_model_ids();
+        let suggestions = suggest_models("claude-sonet-4", known.iter().map(String::as_str));
+        assert_eq!(
+            suggestions.first().map(String::as_str),
+            Some("claude-sonnet-4-5")
+        );
+
+        assert!(suggest_models("totally-different", known.iter().map(String::as_str)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upstream_not_found_rewritten_with_suggestions() {
+        let known = vec!["claude-sonnet-4-5".to_string(), "fast".to_string()];
+        let upstream = (
+            StatusCode::BAD_REQUEST,
+            r#"{"error":{"message":"Invalid model: claude-sonet-4"}}"#,
+        )
+            .into_response();
+
+        let response = suggest_on_model_not_found(upstream, "claude-sonet-4", &known).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "model_not_found");
+        assert_eq!(body["error"]["suggestions"][0], "claude-sonnet-4-5");
+
+        // 其他 400 错误原样返回
+        let upstream = (StatusCode::BAD_REQUEST, "max_tokens too large").into_response();
+        let response = suggest_on_model_not_found(upstream, "claude-sonet-4", &known).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"max_tokens too large");
+    }
+
+    #[test]
+    fn test_not_found_requires_model_error_body() {
+        // base_url 错误导致的 404 不视为模型不存在
+        assert!(!is_model_not_found_error(
+            StatusCode::NOT_FOUND,
+            "404 page not found"
+        ));
+        assert!(!is_model_not_found_error(
+            StatusCode::NOT_FOUND,
+            r#"{"error":{"message":"Not Found","type":"not_found_error"}}"#
+        ));
+        assert!(is_model_not_found_error(
+            StatusCode::NOT_FOUND,
+            r#"{"error":{"code":"model_not_found","message":"The model `gpt-5o` does not exist"}}"#
+        ));
+        assert!(is_model_not_found_error(
+            StatusCode::NOT_FOUND,
+            r#"{"type":"error","error":{"type":"not_found_error","message":"model: claude-sonet-4"}}"#
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+//! 多候选回复（`n` > 1）模拟
+//!
+//! 原生支持 `n` 的 Provider 直接透传；不支持的 Provider 在启用
+//! `server.emulate_multiple_choices` 时并发发起 `n` 次单候选请求，
+//! 再把各次响应的 `choices` 合并为一个响应。流式请求不做模拟，
+//! 未启用模拟时由能力校验返回 400。模拟的候选数不超过
+//! `MAX_EMULATED_CHOICES`，并发调用数不超过 `MAX_CONCURRENT_EMULATED_CALLS`。
+
+use std::future::Future;
+
+use futures::StreamExt;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use proxycast_core::models::openai::ChatCompletionRequest;
+use proxycast_core::models::provider_capabilities::{Capability, CapabilityError};
+use proxycast_core::ProviderType;
+
+use crate::capability_check::required_capabilities_openai;
+
+/// 模拟多候选回复时允许的最大 `n`
+pub const MAX_EMULATED_CHOICES: u32 = 8;
+
+/// 模拟多候选回复时同时进行的上游调用数
+pub const MAX_CONCURRENT_EMULATED_CALLS: usize = 4;
+
+/// 校验 OpenAI 格式请求，并判断是否需要模拟多候选回复
+///
+/// 返回 `Ok(true)` 表示 Provider 不支持 `n` > 1、需要通过多次调用模拟。
+pub fn plan_openai_request(
+    provider: ProviderType,
+    request: &ChatCompletionRequest,
+    emulation_enabled: bool,
+) -> Result<bool, CapabilityError> {
+    let mut required = required_capabilities_openai(request);
+    let emulate = emulation_enabled
+        && !request.stream
+        && required.contains(&Capability::MultipleChoices)
+        && !provider
+            .capabilities()
+            .supports(Capability::MultipleChoices);
+    if emulate {
+        required.retain(|c| *c != Capability::MultipleChoices);
+    }
+    provider.ensure_supports(&required)?;
+    Ok(emulate)
+}
+
+/// 并发发起 `n` 次单候选请求并合并结果
+///
+/// `n` 超过 `MAX_EMULATED_CHOICES` 时返回 400；任意一次调用失败时直接返回该次的错误响应，
+/// 不再发起剩余的调用。
+pub async fn emulate_multiple_choices<F, Fut>(request: &ChatCompletionRequest, call: F) -> Response
+where
+    F: Fn(ChatCompletionRequest) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let count = request.choice_count();
+    if count > MAX_EMULATED_CHOICES {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": {
+                    "type": "invalid_request_error",
+                    "message": format!(
+                        "n={count} exceeds the maximum of {MAX_EMULATED_CHOICES} for providers without native multiple choices"
+                    )
+                }
+            })),
+        )
+            .into_response();
+    }
+
+    let mut single = request.clone();
+    single.n = None;
+
+    let mut responses = futures::stream::iter(0..count)
+        .map(|_| call(single.clone()))
+        .buffered(MAX_CONCURRENT_EMULATED_CALLS);
+
+    let mut bodies = Vec::with_capacity(count as usize);
+    while let Some(response) = responses.next().await {
+        if !response.status().is_success() {
+            return response;
+        }
+        let bytes = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(e) => return invalid_upstream_response(&e.to_string()),
+        };
+        match serde_json::from_slice::<serde_json::Value>(&bytes) {
+            Ok(body) => bodies.push(body),
+            Err(e) => return invalid_upstream_response(&e.to_string()),
+        }
+    }
+
+    Json(merge_choice_responses(bodies)).into_response()
+}
+
+/// 合并多个单候选响应
+///
+/// 以第一个响应为基础，按顺序重新编号 `choices`；
+/// `prompt_tokens` 只计一次，`completion_tokens` 累加。
+pub fn merge_choice_responses(bodies: Vec<serde_json::Value>) -> serde_json::Value {
+    let mut bodies = bodies.into_iter();
+    let Some(mut merged) = bodies.next() else {
+        return serde_json::Value::Null;
+    };
+
+    let mut choices = take_choices(&mut merged);
+    let mut completion_tokens = usage_field(&merged, "completion_tokens");
+    for mut body in bodies {
+        choices.extend(take_choices(&mut body));
+        completion_tokens += usage_field(&body, "completion_tokens");
+    }
+    for (index, choice) in choices.iter_mut().enumerate() {
+        choice["index"] = serde_json::json!(index);
+    }
+    merged["choices"] = serde_json::Value::Array(choices);
+
+    if let Some(usage) = merged.get_mut("usage").filter(|u| u.is_object()) {
+        let prompt_tokens = usage["prompt_tokens"].as_u64().unwrap_or(0);
+        usage["completion_tokens"] = serde_json::json!(completion_tokens);
+        usage["total_tokens"] = serde_json::json!(prompt_tokens + completion_tokens);
+    }
+    merged
+}
+
+fn take_choices(body: &mut serde_json::Value) -> Vec<serde_json::Value> {
+    match body.get_mut("choices").map(serde_json::Value::take) {
+        Some(serde_json::Value::Array(choices)) => choices,
+        _ => Vec::new(),
+    }
+}
+
+fn usage_field(body: &serde_json::Value, field: &str) -> u64 {
+    body["usage"][field].as_u64().unwrap_or(0)
+}
+
+fn invalid_upstream_response(reason: &str) -> Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(serde_json::json!({
+            "error": {"message": format!("Invalid upstream response: {reason}")}
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn request(body: serde_json::Value) -> ChatCompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_plan_openai_request() {
+        let req = request(json!({
+            "model": "claude-sonnet-4-5",
+            "n": 3,
+            "messages": [{"role": "user", "content": "hi"}]
+        }));
+
+        // 原生支持，无需模拟
+        assert_eq!(
+            plan_openai_request(ProviderType::OpenAI, &req, true),
+            Ok(false)
+        );
+        // 不支持且未启用模拟
+        assert_eq!(
+            plan_openai_request(ProviderType::Kiro, &req, false),
+            Err(CapabilityError::Unsupported {
+                provider: ProviderType::Kiro,
+                capability: Capability::MultipleChoices,
+            })
+        );
+        assert_eq!(
+            plan_openai_request(ProviderType::Kiro, &req, true),
+            Ok(true)
+        );
+
+        // 流式请求不模拟
+        let mut streaming = req.clone();
+        streaming.stream = true;
+        assert!(plan_openai_request(ProviderType::Kiro, &streaming, true).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_n3_emulated_yields_three_choices() {
+        let req = request(json!({
+            "model": "claude-sonnet-4-5",
+            "n": 3,
+            "messages": [{"role": "user", "content": "给我讲个笑话"}]
+        }));
+        let calls = AtomicUsize::new(0);
+
+        let response = emulate_multiple_choices(&req, |single| {
+            let i = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                assert_eq!(single.n, None);
+                Json(json!({
+                    "id": format!("chatcmpl-{i}"),
+                    "object": "chat.completion",
+                    "created": 0,
+                    "model": single.model,
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": format!("笑话 {i}")},
+                        "finish_reason": "stop"
+                    }],
+                    "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+                }))
+                .into_response()
+            }
+        })
+        .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let choices = body["choices"].as_array().unwrap();
+        assert_eq!(choices.len(), 3);
+        for (i, choice) in choices.iter().enumerate() {
+            assert_eq!(choice["index"], i);
+        }
+        assert_eq!(body["usage"]["prompt_tokens"], 10);
+        assert_eq!(body["usage"]["completion_tokens"], 15);
+        assert_eq!(body["usage"]["total_tokens"], 25);
+    }
+
+    #[tokio::test]
+    async fn test_emulation_returns_upstream_error() {
+        let req = request(json!({
+            "model": "claude-sonnet-4-5",
+            "n": 2,
+            "messages": [{"role": "user", "content": "hi"}]
+        }));
+        let response = emulate_multiple_choices(&req, |_| async {
+            (StatusCode::TOO_MANY_REQUESTS, "rate limited").into_response()
+        })
+        .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_emulation_caps_choices_and_concurrency() {
+        let req = request(json!({
+            "model": "claude-sonnet-4-5",
+            "n": 500,
+            "messages": [{"role": "user", "content": "hi"}]
+        }));
+        let calls = AtomicUsize::new(0);
+        let response = emulate_multiple_choices(&req, |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Json(json!({"choices": []})).into_response() }
+        })
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let req = request(json!({
+            "model": "claude-sonnet-4-5",
+            "n": MAX_EMULATED_CHOICES,
+            "messages": [{"role": "user", "content": "hi"}]
+        }));
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let response = emulate_multiple_choices(&req, |_| {
+            let in_flight = &in_flight;
+            let peak = &peak;
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Json(json!({"choices": [{"index": 0}]})).into_response()
+            }
+        })
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(peak.load(Ordering::SeqCst) <= MAX_CONCURRENT_EMULATED_CALLS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body["choices"].as_array().unwrap().len(),
+            MAX_EMULATED_CHOICES as usize
+        );
+    }
+}
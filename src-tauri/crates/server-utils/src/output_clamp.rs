@@ -0,0 +1,209 @@
+//! 按模型输出上限裁剪 max_tokens
+//!
+//! 请求的 `max_tokens` 超过模型支持的最大输出时上游会直接返回 400。
+//! 这里根据模型注册表中的 `limits.max_output_tokens` 在分发前把 `max_tokens`
+//! 裁剪到模型上限，并通过响应头告知客户端；注册表中没有上限的模型不做处理。
+
+use axum::http::HeaderValue;
+use axum::response::Response;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 标记 max_tokens 被裁剪的响应头，值为 `<请求值>-><上限>`
+pub const OUTPUT_CLAMP_HEADER: &str = "x-proxycast-max-tokens-clamped";
+
+/// 各模型的最大输出 token 数（模型 ID 不区分大小写）
+#[derive(Debug, Clone, Default)]
+pub struct ModelOutputLimits {
+    ceilings: HashMap<String, u32>,
+}
+
+impl ModelOutputLimits {
+    pub fn new(ceilings: impl IntoIterator<Item = (String, u32)>) -> Self {
+        Self {
+            ceilings: ceilings
+                .into_iter()
+                .map(|(model, ceiling)| (model.to_lowercase(), ceiling))
+                .collect(),
+        }
+    }
+
+    /// 模型的最大输出 token 数
+    pub fn ceiling(&self, model: &str) -> Option<u32> {
+        self.ceilings.get(&model.to_lowercase()).copied()
+    }
+
+    /// 将 `max_tokens` 裁剪到模型上限，发生裁剪时返回裁剪信息
+    pub fn clamp(&self, model: &str, max_tokens: &mut Option<u32>) -> Option<OutputClamp> {
+        let ceiling = self.ceiling(model)?;
+        let requested = (*max_tokens)?;
+        if requested <= ceiling {
+            return None;
+        }
+        *max_tokens = Some(ceiling);
+        tracing::info!(
+            "[OUTPUT_CLAMP] model={} max_tokens {} 超过模型上限，裁剪为 {}",
+            model,
+            requested,
+            ceiling
+        );
+        Some(OutputClamp { requested, ceiling })
+    }
+}
+
+/// 模型输出上限缓存的有效期
+pub const OUTPUT_LIMITS_TTL: Duration = Duration::from_secs(60);
+
+/// 模型输出上限缓存
+///
+/// 注册表刷新后上限随之变化：有效期内复用上次结果，过期后重新读取；
+/// 读取失败时沿用上次成功的结果，从未成功时不裁剪。
+#[derive(Debug, Default)]
+pub struct OutputLimitsCache {
+    entry: Mutex<Option<(Instant, Arc<ModelOutputLimits>)>>,
+}
+
+impl OutputLimitsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 返回缓存的输出上限，过期时通过 `load` 刷新
+    pub fn get_or_load(
+        &self,
+        ttl: Duration,
+        load: impl FnOnce() -> Result<Vec<(String, u32)>, String>,
+    ) -> Arc<ModelOutputLimits> {
+        let mut entry = self.entry.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((loaded_at, limits)) = entry.as_ref() {
+            if loaded_at.elapsed() < ttl {
+                return limits.clone();
+            }
+        }
+        match load() {
+            Ok(ceilings) => {
+                let limits = Arc::new(ModelOutputLimits::new(ceilings));
+                *entry = Some((Instant::now(), limits.clone()));
+                limits
+            }
+            Err(e) => {
+                tracing::warn!("[OUTPUT_CLAMP] 读取模型输出上限失败: {}", e);
+                entry
+                    .as_ref()
+                    .map(|(_, limits)| limits.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// 一次 max_tokens 裁剪
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputClamp {
+    pub requested: u32,
+    pub ceiling: u32,
+}
+
+impl OutputClamp {
+    /// 在响应中添加裁剪标记头
+    pub fn annotate(&self, mut response: Response) -> Response {
+        if let Ok(value) = HeaderValue::from_str(&format!("{}->{}", self.requested, self.ceiling)) {
+            response.headers_mut().insert(OUTPUT_CLAMP_HEADER, value);
+        }
+        response
+    }
+}
+
+/// 在响应中添加裁剪标记头（未裁剪时原样返回）
+pub fn annotate_output_clamp(response: Response, clamp: Option<OutputClamp>) -> Response {
+    match clamp {
+        Some(clamp) => clamp.annotate(response),
+        None => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+
+    fn limits() -> ModelOutputLimits {
+        ModelOutputLimits::new([("claude-sonnet-4-5-20250929".to_string(), 64000)])
+    }
+
+    #[test]
+    fn test_clamps_max_tokens_over_model_ceiling() {
+        let mut max_tokens = Some(128000);
+        let clamp = limits().clamp("Claude-Sonnet-4-5-20250929", &mut max_tokens);
+
+        assert_eq!(max_tokens, Some(64000));
+        assert_eq!(
+            clamp,
+            Some(OutputClamp {
+                requested: 128000,
+                ceiling: 64000
+            })
+        );
+
+        let response = annotate_output_clamp("ok".into_response(), clamp);
+        assert_eq!(
+            response.headers().get(OUTPUT_CLAMP_HEADER).unwrap(),
+            "128000->64000"
+        );
+    }
+
+    #[test]
+    fn test_leaves_requests_within_ceiling_or_unknown_models_unchanged() {
+        let limits = limits();
+
+        let mut within = Some(4096);
+        assert!(limits
+            .clamp("claude-sonnet-4-5-20250929", &mut within)
+            .is_none());
+        assert_eq!(within, Some(4096));
+
+        let mut unknown = Some(1_000_000);
+        assert!(limits.clamp("some-unknown-model", &mut unknown).is_none());
+        assert_eq!(unknown, Some(1_000_000));
+
+        let mut unset = None;
+        assert!(limits
+            .clamp("claude-sonnet-4-5-20250929", &mut unset)
+            .is_none());
+        assert_eq!(unset, None);
+
+        let response = annotate_output_clamp("ok".into_response(), None);
+        assert!(response.headers().get(OUTPUT_CLAMP_HEADER).is_none());
+    }
+
+    #[test]
+    fn test_limits_cache_refreshes_after_ttl_and_keeps_last_on_error() {
+        let cache = OutputLimitsCache::new();
+        let load = |ceiling: u32| move || Ok(vec![("gpt-4o".to_string(), ceiling)]);
+
+        assert_eq!(
+            cache
+                .get_or_load(OUTPUT_LIMITS_TTL, load(16384))
+                .ceiling("gpt-4o"),
+            Some(16384)
+        );
+        // 有效期内不重新读取
+        assert_eq!(
+            cache
+                .get_or_load(OUTPUT_LIMITS_TTL, load(4096))
+                .ceiling("gpt-4o"),
+            Some(16384)
+        );
+        // 过期后读取注册表的最新上限
+        assert_eq!(
+            cache
+                .get_or_load(Duration::ZERO, load(4096))
+                .ceiling("gpt-4o"),
+            Some(4096)
+        );
+        // 读取失败时沿用上次结果
+        let limits = cache.get_or_load(Duration::ZERO, || Err("db locked".to_string()));
+        assert_eq!(limits.ceiling("gpt-4o"), Some(4096));
+    }
+}
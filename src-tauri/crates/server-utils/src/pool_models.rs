@@ -0,0 +1,308 @@
+//! 基于凭证池的模型列表
+//!
+//! `/v1/models` 只返回当前真正可用的模型：凭证池中至少有一个未禁用、
+//! 未处于冷却（健康）的凭证，且该凭证没有排除此模型。每个凭证的候选模型：
+//! - 已发现或配置的支持模型列表（`supported_models`）优先
+//! - 否则按 Provider 类型映射到模型注册表中的 `provider_id`
+//! - 自定义 base_url 的 OpenAI 兼容凭证、本地模型服务和只能提供部分模型的 Kiro
+//!   没有可靠的注册表映射，只使用已发现的模型
+//!
+//! 同一模型可由多个 Provider 提供时只列出一次，`owned_by` 取排在前面的 Provider。
+//! 结果为空时由调用方回退到内置模型列表。
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use proxycast_core::models::provider_pool_model::{CredentialDisplay, ProviderPoolOverview};
+use serde_json::{json, Value};
+
+/// 模型列表缓存有效期
+pub const POOL_MODELS_TTL: Duration = Duration::from_secs(30);
+
+/// Provider 类型对应的模型注册表 `provider_id`
+fn registry_providers(provider_type: &str) -> &'static [&'static str] {
+    match provider_type {
+        "claude" | "claude_oauth" | "anthropic" | "anthropic_compatible" => &["anthropic"],
+        "gemini" | "gemini_api_key" => &["google"],
+        "antigravity" => &["antigravity"],
+        "vertex" => &["google-vertex"],
+        "openai" => &["openai"],
+        "codex" => &["codex"],
+        "azure_openai" => &["azure"],
+        "aws_bedrock" => &["amazon-bedrock"],
+        _ => &[],
+    }
+}
+
+/// 凭证是否能处理请求（未禁用且不在冷却中）
+fn is_serving(credential: &CredentialDisplay) -> bool {
+    !credential.is_disabled && credential.is_healthy
+}
+
+/// 凭证是否可以提供指定模型
+fn credential_serves(credential: &CredentialDisplay, model: &str) -> bool {
+    if credential.not_supported_models.iter().any(|m| m == model) {
+        return false;
+    }
+    credential.supported_models.is_empty() || credential.supported_models.iter().any(|m| m == model)
+}
+
+/// 凭证的候选模型（已发现的模型优先，其次是注册表中对应 Provider 的模型）
+fn candidate_models<'a>(
+    credential: &'a CredentialDisplay,
+    registry: &'a [(String, String)],
+) -> Vec<&'a str> {
+    if !credential.supported_models.is_empty() {
+        return credential
+            .supported_models
+            .iter()
+            .map(String::as_str)
+            .collect();
+    }
+    // 自定义 base_url 的 OpenAI 兼容服务提供的模型与官方注册表无关
+    if credential.provider_type == "openai" && credential.base_url.is_some() {
+        return Vec::new();
+    }
+    let providers = registry_providers(&credential.provider_type);
+    registry
+        .iter()
+        .filter(|(_, provider_id)| providers.contains(&provider_id.as_str()))
+        .map(|(model_id, _)| model_id.as_str())
+        .collect()
+}
+
+/// 根据凭证池概览和注册表模型（模型 ID, provider_id）构建 OpenAI 格式的模型列表
+pub fn build_pool_model_list(
+    overview: &[ProviderPoolOverview],
+    registry: &[(String, String)],
+) -> Vec<Value> {
+    let mut seen = HashSet::new();
+    let mut data = Vec::new();
+
+    for pool in overview {
+        for credential in pool.credentials.iter().filter(|c| is_serving(c)) {
+            for model_id in candidate_models(credential, registry) {
+                if seen.contains(model_id) || !credential_serves(credential, model_id) {
+                    continue;
+                }
+                seen.insert(model_id.to_string());
+                data.push(json!({
+                    "id": model_id,
+                    "object": "model",
+                    "owned_by": pool.provider_type,
+                }));
+            }
+        }
+    }
+
+    data
+}
+
+/// `/v1/models` 结果缓存
+///
+/// 凭证池和注册表变化不频繁，有效期内直接复用上次结果，避免每个请求都读取凭证池概览；
+/// 刷新失败时沿用上次成功的结果。
+#[derive(Debug, Default)]
+pub struct PoolModelsCache {
+    entry: Mutex<Option<(Instant, Vec<Value>)>>,
+}
+
+impl PoolModelsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 返回缓存的模型列表，过期时通过 `load` 刷新
+    ///
+    /// 没有可用结果（从未成功加载，或加载结果为空）时返回 `None`，由调用方回退到内置列表。
+    pub fn get_or_load(
+        &self,
+        ttl: Duration,
+        load: impl FnOnce() -> Result<Vec<Value>, String>,
+    ) -> Option<Vec<Value>> {
+        let mut entry = self.entry.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((loaded_at, models)) = entry.as_ref() {
+            if loaded_at.elapsed() < ttl {
+                return Some(models.clone()).filter(|m| !m.is_empty());
+            }
+        }
+        match load() {
+            Ok(models) => {
+                *entry = Some((Instant::now(), models.clone()));
+                Some(models).filter(|m| !m.is_empty())
+            }
+            Err(e) => {
+                tracing::warn!("[MODELS] 刷新模型列表失败: {}", e);
+                entry
+                    .as_ref()
+                    .map(|(_, models)| models.clone())
+                    .filter(|m| !m.is_empty())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::models::provider_pool_model::{
+        CredentialData, PoolProviderType, PoolStats, ProviderCredential,
+    };
+
+    fn overview(
+        provider_type: PoolProviderType,
+        credentials: Vec<ProviderCredential>,
+    ) -> ProviderPoolOverview {
+        ProviderPoolOverview {
+            provider_type: provider_type.to_string(),
+            stats: PoolStats::from_credentials(&credentials),
+            credentials: credentials.iter().map(|c| c.into()).collect(),
+        }
+    }
+
+    fn credential(provider_type: PoolProviderType) -> ProviderCredential {
+        ProviderCredential::new(
+            provider_type,
+            CredentialData::ClaudeKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        )
+    }
+
+    fn registry() -> Vec<(String, String)> {
+        [
+            ("claude-sonnet-4-5", "anthropic"),
+            ("claude-opus-4-5", "anthropic"),
+            ("gemini-2.5-pro", "google"),
+            ("gpt-4o", "openai"),
+        ]
+        .iter()
+        .map(|(id, provider)| (id.to_string(), provider.to_string()))
+        .collect()
+    }
+
+    fn ids(models: &[Value]) -> Vec<&str> {
+        models.iter().map(|m| m["id"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_lists_models_of_providers_with_serving_credentials() {
+        let mut cooling = credential(PoolProviderType::Gemini);
+        cooling.is_healthy = false;
+        let mut disabled = credential(PoolProviderType::OpenAI);
+        disabled.is_disabled = true;
+
+        let models = build_pool_model_list(
+            &[
+                overview(PoolProviderType::Gemini, vec![cooling]),
+                overview(
+                    PoolProviderType::Claude,
+                    vec![credential(PoolProviderType::Claude)],
+                ),
+                overview(PoolProviderType::OpenAI, vec![disabled]),
+            ],
+            &registry(),
+        );
+
+        assert_eq!(ids(&models), vec!["claude-sonnet-4-5", "claude-opus-4-5"]);
+        assert!(models.iter().all(|m| m["owned_by"] == "claude"));
+        assert!(models.iter().all(|m| m["object"] == "model"));
+    }
+
+    #[test]
+    fn test_credential_model_restrictions_respected() {
+        let mut sonnet_only = credential(PoolProviderType::Claude);
+        sonnet_only.supported_models = vec!["claude-sonnet-4-5".to_string()];
+        let mut no_opus = credential(PoolProviderType::Claude);
+        no_opus.not_supported_models = vec!["claude-opus-4-5".to_string()];
+
+        let models = build_pool_model_list(
+            &[
+                overview(PoolProviderType::Claude, vec![sonnet_only]),
+                overview(PoolProviderType::Claude, vec![no_opus.clone()]),
+            ],
+            &registry(),
+        );
+        // 重复的模型只列出一次
+        assert_eq!(ids(&models), vec!["claude-sonnet-4-5"]);
+        assert_eq!(models[0]["owned_by"], "claude");
+
+        // 另一个凭证可以提供被排除的模型
+        let models = build_pool_model_list(
+            &[overview(
+                PoolProviderType::Claude,
+                vec![no_opus, credential(PoolProviderType::Claude)],
+            )],
+            &registry(),
+        );
+        assert_eq!(ids(&models), vec!["claude-sonnet-4-5", "claude-opus-4-5"]);
+    }
+
+    #[test]
+    fn test_discovered_models_used_without_registry_mapping() {
+        // Kiro 只能提供部分 Anthropic 模型，未发现模型时不按注册表猜测
+        let models = build_pool_model_list(
+            &[overview(
+                PoolProviderType::Kiro,
+                vec![credential(PoolProviderType::Kiro)],
+            )],
+            &registry(),
+        );
+        assert!(models.is_empty());
+
+        let mut kiro = credential(PoolProviderType::Kiro);
+        kiro.supported_models = vec!["claude-sonnet-4-5".to_string()];
+        let mut local = credential(PoolProviderType::Ollama);
+        local.supported_models = vec!["llama3.2".to_string(), "qwen2.5".to_string()];
+
+        let models = build_pool_model_list(
+            &[
+                overview(PoolProviderType::Kiro, vec![kiro]),
+                overview(PoolProviderType::Ollama, vec![local]),
+            ],
+            &[],
+        );
+        assert_eq!(
+            ids(&models),
+            vec!["claude-sonnet-4-5", "llama3.2", "qwen2.5"]
+        );
+        assert_eq!(models[2]["owned_by"], "ollama");
+    }
+
+    #[test]
+    fn test_custom_openai_endpoint_not_mapped_to_registry() {
+        let custom = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: Some("https://api.example.com/v1".to_string()),
+            },
+        );
+        let models = build_pool_model_list(
+            &[overview(PoolProviderType::OpenAI, vec![custom])],
+            &registry(),
+        );
+        assert!(models.is_empty());
+    }
+
+    #[test]
+    fn test_cache_reuses_result_and_falls_back() {
+        let cache = PoolModelsCache::new();
+        let model = json!({"id": "gpt-4o", "object": "model", "owned_by": "openai"});
+
+        // 空结果交给调用方回退
+        assert_eq!(cache.get_or_load(POOL_MODELS_TTL, || Ok(Vec::new())), None);
+
+        let cache = PoolModelsCache::new();
+        let loaded = cache.get_or_load(POOL_MODELS_TTL, || Ok(vec![model.clone()]));
+        assert_eq!(loaded, Some(vec![model.clone()]));
+        // 有效期内不再加载
+        let cached = cache.get_or_load(POOL_MODELS_TTL, || panic!("不应重新加载"));
+        assert_eq!(cached, Some(vec![model.clone()]));
+        // 过期后加载失败时沿用上次结果
+        let stale = cache.get_or_load(Duration::ZERO, || Err("db locked".to_string()));
+        assert_eq!(stale, Some(vec![model]));
+    }
+}
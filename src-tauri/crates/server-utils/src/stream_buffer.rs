@@ -0,0 +1,767 @@
+//! 流式 / 非流式响应互转
+//!
+//! 部分客户端请求 `stream: true`，但经过某些代理后无法消费 SSE；部分 Provider
+//! 又只支持流式输出。启用 `server.force_buffer_stream`（或请求携带
+//! `X-ProxyCast-Buffer: true`）后，代理在服务端消费完整的上游流，聚合内容与用量后
+//! 以单个非流式响应返回。
+//!
+//! 反过来，客户端坚持流式而上游返回了非流式响应时，把它转换成只有一个数据块的
+//! 合成流，保证客户端拿到的响应形式与请求一致。
+
+use std::future::Future;
+use std::time::Duration;
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{stream, StreamExt};
+use serde_json::{json, Map, Value};
+
+use crate::upstream_body::display_text;
+
+/// 请求级缓冲开关请求头（`true`/`1` 强制缓冲，`false`/`0` 关闭缓冲）
+pub const BUFFER_HEADER: &str = "x-proxycast-buffer";
+
+/// 聚合或转换时读取的响应体上限（与服务端请求体上限一致）
+pub const MAX_BUFFERED_BODY_BYTES: usize = 100 * 1024 * 1024;
+
+/// 响应协议格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferFormat {
+    /// OpenAI Chat Completions
+    OpenAi,
+    /// Anthropic Messages
+    Anthropic,
+}
+
+/// 是否把流式请求缓冲为非流式响应（请求头优先于配置）
+pub fn should_buffer_stream(headers: &HeaderMap, force: bool) -> bool {
+    let value = headers
+        .get(BUFFER_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_ascii_lowercase());
+    match value.as_deref() {
+        Some("1" | "true" | "yes" | "on") => true,
+        Some("0" | "false" | "no" | "off") => false,
+        _ => force,
+    }
+}
+
+/// 把响应转换为客户端期望的形式
+///
+/// `stream` 为 true 时把非流式 JSON 响应转换为合成流，否则把 SSE 响应聚合为
+/// 非流式 JSON。错误响应和已是目标形式的响应原样返回。
+pub async fn ensure_response_mode(
+    response: Response,
+    format: BufferFormat,
+    stream: bool,
+) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let is_sse = content_type.starts_with("text/event-stream");
+    let is_json = content_type.starts_with("application/json");
+
+    if stream && is_json {
+        stream_buffered_response(response, format).await
+    } else if !stream && is_sse {
+        buffer_stream_response(response, format).await
+    } else {
+        response
+    }
+}
+
+/// 消费 SSE 响应并聚合为非流式响应
+pub async fn buffer_stream_response(response: Response, format: BufferFormat) -> Response {
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BUFFERED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[STREAM_BUFFER] 读取上游流失败: {}", e);
+            return stream_error_response(&e.to_string());
+        }
+    };
+    let Ok(text) = std::str::from_utf8(&bytes) else {
+        tracing::warn!(
+            "[STREAM_BUFFER] 上游流不是合法 UTF-8（{} 字节）",
+            bytes.len()
+        );
+        return stream_error_response("Upstream stream is not valid UTF-8");
+    };
+    let events = parse_sse_events(text);
+
+    if let Some(error) = events.iter().find_map(|event| event.get("error")) {
+        tracing::warn!("[STREAM_BUFFER] 上游流返回错误: {}", error);
+        return (StatusCode::BAD_GATEWAY, Json(json!({ "error": error }))).into_response();
+    }
+
+    let collected = match format {
+        BufferFormat::OpenAi => collect_openai_stream(&events),
+        BufferFormat::Anthropic => collect_anthropic_stream(&events),
+    };
+    let Some(collected) = collected else {
+        return stream_error_response("Upstream stream ended without any data");
+    };
+
+    let mut response = Json(collected).into_response();
+    for (name, value) in parts.headers.iter() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            response.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
+
+/// 把非流式 JSON 响应转换为单数据块的合成流
+pub async fn stream_buffered_response(response: Response, format: BufferFormat) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BUFFERED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[STREAM_BUFFER] 读取上游响应失败: {}", e);
+            return stream_error_response(&e.to_string());
+        }
+    };
+    let Ok(body) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let sse = match format {
+        BufferFormat::OpenAi => openai_response_to_sse(&body),
+        BufferFormat::Anthropic => anthropic_response_to_sse(&body),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/event-stream"),
+    );
+    parts
+        .headers
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    Response::from_parts(parts, Body::from(sse))
+}
+
+/// 上游需要完整缓冲后才能转换的流式请求：`call` 在 `grace` 内未完成时先返回 SSE 响应头，
+/// 再在响应体中等待 `call`
+///
+/// 缓冲期间响应体没有数据，由心跳层发送保活帧。在 `grace` 内完成的调用原样返回，
+/// 调用方仍可按状态码重试或故障转移；`grace` 为 0（未启用心跳）时直接等待。
+/// 响应头发出后，`call` 返回的 SSE 响应直接转发，非流式 JSON 转换为单数据块的合成流，
+/// 错误响应转换为流内错误事件。客户端断开时响应体被丢弃，`call` 随之取消。
+pub async fn deferred_stream_response<F>(call: F, format: BufferFormat, grace: Duration) -> Response
+where
+    F: Future<Output = Response> + Send + 'static,
+{
+    if grace.is_zero() {
+        return call.await;
+    }
+    let mut call = Box::pin(call);
+    if let Ok(response) = tokio::time::timeout(grace, &mut call).await {
+        return response;
+    }
+
+    let body = stream::once(async move {
+        let response = call.await;
+        let status = response.status();
+        let response = ensure_response_mode(response, format, true).await;
+        if status.is_success() {
+            return response.into_body().into_data_stream().left_stream();
+        }
+        let bytes = axum::body::to_bytes(response.into_body(), MAX_BUFFERED_BODY_BYTES)
+            .await
+            .unwrap_or_default();
+        let error = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|body| body.get("error").cloned())
+            .unwrap_or_else(|| {
+                json!({
+                    "type": "upstream_error",
+                    "message": display_text(&bytes),
+                })
+            });
+        tracing::warn!(
+            "[STREAM_BUFFER] 流式响应头已发送，上游错误转为流内事件: {}",
+            status
+        );
+        let frame = match format {
+            BufferFormat::OpenAi => sse_data(&json!({ "error": error })),
+            BufferFormat::Anthropic => {
+                sse_event("error", &json!({ "type": "error", "error": error }))
+            }
+        };
+        stream::iter([Ok::<_, axum::Error>(Bytes::from(frame))]).right_stream()
+    })
+    .flatten();
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::from_stream(body))
+        .unwrap_or_else(|e| stream_error_response(&e.to_string()))
+}
+
+fn stream_error_response(message: &str) -> Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(json!({
+            "error": {
+                "type": "upstream_stream_error",
+                "message": message
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 解析 SSE 文本中的 `data:` 事件（忽略注释、心跳和 `[DONE]`）
+pub fn parse_sse_events(text: &str) -> Vec<Value> {
+    text.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim)
+        .filter(|data| !data.is_empty() && *data != "[DONE]")
+        .filter_map(|data| serde_json::from_str(data).ok())
+        .collect()
+}
+
+/// 追加字符串字段
+fn append_str(target: &mut Value, key: &str, piece: &str) {
+    match target.get_mut(key) {
+        Some(Value::String(existing)) => existing.push_str(piece),
+        _ => target[key] = Value::String(piece.to_string()),
+    }
+}
+
+/// 聚合 OpenAI `chat.completion.chunk` 事件为 `chat.completion`
+pub fn collect_openai_stream(events: &[Value]) -> Option<Value> {
+    let first = events.first()?;
+    let mut choices: Vec<Value> = Vec::new();
+    let mut usage = Value::Null;
+
+    for event in events {
+        if let Some(u) = event.get("usage").filter(|u| !u.is_null()) {
+            usage = u.clone();
+        }
+        let Some(deltas) = event.get("choices").and_then(Value::as_array) else {
+            continue;
+        };
+        for chunk in deltas {
+            let index = chunk.get("index").and_then(Value::as_u64).unwrap_or(0) as usize;
+            while choices.len() <= index {
+                choices.push(json!({
+                    "index": choices.len(),
+                    "message": { "role": "assistant", "content": Value::Null },
+                    "finish_reason": Value::Null,
+                }));
+            }
+            let choice = &mut choices[index];
+            if let Some(reason) = chunk.get("finish_reason").filter(|r| !r.is_null()) {
+                choice["finish_reason"] = reason.clone();
+            }
+            let Some(delta) = chunk.get("delta") else {
+                continue;
+            };
+            let message = &mut choice["message"];
+            for key in ["content", "reasoning_content"] {
+                if let Some(piece) = delta.get(key).and_then(Value::as_str) {
+                    append_str(message, key, piece);
+                }
+            }
+            if let Some(calls) = delta.get("tool_calls").and_then(Value::as_array) {
+                merge_openai_tool_calls(message, calls);
+            }
+        }
+    }
+
+    let mut response = json!({
+        "id": first.get("id").cloned().unwrap_or(Value::Null),
+        "object": "chat.completion",
+        "created": first.get("created").cloned().unwrap_or(Value::Null),
+        "model": first.get("model").cloned().unwrap_or(Value::Null),
+        "choices": choices,
+    });
+    if !usage.is_null() {
+        response["usage"] = usage;
+    }
+    Some(response)
+}
+
+/// 按 `index` 合并工具调用增量
+fn merge_openai_tool_calls(message: &mut Value, calls: &[Value]) {
+    if !message.get("tool_calls").is_some_and(Value::is_array) {
+        message["tool_calls"] = json!([]);
+    }
+    let Some(merged) = message["tool_calls"].as_array_mut() else {
+        return;
+    };
+    for call in calls {
+        let index = call.get("index").and_then(Value::as_u64).unwrap_or(0) as usize;
+        while merged.len() <= index {
+            merged.push(json!({
+                "id": "",
+                "type": "function",
+                "function": { "name": "", "arguments": "" },
+            }));
+        }
+        let target = &mut merged[index];
+        if let Some(id) = call.get("id").and_then(Value::as_str) {
+            target["id"] = json!(id);
+        }
+        if let Some(function) = call.get("function") {
+            for key in ["name", "arguments"] {
+                if let Some(piece) = function.get(key).and_then(Value::as_str) {
+                    append_str(&mut target["function"], key, piece);
+                }
+            }
+        }
+    }
+}
+
+/// 聚合 Anthropic Messages 流事件为完整的 `message`
+pub fn collect_anthropic_stream(events: &[Value]) -> Option<Value> {
+    let mut message: Option<Value> = None;
+    let mut blocks: Vec<Value> = Vec::new();
+    let mut partial_json: Vec<String> = Vec::new();
+
+    for event in events {
+        match event.get("type").and_then(Value::as_str) {
+            Some("message_start") => {
+                message = event.get("message").cloned();
+            }
+            Some("content_block_start") => {
+                let index = event.get("index").and_then(Value::as_u64).unwrap_or(0) as usize;
+                while blocks.len() <= index {
+                    blocks.push(Value::Null);
+                    partial_json.push(String::new());
+                }
+                blocks[index] = event.get("content_block").cloned().unwrap_or(Value::Null);
+            }
+            Some("content_block_delta") => {
+                let index = event.get("index").and_then(Value::as_u64).unwrap_or(0) as usize;
+                let (Some(block), Some(delta)) = (blocks.get_mut(index), event.get("delta")) else {
+                    continue;
+                };
+                match delta.get("type").and_then(Value::as_str) {
+                    Some("text_delta") => {
+                        append_str(block, "text", delta["text"].as_str().unwrap_or_default())
+                    }
+                    Some("thinking_delta") => append_str(
+                        block,
+                        "thinking",
+                        delta["thinking"].as_str().unwrap_or_default(),
+                    ),
+                    Some("signature_delta") => append_str(
+                        block,
+                        "signature",
+                        delta["signature"].as_str().unwrap_or_default(),
+                    ),
+                    Some("input_json_delta") => partial_json[index]
+                        .push_str(delta["partial_json"].as_str().unwrap_or_default()),
+                    _ => {}
+                }
+            }
+            Some("message_delta") => {
+                let Some(message) = message.as_mut() else {
+                    continue;
+                };
+                if let Some(delta) = event.get("delta").and_then(Value::as_object) {
+                    for (key, value) in delta {
+                        message[key] = value.clone();
+                    }
+                }
+                if let Some(usage) = event.get("usage").and_then(Value::as_object) {
+                    if !message.get("usage").is_some_and(Value::is_object) {
+                        message["usage"] = json!({});
+                    }
+                    for (key, value) in usage {
+                        message["usage"][key] = value.clone();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut message = message?;
+    for (block, input) in blocks.iter_mut().zip(&partial_json) {
+        if !input.is_empty() {
+            block["input"] = serde_json::from_str(input).unwrap_or_else(|_| json!({}));
+        }
+    }
+    message["content"] = Value::Array(blocks.into_iter().filter(|b| !b.is_null()).collect());
+    Some(message)
+}
+
+fn sse_data(data: &Value) -> String {
+    format!("data: {}\n\n", data)
+}
+
+fn sse_event(event: &str, data: &Value) -> String {
+    format!("event: {}\ndata: {}\n\n", event, data)
+}
+
+/// 把 OpenAI `chat.completion` 转换为单数据块的流
+pub fn openai_response_to_sse(response: &Value) -> String {
+    let choices: Vec<Value> = response
+        .get("choices")
+        .and_then(Value::as_array)
+        .map(|choices| {
+            choices
+                .iter()
+                .enumerate()
+                .map(|(i, choice)| {
+                    let mut delta = choice.get("message").cloned().unwrap_or_else(|| json!({}));
+                    if let Some(calls) = delta.get_mut("tool_calls").and_then(Value::as_array_mut)
+                    {
+                        for (index, call) in calls.iter_mut().enumerate() {
+                            call["index"] = json!(index);
+                        }
+                    }
+                    json!({
+                        "index": choice.get("index").cloned().unwrap_or(json!(i)),
+                        "delta": delta,
+                        "finish_reason": choice.get("finish_reason").cloned().unwrap_or(Value::Null),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut chunk = json!({
+        "id": response.get("id").cloned().unwrap_or(Value::Null),
+        "object": "chat.completion.chunk",
+        "created": response.get("created").cloned().unwrap_or(Value::Null),
+        "model": response.get("model").cloned().unwrap_or(Value::Null),
+        "choices": choices,
+    });
+    if let Some(usage) = response.get("usage") {
+        chunk["usage"] = usage.clone();
+    }
+    format!("{}data: [DONE]\n\n", sse_data(&chunk))
+}
+
+/// 把 Anthropic `message` 转换为合成的 Messages 流
+pub fn anthropic_response_to_sse(response: &Value) -> String {
+    let mut start = response.clone();
+    start["content"] = json!([]);
+    start["stop_reason"] = Value::Null;
+    start["stop_sequence"] = Value::Null;
+    let mut out = sse_event(
+        "message_start",
+        &json!({ "type": "message_start", "message": start }),
+    );
+
+    let blocks = response
+        .get("content")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    for (index, block) in blocks.iter().enumerate() {
+        let (empty, delta) = match block.get("type").and_then(Value::as_str) {
+            Some("text") => (
+                json!({ "type": "text", "text": "" }),
+                Some(json!({ "type": "text_delta", "text": block["text"] })),
+            ),
+            Some("thinking") => (
+                json!({ "type": "thinking", "thinking": "", "signature": block["signature"] }),
+                Some(json!({ "type": "thinking_delta", "thinking": block["thinking"] })),
+            ),
+            Some("tool_use") => {
+                let mut empty = block.clone();
+                empty["input"] = json!({});
+                let input = block.get("input").cloned().unwrap_or_else(|| json!({}));
+                (
+                    empty,
+                    Some(json!({ "type": "input_json_delta", "partial_json": input.to_string() })),
+                )
+            }
+            _ => (block.clone(), None),
+        };
+        out.push_str(&sse_event(
+            "content_block_start",
+            &json!({ "type": "content_block_start", "index": index, "content_block": empty }),
+        ));
+        if let Some(delta) = delta {
+            out.push_str(&sse_event(
+                "content_block_delta",
+                &json!({ "type": "content_block_delta", "index": index, "delta": delta }),
+            ));
+        }
+        out.push_str(&sse_event(
+            "content_block_stop",
+            &json!({ "type": "content_block_stop", "index": index }),
+        ));
+    }
+
+    let mut delta = Map::new();
+    delta.insert(
+        "stop_reason".to_string(),
+        response.get("stop_reason").cloned().unwrap_or(Value::Null),
+    );
+    delta.insert(
+        "stop_sequence".to_string(),
+        response
+            .get("stop_sequence")
+            .cloned()
+            .unwrap_or(Value::Null),
+    );
+    out.push_str(&sse_event(
+        "message_delta",
+        &json!({
+            "type": "message_delta",
+            "delta": delta,
+            "usage": response.get("usage").cloned().unwrap_or_else(|| json!({})),
+        }),
+    ));
+    out.push_str(&sse_event(
+        "message_stop",
+        &json!({ "type": "message_stop" }),
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sse_response(body: String) -> Response {
+        Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn body_string(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_buffer_header_overrides_config() {
+        let mut headers = HeaderMap::new();
+        assert!(!should_buffer_stream(&headers, false));
+        assert!(should_buffer_stream(&headers, true));
+
+        headers.insert(BUFFER_HEADER, HeaderValue::from_static("true"));
+        assert!(should_buffer_stream(&headers, false));
+        headers.insert(BUFFER_HEADER, HeaderValue::from_static("0"));
+        assert!(!should_buffer_stream(&headers, true));
+    }
+
+    #[tokio::test]
+    async fn test_openai_stream_round_trip_preserves_content_and_usage() {
+        let chunks = [
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+                "choices": [{"index": 0, "delta": {"role": "assistant", "content": "Hel"}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+                "choices": [{"index": 0, "delta": {"content": "lo"}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+                "choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "id": "call_1", "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":"}}]}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+                "choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "function": {"arguments": "\"Paris\"}"}}]},
+                    "finish_reason": "tool_calls"}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+                "choices": [], "usage": {"prompt_tokens": 12, "completion_tokens": 7, "total_tokens": 19}}),
+        ];
+        let mut sse = String::from(": ping\n\n");
+        for chunk in &chunks {
+            sse.push_str(&sse_data(chunk));
+        }
+        sse.push_str("data: [DONE]\n\n");
+
+        // 流式 -> 非流式
+        let buffered = ensure_response_mode(sse_response(sse), BufferFormat::OpenAi, false).await;
+        assert!(buffered.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("application/json"));
+        let body: Value = serde_json::from_str(&body_string(buffered).await).unwrap();
+        assert_eq!(body["object"], "chat.completion");
+        assert_eq!(body["choices"][0]["message"]["content"], "Hello");
+        let call = &body["choices"][0]["message"]["tool_calls"][0];
+        assert_eq!(call["id"], "call_1");
+        assert_eq!(call["function"]["arguments"], "{\"city\":\"Paris\"}");
+        assert_eq!(body["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(body["usage"]["total_tokens"], 19);
+
+        // 非流式 -> 流式
+        let streamed = ensure_response_mode(
+            Json(body.clone()).into_response(),
+            BufferFormat::OpenAi,
+            true,
+        )
+        .await;
+        assert_eq!(
+            streamed.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let text = body_string(streamed).await;
+        assert!(text.ends_with("data: [DONE]\n\n"));
+        let events = parse_sse_events(&text);
+        assert_eq!(events.len(), 1);
+        assert_eq!(collect_openai_stream(&events).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_stream_round_trip_preserves_content_and_usage() {
+        let message = json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-sonnet-4-5",
+            "content": [
+                {"type": "text", "text": "Let me check."},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+            ],
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 20, "output_tokens": 15}
+        });
+
+        // 非流式 -> 流式
+        let streamed = ensure_response_mode(
+            Json(message.clone()).into_response(),
+            BufferFormat::Anthropic,
+            true,
+        )
+        .await;
+        let text = body_string(streamed).await;
+        assert!(text.starts_with("event: message_start\n"));
+        assert!(text.ends_with("event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"));
+
+        // 流式 -> 非流式
+        let buffered =
+            ensure_response_mode(sse_response(text), BufferFormat::Anthropic, false).await;
+        let body: Value = serde_json::from_str(&body_string(buffered).await).unwrap();
+        assert_eq!(body, message);
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_and_matching_modes_pass_through() {
+        let error = (StatusCode::TOO_MANY_REQUESTS, "rate limited").into_response();
+        let response = ensure_response_mode(error, BufferFormat::OpenAi, true).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let json_response = Json(json!({"ok": true})).into_response();
+        let response = ensure_response_mode(json_response, BufferFormat::OpenAi, false).await;
+        assert_eq!(body_string(response).await, "{\"ok\":true}");
+
+        let failed = sse_response(
+            "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\"}}\n\n"
+                .to_string(),
+        );
+        let response = ensure_response_mode(failed, BufferFormat::Anthropic, false).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_stream_sends_headers_before_slow_call_completes() {
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let call = async move {
+            rx.await.ok();
+            Json(json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-sonnet-4-5",
+                "content": [{"type": "text", "text": "hello"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 3, "output_tokens": 1}
+            }))
+            .into_response()
+        };
+        let response =
+            deferred_stream_response(call, BufferFormat::Anthropic, Duration::from_millis(10))
+                .await;
+        // 上游尚未返回时响应头已可发送
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        tx.send(()).unwrap();
+        let body = body_string(response).await;
+        assert!(body.contains("event: message_start"), "body: {body}");
+        assert!(body.contains("hello"), "body: {body}");
+    }
+
+    #[tokio::test]
+    async fn test_deferred_stream_keeps_status_of_fast_errors() {
+        let call = async {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": {"message": "rate limited"}})),
+            )
+                .into_response()
+        };
+        let response =
+            deferred_stream_response(call, BufferFormat::OpenAi, Duration::from_secs(5)).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_stream_reports_late_error_in_stream() {
+        let grace = Duration::from_millis(10);
+        let call = async move {
+            tokio::time::sleep(grace * 5).await;
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({"error": {"message": "Token refresh failed"}})),
+            )
+                .into_response()
+        };
+        let response = deferred_stream_response(call, BufferFormat::Anthropic, grace).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(body.starts_with("event: error\n"), "body: {body}");
+        let events = parse_sse_events(&body);
+        assert_eq!(events[0]["error"]["message"], "Token refresh failed");
+
+        let call = async move {
+            tokio::time::sleep(grace * 5).await;
+            (StatusCode::BAD_GATEWAY, "upstream down").into_response()
+        };
+        let body =
+            body_string(deferred_stream_response(call, BufferFormat::OpenAi, grace).await).await;
+        let events = parse_sse_events(&body);
+        assert_eq!(events[0]["error"]["message"], "upstream down");
+    }
+
+    #[tokio::test]
+    async fn test_buffer_rejects_oversized_stream() {
+        let chunk = Bytes::from(vec![b' '; 1024 * 1024]);
+        let chunks = (0..=MAX_BUFFERED_BODY_BYTES / chunk.len())
+            .map(move |_| Ok::<_, std::io::Error>(chunk.clone()));
+        let response = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from_stream(stream::iter(chunks)))
+            .unwrap();
+        let response = buffer_stream_response(response, BufferFormat::OpenAi).await;
+        assert!(!response.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_buffer_rejects_non_utf8_stream() {
+        let mut body = b"data: {\"choices\":[{\"delta\":{\"content\":\"caf".to_vec();
+        body.extend_from_slice(b"\xe9\"}}]}\n\n");
+        let response = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from(body))
+            .unwrap();
+        let response = buffer_stream_response(response, BufferFormat::OpenAi).await;
+        assert!(!response.status().is_success());
+        assert!(!body_string(response).await.contains('\u{FFFD}'));
+    }
+}
@@ -0,0 +1,502 @@
+//! 结构化输出（`response_format`）
+//!
+//! 原生支持结构化输出的 Provider 直接透传 `response_format`；
+//! 不支持的 Provider 移除该字段，改为注入一条系统指令要求模型只输出 JSON，
+//! 并在非流式响应返回后解析、修复（去掉代码块围栏、截取 JSON 片段、删除尾随逗号）
+//! 和按 Schema 校验内容。启用 `server.strict_structured_output` 时，
+//! 无法修复或不符合 Schema 的输出返回 502，否则原样返回。
+//!
+//! 流式请求只注入指令；流被缓冲为非流式响应时，在聚合后按
+//! [`PendingStructuredOutput`] 标记校验，直接返回给客户端的流不做校验。
+
+use std::sync::OnceLock;
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use proxycast_core::models::openai::{
+    ChatCompletionRequest, ChatMessage, MessageContent, ResponseFormat,
+};
+use proxycast_core::models::provider_capabilities::Capability;
+use proxycast_core::ProviderType;
+use regex::Regex;
+use serde_json::Value;
+
+/// 结构化输出校验错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructuredOutputError {
+    /// 输出不是合法 JSON 且无法修复
+    InvalidJson,
+    /// 输出不符合 Schema
+    SchemaMismatch(String),
+}
+
+impl std::fmt::Display for StructuredOutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StructuredOutputError::InvalidJson => {
+                write!(
+                    f,
+                    "Model output is not valid JSON and could not be repaired"
+                )
+            }
+            StructuredOutputError::SchemaMismatch(reason) => {
+                write!(f, "Model output does not match the JSON schema: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StructuredOutputError {}
+
+/// 流式响应上待校验的结构化输出格式（响应扩展）
+///
+/// Provider 调用时无法校验流式响应；流最终被缓冲为非流式响应时按此格式校验。
+#[derive(Debug, Clone)]
+pub struct PendingStructuredOutput(pub ResponseFormat);
+
+/// 按 Provider 能力处理请求中的 `response_format`
+///
+/// 原生支持时保持不变并返回 `None`；不支持时移除 `response_format`、注入系统指令，
+/// 返回需要在响应中校验的格式。
+pub fn apply_structured_output(
+    provider: ProviderType,
+    request: &mut ChatCompletionRequest,
+) -> Option<ResponseFormat> {
+    if !request
+        .response_format
+        .as_ref()
+        .is_some_and(|f| f.is_json())
+    {
+        return None;
+    }
+    if provider
+        .capabilities()
+        .supports(Capability::StructuredOutput)
+    {
+        return None;
+    }
+
+    let format = request.response_format.take()?;
+    let instruction = structured_output_instruction(&format);
+    match request.messages.first_mut() {
+        Some(ChatMessage {
+            role,
+            content: Some(MessageContent::Text(text)),
+            ..
+        }) if role == "system" => {
+            text.push_str("\n\n");
+            text.push_str(&instruction);
+        }
+        _ => request.messages.insert(
+            0,
+            ChatMessage {
+                role: "system".to_string(),
+                content: Some(MessageContent::Text(instruction)),
+                tool_calls: None,
+                tool_call_id: None,
+                reasoning_content: None,
+            },
+        ),
+    }
+    Some(format)
+}
+
+/// 要求模型输出 JSON 的系统指令
+pub fn structured_output_instruction(format: &ResponseFormat) -> String {
+    let mut instruction = String::from(
+        "Respond with a single valid JSON value only. Do not wrap it in markdown code fences \
+         and do not add any explanation before or after it.",
+    );
+    match format.schema() {
+        Some(schema) => {
+            instruction.push_str(" The JSON must conform to this JSON Schema:\n");
+            instruction.push_str(&schema.to_string());
+        }
+        None => instruction.push_str(" The top-level value must be a JSON object."),
+    }
+    instruction
+}
+
+/// 校验并修复模型输出
+///
+/// 成功时返回规范化后的 JSON 文本；非严格模式下无法修复的输出原样返回。
+pub fn enforce_structured_output(
+    format: &ResponseFormat,
+    content: &str,
+    strict: bool,
+) -> Result<String, StructuredOutputError> {
+    let checked = repair_json(content)
+        .ok_or(StructuredOutputError::InvalidJson)
+        .and_then(|value| {
+            let result = match format.schema() {
+                Some(schema) => validate_schema(&value, schema, "$"),
+                None if value.is_object() => Ok(()),
+                None => Err("top-level value must be an object".to_string()),
+            };
+            result
+                .map(|()| value.to_string())
+                .map_err(StructuredOutputError::SchemaMismatch)
+        });
+    match checked {
+        Ok(json) => Ok(json),
+        Err(e) if strict => Err(e),
+        Err(e) => {
+            tracing::warn!("[STRUCTURED_OUTPUT] 输出校验失败，原样返回: {}", e);
+            Ok(content.to_string())
+        }
+    }
+}
+
+/// 尝试把模型输出解析为 JSON，必要时做简单修复
+pub fn repair_json(content: &str) -> Option<Value> {
+    static FENCE: OnceLock<Regex> = OnceLock::new();
+    static TRAILING_COMMA: OnceLock<Regex> = OnceLock::new();
+
+    let content = content.trim();
+    if let Ok(value) = serde_json::from_str(content) {
+        return Some(value);
+    }
+
+    let fence = FENCE.get_or_init(|| Regex::new(r"(?s)```(?:json)?\s*(.*?)\s*```").unwrap());
+    let unfenced = fence
+        .captures(content)
+        .and_then(|c| c.get(1))
+        .map_or(content, |m| m.as_str());
+    let start = unfenced.find(['{', '['])?;
+    let end = unfenced.rfind(['}', ']'])?;
+    if end < start {
+        return None;
+    }
+    let candidate = &unfenced[start..=end];
+    if let Ok(value) = serde_json::from_str(candidate) {
+        return Some(value);
+    }
+
+    let trailing = TRAILING_COMMA.get_or_init(|| Regex::new(r",\s*([}\]])").unwrap());
+    serde_json::from_str(&trailing.replace_all(candidate, "$1")).ok()
+}
+
+/// 按 JSON Schema 的常用子集校验（type、enum、properties、required、
+/// additionalProperties: false、items）
+pub fn validate_schema(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+            return Err(format!("{path}: expected {}", types.join(" | ")));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!("{path}: value not in enum"));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    return Err(format!("{path}: missing required property '{key}'"));
+                }
+            }
+        }
+        for (key, item) in object {
+            match properties.and_then(|p| p.get(key)) {
+                Some(item_schema) => validate_schema(item, item_schema, &format!("{path}.{key}"))?,
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    return Err(format!("{path}: unexpected property '{key}'"));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_schema(item, item_schema, &format!("{path}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// 处理 Provider 响应的结构化输出
+///
+/// 非流式响应立即校验；流式响应附带 [`PendingStructuredOutput`]，留待缓冲后校验。
+pub async fn handle_structured_response(
+    mut response: Response,
+    format: ResponseFormat,
+    stream: bool,
+    strict: bool,
+) -> Response {
+    if !stream {
+        return enforce_structured_response(response, &format, strict).await;
+    }
+    response
+        .extensions_mut()
+        .insert(PendingStructuredOutput(format));
+    response
+}
+
+/// 取出响应上待校验的结构化输出格式
+///
+/// 缓冲流式响应会重建响应、丢失扩展，需在转换前取出。
+pub fn take_pending_structured_output(response: &mut Response) -> Option<ResponseFormat> {
+    response
+        .extensions_mut()
+        .remove::<PendingStructuredOutput>()
+        .map(|pending| pending.0)
+}
+
+/// 校验非流式响应中每个候选回复的内容
+///
+/// 上游返回错误或响应体无法解析时原样返回；校验通过时保留上游响应头。
+pub async fn enforce_structured_response(
+    response: Response,
+    format: &ResponseFormat,
+    strict: bool,
+) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[STRUCTURED_OUTPUT] 读取响应体失败: {}", e);
+            return (StatusCode::BAD_GATEWAY, e.to_string()).into_response();
+        }
+    };
+    let Ok(mut body) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    if let Some(choices) = body.get_mut("choices").and_then(Value::as_array_mut) {
+        for choice in choices {
+            let Some(content) = choice["message"]["content"].as_str() else {
+                continue;
+            };
+            match enforce_structured_output(format, content, strict) {
+                Ok(json) => choice["message"]["content"] = Value::String(json),
+                Err(e) => return invalid_structured_output_response(&e),
+            }
+        }
+    }
+    // 修复后的内容长度可能变化
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
+
+/// 构建结构化输出校验失败的 502 响应
+pub fn invalid_structured_output_response(error: &StructuredOutputError) -> Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(serde_json::json!({
+            "error": {
+                "type": "invalid_response_error",
+                "code": "invalid_structured_output",
+                "message": error.to_string()
+            }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(body: Value) -> ChatCompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn schema_format() -> ResponseFormat {
+        serde_json::from_value(json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "weather",
+                "strict": true,
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "city": {"type": "string"},
+                        "temperature": {"type": "number"}
+                    },
+                    "required": ["city", "temperature"],
+                    "additionalProperties": false
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_native_provider_passes_response_format_through() {
+        let mut req = request(json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "天气"}],
+            "response_format": {"type": "json_object"}
+        }));
+        assert_eq!(
+            apply_structured_output(ProviderType::OpenAI, &mut req),
+            None
+        );
+        assert_eq!(req.messages.len(), 1);
+
+        let forwarded = serde_json::to_value(&req).unwrap();
+        assert_eq!(forwarded["response_format"]["type"], "json_object");
+    }
+
+    #[test]
+    fn test_fallback_injects_instruction() {
+        let mut req = request(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "天气"}],
+            "response_format": serde_json::to_value(schema_format()).unwrap()
+        }));
+        let format = apply_structured_output(ProviderType::Kiro, &mut req).unwrap();
+        assert_eq!(format, schema_format());
+        assert!(req.response_format.is_none());
+        assert_eq!(req.messages[0].role, "system");
+        let instruction = req.messages[0].get_content_text();
+        assert!(instruction.contains("valid JSON"));
+        assert!(instruction.contains("\"required\":[\"city\",\"temperature\"]"));
+
+        // 已有系统消息时追加到末尾
+        let mut req = request(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [
+                {"role": "system", "content": "你是天气助手"},
+                {"role": "user", "content": "天气"}
+            ],
+            "response_format": {"type": "json_object"}
+        }));
+        apply_structured_output(ProviderType::Kiro, &mut req).unwrap();
+        assert_eq!(req.messages.len(), 2);
+        assert!(req.messages[0]
+            .get_content_text()
+            .starts_with("你是天气助手\n\n"));
+    }
+
+    #[test]
+    fn test_repair_and_validate_output() {
+        let format = schema_format();
+        let repaired = enforce_structured_output(
+            &format,
+            "好的：\n```json\n{\"city\": \"上海\", \"temperature\": 21.5,}\n```",
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&repaired).unwrap(),
+            json!({"city": "上海", "temperature": 21.5})
+        );
+
+        let json_object = ResponseFormat::JsonObject;
+        assert_eq!(
+            enforce_structured_output(&json_object, "[1, 2]", true),
+            Err(StructuredOutputError::SchemaMismatch(
+                "top-level value must be an object".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_rejects_invalid_output() {
+        let format = schema_format();
+        assert_eq!(
+            enforce_structured_output(&format, "今天上海晴", true),
+            Err(StructuredOutputError::InvalidJson)
+        );
+        assert!(matches!(
+            enforce_structured_output(&format, r#"{"city": "上海"}"#, true),
+            Err(StructuredOutputError::SchemaMismatch(reason)) if reason.contains("temperature")
+        ));
+        // 非严格模式原样返回
+        assert_eq!(
+            enforce_structured_output(&format, "今天上海晴", false).unwrap(),
+            "今天上海晴"
+        );
+
+        let upstream = Json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "今天上海晴"},
+                "finish_reason": "stop"
+            }]
+        }))
+        .into_response();
+        let response = enforce_structured_response(upstream, &format, true).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "invalid_structured_output");
+    }
+
+    #[tokio::test]
+    async fn test_buffered_stream_is_enforced_and_keeps_upstream_headers() {
+        use crate::stream_buffer::{ensure_response_mode, BufferFormat};
+
+        let chunk = json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1,
+            "model": "claude-sonnet-4-5",
+            "choices": [{
+                "index": 0,
+                "delta": {"role": "assistant", "content": "```json\n{\"city\": \"上海\", \"temperature\": 21,}\n```"},
+                "finish_reason": "stop"
+            }]
+        });
+        let upstream = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .header("x-request-id", "req-1")
+            .body(Body::from(format!("data: {chunk}\n\ndata: [DONE]\n\n")))
+            .unwrap();
+
+        // 流式请求只标记，缓冲为非流式后再校验
+        let mut response = handle_structured_response(upstream, schema_format(), true, true).await;
+        let format = take_pending_structured_output(&mut response).unwrap();
+        assert!(take_pending_structured_output(&mut response).is_none());
+        let buffered = ensure_response_mode(response, BufferFormat::OpenAi, false).await;
+        let response = enforce_structured_response(buffered, &format, true).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-request-id"], "req-1");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        let content = body["choices"][0]["message"]["content"].as_str().unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(content).unwrap(),
+            json!({"city": "上海", "temperature": 21})
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Anthropic 工具定义校验
+//!
+//! 在转发请求前检查 `tools[].input_schema` 与 `tool_choice`，
+//! 将畸形请求在本地拒绝为 400，避免上游返回难以定位的错误。
+
+use proxycast_core::models::anthropic::AnthropicMessagesRequest;
+use std::collections::HashSet;
+
+/// 校验 Anthropic 请求中的工具定义
+///
+/// 规则：
+/// - 工具名称不能为空且不能重复
+/// - `input_schema` 必须是 JSON 对象，`type`（如有）必须为 `"object"`
+/// - `properties`（如有）必须是对象，`required`（如有）必须是字符串数组
+/// - `tool_choice` 的 `type` 必须是 `auto`/`any`/`tool`/`none` 之一
+/// - `tool_choice.type == "tool"` 时必须提供 `name` 且引用已声明的工具
+///
+/// 返回的错误信息会直接作为 400 响应的 message。
+pub fn validate_anthropic_tools(request: &AnthropicMessagesRequest) -> Result<(), String> {
+    let tools = request.tools.as_deref().unwrap_or_default();
+    let mut names = HashSet::with_capacity(tools.len());
+
+    for (index, tool) in tools.iter().enumerate() {
+        if tool.name.trim().is_empty() {
+            return Err(format!("tools.{index}.name: tool name must not be empty"));
+        }
+        if !names.insert(tool.name.as_str()) {
+            return Err(format!(
+                "tools.{index}.name: duplicate tool name '{}'",
+                tool.name
+            ));
+        }
+        if let Some(schema) = &tool.input_schema {
+            validate_input_schema(schema)
+                .map_err(|e| format!("tools.{index}.input_schema: {e}"))?;
+        }
+    }
+
+    let Some(choice) = &request.tool_choice else {
+        return Ok(());
+    };
+    let Some(choice) = choice.as_object() else {
+        return Err("tool_choice: must be an object".to_string());
+    };
+
+    match choice.get("type").and_then(|t| t.as_str()) {
+        Some("auto") | Some("any") | Some("none") => Ok(()),
+        Some("tool") => {
+            let name = choice.get("name").and_then(|n| n.as_str()).ok_or_else(|| {
+                "tool_choice.name: field required when type is 'tool'".to_string()
+            })?;
+            if names.contains(name) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "tool_choice.name: '{name}' does not match any tool in tools"
+                ))
+            }
+        }
+        Some(other) => Err(format!("tool_choice.type: unsupported value '{other}'")),
+        None => Err("tool_choice.type: field required".to_string()),
+    }
+}
+
+/// 校验单个工具的 `input_schema`
+fn validate_input_schema(schema: &serde_json::Value) -> Result<(), String> {
+    let Some(obj) = schema.as_object() else {
+        return Err("must be a JSON Schema object".to_string());
+    };
+
+    if let Some(schema_type) = obj.get("type") {
+        if schema_type.as_str() != Some("object") {
+            return Err(format!("type must be 'object', got {schema_type}"));
+        }
+    }
+
+    if let Some(properties) = obj.get("properties") {
+        if !properties.is_object() {
+            return Err("properties must be an object".to_string());
+        }
+    }
+
+    if let Some(required) = obj.get("required") {
+        let valid = required
+            .as_array()
+            .map(|items| items.iter().all(|item| item.is_string()))
+            .unwrap_or(false);
+        if !valid {
+            return Err("required must be an array of strings".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(body: serde_json::Value) -> AnthropicMessagesRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn weather_tool() -> serde_json::Value {
+        json!({
+            "name": "get_weather",
+            "description": "Get weather",
+            "input_schema": {
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"]
+            }
+        })
+    }
+
+    #[test]
+    fn test_tool_choice_missing_name() {
+        let req = request(json!({
+            "model": "claude-sonnet-4",
+            "messages": [],
+            "tools": [weather_tool()],
+            "tool_choice": { "type": "tool" }
+        }));
+        let err = validate_anthropic_tools(&req).unwrap_err();
+        assert!(err.starts_with("tool_choice.name"));
+    }
+
+    #[test]
+    fn test_tool_choice_unknown_name() {
+        let req = request(json!({
+            "model": "claude-sonnet-4",
+            "messages": [],
+            "tools": [weather_tool()],
+            "tool_choice": { "type": "tool", "name": "get_time" }
+        }));
+        let err = validate_anthropic_tools(&req).unwrap_err();
+        assert!(err.contains("get_time"));
+    }
+
+    #[test]
+    fn test_non_object_schema() {
+        let req = request(json!({
+            "model": "claude-sonnet-4",
+            "messages": [],
+            "tools": [{ "name": "bad", "input_schema": "not a schema" }]
+        }));
+        let err = validate_anthropic_tools(&req).unwrap_err();
+        assert!(err.starts_with("tools.0.input_schema"));
+
+        let req = request(json!({
+            "model": "claude-sonnet-4",
+            "messages": [],
+            "tools": [{ "name": "bad", "input_schema": { "type": "string" } }]
+        }));
+        assert!(validate_anthropic_tools(&req).is_err());
+    }
+
+    #[test]
+    fn test_valid_payload_passes_untouched() {
+        let body = json!({
+            "model": "claude-sonnet-4",
+            "messages": [{ "role": "user", "content": "hi" }],
+            "tools": [weather_tool()],
+            "tool_choice": { "type": "tool", "name": "get_weather" }
+        });
+        let req = request(body);
+        let before = serde_json::to_value(&req).unwrap();
+
+        assert!(validate_anthropic_tools(&req).is_ok());
+        assert_eq!(serde_json::to_value(&req).unwrap(), before);
+    }
+
+    #[test]
+    fn test_no_tools_is_valid() {
+        let req = request(json!({
+            "model": "claude-sonnet-4",
+            "messages": []
+        }));
+        assert!(validate_anthropic_tools(&req).is_ok());
+    }
+}
@@ -0,0 +1,179 @@
+//! 上游响应体处理
+//!
+//! 上游可能返回图片等二进制内容或非 UTF-8 编码的文本，
+//! 用 `String::from_utf8_lossy` 转换会把无效字节替换为 `U+FFFD` 而静默损坏内容。
+//! 这里按 `Content-Type` 区分：已知文本类型才按 UTF-8 解码，
+//! 其余内容（以及解码失败的内容）原样透传字节。
+
+use std::borrow::Cow;
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// 是否为已知的文本类 `Content-Type`
+pub fn is_text_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let Some((kind, subtype)) = mime.split_once('/') else {
+        return false;
+    };
+    kind == "text"
+        || subtype.ends_with("+json")
+        || subtype.ends_with("+xml")
+        || matches!(
+            (kind, subtype),
+            ("application", "json")
+                | ("application", "x-ndjson")
+                | ("application", "xml")
+                | ("application", "javascript")
+                | ("application", "x-www-form-urlencoded")
+        )
+}
+
+/// 按内容类型区分后的上游响应体
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamBody {
+    /// 合法 UTF-8 文本
+    Text(String),
+    /// 二进制或无法按 UTF-8 解码的内容，须原样透传
+    Binary(Bytes),
+}
+
+impl UpstreamBody {
+    /// 按 `Content-Type` 分类响应体
+    ///
+    /// 未声明类型时按内容嗅探：合法 UTF-8 视为文本。
+    /// 文本类型但不是合法 UTF-8 时不做有损转换，按二进制透传。
+    pub fn classify(content_type: Option<&str>, bytes: Bytes) -> Self {
+        if content_type.is_some_and(|ct| !is_text_content_type(ct)) {
+            return Self::Binary(bytes);
+        }
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => Self::Text(text),
+            Err(_) => {
+                tracing::warn!(
+                    "[UPSTREAM_BODY] 响应体不是合法 UTF-8（Content-Type: {}），按二进制透传",
+                    content_type.unwrap_or("<none>")
+                );
+                Self::Binary(bytes)
+            }
+        }
+    }
+}
+
+/// 供日志与错误信息展示的响应体文本
+///
+/// 只用于展示，不可用于转发或解析：非 UTF-8 内容会做有损替换并注明原始字节数。
+pub fn display_text(bytes: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(format!(
+            "{} (<{} bytes, not valid UTF-8>)",
+            String::from_utf8_lossy(bytes),
+            bytes.len()
+        )),
+    }
+}
+
+/// 原样透传上游响应体，保留上游的 `Content-Type`
+pub fn passthrough_response(
+    status: StatusCode,
+    content_type: Option<&str>,
+    bytes: Bytes,
+) -> Response {
+    Response::builder()
+        .status(status)
+        .header(
+            header::CONTENT_TYPE,
+            content_type.unwrap_or("application/octet-stream"),
+        )
+        .body(Body::from(bytes))
+        .unwrap_or_else(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
+            )
+                .into_response()
+        })
+}
+
+/// 文本响应按 JSON 解析后返回，二进制响应原样透传
+pub fn json_or_passthrough(
+    status: StatusCode,
+    content_type: Option<&str>,
+    bytes: Bytes,
+) -> Response {
+    match UpstreamBody::classify(content_type, bytes) {
+        UpstreamBody::Binary(bytes) => passthrough_response(status, content_type, bytes),
+        UpstreamBody::Text(text) => match serde_json::from_str::<serde_json::Value>(&text) {
+            Ok(json) => (status, Json(json)).into_response(),
+            Err(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": {"message": "Invalid JSON response"}})),
+            )
+                .into_response(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_bytes(response: Response) -> Bytes {
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_binary_image_forwarded_byte_identical() {
+        // PNG 文件头后跟无效 UTF-8 字节
+        let png = Bytes::from_static(&[
+            0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0xfe, 0x00, 0x80,
+        ]);
+        assert!(matches!(
+            UpstreamBody::classify(Some("image/png"), png.clone()),
+            UpstreamBody::Binary(_)
+        ));
+
+        let response = json_or_passthrough(StatusCode::OK, Some("image/png"), png.clone());
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert_eq!(body_bytes(response).await, png);
+
+        // 声明为文本但不是合法 UTF-8 时同样不做有损转换
+        let latin1 = Bytes::from_static(b"caf\xe9");
+        let response = json_or_passthrough(StatusCode::OK, Some("text/plain"), latin1.clone());
+        assert_eq!(body_bytes(response).await, latin1);
+    }
+
+    #[tokio::test]
+    async fn test_text_response_still_parses() {
+        let json = Bytes::from_static(br#"{"id":"chatcmpl-1","object":"chat.completion"}"#);
+        assert_eq!(
+            UpstreamBody::classify(Some("application/json; charset=utf-8"), json.clone()),
+            UpstreamBody::Text(r#"{"id":"chatcmpl-1","object":"chat.completion"}"#.to_string())
+        );
+
+        let response = json_or_passthrough(StatusCode::OK, None, json);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(body["id"], "chatcmpl-1");
+
+        assert_eq!(display_text(b"upstream error"), "upstream error");
+        assert!(display_text(b"caf\xe9").contains("<4 bytes, not valid UTF-8>"));
+
+        assert!(is_text_content_type("application/problem+json"));
+        assert!(is_text_content_type("text/event-stream"));
+        assert!(!is_text_content_type("audio/mpeg"));
+    }
+}
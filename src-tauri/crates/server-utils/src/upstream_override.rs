@@ -0,0 +1,213 @@
+//! 请求级上游地址覆盖
+//!
+//! 排查上游问题时，可通过 `X-ProxyCast-Upstream-Base` 请求头把单个请求临时指向
+//! mock 或 staging 地址。为避免 SSRF，该功能默认关闭，只有启用
+//! `server.allow_upstream_override` 后才会生效；未启用时请求头会被忽略并记录警告。
+//!
+//! 无论上游地址来自凭证配置还是覆盖请求头，指向服务器自身监听地址时都会被
+//! [`reject_self_upstream`] 拒绝（除非启用 `server.allow_self_upstream`），避免请求回环。
+
+use axum::http::HeaderMap;
+use axum::response::Response;
+use proxycast_core::middleware::loop_guard::{is_self_target, loop_detected_response};
+use proxycast_core::models::provider_pool_model::{CredentialData, ProviderCredential};
+
+/// 上游地址覆盖请求头
+pub const UPSTREAM_OVERRIDE_HEADER: &str = "x-proxycast-upstream-base";
+
+/// 解析请求头中的上游地址覆盖
+///
+/// 仅在 `enabled` 为 true 且地址为合法的 http(s) URL 时返回覆盖地址。
+pub fn resolve_upstream_override(headers: &HeaderMap, enabled: bool) -> Option<String> {
+    let value = headers.get(UPSTREAM_OVERRIDE_HEADER)?;
+    if !enabled {
+        tracing::warn!(
+            "[UPSTREAM_OVERRIDE] 未启用 server.allow_upstream_override，忽略 {} 请求头",
+            UPSTREAM_OVERRIDE_HEADER
+        );
+        return None;
+    }
+
+    let raw = value.to_str().ok()?.trim();
+    match url::Url::parse(raw) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {
+            Some(raw.trim_end_matches('/').to_string())
+        }
+        _ => {
+            tracing::warn!("[UPSTREAM_OVERRIDE] 无效的上游地址，已忽略: {}", raw);
+            None
+        }
+    }
+}
+
+/// 把凭证的上游地址替换为 `base_url`
+///
+/// `credential` 是本次请求选中的凭证副本，修改不会写回凭证池；
+/// Codex 凭证的地址只用于请求 URL，不会保存到凭证文件。
+/// 其他 OAuth 类凭证的地址由 Provider 决定，无法覆盖，返回 false。
+pub fn override_credential_base_url(credential: &mut ProviderCredential, base_url: &str) -> bool {
+    let target = match &mut credential.credential {
+        CredentialData::OpenAIKey { base_url, .. }
+        | CredentialData::ClaudeKey { base_url, .. }
+        | CredentialData::VertexKey { base_url, .. }
+        | CredentialData::GeminiApiKey { base_url, .. }
+        | CredentialData::AnthropicKey { base_url, .. } => base_url,
+        CredentialData::CodexOAuth { api_base_url, .. } => api_base_url,
+        CredentialData::LocalOpenAI {
+            base_url: target, ..
+        } => {
+            *target = base_url.to_string();
+            return true;
+        }
+        _ => return false,
+    };
+    *target = Some(base_url.to_string());
+    true
+}
+
+/// 凭证配置的上游地址（OAuth 类凭证由 Provider 决定，返回 `None`）
+pub fn credential_base_url(credential: &ProviderCredential) -> Option<&str> {
+    match &credential.credential {
+        CredentialData::OpenAIKey { base_url, .. }
+        | CredentialData::ClaudeKey { base_url, .. }
+        | CredentialData::VertexKey { base_url, .. }
+        | CredentialData::GeminiApiKey { base_url, .. }
+        | CredentialData::AnthropicKey { base_url, .. } => base_url.as_deref(),
+        CredentialData::CodexOAuth { api_base_url, .. } => api_base_url.as_deref(),
+        CredentialData::LocalOpenAI { base_url, .. } => Some(base_url),
+        _ => None,
+    }
+}
+
+/// 上游指向服务器自身监听地址时返回拒绝响应
+///
+/// `allowed` 为 true（`server.allow_self_upstream`）时不做检查。
+pub fn reject_self_upstream(
+    credential: &ProviderCredential,
+    bind_host: &str,
+    bind_port: u16,
+    allowed: bool,
+) -> Option<Response> {
+    if allowed {
+        return None;
+    }
+    let base_url = credential_base_url(credential)?;
+    if !is_self_target(base_url, bind_host, bind_port) {
+        return None;
+    }
+    tracing::warn!(
+        "[UPSTREAM_OVERRIDE] 凭证 {} 的上游地址 {} 指向代理自身，已拒绝",
+        credential.uuid,
+        base_url
+    );
+    Some(loop_detected_response(&format!(
+        "Upstream base URL {base_url} points back at this proxy ({bind_host}:{bind_port}); \
+         enable server.allow_self_upstream if this is intended."
+    )))
+}
+
+/// 按请求头覆盖凭证的上游地址，返回实际生效的覆盖地址
+pub fn apply_upstream_override(
+    headers: &HeaderMap,
+    enabled: bool,
+    credential: &mut ProviderCredential,
+) -> Option<String> {
+    let base_url = resolve_upstream_override(headers, enabled)?;
+    if !override_credential_base_url(credential, &base_url) {
+        tracing::warn!(
+            "[UPSTREAM_OVERRIDE] 凭证类型 {} 不支持覆盖上游地址",
+            credential.provider_type
+        );
+        return None;
+    }
+    tracing::info!(
+        "[UPSTREAM_OVERRIDE] 凭证 {} 本次请求使用上游地址 {}",
+        credential.uuid,
+        base_url
+    );
+    Some(base_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::models::provider_pool_model::PoolProviderType;
+
+    fn openai_credential() -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: Some("https://api.openai.com/v1".to_string()),
+            },
+        )
+    }
+
+    fn base_url(credential: &ProviderCredential) -> Option<&str> {
+        match &credential.credential {
+            CredentialData::OpenAIKey { base_url, .. } => base_url.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(UPSTREAM_OVERRIDE_HEADER, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_override_applied_only_when_enabled() {
+        let mut credential = openai_credential();
+        let applied =
+            apply_upstream_override(&headers("http://localhost:9000/v1/"), true, &mut credential);
+        assert_eq!(applied.as_deref(), Some("http://localhost:9000/v1"));
+        assert_eq!(base_url(&credential), Some("http://localhost:9000/v1"));
+
+        // 未启用时忽略请求头，凭证保持不变
+        let mut credential = openai_credential();
+        let applied =
+            apply_upstream_override(&headers("http://localhost:9000/v1"), false, &mut credential);
+        assert_eq!(applied, None);
+        assert_eq!(base_url(&credential), Some("https://api.openai.com/v1"));
+
+        // 没有请求头时不做任何处理
+        assert_eq!(resolve_upstream_override(&HeaderMap::new(), true), None);
+    }
+
+    #[test]
+    fn test_invalid_or_unsupported_override_is_ignored() {
+        for value in ["file:///etc/passwd", "not a url", "ftp://example.com"] {
+            assert_eq!(
+                resolve_upstream_override(&headers(value), true),
+                None,
+                "{value}"
+            );
+        }
+
+        let mut kiro = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/tmp/kiro.json".to_string(),
+            },
+        );
+        assert_eq!(
+            apply_upstream_override(&headers("http://localhost:9000"), true, &mut kiro),
+            None
+        );
+    }
+
+    #[test]
+    fn test_self_upstream_refused_unless_allowed() {
+        let mut credential = openai_credential();
+        assert!(reject_self_upstream(&credential, "0.0.0.0", 8999, false).is_none());
+
+        // 覆盖请求头把上游指向代理自身同样被拒绝
+        apply_upstream_override(&headers("http://127.0.0.1:8999/v1"), true, &mut credential);
+        let response = reject_self_upstream(&credential, "0.0.0.0", 8999, false).unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::LOOP_DETECTED);
+
+        assert!(reject_self_upstream(&credential, "0.0.0.0", 8999, true).is_none());
+        assert!(reject_self_upstream(&credential, "0.0.0.0", 9000, false).is_none());
+    }
+}
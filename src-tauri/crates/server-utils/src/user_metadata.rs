@@ -0,0 +1,134 @@
+//! Anthropic `metadata.user_id` 转发策略
+//!
+//! 上游用 `metadata.user_id` 追踪滥用，但它也可能泄露终端用户身份。
+//! 按配置 `anthropic.forward_user_metadata` 决定转发或剥离；转发时可校验
+//! `user_id` 是否为不透明标识（UUID / 哈希值），明显的个人信息直接拒绝。
+
+use proxycast_core::config::AnthropicConfig;
+use proxycast_core::models::anthropic::AnthropicMessagesRequest;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Anthropic 允许的 `user_id` 最大长度
+pub const MAX_USER_ID_LEN: usize = 256;
+
+fn email_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[^@\s]+@[^@\s]+\.[A-Za-z]{2,}").expect("valid regex"))
+}
+
+fn phone_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\+?[\d\s\-().]{7,20}$").expect("valid regex"))
+}
+
+/// 校验 `user_id` 是否为不透明标识
+///
+/// 拒绝超长、包含空白（疑似姓名）、邮箱地址或电话号码形式的值。
+pub fn validate_user_id(user_id: &str) -> Result<(), String> {
+    if user_id.len() > MAX_USER_ID_LEN {
+        return Err(format!(
+            "metadata.user_id: must be at most {MAX_USER_ID_LEN} characters"
+        ));
+    }
+    if email_regex().is_match(user_id) {
+        return Err(
+            "metadata.user_id: looks like an email address; use a UUID or hash instead".to_string(),
+        );
+    }
+    // 纯数字是常见的用户 ID 形式；只有带 `+` 前缀或分隔符的才视为电话号码
+    let digits = user_id.chars().filter(char::is_ascii_digit).count();
+    let formatted = user_id.starts_with('+') || digits < user_id.len();
+    if formatted && phone_regex().is_match(user_id) && (7..=15).contains(&digits) {
+        return Err(
+            "metadata.user_id: looks like a phone number; use a UUID or hash instead".to_string(),
+        );
+    }
+    if user_id.trim().contains(char::is_whitespace) {
+        return Err(
+            "metadata.user_id: must be an opaque identifier without whitespace".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// 按配置转发或剥离请求中的 `metadata`
+///
+/// 返回的错误信息会直接作为 400 响应的 message。
+pub fn apply_user_metadata_policy(
+    request: &mut AnthropicMessagesRequest,
+    config: &AnthropicConfig,
+) -> Result<(), String> {
+    if !config.forward_user_metadata {
+        request.metadata = None;
+        return Ok(());
+    }
+    if config.validate_user_metadata {
+        if let Some(user_id) = request.metadata.as_ref().and_then(|m| m.user_id.as_deref()) {
+            validate_user_id(user_id)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(user_id: &str) -> AnthropicMessagesRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 16,
+            "messages": [{"role": "user", "content": "hi"}],
+            "metadata": {"user_id": user_id}
+        }))
+        .unwrap()
+    }
+
+    fn config(forward: bool, validate: bool) -> AnthropicConfig {
+        AnthropicConfig {
+            forward_user_metadata: forward,
+            validate_user_metadata: validate,
+        }
+    }
+
+    #[test]
+    fn test_forward_or_strip_metadata() {
+        let user_id = "5f2b8c1e-9a7d-4c3e-8b1a-2d4f6e8a0c1b";
+
+        let mut forwarded = request(user_id);
+        apply_user_metadata_policy(&mut forwarded, &config(true, true)).unwrap();
+        let body = serde_json::to_value(&forwarded).unwrap();
+        assert_eq!(body["metadata"]["user_id"], user_id);
+
+        let mut stripped = request(user_id);
+        apply_user_metadata_policy(&mut stripped, &config(false, true)).unwrap();
+        let body = serde_json::to_value(&stripped).unwrap();
+        assert!(body.get("metadata").is_none());
+    }
+
+    #[test]
+    fn test_rejects_pii_user_id_when_validating() {
+        for pii in ["alice@example.com", "+1 (415) 555-0132", "Alice Smith"] {
+            let mut req = request(pii);
+            assert!(
+                apply_user_metadata_policy(&mut req, &config(true, true)).is_err(),
+                "{pii} 应被拒绝"
+            );
+            // 关闭校验时原样转发
+            let mut req = request(pii);
+            assert!(apply_user_metadata_policy(&mut req, &config(true, false)).is_ok());
+        }
+
+        // 哈希值和 UUID 不受影响
+        assert!(validate_user_id(
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+        )
+        .is_ok());
+        assert!(validate_user_id("user_1234").is_ok());
+        // 纯数字用户 ID 不是电话号码
+        assert!(validate_user_id("10000001").is_ok());
+        assert!(validate_user_id("415-555-0132").is_err());
+        assert!(validate_user_id(&"a".repeat(MAX_USER_ID_LEN + 1)).is_err());
+    }
+}
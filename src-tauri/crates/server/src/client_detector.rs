@@ -53,6 +53,7 @@ mod property_tests {
                     windsurf,
                     kiro,
                     other,
+                    disabled: Vec::new(),
                 }
             })
     }
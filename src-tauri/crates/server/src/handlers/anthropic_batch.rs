@@ -0,0 +1,546 @@
+//! Anthropic Message Batches API 兼容端点
+//!
+//! 将 `/v1/messages/batches` 映射到 ProxyCast 的批量任务引擎：
+//! - 创建批次时每个请求转换为 OpenAI 格式，作为子任务的完整请求入队
+//! - 批次状态映射为 Anthropic 的 `processing_status` 与 `request_counts`
+//! - 结果以 JSONL 返回，每行对应一个 `custom_id`，消息内容和 `stop_reason`
+//!   由子任务保存的 OpenAI 响应还原（包括工具调用）
+//! - 批量任务执行器未就绪时拒绝创建批次，避免批次永远停留在处理中
+
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use proxycast_core::models::anthropic::AnthropicMessagesRequest;
+use proxycast_providers::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use proxycast_providers::stream::StopReason;
+use proxycast_scheduler::{
+    BatchOptions, BatchTask, BatchTaskDao, BatchTaskStatus, BatchTaskStatus2, TaskDefinition,
+    TaskResult,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::api::verify_api_key_anthropic;
+use super::batch_executor::TASK_REQUEST_METADATA_KEY;
+use crate::AppState;
+
+/// Anthropic 批次 ID 前缀
+const BATCH_ID_PREFIX: &str = "msgbatch_";
+
+/// 子任务元数据中保存 `custom_id` 的键
+const CUSTOM_ID_METADATA_KEY: &str = "custom_id";
+
+/// 批次结果的保留时间（Anthropic 批次 24 小时后过期）
+const BATCH_EXPIRY_HOURS: i64 = 24;
+
+/// 创建批次请求
+#[derive(Debug, Deserialize)]
+pub struct CreateMessageBatchRequest {
+    pub requests: Vec<MessageBatchRequestItem>,
+}
+
+/// 批次中的单个请求
+#[derive(Debug, Deserialize)]
+pub struct MessageBatchRequestItem {
+    /// 调用方指定的请求标识，在结果中原样返回
+    pub custom_id: String,
+    /// Messages API 请求参数
+    pub params: AnthropicMessagesRequest,
+}
+
+/// 将 Anthropic 批次请求转换为批量任务
+pub fn build_message_batch(request: CreateMessageBatchRequest) -> Result<BatchTask, String> {
+    if request.requests.is_empty() {
+        return Err("requests must not be empty".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    let mut tasks = Vec::with_capacity(request.requests.len());
+    for item in request.requests {
+        if item.custom_id.is_empty() {
+            return Err("custom_id must not be empty".to_string());
+        }
+        if !seen.insert(item.custom_id.clone()) {
+            return Err(format!("Duplicate custom_id: {}", item.custom_id));
+        }
+        let openai_request = convert_anthropic_to_openai(&item.params);
+        let raw = serde_json::to_string(&openai_request).map_err(|e| e.to_string())?;
+        tasks.push(TaskDefinition {
+            id: Some(Uuid::new_v4()),
+            variables: HashMap::new(),
+            metadata: HashMap::from([
+                (CUSTOM_ID_METADATA_KEY.to_string(), item.custom_id),
+                (TASK_REQUEST_METADATA_KEY.to_string(), raw),
+            ]),
+        });
+    }
+
+    // 所有子任务都带有完整请求，不需要模板
+    Ok(BatchTask::new(
+        "anthropic-message-batch".to_string(),
+        Uuid::nil(),
+        tasks,
+        BatchOptions::default(),
+    ))
+}
+
+/// 批量任务 ID 转为 Anthropic 批次 ID
+pub fn to_message_batch_id(id: &Uuid) -> String {
+    format!("{BATCH_ID_PREFIX}{}", id.simple())
+}
+
+/// 解析 Anthropic 批次 ID
+pub fn parse_message_batch_id(id: &str) -> Option<Uuid> {
+    id.strip_prefix(BATCH_ID_PREFIX)
+        .and_then(|s| Uuid::parse_str(s).ok())
+}
+
+fn is_processing(status: BatchTaskStatus) -> bool {
+    matches!(status, BatchTaskStatus::Pending | BatchTaskStatus::Running)
+}
+
+/// 将批量任务映射为 Anthropic `message_batch` 对象
+pub fn message_batch_object(batch: &BatchTask) -> Value {
+    let count =
+        |status: BatchTaskStatus2| batch.results.iter().filter(|r| r.status == status).count();
+    let succeeded = count(BatchTaskStatus2::Completed);
+    let errored = count(BatchTaskStatus2::Failed);
+    let mut canceled = count(BatchTaskStatus2::Cancelled);
+    let mut processing = batch
+        .tasks
+        .len()
+        .saturating_sub(succeeded + errored + canceled);
+
+    let processing_status = if is_processing(batch.status) {
+        "in_progress"
+    } else {
+        // 已结束但没有结果的请求视为已取消
+        canceled += processing;
+        processing = 0;
+        "ended"
+    };
+    let id = to_message_batch_id(&batch.id);
+
+    json!({
+        "id": id,
+        "type": "message_batch",
+        "processing_status": processing_status,
+        "request_counts": {
+            "processing": processing,
+            "succeeded": succeeded,
+            "errored": errored,
+            "canceled": canceled,
+            "expired": 0
+        },
+        "ended_at": batch.completed_at.map(|t| t.to_rfc3339()),
+        "created_at": batch.created_at.to_rfc3339(),
+        "expires_at": (batch.created_at + chrono::Duration::hours(BATCH_EXPIRY_HOURS)).to_rfc3339(),
+        "archived_at": null,
+        "cancel_initiated_at": null,
+        "results_url": (processing_status == "ended")
+            .then(|| format!("/v1/messages/batches/{id}/results"))
+    })
+}
+
+/// 由子任务结果还原 Anthropic 消息的 `content` 与 `stop_reason`
+///
+/// 没有保存完整响应的结果只还原文本。
+fn message_content(result: &TaskResult) -> (Vec<Value>, &'static str) {
+    let Some(choice) = result.response.as_ref().and_then(|r| r["choices"].get(0)) else {
+        let text = result.content.clone().unwrap_or_default();
+        return (
+            vec![json!({"type": "text", "text": text})],
+            StopReason::EndTurn.to_anthropic_str(),
+        );
+    };
+
+    let message = &choice["message"];
+    let mut content = Vec::new();
+    if let Some(text) = message["content"].as_str().filter(|t| !t.is_empty()) {
+        content.push(json!({"type": "text", "text": text}));
+    }
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+        content.push(json!({
+            "type": "tool_use",
+            "id": call["id"],
+            "name": call["function"]["name"],
+            "input": serde_json::from_str::<Value>(arguments).unwrap_or_else(|_| json!({}))
+        }));
+    }
+    let stop_reason = choice["finish_reason"]
+        .as_str()
+        .map(StopReason::from_openai)
+        .unwrap_or_default()
+        .to_anthropic_str();
+    (content, stop_reason)
+}
+
+/// 生成批次结果（JSONL，每行一个请求）
+pub fn message_batch_results(batch: &BatchTask) -> String {
+    let mut lines = String::new();
+    for task in &batch.tasks {
+        let custom_id = task
+            .metadata
+            .get(CUSTOM_ID_METADATA_KEY)
+            .cloned()
+            .unwrap_or_default();
+        let model = task
+            .metadata
+            .get(TASK_REQUEST_METADATA_KEY)
+            .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+            .and_then(|v| v["model"].as_str().map(str::to_string))
+            .unwrap_or_default();
+        let result = task
+            .id
+            .and_then(|id| batch.results.iter().find(|r| r.task_id == id));
+
+        let result = match result {
+            Some(r) if r.status == BatchTaskStatus2::Completed => {
+                let (content, stop_reason) = message_content(r);
+                json!({
+                    "type": "succeeded",
+                    "message": {
+                        "id": format!("msg_{}", r.task_id.simple()),
+                        "type": "message",
+                        "role": "assistant",
+                        "model": model,
+                        "content": content,
+                        "stop_reason": stop_reason,
+                        "stop_sequence": null,
+                        "usage": {
+                            "input_tokens": r.usage.prompt_tokens,
+                            "output_tokens": r.usage.completion_tokens
+                        }
+                    }
+                })
+            }
+            Some(r) if r.status == BatchTaskStatus2::Failed => json!({
+                "type": "errored",
+                "error": {
+                    "type": "error",
+                    "error": {
+                        "type": "api_error",
+                        "message": r.error.clone().unwrap_or_default()
+                    }
+                }
+            }),
+            _ => json!({"type": "canceled"}),
+        };
+        lines.push_str(&json!({"custom_id": custom_id, "result": result}).to_string());
+        lines.push('\n');
+    }
+    lines
+}
+
+fn error_response(status: StatusCode, error_type: &str, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "type": "error",
+            "error": {
+                "type": error_type,
+                "message": message
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 校验 API 密钥并按 Anthropic 批次 ID 加载批量任务
+async fn load_message_batch(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: &str,
+) -> Result<BatchTask, Response> {
+    verify_api_key_anthropic(headers, &*state.api_keys.read().await)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    let not_found = || {
+        error_response(
+            StatusCode::NOT_FOUND,
+            "not_found_error",
+            &format!("Message batch not found: {id}"),
+        )
+    };
+    let batch_id = parse_message_batch_id(id).ok_or_else(not_found)?;
+    let db = state.db.as_ref().ok_or_else(|| {
+        error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "api_error",
+            "数据库未初始化",
+        )
+    })?;
+    match BatchTaskDao::get_by_id(db, &batch_id) {
+        Ok(Some(batch)) => Ok(batch),
+        Ok(None) => Err(not_found()),
+        Err(e) => Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "api_error",
+            &format!("查询批量任务失败: {e}"),
+        )),
+    }
+}
+
+/// POST /v1/messages/batches - 创建消息批次
+pub async fn create_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateMessageBatchRequest>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &*state.api_keys.read().await).await {
+        return e.into_response();
+    }
+    let Some(db) = &state.db else {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "api_error",
+            "数据库未初始化",
+        );
+    };
+
+    // 没有执行器时批次永远不会被处理，直接拒绝而不是返回一个停留在处理中的批次
+    let executor = state.batch_executor.read().await;
+    let Some(executor) = executor.as_ref() else {
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "api_error",
+            "Batch processing is not available: the batch executor is not running",
+        );
+    };
+
+    let batch = match build_message_batch(request) {
+        Ok(batch) => batch,
+        Err(message) => {
+            return error_response(StatusCode::BAD_REQUEST, "invalid_request_error", &message)
+        }
+    };
+    if let Err(e) = BatchTaskDao::save(db, &batch) {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "api_error",
+            &format!("保存批量任务失败: {e}"),
+        );
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[BATCH] 创建 Anthropic 消息批次: id={}, request_count={}",
+            batch.id,
+            batch.tasks.len()
+        ),
+    );
+
+    executor.start_batch(batch.id).await;
+
+    (StatusCode::OK, Json(message_batch_object(&batch))).into_response()
+}
+
+/// GET /v1/messages/batches/{id} - 查询消息批次状态
+pub async fn get_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    match load_message_batch(&state, &headers, &id).await {
+        Ok(batch) => (StatusCode::OK, Json(message_batch_object(&batch))).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// GET /v1/messages/batches/{id}/results - 获取消息批次结果（JSONL）
+pub async fn get_message_batch_results(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let batch = match load_message_batch(&state, &headers, &id).await {
+        Ok(batch) => batch,
+        Err(response) => return response,
+    };
+    if is_processing(batch.status) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            &format!("Message batch {id} is still processing"),
+        );
+    }
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/x-jsonl")],
+        message_batch_results(&batch),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_scheduler::{TaskResult, TokenUsage};
+
+    fn create_request() -> CreateMessageBatchRequest {
+        serde_json::from_value(json!({
+            "requests": [
+                {
+                    "custom_id": "first",
+                    "params": {
+                        "model": "claude-sonnet-4-5",
+                        "max_tokens": 256,
+                        "messages": [{"role": "user", "content": "Hello"}]
+                    }
+                },
+                {
+                    "custom_id": "second",
+                    "params": {
+                        "model": "claude-sonnet-4-5",
+                        "max_tokens": 256,
+                        "stream": true,
+                        "messages": [{"role": "user", "content": "Bye"}]
+                    }
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn result(task: &TaskDefinition, status: BatchTaskStatus2) -> TaskResult {
+        TaskResult {
+            task_id: task.id.unwrap(),
+            status,
+            content: (status == BatchTaskStatus2::Completed).then(|| "Hi there".to_string()),
+            error: (status == BatchTaskStatus2::Failed).then(|| "upstream 500".to_string()),
+            usage: TokenUsage::new(10, 3),
+            response: None,
+            started_at: chrono::Utc::now(),
+            completed_at: Some(chrono::Utc::now()),
+        }
+    }
+
+    #[test]
+    fn test_create_poll_retrieve() {
+        // 创建：每个请求成为带完整请求的子任务
+        let mut batch = build_message_batch(create_request()).unwrap();
+        assert_eq!(batch.tasks.len(), 2);
+        let request = super::super::batch_executor::build_task_request(&batch.tasks[1], None)
+            .expect("子任务应带有完整请求");
+        assert_eq!(request.model, "claude-sonnet-4-5");
+        assert!(!request.stream);
+
+        let id = to_message_batch_id(&batch.id);
+        assert_eq!(parse_message_batch_id(&id), Some(batch.id));
+
+        // 轮询：执行中
+        batch.status = BatchTaskStatus::Running;
+        batch
+            .results
+            .push(result(&batch.tasks[0], BatchTaskStatus2::Completed));
+        let object = message_batch_object(&batch);
+        assert_eq!(object["id"], id);
+        assert_eq!(object["processing_status"], "in_progress");
+        assert_eq!(object["request_counts"]["processing"], 1);
+        assert_eq!(object["request_counts"]["succeeded"], 1);
+        assert!(object["results_url"].is_null());
+
+        // 轮询：结束
+        batch
+            .results
+            .push(result(&batch.tasks[1], BatchTaskStatus2::Failed));
+        batch.status = BatchTaskStatus::PartiallyCompleted;
+        batch.completed_at = Some(chrono::Utc::now());
+        let object = message_batch_object(&batch);
+        assert_eq!(object["processing_status"], "ended");
+        assert_eq!(object["request_counts"]["processing"], 0);
+        assert_eq!(object["request_counts"]["errored"], 1);
+        assert_eq!(
+            object["results_url"],
+            format!("/v1/messages/batches/{id}/results")
+        );
+
+        // 获取结果
+        let lines: Vec<Value> = message_batch_results(&batch)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["custom_id"], "first");
+        assert_eq!(lines[0]["result"]["type"], "succeeded");
+        assert_eq!(
+            lines[0]["result"]["message"]["content"][0]["text"],
+            "Hi there"
+        );
+        assert_eq!(lines[0]["result"]["message"]["usage"]["output_tokens"], 3);
+        assert_eq!(lines[1]["custom_id"], "second");
+        assert_eq!(lines[1]["result"]["type"], "errored");
+        assert_eq!(
+            lines[1]["result"]["error"]["error"]["message"],
+            "upstream 500"
+        );
+    }
+
+    #[test]
+    fn test_results_keep_tool_use_and_stop_reason() {
+        let mut batch = build_message_batch(create_request()).unwrap();
+        batch.status = BatchTaskStatus::Completed;
+
+        let mut tool_call = result(&batch.tasks[0], BatchTaskStatus2::Completed);
+        tool_call.response = Some(json!({
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }));
+        let mut truncated = result(&batch.tasks[1], BatchTaskStatus2::Completed);
+        truncated.response = Some(json!({
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Partial"},
+                "finish_reason": "length"
+            }]
+        }));
+        batch.results = vec![tool_call, truncated];
+
+        let lines: Vec<Value> = message_batch_results(&batch)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let message = &lines[0]["result"]["message"];
+        assert_eq!(message["stop_reason"], "tool_use");
+        assert_eq!(message["content"][0]["type"], "tool_use");
+        assert_eq!(message["content"][0]["id"], "call_1");
+        assert_eq!(message["content"][0]["name"], "get_weather");
+        assert_eq!(message["content"][0]["input"]["city"], "Paris");
+
+        let message = &lines[1]["result"]["message"];
+        assert_eq!(message["stop_reason"], "max_tokens");
+        assert_eq!(message["content"][0]["text"], "Partial");
+    }
+
+    #[test]
+    fn test_invalid_batches_rejected() {
+        assert!(build_message_batch(CreateMessageBatchRequest { requests: vec![] }).is_err());
+
+        let mut request = create_request();
+        request.requests[1].custom_id = "first".to_string();
+        assert!(build_message_batch(request)
+            .unwrap_err()
+            .contains("Duplicate custom_id"));
+
+        assert_eq!(parse_message_batch_id("batch_123"), None);
+    }
+}
@@ -24,30 +24,57 @@ use axum::{
 };
 use serde_json::json;
 use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
 
 use crate::client_detector::ClientType;
-use crate::{record_request_telemetry, record_token_usage, AppState};
+use crate::{
+    record_request_telemetry, record_stream_telemetry, record_token_usage, watch_disconnect,
+    AppState, DEFAULT_KIRO_REFRESH_KEY, SESSION_METADATA_KEY,
+};
+use proxycast_core::config::{ApiKeyMatch, ServerApiKeys};
+use proxycast_core::credential::SelectionTrace;
+use proxycast_core::database::dao::model_registry::ModelRegistryDao;
+use proxycast_core::middleware::request_id_from_headers;
 use proxycast_core::models::anthropic::AnthropicMessagesRequest;
 use proxycast_core::models::openai::ChatCompletionRequest;
+use proxycast_core::models::provider_capabilities::CapabilityError;
+use proxycast_core::models::provider_pool_model::ProviderCredential;
+use proxycast_core::models::route_model::RequestRoute;
+use proxycast_core::session::session_id_from_headers;
 use proxycast_core::ProviderType;
-use proxycast_processor::RequestContext;
+use proxycast_infra::resilience::{CircuitOpenError, OVERLOADED_STATUS_CODE};
+use proxycast_processor::{RequestContext, RequestStage};
 use proxycast_providers::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use proxycast_providers::stream::StopReason;
 use proxycast_providers::streaming::StreamFormat as StreamingFormat;
 use proxycast_server_utils::{
-    build_anthropic_response, build_anthropic_stream_response, message_content_len,
-    parse_cw_response, safe_truncate,
+    annotate_output_clamp, apply_structured_output, apply_upstream_override,
+    apply_user_metadata_policy, build_anthropic_response, build_anthropic_stream_response,
+    check_anthropic_capabilities, cw_parse_error_response, deferred_stream_response, display_text,
+    emulate_multiple_choices, enforce_structured_response, ensure_response_mode, fallback_allowed,
+    handle_structured_response, is_local_error, known_model_ids, message_content_len,
+    no_credential_response, parse_cw_response_bytes, plan_openai_request, reject_self_upstream,
+    safe_truncate, should_buffer_stream, suggest_on_model_not_found,
+    take_pending_structured_output, unsupported_capability_response, validate_anthropic_tools,
+    BufferFormat, CWParseError, ModelOutputLimits, OutputClamp, OUTPUT_LIMITS_TTL,
 };
 
 use super::{call_provider_anthropic, call_provider_openai};
 
+/// 上游过载时最多换用的凭证数
+pub(crate) const MAX_OVERLOAD_FAILOVERS: usize = 2;
+
 async fn select_credential_for_request(
     state: &AppState,
     selected_provider: &str,
     model: &str,
     client_type: &ClientType,
     explicit_provider_id: Option<&str>,
+    allow_fallback: bool,
     log_prefix: &str,
     include_error_code: bool,
+    trace: &mut SelectionTrace,
 ) -> Result<Option<proxycast_core::models::provider_pool_model::ProviderCredential>, Response> {
     let db = match &state.db {
         Some(db) => db,
@@ -59,13 +86,16 @@ async fn select_credential_for_request(
 
     if let Some(explicit_provider_id) = explicit_provider_id {
         eprintln!("[{log_prefix}] 使用 X-Provider-Id 指定的 provider: {explicit_provider_id}");
+        trace.provider_type = explicit_provider_id.to_string();
         let cred = state
             .pool_service
-            .select_credential_with_client_check(
+            .select_credential_traced(
                 db,
                 explicit_provider_id,
                 Some(model),
                 Some(client_type),
+                &[],
+                trace,
             )
             .ok()
             .flatten();
@@ -97,15 +127,25 @@ async fn select_credential_for_request(
         return Ok(cred);
     }
 
-    if !state.allow_provider_fallback {
+    // 自定义 Provider 在 routing.provider_protocols 中声明的协议，按该协议选择凭证
+    let declared_protocol = state
+        .routing
+        .read()
+        .await
+        .declared_protocol(selected_provider);
+
+    if !allow_fallback {
         eprintln!(
-            "[{log_prefix}] 已禁用自动降级（retry.auto_switch_provider=false），仅从 Provider Pool 选择"
+            "[{log_prefix}] 已禁用自动降级（retry 配置或 X-ProxyCast-No-Fallback），仅从 Provider Pool 选择"
         );
-        return match state.pool_service.select_credential_with_client_check(
+        let pool_provider = declared_protocol.map(|protocol| protocol.to_string());
+        return match state.pool_service.select_credential_traced(
             db,
-            selected_provider,
+            pool_provider.as_deref().unwrap_or(selected_provider),
             Some(model),
             Some(client_type),
+            &[],
+            trace,
         ) {
             Ok(cred) => {
                 if cred.is_some() {
@@ -127,13 +167,15 @@ async fn select_credential_for_request(
     let provider_id_hint = selected_provider.to_lowercase();
     match state
         .pool_service
-        .select_credential_with_fallback(
+        .select_credential_with_fallback_traced(
             db,
             &state.api_key_service,
             selected_provider,
+            declared_protocol,
             Some(model),
             Some(provider_id_hint.as_str()),
             Some(client_type),
+            trace,
         )
         .await
     {
@@ -152,19 +194,45 @@ async fn select_credential_for_request(
     }
 }
 
+/// Provider 熔断时的快速失败响应
+pub(crate) fn circuit_open_response(error: &CircuitOpenError) -> Response {
+    let mut response = (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "error": {
+                "type": "provider_unavailable",
+                "code": "circuit_open",
+                "message": error.to_string()
+            }
+        })),
+    )
+        .into_response();
+    if let Ok(value) = error.retry_after.as_secs().max(1).to_string().parse() {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+/// 带超时、重试和熔断地调用单个凭证
+///
+/// `switch_on_overload` 为 true 时上游过载（529）直接返回，由调用方换用其他凭证。
 async fn call_with_single_provider_resilience<F, Fut>(
     state: &AppState,
     request_id: &str,
-    provider_label: &str,
+    credential: &ProviderCredential,
     is_stream: bool,
+    switch_on_overload: bool,
     mut operation: F,
 ) -> Response
 where
     F: FnMut() -> Fut,
     Fut: Future<Output = Response>,
 {
+    let provider = credential.provider_type;
+    let provider_label = provider.to_string();
     let retrier = state.processor.retrier.clone();
     let timeout_controller = state.processor.timeout.clone();
+    let circuit_breaker = state.processor.circuit_breaker.clone();
     let max_retries = if is_stream {
         0
     } else {
@@ -176,9 +244,26 @@ where
     loop {
         attempt += 1;
 
+        // 熔断期间不再向上游发起请求（包括剩余的重试），避免放大故障。
+        // 熔断按凭证统计，单个自定义端点故障不影响同类型的其他凭证
+        let permit = match circuit_breaker.try_acquire(provider, &credential.uuid) {
+            Ok(permit) => permit,
+            Err(open) => {
+                state.logs.write().await.add(
+                    "warn",
+                    &format!(
+                        "[CIRCUIT] request_id={} provider={} attempt={}/{} fast-fail: {}",
+                        request_id, provider_label, attempt, total_attempts, open
+                    ),
+                );
+                return circuit_open_response(&open);
+            }
+        };
+
         let response = match timeout_controller.execute_with_timeout(operation()).await {
             Ok(resp) => resp,
             Err(timeout_err) => {
+                permit.record_failure();
                 if attempt <= max_retries {
                     let delay = retrier.backoff_delay(attempt - 1);
                     state.logs.write().await.add(
@@ -220,10 +305,31 @@ where
         };
 
         let status_code = response.status().as_u16();
-        let should_retry = attempt <= max_retries && retrier.config().is_retryable(status_code);
+        if is_local_error(&response) {
+            // 请求没有到达上游，不计入熔断统计
+            permit.release();
+        } else {
+            permit.record_status(status_code);
+        }
+
+        // 上游过载：调用方会换用其他凭证时直接交还，避免继续压垮同一上游
+        if status_code == OVERLOADED_STATUS_CODE && switch_on_overload {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[OVERLOADED] request_id={} provider={} attempt={}/{} status=529 switching credential",
+                    request_id, provider_label, attempt, total_attempts
+                ),
+            );
+            return response;
+        }
+
+        let should_retry = attempt <= max_retries
+            && (status_code == OVERLOADED_STATUS_CODE
+                || retrier.config().is_retryable(status_code));
 
         if should_retry {
-            let delay = retrier.backoff_delay(attempt - 1);
+            let delay = retrier.backoff_delay_for_status(attempt - 1, status_code);
 
             if status_code == StatusCode::TOO_MANY_REQUESTS.as_u16() {
                 state.logs.write().await.add(
@@ -233,6 +339,14 @@ where
                         request_id, provider_label, attempt, total_attempts
                     ),
                 );
+            } else if status_code == OVERLOADED_STATUS_CODE {
+                state.logs.write().await.add(
+                    "warn",
+                    &format!(
+                        "[OVERLOADED] request_id={} provider={} attempt={}/{} status=529",
+                        request_id, provider_label, attempt, total_attempts
+                    ),
+                );
             }
 
             state.logs.write().await.add(
@@ -270,24 +384,57 @@ where
 // ============================================================================
 
 /// 根据客户端类型和端点配置选择 Provider
-async fn select_provider_for_client(headers: &HeaderMap, state: &AppState) -> (String, ClientType) {
-    let user_agent = headers
-        .get("user-agent")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    let client_type = ClientType::from_user_agent(user_agent);
-
+async fn select_provider_for_client(client_type: ClientType, state: &AppState) -> String {
     let endpoint_providers = state.endpoint_providers.read().await;
-    let endpoint_provider = endpoint_providers.get_provider(client_type.config_key());
-
-    let default_provider = state.default_provider.read().await.clone();
-
-    let selected_provider = match endpoint_provider {
+    match endpoint_providers.get_provider(client_type.config_key()) {
         Some(provider) => provider.clone(),
-        None => default_provider,
-    };
+        None => state.default_provider.read().await.clone(),
+    }
+}
 
-    (selected_provider, client_type)
+/// 端点 Provider 被停用时的 503 响应
+///
+/// 同时带有 Anthropic 的 `type: "error"` 与 OpenAI 的 `error.code`，两种客户端都能解析。
+pub fn endpoint_disabled_response(client_type: ClientType) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "type": "error",
+            "error": {
+                "type": "endpoint_disabled",
+                "code": "endpoint_disabled",
+                "message": format!(
+                    "Endpoint provider for client type '{}' is disabled",
+                    client_type.config_key()
+                )
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 客户端类型对应的端点 Provider 已停用时返回 503 响应
+async fn reject_disabled_endpoint(
+    state: &AppState,
+    ctx: &RequestContext,
+    client_type: ClientType,
+) -> Option<Response> {
+    if state
+        .endpoint_providers
+        .read()
+        .await
+        .is_enabled(client_type.config_key())
+    {
+        return None;
+    }
+    state.logs.write().await.add(
+        "warn",
+        &format!(
+            "[CLIENT] request_id={} client_type={} 端点 Provider 已停用，拒绝请求",
+            ctx.request_id, client_type
+        ),
+    );
+    Some(endpoint_disabled_response(client_type))
 }
 
 // ============================================================================
@@ -295,10 +442,12 @@ async fn select_provider_for_client(headers: &HeaderMap, state: &AppState) -> (S
 // ============================================================================
 
 /// OpenAI 格式的 API key 验证
+///
+/// 成功时返回匹配到的密钥标签（用于请求日志）
 pub async fn verify_api_key(
     headers: &HeaderMap,
-    expected_key: &str,
-) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    api_keys: &ServerApiKeys,
+) -> Result<Option<String>, (StatusCode, Json<serde_json::Value>)> {
     let auth = headers
         .get("authorization")
         .or_else(|| headers.get("x-api-key"))
@@ -315,21 +464,26 @@ pub async fn verify_api_key(
         }
     };
 
-    if key != expected_key {
-        return Err((
+    match api_keys.check(key) {
+        ApiKeyMatch::Accepted { label } => Ok(label),
+        ApiKeyMatch::Disabled => Err((
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": {"message": "API key has been disabled"}})),
+        )),
+        ApiKeyMatch::Invalid => Err((
             StatusCode::UNAUTHORIZED,
             Json(serde_json::json!({"error": {"message": "Invalid API key"}})),
-        ));
+        )),
     }
-
-    Ok(())
 }
 
 /// Anthropic 格式的 API key 验证
+///
+/// 成功时返回匹配到的密钥标签（用于请求日志）
 pub async fn verify_api_key_anthropic(
     headers: &HeaderMap,
-    expected_key: &str,
-) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    api_keys: &ServerApiKeys,
+) -> Result<Option<String>, (StatusCode, Json<serde_json::Value>)> {
     let auth = headers
         .get("x-api-key")
         .or_else(|| headers.get("authorization"))
@@ -352,23 +506,133 @@ pub async fn verify_api_key_anthropic(
         }
     };
 
-    if key != expected_key {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({
-                "type": "error",
-                "error": {
-                    "type": "authentication_error",
-                    "message": "Invalid API key"
-                }
-            })),
-        ));
+    let message = match api_keys.check(key) {
+        ApiKeyMatch::Accepted { label } => return Ok(label),
+        ApiKeyMatch::Disabled => "API key has been disabled",
+        ApiKeyMatch::Invalid => "Invalid API key",
+    };
+    Err((
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "authentication_error",
+                "message": message
+            }
+        })),
+    ))
+}
+
+pub async fn chat_completions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatCompletionRequest>,
+) -> Response {
+    let model = request.model.clone();
+    let output_clamp = clamp_output_tokens(&state, &headers, &model, &mut request.max_tokens).await;
+    // 流式请求按配置或请求头缓冲为非流式响应；响应形式与客户端期望不一致时互相转换
+    let client_stream = request.stream;
+    let buffer = client_stream && should_buffer_stream(&headers, state.force_buffer_stream);
+    if aggregates_stream(&state, client_stream, buffer) {
+        request.request_stream_usage();
     }
+    let response = handle_chat_completions(State(state.clone()), headers, Json(request)).await;
+    let response = finalize_response(
+        &state,
+        response,
+        BufferFormat::OpenAi,
+        client_stream && !buffer,
+    )
+    .await;
+    let response = annotate_output_clamp(response, output_clamp);
+    suggest_for_unknown_model(&state, &model, response).await
+}
 
-    Ok(())
+/// Anthropic 响应的估算输出 Token 数（上游用量未解析时使用）
+pub(crate) const ESTIMATED_ANTHROPIC_OUTPUT_TOKENS: u32 = 100;
+
+/// 按消息文本长度估算 Anthropic 请求的输入 Token 数（约 4 字符 = 1 token）
+pub(crate) fn estimate_anthropic_input_tokens(request: &AnthropicMessagesRequest) -> u32 {
+    request
+        .messages
+        .iter()
+        .map(|m| {
+            let content_len = match &m.content {
+                serde_json::Value::String(s) => s.len(),
+                serde_json::Value::Array(arr) => arr
+                    .iter()
+                    .filter_map(|v| v.get("text").and_then(|t| t.as_str()))
+                    .map(|s| s.len())
+                    .sum(),
+                _ => 0,
+            };
+            content_len / 4
+        })
+        .sum::<usize>() as u32
 }
 
-pub async fn chat_completions(
+/// 当前的模型输出上限（来自模型注册表，按有效期刷新）
+pub(crate) fn output_limits(state: &AppState) -> Arc<ModelOutputLimits> {
+    state
+        .model_output_limits
+        .get_or_load(OUTPUT_LIMITS_TTL, || match &state.db {
+            Some(db) => {
+                let conn = proxycast_core::database::lock_db(db)?;
+                ModelRegistryDao::get_max_output_tokens(&conn).map_err(|e| e.to_string())
+            }
+            None => Ok(Vec::new()),
+        })
+}
+
+/// 按分发时实际使用的模型裁剪 `max_tokens`
+///
+/// 与处理流程一致，先应用客户端默认模型、再解析模型别名，按解析结果查找上限。
+async fn clamp_output_tokens(
+    state: &AppState,
+    headers: &HeaderMap,
+    model: &str,
+    max_tokens: &mut Option<u32>,
+) -> Option<OutputClamp> {
+    let client = state.client_detector.detect(headers);
+    let model = client.model_fallback(model).unwrap_or(model);
+    let resolved = state.processor.resolve_model(model).await;
+    output_limits(state).clamp(&resolved, max_tokens)
+}
+
+/// 服务端是否会聚合上游的流式响应（缓冲为非流式或执行后处理器）
+///
+/// 聚合时需要上游在流末尾返回用量，OpenAI 请求应设置 `stream_options.include_usage`。
+pub(crate) fn aggregates_stream(state: &AppState, client_stream: bool, buffer: bool) -> bool {
+    buffer || (client_stream && !state.post_processors.is_empty())
+}
+
+/// 把响应转换为客户端期望的形式并执行响应后处理器
+///
+/// 后处理器需要完整的响应文本：配置了后处理器时，流式响应先聚合为非流式响应，
+/// 处理后再转换为合成流返回。流被缓冲为非流式响应时校验待校验的结构化输出。
+pub(crate) async fn finalize_response(
+    state: &AppState,
+    mut response: Response,
+    format: BufferFormat,
+    stream: bool,
+) -> Response {
+    let structured_output = take_pending_structured_output(&mut response);
+    if stream && !state.post_processors.is_empty() {
+        let response = ensure_response_mode(response, format, false).await;
+        let response = state.post_processors.apply_to_response(response).await;
+        return ensure_response_mode(response, format, true).await;
+    }
+    let response = ensure_response_mode(response, format, stream).await;
+    let response = match structured_output {
+        Some(structured) if !stream => {
+            enforce_structured_response(response, &structured, state.strict_structured_output).await
+        }
+        _ => response,
+    };
+    state.post_processors.apply_to_response(response).await
+}
+
+async fn handle_chat_completions(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(mut request): Json<ChatCompletionRequest>,
@@ -380,19 +644,45 @@ pub async fn chat_completions(
     eprintln!("[CHAT_COMPLETIONS] 流式: {}", request.stream);
     eprintln!("[CHAT_COMPLETIONS] 消息数量: {}", request.messages.len());
 
-    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
-        eprintln!("[CHAT_COMPLETIONS] 认证失败!");
-        state
-            .logs
-            .write()
-            .await
-            .add("warn", "Unauthorized request to /v1/chat/completions");
-        return e.into_response();
-    }
+    let api_key_label = match verify_api_key(&headers, &*state.api_keys.read().await).await {
+        Ok(label) => label,
+        Err(e) => {
+            eprintln!("[CHAT_COMPLETIONS] 认证失败!");
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", "Unauthorized request to /v1/chat/completions");
+            return e.into_response();
+        }
+    };
     eprintln!("[CHAT_COMPLETIONS] 认证成功");
 
+    let session = match check_session_quota(&state, &headers).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    // 识别客户端；请求未指定模型时使用该客户端配置的默认模型
+    let client = state.client_detector.detect(&headers);
+    if let Some(model) = client.model_fallback(&request.model) {
+        request.model = model.to_string();
+    }
+
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    if let Some(request_id) = request_id_from_headers(&headers) {
+        ctx = ctx.with_request_id(request_id);
+    }
+    ctx.set_client_id(client.client_id.clone());
+    ctx.set_route(RequestRoute::Default);
+    ctx.set_api_key_label(api_key_label);
+    if let Some(session_id) = session_id_from_headers(&headers) {
+        ctx.set_session_id(session_id);
+    }
+    if let Some(session) = session {
+        ctx.set_metadata(SESSION_METADATA_KEY, json!(session));
+    }
     eprintln!("[CHAT_COMPLETIONS] 请求ID: {}", ctx.request_id);
 
     state.logs.write().await.add(
@@ -429,7 +719,9 @@ pub async fn chat_completions(
     if injection_enabled {
         let injector = state.processor.injector.read().await;
         let mut payload = serde_json::to_value(&request).unwrap_or_default();
-        let result = injector.inject(&request.model, &mut payload);
+        let result = injector.inject_filtered(&request.model, &mut payload, |id| {
+            client.allows_injection_rule(id)
+        });
         if result.has_injections() {
             state.logs.write().await.add(
                 "info",
@@ -447,15 +739,20 @@ pub async fn chat_completions(
 
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
-    let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    let client_type = client.client_type;
+    let selected_provider = select_provider_for_client(client_type, &state).await;
+    let allow_fallback = fallback_allowed(&state.fallback_settings, &selected_provider, &headers);
     eprintln!("[CHAT_COMPLETIONS] 客户端类型: {client_type}, 选择的Provider: {selected_provider}");
+    if let Some(response) = reject_disabled_endpoint(&state, &ctx, client_type).await {
+        return response;
+    }
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
         "info",
         &format!(
-            "[CLIENT] request_id={} client_type={} selected_provider={}",
-            ctx.request_id, client_type, selected_provider
+            "[CLIENT] request_id={} client={} client_type={} selected_provider={}",
+            ctx.request_id, client.client_id, client_type, selected_provider
         ),
     );
 
@@ -478,23 +775,47 @@ pub async fn chat_completions(
     // 1) X-Provider-Id 指定时仅走精确匹配（不降级）
     // 2) 否则走统一的“池优先 + API Key Provider 智能降级”路径
     eprintln!("[CHAT_COMPLETIONS] 开始选择凭证...");
-    let credential = match select_credential_for_request(
+    let selection_started = Instant::now();
+    let mut selection_trace =
+        SelectionTrace::new(selected_provider.as_str(), state.verbose_selection_trace);
+    let credential = select_credential_for_request(
         &state,
         &selected_provider,
         &request.model,
         &client_type,
         provider_id_header.as_deref(),
+        allow_fallback,
         "CHAT_COMPLETIONS",
         true,
+        &mut selection_trace,
     )
-    .await
-    {
+    .await;
+    ctx.record_stage(RequestStage::Selection, selection_started.elapsed());
+    ctx.set_selection_trace(selection_trace);
+    let credential = match credential {
         Ok(cred) => cred,
         Err(resp) => return resp,
     };
 
     // 如果找到凭证池中的凭证，使用它
-    if let Some(cred) = credential {
+    if let Some(mut cred) = credential {
+        apply_upstream_override(&headers, state.allow_upstream_override, &mut cred);
+        if let Some(resp) = reject_self_upstream(
+            &cred,
+            &state.bind_host,
+            state.bind_port,
+            state.allow_self_upstream,
+        ) {
+            return resp;
+        }
+        let emulate_choices =
+            match plan_openai_request(cred.provider_type, &request, state.emulate_multiple_choices)
+            {
+                Ok(emulate) => emulate,
+                Err(e) => return unsupported_capability(&state, &e).await,
+            };
+        let structured_output = apply_structured_output(cred.provider_type, &mut request);
+        ctx.set_credential_id(cred.uuid.clone());
         eprintln!(
             "[CHAT_COMPLETIONS] 使用凭证: type={}, name={:?}, uuid={}",
             cred.provider_type,
@@ -534,29 +855,110 @@ pub async fn chat_completions(
         // **Validates: Requirements 2.1, 2.3, 2.5**
 
         eprintln!("[CHAT_COMPLETIONS] 调用 Provider: {}", cred.provider_type);
-        let provider_label = cred.provider_type.to_string();
-        let response = call_with_single_provider_resilience(
-            &state,
-            &ctx.request_id,
-            &provider_label,
-            request.stream,
-            || async { call_provider_openai(&state, &cred, &request, None).await },
-        )
-        .await;
+        // 上游过载时换用同类型的其他凭证重试（模拟多候选回复时不换用），
+        // 没有其他凭证时按过载退避后在当前凭证上重试
+        let mut current = cred.clone();
+        let mut failovers = 0;
+        let response = loop {
+            let switch_on_overload =
+                allow_fallback && !emulate_choices && failovers < MAX_OVERLOAD_FAILOVERS;
+            let upstream_started = Instant::now();
+            let cancel_token = &ctx.cancel_token;
+            let upstream = async {
+                if emulate_choices {
+                    tracing::info!(
+                        "[CHAT_COMPLETIONS] {} 不支持 n={}，并发调用模拟多候选回复",
+                        current.provider_type,
+                        request.choice_count()
+                    );
+                    emulate_multiple_choices(&request, |single| {
+                        let (state, cred, request_id) = (&state, &current, &ctx.request_id);
+                        async move {
+                            call_with_single_provider_resilience(
+                                state,
+                                request_id,
+                                cred,
+                                false,
+                                false,
+                                || async {
+                                    call_provider_openai(state, cred, &single, None, cancel_token)
+                                        .await
+                                },
+                            )
+                            .await
+                        }
+                    })
+                    .await
+                } else {
+                    call_with_single_provider_resilience(
+                        &state,
+                        &ctx.request_id,
+                        &current,
+                        request.stream,
+                        switch_on_overload,
+                        || async {
+                            call_provider_openai(&state, &current, &request, None, cancel_token)
+                                .await
+                        },
+                    )
+                    .await
+                }
+            };
+            let response = watch_disconnect(&state, &ctx, upstream).await;
+            ctx.record_stage(RequestStage::Upstream, upstream_started.elapsed());
+            if !switch_on_overload || response.status().as_u16() != OVERLOADED_STATUS_CODE {
+                break response;
+            }
+            match switch_overloaded_credential(
+                &state,
+                &mut ctx,
+                &current,
+                &selected_provider,
+                &request.model,
+                Some(&client_type),
+            )
+            .await
+            {
+                Some(alternative) => {
+                    current = alternative;
+                    failovers += 1;
+                }
+                None => {
+                    wait_overload_backoff(&state, &ctx).await;
+                    failovers = MAX_OVERLOAD_FAILOVERS;
+                }
+            }
+        };
+        let response = match structured_output {
+            Some(format) => {
+                handle_structured_response(
+                    response,
+                    format,
+                    request.stream,
+                    state.strict_structured_output,
+                )
+                .await
+            }
+            None => response,
+        };
         eprintln!(
             "[CHAT_COMPLETIONS] Provider 响应状态: {}",
             response.status()
         );
 
-        // 记录请求统计
+        // 记录请求统计（成功的流式响应在响应体结束时记录，附带首 token 耗时）
         let is_success = response.status().is_success();
-        let _status_code = response.status().as_u16();
+        if is_success && request.stream {
+            return record_stream_telemetry(&state, &ctx, response);
+        }
         let status = if is_success {
             proxycast_infra::telemetry::RequestStatus::Success
         } else {
             proxycast_infra::telemetry::RequestStatus::Failed
         };
-        record_request_telemetry(&state, &ctx, status, None);
+        let error_message = (response.status().as_u16() == OVERLOADED_STATUS_CODE)
+            .then(|| "upstream overloaded (529)".to_string());
+        record_request_telemetry(&state, &ctx, status, error_message);
 
         // 如果成功且需要 Flow 捕获，提取响应体内容和响应头
         // 注意：非流式响应需要读取 body，所以必须在这里处理
@@ -566,9 +968,9 @@ pub async fn chat_completions(
     // 回退到旧的单凭证模式（仅当允许自动降级且选择的 Provider 是 Kiro 时）
     // 其余情况（含禁用自动降级）直接返回无可用凭证错误
     // **Validates: Requirements 3.2**
-    if !state.allow_provider_fallback || selected_provider.to_lowercase() != "kiro" {
-        let reason = if !state.allow_provider_fallback {
-            "auto fallback disabled by retry.auto_switch_provider=false"
+    if !allow_fallback || selected_provider.to_lowercase() != "kiro" {
+        let reason = if !allow_fallback {
+            "auto fallback disabled by retry config or X-ProxyCast-No-Fallback"
         } else {
             "legacy mode only supports Kiro"
         };
@@ -578,28 +980,7 @@ pub async fn chat_completions(
                 "[ROUTE] No pool credential found for '{selected_provider}' (client_type={client_type}), {reason}"
             ),
         );
-        let message = if !state.allow_provider_fallback {
-            format!(
-                "没有找到可用的 '{}' 凭证（已禁用自动降级）。请在凭证池中添加对应的凭证。",
-                selected_provider
-            )
-        } else {
-            format!(
-                "没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。",
-                selected_provider
-            )
-        };
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(serde_json::json!({
-                "error": {
-                    "message": message,
-                    "type": "no_credential_error",
-                    "code": "no_credential"
-                }
-            })),
-        )
-            .into_response();
+        return no_credential_response(BufferFormat::OpenAi, &selected_provider, allow_fallback);
     }
 
     state.logs.write().await.add(
@@ -617,9 +998,29 @@ pub async fn chat_completions(
     // 检查是否需要拦截请求（legacy mode）
     // **Validates: Requirements 2.1, 2.3, 2.5**
 
+    // Kiro 需要完整缓冲上游响应后才能转换：流式请求较慢时先发送响应头，缓冲期间由心跳层保活
+    let stream = request.stream && !should_buffer_stream(&headers, state.force_buffer_stream);
+    let grace = state.stream_heartbeat;
+    let call = legacy_kiro_chat_completions(state, ctx, request);
+    if stream {
+        return deferred_stream_response(call, BufferFormat::OpenAi, grace).await;
+    }
+    call.await
+}
+
+/// 旧的单凭证模式：直接使用 Kiro 默认凭证处理 Chat Completions 请求
+async fn legacy_kiro_chat_completions(
+    state: AppState,
+    mut ctx: RequestContext,
+    request: ChatCompletionRequest,
+) -> Response {
     // 检查是否需要刷新 token（无 token 或即将过期）
+    let refresh_started = Instant::now();
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let _guard = state
+            .kiro_refresh_locks
+            .lock(DEFAULT_KIRO_REFRESH_KEY)
+            .await;
         let mut kiro = state.kiro.write().await;
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
@@ -638,16 +1039,24 @@ pub async fn chat_completions(
             }
         }
     }
+    ctx.record_stage(RequestStage::Refresh, refresh_started.elapsed());
 
     let kiro = state.kiro.read().await;
 
-    match kiro.call_api(&request).await {
+    let upstream_started = Instant::now();
+    let upstream = watch_disconnect(&state, &ctx, kiro.call_api(&request)).await;
+    ctx.record_stage(RequestStage::Upstream, upstream_started.elapsed());
+    match upstream {
         Ok(resp) => {
             let status = resp.status();
             if status.is_success() {
-                match resp.text().await {
+                match watch_disconnect(&state, &ctx, resp.bytes()).await {
                     Ok(body) => {
-                        let parsed = parse_cw_response(&body);
+                        let parsed =
+                            match parse_cw_response_bytes(&body).or_else(CWParseError::recover) {
+                                Ok(parsed) => parsed,
+                                Err(e) => return cw_parse_error_response(&e),
+                            };
                         let has_tool_calls = !parsed.tool_calls.is_empty();
 
                         state.logs.write().await.add(
@@ -754,7 +1163,10 @@ pub async fn chat_completions(
             } else if status.as_u16() == 403 || status.as_u16() == 402 {
                 // Token 过期或账户问题，尝试重新加载凭证并刷新
                 drop(kiro);
-                let _guard = state.kiro_refresh_lock.lock().await;
+                let _guard = state
+                    .kiro_refresh_locks
+                    .lock(DEFAULT_KIRO_REFRESH_KEY)
+                    .await;
                 let mut kiro = state.kiro.write().await;
                 state.logs.write().await.add(
                     "warn",
@@ -782,12 +1194,17 @@ pub async fn chat_completions(
                         // 重试请求
                         drop(kiro);
                         let kiro = state.kiro.read().await;
-                        match kiro.call_api(&request).await {
+                        match watch_disconnect(&state, &ctx, kiro.call_api(&request)).await {
                             Ok(retry_resp) => {
                                 if retry_resp.status().is_success() {
-                                    match retry_resp.text().await {
+                                    match watch_disconnect(&state, &ctx, retry_resp.bytes()).await {
                                         Ok(body) => {
-                                            let parsed = parse_cw_response(&body);
+                                            let parsed = match parse_cw_response_bytes(&body)
+                                                .or_else(CWParseError::recover)
+                                            {
+                                                Ok(parsed) => parsed,
+                                                Err(e) => return cw_parse_error_response(&e),
+                                            };
                                             let has_tool_calls = !parsed.tool_calls.is_empty();
 
                                             let message = if has_tool_calls {
@@ -844,7 +1261,9 @@ pub async fn chat_completions(
                                         }
                                     }
                                 }
-                                let body = retry_resp.text().await.unwrap_or_default();
+                                let body = watch_disconnect(&state, &ctx, retry_resp.text())
+                                    .await
+                                    .unwrap_or_default();
                                 // 标记 Flow 失败（重试失败）
                                 (
                                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -876,7 +1295,9 @@ pub async fn chat_completions(
                     }
                 }
             } else {
-                let body = resp.text().await.unwrap_or_default();
+                let body = watch_disconnect(&state, &ctx, resp.text())
+                    .await
+                    .unwrap_or_default();
                 state.logs.write().await.add(
                     "error",
                     &format!("Upstream error {}: {}", status, safe_truncate(&body, 200)),
@@ -904,23 +1325,283 @@ pub async fn chat_completions(
     }
 }
 
+/// 为重试选择同类型的凭证
+///
+/// 优先选择本次请求尚未尝试过的凭证，全部尝试过时才重新使用已尝试的凭证
+fn select_retry_credential(
+    state: &AppState,
+    selected_provider: &str,
+    model: &str,
+    client_type: Option<&ClientType>,
+    ctx: &RequestContext,
+) -> Option<proxycast_core::models::provider_pool_model::ProviderCredential> {
+    let db = state.db.as_ref()?;
+    state
+        .pool_service
+        .select_credential_for_retry(
+            db,
+            selected_provider,
+            Some(model),
+            client_type,
+            &ctx.tried_credential_ids(),
+        )
+        .ok()
+        .flatten()
+}
+
+/// 上游过载（529）时为本次请求换用同类型的其他凭证
+///
+/// 选中的凭证记入请求上下文；没有其他可用凭证时返回 `None`。
+pub(crate) async fn switch_overloaded_credential(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    current: &ProviderCredential,
+    selected_provider: &str,
+    model: &str,
+    client_type: Option<&ClientType>,
+) -> Option<ProviderCredential> {
+    let alternative = select_retry_credential(state, selected_provider, model, client_type, ctx)
+        .filter(|alternative| alternative.uuid != current.uuid)?;
+    state.logs.write().await.add(
+        "warn",
+        &format!(
+            "[FAILOVER] request_id={} reason=overloaded status=529 from={} to={}",
+            ctx.request_id,
+            &current.uuid[..8.min(current.uuid.len())],
+            &alternative.uuid[..8.min(alternative.uuid.len())]
+        ),
+    );
+    ctx.set_credential_id(alternative.uuid.clone());
+    ctx.increment_retry();
+    Some(alternative)
+}
+
+/// 上游过载且没有其他凭证可换时，按过载退避等待后再在当前凭证上重试
+async fn wait_overload_backoff(state: &AppState, ctx: &RequestContext) {
+    let delay = state
+        .processor
+        .retrier
+        .backoff_delay_for_status(0, OVERLOADED_STATUS_CODE);
+    state.logs.write().await.add(
+        "warn",
+        &format!(
+            "[OVERLOADED] request_id={} no alternative credential, retrying after delay_ms={}",
+            ctx.request_id,
+            delay.as_millis()
+        ),
+    );
+    tokio::time::sleep(delay).await;
+}
+
+/// 上游返回“模型不存在”时附带最接近的已知模型名
+///
+/// 已知模型包括模型注册表中的模型（注册表不可用时为内置模型列表）
+/// 和配置的模型别名（别名名称及其目标模型）。
+async fn suggest_for_unknown_model(state: &AppState, model: &str, response: Response) -> Response {
+    if response.status().is_success() {
+        return response;
+    }
+    let mut known = registry_model_ids(state).unwrap_or_else(known_model_ids);
+    {
+        let mapper = state.processor.mapper.read().await;
+        for (alias, actual) in mapper.aliases() {
+            known.push(alias.clone());
+            known.push(actual.clone());
+        }
+    }
+    suggest_on_model_not_found(response, model, &known).await
+}
+
+/// 模型注册表中的模型 ID
+///
+/// 读取失败时使用上次成功读取的结果，没有数据库或注册表为空时返回 `None`。
+fn registry_model_ids(state: &AppState) -> Option<Vec<String>> {
+    let db = state.db.as_ref()?;
+    let read = proxycast_core::database::lock_db(db)
+        .and_then(|conn| ModelRegistryDao::get_model_providers(&conn).map_err(|e| e.to_string()));
+    let snapshot = state.registry_models.resolve(read).ok()?;
+    let ids: Vec<String> = snapshot.models.into_iter().map(|(id, _)| id).collect();
+    (!ids.is_empty()).then_some(ids)
+}
+
+/// 构建请求校验失败的 400 响应（Anthropic 错误格式），并以 `log_prefix` 记录告警日志
+async fn invalid_request_response(state: &AppState, log_prefix: &str, message: &str) -> Response {
+    state
+        .logs
+        .write()
+        .await
+        .add("warn", &format!("[VALIDATE] {log_prefix}: {message}"));
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "type": "error",
+            "error": {
+                "type": "invalid_request_error",
+                "message": message
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 构建工具校验失败的 400 响应（Anthropic 错误格式）
+pub async fn invalid_tools_response(state: &AppState, message: &str) -> Response {
+    invalid_request_response(state, "工具定义校验失败", message).await
+}
+
+/// `metadata.user_id` 校验失败时的 400 响应
+pub async fn invalid_metadata_response(state: &AppState, message: &str) -> Response {
+    invalid_request_response(state, "metadata 校验失败", message).await
+}
+
+/// 构建 Provider 能力不支持的 400 响应
+pub async fn unsupported_capability(state: &AppState, error: &CapabilityError) -> Response {
+    state
+        .logs
+        .write()
+        .await
+        .add("warn", &format!("[ROUTE] 请求被拒绝: {error}"));
+    unsupported_capability_response(error)
+}
+
+/// 检查 `X-ProxyCast-Session` 会话配额
+///
+/// 未启用或未携带会话头时返回 `Ok(None)`；超限时返回带
+/// `x-ratelimit-*` 与 `retry-after` 响应头的 429 响应。
+pub async fn check_session_quota(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<String>, Response> {
+    if !state.session_quota.is_enabled() {
+        return Ok(None);
+    }
+    let Some(session) = session_id_from_headers(headers) else {
+        return Ok(None);
+    };
+
+    match state.session_quota.check(&session) {
+        Ok(_) => Ok(Some(session)),
+        Err(status) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[SESSION_QUOTA] session={} 超出配额, {}s 后重置",
+                    session, status.reset_after_secs
+                ),
+            );
+            Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                [
+                    (
+                        "x-ratelimit-limit-requests",
+                        status.limit_requests.to_string(),
+                    ),
+                    (
+                        "x-ratelimit-remaining-requests",
+                        status.remaining_requests.to_string(),
+                    ),
+                    ("x-ratelimit-limit-tokens", status.limit_tokens.to_string()),
+                    (
+                        "x-ratelimit-remaining-tokens",
+                        status.remaining_tokens.to_string(),
+                    ),
+                    ("retry-after", status.reset_after_secs.to_string()),
+                ],
+                Json(json!({
+                    "type": "error",
+                    "error": {
+                        "type": "rate_limit_error",
+                        "message": format!(
+                            "Session quota exceeded for '{session}', retry after {}s",
+                            status.reset_after_secs
+                        )
+                    }
+                })),
+            )
+                .into_response())
+        }
+    }
+}
+
 pub async fn anthropic_messages(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(mut request): Json<AnthropicMessagesRequest>,
+) -> Response {
+    let model = request.model.clone();
+    let output_clamp = clamp_output_tokens(&state, &headers, &model, &mut request.max_tokens).await;
+    // 流式请求按配置或请求头缓冲为非流式响应；响应形式与客户端期望不一致时互相转换
+    let client_stream = request.stream;
+    let buffer = client_stream && should_buffer_stream(&headers, state.force_buffer_stream);
+    let response = handle_anthropic_messages(State(state.clone()), headers, Json(request)).await;
+    let response = finalize_response(
+        &state,
+        response,
+        BufferFormat::Anthropic,
+        client_stream && !buffer,
+    )
+    .await;
+    let response = annotate_output_clamp(response, output_clamp);
+    suggest_for_unknown_model(&state, &model, response).await
+}
+
+async fn handle_anthropic_messages(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证（优先检查 x-api-key）
-    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
-        state
-            .logs
-            .write()
-            .await
-            .add("warn", "Unauthorized request to /v1/messages");
-        return e.into_response();
+    let api_key_label =
+        match verify_api_key_anthropic(&headers, &*state.api_keys.read().await).await {
+            Ok(label) => label,
+            Err(e) => {
+                state
+                    .logs
+                    .write()
+                    .await
+                    .add("warn", "Unauthorized request to /v1/messages");
+                return e.into_response();
+            }
+        };
+
+    // 本地校验工具定义，避免将畸形请求转发到上游
+    if state.validate_tools {
+        if let Err(message) = validate_anthropic_tools(&request) {
+            return invalid_tools_response(&state, &message).await;
+        }
+    }
+
+    // 按配置转发或剥离 metadata.user_id
+    let anthropic_config = state.anthropic.read().await.clone();
+    if let Err(message) = apply_user_metadata_policy(&mut request, &anthropic_config) {
+        return invalid_metadata_response(&state, &message).await;
+    }
+
+    let session = match check_session_quota(&state, &headers).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    // 识别客户端；请求未指定模型时使用该客户端配置的默认模型
+    let client = state.client_detector.detect(&headers);
+    if let Some(model) = client.model_fallback(&request.model) {
+        request.model = model.to_string();
     }
 
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    if let Some(request_id) = request_id_from_headers(&headers) {
+        ctx = ctx.with_request_id(request_id);
+    }
+    ctx.set_client_id(client.client_id.clone());
+    ctx.set_route(RequestRoute::Default);
+    ctx.set_api_key_label(api_key_label);
+    if let Some(session_id) = session_id_from_headers(&headers) {
+        ctx.set_session_id(session_id);
+    }
+    if let Some(session) = session {
+        ctx.set_metadata(SESSION_METADATA_KEY, json!(session));
+    }
 
     // 详细记录请求信息
     let msg_count = request.messages.len();
@@ -981,7 +1662,9 @@ pub async fn anthropic_messages(
     if injection_enabled {
         let injector = state.processor.injector.read().await;
         let mut payload = serde_json::to_value(&request).unwrap_or_default();
-        let result = injector.inject(&request.model, &mut payload);
+        let result = injector.inject_filtered(&request.model, &mut payload, |id| {
+            client.allows_injection_rule(id)
+        });
         if result.has_injections() {
             state.logs.write().await.add(
                 "info",
@@ -999,14 +1682,19 @@ pub async fn anthropic_messages(
 
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
-    let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    let client_type = client.client_type;
+    let selected_provider = select_provider_for_client(client_type, &state).await;
+    let allow_fallback = fallback_allowed(&state.fallback_settings, &selected_provider, &headers);
+    if let Some(response) = reject_disabled_endpoint(&state, &ctx, client_type).await {
+        return response;
+    }
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
         "info",
         &format!(
-            "[CLIENT] request_id={} client_type={} selected_provider={}",
-            ctx.request_id, client_type, selected_provider
+            "[CLIENT] request_id={} client={} client_type={} selected_provider={}",
+            ctx.request_id, client.client_id, client_type, selected_provider
         ),
     );
 
@@ -1028,23 +1716,43 @@ pub async fn anthropic_messages(
     // 尝试选择凭证：
     // 1) X-Provider-Id 指定时仅走精确匹配（不降级）
     // 2) 否则走统一的“池优先 + API Key Provider 智能降级”路径
-    let credential = match select_credential_for_request(
+    let selection_started = Instant::now();
+    let mut selection_trace =
+        SelectionTrace::new(selected_provider.as_str(), state.verbose_selection_trace);
+    let credential = select_credential_for_request(
         &state,
         &selected_provider,
         &request.model,
         &client_type,
         provider_id_header.as_deref(),
+        allow_fallback,
         "ANTHROPIC_MESSAGES",
         false,
+        &mut selection_trace,
     )
-    .await
-    {
+    .await;
+    ctx.record_stage(RequestStage::Selection, selection_started.elapsed());
+    ctx.set_selection_trace(selection_trace);
+    let credential = match credential {
         Ok(cred) => cred,
         Err(resp) => return resp,
     };
 
     // 如果找到凭证池中的凭证，使用它
-    if let Some(cred) = credential {
+    if let Some(mut cred) = credential {
+        apply_upstream_override(&headers, state.allow_upstream_override, &mut cred);
+        if let Some(resp) = reject_self_upstream(
+            &cred,
+            &state.bind_host,
+            state.bind_port,
+            state.allow_self_upstream,
+        ) {
+            return resp;
+        }
+        if let Err(e) = check_anthropic_capabilities(cred.provider_type, &request) {
+            return unsupported_capability(&state, &e).await;
+        }
+        ctx.set_credential_id(cred.uuid.clone());
         state.logs.write().await.add(
             "info",
             &format!(
@@ -1075,15 +1783,49 @@ pub async fn anthropic_messages(
         // 检查是否需要拦截请求
         // **Validates: Requirements 2.1, 2.3, 2.5**
 
-        let provider_label = cred.provider_type.to_string();
-        let response = call_with_single_provider_resilience(
-            &state,
-            &ctx.request_id,
-            &provider_label,
-            request.stream,
-            || async { call_provider_anthropic(&state, &cred, &request, None).await },
-        )
-        .await;
+        // 上游过载时换用同类型的其他凭证重试，优先选择尚未尝试过的凭证；
+        // 没有其他凭证时按过载退避后在当前凭证上重试
+        let mut current = cred.clone();
+        let mut failovers = 0;
+        let response = loop {
+            let switch_on_overload = allow_fallback && failovers < MAX_OVERLOAD_FAILOVERS;
+            let upstream_started = Instant::now();
+            let upstream = call_with_single_provider_resilience(
+                &state,
+                &ctx.request_id,
+                &current,
+                request.stream,
+                switch_on_overload,
+                || async {
+                    call_provider_anthropic(&state, &current, &request, None, &ctx.cancel_token)
+                        .await
+                },
+            );
+            let response = watch_disconnect(&state, &ctx, upstream).await;
+            ctx.record_stage(RequestStage::Upstream, upstream_started.elapsed());
+            if !switch_on_overload || response.status().as_u16() != OVERLOADED_STATUS_CODE {
+                break response;
+            }
+            match switch_overloaded_credential(
+                &state,
+                &mut ctx,
+                &current,
+                &selected_provider,
+                &request.model,
+                Some(&client_type),
+            )
+            .await
+            {
+                Some(alternative) => {
+                    current = alternative;
+                    failovers += 1;
+                }
+                None => {
+                    wait_overload_backoff(&state, &ctx).await;
+                    failovers = MAX_OVERLOAD_FAILOVERS;
+                }
+            }
+        };
 
         // 记录请求统计
         let is_success = response.status().is_success();
@@ -1092,26 +1834,23 @@ pub async fn anthropic_messages(
         } else {
             proxycast_infra::telemetry::RequestStatus::Failed
         };
-        record_request_telemetry(&state, &ctx, status, None);
+        let error_message = (response.status().as_u16() == OVERLOADED_STATUS_CODE)
+            .then(|| "upstream overloaded (529)".to_string());
+        // 成功的流式响应在响应体结束时记录，附带首 token 耗时
+        let response = if is_success && request.stream {
+            record_stream_telemetry(&state, &ctx, response)
+        } else {
+            record_request_telemetry(&state, &ctx, status, error_message);
+            response
+        };
 
         // 估算 Token 使用量
-        let estimated_input_tokens = request
-            .messages
-            .iter()
-            .map(|m| {
-                let content_len = match &m.content {
-                    serde_json::Value::String(s) => s.len(),
-                    serde_json::Value::Array(arr) => arr
-                        .iter()
-                        .filter_map(|v| v.get("text").and_then(|t| t.as_str()))
-                        .map(|s| s.len())
-                        .sum(),
-                    _ => 0,
-                };
-                content_len / 4
-            })
-            .sum::<usize>() as u32;
-        let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
+        let estimated_input_tokens = estimate_anthropic_input_tokens(&request);
+        let estimated_output_tokens = if is_success {
+            ESTIMATED_ANTHROPIC_OUTPUT_TOKENS
+        } else {
+            0
+        };
 
         if is_success {
             record_token_usage(
@@ -1131,9 +1870,9 @@ pub async fn anthropic_messages(
     // 回退到旧的单凭证模式（仅当允许自动降级且选择的 Provider 是 Kiro 时）
     // 其余情况（含禁用自动降级）直接返回无可用凭证错误
     // **Validates: Requirements 3.2**
-    if !state.allow_provider_fallback || selected_provider.to_lowercase() != "kiro" {
-        let reason = if !state.allow_provider_fallback {
-            "auto fallback disabled by retry.auto_switch_provider=false"
+    if !allow_fallback || selected_provider.to_lowercase() != "kiro" {
+        let reason = if !allow_fallback {
+            "auto fallback disabled by retry config or X-ProxyCast-No-Fallback"
         } else {
             "legacy mode only supports Kiro"
         };
@@ -1143,28 +1882,7 @@ pub async fn anthropic_messages(
                 "[ROUTE] No pool credential found for '{selected_provider}' (client_type={client_type}), {reason}"
             ),
         );
-        let message = if !state.allow_provider_fallback {
-            format!(
-                "没有找到可用的 '{}' 凭证（已禁用自动降级）。请在凭证池中添加对应的凭证。",
-                selected_provider
-            )
-        } else {
-            format!(
-                "没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。",
-                selected_provider
-            )
-        };
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(serde_json::json!({
-                "type": "error",
-                "error": {
-                    "type": "no_credential_error",
-                    "message": message
-                }
-            })),
-        )
-            .into_response();
+        return no_credential_response(BufferFormat::Anthropic, &selected_provider, allow_fallback);
     }
 
     state.logs.write().await.add(
@@ -1182,9 +1900,29 @@ pub async fn anthropic_messages(
     // 检查是否需要拦截请求（legacy mode）
     // **Validates: Requirements 2.1, 2.3, 2.5**
 
+    // Kiro 需要完整缓冲上游响应后才能转换：流式请求较慢时先发送响应头，缓冲期间由心跳层保活
+    let stream = request.stream && !should_buffer_stream(&headers, state.force_buffer_stream);
+    let grace = state.stream_heartbeat;
+    let call = legacy_kiro_messages(state, ctx, request);
+    if stream {
+        return deferred_stream_response(call, BufferFormat::Anthropic, grace).await;
+    }
+    call.await
+}
+
+/// 旧的单凭证模式：直接使用 Kiro 默认凭证处理 Anthropic Messages 请求
+async fn legacy_kiro_messages(
+    state: AppState,
+    mut ctx: RequestContext,
+    request: AnthropicMessagesRequest,
+) -> Response {
     // 检查是否需要刷新 token（无 token 或即将过期）
+    let refresh_started = Instant::now();
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let _guard = state
+            .kiro_refresh_locks
+            .lock(DEFAULT_KIRO_REFRESH_KEY)
+            .await;
         let mut kiro = state.kiro.write().await;
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
@@ -1213,6 +1951,7 @@ pub async fn anthropic_messages(
                 .add("info", "[AUTH] Token refreshed successfully");
         }
     }
+    ctx.record_stage(RequestStage::Refresh, refresh_started.elapsed());
 
     // 转换为 OpenAI 格式
     let openai_request = convert_anthropic_to_openai(&request);
@@ -1230,7 +1969,10 @@ pub async fn anthropic_messages(
 
     let kiro = state.kiro.read().await;
 
-    match kiro.call_api(&openai_request).await {
+    let upstream_started = Instant::now();
+    let upstream = watch_disconnect(&state, &ctx, kiro.call_api(&openai_request)).await;
+    ctx.record_stage(RequestStage::Upstream, upstream_started.elapsed());
+    match upstream {
         Ok(resp) => {
             let status = resp.status();
             state
@@ -1240,10 +1982,10 @@ pub async fn anthropic_messages(
                 .add("info", &format!("[RESP] Upstream status: {status}"));
 
             if status.is_success() {
-                match resp.bytes().await {
+                match watch_disconnect(&state, &ctx, resp.bytes()).await {
                     Ok(bytes) => {
-                        // 使用 lossy 转换，避免无效 UTF-8 导致崩溃
-                        let body = String::from_utf8_lossy(&bytes).to_string();
+                        // 解析直接基于原始字节，文本形式只用于日志展示
+                        let body = display_text(&bytes);
 
                         // 记录原始响应长度
                         state.logs.write().await.add(
@@ -1268,7 +2010,11 @@ pub async fn anthropic_messages(
                             .await
                             .add("debug", &format!("[RESP] Body preview: {preview}"));
 
-                        let parsed = parse_cw_response(&body);
+                        let parsed =
+                            match parse_cw_response_bytes(&bytes).or_else(CWParseError::recover) {
+                                Ok(parsed) => parsed,
+                                Err(e) => return cw_parse_error_response(&e),
+                            };
 
                         // 详细记录解析结果
                         state.logs.write().await.add(
@@ -1322,7 +2068,10 @@ pub async fn anthropic_messages(
             } else if status.as_u16() == 403 || status.as_u16() == 402 {
                 // Token 过期或账户问题，尝试重新加载凭证并刷新
                 drop(kiro);
-                let _guard = state.kiro_refresh_lock.lock().await;
+                let _guard = state
+                    .kiro_refresh_locks
+                    .lock(DEFAULT_KIRO_REFRESH_KEY)
+                    .await;
                 let mut kiro = state.kiro.write().await;
                 state.logs.write().await.add(
                     "warn",
@@ -1348,7 +2097,7 @@ pub async fn anthropic_messages(
                         );
                         drop(kiro);
                         let kiro = state.kiro.read().await;
-                        match kiro.call_api(&openai_request).await {
+                        match watch_disconnect(&state, &ctx, kiro.call_api(&openai_request)).await {
                             Ok(retry_resp) => {
                                 let retry_status = retry_resp.status();
                                 state.logs.write().await.add(
@@ -1356,10 +2105,14 @@ pub async fn anthropic_messages(
                                     &format!("[RETRY] Response status: {retry_status}"),
                                 );
                                 if retry_resp.status().is_success() {
-                                    match retry_resp.bytes().await {
+                                    match watch_disconnect(&state, &ctx, retry_resp.bytes()).await {
                                         Ok(bytes) => {
-                                            let body = String::from_utf8_lossy(&bytes).to_string();
-                                            let parsed = parse_cw_response(&body);
+                                            let parsed = match parse_cw_response_bytes(&bytes)
+                                                .or_else(CWParseError::recover)
+                                            {
+                                                Ok(parsed) => parsed,
+                                                Err(e) => return cw_parse_error_response(&e),
+                                            };
                                             state.logs.write().await.add(
                                                 "info",
                                                 &format!(
@@ -1397,7 +2150,7 @@ pub async fn anthropic_messages(
                                 let body = retry_resp
                                     .bytes()
                                     .await
-                                    .map(|b| String::from_utf8_lossy(&b).to_string())
+                                    .map(|b| display_text(&b).into_owned())
                                     .unwrap_or_default();
                                 state.logs.write().await.add(
                                     "error",
@@ -1443,7 +2196,9 @@ pub async fn anthropic_messages(
                     }
                 }
             } else {
-                let body = resp.text().await.unwrap_or_default();
+                let body = watch_disconnect(&state, &ctx, resp.text())
+                    .await
+                    .unwrap_or_default();
                 state.logs.write().await.add(
                     "error",
                     &format!(
@@ -1713,12 +2468,10 @@ fn convert_anthropic_response_to_openai(anthropic_resp: &serde_json::Value, mode
                 "role": "assistant",
                 "content": content
             },
-            "finish_reason": match anthropic_resp["stop_reason"].as_str() {
-                Some("end_turn") => "stop",
-                Some("max_tokens") => "length",
-                Some("tool_use") => "tool_calls",
-                _ => "stop"
-            }
+            "finish_reason": StopReason::from_anthropic(
+                anthropic_resp["stop_reason"].as_str().unwrap_or_default()
+            )
+            .to_openai_str()
         }],
         "usage": usage
     });
@@ -3,16 +3,20 @@
 //! 提供批量任务的创建、查询和管理接口
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
 use proxycast_scheduler::{
-    BatchOptions, BatchTask, BatchTaskDao, BatchTaskStatistics, TaskDefinition, TaskTemplate,
-    TemplateDao,
+    BatchEvent, BatchExportFormat, BatchOptions, BatchTask, BatchTaskDao, BatchTaskStatistics,
+    BatchTaskStatus, TaskDefinition, TaskTemplate, TemplateDao,
 };
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
 use crate::AppState;
@@ -341,6 +345,182 @@ pub async fn cancel_batch_task(State(state): State<AppState>, Path(id): Path<Uui
     (StatusCode::OK, Json(serde_json::json!({"cancelled": true}))).into_response()
 }
 
+/// GET /api/batch/tasks/:id/events - 以 SSE 推送批量任务进度事件
+///
+/// 推送该批量任务的 `batch:*` 事件，收到 `batch:finished` 后结束。
+/// 订阅时任务已经结束的，直接推送一个 `batch:finished` 事件。
+pub async fn stream_batch_task_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Response {
+    // 先订阅再查询状态，避免错过查询与订阅之间发生的事件
+    let mut rx = match state.batch_executor.read().await.as_ref() {
+        Some(executor) => executor.subscribe(),
+        None => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": "批量任务执行器未初始化",
+                        "type": "executor_unavailable"
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let current_status = match state.db.as_ref().map(|db| BatchTaskDao::get_by_id(db, &id)) {
+        Some(Ok(Some(task))) => task.status,
+        Some(Ok(None)) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": format!("批量任务不存在: {}", id),
+                        "type": "not_found"
+                    }
+                })),
+            )
+                .into_response();
+        }
+        Some(Err(e)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": format!("查询批量任务失败: {}", e),
+                        "type": "database_error"
+                    }
+                })),
+            )
+                .into_response();
+        }
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": "数据库未初始化",
+                        "type": "database_error"
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let stream = async_stream::stream! {
+        if !matches!(current_status, BatchTaskStatus::Pending | BatchTaskStatus::Running) {
+            let event = BatchEvent::Finished { batch_id: id, status: current_status };
+            yield batch_sse_event(&event);
+            return;
+        }
+        loop {
+            match rx.recv().await {
+                Ok(event) if event.batch_id() == id => {
+                    let finished = matches!(event, BatchEvent::Finished { .. });
+                    yield batch_sse_event(&event);
+                    if finished {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("[BATCH] 事件订阅落后，丢弃 {} 个事件: id={}", skipped, id);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    };
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// 将批量任务事件转换为 SSE 事件
+fn batch_sse_event(event: &BatchEvent) -> Result<Event, std::convert::Infallible> {
+    let data = serde_json::to_string(event).unwrap_or_default();
+    Ok(Event::default().event(event.name()).data(data))
+}
+
+/// 导出查询参数
+#[derive(Debug, Deserialize)]
+pub struct ExportBatchTaskQuery {
+    /// 导出格式（csv / jsonl），默认 csv
+    #[serde(default = "default_export_format")]
+    pub format: BatchExportFormat,
+}
+
+fn default_export_format() -> BatchExportFormat {
+    BatchExportFormat::Csv
+}
+
+/// GET /api/batch/tasks/:id/export - 导出批量任务结果
+pub async fn export_batch_task(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ExportBatchTaskQuery>,
+) -> Response {
+    let db = match &state.db {
+        Some(db) => db,
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": "数据库未初始化",
+                        "type": "database_error"
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    match BatchTaskDao::export_batch_results(db, &id, query.format) {
+        Ok(Some(body)) => {
+            let (content_type, extension) = match query.format {
+                BatchExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+                BatchExportFormat::Jsonl => ("application/x-ndjson", "jsonl"),
+            };
+            (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, content_type.to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"batch-{id}.{extension}\""),
+                    ),
+                ],
+                body,
+            )
+                .into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": {
+                    "message": format!("批量任务不存在: {}", id),
+                    "type": "not_found"
+                }
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": {
+                    "message": format!("导出批量任务失败: {}", e),
+                    "type": "database_error"
+                }
+            })),
+        )
+            .into_response(),
+    }
+}
+
 /// POST /api/batch/templates - 创建任务模板
 pub async fn create_template(
     State(state): State<AppState>,
@@ -1,26 +1,56 @@
 //! 批量任务执行器
 //!
-//! 负责异步执行批量任务，支持并发控制、重试、超时和取消
+//! 负责异步执行批量任务，支持并发控制、重试、超时和取消。
+//! 取消批次时除了通知取消令牌，还会中止正在执行的子任务，
+//! 确保上游调用真正停止而不是在后台继续消耗 Token。
+//! 子任务出现终止性错误（如认证失败）时按 `BatchOptions.on_error` 策略
+//! 继续、跳过剩余任务或终止整个批次。
+//!
+//! 子任务的元数据中带有完整请求（[`TASK_REQUEST_METADATA_KEY`]）时直接执行该请求，
+//! 否则按模板渲染请求。
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 
 use axum::http::StatusCode;
+use proxycast_core::backends::{BackendError, BackendErrorKind};
 use proxycast_core::models::openai::{
     ChatCompletionRequest, ChatCompletionResponse, ChatMessage, MessageContent,
 };
-use proxycast_scheduler::{BatchTaskDao, BatchTaskStatus, TaskResult, TemplateDao, TokenUsage};
-use tokio::sync::RwLock;
+use proxycast_scheduler::{
+    BatchEvent, BatchEventEmitter, BatchProgress, BatchTaskDao, BatchTaskStatus, OnErrorPolicy,
+    TaskDefinition, TaskResult, TaskTemplate, TemplateDao, TokenUsage,
+};
+use proxycast_server_utils::display_text;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::AbortHandle;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::AppState;
 
+/// 子任务元数据中保存完整请求（OpenAI 格式 JSON）的键
+pub const TASK_REQUEST_METADATA_KEY: &str = "request";
+
+/// 批次内子任务的中止句柄
+type TaskAborts = Arc<std::sync::Mutex<Vec<AbortHandle>>>;
+
+/// 中止批次内所有子任务（已结束的子任务不受影响）
+fn abort_tasks(aborts: &TaskAborts) {
+    for handle in aborts.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        handle.abort();
+    }
+}
+
 /// 批量任务执行器
 #[derive(Clone)]
 pub struct BatchTaskExecutor {
     state: AppState,
     cancel_tokens: Arc<RwLock<HashMap<Uuid, CancellationToken>>>,
+    /// 运行中批次的子任务中止句柄
+    abort_handles: Arc<RwLock<HashMap<Uuid, TaskAborts>>>,
+    events: BatchEventEmitter,
 }
 
 impl BatchTaskExecutor {
@@ -28,9 +58,16 @@ impl BatchTaskExecutor {
         Self {
             state,
             cancel_tokens: Arc::new(RwLock::new(HashMap::new())),
+            abort_handles: Arc::new(RwLock::new(HashMap::new())),
+            events: BatchEventEmitter::new(),
         }
     }
 
+    /// 订阅批量任务进度事件
+    pub fn subscribe(&self) -> broadcast::Receiver<BatchEvent> {
+        self.events.subscribe()
+    }
+
     /// 启动批量任务执行（spawn 后台任务）
     pub async fn start_batch(&self, batch_id: Uuid) {
         let cancel_token = CancellationToken::new();
@@ -38,33 +75,55 @@ impl BatchTaskExecutor {
             .write()
             .await
             .insert(batch_id, cancel_token.clone());
+        let aborts = TaskAborts::default();
+        self.abort_handles
+            .write()
+            .await
+            .insert(batch_id, aborts.clone());
 
         let state = self.state.clone();
         let cancel_tokens = self.cancel_tokens.clone();
+        let abort_handles = self.abort_handles.clone();
+        let events = self.events.clone();
 
         tokio::spawn(async move {
-            Self::execute_batch(state, batch_id, cancel_token).await;
-            // 执行完毕后清理 cancel token
+            Self::execute_batch(state, batch_id, cancel_token, aborts, events).await;
+            // 执行完毕后清理 cancel token 和中止句柄
             cancel_tokens.write().await.remove(&batch_id);
+            abort_handles.write().await.remove(&batch_id);
         });
     }
 
     /// 取消运行中的批量任务
+    ///
+    /// 通知取消令牌后中止所有正在执行的子任务，被中止的子任务标记为 `Cancelled`。
     pub async fn cancel_batch(&self, batch_id: &Uuid) -> bool {
-        if let Some(token) = self.cancel_tokens.read().await.get(batch_id) {
-            token.cancel();
-            true
-        } else {
-            false
+        let Some(token) = self.cancel_tokens.read().await.get(batch_id).cloned() else {
+            return false;
+        };
+        token.cancel();
+        if let Some(aborts) = self.abort_handles.read().await.get(batch_id) {
+            abort_tasks(aborts);
         }
+        true
     }
 
     /// 核心执行逻辑
-    async fn execute_batch(state: AppState, batch_id: Uuid, cancel_token: CancellationToken) {
+    async fn execute_batch(
+        state: AppState,
+        batch_id: Uuid,
+        cancel_token: CancellationToken,
+        aborts: TaskAborts,
+        events: BatchEventEmitter,
+    ) {
         let db = match &state.db {
             Some(db) => db,
             None => {
                 tracing::error!("[BATCH] 数据库未初始化, batch_id={}", batch_id);
+                events.emit(BatchEvent::Finished {
+                    batch_id,
+                    status: BatchTaskStatus::Failed,
+                });
                 return;
             }
         };
@@ -74,27 +133,46 @@ impl BatchTaskExecutor {
             Ok(Some(task)) => task,
             Ok(None) => {
                 tracing::error!("[BATCH] 批量任务不存在: {}", batch_id);
+                events.emit(BatchEvent::Finished {
+                    batch_id,
+                    status: BatchTaskStatus::Failed,
+                });
                 return;
             }
             Err(e) => {
                 tracing::error!("[BATCH] 加载批量任务失败: {}", e);
+                events.emit(BatchEvent::Finished {
+                    batch_id,
+                    status: BatchTaskStatus::Failed,
+                });
                 return;
             }
         };
+        let progress = Arc::new(events.progress(batch_id, batch_task.tasks.len()));
 
-        // 2. 加载模板
-        let template = match TemplateDao::get_by_id(db, &batch_task.template_id) {
-            Ok(Some(t)) => t,
-            Ok(None) => {
-                tracing::error!("[BATCH] 模板不存在: {}", batch_task.template_id);
-                let _ = BatchTaskDao::update_status(db, &batch_id, BatchTaskStatus::Failed);
-                return;
-            }
-            Err(e) => {
-                tracing::error!("[BATCH] 加载模板失败: {}", e);
-                let _ = BatchTaskDao::update_status(db, &batch_id, BatchTaskStatus::Failed);
-                return;
+        // 2. 加载模板（所有子任务都带有完整请求时不需要模板）
+        let needs_template = batch_task
+            .tasks
+            .iter()
+            .any(|t| !t.metadata.contains_key(TASK_REQUEST_METADATA_KEY));
+        let template = if needs_template {
+            match TemplateDao::get_by_id(db, &batch_task.template_id) {
+                Ok(Some(t)) => Some(t),
+                Ok(None) => {
+                    tracing::error!("[BATCH] 模板不存在: {}", batch_task.template_id);
+                    let _ = BatchTaskDao::update_status(db, &batch_id, BatchTaskStatus::Failed);
+                    progress.finished(BatchTaskStatus::Failed);
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!("[BATCH] 加载模板失败: {}", e);
+                    let _ = BatchTaskDao::update_status(db, &batch_id, BatchTaskStatus::Failed);
+                    progress.finished(BatchTaskStatus::Failed);
+                    return;
+                }
             }
+        } else {
+            None
         };
 
         // 3. 更新状态为 Running
@@ -117,85 +195,70 @@ impl BatchTaskExecutor {
             batch_task.tasks.len()
         );
 
-        // 4. 用 Semaphore 控制并发
-        let concurrency = batch_task.options.concurrency.max(1);
-        let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency));
-        let results = Arc::new(RwLock::new(Vec::<TaskResult>::new()));
-        let mut handles = Vec::new();
-
-        for task_def in &batch_task.tasks {
-            let task_id = task_def.id.unwrap_or_else(Uuid::new_v4);
-            let variables = task_def.variables.clone();
-            let sem = semaphore.clone();
-            let state = state.clone();
-            let cancel = cancel_token.clone();
-            let results = results.clone();
-            let model = template.model.clone();
-            let system_prompt = template.system_prompt.clone();
-            let user_message = template.render_user_message(&variables);
-            let temperature = template.temperature;
-            let max_tokens = template.max_tokens;
-            let retry_count = batch_task.options.retry_count;
-            let timeout_secs = batch_task.options.timeout_seconds;
-            let db_clone = db.clone();
-            let batch_id_clone = batch_id;
-
-            let handle = tokio::spawn(async move {
-                let _permit = sem.acquire().await.unwrap();
-
-                // 检查取消
-                if cancel.is_cancelled() {
-                    let result = TaskResult {
+        // 4. 按并发上限执行子任务，并按 on_error 策略处理终止性错误
+        let policy = batch_task.options.error_policy();
+        let retry_count = batch_task.options.retry_count;
+        let timeout_secs = batch_task.options.timeout_seconds;
+        let task_ids: Vec<Uuid> = batch_task
+            .tasks
+            .iter()
+            .map(|t| t.id.unwrap_or_else(Uuid::new_v4))
+            .collect();
+        let requests: Result<Vec<ChatCompletionRequest>, String> = batch_task
+            .tasks
+            .iter()
+            .map(|t| build_task_request(t, template.as_ref()))
+            .collect();
+        let requests = match requests {
+            Ok(requests) => Arc::new(requests),
+            Err(e) => {
+                tracing::error!("[BATCH] 构建子任务请求失败: {}", e);
+                let _ = BatchTaskDao::update_status(db, &batch_id, BatchTaskStatus::Failed);
+                progress.finished(BatchTaskStatus::Failed);
+                return;
+            }
+        };
+
+        let run_state = state.clone();
+        let db_clone = db.clone();
+
+        let final_results = run_tasks(
+            task_ids,
+            batch_task.options.concurrency,
+            policy,
+            &cancel_token,
+            &aborts,
+            &progress,
+            move |index, task_id, cancel| {
+                let state = run_state.clone();
+                let requests = requests.clone();
+                async move {
+                    Self::execute_single_task(
+                        &state,
                         task_id,
-                        status: proxycast_scheduler::BatchTaskStatus2::Cancelled,
-                        content: None,
-                        error: Some("任务已取消".to_string()),
-                        usage: TokenUsage::default(),
-                        started_at: chrono::Utc::now(),
-                        completed_at: Some(chrono::Utc::now()),
-                    };
-                    results.write().await.push(result);
-                    return;
+                        &requests[index],
+                        retry_count,
+                        timeout_secs,
+                        &cancel,
+                    )
+                    .await
                 }
-
-                let result = Self::execute_single_task(
-                    &state,
-                    task_id,
-                    &model,
-                    system_prompt.as_deref(),
-                    &user_message,
-                    temperature,
-                    max_tokens,
-                    retry_count,
-                    timeout_secs,
-                    &cancel,
-                )
-                .await;
-
-                results.write().await.push(result);
-
+            },
+            move |_, current_results| {
                 // 实时更新 DB 进度
-                let current_results = results.read().await.clone();
                 let _ = BatchTaskDao::update_results(
                     &db_clone,
-                    &batch_id_clone,
+                    &batch_id,
                     BatchTaskStatus::Running,
-                    &current_results,
+                    current_results,
                     None,
                     None,
                 );
-            });
-
-            handles.push(handle);
-        }
-
-        // 等待所有任务完成
-        for handle in handles {
-            let _ = handle.await;
-        }
+            },
+        )
+        .await;
 
         // 5. 计算最终状态
-        let final_results = results.read().await.clone();
         let total = batch_task.tasks.len();
         let completed = final_results
             .iter()
@@ -205,16 +268,7 @@ impl BatchTaskExecutor {
             .iter()
             .filter(|r| r.status == proxycast_scheduler::BatchTaskStatus2::Cancelled)
             .count();
-
-        let final_status = if cancel_token.is_cancelled() {
-            BatchTaskStatus::Cancelled
-        } else if completed == total {
-            BatchTaskStatus::Completed
-        } else if completed == 0 {
-            BatchTaskStatus::Failed
-        } else {
-            BatchTaskStatus::PartiallyCompleted
-        };
+        let final_status = final_status(&final_results, total, cancel_token.is_cancelled());
 
         let completed_at = chrono::Utc::now();
         let _ = BatchTaskDao::update_results(
@@ -225,6 +279,7 @@ impl BatchTaskExecutor {
             batch_task.started_at,
             Some(completed_at),
         );
+        progress.finished(final_status);
 
         tracing::info!(
             "[BATCH] 批量任务完成: id={}, status={:?}, completed={}/{}, cancelled={}",
@@ -237,32 +292,23 @@ impl BatchTaskExecutor {
     }
 
     /// 执行单个子任务（含重试和超时）
+    ///
+    /// 只重试可重试的错误；终止性错误立即返回，并标记 `terminal`。
     async fn execute_single_task(
         state: &AppState,
         task_id: Uuid,
-        model: &str,
-        system_prompt: Option<&str>,
-        user_message: &str,
-        temperature: Option<f32>,
-        max_tokens: Option<u32>,
+        request: &ChatCompletionRequest,
         retry_count: usize,
         timeout_secs: u64,
         cancel: &CancellationToken,
-    ) -> TaskResult {
+    ) -> TaskOutcome {
         let started_at = chrono::Utc::now();
         let max_attempts = retry_count + 1;
+        let mut last_error = BackendError::new(BackendErrorKind::Other, "未知错误");
 
         for attempt in 0..max_attempts {
             if cancel.is_cancelled() {
-                return TaskResult {
-                    task_id,
-                    status: proxycast_scheduler::BatchTaskStatus2::Cancelled,
-                    content: None,
-                    error: Some("任务已取消".to_string()),
-                    usage: TokenUsage::default(),
-                    started_at,
-                    completed_at: Some(chrono::Utc::now()),
-                };
+                return TaskOutcome::cancelled(task_id, started_at, "任务已取消");
             }
 
             if attempt > 0 {
@@ -274,106 +320,70 @@ impl BatchTaskExecutor {
                 );
             }
 
-            // 构建请求
-            let mut messages = Vec::new();
-            if let Some(sys) = system_prompt {
-                messages.push(ChatMessage {
-                    role: "system".to_string(),
-                    content: Some(MessageContent::Text(sys.to_string())),
-                    tool_calls: None,
-                    tool_call_id: None,
-                    reasoning_content: None,
-                });
-            }
-            messages.push(ChatMessage {
-                role: "user".to_string(),
-                content: Some(MessageContent::Text(user_message.to_string())),
-                tool_calls: None,
-                tool_call_id: None,
-                reasoning_content: None,
-            });
-
-            let request = ChatCompletionRequest {
-                model: model.to_string(),
-                messages,
-                temperature,
-                max_tokens,
-                top_p: None,
-                stream: false,
-                tools: None,
-                tool_choice: None,
-                reasoning_effort: None,
+            // 调用 LLM（带超时，批次终止时立即放弃）
+            let result = tokio::select! {
+                _ = cancel.cancelled() => {
+                    return TaskOutcome::cancelled(task_id, started_at, "任务已取消");
+                }
+                result = tokio::time::timeout(
+                    std::time::Duration::from_secs(timeout_secs),
+                    Self::call_llm(state, request, cancel),
+                ) => result,
             };
 
-            // 调用 LLM（带超时）
-            let result = tokio::time::timeout(
-                std::time::Duration::from_secs(timeout_secs),
-                Self::call_llm(state, &request),
-            )
-            .await;
-
-            match result {
-                Ok(Ok((content, usage))) => {
-                    return TaskResult {
-                        task_id,
-                        status: proxycast_scheduler::BatchTaskStatus2::Completed,
-                        content: Some(content),
-                        error: None,
-                        usage,
-                        started_at,
-                        completed_at: Some(chrono::Utc::now()),
-                    };
-                }
-                Ok(Err(e)) => {
-                    if attempt == max_attempts - 1 {
-                        return TaskResult {
+            let error = match result {
+                Ok(Ok((content, usage, response))) => {
+                    return TaskOutcome {
+                        result: TaskResult {
                             task_id,
-                            status: proxycast_scheduler::BatchTaskStatus2::Failed,
-                            content: None,
-                            error: Some(e),
-                            usage: TokenUsage::default(),
+                            status: proxycast_scheduler::BatchTaskStatus2::Completed,
+                            content: Some(content),
+                            error: None,
+                            usage,
+                            response: Some(response),
                             started_at,
                             completed_at: Some(chrono::Utc::now()),
-                        };
-                    }
-                    // 重试前等待
-                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                }
-                Err(_) => {
-                    if attempt == max_attempts - 1 {
-                        return TaskResult {
-                            task_id,
-                            status: proxycast_scheduler::BatchTaskStatus2::Failed,
-                            content: None,
-                            error: Some(format!("任务超时 ({}s)", timeout_secs)),
-                            usage: TokenUsage::default(),
-                            started_at,
-                            completed_at: Some(chrono::Utc::now()),
-                        };
-                    }
-                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                        },
+                        terminal: false,
+                    };
                 }
+                Ok(Err(e)) => e,
+                Err(_) => BackendError::new(
+                    BackendErrorKind::Timeout,
+                    format!("任务超时 ({}s)", timeout_secs),
+                ),
+            };
+
+            if !error.is_retryable() {
+                tracing::warn!(
+                    "[BATCH] 任务出现终止性错误，不再重试: task_id={}, error={}",
+                    task_id,
+                    error
+                );
+                return TaskOutcome::failed(task_id, started_at, &error, true);
+            }
+            last_error = error;
+            if attempt + 1 < max_attempts {
+                // 重试前等待
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
             }
         }
 
-        // 不应到达这里
-        TaskResult {
-            task_id,
-            status: proxycast_scheduler::BatchTaskStatus2::Failed,
-            content: None,
-            error: Some("未知错误".to_string()),
-            usage: TokenUsage::default(),
-            started_at,
-            completed_at: Some(chrono::Utc::now()),
-        }
+        TaskOutcome::failed(task_id, started_at, &last_error, false)
     }
 
     /// 调用 LLM：选择凭证 + 调用 provider
+    ///
+    /// 返回首个回复的文本、Token 用量和完整响应（OpenAI 格式）。
     async fn call_llm(
         state: &AppState,
         request: &ChatCompletionRequest,
-    ) -> Result<(String, TokenUsage), String> {
-        let db = state.db.as_ref().ok_or("数据库未初始化")?;
+        cancel: &CancellationToken,
+    ) -> Result<(String, TokenUsage, serde_json::Value), BackendError> {
+        let db = state
+            .db
+            .as_ref()
+            .ok_or_else(|| BackendError::new(BackendErrorKind::Other, "数据库未初始化"))?;
 
         // 选择凭证
         let credential = state
@@ -386,26 +396,45 @@ impl BatchTaskExecutor {
                 None,
                 None,
             )
-            .await?
-            .ok_or_else(|| format!("没有可用的凭证来调用模型: {}", request.model))?;
+            .await
+            .map_err(|e| BackendError::new(BackendErrorKind::Other, e))?
+            .ok_or_else(|| {
+                BackendError::new(
+                    BackendErrorKind::Other,
+                    format!("没有可用的凭证来调用模型: {}", request.model),
+                )
+            })?;
 
         // 调用 provider
         let response =
-            super::provider_calls::call_provider_openai(state, &credential, request, None).await;
+            super::provider_calls::call_provider_openai(state, &credential, request, None, cancel)
+                .await;
 
         // 解析响应
         let status = response.status();
         let body = axum::body::to_bytes(response.into_body(), 10 * 1024 * 1024)
             .await
-            .map_err(|e| format!("读取响应体失败: {}", e))?;
+            .map_err(|e| {
+                BackendError::new(
+                    BackendErrorKind::NetworkError,
+                    format!("读取响应体失败: {}", e),
+                )
+            })?;
 
         if status != StatusCode::OK {
-            let error_text = String::from_utf8_lossy(&body);
-            return Err(format!("LLM 调用失败 ({}): {}", status, error_text));
+            let error_text = display_text(&body);
+            return Err(BackendError::from_status(
+                status.as_u16(),
+                format!("LLM 调用失败 ({}): {}", status, error_text),
+            ));
         }
 
-        let resp: ChatCompletionResponse =
-            serde_json::from_slice(&body).map_err(|e| format!("解析响应失败: {}", e))?;
+        let raw: serde_json::Value = serde_json::from_slice(&body).map_err(|e| {
+            BackendError::new(BackendErrorKind::Other, format!("解析响应失败: {}", e))
+        })?;
+        let resp: ChatCompletionResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+            BackendError::new(BackendErrorKind::Other, format!("解析响应失败: {}", e))
+        })?;
 
         let content = resp
             .choices
@@ -415,6 +444,538 @@ impl BatchTaskExecutor {
 
         let usage = TokenUsage::new(resp.usage.prompt_tokens, resp.usage.completion_tokens);
 
-        Ok((content, usage))
+        Ok((content, usage, raw))
+    }
+}
+
+/// 构建子任务的请求
+///
+/// 元数据中带有完整请求时直接使用（强制非流式），否则按模板渲染用户消息。
+pub fn build_task_request(
+    task: &TaskDefinition,
+    template: Option<&TaskTemplate>,
+) -> Result<ChatCompletionRequest, String> {
+    if let Some(raw) = task.metadata.get(TASK_REQUEST_METADATA_KEY) {
+        let mut request: ChatCompletionRequest =
+            serde_json::from_str(raw).map_err(|e| format!("子任务请求格式无效: {}", e))?;
+        request.stream = false;
+        return Ok(request);
+    }
+    let template = template.ok_or_else(|| "子任务缺少模板".to_string())?;
+
+    let mut messages = Vec::new();
+    if let Some(sys) = &template.system_prompt {
+        messages.push(ChatMessage {
+            role: "system".to_string(),
+            content: Some(MessageContent::Text(sys.clone())),
+            tool_calls: None,
+            tool_call_id: None,
+            reasoning_content: None,
+        });
+    }
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: Some(MessageContent::Text(
+            template.render_user_message(&task.variables),
+        )),
+        tool_calls: None,
+        tool_call_id: None,
+        reasoning_content: None,
+    });
+
+    Ok(ChatCompletionRequest {
+        model: template.model.clone(),
+        messages,
+        temperature: template.temperature,
+        max_tokens: template.max_tokens,
+        top_p: None,
+        stream: false,
+        tools: None,
+        tool_choice: None,
+        reasoning_effort: None,
+        n: None,
+        logprobs: None,
+        top_logprobs: None,
+        response_format: None,
+        stream_options: None,
+    })
+}
+
+/// 单个子任务的执行结果
+struct TaskOutcome {
+    result: TaskResult,
+    /// 是否为终止性（不可重试）错误，用于触发 `on_error` 策略
+    terminal: bool,
+}
+
+impl TaskOutcome {
+    fn cancelled(task_id: Uuid, started_at: chrono::DateTime<chrono::Utc>, reason: &str) -> Self {
+        Self {
+            result: TaskResult {
+                task_id,
+                status: proxycast_scheduler::BatchTaskStatus2::Cancelled,
+                content: None,
+                error: Some(reason.to_string()),
+                usage: TokenUsage::default(),
+                response: None,
+                started_at,
+                completed_at: Some(chrono::Utc::now()),
+            },
+            terminal: false,
+        }
+    }
+
+    fn failed(
+        task_id: Uuid,
+        started_at: chrono::DateTime<chrono::Utc>,
+        error: &BackendError,
+        terminal: bool,
+    ) -> Self {
+        Self {
+            result: TaskResult {
+                task_id,
+                status: proxycast_scheduler::BatchTaskStatus2::Failed,
+                content: None,
+                error: Some(error.message.clone()),
+                usage: TokenUsage::default(),
+                response: None,
+                started_at,
+                completed_at: Some(chrono::Utc::now()),
+            },
+            terminal,
+        }
+    }
+}
+
+/// 批量任务的最终状态
+fn final_status(results: &[TaskResult], total: usize, cancelled: bool) -> BatchTaskStatus {
+    let completed = results
+        .iter()
+        .filter(|r| r.status == proxycast_scheduler::BatchTaskStatus2::Completed)
+        .count();
+    if cancelled {
+        BatchTaskStatus::Cancelled
+    } else if completed == total {
+        BatchTaskStatus::Completed
+    } else if completed == 0 {
+        BatchTaskStatus::Failed
+    } else {
+        BatchTaskStatus::PartiallyCompleted
+    }
+}
+
+/// 按并发上限执行全部子任务，并按 `on_error` 策略处理终止性错误
+///
+/// `run` 接收子任务序号、ID 和批次内的取消令牌；`on_completed` 在每个子任务
+/// 结束后调用，附带截至目前的全部结果。子任务的中止句柄登记到 `aborts`，
+/// 被中止的子任务记为 `Cancelled`。每个子任务的开始和结束（含失败、跳过和
+/// 中止）都通过 `progress` 推送事件。返回所有子任务的结果。
+async fn run_tasks<R, Fut, C>(
+    task_ids: Vec<Uuid>,
+    concurrency: usize,
+    policy: OnErrorPolicy,
+    cancel: &CancellationToken,
+    aborts: &TaskAborts,
+    progress: &Arc<BatchProgress>,
+    run: R,
+    on_completed: C,
+) -> Vec<TaskResult>
+where
+    R: Fn(usize, Uuid, CancellationToken) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = TaskOutcome> + Send,
+    C: Fn(&TaskResult, &[TaskResult]) + Send + Sync + 'static,
+{
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+    // 用户取消或 StopBatch 时触发，正在执行的任务也会收到
+    let stop = cancel.child_token();
+    // SkipRemaining 时触发，只影响尚未开始的任务
+    let skip = CancellationToken::new();
+    let results = Arc::new(RwLock::new(Vec::<TaskResult>::new()));
+    let run = Arc::new(run);
+    let on_completed = Arc::new(on_completed);
+    let mut handles = Vec::new();
+
+    for (index, task_id) in task_ids.into_iter().enumerate() {
+        let sem = semaphore.clone();
+        let stop = stop.clone();
+        let skip = skip.clone();
+        let results = results.clone();
+        let run = run.clone();
+        let on_completed = on_completed.clone();
+        let progress = progress.clone();
+
+        let handle = tokio::spawn(async move {
+            let _permit = sem.acquire_owned().await;
+            let started_at = chrono::Utc::now();
+
+            let outcome = if stop.is_cancelled() {
+                TaskOutcome::cancelled(task_id, started_at, "任务已取消")
+            } else if skip.is_cancelled() {
+                TaskOutcome::cancelled(task_id, started_at, "前序任务出现终止性错误，已跳过")
+            } else {
+                progress.task_started(task_id);
+                run(index, task_id, stop.clone()).await
+            };
+
+            if outcome.terminal {
+                match policy {
+                    OnErrorPolicy::StopBatch => {
+                        tracing::warn!("[BATCH] 任务 {} 出现终止性错误，终止整个批次", task_id);
+                        stop.cancel();
+                    }
+                    OnErrorPolicy::SkipRemaining => {
+                        tracing::warn!("[BATCH] 任务 {} 出现终止性错误，跳过剩余任务", task_id);
+                        skip.cancel();
+                    }
+                    OnErrorPolicy::ContinueTask => {}
+                }
+            }
+
+            let current_results = {
+                let mut results = results.write().await;
+                results.push(outcome.result.clone());
+                results.clone()
+            };
+            on_completed(&outcome.result, &current_results);
+            progress.task_completed(outcome.result);
+        });
+        aborts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(handle.abort_handle());
+        handles.push((task_id, handle));
+    }
+
+    // 等待所有任务完成
+    for (task_id, handle) in handles {
+        match handle.await {
+            Ok(()) => {}
+            Err(e) if e.is_cancelled() => {
+                // 非流式调用只在响应完成时返回用量，被中止的子任务没有可记录的用量
+                tracing::info!("[BATCH] 子任务已中止: task_id={}", task_id);
+                let outcome = TaskOutcome::cancelled(task_id, chrono::Utc::now(), "任务已中止");
+                let current_results = {
+                    let mut results = results.write().await;
+                    results.push(outcome.result.clone());
+                    results.clone()
+                };
+                on_completed(&outcome.result, &current_results);
+                progress.task_completed(outcome.result);
+            }
+            Err(e) => tracing::error!("[BATCH] 子任务异常退出: task_id={}, error={}", task_id, e),
+        }
+    }
+
+    let guard = results.read().await;
+    guard.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_scheduler::BatchTaskStatus2;
+    use BatchTaskStatus2::{Cancelled, Completed, Failed};
+
+    /// 模拟子任务的行为
+    #[derive(Clone, Copy)]
+    enum Behavior {
+        Succeed,
+        /// 执行较慢，直到被取消或超时才结束
+        Slow,
+        /// 认证失败（终止性错误）
+        AuthFailure,
+        /// 限流（可重试错误，重试耗尽）
+        RateLimited,
+    }
+
+    async fn run_batch(
+        behaviors: Vec<Behavior>,
+        concurrency: usize,
+        policy: OnErrorPolicy,
+    ) -> Vec<(Uuid, BatchTaskStatus2)> {
+        let task_ids: Vec<Uuid> = behaviors.iter().map(|_| Uuid::new_v4()).collect();
+        let progress = Arc::new(BatchEventEmitter::new().progress(Uuid::new_v4(), task_ids.len()));
+        let results =
+            run_simulated(task_ids.clone(), behaviors, concurrency, policy, &progress).await;
+
+        // 按任务原始顺序返回状态
+        task_ids
+            .iter()
+            .map(|id| {
+                let result = results.iter().find(|r| r.task_id == *id).unwrap();
+                (*id, result.status)
+            })
+            .collect()
+    }
+
+    /// 用模拟的子任务行为执行批次
+    async fn run_simulated(
+        task_ids: Vec<Uuid>,
+        behaviors: Vec<Behavior>,
+        concurrency: usize,
+        policy: OnErrorPolicy,
+        progress: &Arc<BatchProgress>,
+    ) -> Vec<TaskResult> {
+        let behaviors = Arc::new(behaviors);
+        run_tasks(
+            task_ids,
+            concurrency,
+            policy,
+            &CancellationToken::new(),
+            &TaskAborts::default(),
+            progress,
+            move |index, task_id, cancel| {
+                let behavior = behaviors[index];
+                async move {
+                    let started_at = chrono::Utc::now();
+                    let error = match behavior {
+                        Behavior::Succeed => None,
+                        Behavior::Slow => {
+                            tokio::select! {
+                                _ = cancel.cancelled() => {
+                                    return TaskOutcome::cancelled(task_id, started_at, "任务已取消");
+                                }
+                                _ = tokio::time::sleep(std::time::Duration::from_millis(200)) => None,
+                            }
+                        }
+                        Behavior::AuthFailure => Some(BackendError::from_status(401, "bad key")),
+                        Behavior::RateLimited => Some(BackendError::from_status(429, "slow down")),
+                    };
+                    match error {
+                        None => TaskOutcome {
+                            result: TaskResult {
+                                task_id,
+                                status: BatchTaskStatus2::Completed,
+                                content: Some("ok".to_string()),
+                                error: None,
+                                usage: TokenUsage::default(),
+                                response: None,
+                                started_at,
+                                completed_at: Some(chrono::Utc::now()),
+                            },
+                            terminal: false,
+                        },
+                        Some(e) => {
+                            let terminal = !e.is_retryable();
+                            TaskOutcome::failed(task_id, started_at, &e, terminal)
+                        }
+                    }
+                }
+            },
+            |_, _| {},
+        )
+        .await
+    }
+
+    fn statuses(results: &[(Uuid, BatchTaskStatus2)]) -> Vec<BatchTaskStatus2> {
+        results.iter().map(|(_, status)| *status).collect()
+    }
+
+    #[tokio::test]
+    async fn test_continue_task_runs_everything() {
+        let results = run_batch(
+            vec![
+                Behavior::Succeed,
+                Behavior::AuthFailure,
+                Behavior::Succeed,
+                Behavior::Succeed,
+            ],
+            1,
+            OnErrorPolicy::ContinueTask,
+        )
+        .await;
+        assert_eq!(
+            statuses(&results),
+            vec![Completed, Failed, Completed, Completed]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_skip_remaining_lets_running_tasks_finish() {
+        let results = run_batch(
+            vec![
+                Behavior::Slow,
+                Behavior::AuthFailure,
+                Behavior::Succeed,
+                Behavior::Succeed,
+            ],
+            2,
+            OnErrorPolicy::SkipRemaining,
+        )
+        .await;
+        // 正在执行的慢任务正常完成，尚未开始的任务被跳过
+        assert_eq!(
+            statuses(&results),
+            vec![Completed, Failed, Cancelled, Cancelled]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_batch_cancels_outstanding_tasks() {
+        let results = run_batch(
+            vec![
+                Behavior::Slow,
+                Behavior::AuthFailure,
+                Behavior::Succeed,
+                Behavior::Succeed,
+            ],
+            2,
+            OnErrorPolicy::StopBatch,
+        )
+        .await;
+        // 正在执行的慢任务也被取消
+        assert_eq!(
+            statuses(&results),
+            vec![Cancelled, Failed, Cancelled, Cancelled]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_running_tasks() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        /// 子任务 future 被丢弃时置位
+        struct DropFlag(Arc<AtomicBool>);
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let task_ids: Vec<Uuid> = (0..2).map(|_| Uuid::new_v4()).collect();
+        let cancel = CancellationToken::new();
+        let aborts = TaskAborts::default();
+        let dropped = Arc::new(AtomicBool::new(false));
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        let run = {
+            let dropped = dropped.clone();
+            let finished = finished.clone();
+            move |_index: usize, task_id: Uuid, _cancel: CancellationToken| {
+                let guard = DropFlag(dropped.clone());
+                let finished = finished.clone();
+                // 模拟不响应取消令牌的上游调用
+                async move {
+                    let _guard = guard;
+                    let started_at = chrono::Utc::now();
+                    tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                    finished.fetch_add(1, Ordering::SeqCst);
+                    TaskOutcome::cancelled(task_id, started_at, "不应执行到这里")
+                }
+            }
+        };
+        let progress = Arc::new(BatchEventEmitter::new().progress(Uuid::new_v4(), 2));
+        let started = std::time::Instant::now();
+        let (results, ()) = tokio::join!(
+            run_tasks(
+                task_ids.clone(),
+                2,
+                OnErrorPolicy::ContinueTask,
+                &cancel,
+                &aborts,
+                &progress,
+                run,
+                |_, _| {},
+            ),
+            async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                cancel.cancel();
+                abort_tasks(&aborts);
+            }
+        );
+
+        // 正在执行的 future 被中止，而不是在后台继续运行
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(dropped.load(Ordering::SeqCst));
+        assert_eq!(finished.load(Ordering::SeqCst), 0);
+        assert_eq!(results.len(), 2);
+        for id in &task_ids {
+            let result = results.iter().find(|r| r.task_id == *id).unwrap();
+            assert_eq!(result.status, Cancelled);
+            assert_eq!(result.usage.total_tokens, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_emits_event_sequence_including_failures() {
+        let emitter = BatchEventEmitter::new();
+        let mut rx = emitter.subscribe();
+        let batch_id = Uuid::new_v4();
+        let task_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let progress = Arc::new(emitter.progress(batch_id, task_ids.len()));
+
+        let results = run_simulated(
+            task_ids.clone(),
+            vec![Behavior::Succeed, Behavior::AuthFailure, Behavior::Succeed],
+            1,
+            OnErrorPolicy::SkipRemaining,
+            &progress,
+        )
+        .await;
+        progress.finished(final_status(&results, task_ids.len(), false));
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            assert_eq!(event.batch_id(), batch_id);
+            events.push(event);
+        }
+        let names: Vec<&str> = events.iter().map(BatchEvent::name).collect();
+        // 被跳过的子任务没有开始事件，但同样推送结果和进度
+        assert_eq!(
+            names,
+            vec![
+                "batch:task_started",
+                "batch:task_completed",
+                "batch:progress",
+                "batch:task_started",
+                "batch:task_completed",
+                "batch:progress",
+                "batch:task_completed",
+                "batch:progress",
+                "batch:finished",
+            ]
+        );
+
+        let completed: Vec<(Uuid, BatchTaskStatus2)> = events
+            .iter()
+            .filter_map(|event| match event {
+                BatchEvent::TaskCompleted { result, .. } => Some((result.task_id, result.status)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            completed,
+            vec![
+                (task_ids[0], Completed),
+                (task_ids[1], Failed),
+                (task_ids[2], Cancelled),
+            ]
+        );
+        assert!(matches!(
+            events[7],
+            BatchEvent::Progress {
+                done: 3,
+                total: 3,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[8],
+            BatchEvent::Finished {
+                status: BatchTaskStatus::PartiallyCompleted,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retryable_errors_do_not_trigger_policy() {
+        let results = run_batch(
+            vec![Behavior::RateLimited, Behavior::Succeed, Behavior::Succeed],
+            1,
+            OnErrorPolicy::StopBatch,
+        )
+        .await;
+        assert_eq!(statuses(&results), vec![Failed, Completed, Completed]);
     }
 }
@@ -27,6 +27,7 @@ use crate::handlers::verify_api_key;
 use crate::AppState;
 use proxycast_core::models::openai::ImageGenerationRequest;
 use proxycast_core::models::provider_pool_model::CredentialData;
+use proxycast_core::ProviderType;
 use proxycast_providers::converter::openai_to_antigravity::{
     convert_antigravity_image_response, convert_image_request_to_antigravity,
 };
@@ -66,7 +67,7 @@ pub async fn handle_image_generation(
     Json(request): Json<ImageGenerationRequest>,
 ) -> Response {
     // 验证 API Key
-    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = verify_api_key(&headers, &*state.api_keys.read().await).await {
         return e.into_response();
     }
 
@@ -187,7 +188,8 @@ pub async fn handle_image_generation(
     };
 
     // 创建 Antigravity Provider
-    let mut antigravity = AntigravityProvider::new();
+    let mut antigravity =
+        AntigravityProvider::with_client(state.provider_clients.get(ProviderType::Antigravity));
     if let Err(e) = antigravity
         .load_credentials_from_path(&creds_file_path)
         .await
@@ -241,14 +243,8 @@ pub async fn handle_image_generation(
         }
     }
 
-    // 设置项目 ID
-    if let Some(pid) = project_id {
-        antigravity.project_id = Some(pid);
-    } else if let Err(e) = antigravity.discover_project().await {
-        tracing::warn!("[IMAGE] Failed to discover project: {}", e);
-    }
-
-    let proj_id = antigravity.project_id.clone().unwrap_or_default();
+    // 设置项目 ID（已配置 -> 发现 -> 生成）
+    let proj_id = antigravity.ensure_project_id(project_id).await;
 
     // 转换请求为 Antigravity 格式
     let antigravity_request = convert_image_request_to_antigravity(&request, &proj_id);
@@ -4,11 +4,21 @@
 
 #![allow(dead_code)]
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 
 use crate::AppState;
+use proxycast_core::config::{effective_config, EndpointProvidersConfig, RuntimeOverrides};
+use proxycast_core::database::dao::audit_log::AuditLogDao;
 use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::DbConnection;
+use proxycast_core::models::provider_pool_model::ProviderCredential;
 
 // ============ Types ============
 
@@ -149,6 +159,125 @@ pub struct UpdateConfigResponse {
     pub message: String,
 }
 
+/// 调整日志级别请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetLogLevelRequest {
+    /// 模块路径（如 `proxycast_server`），为空时调整默认级别
+    #[serde(default)]
+    pub target: String,
+    /// 日志级别（trace/debug/info/warn/error/off）
+    pub level: String,
+}
+
+/// 启用/停用端点 Provider 请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetEndpointEnabledRequest {
+    /// 是否启用
+    pub enabled: bool,
+}
+
+/// 审计日志查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditQuery {
+    /// 返回条数，默认 100
+    #[serde(default = "default_audit_limit")]
+    pub limit: usize,
+}
+
+fn default_audit_limit() -> usize {
+    100
+}
+
+// ============ Audit ============
+
+/// 记录管理 API 审计日志
+///
+/// 写入失败只记录警告，不影响请求结果；`diff` 中的密钥字段由 DAO 脱敏。
+fn record_audit(
+    db: Option<&DbConnection>,
+    action: &str,
+    target: &str,
+    client: Option<ConnectInfo<SocketAddr>>,
+    diff: serde_json::Value,
+) {
+    let Some(db) = db else {
+        return;
+    };
+    let source_ip = client.map(|ConnectInfo(addr)| addr.ip().to_string());
+    let result = db.lock().map_err(|e| e.to_string()).and_then(|conn| {
+        AuditLogDao::append(&conn, action, target, source_ip.as_deref(), diff)
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        tracing::warn!(
+            "[MANAGEMENT] 写入审计日志失败: {} {}: {}",
+            action,
+            target,
+            e
+        );
+    }
+}
+
+/// 写入新凭证并记录审计日志
+pub fn insert_audited_credential(
+    db: &DbConnection,
+    credential: &ProviderCredential,
+    client: Option<ConnectInfo<SocketAddr>>,
+) -> Result<(), String> {
+    {
+        let conn = proxycast_core::database::lock_db(db)?;
+        ProviderPoolDao::insert(&conn, credential).map_err(|e| e.to_string())?;
+    }
+    record_audit(
+        Some(db),
+        "add_credential",
+        &credential.uuid,
+        client,
+        serde_json::json!({ "after": credential }),
+    );
+    Ok(())
+}
+
+/// 应用配置更新并记录审计日志
+///
+/// 返回是否有变更；Provider 类型无效时返回错误信息且不做任何修改。
+pub async fn apply_config_update(
+    default_provider: &tokio::sync::RwLock<String>,
+    db: Option<&DbConnection>,
+    request: UpdateConfigRequest,
+    client: Option<ConnectInfo<SocketAddr>>,
+) -> Result<bool, String> {
+    let mut diff = serde_json::Map::new();
+
+    // 更新默认 Provider
+    if let Some(provider) = request.default_provider {
+        // 验证 provider 类型
+        if provider.parse::<proxycast_core::ProviderType>().is_err() {
+            return Err(format!("Invalid provider type: {provider}"));
+        }
+        let mut dp = default_provider.write().await;
+        diff.insert(
+            "default_provider".to_string(),
+            serde_json::json!({ "before": *dp, "after": provider }),
+        );
+        *dp = provider.clone();
+        tracing::info!("[MANAGEMENT] Updated default_provider to: {}", provider);
+    }
+
+    if diff.is_empty() {
+        return Ok(false);
+    }
+    let target = diff.keys().cloned().collect::<Vec<_>>().join(",");
+    record_audit(
+        db,
+        "update_config",
+        &target,
+        client,
+        serde_json::Value::Object(diff),
+    );
+    Ok(true)
+}
+
 // ============ Handlers ============
 
 /// GET /v0/management/status - 获取服务器状态
@@ -199,11 +328,10 @@ pub async fn management_list_credentials(State(state): State<AppState>) -> impl
 /// POST /v0/management/credentials - 添加凭证
 pub async fn management_add_credential(
     State(state): State<AppState>,
+    client: Option<ConnectInfo<SocketAddr>>,
     Json(request): Json<AddCredentialRequest>,
 ) -> impl IntoResponse {
-    use proxycast_core::models::provider_pool_model::{
-        CredentialData, PoolProviderType, ProviderCredential,
-    };
+    use proxycast_core::models::provider_pool_model::{CredentialData, PoolProviderType};
 
     // 验证请求
     if request.id.is_empty() {
@@ -435,8 +563,15 @@ pub async fn management_add_credential(
                 );
             }
         }
+        // 本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM）- API Key 可选
+        PoolProviderType::Ollama => CredentialData::LocalOpenAI {
+            base_url: request
+                .base_url
+                .unwrap_or_else(|| "http://localhost:11434".to_string()),
+            api_key: request.api_key.filter(|key| !key.is_empty()),
+        },
         // API Key Provider 类型 - 不支持通过此接口添加凭证
-        PoolProviderType::AzureOpenai | PoolProviderType::AwsBedrock | PoolProviderType::Ollama => {
+        PoolProviderType::AzureOpenai | PoolProviderType::AwsBedrock => {
             return (
                 StatusCode::BAD_REQUEST,
                 Json(AddCredentialResponse {
@@ -457,36 +592,34 @@ pub async fn management_add_credential(
 
     // 添加凭证到数据库
     if let Some(ref db) = state.db {
-        if let Ok(conn) = db.lock() {
-            match ProviderPoolDao::insert(&conn, &credential) {
-                Ok(_) => {
-                    tracing::info!(
-                        "[MANAGEMENT] Added credential: {} ({})",
-                        request.id,
-                        request.provider_type
-                    );
-                    return (
-                        StatusCode::CREATED,
-                        Json(AddCredentialResponse {
-                            success: true,
-                            message: "Credential added successfully".to_string(),
-                            id: Some(request.id),
-                        }),
-                    );
-                }
-                Err(e) => {
-                    tracing::error!("[MANAGEMENT] Failed to add credential: {}", e);
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(AddCredentialResponse {
-                            success: false,
-                            message: format!("Failed to add credential: {e}"),
-                            id: None,
-                        }),
-                    );
-                }
+        return match insert_audited_credential(db, &credential, client) {
+            Ok(()) => {
+                tracing::info!(
+                    "[MANAGEMENT] Added credential: {} ({})",
+                    request.id,
+                    request.provider_type
+                );
+                (
+                    StatusCode::CREATED,
+                    Json(AddCredentialResponse {
+                        success: true,
+                        message: "Credential added successfully".to_string(),
+                        id: Some(request.id),
+                    }),
+                )
             }
-        }
+            Err(e) => {
+                tracing::error!("[MANAGEMENT] Failed to add credential: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(AddCredentialResponse {
+                        success: false,
+                        message: format!("Failed to add credential: {e}"),
+                        id: None,
+                    }),
+                )
+            }
+        };
     }
 
     (
@@ -528,47 +661,333 @@ pub async fn management_get_config(State(state): State<AppState>) -> impl IntoRe
     Json(response)
 }
 
+/// GET /v0/management/effective-config - 获取服务器实际生效的配置（已脱敏）
+///
+/// 与磁盘上的配置文件不同，包含热重载后的配置和运行时修改（默认 Provider、
+/// 实际监听地址、运行时停用的端点 Provider 等）。
+pub async fn management_get_effective_config(State(state): State<AppState>) -> impl IntoResponse {
+    let Some(manager) = &state.hot_reload_manager else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "error": "Effective configuration is unavailable: server was started without a config file"
+            })),
+        )
+            .into_response();
+    };
+
+    let overrides = RuntimeOverrides {
+        default_provider: Some(state.default_provider.read().await.clone()),
+        host: Some(state.bind_host.clone()),
+        port: Some(state.bind_port),
+        api_key: Some(state.api_key.clone()),
+        endpoint_providers: Some(state.endpoint_providers.read().await.clone()),
+    };
+    Json(effective_config(&manager.config(), &overrides)).into_response()
+}
+
 /// PUT /v0/management/config - 更新配置
 pub async fn management_update_config(
     State(state): State<AppState>,
+    client: Option<ConnectInfo<SocketAddr>>,
     Json(request): Json<UpdateConfigRequest>,
 ) -> impl IntoResponse {
-    let mut updated = false;
-
-    // 更新默认 Provider
-    if let Some(provider) = request.default_provider {
-        // 验证 provider 类型
-        if provider.parse::<proxycast_core::ProviderType>().is_ok() {
-            let mut dp = state.default_provider.write().await;
-            *dp = provider.clone();
-            tracing::info!("[MANAGEMENT] Updated default_provider to: {}", provider);
-            updated = true;
-        } else {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(UpdateConfigResponse {
-                    success: false,
-                    message: format!("Invalid provider type: {provider}"),
-                }),
-            );
-        }
-    }
-
-    if updated {
-        (
+    match apply_config_update(&state.default_provider, state.db.as_ref(), request, client).await {
+        Ok(true) => (
             StatusCode::OK,
             Json(UpdateConfigResponse {
                 success: true,
                 message: "Configuration updated successfully".to_string(),
             }),
-        )
-    } else {
-        (
+        ),
+        Ok(false) => (
             StatusCode::OK,
             Json(UpdateConfigResponse {
                 success: true,
                 message: "No changes applied".to_string(),
             }),
-        )
+        ),
+        Err(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(UpdateConfigResponse {
+                success: false,
+                message,
+            }),
+        ),
+    }
+}
+
+/// PUT /v0/management/log-level - 运行时调整日志级别（不持久化）
+pub async fn management_set_log_level(
+    State(state): State<AppState>,
+    client: Option<ConnectInfo<SocketAddr>>,
+    Json(request): Json<SetLogLevelRequest>,
+) -> impl IntoResponse {
+    match proxycast_core::log_filter::set_log_level(&request.target, &request.level) {
+        Ok(()) => {
+            record_audit(
+                state.db.as_ref(),
+                "set_log_level",
+                &request.target,
+                client,
+                serde_json::json!({ "level": request.level }),
+            );
+            (
+                StatusCode::OK,
+                Json(UpdateConfigResponse {
+                    success: true,
+                    message: format!(
+                        "Log level updated: {}={}",
+                        if request.target.is_empty() {
+                            "*"
+                        } else {
+                            &request.target
+                        },
+                        request.level
+                    ),
+                }),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(UpdateConfigResponse {
+                success: false,
+                message: e,
+            }),
+        ),
+    }
+}
+
+/// 原子地启用或停用端点 Provider，客户端类型无效时返回 false
+pub async fn set_endpoint_enabled(
+    endpoint_providers: &tokio::sync::RwLock<EndpointProvidersConfig>,
+    client_type: &str,
+    enabled: bool,
+) -> bool {
+    endpoint_providers
+        .write()
+        .await
+        .set_enabled(client_type, enabled)
+}
+
+/// GET /v0/management/endpoint-providers - 获取端点 Provider 配置（含停用列表）
+pub async fn management_get_endpoint_providers(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.endpoint_providers.read().await.clone())
+}
+
+/// PUT /v0/management/endpoint-providers/:client_type - 运行时启用/停用端点 Provider（不持久化）
+///
+/// 立即生效：停用后该客户端类型的聊天请求返回 503。
+pub async fn management_set_endpoint_enabled(
+    State(state): State<AppState>,
+    client: Option<ConnectInfo<SocketAddr>>,
+    Path(client_type): Path<String>,
+    Json(request): Json<SetEndpointEnabledRequest>,
+) -> impl IntoResponse {
+    if !set_endpoint_enabled(&state.endpoint_providers, &client_type, request.enabled).await {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(UpdateConfigResponse {
+                success: false,
+                message: format!("Unknown client type: {client_type}"),
+            }),
+        );
+    }
+
+    let action = if request.enabled {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    tracing::info!("[MANAGEMENT] Endpoint provider {} {}", client_type, action);
+    record_audit(
+        state.db.as_ref(),
+        "set_endpoint_enabled",
+        &client_type,
+        client,
+        serde_json::json!({ "enabled": request.enabled }),
+    );
+    (
+        StatusCode::OK,
+        Json(UpdateConfigResponse {
+            success: true,
+            message: format!("Endpoint provider {client_type} {action}"),
+        }),
+    )
+}
+
+/// GET /v0/management/audit - 获取管理 API 审计日志（按时间倒序）
+pub async fn management_list_audit(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    let entries = match &state.db {
+        Some(db) => db
+            .lock()
+            .map_err(|e| e.to_string())
+            .and_then(|conn| AuditLogDao::list(&conn, query.limit).map_err(|e| e.to_string())),
+        None => Err("Database not available".to_string()),
+    };
+    match entries {
+        Ok(entries) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "entries": entries })),
+        ),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "success": false, "message": e })),
+        ),
+    }
+}
+
+/// GET /v0/management/diagnostics - 导出诊断包（zip）
+///
+/// 包含脱敏配置、最近日志、凭证池健康概览、遥测摘要以及版本和系统信息。
+pub async fn management_export_diagnostics(
+    State(state): State<AppState>,
+) -> axum::response::Response {
+    let config = state.hot_reload_manager.as_ref().map(|m| m.config());
+    if config.is_none() {
+        tracing::warn!("[MANAGEMENT] 未启用配置热重载，诊断包中不包含配置");
+    }
+    let logs = state.logs.read().await.get_logs();
+    let pool = match &state.db {
+        Some(db) => state.pool_service.get_overview(db).unwrap_or_else(|e| {
+            tracing::warn!("[MANAGEMENT] 获取凭证池概览失败: {}", e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let telemetry =
+        serde_json::to_value(state.processor.stats.read().summary(None)).unwrap_or_default();
+
+    let input = proxycast_core::diagnostics::DiagnosticsInput {
+        config: config.as_ref(),
+        logs: &logs,
+        pool: &pool,
+        telemetry,
+        app_version: env!("CARGO_PKG_VERSION"),
+    };
+    match proxycast_core::diagnostics::build_diagnostics_bundle(&input) {
+        Ok(bytes) => {
+            let filename = format!(
+                "proxycast_diagnostics_{}.zip",
+                chrono::Local::now().format("%Y%m%d_%H%M%S")
+            );
+            (
+                StatusCode::OK,
+                [
+                    (
+                        axum::http::header::CONTENT_TYPE,
+                        "application/zip".to_string(),
+                    ),
+                    (
+                        axum::http::header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{filename}\""),
+                    ),
+                ],
+                bytes,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("[MANAGEMENT] 导出诊断包失败: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(UpdateConfigResponse {
+                    success: false,
+                    message: e.to_string(),
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_detector::ClientType;
+    use crate::handlers::api::endpoint_disabled_response;
+
+    #[tokio::test]
+    async fn test_disabled_endpoint_stops_serving_until_reenabled() {
+        let endpoint_providers = tokio::sync::RwLock::new(EndpointProvidersConfig::default());
+        assert!(endpoint_providers.read().await.is_enabled("cursor"));
+
+        assert!(set_endpoint_enabled(&endpoint_providers, "cursor", false).await);
+        assert!(!endpoint_providers.read().await.is_enabled("cursor"));
+        assert!(endpoint_providers.read().await.is_enabled("claude_code"));
+        let response = endpoint_disabled_response(ClientType::Cursor);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        assert!(set_endpoint_enabled(&endpoint_providers, "cursor", true).await);
+        assert!(endpoint_providers.read().await.is_enabled("cursor"));
+
+        assert!(!set_endpoint_enabled(&endpoint_providers, "unknown", false).await);
+    }
+
+    #[tokio::test]
+    async fn test_management_mutations_are_audited_without_secrets() {
+        use proxycast_core::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        proxycast_core::database::schema::create_tables(&conn).unwrap();
+        let db: DbConnection = std::sync::Arc::new(std::sync::Mutex::new(conn));
+        let client = || Some(ConnectInfo("10.0.0.2:51234".parse::<SocketAddr>().unwrap()));
+
+        let mut credential = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-audit-secret-1234".to_string(),
+                base_url: None,
+            },
+        );
+        credential.uuid = "openai-audit".to_string();
+        insert_audited_credential(&db, &credential, client()).unwrap();
+
+        let default_provider = tokio::sync::RwLock::new("kiro".to_string());
+        let request = UpdateConfigRequest {
+            default_provider: Some("openai".to_string()),
+            allow_remote: None,
+        };
+        assert!(
+            apply_config_update(&default_provider, Some(&db), request, client())
+                .await
+                .unwrap()
+        );
+        assert_eq!(*default_provider.read().await, "openai");
+
+        // 无效的 Provider 不修改配置，也不记录审计日志
+        let invalid = UpdateConfigRequest {
+            default_provider: Some("not-a-provider".to_string()),
+            allow_remote: None,
+        };
+        assert!(
+            apply_config_update(&default_provider, Some(&db), invalid, client())
+                .await
+                .is_err()
+        );
+
+        let conn = db.lock().unwrap();
+        let entries = AuditLogDao::list(&conn, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, "update_config");
+        assert_eq!(entries[0].target, "default_provider");
+        assert_eq!(entries[0].source_ip.as_deref(), Some("10.0.0.2"));
+        assert_eq!(entries[0].diff["default_provider"]["before"], "kiro");
+        assert_eq!(entries[0].diff["default_provider"]["after"], "openai");
+        assert_eq!(entries[1].action, "add_credential");
+        assert_eq!(entries[1].target, "openai-audit");
+
+        let raw: Vec<String> = conn
+            .prepare("SELECT diff FROM management_audit_log")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let raw = raw.join("\n");
+        assert!(!raw.contains("sk-audit-secret-1234"), "{raw}");
     }
 }
@@ -2,6 +2,7 @@
 //!
 //! 将 server 中的各类处理器拆分到独立文件
 
+pub mod anthropic_batch;
 pub mod api;
 pub mod api_key_provider_utils;
 pub mod batch_api;
@@ -13,6 +14,7 @@ pub mod management;
 pub mod provider_calls;
 pub mod websocket;
 
+pub use anthropic_batch::{create_message_batch, get_message_batch, get_message_batch_results};
 pub use api::*;
 pub use batch_api::*;
 pub use credentials_api::*;
@@ -48,31 +48,149 @@ use axum::{
     Json,
 };
 use futures::StreamExt;
+use tokio_util::sync::CancellationToken;
 
 use crate::AppState;
+use proxycast_core::credential::RefreshLocks;
 use proxycast_core::models::anthropic::AnthropicMessagesRequest;
 use proxycast_core::models::openai::ChatCompletionRequest;
 use proxycast_core::models::provider_pool_model::{CredentialData, ProviderCredential};
+use proxycast_core::ProviderType;
 use proxycast_providers::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use proxycast_providers::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
+use proxycast_providers::converter::reasoning_handler::ReasoningHandler;
 use proxycast_providers::providers::{
     AntigravityProvider, ClaudeCustomProvider, CodexProvider, KiroProvider, OpenAICustomProvider,
     VertexProvider,
 };
 use proxycast_providers::session::store_thought_signature;
-use proxycast_providers::stream::{PipelineConfig, StreamPipeline};
+use proxycast_providers::stream::{PipelineConfig, StopReason, StreamPipeline};
 use proxycast_providers::streaming::traits::StreamingProvider;
 use proxycast_providers::streaming::{
     StreamConfig, StreamContext, StreamError, StreamFormat as StreamingFormat, StreamManager,
     StreamResponse,
 };
+use proxycast_providers::translator::kiro::inject_thinking_prompt;
 use proxycast_server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_error_response,
-    build_error_response_with_status, parse_cw_response, safe_truncate, CWParsedResponse,
+    build_error_response_with_status, cw_parse_error_response, deferred_stream_response,
+    extract_thinking, json_or_passthrough, local_error_response, parse_cw_response_bytes,
+    passthrough_response, safe_truncate, BufferFormat, CWParseError, CWParsedResponse,
 };
 
+/// 请求已取消（客户端断开或批量任务终止）时返回的本地错误响应
+fn cancelled_response() -> Response {
+    local_error_response(
+        StatusCode::from_u16(499).unwrap_or(StatusCode::BAD_REQUEST),
+        "Request cancelled: client disconnected",
+    )
+}
+
+/// 取消令牌触发时中止进行中的上游调用
+async fn with_cancellation(
+    cancel_token: &CancellationToken,
+    call: impl std::future::Future<Output = Response>,
+) -> Response {
+    tokio::select! {
+        biased;
+        _ = cancel_token.cancelled() => cancelled_response(),
+        response = call => response,
+    }
+}
+
+/// 从源文件加载 Kiro 凭证失败的原因
+#[derive(Debug)]
+pub(crate) enum KiroSourceError {
+    /// 凭证文件加载失败
+    Load(String),
+    /// Token 刷新失败
+    Refresh(String),
+}
+
+impl std::fmt::Display for KiroSourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Load(e) => write!(f, "Failed to load Kiro credentials: {e}"),
+            Self::Refresh(e) => write!(f, "Token refresh failed: {e}"),
+        }
+    }
+}
+
+impl KiroSourceError {
+    /// 记录到凭证健康状态的错误信息
+    fn health_message(&self) -> String {
+        match self {
+            Self::Load(e) => format!("Failed to load credentials: {e}"),
+            Self::Refresh(e) => format!("Token refresh failed: {e}"),
+        }
+    }
+}
+
+impl IntoResponse for KiroSourceError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Load(_) => {
+                local_error_response(StatusCode::INTERNAL_SERVER_ERROR, &self.to_string())
+            }
+            Self::Refresh(_) => (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({"error": {"message": self.to_string()}})),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Token 缓存不可用时从源文件加载 Kiro 凭证
+///
+/// 失败时将凭证标记为不健康。刷新锁只覆盖加载与刷新，返回前即释放，
+/// 不会串行化同一凭证后续的上游调用。
+pub(crate) async fn load_kiro_from_source(
+    state: &AppState,
+    credential_uuid: &str,
+    creds_file_path: &str,
+) -> Result<KiroProvider, KiroSourceError> {
+    let result =
+        refresh_kiro_from_source(&state.kiro_refresh_locks, credential_uuid, creds_file_path)
+            .await
+            .map(|mut kiro| {
+                kiro.client = state.provider_clients.get(ProviderType::Kiro);
+                kiro
+            });
+    if let (Err(e), Some(db)) = (&result, &state.db) {
+        let _ = state
+            .pool_service
+            .mark_unhealthy(db, credential_uuid, Some(&e.health_message()));
+    }
+    result
+}
+
+/// 在凭证刷新锁内加载源文件，Token 仍然过期时才刷新
+///
+/// 等锁期间其他请求可能已刷新并写回源文件，因此拿到锁后重新读取并复查。
+async fn refresh_kiro_from_source(
+    locks: &RefreshLocks,
+    credential_uuid: &str,
+    creds_file_path: &str,
+) -> Result<KiroProvider, KiroSourceError> {
+    let _refresh_guard = locks.lock(credential_uuid).await;
+    let mut kiro = KiroProvider::new();
+    kiro.load_credentials_from_path(creds_file_path)
+        .await
+        .map_err(|e| KiroSourceError::Load(e.to_string()))?;
+    if kiro.credentials.access_token.is_none()
+        || kiro.is_token_expired()
+        || kiro.is_token_expiring_soon()
+    {
+        kiro.refresh_token()
+            .await
+            .map_err(|e| KiroSourceError::Refresh(e.to_string()))?;
+    }
+    Ok(kiro)
+}
+
 /// 根据凭证调用 Provider (Anthropic 格式)
 ///
 /// # 参数
@@ -80,11 +198,51 @@ use proxycast_server_utils::{
 /// - `credential`: 凭证信息
 /// - `request`: Anthropic 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+/// - `cancel_token`: 取消令牌（客户端断开时触发，中止上游调用）
 pub async fn call_provider_anthropic(
     state: &AppState,
     credential: &ProviderCredential,
     request: &AnthropicMessagesRequest,
     flow_id: Option<&str>,
+    cancel_token: &CancellationToken,
+) -> Response {
+    // 这些凭证需要完整缓冲上游响应后再转换为流：较慢时先发送响应头，缓冲期间由心跳层保活
+    if request.stream
+        && matches!(
+            credential.credential,
+            CredentialData::AntigravityOAuth { .. }
+                | CredentialData::OpenAIKey { .. }
+                | CredentialData::LocalOpenAI { .. }
+        )
+    {
+        let grace = state.stream_heartbeat;
+        let state = state.clone();
+        let credential = credential.clone();
+        let request = request.clone();
+        let flow_id = flow_id.map(str::to_string);
+        let cancel_token = cancel_token.clone();
+        let call = async move {
+            with_cancellation(
+                &cancel_token,
+                dispatch_anthropic(&state, &credential, &request, flow_id.as_deref()),
+            )
+            .await
+        };
+        return deferred_stream_response(call, BufferFormat::Anthropic, grace).await;
+    }
+
+    with_cancellation(
+        cancel_token,
+        dispatch_anthropic(state, credential, request, flow_id),
+    )
+    .await
+}
+
+async fn dispatch_anthropic(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &AnthropicMessagesRequest,
+    flow_id: Option<&str>,
 ) -> Response {
     match &credential.credential {
         CredentialData::KiroOAuth { creds_file_path } => {
@@ -98,11 +256,7 @@ pub async fn call_provider_anthropic(
             let db = match &state.db {
                 Some(db) => db,
                 None => {
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": "Database not available"}})),
-                    )
-                        .into_response();
+                    return local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database not available");
                 }
             };
             // 获取缓存的 token
@@ -115,44 +269,23 @@ pub async fn call_provider_anthropic(
                 Err(e) => {
                     tracing::warn!("[POOL] Token cache miss, loading from source: {}", e);
                     // 回退到从源文件加载
-                    let mut kiro = KiroProvider::new();
-                    if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
-                        // 记录凭证加载失败
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&format!("Failed to load credentials: {e}")),
-                        );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                        )
-                            .into_response();
-                    }
-                    if let Err(e) = kiro.refresh_token().await {
-                        // 记录 Token 刷新失败
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&format!("Token refresh failed: {e}")),
-                        );
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
-                        )
-                            .into_response();
+                    match load_kiro_from_source(state, &credential.uuid, creds_file_path).await {
+                        Ok(kiro) => kiro.credentials.access_token.unwrap_or_default(),
+                        Err(e) => return e.into_response(),
                     }
-                    kiro.credentials.access_token.unwrap_or_default()
                 }
             };
             // 使用获取到的 token 创建 KiroProvider
-            let mut kiro = KiroProvider::new();
+            let mut kiro = KiroProvider::with_client(state.provider_clients.get(ProviderType::Kiro));
             // 从源文件加载其他配置（region, profile_arn 等）
             // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
             let _ = kiro.load_credentials_from_path(creds_file_path).await;
             // 使用缓存的 token 覆盖文件中的 token（缓存的 token 更新）
             kiro.credentials.access_token = Some(token);
-            let openai_request = convert_anthropic_to_openai(request);
+            let mut openai_request = convert_anthropic_to_openai(request);
+            if let Some(budget) = request.thinking_budget() {
+                inject_thinking_prompt(&mut openai_request, budget);
+            }
             let resp = match kiro.call_api(&openai_request).await {
                 Ok(r) => r,
                 Err(e) => {
@@ -173,8 +306,13 @@ pub async fn call_provider_anthropic(
             if status.is_success() {
                 match resp.bytes().await {
                     Ok(bytes) => {
-                        let body = String::from_utf8_lossy(&bytes).to_string();
-                        let parsed = parse_cw_response(&body);
+                        let mut parsed = match parse_cw_response_bytes(&bytes).or_else(CWParseError::recover) {
+                            Ok(parsed) => parsed,
+                            Err(e) => return cw_parse_error_response(&e),
+                        };
+                        if request.thinking_budget().is_some() {
+                            extract_thinking(&mut parsed);
+                        }
                         // 记录成功
                         let _ = state.pool_service.mark_healthy(
                             db,
@@ -232,8 +370,13 @@ pub async fn call_provider_anthropic(
                         if retry_resp.status().is_success() {
                             match retry_resp.bytes().await {
                                 Ok(bytes) => {
-                                    let body = String::from_utf8_lossy(&bytes).to_string();
-                                    let parsed = parse_cw_response(&body);
+                                    let mut parsed = match parse_cw_response_bytes(&bytes).or_else(CWParseError::recover) {
+                                        Ok(parsed) => parsed,
+                                        Err(e) => return cw_parse_error_response(&e),
+                                    };
+                                    if request.thinking_budget().is_some() {
+                                        extract_thinking(&mut parsed);
+                                    }
                                     // 记录重试成功
                                     let _ = state.pool_service.mark_healthy(
                                         db,
@@ -287,7 +430,8 @@ pub async fn call_provider_anthropic(
             } else {
                 let status_code = status.as_u16();
                 let body = resp.text().await.unwrap_or_default();
-                eprintln!("[PROVIDER_CALL] Kiro 请求失败: status={} body={}", status_code, &body[..body.len().min(500)]);
+                let redacted = state.logs.read().await.redact(&body);
+                eprintln!("[PROVIDER_CALL] Kiro 请求失败: status={} body={}", status_code, redacted.chars().take(500).collect::<String>());
                 // 只有 5xx 错误才标记为不健康
                 if status_code >= 500 {
                     let _ = state
@@ -304,17 +448,14 @@ pub async fn call_provider_anthropic(
         }
         CredentialData::GeminiOAuth { .. } => {
             // Gemini OAuth 路由暂不支持
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Gemini OAuth routing not yet implemented. Use /v1/messages with Gemini models instead."}})),
-            )
-                .into_response()
+            local_error_response(StatusCode::NOT_IMPLEMENTED, "Gemini OAuth routing not yet implemented. Use /v1/messages with Gemini models instead.")
         }
         CredentialData::AntigravityOAuth {
             creds_file_path,
             project_id,
         } => {
-            let mut antigravity = AntigravityProvider::new();
+            let mut antigravity =
+                AntigravityProvider::with_client(state.provider_clients.get(ProviderType::Antigravity));
             if let Err(e) = antigravity
                 .load_credentials_from_path(creds_file_path)
                 .await
@@ -327,11 +468,7 @@ pub async fn call_provider_anthropic(
                         Some(&format!("Failed to load credentials: {e}")),
                     );
                 }
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Antigravity credentials: {}", e)}})),
-                )
-                    .into_response();
+                return local_error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("Failed to load Antigravity credentials: {}", e));
             }
 
             // 使用新的 validate_token() 方法检查 Token 状态
@@ -380,14 +517,8 @@ pub async fn call_provider_anthropic(
                 }
             }
 
-            // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            } else if let Err(e) = antigravity.discover_project().await {
-                tracing::warn!("[Antigravity] Failed to discover project: {}", e);
-            }
-            // 获取 project_id 用于请求
-            let proj_id = antigravity.project_id.clone().unwrap_or_default();
+            // 设置项目 ID（已配置 -> 发现 -> 生成）
+            let proj_id = antigravity.ensure_project_id(project_id.clone()).await;
             // 先转换为 OpenAI 格式，再转换为 Antigravity 格式
             let openai_request = convert_anthropic_to_openai(request);
             let antigravity_request = convert_openai_to_antigravity_with_context(&openai_request, &proj_id);
@@ -402,6 +533,7 @@ pub async fn call_provider_anthropic(
                         .unwrap_or("");
                     let parsed = CWParsedResponse {
                         content: content.to_string(),
+                        thinking: String::new(),
                         tool_calls: Vec::new(),
                         usage_credits: 0.0,
                         context_usage_percentage: 0.0,
@@ -436,119 +568,27 @@ pub async fn call_provider_anthropic(
                 }
             }
         }
+        // OpenAI API Key 与本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM）
         CredentialData::OpenAIKey { api_key, base_url } => {
-            let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
-            let openai_request = convert_anthropic_to_openai(request);
-            match openai.call_api(&openai_request).await {
-                Ok(resp) => {
-                    let status = resp.status();
-                    if status.is_success() {
-                        match resp.text().await {
-                            Ok(body) => {
-                                // 记录原始响应以便调试
-                                eprintln!("[PROVIDER_CALL] OpenAI 响应: {}", &body[..body.len().min(500)]);
-
-                                if let Ok(openai_resp) =
-                                    serde_json::from_str::<serde_json::Value>(&body)
-                                {
-                                    let content = openai_resp["choices"][0]["message"]["content"]
-                                        .as_str()
-                                        .unwrap_or("");
-                                    let parsed = CWParsedResponse {
-                                        content: content.to_string(),
-                                        tool_calls: Vec::new(),
-                                        usage_credits: 0.0,
-                                        context_usage_percentage: 0.0,
-                                    };
-                                    // 记录成功
-                                    if let Some(db) = &state.db {
-                                        let _ = state.pool_service.mark_healthy(
-                                            db,
-                                            &credential.uuid,
-                                            Some(&request.model),
-                                        );
-                                        let _ =
-                                            state.pool_service.record_usage(db, &credential.uuid);
-                                    }
-                                    if request.stream {
-                                        build_anthropic_stream_response(&request.model, &parsed)
-                                    } else {
-                                        build_anthropic_response(&request.model, &parsed)
-                                    }
-                                } else {
-                                    // 记录解析失败和原始响应
-                                    eprintln!("[PROVIDER_CALL] 解析 OpenAI 响应失败，原始响应: {}", &body);
-                                    if let Some(db) = &state.db {
-                                        let _ = state.pool_service.mark_unhealthy(
-                                            db,
-                                            &credential.uuid,
-                                            Some("Failed to parse OpenAI response"),
-                                        );
-                                    }
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": format!("Failed to parse OpenAI response. Body: {}", &body[..body.len().min(200)])}})),
-                                    )
-                                        .into_response()
-                                }
-                            }
-                            Err(e) => {
-                                if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(
-                                        db,
-                                        &credential.uuid,
-                                        Some(&e.to_string()),
-                                    );
-                                }
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                                )
-                                    .into_response()
-                            }
-                        }
-                    } else {
-                        let status_code = status.as_u16();
-                        let body = resp.text().await.unwrap_or_default();
-                        eprintln!("[PROVIDER_CALL] OpenAI 请求失败: status={} body={}", status_code, &body[..body.len().min(500)]);
-                        // 只有 5xx 错误才标记为不健康，4xx 错误（如模型不支持）不应该标记凭证为不健康
-                        if status_code >= 500 {
-                            if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_unhealthy(
-                                    db,
-                                    &credential.uuid,
-                                    Some(&body),
-                                );
-                            }
-                        }
-                        // 转发上游的实际状态码
-                        (
-                            StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
-                            .into_response()
-                    }
-                }
-                Err(e) => {
-                    if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&e.to_string()),
-                        );
-                    }
-                    (
-                        StatusCode::BAD_GATEWAY,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response()
-                }
-            }
+            let openai = OpenAICustomProvider::with_client(
+                api_key.clone(),
+                base_url.clone(),
+                state.provider_clients.get(ProviderType::OpenAI),
+            );
+            dispatch_anthropic_openai_compatible(state, credential, request, openai).await
+        }
+        CredentialData::LocalOpenAI { base_url, api_key } => {
+            let openai = OpenAICustomProvider::with_local_server(
+                base_url.clone(),
+                api_key.clone(),
+                state.provider_clients.get(ProviderType::OpenAI),
+            );
+            dispatch_anthropic_openai_compatible(state, credential, request, openai).await
         }
         CredentialData::ClaudeKey { api_key, base_url } => {
             // 打印 Claude 代理 URL 用于调试
             let actual_base_url = base_url.as_deref().unwrap_or("https://api.anthropic.com");
-            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let claude = ClaudeCustomProvider::with_client(api_key.clone(), base_url.clone(), state.provider_clients.get(ProviderType::Claude));
             let request_url = claude.get_base_url();
             state.logs.write().await.add(
                 "info",
@@ -609,11 +649,7 @@ pub async fn call_provider_anthropic(
                             .header("Transfer-Encoding", "chunked")
                             .body(Body::from_stream(stream))
                             .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
-                                )
-                                    .into_response()
+                                local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build stream response")
                             });
                     }
 
@@ -643,11 +679,7 @@ pub async fn call_provider_anthropic(
                                     .header(header::CONTENT_TYPE, "application/json")
                                     .body(Body::from(body))
                                     .unwrap_or_else(|_| {
-                                        (
-                                            StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
-                                        )
-                                            .into_response()
+                                        local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response")
                                     })
                             } else {
                                 state.logs.write().await.add(
@@ -711,8 +743,10 @@ pub async fn call_provider_anthropic(
         }
         CredentialData::VertexKey { api_key, base_url, .. } => {
             // Vertex AI uses Gemini-compatible API, convert Anthropic to OpenAI format first
-            let openai_request = convert_anthropic_to_openai(request);
-            let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
+            let mut openai_request = convert_anthropic_to_openai(request);
+            ReasoningHandler::strip_unsupported_effort(&mut openai_request);
+            let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone())
+                .with_client(state.provider_clients.get(ProviderType::Vertex));
             match vertex.chat_completions(&serde_json::to_value(&openai_request).unwrap_or_default()).await {
                 Ok(resp) => {
                     let status = resp.status();
@@ -728,7 +762,7 @@ pub async fn call_provider_anthropic(
                                     .header(header::CONTENT_TYPE, "application/json")
                                     .body(Body::from(body))
                                     .unwrap_or_else(|_| {
-                                        (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": "Failed to build response"}}))).into_response()
+                                        local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response")
                                     })
                             } else {
                                 if let Some(db) = &state.db {
@@ -773,7 +807,7 @@ pub async fn call_provider_anthropic(
         // Anthropic API Key - 根据 base_url 决定调用方式
         CredentialData::AnthropicKey { api_key, base_url } => {
             // 使用 Anthropic 原生格式调用（无论是否有自定义 base_url）
-            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let claude = ClaudeCustomProvider::with_client(api_key.clone(), base_url.clone(), state.provider_clients.get(ProviderType::Anthropic));
             let request_url = claude.get_base_url();
             state.logs.write().await.add(
                 "info",
@@ -821,11 +855,7 @@ pub async fn call_provider_anthropic(
                             .header("Transfer-Encoding", "chunked")
                             .body(Body::from_stream(stream))
                             .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
-                                )
-                                    .into_response()
+                                local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build stream response")
                             });
                     }
 
@@ -846,11 +876,7 @@ pub async fn call_provider_anthropic(
                                     .header(header::CONTENT_TYPE, "application/json")
                                     .body(Body::from(body))
                                     .unwrap_or_else(|_| {
-                                        (
-                                            StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
-                                        )
-                                            .into_response()
+                                        local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response")
                                     })
                             } else {
                                 state.logs.write().await.add(
@@ -902,6 +928,134 @@ pub async fn call_provider_anthropic(
     }
 }
 
+/// 通过 OpenAI 兼容凭证（OpenAI API Key、本地服务）处理 Anthropic 格式请求
+async fn dispatch_anthropic_openai_compatible(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &AnthropicMessagesRequest,
+    openai: OpenAICustomProvider,
+) -> Response {
+    let mut openai_request = convert_anthropic_to_openai(request);
+    ReasoningHandler::strip_unsupported_effort(&mut openai_request);
+    match openai.call_api(&openai_request).await {
+        Ok(resp) => {
+            let status = resp.status();
+            if status.is_success() {
+                match resp.text().await {
+                    Ok(body) => {
+                        // 记录原始响应以便调试
+                        let redacted = state.logs.read().await.redact(&body);
+                        eprintln!(
+                            "[PROVIDER_CALL] OpenAI 响应: {}",
+                            redacted.chars().take(500).collect::<String>()
+                        );
+
+                        if let Ok(openai_resp) = serde_json::from_str::<serde_json::Value>(&body) {
+                            let content = openai_resp["choices"][0]["message"]["content"]
+                                .as_str()
+                                .unwrap_or("");
+                            let thinking = openai_resp["choices"][0]["message"]
+                                ["reasoning_content"]
+                                .as_str()
+                                .unwrap_or("");
+                            let parsed = CWParsedResponse {
+                                content: content.to_string(),
+                                thinking: thinking.to_string(),
+                                tool_calls: Vec::new(),
+                                usage_credits: 0.0,
+                                context_usage_percentage: 0.0,
+                            };
+                            // 记录成功
+                            if let Some(db) = &state.db {
+                                let _ = state.pool_service.mark_healthy(
+                                    db,
+                                    &credential.uuid,
+                                    Some(&request.model),
+                                );
+                                let _ = state.pool_service.record_usage(db, &credential.uuid);
+                            }
+                            if request.stream {
+                                build_anthropic_stream_response(&request.model, &parsed)
+                            } else {
+                                build_anthropic_response(&request.model, &parsed)
+                            }
+                        } else {
+                            // 记录解析失败和原始响应
+                            eprintln!(
+                                "[PROVIDER_CALL] 解析 OpenAI 响应失败，原始响应: {}",
+                                state.logs.read().await.redact(&body)
+                            );
+                            if let Some(db) = &state.db {
+                                let _ = state.pool_service.mark_unhealthy(
+                                    db,
+                                    &credential.uuid,
+                                    Some("Failed to parse OpenAI response"),
+                                );
+                            }
+                            (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                Json(serde_json::json!({"error": {"message": format!("Failed to parse OpenAI response. Body: {}", body.chars().take(200).collect::<String>())}})),
+                            )
+                                .into_response()
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(db) = &state.db {
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&e.to_string()),
+                            );
+                        }
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                        )
+                            .into_response()
+                    }
+                }
+            } else {
+                let status_code = status.as_u16();
+                let body = resp.text().await.unwrap_or_default();
+                let redacted = state.logs.read().await.redact(&body);
+                eprintln!(
+                    "[PROVIDER_CALL] OpenAI 请求失败: status={} body={}",
+                    status_code,
+                    redacted.chars().take(500).collect::<String>()
+                );
+                // 只有 5xx 错误才标记为不健康，4xx 错误（如模型不支持）不应该标记凭证为不健康
+                if status_code >= 500 {
+                    if let Some(db) = &state.db {
+                        let _ =
+                            state
+                                .pool_service
+                                .mark_unhealthy(db, &credential.uuid, Some(&body));
+                    }
+                }
+                // 转发上游的实际状态码
+                (
+                    StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                    Json(serde_json::json!({"error": {"message": body}})),
+                )
+                    .into_response()
+            }
+        }
+        Err(e) => {
+            if let Some(db) = &state.db {
+                let _ =
+                    state
+                        .pool_service
+                        .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+            }
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+            )
+                .into_response()
+        }
+    }
+}
+
 /// 根据凭证调用 Provider (OpenAI 格式)
 ///
 /// # 参数
@@ -909,11 +1063,27 @@ pub async fn call_provider_anthropic(
 /// - `credential`: 凭证信息
 /// - `request`: OpenAI 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+/// - `cancel_token`: 取消令牌（客户端断开时触发，中止上游调用）
 pub async fn call_provider_openai(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+    cancel_token: &CancellationToken,
+) -> Response {
+    with_cancellation(
+        cancel_token,
+        dispatch_openai(state, credential, request, flow_id, cancel_token),
+    )
+    .await
+}
+
+async fn dispatch_openai(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
     _flow_id: Option<&str>,
+    cancel_token: &CancellationToken,
 ) -> Response {
     let _start_time = std::time::Instant::now();
 
@@ -922,6 +1092,7 @@ pub async fn call_provider_openai(
         CredentialData::KiroOAuth { .. } => "KiroOAuth",
         CredentialData::ClaudeKey { .. } => "ClaudeKey",
         CredentialData::OpenAIKey { .. } => "OpenAIKey",
+        CredentialData::LocalOpenAI { .. } => "LocalOpenAI",
         CredentialData::GeminiOAuth { .. } => "GeminiOAuth",
         CredentialData::GeminiApiKey { .. } => "GeminiApiKey",
         CredentialData::VertexKey { .. } => "VertexKey",
@@ -942,11 +1113,7 @@ pub async fn call_provider_openai(
             let db = match &state.db {
                 Some(db) => db,
                 None => {
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": "Database not available"}})),
-                    )
-                        .into_response();
+                    return local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database not available");
                 }
             };
 
@@ -960,37 +1127,15 @@ pub async fn call_provider_openai(
                 Err(e) => {
                     tracing::warn!("[POOL] Token cache miss, loading from source: {}", e);
                     // 降级：从源文件加载并刷新
-                    let mut kiro = KiroProvider::new();
-                    if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&format!("Failed to load credentials: {e}")),
-                        );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                        )
-                            .into_response();
-                    }
-                    if let Err(e) = kiro.refresh_token().await {
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&format!("Token refresh failed: {e}")),
-                        );
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
-                        )
-                            .into_response();
+                    match load_kiro_from_source(state, &credential.uuid, creds_file_path).await {
+                        Ok(kiro) => kiro.credentials.access_token.unwrap_or_default(),
+                        Err(e) => return e.into_response(),
                     }
-                    kiro.credentials.access_token.unwrap_or_default()
                 }
             };
 
             // 使用获取到的 token 创建 KiroProvider
-            let mut kiro = KiroProvider::new();
+            let mut kiro = KiroProvider::with_client(state.provider_clients.get(ProviderType::Kiro));
             // 从源文件加载其他配置（region, profile_arn 等）
             // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
             let _ = kiro.load_credentials_from_path(creds_file_path).await;
@@ -1014,7 +1159,8 @@ pub async fn call_provider_openai(
                         tracing::info!("[OPENAI_STREAM] 开始转换流式响应");
 
                         // 使用新的统一流处理管道 (Kiro → OpenAI)
-                        let config = PipelineConfig::kiro_to_openai(request.model.clone());
+                        let config = PipelineConfig::kiro_to_openai(request.model.clone())
+                            .with_coalesce_tool_calls(state.coalesce_tool_calls);
                         let pipeline = std::sync::Arc::new(tokio::sync::Mutex::new(
                             StreamPipeline::new(config),
                         ));
@@ -1094,13 +1240,7 @@ pub async fn call_provider_openai(
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(body_stream))
                             .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
-                                )
-                                    .into_response()
+                                local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build streaming response")
                             });
                     }
                     Err(e) => {
@@ -1127,9 +1267,12 @@ pub async fn call_provider_openai(
                             let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
-                        match resp.text().await {
+                        match resp.bytes().await {
                             Ok(body) => {
-                                let parsed = parse_cw_response(&body);
+                                let parsed = match parse_cw_response_bytes(&body).or_else(CWParseError::recover) {
+                                    Ok(parsed) => parsed,
+                                    Err(e) => return cw_parse_error_response(&e),
+                                };
                                 let has_tool_calls = !parsed.tool_calls.is_empty();
                                 let message = if has_tool_calls {
                                     serde_json::json!({
@@ -1206,11 +1349,7 @@ pub async fn call_provider_openai(
             }
         }
         CredentialData::GeminiOAuth { .. } => {
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Gemini OAuth routing not yet implemented."}})),
-            )
-                .into_response()
+            local_error_response(StatusCode::NOT_IMPLEMENTED, "Gemini OAuth routing not yet implemented.")
         }
         CredentialData::AntigravityOAuth { creds_file_path, project_id } => {
             eprintln!("\n========== [ANTIGRAVITY] 开始处理 Antigravity 请求 ==========");
@@ -1219,7 +1358,8 @@ pub async fn call_provider_openai(
             eprintln!("[ANTIGRAVITY] 模型: {}", request.model);
             eprintln!("[ANTIGRAVITY] 流式: {}", request.stream);
 
-            let mut antigravity = AntigravityProvider::new();
+            let mut antigravity =
+                AntigravityProvider::with_client(state.provider_clients.get(ProviderType::Antigravity));
             if let Err(e) = antigravity.load_credentials_from_path(creds_file_path).await {
                 eprintln!("[ANTIGRAVITY] 加载凭证失败: {e}");
                 // 记录凭证加载失败
@@ -1230,11 +1370,7 @@ pub async fn call_provider_openai(
                         Some(&format!("Failed to load credentials: {e}")),
                     );
                 }
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Antigravity credentials: {}", e)}})),
-                )
-                    .into_response();
+                return local_error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("Failed to load Antigravity credentials: {}", e));
             }
             eprintln!("[ANTIGRAVITY] 凭证加载成功");
 
@@ -1291,12 +1427,8 @@ pub async fn call_provider_openai(
                 eprintln!("[ANTIGRAVITY] Token 不需要刷新，继续使用现有 Token");
             }
 
-            // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            } else if let Err(e) = antigravity.discover_project().await {
-                tracing::warn!("[Antigravity] Failed to discover project: {}", e);
-            }
+            // 设置项目 ID（已配置 -> 发现 -> 生成）
+            antigravity.ensure_project_id(project_id.clone()).await;
 
             tracing::info!("[ANTIGRAVITY] request.stream = {}, model = {}, project_id = {:?}",
                 request.stream, request.model, antigravity.project_id);
@@ -1327,8 +1459,12 @@ pub async fn call_provider_openai(
                     // 直接调用 call_api，因为 antigravity_request 已经是完整格式
                     match antigravity.call_api("generateContent", &antigravity_request).await {
                         Ok(resp) => {
-                            // 保存原始响应到文件用于调试
-                            let resp_str = serde_json::to_string_pretty(&resp).unwrap_or_default();
+                            // 保存原始响应到文件用于调试（写入前按日志脱敏规则处理）
+                            let resp_str = state
+                                .logs
+                                .read()
+                                .await
+                                .redact(&serde_json::to_string_pretty(&resp).unwrap_or_default());
                             let debug_dir = dirs::home_dir()
                                 .map(|h| h.join(".proxycast/logs"))
                                 .unwrap_or_else(|| std::path::PathBuf::from("/tmp"));
@@ -1344,7 +1480,11 @@ pub async fn call_provider_openai(
                             let openai_response = convert_antigravity_to_openai_response(&resp, &request.model);
 
                             // 保存转换后的响应到文件
-                            let openai_str = serde_json::to_string_pretty(&openai_response).unwrap_or_default();
+                            let openai_str = state
+                                .logs
+                                .read()
+                                .await
+                                .redact(&serde_json::to_string_pretty(&openai_response).unwrap_or_default());
                             let openai_debug_file = debug_dir.join("antigravity_image_openai_response.json");
                             let _ = std::fs::write(&openai_debug_file, &openai_str);
                             tracing::info!("[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes", openai_debug_file, openai_str.len());
@@ -1411,11 +1551,7 @@ pub async fn call_provider_openai(
                                 .header(header::CONNECTION, "keep-alive")
                                 .body(Body::from(sse_events))
                                 .unwrap_or_else(|_| {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build streaming response"}})),
-                                    )
-                                        .into_response()
+                                    local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build streaming response")
                                 });
                         }
                         Err(api_err) => {
@@ -1435,22 +1571,38 @@ pub async fn call_provider_openai(
 
                         // Antigravity 返回的是分片的 JSON，需要累积所有数据后解析
                         // 使用 channel 来收集所有数据，然后一次性返回
-                        let (tx, rx) = tokio::sync::oneshot::channel::<Result<String, String>>();
+                        let (mut tx, rx) = tokio::sync::oneshot::channel::<Result<String, String>>();
 
                         // 在后台任务中收集所有数据
+                        // 请求取消或响应体（持有 rx）被丢弃时停止读取并释放上游连接
                         let model_clone = model.clone();
+                        let cancel_token = cancel_token.clone();
                         tokio::spawn(async move {
                             use futures::StreamExt;
                             let mut stream = stream_response;
-                            let mut all_data = String::new();
+                            let mut all_data: Vec<u8> = Vec::new();
                             let mut chunk_count = 0u32;
 
-                            while let Some(result) = stream.next().await {
+                            loop {
+                                let result = tokio::select! {
+                                    _ = cancel_token.cancelled() => {
+                                        tracing::info!("[ANTIGRAVITY_STREAM] 请求已取消，中止上游请求");
+                                        return;
+                                    }
+                                    _ = tx.closed() => {
+                                        tracing::info!("[ANTIGRAVITY_STREAM] 客户端断开，中止上游请求");
+                                        return;
+                                    }
+                                    next = stream.next() => match next {
+                                        Some(result) => result,
+                                        None => break,
+                                    },
+                                };
                                 chunk_count += 1;
                                 match result {
                                     Ok(bytes) => {
-                                        let text = String::from_utf8_lossy(&bytes);
-                                        all_data.push_str(&text);
+                                        // 按字节累积，避免多字节字符跨 chunk 时被有损替换
+                                        all_data.extend_from_slice(&bytes);
 
                                         if chunk_count <= 3 {
                                             eprintln!("[ANTIGRAVITY_STREAM] 收集 chunk #{}: {} bytes", chunk_count, bytes.len());
@@ -1470,7 +1622,9 @@ pub async fn call_provider_openai(
 
                             // 尝试解析累积的 JSON 数据
                             // Antigravity 返回格式: { "response": { "candidates": [...] } }
-                            let result = parse_antigravity_accumulated_response(&all_data, &model_clone);
+                            let result = String::from_utf8(all_data)
+                                .map_err(|e| format!("Antigravity 响应不是合法 UTF-8: {e}"))
+                                .and_then(|text| parse_antigravity_accumulated_response(&text, &model_clone));
                             let _ = tx.send(result);
                         });
 
@@ -1505,13 +1659,7 @@ pub async fn call_provider_openai(
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(sse_stream))
                             .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
-                                )
-                                    .into_response()
+                                local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build streaming response")
                             });
                     }
                     Err(provider_err) => {
@@ -1551,91 +1699,22 @@ pub async fn call_provider_openai(
                 }
             }
         }
+        // OpenAI API Key 与本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM）
         CredentialData::OpenAIKey { api_key, base_url } => {
-            let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
-
-            tracing::info!("[OPENAI_KEY] request.stream = {}, model = {}", request.stream, request.model);
-
-            // 检查是否为流式请求
-            if request.stream {
-                tracing::info!("[OPENAI_KEY_STREAM] 处理流式请求, model={}", request.model);
-                match openai.call_api_stream(request).await {
-                    Ok(stream_response) => {
-                        tracing::info!("[OPENAI_KEY_STREAM] 开始直接转发 OpenAI SSE 流");
-
-                        // OpenAI 提供商已经返回 OpenAI SSE 格式，直接转发
-                        let body_stream = stream_response.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-                            match result {
-                                Ok(bytes) => Ok(bytes),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-                            }
-                        });
-
-                        return Response::builder()
-                            .status(StatusCode::OK)
-                            .header(header::CONTENT_TYPE, "text/event-stream")
-                            .header(header::CACHE_CONTROL, "no-cache")
-                            .header(header::CONNECTION, "keep-alive")
-                            .header(header::TRANSFER_ENCODING, "chunked")
-                            .header("X-Accel-Buffering", "no")
-                            .body(Body::from_stream(body_stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
-                                )
-                                    .into_response()
-                            });
-                    }
-                    Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response();
-                    }
-                }
-            }
-
-            // 非流式请求处理
-            match openai.call_api(request).await {
-                Ok(resp) => {
-                    if resp.status().is_success() {
-                        match resp.text().await {
-                            Ok(body) => {
-                                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
-                                    Json(json).into_response()
-                                } else {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Invalid JSON response"}})),
-                                    )
-                                        .into_response()
-                                }
-                            }
-                            Err(e) => (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                            )
-                                .into_response(),
-                        }
-                    } else {
-                        let body = resp.text().await.unwrap_or_default();
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
-                            .into_response()
-                    }
-                }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                )
-                    .into_response(),
-            }
+            let openai = OpenAICustomProvider::with_client(
+                api_key.clone(),
+                base_url.clone(),
+                state.provider_clients.get(ProviderType::OpenAI),
+            );
+            dispatch_openai_compatible(request, openai).await
+        }
+        CredentialData::LocalOpenAI { base_url, api_key } => {
+            let openai = OpenAICustomProvider::with_local_server(
+                base_url.clone(),
+                api_key.clone(),
+                state.provider_clients.get(ProviderType::OpenAI),
+            );
+            dispatch_openai_compatible(request, openai).await
         }
         CredentialData::ClaudeKey { api_key, base_url } => {
             // 打印 Claude 代理 URL 用于调试
@@ -1646,7 +1725,7 @@ pub async fn call_provider_openai(
                 &credential.uuid[..8],
                 request.stream
             );
-            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let claude = ClaudeCustomProvider::with_client(api_key.clone(), base_url.clone(), state.provider_clients.get(ProviderType::Claude));
 
             // 检查是否为流式请求
             if request.stream {
@@ -1719,13 +1798,7 @@ pub async fn call_provider_openai(
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(body_stream))
                             .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
-                                )
-                                    .into_response()
+                                local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build streaming response")
                             });
                     }
                     Err(e) => {
@@ -1753,18 +1826,14 @@ pub async fn call_provider_openai(
             let resolved_model = model_aliases.get(&request.model).cloned().unwrap_or_else(|| request.model.clone());
             let mut modified_request = request.clone();
             modified_request.model = resolved_model;
-            let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
+            let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone())
+                .with_client(state.provider_clients.get(ProviderType::Vertex));
             match vertex.chat_completions(&serde_json::to_value(&modified_request).unwrap_or_default()).await {
                 Ok(resp) => {
                     if resp.status().is_success() {
-                        match resp.text().await {
-                            Ok(body) => {
-                                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
-                                    Json(json).into_response()
-                                } else {
-                                    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": "Invalid JSON response"}}))).into_response()
-                                }
-                            }
+                        let content_type = upstream_content_type(&resp);
+                        match resp.bytes().await {
+                            Ok(body) => json_or_passthrough(StatusCode::OK, content_type.as_deref(), body),
                             Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response(),
                         }
                     } else {
@@ -1787,7 +1856,7 @@ pub async fn call_provider_openai(
         CredentialData::AnthropicKey { api_key, base_url } => {
             // 如果有自定义 base_url，假设是 OpenAI 兼容的代理服务器
             if let Some(custom_url) = base_url {
-                let openai = OpenAICustomProvider::with_config(api_key.clone(), Some(custom_url.clone()));
+                let openai = OpenAICustomProvider::with_client(api_key.clone(), Some(custom_url.clone()), state.provider_clients.get(ProviderType::Anthropic));
                 state.logs.write().await.add(
                     "info",
                     &format!(
@@ -1833,11 +1902,7 @@ pub async fn call_provider_openai(
                                 .header("Transfer-Encoding", "chunked")
                                 .body(Body::from_stream(stream))
                                 .unwrap_or_else(|_| {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
-                                    )
-                                        .into_response()
+                                    local_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build stream response")
                                 });
                         }
 
@@ -1859,18 +1924,10 @@ pub async fn call_provider_openai(
                             );
                         }
 
+                        let content_type = upstream_content_type(&resp)
+                            .unwrap_or_else(|| "application/json".to_string());
                         match resp.bytes().await {
-                            Ok(body) => Response::builder()
-                                .status(status)
-                                .header(header::CONTENT_TYPE, "application/json")
-                                .body(Body::from(body))
-                                .unwrap_or_else(|_| {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
-                                    )
-                                        .into_response()
-                                }),
+                            Ok(body) => passthrough_response(status, Some(&content_type), body),
                             Err(e) => (
                                 StatusCode::INTERNAL_SERVER_ERROR,
                                 Json(serde_json::json!({"error": {"message": format!("Failed to read response: {}", e)}})),
@@ -1908,19 +1965,16 @@ pub async fn call_provider_openai(
             api_base_url,
         } => {
             // 加载 Codex 凭证
-            let mut codex = CodexProvider::new();
+            let mut codex = CodexProvider::with_client(state.provider_clients.get(ProviderType::Codex));
             if let Err(e) = codex.load_credentials_from_path(creds_file_path).await {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Codex credentials: {}", e)}})),
-                )
-                    .into_response();
+                return local_error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("Failed to load Codex credentials: {}", e));
             }
 
-            // 如果配置了自定义 API Base URL，覆盖凭证文件中的配置
+            // 如果配置了自定义 API Base URL（或本次请求覆盖了上游地址），只用于本次请求的 URL，
+            // 不写入凭证（刷新 Token 时凭证会被保存回文件）
             if let Some(base_url) = api_base_url {
                 if !base_url.trim().is_empty() {
-                    codex.credentials.api_base_url = Some(base_url.clone());
+                    codex.request_base_url = Some(base_url.clone());
                 }
             }
 
@@ -1963,12 +2017,13 @@ pub async fn call_provider_openai(
                         // 创建转换状态（包含缓冲区）
                         struct StreamState {
                             convert_state: CodexConvertState,
-                            buffer: String,
+                            // 按字节缓冲，只解码完整行，避免多字节字符跨 chunk 被截断
+                            buffer: Vec<u8>,
                         }
 
                         let state = Arc::new(Mutex::new(StreamState {
                             convert_state: CodexConvertState::default(),
-                            buffer: String::new(),
+                            buffer: Vec::new(),
                         }));
 
                         let converted_stream = bytes_stream.map(move |result| {
@@ -1976,16 +2031,18 @@ pub async fn call_provider_openai(
                             async move {
                                 match result {
                                     Ok(bytes) => {
-                                        let chunk = String::from_utf8_lossy(&bytes);
                                         let mut state = state.lock().await;
-                                        state.buffer.push_str(&chunk);
+                                        state.buffer.extend_from_slice(&bytes);
 
                                         let mut output = String::new();
 
                                         // 处理缓冲区中的完整行
-                                        while let Some(newline_pos) = state.buffer.find('\n') {
-                                            let line = state.buffer[..newline_pos].to_string();
-                                            state.buffer = state.buffer[newline_pos + 1..].to_string();
+                                        while let Some(newline_pos) = state.buffer.iter().position(|b| *b == b'\n') {
+                                            let line_bytes: Vec<u8> = state.buffer.drain(..=newline_pos).collect();
+                                            let Ok(line) = std::str::from_utf8(&line_bytes[..newline_pos]) else {
+                                                tracing::warn!("[Codex] 跳过非 UTF-8 的 SSE 行（{} 字节）", newline_pos);
+                                                continue;
+                                            };
 
                                             if let Some(data) = line.strip_prefix("data: ") {
                                                 if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
@@ -2041,10 +2098,11 @@ pub async fn call_provider_openai(
                         match response.bytes().await {
                             Ok(body) => {
                                 // 解析 SSE 数据，查找 response.completed 事件
-                                let body_str = String::from_utf8_lossy(&body);
                                 let mut completed_data: Option<serde_json::Value> = None;
 
-                                for line in body_str.lines() {
+                                // 逐行解码，非 UTF-8 的行直接跳过而不是有损替换
+                                for line in body.split(|b| *b == b'\n').filter_map(|l| std::str::from_utf8(l).ok()) {
+                                    let line = line.trim_end_matches('\r');
                                     if let Some(data) = line.strip_prefix("data: ") {
                                         if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
                                             if json.get("type").and_then(|t| t.as_str()) == Some("response.completed") {
@@ -2110,6 +2168,88 @@ pub async fn call_provider_openai(
     }
 }
 
+/// 通过 OpenAI 兼容凭证（OpenAI API Key、本地服务）处理 OpenAI 格式请求
+async fn dispatch_openai_compatible(
+    request: &ChatCompletionRequest,
+    openai: OpenAICustomProvider,
+) -> Response {
+    tracing::info!(
+        "[OPENAI_KEY] request.stream = {}, model = {}",
+        request.stream,
+        request.model
+    );
+
+    // 检查是否为流式请求
+    if request.stream {
+        tracing::info!("[OPENAI_KEY_STREAM] 处理流式请求, model={}", request.model);
+        match openai.call_api_stream(request).await {
+            Ok(stream_response) => {
+                tracing::info!("[OPENAI_KEY_STREAM] 开始直接转发 OpenAI SSE 流");
+
+                // OpenAI 提供商已经返回 OpenAI SSE 格式，直接转发
+                let body_stream =
+                    stream_response.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
+                        match result {
+                            Ok(bytes) => Ok(bytes),
+                            Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                        }
+                    });
+
+                return Response::builder()
+                    .status(StatusCode::OK)
+                    .header(header::CONTENT_TYPE, "text/event-stream")
+                    .header(header::CACHE_CONTROL, "no-cache")
+                    .header(header::CONNECTION, "keep-alive")
+                    .header(header::TRANSFER_ENCODING, "chunked")
+                    .header("X-Accel-Buffering", "no")
+                    .body(Body::from_stream(body_stream))
+                    .unwrap_or_else(|_| {
+                        local_error_response(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "Failed to build streaming response",
+                        )
+                    });
+            }
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    // 非流式请求处理
+    match openai.call_api(request).await {
+        Ok(resp) => {
+            if resp.status().is_success() {
+                let content_type = upstream_content_type(&resp);
+                match resp.bytes().await {
+                    Ok(body) => json_or_passthrough(StatusCode::OK, content_type.as_deref(), body),
+                    Err(e) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                    )
+                        .into_response(),
+                }
+            } else {
+                let body = resp.text().await.unwrap_or_default();
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": {"message": body}})),
+                )
+                    .into_response()
+            }
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": {"message": e.to_string()}})),
+        )
+            .into_response(),
+    }
+}
+
 // ============================================================================
 // 流式传输支持
 // ============================================================================
@@ -2128,6 +2268,7 @@ pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> Stre
         CredentialData::KiroOAuth { .. } => StreamingFormat::AwsEventStream,
         CredentialData::ClaudeKey { .. } => StreamingFormat::AnthropicSse,
         CredentialData::OpenAIKey { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::LocalOpenAI { .. } => StreamingFormat::OpenAiSse,
         // TODO: 任务 6 完成后，将这些改为 GeminiStream
         CredentialData::AntigravityOAuth { .. } => StreamingFormat::OpenAiSse,
         CredentialData::GeminiOAuth { .. } => StreamingFormat::OpenAiSse,
@@ -2156,7 +2297,7 @@ pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> Stre
 /// - 需求 4.2: 调用 process_chunk 更新流重建器
 /// - 需求 5.1: 在收到 chunk 后立即转发给客户端
 pub async fn handle_streaming_response(
-    _state: &AppState,
+    state: &AppState,
     flow_id: Option<&str>,
     source_stream: StreamResponse,
     source_format: StreamingFormat,
@@ -2164,7 +2305,8 @@ pub async fn handle_streaming_response(
     model: &str,
 ) -> Response {
     // 创建流式管理器
-    let manager = StreamManager::with_default_config();
+    let manager =
+        StreamManager::new(StreamConfig::new().with_coalesce_tool_calls(state.coalesce_tool_calls));
 
     // 创建流式上下文
     let context = StreamContext::new(
@@ -2199,13 +2341,10 @@ pub async fn handle_streaming_response(
         .header("X-Accel-Buffering", "no")
         .body(managed_stream)
         .unwrap_or_else(|_| {
-            (
+            local_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
         })
 }
 
@@ -2229,7 +2368,7 @@ pub async fn handle_streaming_response(
 /// - 需求 6.2: 超时错误处理
 /// - 需求 6.5: 可配置的流式响应超时
 pub async fn handle_streaming_response_with_timeout(
-    _state: &AppState,
+    state: &AppState,
     flow_id: Option<&str>,
     source_stream: StreamResponse,
     source_format: StreamingFormat,
@@ -2242,7 +2381,8 @@ pub async fn handle_streaming_response_with_timeout(
     // 创建带超时配置的流式管理器
     let config = StreamConfig::new()
         .with_timeout_ms(timeout_ms)
-        .with_chunk_timeout_ms(30_000); // 30 秒 chunk 超时
+        .with_chunk_timeout_ms(30_000) // 30 秒 chunk 超时
+        .with_coalesce_tool_calls(state.coalesce_tool_calls);
 
     let manager = StreamManager::new(config.clone());
 
@@ -2284,13 +2424,10 @@ pub async fn handle_streaming_response_with_timeout(
         .header("X-Accel-Buffering", "no")
         .body(Body::from_stream(body_stream))
         .unwrap_or_else(|_| {
-            (
+            local_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
         })
 }
 
@@ -2307,6 +2444,15 @@ pub fn response_to_stream(response: reqwest::Response) -> StreamResponse {
     proxycast_providers::streaming::reqwest_stream_to_stream_response(response)
 }
 
+/// 读取上游响应的 `Content-Type`
+fn upstream_content_type(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
 // ============================================================================
 // 客户端断开检测
 // ============================================================================
@@ -2333,7 +2479,7 @@ pub fn response_to_stream(response: reqwest::Response) -> StreamResponse {
 /// # 需求覆盖
 /// - 需求 5.4: 客户端断开时取消上游请求
 pub async fn handle_streaming_with_disconnect_detection(
-    _state: &AppState,
+    state: &AppState,
     flow_id: Option<&str>,
     source_stream: StreamResponse,
     source_format: StreamingFormat,
@@ -2344,7 +2490,8 @@ pub async fn handle_streaming_with_disconnect_detection(
     use futures::StreamExt;
 
     // 创建流式管理器
-    let manager = StreamManager::with_default_config();
+    let manager =
+        StreamManager::new(StreamConfig::new().with_coalesce_tool_calls(state.coalesce_tool_calls));
 
     // 创建流式上下文
     let context = StreamContext::new(
@@ -2414,13 +2561,10 @@ pub async fn handle_streaming_with_disconnect_detection(
         .header("X-Accel-Buffering", "no")
         .body(body_stream)
         .unwrap_or_else(|_| {
-            (
+            local_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
         })
 }
 
@@ -2543,11 +2687,10 @@ pub async fn handle_kiro_stream(
         CredentialData::KiroOAuth { creds_file_path } => creds_file_path.clone(),
         _ => {
             tracing::error!("[KIRO_STREAM] 无效的凭证类型");
-            return (
+            return local_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": "Invalid credential type for Kiro stream"}})),
-            )
-                .into_response();
+                "Invalid credential type for Kiro stream",
+            );
         }
     };
 
@@ -2558,11 +2701,10 @@ pub async fn handle_kiro_stream(
         Some(db) => db,
         None => {
             tracing::error!("[KIRO_STREAM] 数据库不可用");
-            return (
+            return local_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": "Database not available"}})),
-            )
-                .into_response();
+                "Database not available",
+            );
         }
     };
 
@@ -2579,37 +2721,15 @@ pub async fn handle_kiro_stream(
                 e
             );
             // 回退到从源文件加载
-            let mut kiro = KiroProvider::new();
-            if let Err(e) = kiro.load_credentials_from_path(&creds_file_path).await {
-                let _ = state.pool_service.mark_unhealthy(
-                    db,
-                    &credential.uuid,
-                    Some(&format!("Failed to load credentials: {e}")),
-                );
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                )
-                    .into_response();
+            match load_kiro_from_source(state, &credential.uuid, &creds_file_path).await {
+                Ok(kiro) => kiro.credentials.access_token.unwrap_or_default(),
+                Err(e) => return e.into_response(),
             }
-            if let Err(e) = kiro.refresh_token().await {
-                let _ = state.pool_service.mark_unhealthy(
-                    db,
-                    &credential.uuid,
-                    Some(&format!("Token refresh failed: {e}")),
-                );
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
-                )
-                    .into_response();
-            }
-            kiro.credentials.access_token.unwrap_or_default()
         }
     };
 
     // 创建 KiroProvider 并设置 token
-    let mut kiro = KiroProvider::new();
+    let mut kiro = KiroProvider::with_client(state.provider_clients.get(ProviderType::Kiro));
     // 从源文件加载其他配置（region, profile_arn 等）
     // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
     let _ = kiro.load_credentials_from_path(&creds_file_path).await;
@@ -2720,7 +2840,8 @@ pub async fn handle_kiro_stream(
     );
 
     // 使用新的统一流处理管道 (Kiro → Anthropic)
-    let config = PipelineConfig::kiro_to_anthropic(request.model.clone());
+    let config = PipelineConfig::kiro_to_anthropic(request.model.clone())
+        .with_extract_thinking(request.thinking_budget().is_some());
     let pipeline = std::sync::Arc::new(tokio::sync::Mutex::new(StreamPipeline::new(config)));
 
     let pipeline_clone = pipeline.clone();
@@ -2795,13 +2916,10 @@ pub async fn handle_kiro_stream(
         .header("X-Accel-Buffering", "no")
         .body(Body::from_stream(body_stream))
         .unwrap_or_else(|_| {
-            (
+            local_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
         })
 }
 
@@ -3164,13 +3282,7 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
     let finish_reason = candidate
         .get("finishReason")
         .and_then(|f| f.as_str())
-        .map(|r| match r {
-            "STOP" => "stop",
-            "MAX_TOKENS" => "length",
-            "SAFETY" => "content_filter",
-            "RECITATION" => "content_filter",
-            _ => "stop",
-        });
+        .map(|r| StopReason::from_gemini(r).to_openai_str());
 
     // 如果没有内容变化且没有 finish_reason，跳过
     if content_delta.is_none() && !has_image && finish_reason.is_none() {
@@ -3257,12 +3369,7 @@ fn convert_openai_response_to_anthropic(
     let stop_reason = openai_resp
         .choices
         .first()
-        .map(|c| match c.finish_reason.as_str() {
-            "stop" => "end_turn",
-            "length" => "max_tokens",
-            "tool_calls" => "tool_use",
-            _ => "end_turn",
-        })
+        .map(|c| StopReason::from_openai(&c.finish_reason).to_anthropic_str())
         .unwrap_or("end_turn");
 
     // 构建 Anthropic 响应
@@ -3596,3 +3703,93 @@ fn convert_codex_event_to_openai_sse(
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancellation_aborts_pending_upstream_call() {
+        let cancel_token = CancellationToken::new();
+        let trigger = cancel_token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            trigger.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let response = with_cancellation(&cancel_token, async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            StatusCode::OK.into_response()
+        })
+        .await;
+        assert_eq!(response.status().as_u16(), 499);
+        assert!(proxycast_server_utils::is_local_error(&response));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    /// 写入 Token 尚未过期的 Kiro 凭证文件（无 refresh_token，刷新必然失败）
+    fn write_valid_kiro_creds(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "proxycast-kiro-{}-{}.json",
+            name,
+            uuid::Uuid::new_v4()
+        ));
+        let expire = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        let creds = serde_json::json!({
+            "accessToken": "cached-token",
+            "clientId": "client",
+            "clientSecret": "secret",
+            "expire": expire,
+            "region": "us-east-1",
+        });
+        std::fs::write(&path, creds.to_string()).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_source_refresh_skipped_when_token_still_valid() {
+        let path = write_valid_kiro_creds("valid");
+        let locks = std::sync::Arc::new(RefreshLocks::new());
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let (locks, path) = (locks.clone(), path.to_string_lossy().to_string());
+                tokio::spawn(async move {
+                    refresh_kiro_from_source(&locks, "cred-a", &path)
+                        .await
+                        .map(|kiro| kiro.credentials.access_token)
+                })
+            })
+            .collect();
+        for task in tasks {
+            let token = task.await.unwrap().expect("Token 未过期时不应刷新");
+            assert_eq!(token.as_deref(), Some("cached-token"));
+        }
+
+        // 返回前已释放刷新锁
+        tokio::time::timeout(Duration::from_millis(100), locks.lock("cred-a"))
+            .await
+            .expect("刷新锁应在加载完成后释放");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_source_load_failure_reported() {
+        let locks = RefreshLocks::new();
+        let missing =
+            std::env::temp_dir().join(format!("proxycast-kiro-{}.json", uuid::Uuid::new_v4()));
+        let result = refresh_kiro_from_source(&locks, "cred-a", &missing.to_string_lossy()).await;
+        let response = result.err().expect("凭证文件不存在").into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_completed_call_returns_upstream_response() {
+        let cancel_token = CancellationToken::new();
+        let response =
+            with_cancellation(&cancel_token, async { StatusCode::OK.into_response() }).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -14,23 +14,28 @@ use axum::{
 use futures::{SinkExt, StreamExt as FuturesStreamExt};
 use serde::Deserialize;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 
+use crate::handlers::provider_calls::load_kiro_from_source;
 use crate::AppState;
+use proxycast_core::config::{ApiKeyMatch, ServerApiKeys};
 use proxycast_core::models::anthropic::AnthropicMessagesRequest;
 use proxycast_core::models::openai::ChatCompletionRequest;
 use proxycast_core::models::provider_pool_model::ProviderCredential;
+use proxycast_core::models::route_model::RequestRoute;
+use proxycast_core::ProviderType;
 use proxycast_processor::RequestContext;
 use proxycast_providers::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use proxycast_providers::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
+use proxycast_providers::converter::reasoning_handler::ReasoningHandler;
 use proxycast_providers::providers::{
-    AntigravityProvider, ClaudeCustomProvider, KiroProvider, OpenAICustomProvider,
+    AntigravityProvider, ClaudeCustomProvider, OpenAICustomProvider,
 };
-use proxycast_server_utils::parse_cw_response;
+use proxycast_server_utils::{parse_cw_response_bytes, CWParseError};
 use proxycast_websocket::{
-    WsApiRequest, WsApiResponse, WsEndpoint, WsError, WsMessage as WsProtoMessage,
+    api_key_identity, WsApiRequest, WsApiResponse, WsEndpoint, WsError, WsMessage as WsProtoMessage,
 };
 
 /// WebSocket 查询参数
@@ -40,16 +45,44 @@ pub struct WsQueryParams {
     pub api_key: Option<String>,
     /// Token（通过 URL 参数传递，与 api_key 等效）
     pub token: Option<String>,
+    /// 认证方式：`frame` 表示升级后通过第一条 `auth` 消息认证
+    pub auth: Option<String>,
 }
 
-/// WebSocket 升级处理器
-pub async fn ws_upgrade_handler(
-    ws: WebSocketUpgrade,
-    State(state): State<AppState>,
-    Query(params): Query<WsQueryParams>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    // 验证 API 密钥：优先从 header 获取，其次从 URL 参数获取
+/// 等待认证消息的超时时间
+const AUTH_FRAME_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// 升级请求的认证结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsUpgradeAuth {
+    /// 已通过请求头或 URL 参数认证，附带认证身份
+    Authenticated(String),
+    /// 升级后等待第一条 `auth` 消息
+    AwaitingAuthFrame,
+}
+
+/// 按服务器密钥集合校验密钥，成功时返回认证身份（脱敏的密钥，附带密钥标签）
+fn check_ws_key(api_keys: &ServerApiKeys, key: &str) -> Result<String, &'static str> {
+    match api_keys.check(key) {
+        ApiKeyMatch::Accepted { label: Some(label) } => {
+            Ok(format!("{} ({label})", api_key_identity(key)))
+        }
+        ApiKeyMatch::Accepted { label: None } => Ok(api_key_identity(key)),
+        ApiKeyMatch::Disabled => Err("API key has been disabled"),
+        ApiKeyMatch::Invalid => Err("Invalid API key"),
+    }
+}
+
+/// 校验 WebSocket 升级请求的 API 密钥
+///
+/// 依次从 `Authorization`/`x-api-key` 请求头、`api_key`/`token` URL 参数获取密钥，
+/// 与 HTTP 接口一样接受 `server.api_key` 和 `server.api_keys` 中启用的密钥；
+/// 都未提供时仅在 `auth=frame` 下允许升级，否则返回错误信息（401）。
+pub fn authenticate_ws_upgrade(
+    headers: &HeaderMap,
+    params: &WsQueryParams,
+    api_keys: &ServerApiKeys,
+) -> Result<WsUpgradeAuth, &'static str> {
     let auth = headers
         .get("authorization")
         .or_else(|| headers.get("x-api-key"))
@@ -58,28 +91,45 @@ pub async fn ws_upgrade_handler(
     let key = match auth {
         Some(s) if s.starts_with("Bearer ") => Some(&s[7..]),
         Some(s) => Some(s),
-        None => {
-            // 尝试从 URL 参数获取
-            params.api_key.as_deref().or(params.token.as_deref())
-        }
+        None => params.api_key.as_deref().or(params.token.as_deref()),
     };
 
-    // 如果没有提供任何认证信息，允许连接（用于内部 Flow Monitor）
-    // 但会在日志中记录
-    let authenticated = match key {
-        Some(k) if k == state.api_key => true,
-        Some(_) => {
+    match key {
+        Some(k) => check_ws_key(api_keys, k).map(WsUpgradeAuth::Authenticated),
+        None if params.auth.as_deref() == Some("frame") => Ok(WsUpgradeAuth::AwaitingAuthFrame),
+        None => Err("No API key provided"),
+    }
+}
+
+/// 校验连接的第一条消息是否为有效的认证消息，成功时返回认证身份
+pub fn authenticate_ws_frame(text: &str, api_keys: &ServerApiKeys) -> Result<String, WsError> {
+    match serde_json::from_str::<WsProtoMessage>(text) {
+        Ok(WsProtoMessage::Auth { api_key }) => {
+            check_ws_key(api_keys, &api_key).map_err(WsError::unauthorized)
+        }
+        _ => Err(WsError::unauthorized(
+            "The first message must be an auth message",
+        )),
+    }
+}
+
+/// WebSocket 升级处理器
+pub async fn ws_upgrade_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(params): Query<WsQueryParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let auth = match authenticate_ws_upgrade(&headers, &params, &*state.api_keys.read().await) {
+        Ok(auth) => auth,
+        Err(message) => {
+            tracing::warn!("[WS] 拒绝未认证的升级请求: {}", message);
             return axum::http::Response::builder()
                 .status(401)
-                .body(Body::from("Invalid API key"))
+                .body(Body::from(message))
                 .unwrap()
                 .into_response();
         }
-        None => {
-            // 允许无认证连接（仅用于本地 Flow Monitor UI）
-            tracing::debug!("[WS] Allowing unauthenticated connection for Flow Monitor");
-            false
-        }
     };
 
     // 获取客户端信息
@@ -88,23 +138,67 @@ pub async fn ws_upgrade_handler(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    ws.on_upgrade(move |socket| handle_websocket(socket, state, client_info, authenticated))
+    ws.on_upgrade(move |socket| handle_websocket(socket, state, client_info, auth))
+}
+
+/// 等待并校验第一条认证消息
+async fn await_auth_frame(
+    socket: &mut WebSocket,
+    api_keys: &RwLock<ServerApiKeys>,
+) -> Result<String, WsError> {
+    let first = tokio::time::timeout(AUTH_FRAME_TIMEOUT, socket.recv())
+        .await
+        .map_err(|_| WsError::unauthorized("Timed out waiting for auth message"))?;
+    match first {
+        Some(Ok(WsMessage::Text(text))) => authenticate_ws_frame(&text, &*api_keys.read().await),
+        _ => Err(WsError::unauthorized(
+            "The first message must be an auth message",
+        )),
+    }
 }
 
 /// 处理 WebSocket 连接
 pub async fn handle_websocket(
-    socket: WebSocket,
+    mut socket: WebSocket,
     state: AppState,
     client_info: Option<String>,
-    authenticated: bool,
+    auth: WsUpgradeAuth,
 ) {
+    let identity = match auth {
+        WsUpgradeAuth::Authenticated(identity) => identity,
+        WsUpgradeAuth::AwaitingAuthFrame => {
+            match await_auth_frame(&mut socket, &state.api_keys).await {
+                Ok(identity) => {
+                    let ok = WsProtoMessage::Response(WsApiResponse {
+                        request_id: "auth".to_string(),
+                        payload: serde_json::json!({"status": "authenticated"}),
+                    });
+                    let ok_text = serde_json::to_string(&ok).unwrap_or_default();
+                    if socket.send(WsMessage::Text(ok_text)).await.is_err() {
+                        return;
+                    }
+                    identity
+                }
+                Err(error) => {
+                    tracing::warn!("[WS] 认证消息校验失败: {}", error.message);
+                    let error_text =
+                        serde_json::to_string(&WsProtoMessage::Error(error)).unwrap_or_default();
+                    let _ = socket.send(WsMessage::Text(error_text)).await;
+                    let _ = socket.send(WsMessage::Close(None)).await;
+                    return;
+                }
+            }
+        }
+    };
+
     let conn_id = uuid::Uuid::new_v4().to_string();
 
     // 注册连接
-    if let Err(e) = state
-        .ws_manager
-        .register(conn_id.clone(), client_info.clone())
-    {
+    if let Err(e) = state.ws_manager.register_with_identity(
+        conn_id.clone(),
+        client_info.clone(),
+        Some(identity.clone()),
+    ) {
         state.logs.write().await.add(
             "error",
             &format!("[WS] Failed to register connection: {}", e.message),
@@ -115,10 +209,10 @@ pub async fn handle_websocket(
     state.logs.write().await.add(
         "info",
         &format!(
-            "[WS] New connection: {} (client: {:?}, authenticated: {})",
+            "[WS] New connection: {} (client: {:?}, identity: {})",
             &conn_id[..8],
             client_info,
-            authenticated
+            identity
         ),
     );
 
@@ -256,6 +350,8 @@ async fn handle_ws_message(
                 }),
             }))
         }
+        // 连接已在建立时完成认证
+        WsProtoMessage::Auth { .. } => None,
         WsProtoMessage::KiroCredentialEvent(_) => {
             // Kiro事件是服务端到客户端的消息，客户端不应该发送
             Some(WsProtoMessage::Error(WsError::invalid_message(
@@ -321,6 +417,7 @@ async fn handle_ws_chat_completions(
 ) -> WsProtoMessage {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    ctx.set_route(RequestRoute::Default);
 
     // 使用 RequestProcessor 解析模型别名和路由
     let _provider = state.processor.resolve_and_route(&mut ctx).await;
@@ -386,6 +483,7 @@ async fn handle_ws_anthropic_messages(
 ) -> WsProtoMessage {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    ctx.set_route(RequestRoute::Default);
 
     // 使用 RequestProcessor 解析模型别名和路由
     let _provider = state.processor.resolve_and_route(&mut ctx).await;
@@ -451,27 +549,9 @@ pub async fn call_provider_openai_for_ws(
 
     match &credential.credential {
         CredentialData::KiroOAuth { creds_file_path } => {
-            let mut kiro = KiroProvider::new();
-            if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
-                if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
-                        db,
-                        &credential.uuid,
-                        Some(&format!("Failed to load credentials: {e}")),
-                    );
-                }
-                return Err(e.to_string());
-            }
-            if let Err(e) = kiro.refresh_token().await {
-                if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
-                        db,
-                        &credential.uuid,
-                        Some(&format!("Token refresh failed: {e}")),
-                    );
-                }
-                return Err(e.to_string());
-            }
+            let kiro = load_kiro_from_source(state, &credential.uuid, creds_file_path)
+                .await
+                .map_err(|e| e.to_string())?;
 
             let resp = match kiro.call_api(request).await {
                 Ok(r) => r,
@@ -487,8 +567,10 @@ pub async fn call_provider_openai_for_ws(
                 }
             };
             if resp.status().is_success() {
-                let body = resp.text().await.map_err(|e| e.to_string())?;
-                let parsed = parse_cw_response(&body);
+                let body = resp.bytes().await.map_err(|e| e.to_string())?;
+                let parsed = parse_cw_response_bytes(&body)
+                    .or_else(CWParseError::recover)
+                    .map_err(|e| e.to_string())?;
                 let has_tool_calls = !parsed.tool_calls.is_empty();
 
                 // 记录成功
@@ -552,41 +634,20 @@ pub async fn call_provider_openai_for_ws(
             }
         }
         CredentialData::OpenAIKey { api_key, base_url } => {
-            let provider = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
-            let resp = match provider.call_api(request).await {
-                Ok(r) => r,
-                Err(e) => {
-                    if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
-                            db,
-                            &credential.uuid,
-                            Some(&e.to_string()),
-                        );
-                    }
-                    return Err(e.to_string());
-                }
-            };
-            if resp.status().is_success() {
-                // 记录成功
-                if let Some(db) = &state.db {
-                    let _ =
-                        state
-                            .pool_service
-                            .mark_healthy(db, &credential.uuid, Some(&request.model));
-                    let _ = state.pool_service.record_usage(db, &credential.uuid);
-                }
-                resp.json::<serde_json::Value>()
-                    .await
-                    .map_err(|e| e.to_string())
-            } else {
-                let body = resp.text().await.unwrap_or_default();
-                if let Some(db) = &state.db {
-                    let _ = state
-                        .pool_service
-                        .mark_unhealthy(db, &credential.uuid, Some(&body));
-                }
-                Err(format!("Upstream error: {body}"))
-            }
+            let openai = OpenAICustomProvider::with_client(
+                api_key.clone(),
+                base_url.clone(),
+                state.provider_clients.get(ProviderType::OpenAI),
+            );
+            call_openai_compatible_for_ws(state, credential, request, openai).await
+        }
+        CredentialData::LocalOpenAI { base_url, api_key } => {
+            let openai = OpenAICustomProvider::with_local_server(
+                base_url.clone(),
+                api_key.clone(),
+                state.provider_clients.get(ProviderType::OpenAI),
+            );
+            call_openai_compatible_for_ws(state, credential, request, openai).await
         }
         CredentialData::ClaudeKey { api_key, base_url } => {
             // 打印 Claude 代理 URL 用于调试
@@ -596,7 +657,11 @@ pub async fn call_provider_openai_for_ws(
                 actual_base_url,
                 &credential.uuid[..8]
             );
-            let provider = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let provider = ClaudeCustomProvider::with_client(
+                api_key.clone(),
+                base_url.clone(),
+                state.provider_clients.get(ProviderType::Claude),
+            );
             match provider.call_openai_api(request).await {
                 Ok(result) => {
                     // 记录成功
@@ -626,7 +691,9 @@ pub async fn call_provider_openai_for_ws(
             creds_file_path,
             project_id,
         } => {
-            let mut antigravity = AntigravityProvider::new();
+            let mut antigravity = AntigravityProvider::with_client(
+                state.provider_clients.get(ProviderType::Antigravity),
+            );
             if let Err(e) = antigravity
                 .load_credentials_from_path(creds_file_path)
                 .await
@@ -720,6 +787,47 @@ pub async fn call_provider_openai_for_ws(
     }
 }
 
+/// WebSocket 通过 OpenAI 兼容凭证（OpenAI API Key、本地服务）调用
+async fn call_openai_compatible_for_ws(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    provider: OpenAICustomProvider,
+) -> Result<serde_json::Value, String> {
+    let resp = match provider.call_api(request).await {
+        Ok(r) => r,
+        Err(e) => {
+            if let Some(db) = &state.db {
+                let _ =
+                    state
+                        .pool_service
+                        .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+            }
+            return Err(e.to_string());
+        }
+    };
+    if resp.status().is_success() {
+        // 记录成功
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_healthy(db, &credential.uuid, Some(&request.model));
+            let _ = state.pool_service.record_usage(db, &credential.uuid);
+        }
+        resp.json::<serde_json::Value>()
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        let body = resp.text().await.unwrap_or_default();
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(&body));
+        }
+        Err(format!("Upstream error: {body}"))
+    }
+}
+
 /// WebSocket 专用的 Anthropic 格式 Provider 调用
 pub async fn call_provider_anthropic_for_ws(
     state: &AppState,
@@ -737,7 +845,11 @@ pub async fn call_provider_anthropic_for_ws(
                 actual_base_url,
                 &credential.uuid[..8]
             );
-            let provider = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let provider = ClaudeCustomProvider::with_client(
+                api_key.clone(),
+                base_url.clone(),
+                state.provider_clients.get(ProviderType::Claude),
+            );
             let resp = match provider.call_api(request).await {
                 Ok(r) => r,
                 Err(e) => {
@@ -775,7 +887,8 @@ pub async fn call_provider_anthropic_for_ws(
         }
         _ => {
             // 转换为 OpenAI 格式并调用（健康状态更新在 call_provider_openai_for_ws 中处理）
-            let openai_request = convert_anthropic_to_openai(request);
+            let mut openai_request = convert_anthropic_to_openai(request);
+            ReasoningHandler::strip_unsupported_effort(&mut openai_request);
             let result = call_provider_openai_for_ws(state, credential, &openai_request).await?;
 
             // 转换响应为 Anthropic 格式
@@ -802,3 +915,137 @@ pub async fn call_provider_anthropic_for_ws(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::config::ServerApiKey;
+
+    const API_KEY: &str = "pc-test-key-123456";
+
+    fn keys() -> ServerApiKeys {
+        ServerApiKeys::new(
+            API_KEY,
+            vec![
+                ServerApiKey {
+                    key: "pc-ci-key-654321".to_string(),
+                    label: "ci".to_string(),
+                    enabled: true,
+                },
+                ServerApiKey {
+                    key: API_KEY.to_string(),
+                    label: "legacy".to_string(),
+                    enabled: false,
+                },
+            ],
+        )
+    }
+
+    fn primary_only() -> ServerApiKeys {
+        ServerApiKeys::new(API_KEY, Vec::new())
+    }
+
+    fn params(api_key: Option<&str>, auth: Option<&str>) -> WsQueryParams {
+        WsQueryParams {
+            api_key: api_key.map(str::to_string),
+            token: None,
+            auth: auth.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_unauthenticated_upgrade_rejected() {
+        let headers = HeaderMap::new();
+        assert_eq!(
+            authenticate_ws_upgrade(&headers, &params(None, None), &primary_only()),
+            Err("No API key provided")
+        );
+        assert_eq!(
+            authenticate_ws_upgrade(&headers, &params(Some("wrong"), None), &primary_only()),
+            Err("Invalid API key")
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer wrong".parse().unwrap());
+        assert!(
+            authenticate_ws_upgrade(&headers, &params(None, Some("frame")), &primary_only())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_authenticated_upgrade_accepted_with_identity() {
+        let expected = WsUpgradeAuth::Authenticated(api_key_identity(API_KEY));
+        assert_eq!(
+            authenticate_ws_upgrade(
+                &HeaderMap::new(),
+                &params(Some(API_KEY), None),
+                &primary_only()
+            ),
+            Ok(expected.clone())
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            format!("Bearer {API_KEY}").parse().unwrap(),
+        );
+        assert_eq!(
+            authenticate_ws_upgrade(&headers, &params(None, None), &primary_only()),
+            Ok(expected)
+        );
+        // 身份不包含完整密钥
+        assert!(!api_key_identity(API_KEY).contains(API_KEY));
+    }
+
+    #[test]
+    fn test_auth_frame() {
+        assert_eq!(
+            authenticate_ws_upgrade(
+                &HeaderMap::new(),
+                &params(None, Some("frame")),
+                &primary_only()
+            ),
+            Ok(WsUpgradeAuth::AwaitingAuthFrame)
+        );
+
+        let frame = format!(r#"{{"type":"auth","api_key":"{API_KEY}"}}"#);
+        assert_eq!(
+            authenticate_ws_frame(&frame, &primary_only()).unwrap(),
+            api_key_identity(API_KEY)
+        );
+        assert!(
+            authenticate_ws_frame(r#"{"type":"auth","api_key":"wrong"}"#, &primary_only()).is_err()
+        );
+        assert!(
+            authenticate_ws_frame(r#"{"type":"ping","timestamp":1}"#, &primary_only()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_ws_auth_uses_server_api_keys() {
+        let keys = keys();
+        let ci =
+            WsUpgradeAuth::Authenticated(format!("{} (ci)", api_key_identity("pc-ci-key-654321")));
+        assert_eq!(
+            authenticate_ws_upgrade(
+                &HeaderMap::new(),
+                &params(Some("pc-ci-key-654321"), None),
+                &keys
+            ),
+            Ok(ci)
+        );
+        // 在 api_keys 中停用的主密钥不能通过 WebSocket 认证
+        assert_eq!(
+            authenticate_ws_upgrade(&HeaderMap::new(), &params(Some(API_KEY), None), &keys),
+            Err("API key has been disabled")
+        );
+
+        let frame = r#"{"type":"auth","api_key":"pc-ci-key-654321"}"#;
+        assert!(authenticate_ws_frame(frame, &keys)
+            .unwrap()
+            .ends_with("(ci)"));
+        let frame = format!(r#"{{"type":"auth","api_key":"{API_KEY}"}}"#);
+        assert!(authenticate_ws_frame(&frame, &keys).is_err());
+    }
+}
@@ -10,18 +10,22 @@ use axum::{
     Json, Router,
 };
 use proxycast_core::config::{
-    Config, ConfigChangeKind, ConfigManager, EndpointProvidersConfig, FileChangeEvent, FileWatcher,
-    HotReloadManager, ReloadResult,
+    AnthropicConfig, Config, ConfigChangeKind, ConfigManager, EndpointProvidersConfig,
+    FailoverChain, FileChangeEvent, FileWatcher, HotReloadManager, ReloadResult, RoutingConfig,
 };
-use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::dao::model_registry::ModelRegistryDao;
+use proxycast_core::database::dao::provider_pool::{ConfigSyncResult, ProviderPoolDao};
 use proxycast_core::database::DbConnection;
-use proxycast_core::logger::LogStore;
+use proxycast_core::logger::{LogStore, Redactor};
+use proxycast_core::middleware::{request_id_from_headers, InFlightTracker, ShutdownReport};
 use proxycast_core::models::anthropic::*;
+use proxycast_core::models::model_registry::LastKnownModels;
 use proxycast_core::models::openai::*;
-use proxycast_core::models::provider_pool_model::CredentialData;
-use proxycast_core::models::route_model::{RouteInfo, RouteListResponse};
+use proxycast_core::models::provider_pool_model::{CredentialData, ProviderCredential};
+use proxycast_core::models::route_model::{RequestRoute, RouteInfo, RouteListResponse};
 use proxycast_credential::CredentialSyncService;
 use proxycast_infra::injection::Injector;
+use proxycast_infra::RetryConfig;
 use proxycast_processor::{RequestContext, RequestProcessor};
 use proxycast_providers::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use proxycast_providers::providers::antigravity::AntigravityProvider;
@@ -30,15 +34,22 @@ use proxycast_providers::providers::gemini::GeminiProvider;
 use proxycast_providers::providers::kiro::KiroProvider;
 use proxycast_providers::providers::openai_custom::OpenAICustomProvider;
 use proxycast_server_utils::{
-    build_anthropic_response, build_anthropic_stream_response, build_error_response,
-    build_error_response_with_status, build_gemini_cli_request, build_gemini_native_request,
-    health, models, parse_cw_response,
+    annotate_output_clamp, apply_structured_output, apply_upstream_override,
+    apply_user_metadata_policy, build_anthropic_response, build_anthropic_stream_response,
+    build_error_response, build_error_response_with_status, build_gemini_cli_request,
+    build_gemini_native_request, build_gemini_native_stream_response, build_pool_model_list,
+    check_anthropic_capabilities, cw_parse_error_response, emulate_multiple_choices,
+    fallback_allowed, handle_structured_response, health, models, no_fallback_requested,
+    parse_cw_response_bytes, plan_openai_request, reject_self_upstream, should_buffer_stream,
+    validate_anthropic_tools, version_info, BufferFormat, CWParseError, CountTokensCache,
+    OutputLimitsCache, PoolModelsCache, POOL_MODELS_TTL,
 };
 use proxycast_services::kiro_event_service::KiroEventService;
-use proxycast_services::provider_pool_service::ProviderPoolService;
+use proxycast_services::provider_pool_service::{ChainStep, ProviderPoolService};
 use proxycast_services::token_cache_service::TokenCacheService;
 use proxycast_websocket::{WsConfig, WsConnectionManager, WsStats};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{oneshot, RwLock};
@@ -82,32 +93,291 @@ pub fn record_request_telemetry(
     // 设置凭证 ID
     if let Some(cred_id) = &ctx.credential_id {
         log.set_credential_id(cred_id.clone());
+        // 成功请求计入凭证延迟统计（流式请求使用首 token 耗时）
+        if status == proxycast_infra::telemetry::RequestStatus::Success {
+            state.pool_service.record_latency(
+                cred_id,
+                ctx.first_token_ms.unwrap_or_else(|| ctx.elapsed_ms()),
+            );
+        }
     }
 
     // 设置重试次数
     log.retry_count = ctx.retry_count;
 
-    // 记录到统计聚合器
-    {
-        let stats = state.processor.stats.write();
-        stats.record(log.clone());
+    // 设置分阶段耗时
+    let timing = ctx.timing();
+    log.set_timing(timing);
+
+    // 设置客户端 ID
+    if let Some(client_id) = &ctx.client_id {
+        log.set_client_id(client_id.clone());
     }
 
+    // 设置首 token 耗时（流式请求）
+    if let Some(ttft_ms) = ctx.first_token_ms {
+        log.set_ttft(ttft_ms);
+    }
+
+    // 设置路由标签
+    if let Some(route) = &ctx.route {
+        log.set_route(route.clone());
+    }
+    if let Some(trace) = &ctx.selection_trace {
+        log.set_selection_trace(trace.clone());
+    }
+    if let Some(label) = &ctx.api_key_label {
+        log.set_api_key_label(label.clone());
+    }
+
+    // 记录到统计聚合器（聚合器内部分片加锁，这里只需共享引用）
+    state.processor.stats.read().record(log.clone());
+
     // 记录到请求日志记录器（用于前端日志列表显示）
     if let Some(logger) = &state.request_logger {
         let _ = logger.record(log.clone());
     }
 
     tracing::info!(
-        "[TELEMETRY] request_id={} provider={:?} model={} status={:?} duration_ms={}",
+        "[TELEMETRY] request_id={} route={} api_key={} provider={:?} model={} status={:?} duration_ms={} selection_ms={} refresh_ms={} upstream_ms={}",
         ctx.request_id,
+        ctx.route.as_deref().unwrap_or("-"),
+        ctx.api_key_label.as_deref().unwrap_or("-"),
         provider,
         ctx.resolved_model,
         status,
-        ctx.elapsed_ms()
+        timing.total_ms,
+        timing.selection_ms,
+        timing.refresh_ms,
+        timing.upstream_ms
     );
 }
 
+/// 上游调用期间的客户端断开守卫
+///
+/// 客户端在响应头返回前断开时，处理函数的 future 被丢弃，守卫随之释放：
+/// 取消请求上下文的令牌（中止持有该令牌的上游调用和后台任务），
+/// 并以截至断开时刻的耗时记为已取消。
+struct DisconnectGuard {
+    state: AppState,
+    ctx: Option<RequestContext>,
+}
+
+impl DisconnectGuard {
+    fn new(state: &AppState, ctx: &RequestContext) -> Self {
+        Self {
+            state: state.clone(),
+            ctx: Some(ctx.clone()),
+        }
+    }
+
+    /// 上游调用已返回，后续由调用方记录请求统计
+    fn disarm(mut self) {
+        self.ctx = None;
+    }
+}
+
+impl Drop for DisconnectGuard {
+    fn drop(&mut self) {
+        if let Some(ctx) = self.ctx.take() {
+            ctx.cancel();
+            tracing::info!(
+                "[REQUEST] 客户端在响应返回前断开，已取消请求 {} (耗时 {}ms)",
+                ctx.request_id,
+                ctx.elapsed_ms()
+            );
+            record_request_telemetry(
+                &self.state,
+                &ctx,
+                proxycast_infra::telemetry::RequestStatus::Cancelled,
+                None,
+            );
+        }
+    }
+}
+
+/// 等待上游调用，期间客户端断开（处理函数被丢弃）时取消请求并记为已取消
+///
+/// 调用正常返回时不记录统计，由调用方按响应结果记录。
+pub async fn watch_disconnect<F: std::future::Future>(
+    state: &AppState,
+    ctx: &RequestContext,
+    call: F,
+) -> F::Output {
+    let guard = DisconnectGuard::new(state, ctx);
+    let output = call.await;
+    guard.disarm();
+    output
+}
+
+/// 成功的流式响应在响应体结束时记录请求统计
+///
+/// 此时的耗时覆盖整个生成过程，并附带首 token 耗时（TTFT）。
+/// 客户端中途断开时（响应体被丢弃，上游流随之释放）记为已取消，耗时截至断开时刻；
+/// 被空闲看门狗中止的（见 [`StreamTimeouts`](proxycast_infra::telemetry::StreamTimeouts)）记为超时。
+pub fn record_stream_telemetry(
+    state: &AppState,
+    ctx: &RequestContext,
+    response: axum::response::Response,
+) -> axum::response::Response {
+    let state = state.clone();
+    let mut ctx = ctx.clone();
+    let started = ctx.start_time;
+    proxycast_server_utils::track_first_token(response, started, move |end| {
+        if let Some(ms) = end.first_token_ms {
+            ctx.set_first_token_ms(ms);
+        }
+        let status = state
+            .stream_timeouts
+            .end_status(&ctx.request_id, end.client_disconnected);
+        if status == proxycast_infra::telemetry::RequestStatus::Cancelled {
+            tracing::info!(
+                "[STREAM] 客户端断开，已取消请求 {} (耗时 {}ms)",
+                ctx.request_id,
+                ctx.elapsed_ms()
+            );
+        }
+        record_request_telemetry(&state, &ctx, status, None);
+    })
+}
+
+/// 创建选择器路由请求的上下文（路由标签在解析选择器后设置）
+///
+/// `session` 为通过配额检查的会话，Token 用量据此计入会话配额。
+fn selector_request_context(
+    state: &AppState,
+    headers: &HeaderMap,
+    model: &str,
+    stream: bool,
+    session: Option<String>,
+) -> RequestContext {
+    let mut ctx = RequestContext::new(model.to_string()).with_stream(stream);
+    if let Some(request_id) = request_id_from_headers(headers) {
+        ctx = ctx.with_request_id(request_id);
+    }
+    ctx.set_client_id(state.client_detector.detect(headers).client_id.clone());
+    if let Some(session_id) = proxycast_core::session::session_id_from_headers(headers) {
+        ctx.set_session_id(session_id);
+    }
+    if let Some(session) = session {
+        ctx.set_metadata(SESSION_METADATA_KEY, serde_json::json!(session));
+    }
+    ctx
+}
+
+/// 按与默认路由相同的估算方式记录 Anthropic 请求的 Token 用量（仅成功响应）
+fn record_selector_anthropic_tokens(
+    state: &AppState,
+    ctx: &RequestContext,
+    request: &AnthropicMessagesRequest,
+    response: &Response,
+) {
+    if response.status().is_success() {
+        record_token_usage(
+            state,
+            ctx,
+            Some(handlers::estimate_anthropic_input_tokens(request)),
+            Some(handlers::ESTIMATED_ANTHROPIC_OUTPUT_TOKENS),
+        );
+    }
+}
+
+/// 选择器解析到的凭证记入请求上下文
+fn set_selector_credential(ctx: &mut RequestContext, cred: &ProviderCredential) {
+    if let Ok(provider) = cred
+        .provider_type
+        .to_string()
+        .parse::<proxycast_core::ProviderType>()
+    {
+        ctx.set_provider(provider);
+    }
+    ctx.set_credential_id(cred.uuid.clone());
+}
+
+/// 调用选择器解析到的凭证
+///
+/// `switch_on_overload` 为 true 时（按 Provider 类型选择的凭证），上游过载（529）后换用
+/// 同类型的其他凭证；按名称或 UUID 指定的凭证不换用。
+#[allow(clippy::too_many_arguments)]
+async fn call_selector_credential<F, Fut>(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    headers: &HeaderMap,
+    selector: &str,
+    model: &str,
+    mut cred: ProviderCredential,
+    switch_on_overload: bool,
+    call: F,
+) -> Response
+where
+    F: Fn(ProviderCredential, tokio_util::sync::CancellationToken) -> Fut,
+    Fut: std::future::Future<Output = Response>,
+{
+    let mut failovers = 0;
+    loop {
+        let upstream = call(cred.clone(), ctx.cancel_token.clone());
+        let response = watch_disconnect(state, ctx, upstream).await;
+        if !switch_on_overload
+            || failovers >= handlers::MAX_OVERLOAD_FAILOVERS
+            || response.status().as_u16() != proxycast_infra::resilience::OVERLOADED_STATUS_CODE
+        {
+            return response;
+        }
+        let Some(mut alternative) =
+            handlers::switch_overloaded_credential(state, ctx, &cred, selector, model, None).await
+        else {
+            return response;
+        };
+        apply_upstream_override(headers, state.allow_upstream_override, &mut alternative);
+        if reject_self_upstream(
+            &alternative,
+            &state.bind_host,
+            state.bind_port,
+            state.allow_self_upstream,
+        )
+        .is_some()
+        {
+            return response;
+        }
+        cred = alternative;
+        failovers += 1;
+    }
+}
+
+/// 记录选择器路由请求的统计（成功的流式响应在响应体结束时记录）
+fn record_selector_telemetry(
+    state: &AppState,
+    ctx: &RequestContext,
+    response: Response,
+) -> Response {
+    let status = response.status();
+    if status.is_success() && ctx.is_stream {
+        return record_stream_telemetry(state, ctx, response);
+    }
+    if status.is_success() {
+        record_request_telemetry(
+            state,
+            ctx,
+            proxycast_infra::telemetry::RequestStatus::Success,
+            None,
+        );
+    } else {
+        record_request_telemetry(
+            state,
+            ctx,
+            proxycast_infra::telemetry::RequestStatus::Failed,
+            Some(format!("HTTP {}", status.as_u16())),
+        );
+    }
+    response
+}
+
+/// 默认 Kiro 凭证（`AppState::kiro`，不属于凭证池）的刷新锁键
+pub const DEFAULT_KIRO_REFRESH_KEY: &str = "kiro:default";
+
+/// 请求上下文中记录会话 ID 的元数据键
+pub const SESSION_METADATA_KEY: &str = "session_id";
+
 /// 记录 Token 使用量到遥测系统
 pub fn record_token_usage(
     state: &AppState,
@@ -122,6 +392,23 @@ pub fn record_token_usage(
         return;
     }
 
+    let total_tokens = u64::from(input_tokens.unwrap_or(0)) + u64::from(output_tokens.unwrap_or(0));
+
+    // 计入会话配额
+    if let Some(session) = ctx
+        .get_metadata(SESSION_METADATA_KEY)
+        .and_then(|v| v.as_str())
+    {
+        state.session_quota.record_tokens(session, total_tokens);
+    }
+
+    // 计入凭证每日 Token 用量
+    if let (Some(cred_id), Some(db)) = (&ctx.credential_id, &state.db) {
+        state
+            .pool_service
+            .record_token_usage(db, cred_id, total_tokens);
+    }
+
     let provider = ctx.provider.unwrap_or(proxycast_core::ProviderType::Kiro);
     let record = TokenUsageRecord::new(
         uuid::Uuid::new_v4().to_string(),
@@ -131,7 +418,8 @@ pub fn record_token_usage(
         output_tokens.unwrap_or(0),
         TokenSource::Actual,
     )
-    .with_request_id(ctx.request_id.clone());
+    .with_request_id(ctx.request_id.clone())
+    .with_attribution(ctx.session_id.clone(), ctx.client_id.clone());
 
     // 记录到 Token 追踪器
     {
@@ -168,6 +456,10 @@ pub struct ServerState {
     pub default_provider_ref: Arc<RwLock<String>>,
     /// 路由器引用（用于动态更新默认 Provider）
     pub router_ref: Option<Arc<RwLock<proxycast_core::router::Router>>>,
+    /// 熔断器引用（用于读取熔断状态切换记录）
+    pub circuit_breaker_ref: Option<Arc<proxycast_infra::CircuitBreaker>>,
+    /// 在途请求跟踪器（用于停止时排空请求）
+    in_flight_ref: Option<Arc<InFlightTracker>>,
     shutdown_tx: Option<oneshot::Sender<()>>,
     /// 服务器运行时使用的 API key（启动时从配置复制）
     /// 用于 test_api 命令，确保测试使用的 API key 和服务器一致
@@ -195,6 +487,8 @@ impl ServerState {
             claude_custom_provider: claude_custom,
             default_provider_ref,
             router_ref: None,
+            circuit_breaker_ref: None,
+            in_flight_ref: None,
             shutdown_tx: None,
             running_api_key: None,
             running_host: None,
@@ -215,6 +509,18 @@ impl ServerState {
         }
     }
 
+    /// 服务器实际生效的配置（叠加运行时修改后脱敏）
+    pub async fn effective_config(&self) -> Config {
+        let overrides = proxycast_core::config::RuntimeOverrides {
+            default_provider: Some(self.default_provider_ref.read().await.clone()),
+            host: self.running_host.clone(),
+            port: None,
+            api_key: self.running_api_key.clone(),
+            endpoint_providers: None,
+        };
+        proxycast_core::config::effective_config(&self.config, &overrides)
+    }
+
     /// 增加请求计数
     pub fn increment_request_count(&mut self) {
         self.requests = self.requests.saturating_add(1);
@@ -222,11 +528,20 @@ impl ServerState {
 
     /// 解析绑定地址
     ///
-    /// 直接返回用户配置的地址，不做任何自动替换。
+    /// 配置了 `server.bind_interface` 时绑定到该网卡的 IPv4 地址，
+    /// 网卡不存在或未启用时返回错误；否则直接返回用户配置的地址。
     /// 如果地址无效，绑定时会失败并返回错误。
-    fn resolve_bind_host(&self, configured_host: &str) -> String {
-        tracing::info!("[SERVER] 使用配置的监听地址: {}", configured_host);
-        configured_host.to_string()
+    fn resolve_bind_host(&self, configured_host: &str) -> Result<String, String> {
+        let bind_interface = self.config.server.bind_interface.as_deref();
+        let host = proxycast_core::network::resolve_bind_host(configured_host, bind_interface)
+            .map_err(|e| format!("无法解析监听网卡: {e}"))?;
+        match bind_interface {
+            Some(name) if !name.trim().is_empty() => {
+                tracing::info!("[SERVER] 使用网卡 {} 的监听地址: {}", name, host)
+            }
+            _ => tracing::info!("[SERVER] 使用配置的监听地址: {}", host),
+        }
+        Ok(host)
     }
 
     pub async fn start(
@@ -287,20 +602,9 @@ impl ServerState {
         let (tx, rx) = oneshot::channel();
         self.shutdown_tx = Some(tx);
 
-        // 智能选择监听地址
-        // - 127.0.0.1, localhost, 0.0.0.0, :: 直接使用
-        // - 局域网 IP：检查是否在当前网卡列表中，如果不在则自动切换到当前局域网 IP
+        // 选择监听地址：配置了 bind_interface 时使用该网卡的 IPv4 地址，否则使用配置的 host
         let configured_host = self.config.server.host.clone();
-        let host = self.resolve_bind_host(&configured_host);
-
-        // 如果地址发生了变化，记录日志
-        if host != configured_host {
-            tracing::warn!(
-                "[SERVER] 配置的监听地址 {} 不可用，自动切换到 {}",
-                configured_host,
-                host
-            );
-        }
+        let host = self.resolve_bind_host(&configured_host)?;
 
         let port = self.config.server.port;
         let api_key = self.config.server.api_key.clone();
@@ -340,8 +644,8 @@ impl ServerState {
         {
             let default_provider_str = &config.routing.default_provider;
 
-            // 尝试解析为 ProviderType 枚举
-            match default_provider_str.parse::<proxycast_core::ProviderType>() {
+            // 尝试解析为 ProviderType 枚举（含 routing.provider_protocols 中声明的协议）
+            match config.routing.resolve_provider_type(default_provider_str) {
                 Ok(provider_type) => {
                     let mut router = processor.router.write().await;
                     router.set_default_provider(provider_type);
@@ -355,7 +659,8 @@ impl ServerState {
                     // 这种情况下，路由器保持空状态，请求会直接使用 provider_id 进行凭证查找
                     tracing::warn!(
                         "[SERVER] 配置的默认 Provider '{}' 不是有效的 ProviderType 枚举值，可能是自定义 Provider ID。\
-                        路由器将保持空状态，请求将直接使用 provider_id 进行凭证查找。",
+                        路由器将保持空状态，请求将直接使用 provider_id 进行凭证查找；\
+                        可在 routing.provider_protocols 中声明其协议。",
                         default_provider_str
                     );
                     eprintln!(
@@ -368,6 +673,10 @@ impl ServerState {
 
         // 保存 router_ref 以便后续动态更新
         self.router_ref = Some(processor.router.clone());
+        self.circuit_breaker_ref = Some(processor.circuit_breaker.clone());
+
+        let in_flight = InFlightTracker::new();
+        self.in_flight_ref = Some(in_flight.clone());
 
         // 保存实际使用的 host（在移动到 spawn 之前克隆）
         let running_host = host.clone();
@@ -381,6 +690,7 @@ impl ServerState {
                 kiro,
                 logs,
                 rx,
+                in_flight,
                 pool_service,
                 token_cache,
                 db,
@@ -409,15 +719,31 @@ impl ServerState {
         Ok(())
     }
 
-    pub async fn stop(&mut self) {
+    /// 停止服务器，等待在途请求排空（最长 `server.shutdown_drain_secs`）后返回停机结果
+    pub async fn stop(&mut self) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
+            report = match self.in_flight_ref.take() {
+                Some(in_flight) => {
+                    let timeout =
+                        std::time::Duration::from_secs(self.config.server.shutdown_drain_secs);
+                    in_flight.drain(timeout).await
+                }
+                None => ShutdownReport {
+                    stopped: true,
+                    ..Default::default()
+                },
+            };
         }
         self.running = false;
+        self.in_flight_ref = None;
         self.start_time = None;
         self.running_api_key = None;
         self.running_host = None;
         self.router_ref = None;
+        self.circuit_breaker_ref = None;
+        report
     }
 }
 
@@ -427,23 +753,32 @@ pub mod handlers;
 #[allow(dead_code)]
 pub struct AppState {
     pub api_key: String,
+    /// 接受的 API 密钥（`server.api_key` 和 `server.api_keys`，后者支持热重载）
+    pub api_keys: Arc<RwLock<proxycast_core::config::ServerApiKeys>>,
     pub base_url: String,
     pub default_provider: Arc<RwLock<String>>,
     pub kiro: Arc<RwLock<KiroProvider>>,
     pub logs: Arc<RwLock<LogStore>>,
-    pub kiro_refresh_lock: Arc<tokio::sync::Mutex<()>>,
+    /// 按凭证划分的 Kiro Token 刷新锁（与 TokenCacheService 共享）
+    pub kiro_refresh_locks: Arc<proxycast_core::credential::RefreshLocks>,
     pub gemini_refresh_lock: Arc<tokio::sync::Mutex<()>>,
     pub pool_service: Arc<ProviderPoolService>,
     pub token_cache: Arc<TokenCacheService>,
     pub db: Option<DbConnection>,
+    /// 预热配置（来自配置 server.warmup，预热池维持任务支持热重载）
+    pub warmup_config: Arc<RwLock<proxycast_core::config::WarmupConfig>>,
+    /// `/v1/models` 结果缓存
+    pub pool_models: Arc<PoolModelsCache>,
+    /// 最近一次成功读取的注册表模型（模型 ID, Provider ID），注册表读取失败时降级使用
+    pub registry_models: Arc<LastKnownModels<(String, String)>>,
     /// 参数注入器
     pub injector: Arc<RwLock<Injector>>,
     /// 是否启用参数注入
     pub injection_enabled: Arc<RwLock<bool>>,
     /// 请求处理器
     pub processor: Arc<RequestProcessor>,
-    /// 是否允许自动降级/切换 Provider（来自配置 retry.auto_switch_provider）
-    pub allow_provider_fallback: bool,
+    /// 自动降级/切换 Provider 配置（retry.auto_switch_provider 及按 Provider 类型的覆盖）
+    pub fallback_settings: proxycast_core::config::RetrySettings,
     /// WebSocket 连接管理器
     pub ws_manager: Arc<WsConnectionManager>,
     /// WebSocket 统计信息
@@ -452,6 +787,8 @@ pub struct AppState {
     pub hot_reload_manager: Option<Arc<HotReloadManager>>,
     /// 请求日志记录器（与 TelemetryState 共享）
     pub request_logger: Option<Arc<proxycast_infra::telemetry::RequestLogger>>,
+    /// 被空闲看门狗中止、尚未记录统计的流式请求
+    pub stream_timeouts: Arc<proxycast_infra::telemetry::StreamTimeouts>,
     /// Amp CLI 路由器
     pub amp_router: Arc<proxycast_core::router::AmpRouter>,
     /// 端点 Provider 配置
@@ -463,6 +800,50 @@ pub struct AppState {
     /// 批量任务执行器
     pub batch_executor:
         Arc<tokio::sync::RwLock<Option<handlers::batch_executor::BatchTaskExecutor>>>,
+    /// 共享上游 HTTP 客户端（连接池复用，来自配置 server.upstream_pool）
+    pub http_client: reqwest::Client,
+    /// 按 Provider 类型带默认请求头的上游客户端（来自配置 server.provider_headers）
+    pub provider_clients: Arc<proxycast_providers::http_client::ProviderClients>,
+    /// 是否校验 Anthropic 工具定义（来自配置 server.validate_tools）
+    pub validate_tools: bool,
+    /// Anthropic 请求 metadata 转发策略（来自配置 anthropic，支持热重载）
+    pub anthropic: Arc<RwLock<AnthropicConfig>>,
+    /// 是否模拟多候选回复（来自配置 server.emulate_multiple_choices）
+    pub emulate_multiple_choices: bool,
+    /// 结构化输出严格模式（来自配置 server.strict_structured_output）
+    pub strict_structured_output: bool,
+    /// 是否允许请求级上游地址覆盖（来自配置 server.allow_upstream_override）
+    pub allow_upstream_override: bool,
+    /// 是否把流式请求缓冲为非流式响应（来自配置 server.force_buffer_stream）
+    pub force_buffer_stream: bool,
+    /// 流式响应心跳间隔（来自配置 server.stream_heartbeat_secs，0 表示不发送）
+    pub stream_heartbeat: std::time::Duration,
+    /// 服务器监听主机（用于拒绝指向自身的上游地址）
+    pub bind_host: String,
+    /// 服务器监听端口
+    pub bind_port: u16,
+    /// 是否允许上游地址指向服务器自身（来自配置 server.allow_self_upstream）
+    pub allow_self_upstream: bool,
+    /// 会话配额限流器（来自配置 server.session_quota）
+    pub session_quota: Arc<proxycast_core::session::SessionQuotaLimiter>,
+    /// 全局并发限制器（来自配置 server.max_concurrent_requests）
+    pub concurrency_limiter: Arc<proxycast_core::middleware::ConcurrencyLimiter>,
+    /// 是否合并 OpenAI 流式工具调用参数增量（来自配置 server.coalesce_tool_calls）
+    pub coalesce_tool_calls: bool,
+    /// 响应后处理器链（来自配置 server.response_post_processors）
+    pub post_processors: Arc<proxycast_core::response_postprocess::PostProcessorChain>,
+    /// 路由配置（用于解析 routing.provider_protocols 中声明的协议，支持热重载）
+    pub routing: Arc<RwLock<RoutingConfig>>,
+    /// 模型最大输出 token 上限（来自模型注册表，按有效期刷新）
+    pub model_output_limits: Arc<OutputLimitsCache>,
+    /// 客户端识别器（来自配置 client_detection）
+    pub client_detector: Arc<client_detector::ClientDetector>,
+    /// 规范化后的选择器路由前缀（来自配置 server.selector_prefix）
+    pub selector_prefix: String,
+    /// `count_tokens` 原生计数结果缓存
+    pub count_tokens_cache: Arc<CountTokensCache>,
+    /// 请求日志是否记录完整的凭证选择列表（来自配置 telemetry.verbose_selection_trace）
+    pub verbose_selection_trace: bool,
 }
 
 /// 启动配置文件监控
@@ -483,6 +864,7 @@ async fn start_config_watcher(
     logs: Arc<RwLock<LogStore>>,
     db: Option<DbConnection>,
     config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>>,
+    app_state: AppState,
 ) -> Option<FileWatcher> {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<FileChangeEvent>();
 
@@ -510,6 +892,17 @@ async fn start_config_watcher(
     let db_clone = db.clone();
     let config_manager_clone = config_manager.clone();
 
+    // 配置中的凭证 ID，用于识别热重载时从配置中移除的凭证
+    let mut config_credential_ids: HashSet<String> = config_manager
+        .as_ref()
+        .and_then(|manager| {
+            CredentialSyncService::new(manager.clone())
+                .load_from_config()
+                .ok()
+        })
+        .map(|credentials| credentials.into_iter().map(|c| c.uuid).collect())
+        .unwrap_or_default();
+
     tokio::spawn(async move {
         while let Some(event) = rx.recv().await {
             // 只处理修改事件
@@ -537,22 +930,33 @@ async fn start_config_watcher(
                         // 更新处理器中的组件
                         let new_config = manager.config();
                         update_processor_config(&processor_clone, &new_config).await;
+                        update_app_state_config(&app_state, &new_config).await;
 
                         // 同步凭证池
                         if let (Some(ref db), Some(ref cfg_manager)) =
                             (&db_clone, &config_manager_clone)
                         {
-                            match sync_credential_pool_from_config(db, cfg_manager, &logs_clone)
-                                .await
+                            match sync_credential_pool_from_config(
+                                db,
+                                cfg_manager,
+                                &mut config_credential_ids,
+                                new_config.credentials.prune_on_reload,
+                            )
+                            .await
                             {
-                                Ok(count) => {
+                                Ok(result) => {
                                     tracing::info!(
-                                        "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证",
-                                        count
+                                        "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证，禁用 {} 个已移除的凭证",
+                                        result.synced,
+                                        result.disabled.len()
                                     );
                                     logs_clone.write().await.add(
                                         "info",
-                                        &format!("[HOT_RELOAD] 凭证池同步完成，共 {count} 个凭证"),
+                                        &format!(
+                                            "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证，禁用 {} 个已移除的凭证",
+                                            result.synced,
+                                            result.disabled.len()
+                                        ),
                                     );
                                 }
                                 Err(e) => {
@@ -597,6 +1001,62 @@ async fn start_config_watcher(
     Some(watcher)
 }
 
+/// 更新服务器状态中支持热重载的配置
+///
+/// 当配置热重载成功后，替换 AppState 中对应字段的内容，新请求立即使用新配置。
+async fn update_app_state_config(state: &AppState, config: &Config) {
+    // 更新接受的 API 密钥（`server.api_key` 变更仍需重启）
+    *state.api_keys.write().await = proxycast_core::config::ServerApiKeys::new(
+        state.api_key.clone(),
+        config.server.api_keys.clone(),
+    );
+    tracing::debug!(
+        "[HOT_RELOAD] API 密钥已更新: {} 个附加密钥",
+        config.server.api_keys.len()
+    );
+
+    // 更新冷却恢复配置和凭证选择策略
+    state
+        .pool_service
+        .set_cooldown_recovery(config.credentials.cooldown_recovery.clone());
+    state
+        .pool_service
+        .set_selection_strategy(config.credentials.selection_strategy);
+
+    // 更新健康检查探测模型
+    state
+        .pool_service
+        .set_probe_models(config.health_probe.models.clone());
+
+    // 更新预热池配置
+    *state.warmup_config.write().await = config.server.warmup.clone();
+
+    // 更新自定义 Provider 协议声明
+    warn_invalid_provider_protocols(&config.routing);
+    *state.routing.write().await = config.routing.clone();
+
+    // 更新 Anthropic metadata 转发策略
+    *state.anthropic.write().await = config.anthropic.clone();
+
+    // 更新日志脱敏规则
+    state
+        .logs
+        .write()
+        .await
+        .set_redactor(Redactor::from_config(&config.logging.redaction));
+}
+
+/// 提示 routing.provider_protocols 中无法识别的协议
+fn warn_invalid_provider_protocols(routing: &RoutingConfig) {
+    for (provider_id, protocol) in routing.invalid_provider_protocols() {
+        tracing::warn!(
+            "[ROUTING] provider_protocols 中 '{}' 的协议 '{}' 无法识别，已忽略",
+            provider_id,
+            protocol
+        );
+    }
+}
+
 /// 更新处理器配置
 ///
 /// 当配置热重载成功后，更新 RequestProcessor 中的各个组件。
@@ -607,7 +1067,7 @@ async fn start_config_watcher(
 /// - 正在处理的请求不会看到部分更新的状态
 /// - 更新过程不会阻塞新请求的处理
 /// - 现有连接不受影响
-async fn update_processor_config(processor: &RequestProcessor, config: &Config) {
+pub async fn update_processor_config(processor: &RequestProcessor, config: &Config) {
     // 更新注入器规则
     {
         let mut injector = processor.injector.write().await;
@@ -625,11 +1085,10 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
     {
         let mut router = processor.router.write().await;
 
-        // 尝试解析为 ProviderType 枚举
+        // 尝试解析为 ProviderType 枚举（含 routing.provider_protocols 中声明的协议）
         match config
             .routing
-            .default_provider
-            .parse::<proxycast_core::ProviderType>()
+            .resolve_provider_type(&config.routing.default_provider)
         {
             Ok(provider_type) => {
                 router.set_default_provider(provider_type);
@@ -663,12 +1122,20 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
         );
     }
 
-    // 注意：重试配置目前不支持热更新，因为 Retrier 是不可变的
-    // 如果需要更新重试配置，需要重启服务器
+    // 熔断配置支持热更新，已有的熔断状态保留
+    processor
+        .circuit_breaker
+        .set_settings(config.retry.circuit_breaker.clone());
+
+    // 重试配置（次数、退避、抖动方式）热更新，下一次退避即生效
+    processor
+        .retrier
+        .set_config(RetryConfig::from_settings(&config.retry));
     tracing::debug!(
-        "[HOT_RELOAD] 重试配置: max_retries={}, base_delay={}ms (需重启生效)",
+        "[HOT_RELOAD] 重试配置已更新: max_retries={}, base_delay={}ms, jitter={:?}",
         config.retry.max_retries,
-        config.retry.base_delay_ms
+        config.retry.base_delay_ms,
+        config.retry.jitter
     );
 
     tracing::info!("[HOT_RELOAD] 处理器配置更新完成");
@@ -683,12 +1150,16 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
 /// - 从配置中加载所有凭证
 /// - 对于配置中存在但数据库中不存在的凭证，添加到数据库
 /// - 对于配置中存在且数据库中也存在的凭证，更新数据库中的记录
-/// - 对于数据库中存在但配置中不存在的凭证，保留（不删除，避免丢失运行时状态）
+/// - 对于上次同步时在配置中、本次已移除的凭证：开启 `credentials.prune_on_reload`
+///   时禁用（保留统计，不再参与选择），否则保留不变
+///
+/// `config_credential_ids` 记录配置中的凭证 ID，同步后更新为本次配置的凭证 ID。
 async fn sync_credential_pool_from_config(
     db: &DbConnection,
     config_manager: &Arc<std::sync::RwLock<ConfigManager>>,
-    _logs: &Arc<RwLock<LogStore>>,
-) -> Result<usize, String> {
+    config_credential_ids: &mut HashSet<String>,
+    prune_on_reload: bool,
+) -> Result<ConfigSyncResult, String> {
     // 创建凭证同步服务
     let sync_service = CredentialSyncService::new(config_manager.clone());
 
@@ -696,39 +1167,39 @@ async fn sync_credential_pool_from_config(
     let credentials = sync_service.load_from_config().map_err(|e| e.to_string())?;
 
     let conn = proxycast_core::database::lock_db(db)?;
-    let mut synced_count = 0;
-
-    for cred in &credentials {
-        // 检查凭证是否已存在
-        let existing =
-            ProviderPoolDao::get_by_uuid(&conn, &cred.uuid).map_err(|e| e.to_string())?;
-
-        if existing.is_some() {
-            // 更新现有凭证
-            ProviderPoolDao::update(&conn, cred).map_err(|e| e.to_string())?;
-            tracing::debug!(
-                "[HOT_RELOAD] 更新凭证: {} ({})",
-                cred.uuid,
-                cred.provider_type
-            );
-        } else {
-            // 添加新凭证
-            ProviderPoolDao::insert(&conn, cred).map_err(|e| e.to_string())?;
-            tracing::debug!(
-                "[HOT_RELOAD] 添加凭证: {} ({})",
-                cred.uuid,
-                cred.provider_type
-            );
-        }
-        synced_count += 1;
-    }
+    let result = ProviderPoolDao::sync_from_config(
+        &conn,
+        &credentials,
+        config_credential_ids,
+        prune_on_reload,
+    )
+    .map_err(|e| e.to_string())?;
 
-    Ok(synced_count)
+    *config_credential_ids = credentials.into_iter().map(|c| c.uuid).collect();
+    Ok(result)
 }
 
 /// 开发桥接启动回调类型
 pub type DevBridgeCallback = Box<dyn FnOnce(AppState) + Send + 'static>;
 
+/// 随服务器生命周期运行的后台任务，释放时（服务器停止）全部中止
+#[derive(Default)]
+struct BackgroundTasks(Vec<tokio::task::JoinHandle<()>>);
+
+impl BackgroundTasks {
+    fn push(&mut self, handle: tokio::task::JoinHandle<()>) {
+        self.0.push(handle);
+    }
+}
+
+impl Drop for BackgroundTasks {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
 async fn run_server(
     host: &str,
     port: u16,
@@ -737,6 +1208,7 @@ async fn run_server(
     kiro: KiroProvider,
     logs: Arc<RwLock<LogStore>>,
     shutdown: oneshot::Receiver<()>,
+    in_flight: Arc<InFlightTracker>,
     pool_service: Arc<ProviderPoolService>,
     token_cache: Arc<TokenCacheService>,
     db: Option<DbConnection>,
@@ -773,12 +1245,18 @@ async fn run_server(
         }
     }
 
-    // 从配置初始化 Router 的默认 Provider
+    // 从配置初始化 Router 的默认 Provider、熔断和重试配置
     if let Some(cfg) = &config {
+        processor
+            .circuit_breaker
+            .set_settings(cfg.retry.circuit_breaker.clone());
+        processor
+            .retrier
+            .set_config(RetryConfig::from_settings(&cfg.retry));
         let default_provider_str = &cfg.routing.default_provider;
 
-        // 尝试解析为 ProviderType 枚举
-        match default_provider_str.parse::<proxycast_core::ProviderType>() {
+        // 尝试解析为 ProviderType 枚举（含 routing.provider_protocols 中声明的协议）
+        match cfg.routing.resolve_provider_type(default_provider_str) {
             Ok(provider_type) => {
                 let mut router = processor.router.write().await;
                 router.set_default_provider(provider_type);
@@ -791,7 +1269,8 @@ async fn run_server(
                 // 如果解析失败，可能是自定义 provider ID
                 tracing::warn!(
                     "[SERVER] 配置的默认 Provider '{}' 不是有效的 ProviderType 枚举值，可能是自定义 Provider ID。\
-                    路由器将保持空状态，请求将直接使用 provider_id 进行凭证查找。",
+                    路由器将保持空状态，请求将直接使用 provider_id 进行凭证查找；\
+                    可在 routing.provider_protocols 中声明其协议。",
                     default_provider_str
                 );
                 eprintln!(
@@ -846,38 +1325,286 @@ async fn run_server(
     let api_key_service =
         Arc::new(proxycast_services::api_key_provider_service::ApiKeyProviderService::new());
 
-    // 是否允许自动降级/切换 Provider（默认开启，兼容旧行为）
-    let allow_provider_fallback = config
+    // 是否允许自动降级/切换 Provider（默认开启，兼容旧行为），可按 Provider 类型覆盖
+    let fallback_settings = config.as_ref().map(|c| c.retry.clone()).unwrap_or_default();
+
+    // 创建共享上游 HTTP 客户端（所有 Provider 复用同一连接池）
+    let http_client = proxycast_providers::http_client::build_shared_client(
+        &config
+            .as_ref()
+            .map(|c| c.server.upstream_pool.clone())
+            .unwrap_or_default(),
+    );
+
+    // 按 Provider 类型带默认请求头（User-Agent 等）的上游客户端
+    let provider_clients = Arc::new(proxycast_providers::http_client::ProviderClients::new(
+        config
+            .as_ref()
+            .map(|c| c.server.upstream_pool.clone())
+            .unwrap_or_default(),
+        config
+            .as_ref()
+            .map(|c| c.server.provider_headers.clone())
+            .unwrap_or_default(),
+    ));
+
+    let validate_tools = config
         .as_ref()
-        .map(|c| c.retry.auto_switch_provider)
+        .map(|c| c.server.validate_tools)
         .unwrap_or(true);
 
+    let anthropic = config
+        .as_ref()
+        .map(|c| c.anthropic.clone())
+        .unwrap_or_default();
+    let anthropic = Arc::new(RwLock::new(anthropic));
+
+    let emulate_multiple_choices = config
+        .as_ref()
+        .is_some_and(|c| c.server.emulate_multiple_choices);
+
+    let strict_structured_output = config
+        .as_ref()
+        .is_some_and(|c| c.server.strict_structured_output);
+
+    let allow_upstream_override = config
+        .as_ref()
+        .is_some_and(|c| c.server.allow_upstream_override);
+
+    let force_buffer_stream = config
+        .as_ref()
+        .is_some_and(|c| c.server.force_buffer_stream);
+    let stream_heartbeat = std::time::Duration::from_secs(
+        config
+            .as_ref()
+            .map(|c| c.server.stream_heartbeat_secs)
+            .unwrap_or(15),
+    );
+
+    let allow_self_upstream = config
+        .as_ref()
+        .is_some_and(|c| c.server.allow_self_upstream);
+
+    let verbose_selection_trace = config
+        .as_ref()
+        .is_some_and(|c| c.telemetry.verbose_selection_trace);
+
+    let api_keys = proxycast_core::config::ServerApiKeys::new(
+        api_key,
+        config
+            .as_ref()
+            .map(|c| c.server.api_keys.clone())
+            .unwrap_or_default(),
+    );
+
+    let selector_prefix = config
+        .as_ref()
+        .map(|c| c.server.selector_prefix.as_str())
+        .unwrap_or_default();
+    let selector_prefix = match proxycast_core::router::normalize_selector_prefix(selector_prefix) {
+        Ok(prefix) => prefix,
+        Err(e) => {
+            tracing::warn!("[ROUTE] 选择器路由前缀配置无效，已忽略: {}", e);
+            String::new()
+        }
+    };
+    if !selector_prefix.is_empty() {
+        tracing::info!("[ROUTE] 选择器路由前缀: {}", selector_prefix);
+    }
+
+    let dedup_window = std::time::Duration::from_millis(
+        config
+            .as_ref()
+            .map(|c| c.server.dedup_window_ms)
+            .unwrap_or_default(),
+    );
+
+    let coalesce_tool_calls = config
+        .as_ref()
+        .is_some_and(|c| c.server.coalesce_tool_calls);
+
+    let routing = config
+        .as_ref()
+        .map(|c| c.routing.clone())
+        .unwrap_or_default();
+    warn_invalid_provider_protocols(&routing);
+    let routing = Arc::new(RwLock::new(routing));
+
+    let client_detector = Arc::new(
+        config
+            .as_ref()
+            .map(|c| client_detector::ClientDetector::from_config(&c.client_detection))
+            .unwrap_or_default(),
+    );
+
+    // 后处理器可能承担脱敏，配置无效时拒绝启动而不是静默禁用
+    let post_processors = match config.as_ref() {
+        Some(c) => proxycast_core::response_postprocess::PostProcessorChain::from_config(
+            &c.server.response_post_processors,
+        )
+        .map_err(|e| {
+            tracing::error!("[POSTPROCESS] 响应后处理器配置无效: {}", e);
+            e
+        })?,
+        None => Default::default(),
+    };
+    let post_processors = Arc::new(post_processors);
+
+    let session_quota = Arc::new(proxycast_core::session::SessionQuotaLimiter::new(
+        config
+            .as_ref()
+            .map(|c| c.server.session_quota.clone())
+            .unwrap_or_default(),
+    ));
+
+    let concurrency_limiter = Arc::new(
+        config
+            .as_ref()
+            .map(|c| {
+                proxycast_core::middleware::ConcurrencyLimiter::new(
+                    c.server.max_concurrent_requests,
+                    std::time::Duration::from_millis(c.server.concurrency_wait_ms),
+                )
+            })
+            .unwrap_or_default(),
+    );
+    if concurrency_limiter.is_enabled() {
+        tracing::info!(
+            "[CONCURRENCY] 全局并发上限: {}",
+            concurrency_limiter.status().max_concurrent_requests
+        );
+    }
+
     let state = AppState {
         api_key: api_key.to_string(),
+        api_keys: Arc::new(RwLock::new(api_keys)),
         base_url,
         default_provider,
         kiro: Arc::new(RwLock::new(kiro)),
         logs,
-        kiro_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+        kiro_refresh_locks: token_cache.refresh_locks(),
         gemini_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
         pool_service,
         token_cache,
         db,
+        warmup_config: Arc::new(RwLock::new(
+            config
+                .as_ref()
+                .map(|c| c.server.warmup.clone())
+                .unwrap_or_default(),
+        )),
+        pool_models: Arc::new(PoolModelsCache::new()),
+        registry_models: Arc::new(LastKnownModels::new()),
         injector: Arc::new(RwLock::new(injector)),
         injection_enabled: Arc::new(RwLock::new(injection_enabled)),
         processor: processor.clone(),
-        allow_provider_fallback,
+        fallback_settings,
         ws_manager,
         ws_stats,
         hot_reload_manager: hot_reload_manager.clone(),
         request_logger: shared_logger,
+        stream_timeouts: Arc::new(proxycast_infra::telemetry::StreamTimeouts::new()),
         amp_router,
         endpoint_providers,
         kiro_event_service,
         api_key_service,
         batch_executor: Arc::new(tokio::sync::RwLock::new(None)),
+        http_client,
+        provider_clients,
+        validate_tools,
+        anthropic,
+        emulate_multiple_choices,
+        strict_structured_output,
+        allow_upstream_override,
+        force_buffer_stream,
+        stream_heartbeat,
+        bind_host: host.to_string(),
+        bind_port: port,
+        allow_self_upstream,
+        session_quota,
+        concurrency_limiter: concurrency_limiter.clone(),
+        coalesce_tool_calls,
+        post_processors,
+        routing,
+        model_output_limits: Arc::new(OutputLimitsCache::new()),
+        client_detector,
+        selector_prefix: selector_prefix.clone(),
+        count_tokens_cache: Arc::new(CountTokensCache::default()),
+        verbose_selection_trace,
     };
 
+    // 启动预热：后台刷新即将过期的 Token 并解析 project id，失败不影响启动
+    if let (Some(warmup), Some(db)) = (
+        config
+            .as_ref()
+            .map(|c| c.server.warmup.clone())
+            .filter(|w| w.enabled),
+        state.db.clone(),
+    ) {
+        let token_cache = state.token_cache.clone();
+        let pool_service = state.pool_service.clone();
+        tokio::spawn(async move {
+            proxycast_services::provider_warmup_service::run_warmup(
+                db,
+                token_cache,
+                pool_service,
+                &warmup,
+            )
+            .await;
+        });
+    }
+
+    // 服务器停止时中止的后台任务
+    let mut background_tasks = BackgroundTasks::default();
+
+    // 凭证轮换检查：将超过轮换周期的凭证标记为需要轮换
+    if let Some(db) = state.db.clone() {
+        background_tasks.push(
+            proxycast_services::provider_pool_service::spawn_rotation_check_task(
+                state.pool_service.clone(),
+                db,
+                proxycast_services::provider_pool_service::ROTATION_CHECK_INTERVAL,
+            ),
+        );
+    }
+
+    // 凭证选择策略
+    state.pool_service.set_selection_strategy(
+        config
+            .as_ref()
+            .map(|c| c.credentials.selection_strategy)
+            .unwrap_or_default(),
+    );
+
+    // 冷却恢复：冷却期结束后自动恢复因连续错误被标记为不健康的凭证
+    if let Some(db) = state.db.clone() {
+        state.pool_service.set_cooldown_recovery(
+            config
+                .as_ref()
+                .map(|c| c.credentials.cooldown_recovery.clone())
+                .unwrap_or_default(),
+        );
+        background_tasks.push(
+            proxycast_services::provider_pool_service::spawn_cooldown_recovery_task(
+                state.pool_service.clone(),
+                db,
+                proxycast_services::provider_pool_service::COOLDOWN_RECOVERY_INTERVAL,
+            ),
+        );
+    }
+
+    // 预热池：后台维持每个 Provider 类型的最少预热凭证数
+    if let Some(db) = state.db.clone() {
+        background_tasks.push(
+            proxycast_services::provider_warmup_service::spawn_warm_pool_maintainer(
+                db,
+                state.token_cache.clone(),
+                state.pool_service.clone(),
+                state.warmup_config.clone(),
+            ),
+        );
+    }
+
     // 初始化批量任务执行器
     {
         let executor = handlers::batch_executor::BatchTaskExecutor::new(state.clone());
@@ -898,6 +1625,7 @@ async fn run_server(
             logs_clone,
             db_clone,
             config_manager,
+            state.clone(),
         )
         .await
     } else {
@@ -907,6 +1635,32 @@ async fn run_server(
     // 设置请求体大小限制为 100MB，支持大型上下文请求（如 Claude Code 的 /compact 命令）
     let body_limit = 100 * 1024 * 1024; // 100MB
 
+    let stream_idle_timeout_ms = config
+        .as_ref()
+        .map(|c| c.server.stream_idle_timeout_ms)
+        .unwrap_or(300_000);
+    let stream_compat = config
+        .as_ref()
+        .map(|c| c.server.stream_compat.clone())
+        .unwrap_or_default();
+    let stream_coalesce = config
+        .as_ref()
+        .map(|c| c.server.stream_coalesce.clone())
+        .unwrap_or_default();
+    // 流式请求在响应体结束时记录统计；看门狗中止时先标记请求，
+    // 随后丢弃上游流时 record_stream_telemetry 据此记为超时而非客户端断开
+    let stream_timeouts = state.stream_timeouts.clone();
+    let stream_idle_timeout_layer = proxycast_core::middleware::StreamIdleTimeoutLayer::new(
+        std::time::Duration::from_millis(stream_idle_timeout_ms),
+    )
+    .on_timeout(Arc::new(
+        move |event: &proxycast_core::middleware::StreamIdleTimeoutEvent| {
+            if let Some(request_id) = &event.request_id {
+                stream_timeouts.mark(request_id);
+            }
+        },
+    ));
+
     // 创建管理 API 路由（带认证中间件）
     let management_config = config
         .as_ref()
@@ -931,6 +1685,27 @@ async fn run_server(
             "/v0/management/config",
             axum::routing::put(handlers::management_update_config),
         )
+        .route(
+            "/v0/management/log-level",
+            axum::routing::put(handlers::management_set_log_level),
+        )
+        .route("/v0/management/audit", get(handlers::management_list_audit))
+        .route(
+            "/v0/management/diagnostics",
+            get(handlers::management_export_diagnostics),
+        )
+        .route(
+            "/v0/management/effective-config",
+            get(handlers::management_get_effective_config),
+        )
+        .route(
+            "/v0/management/endpoint-providers",
+            get(handlers::management_get_endpoint_providers),
+        )
+        .route(
+            "/v0/management/endpoint-providers/:client_type",
+            axum::routing::put(handlers::management_set_endpoint_enabled),
+        )
         .layer(proxycast_core::middleware::ManagementAuthLayer::new(
             management_config,
         ));
@@ -967,6 +1742,14 @@ async fn run_server(
         .route("/api/batch/tasks", post(handlers::create_batch_task))
         .route("/api/batch/tasks", get(handlers::list_batch_tasks))
         .route("/api/batch/tasks/:id", get(handlers::get_batch_task))
+        .route(
+            "/api/batch/tasks/:id/events",
+            get(handlers::stream_batch_task_events),
+        )
+        .route(
+            "/api/batch/tasks/:id/export",
+            get(handlers::export_batch_task),
+        )
         .route(
             "/api/batch/tasks/:id",
             axum::routing::delete(handlers::cancel_batch_task),
@@ -980,8 +1763,14 @@ async fn run_server(
         );
 
     let app = Router::new()
-        .route("/health", get(health))
-        .route("/v1/models", get(models))
+        .route(
+            "/health",
+            get(|State(state): State<AppState>| async move {
+                health(state.concurrency_limiter.status())
+            }),
+        )
+        .route("/version", get(|| async { version_info() }))
+        .route("/v1/models", get(list_models))
         .route("/v1/routes", get(list_routes))
         .route("/v1/chat/completions", post(
             |State(state): State<AppState>,
@@ -998,6 +1787,13 @@ async fn run_server(
             }
         ))
         .route("/v1/messages/count_tokens", post(count_tokens))
+        // Anthropic Message Batches API（映射到批量任务引擎）
+        .route("/v1/messages/batches", post(handlers::create_message_batch))
+        .route("/v1/messages/batches/:id", get(handlers::get_message_batch))
+        .route(
+            "/v1/messages/batches/:id/results",
+            get(handlers::get_message_batch_results),
+        )
         // 图像生成 API 路由
         .route(
             "/v1/images/generations",
@@ -1006,13 +1802,16 @@ async fn run_server(
         // WebSocket 路由
         .route("/v1/ws", get(handlers::ws_upgrade_handler))
         .route("/ws", get(handlers::ws_upgrade_handler))
-        // 多供应商路由
+        // 多供应商路由（可配置前缀，如 `/p/{selector}/v1/messages`）
         .route(
-            "/{selector}/v1/messages",
+            &proxycast_core::router::selector_route_pattern(&selector_prefix, "/v1/messages"),
             post(anthropic_messages_with_selector),
         )
         .route(
-            "/{selector}/v1/chat/completions",
+            &proxycast_core::router::selector_route_pattern(
+                &selector_prefix,
+                "/v1/chat/completions",
+            ),
             post(chat_completions_with_selector),
         )
         // 管理 API 路由
@@ -1024,8 +1823,59 @@ async fn run_server(
         // 批量任务 API 路由
         .merge(batch_api_routes)
         .layer(DefaultBodyLimit::max(body_limit))
+        // 请求级租约：凭证并发名额等资源在响应体发送完毕后释放
+        .layer(proxycast_core::middleware::RequestLeaseLayer::new())
+        // 全局并发上限：聊天接口排队等待名额，超时返回 503
+        .layer(proxycast_core::middleware::ConcurrencyLimitLayer::new(
+            concurrency_limiter,
+        ))
+        // 相同请求合并：相同的并发非流式请求共享一次上游调用
+        .layer(proxycast_core::middleware::RequestDedupLayer::new(
+            dedup_window,
+        ))
+        // 流式响应合并：把细碎的文本增量合并为较少的客户端帧
+        .layer(proxycast_core::middleware::StreamCoalesceLayer::new(
+            stream_coalesce,
+        ))
+        // 流式响应空闲超时：上游长时间无数据时中止并发送错误帧
+        .layer(stream_idle_timeout_layer)
+        // 流式响应心跳：等待首个上游数据块期间定时发送 ping / keep-alive
+        .layer(proxycast_core::middleware::SseHeartbeatLayer::new(
+            stream_heartbeat,
+        ))
+        // 代理兼容：禁用中间代理缓冲，并在流式响应开头发送冲刷注释
+        .layer(proxycast_core::middleware::StreamCompatLayer::new(
+            stream_compat,
+        ))
+        // 回环检测：拒绝跳数过多的请求，并为上游请求附加 X-ProxyCast-Hop
+        .layer(proxycast_core::middleware::LoopGuardLayer::default())
+        // 请求 ID：回显 X-Request-Id，并为请求内日志附加 request_id
+        .layer(proxycast_core::middleware::RequestIdLayer::new())
+        // 在途请求跟踪：停止服务器时排空或中止
+        .layer(proxycast_core::middleware::InFlightLayer::new(in_flight))
         .with_state(state);
 
+    // 路由别名：在路由匹配前把别名路径改写为规范路径
+    let route_aliases = config
+        .as_ref()
+        .map(|c| c.server.route_aliases.clone())
+        .unwrap_or_default();
+    let route_alias_layer = match proxycast_core::middleware::validate_route_aliases(&route_aliases)
+    {
+        Ok(()) => proxycast_core::middleware::RouteAliasLayer::new(route_aliases),
+        Err(e) => {
+            tracing::warn!("[ROUTE_ALIAS] 路由别名配置无效，已忽略: {}", e);
+            proxycast_core::middleware::RouteAliasLayer::default()
+        }
+    };
+    if !route_alias_layer.is_empty() {
+        tracing::info!(
+            "[ROUTE_ALIAS] 已启用 {} 个路由别名",
+            route_alias_layer.len()
+        );
+    }
+    let app = tower::Layer::layer(&route_alias_layer, app);
+
     let addr: std::net::SocketAddr = format!("{host}:{port}")
         .parse()
         .map_err(|e| format!("无效的监听地址 {host}:{port} - {e}"))?;
@@ -1036,11 +1886,17 @@ async fn run_server(
 
     tracing::info!("Server listening on {}", addr);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown.await;
-        })
-        .await?;
+    axum::serve(
+        listener,
+        // 提供 ConnectInfo，供管理 API 鉴权和审计日志获取来源地址
+        axum::ServiceExt::<axum::extract::Request>::into_make_service_with_connect_info::<
+            std::net::SocketAddr,
+        >(app),
+    )
+    .with_graceful_shutdown(async move {
+        let _ = shutdown.await;
+    })
+    .await?;
 
     Ok(())
 }
@@ -1048,19 +1904,80 @@ async fn run_server(
 async fn count_tokens(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(_request): Json<serde_json::Value>,
+    Json(request): Json<serde_json::Value>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key(&headers, &*state.api_keys.read().await).await {
         return e.into_response();
     }
 
-    // Claude Code 需要这个端点，返回估算值
+    // 选中的 Provider 支持原生计数时转发到上游，否则本地估算
+    let native = native_count_provider(&state, &headers, &request).await;
+    let count = state
+        .count_tokens_cache
+        .count(
+            &request,
+            native.map(|claude| {
+                let request = &request;
+                move || async move {
+                    claude
+                        .count_tokens(request)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+            }),
+        )
+        .await;
+    tracing::debug!(
+        "[COUNT_TOKENS] input_tokens={} source={:?}",
+        count.input_tokens,
+        count.source
+    );
+
     Json(serde_json::json!({
-        "input_tokens": 100
+        "input_tokens": count.input_tokens
     }))
     .into_response()
 }
 
+/// 为 `count_tokens` 选择支持原生计数的上游（Claude / Anthropic API Key 凭证）
+///
+/// 按 `X-Provider-Id` 或默认 Provider 选择凭证，凭证不支持原生计数时返回 `None`。
+async fn native_count_provider(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &serde_json::Value,
+) -> Option<ClaudeCustomProvider> {
+    let db = state.db.as_ref()?;
+    let provider = match headers.get("x-provider-id").and_then(|v| v.to_str().ok()) {
+        Some(provider_id) => provider_id.to_lowercase(),
+        None => state.default_provider.read().await.clone(),
+    };
+    if !matches!(provider.as_str(), "claude" | "anthropic") {
+        return None;
+    }
+
+    let model = request.get("model").and_then(|v| v.as_str());
+    let credential = state
+        .pool_service
+        .select_credential(db, &provider, model)
+        .ok()
+        .flatten()?;
+    let (api_key, base_url, provider_type) = match &credential.credential {
+        CredentialData::ClaudeKey { api_key, base_url } => {
+            (api_key, base_url, proxycast_core::ProviderType::Claude)
+        }
+        CredentialData::AnthropicKey { api_key, base_url } => {
+            (api_key, base_url, proxycast_core::ProviderType::Anthropic)
+        }
+        _ => return None,
+    };
+    Some(ClaudeCustomProvider::with_client(
+        api_key.clone(),
+        base_url.clone(),
+        state.provider_clients.get(provider_type),
+    ))
+}
+
 /// Gemini 原生协议处理
 /// 路由: POST /v1/gemini/{model}:{method}
 /// 例如: /v1/gemini/gemini-3-pro-preview:generateContent
@@ -1071,7 +1988,7 @@ async fn gemini_generate_content(
     Path(path): Path<String>,
     Json(request): Json<serde_json::Value>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key(&headers, &*state.api_keys.read().await).await {
         return e.into_response();
     }
 
@@ -1098,13 +2015,13 @@ async fn gemini_generate_content(
         &format!("[GEMINI] POST /v1/gemini/{path} model={model} method={method}"),
     );
 
-    // 目前只支持 generateContent 方法
+    // 只支持 generateContent 和 streamGenerateContent 方法
     if method != "generateContent" && method != "streamGenerateContent" {
         return (
             StatusCode::BAD_REQUEST,
             Json(serde_json::json!({
                 "error": {
-                    "message": format!("不支持的方法: {}，目前只支持 generateContent", method)
+                    "message": format!("不支持的方法: {}，只支持 generateContent 和 streamGenerateContent", method)
                 }
             })),
         )
@@ -1157,7 +2074,11 @@ async fn gemini_generate_content(
             creds_file_path,
             project_id,
         } => {
-            let mut antigravity = AntigravityProvider::new();
+            let mut antigravity = AntigravityProvider::with_client(
+                state
+                    .provider_clients
+                    .get(proxycast_core::ProviderType::Antigravity),
+            );
             if let Err(e) = antigravity
                 .load_credentials_from_path(creds_file_path)
                 .await
@@ -1216,30 +2137,8 @@ async fn gemini_generate_content(
                 }
             }
 
-            // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            } else if antigravity.project_id.is_none() {
-                // 如果凭证中没有 project_id，尝试从 API 获取或生成随机 ID
-                if let Err(e) = antigravity.discover_project().await {
-                    tracing::warn!("[Antigravity] 获取项目 ID 失败: {}，使用随机生成的 ID", e);
-                    // 生成随机项目 ID
-                    let uuid = uuid::Uuid::new_v4();
-                    let bytes = uuid.as_bytes();
-                    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
-                    let nouns = ["fuze", "wave", "spark", "flow", "core"];
-                    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
-                    let noun = nouns[(bytes[1] as usize) % nouns.len()];
-                    let random_part: String = uuid.to_string()[..5].to_lowercase();
-                    antigravity.project_id = Some(format!("{adj}-{noun}-{random_part}"));
-                }
-            }
-
-            let proj_id = antigravity.project_id.clone().unwrap_or_else(|| {
-                // 最后的后备：生成随机 ID
-                let uuid = uuid::Uuid::new_v4();
-                format!("proxycast-{}", &uuid.to_string()[..8])
-            });
+            // 设置项目 ID（已配置 -> 发现 -> 生成）
+            let proj_id = antigravity.ensure_project_id(project_id.clone()).await;
 
             state
                 .logs
@@ -1260,16 +2159,20 @@ async fn gemini_generate_content(
             );
 
             if is_stream {
-                // 流式响应 - 暂不支持，返回错误
-                return (
-                    StatusCode::NOT_IMPLEMENTED,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": "流式响应暂不支持，请使用 generateContent"
-                        }
-                    })),
-                )
-                    .into_response();
+                // 流式响应：转发上游 SSE 数据块
+                return match antigravity.call_api_sse(&antigravity_request).await {
+                    Ok(resp) => build_gemini_native_stream_response(resp.bytes_stream()),
+                    Err(api_err) => {
+                        state.logs.write().await.add(
+                            "error",
+                            &format!(
+                                "[GEMINI] 流式请求失败 (HTTP {}): {}",
+                                api_err.status_code, api_err.message
+                            ),
+                        );
+                        build_error_response_with_status(api_err.status_code, &api_err.to_string())
+                    }
+                };
             }
 
             // 非流式响应
@@ -1313,6 +2216,9 @@ async fn gemini_generate_content(
         } => {
             // 使用 GeminiProvider 处理 Gemini CLI OAuth 凭证
             let mut gemini = GeminiProvider::new();
+            gemini.client = state
+                .provider_clients
+                .get(proxycast_core::ProviderType::Gemini);
             if let Err(e) = gemini.load_credentials_from_path(creds_file_path).await {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -1350,28 +2256,8 @@ async fn gemini_generate_content(
                 }
             }
 
-            // 设置项目 ID
-            if let Some(pid) = project_id {
-                gemini.project_id = Some(pid.clone());
-            } else if gemini.project_id.is_none() {
-                // 尝试从 API 获取项目 ID
-                if let Err(e) = gemini.discover_project().await {
-                    tracing::warn!("[Gemini CLI] 获取项目 ID 失败: {}，使用随机生成的 ID", e);
-                    let uuid = uuid::Uuid::new_v4();
-                    let bytes = uuid.as_bytes();
-                    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
-                    let nouns = ["fuze", "wave", "spark", "flow", "core"];
-                    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
-                    let noun = nouns[(bytes[1] as usize) % nouns.len()];
-                    let random_part: String = uuid.to_string()[..5].to_lowercase();
-                    gemini.project_id = Some(format!("{adj}-{noun}-{random_part}"));
-                }
-            }
-
-            let proj_id = gemini.project_id.clone().unwrap_or_else(|| {
-                let uuid = uuid::Uuid::new_v4();
-                format!("proxycast-{}", &uuid.to_string()[..8])
-            });
+            // 设置项目 ID（已配置 -> 发现 -> 生成）
+            let proj_id = gemini.ensure_project_id(project_id.clone()).await;
 
             state
                 .logs
@@ -1392,16 +2278,18 @@ async fn gemini_generate_content(
             );
 
             if is_stream {
-                // 流式响应 - 暂不支持
-                return (
-                    StatusCode::NOT_IMPLEMENTED,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": "Gemini CLI 流式响应暂不支持，请使用 generateContent"
-                        }
-                    })),
-                )
-                    .into_response();
+                // 流式响应：转发上游 SSE 数据块
+                return match gemini.call_api_sse(&gemini_request).await {
+                    Ok(resp) => build_gemini_native_stream_response(resp.bytes_stream()),
+                    Err(api_err) => {
+                        state
+                            .logs
+                            .write()
+                            .await
+                            .add("error", &format!("[GEMINI CLI] 流式请求失败: {api_err}"));
+                        build_error_response(&api_err.to_string())
+                    }
+                };
             }
 
             // 非流式响应
@@ -1445,6 +2333,39 @@ async fn gemini_generate_content(
     }
 }
 
+/// 模型列表：只列出凭证池中有可用凭证能提供的模型
+///
+/// 结果按 `POOL_MODELS_TTL` 缓存；没有数据库、读取失败或没有可用模型时返回内置模型列表
+async fn list_models(State(state): State<AppState>) -> Response {
+    let Some(db) = &state.db else {
+        return models().await.into_response();
+    };
+
+    let data = state.pool_models.get_or_load(POOL_MODELS_TTL, || {
+        let overview = state
+            .pool_service
+            .get_overview(db)
+            .map_err(|e| format!("读取凭证池失败: {e}"))?;
+        let read = proxycast_core::database::lock_db(db)
+            .and_then(|conn| {
+                ModelRegistryDao::get_model_providers(&conn).map_err(|e| e.to_string())
+            })
+            .map_err(|e| format!("读取模型注册表失败: {e}"));
+        // 注册表短暂不可用（如数据库被锁住）时使用上次成功读取的结果
+        let registry = state.registry_models.resolve(read)?;
+        Ok(build_pool_model_list(&overview, &registry.models))
+    });
+
+    match data {
+        Some(data) => Json(serde_json::json!({
+            "object": "list",
+            "data": data
+        }))
+        .into_response(),
+        None => models().await.into_response(),
+    }
+}
+
 /// 列出所有可用路由
 async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
     // 处理 base_url：检查 IP 是否有效（在当前网卡列表中或是特殊地址）
@@ -1495,7 +2416,7 @@ async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
     let routes = match &state.db {
         Some(db) => state
             .pool_service
-            .get_available_routes(db, &display_base_url)
+            .get_available_routes(db, &display_base_url, &state.selector_prefix)
             .unwrap_or_default(),
         None => Vec::new(),
     };
@@ -1522,6 +2443,7 @@ async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
         ],
         tags: vec!["默认".to_string()],
         enabled: true,
+        capabilities: proxycast_core::models::route_model::provider_capabilities(&default_provider),
     }];
     all_routes.extend(routes);
 
@@ -1539,52 +2461,111 @@ async fn anthropic_messages_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
-    Json(request): Json<AnthropicMessagesRequest>,
+    Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证
-    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!("Unauthorized request to /{selector}/v1/messages"),
-        );
-        return e.into_response();
+    let api_key_label =
+        match handlers::verify_api_key_anthropic(&headers, &*state.api_keys.read().await).await {
+            Ok(label) => label,
+            Err(e) => {
+                state.logs.write().await.add(
+                    "warn",
+                    &format!(
+                        "Unauthorized request to {}/{selector}/v1/messages",
+                        state.selector_prefix
+                    ),
+                );
+                return e.into_response();
+            }
+        };
+
+    if state.validate_tools {
+        if let Err(message) = validate_anthropic_tools(&request) {
+            return handlers::invalid_tools_response(&state, &message).await;
+        }
+    }
+
+    let anthropic_config = state.anthropic.read().await.clone();
+    if let Err(message) = apply_user_metadata_policy(&mut request, &anthropic_config) {
+        return handlers::invalid_metadata_response(&state, &message).await;
     }
 
+    let session = match handlers::check_session_quota(&state, &headers).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
     state.logs.write().await.add(
         "info",
         &format!(
-            "[REQ] POST /{}/v1/messages model={} stream={}",
-            selector, request.model, request.stream
+            "[REQ] POST {}/{}/v1/messages model={} stream={}",
+            state.selector_prefix, selector, request.model, request.stream
         ),
     );
 
-    // 尝试解析凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => {
-            // 首先尝试按名称查找
-            if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
-                Some(cred)
-            }
-            // 然后尝试按 UUID 查找
-            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
-                Some(cred)
+    // 选择器路由不解析模型别名，按请求的模型裁剪 max_tokens
+    let output_clamp =
+        handlers::output_limits(&state).clamp(&request.model, &mut request.max_tokens);
+    let mut ctx =
+        selector_request_context(&state, &headers, &request.model, request.stream, session);
+    ctx.set_api_key_label(api_key_label);
+    // 与默认路由一致：按配置或请求头缓冲流式请求，并执行响应后处理器
+    let client_stream = request.stream;
+    let buffer = client_stream && should_buffer_stream(&headers, state.force_buffer_stream);
+    let stream = client_stream && !buffer;
+
+    // 按名称或 UUID 指定的凭证优先于同名的故障转移链
+    let pinned = pinned_selector_credential(&state, &selector);
+    if let Some(chain) = selector_failover_chain(&state, &selector, pinned.is_some()).await {
+        ctx.set_route(RequestRoute::Selector(selector.clone()));
+        let chain_call = call_failover_chain(&state, &chain, &headers, &request.model, |cred| {
+            let (state, request, cancel_token) = (&state, &request, &ctx.cancel_token);
+            async move {
+                if let Err(e) = check_anthropic_capabilities(cred.provider_type, request) {
+                    return handlers::unsupported_capability(state, &e).await;
+                }
+                handlers::call_provider_anthropic(state, &cred, request, None, cancel_token).await
             }
-            // 最后尝试按 provider 类型选择（不降级）
-            else if let Ok(Some(cred)) =
+        });
+        let response = watch_disconnect(&state, &ctx, chain_call).await;
+        let response =
+            handlers::finalize_response(&state, response, BufferFormat::Anthropic, stream).await;
+        record_selector_anthropic_tokens(&state, &ctx, &request, &response);
+        let response = record_selector_telemetry(&state, &ctx, response);
+        return annotate_output_clamp(response, output_clamp);
+    }
+
+    // 尝试解析凭证（不降级，指定什么就用什么）：按名称或 UUID 查找，否则按 provider 类型选择
+    let credential = match pinned {
+        Some(cred) => Some((cred, RequestRoute::Selector(selector.clone()))),
+        None => state
+            .db
+            .as_ref()
+            .and_then(|db| {
                 state
                     .pool_service
                     .select_credential(db, &selector, Some(&request.model))
-            {
-                Some(cred)
-            } else {
-                None
-            }
-        }
-        None => None,
+                    .ok()
+                    .flatten()
+            })
+            .map(|cred| (cred, RequestRoute::Pool(selector.clone()))),
     };
 
     match credential {
-        Some(cred) => {
+        Some((mut cred, route)) => {
+            let switch_on_overload = matches!(route, RequestRoute::Pool(_))
+                && fallback_allowed(&state.fallback_settings, &selector, &headers);
+            ctx.set_route(route);
+            set_selector_credential(&mut ctx, &cred);
+            apply_upstream_override(&headers, state.allow_upstream_override, &mut cred);
+            if let Some(resp) = reject_self_upstream(
+                &cred,
+                &state.bind_host,
+                state.bind_port,
+                state.allow_self_upstream,
+            ) {
+                return resp;
+            }
             state.logs.write().await.add(
                 "info",
                 &format!(
@@ -1595,9 +2576,42 @@ async fn anthropic_messages_with_selector(
                 ),
             );
 
+            if let Err(e) = check_anthropic_capabilities(cred.provider_type, &request) {
+                return handlers::unsupported_capability(&state, &e).await;
+            }
+
             // 根据凭证类型调用相应的 Provider
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
+            let model = request.model.clone();
+            let response = call_selector_credential(
+                &state,
+                &mut ctx,
+                &headers,
+                &selector,
+                &model,
+                cred,
+                switch_on_overload,
+                |cred, cancel_token| {
+                    let (state, request) = (&state, &request);
+                    async move {
+                        handlers::call_provider_anthropic(
+                            state,
+                            &cred,
+                            request,
+                            None,
+                            &cancel_token,
+                        )
+                        .await
+                    }
+                },
+            )
+            .await;
+            let response =
+                handlers::finalize_response(&state, response, BufferFormat::Anthropic, stream)
+                    .await;
+            record_selector_anthropic_tokens(&state, &ctx, &request, &response);
+            let response = record_selector_telemetry(&state, &ctx, response);
+            annotate_output_clamp(response, output_clamp)
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1621,51 +2635,305 @@ async fn anthropic_messages_with_selector(
     }
 }
 
+/// 使用选定的凭证处理 OpenAI chat completions 请求
+async fn call_openai_with_credential(
+    state: &AppState,
+    cred: ProviderCredential,
+    mut request: ChatCompletionRequest,
+    cancel_token: &tokio_util::sync::CancellationToken,
+) -> Response {
+    let emulate_choices =
+        match plan_openai_request(cred.provider_type, &request, state.emulate_multiple_choices) {
+            Ok(emulate) => emulate,
+            Err(e) => return handlers::unsupported_capability(state, &e).await,
+        };
+    let structured_output = apply_structured_output(cred.provider_type, &mut request);
+
+    // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
+    let response = if emulate_choices {
+        emulate_multiple_choices(&request, |single| {
+            let cred = &cred;
+            async move {
+                handlers::call_provider_openai(state, cred, &single, None, cancel_token).await
+            }
+        })
+        .await
+    } else {
+        handlers::call_provider_openai(state, &cred, &request, None, cancel_token).await
+    };
+    match structured_output {
+        Some(format) => {
+            handle_structured_response(
+                response,
+                format,
+                request.stream,
+                state.strict_structured_output,
+            )
+            .await
+        }
+        None => response,
+    }
+}
+
+/// 选择器对应的故障转移链（读取当前路由配置，热重载后立即生效）
+///
+/// 按名称或 UUID 匹配到凭证（`pinned`）时使用该凭证，同名的链不生效。
+async fn selector_failover_chain(
+    state: &AppState,
+    selector: &str,
+    pinned: bool,
+) -> Option<FailoverChain> {
+    let chain = state
+        .routing
+        .read()
+        .await
+        .failover_chain(selector)
+        .cloned()?;
+    if pinned {
+        tracing::warn!(
+            "[FAILOVER] 选择器 '{}' 同时匹配凭证名称/UUID 和故障转移链，使用凭证",
+            selector
+        );
+        return None;
+    }
+    Some(chain)
+}
+
+/// 按名称或 UUID 查找选择器指定的凭证
+fn pinned_selector_credential(state: &AppState, selector: &str) -> Option<ProviderCredential> {
+    let db = state.db.as_ref()?;
+    let pool = &state.pool_service;
+    pool.get_by_name(db, selector)
+        .ok()
+        .flatten()
+        .or_else(|| pool.get_by_uuid(db, selector).ok().flatten())
+}
+
+/// 按故障转移链依次尝试成员凭证
+///
+/// 成员返回 429 或 5xx 时记为不健康并换用链中下一个可用成员，整条链都不可用时返回 503。
+/// 所属 Provider 类型已熔断的成员直接跳过。请求携带 `X-ProxyCast-No-Fallback` 时
+/// 只尝试首个可用成员。请求级上游地址覆盖对每个成员生效。
+async fn call_failover_chain<F, Fut>(
+    state: &AppState,
+    chain: &FailoverChain,
+    headers: &HeaderMap,
+    model: &str,
+    call: F,
+) -> Response
+where
+    F: Fn(ProviderCredential) -> Fut,
+    Fut: std::future::Future<Output = Response>,
+{
+    let allow_failover = !no_fallback_requested(headers);
+    let attempt = std::sync::atomic::AtomicUsize::new(0);
+    let (attempt, call) = (&attempt, &call);
+    let walked = match &state.db {
+        Some(db) => {
+            state
+                .pool_service
+                .call_chain(
+                    db,
+                    chain,
+                    Some(model),
+                    allow_failover,
+                    move |mut cred| async move {
+                        let attempt =
+                            attempt.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+                        state.logs.write().await.add(
+                            "info",
+                            &format!(
+                                "[FAILOVER] chain={} attempt={} type={} name={:?} uuid={}",
+                                chain.name,
+                                attempt,
+                                cred.provider_type,
+                                cred.name,
+                                &cred.uuid[..8]
+                            ),
+                        );
+                        apply_upstream_override(headers, state.allow_upstream_override, &mut cred);
+                        if let Some(resp) = reject_self_upstream(
+                            &cred,
+                            &state.bind_host,
+                            state.bind_port,
+                            state.allow_self_upstream,
+                        ) {
+                            return ChainStep::Done(resp);
+                        }
+                        let uuid = cred.uuid.clone();
+                        let permit = match state
+                            .processor
+                            .circuit_breaker
+                            .try_acquire(cred.provider_type, &uuid)
+                        {
+                            Ok(permit) => permit,
+                            Err(open) => {
+                                state.logs.write().await.add(
+                                    "warn",
+                                    &format!(
+                                        "[FAILOVER] chain={} member {} skipped: {}",
+                                        chain.name,
+                                        &uuid[..8],
+                                        open
+                                    ),
+                                );
+                                return ChainStep::Skipped(handlers::circuit_open_response(&open));
+                            }
+                        };
+                        let response = call(cred).await;
+                        let status = response.status();
+                        if proxycast_server_utils::is_local_error(&response) {
+                            permit.release();
+                        } else {
+                            permit.record_status(status.as_u16());
+                        }
+                        if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+                            return ChainStep::Done(response);
+                        }
+                        state.logs.write().await.add(
+                            "warn",
+                            &format!(
+                                "[FAILOVER] chain={} member {} failed with {}",
+                                chain.name,
+                                &uuid[..8],
+                                status.as_u16()
+                            ),
+                        );
+                        ChainStep::Failed {
+                            result: response,
+                            error: format!(
+                                "Failover chain '{}' member failed with HTTP {}",
+                                chain.name,
+                                status.as_u16()
+                            ),
+                        }
+                    },
+                )
+                .await
+        }
+        None => Ok(None),
+    };
+    match walked {
+        Ok(Some(response)) => return response,
+        Ok(None) => {}
+        Err(e) => tracing::error!("[FAILOVER] 链 {} 选择凭证失败: {}", chain.name, e),
+    }
+
+    state.logs.write().await.add(
+        "error",
+        &format!(
+            "[FAILOVER] All {} members of chain '{}' are unavailable",
+            chain.members.len(),
+            chain.name
+        ),
+    );
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({
+            "error": {
+                "message": format!("All credentials in failover chain '{}' are unavailable", chain.name),
+                "type": "provider_unavailable",
+                "code": "failover_chain_exhausted"
+            }
+        })),
+    )
+        .into_response()
+}
+
 /// 带选择器的 OpenAI chat completions 处理
 async fn chat_completions_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
-    Json(request): Json<ChatCompletionRequest>,
+    Json(mut request): Json<ChatCompletionRequest>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!("Unauthorized request to /{selector}/v1/chat/completions"),
-        );
-        return e.into_response();
-    }
+    let api_key_label =
+        match handlers::verify_api_key(&headers, &*state.api_keys.read().await).await {
+            Ok(label) => label,
+            Err(e) => {
+                state.logs.write().await.add(
+                    "warn",
+                    &format!(
+                        "Unauthorized request to {}/{selector}/v1/chat/completions",
+                        state.selector_prefix
+                    ),
+                );
+                return e.into_response();
+            }
+        };
+
+    let session = match handlers::check_session_quota(&state, &headers).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
 
     state.logs.write().await.add(
         "info",
         &format!(
-            "[REQ] POST /{}/v1/chat/completions model={} stream={}",
-            selector, request.model, request.stream
+            "[REQ] POST {}/{}/v1/chat/completions model={} stream={}",
+            state.selector_prefix, selector, request.model, request.stream
         ),
     );
 
-    // 尝试解析凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => {
-            if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
-                Some(cred)
-            } else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
-                Some(cred)
-            } else if let Ok(Some(cred)) =
+    // 选择器路由不解析模型别名，按请求的模型裁剪 max_tokens
+    let output_clamp =
+        handlers::output_limits(&state).clamp(&request.model, &mut request.max_tokens);
+    let mut ctx =
+        selector_request_context(&state, &headers, &request.model, request.stream, session);
+    ctx.set_api_key_label(api_key_label);
+    // 与默认路由一致：按配置或请求头缓冲流式请求，并执行响应后处理器
+    let client_stream = request.stream;
+    let buffer = client_stream && should_buffer_stream(&headers, state.force_buffer_stream);
+    if handlers::aggregates_stream(&state, client_stream, buffer) {
+        request.request_stream_usage();
+    }
+    let stream = client_stream && !buffer;
+
+    // 按名称或 UUID 指定的凭证优先于同名的故障转移链
+    let pinned = pinned_selector_credential(&state, &selector);
+    if let Some(chain) = selector_failover_chain(&state, &selector, pinned.is_some()).await {
+        ctx.set_route(RequestRoute::Selector(selector.clone()));
+        let chain_call = call_failover_chain(&state, &chain, &headers, &request.model, |cred| {
+            call_openai_with_credential(&state, cred, request.clone(), &ctx.cancel_token)
+        });
+        let response = watch_disconnect(&state, &ctx, chain_call).await;
+        let response =
+            handlers::finalize_response(&state, response, BufferFormat::OpenAi, stream).await;
+        let response = record_selector_telemetry(&state, &ctx, response);
+        return annotate_output_clamp(response, output_clamp);
+    }
+
+    // 尝试解析凭证（不降级，指定什么就用什么）：按名称或 UUID 查找，否则按 provider 类型选择
+    let credential = match pinned {
+        Some(cred) => Some((cred, RequestRoute::Selector(selector.clone()))),
+        None => state
+            .db
+            .as_ref()
+            .and_then(|db| {
                 state
                     .pool_service
                     .select_credential(db, &selector, Some(&request.model))
-            {
-                Some(cred)
-            } else {
-                None
-            }
-        }
-        None => None,
+                    .ok()
+                    .flatten()
+            })
+            .map(|cred| (cred, RequestRoute::Pool(selector.clone()))),
     };
 
     match credential {
-        Some(cred) => {
+        Some((mut cred, route)) => {
+            let switch_on_overload = matches!(route, RequestRoute::Pool(_))
+                && fallback_allowed(&state.fallback_settings, &selector, &headers);
+            ctx.set_route(route);
+            set_selector_credential(&mut ctx, &cred);
+            apply_upstream_override(&headers, state.allow_upstream_override, &mut cred);
+            if let Some(resp) = reject_self_upstream(
+                &cred,
+                &state.bind_host,
+                state.bind_port,
+                state.allow_self_upstream,
+            ) {
+                return resp;
+            }
             state.logs.write().await.add(
                 "info",
                 &format!(
@@ -1676,8 +2944,28 @@ async fn chat_completions_with_selector(
                 ),
             );
 
-            // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
+            let model = request.model.clone();
+            let response =
+                call_selector_credential(
+                    &state,
+                    &mut ctx,
+                    &headers,
+                    &selector,
+                    &model,
+                    cred,
+                    switch_on_overload,
+                    |cred, cancel_token| {
+                        let (state, request) = (&state, request.clone());
+                        async move {
+                            call_openai_with_credential(state, cred, request, &cancel_token).await
+                        }
+                    },
+                )
+                .await;
+            let response =
+                handlers::finalize_response(&state, response, BufferFormat::OpenAi, stream).await;
+            let response = record_selector_telemetry(&state, &ctx, response);
+            annotate_output_clamp(response, output_clamp)
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1711,7 +2999,10 @@ async fn anthropic_messages_internal(
 ) -> Response {
     // 检查 token
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let _guard = state
+            .kiro_refresh_locks
+            .lock(DEFAULT_KIRO_REFRESH_KEY)
+            .await;
         let mut kiro = state.kiro.write().await;
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
@@ -1740,8 +3031,11 @@ async fn anthropic_messages_internal(
             if status.is_success() {
                 match resp.bytes().await {
                     Ok(bytes) => {
-                        let body = String::from_utf8_lossy(&bytes).to_string();
-                        let parsed = parse_cw_response(&body);
+                        let parsed =
+                            match parse_cw_response_bytes(&bytes).or_else(CWParseError::recover) {
+                                Ok(parsed) => parsed,
+                                Err(e) => return cw_parse_error_response(&e),
+                            };
                         if request.stream {
                             build_anthropic_stream_response(&request.model, &parsed)
                         } else {
@@ -1776,7 +3070,10 @@ async fn anthropic_messages_internal(
 #[allow(dead_code)]
 async fn chat_completions_internal(state: &AppState, request: &ChatCompletionRequest) -> Response {
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let _guard = state
+            .kiro_refresh_locks
+            .lock(DEFAULT_KIRO_REFRESH_KEY)
+            .await;
         let mut kiro = state.kiro.write().await;
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
@@ -1796,9 +3093,13 @@ async fn chat_completions_internal(state: &AppState, request: &ChatCompletionReq
         Ok(resp) => {
             let status = resp.status();
             if status.is_success() {
-                match resp.text().await {
+                match resp.bytes().await {
                     Ok(body) => {
-                        let parsed = parse_cw_response(&body);
+                        let parsed =
+                            match parse_cw_response_bytes(&body).or_else(CWParseError::recover) {
+                                Ok(parsed) => parsed,
+                                Err(e) => return cw_parse_error_response(&e),
+                            };
                         let has_tool_calls = !parsed.tool_calls.is_empty();
 
                         let message = if has_tool_calls {
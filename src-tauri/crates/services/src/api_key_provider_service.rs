@@ -340,6 +340,11 @@ impl ApiKeyProviderService {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream_options: None,
         };
 
         let resp = provider
@@ -1562,6 +1567,7 @@ impl ApiKeyProviderService {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -1571,6 +1577,12 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported,
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         })
     }
 
@@ -1620,6 +1632,7 @@ impl ApiKeyProviderService {
             usage_count: 0,
             error_count: 0,
             last_used: None,
+            last_success_time: None,
             last_error_time: None,
             last_error_message: None,
             last_health_check_time: None,
@@ -1629,6 +1642,12 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported, // 标记为导入来源
             proxy_url: None,
+            daily_token_limit: None,
+            preferred_models: Vec::new(),
+            rotate_after_days: None,
+            rotated_at: None,
+            needs_rotation: false,
+            max_concurrency: None,
         })
     }
 
@@ -20,6 +20,7 @@
 //! - `live_sync` - 实时同步
 //! - `mcp_sync` - MCP 同步
 //! - `prompt_sync` - Prompt 同步
+//! - `sync_guard` - 外部配置同步冲突检测
 //! - `skill_service` - 技能服务
 //! - `backup_service` - 备份服务
 //! - `material_service` - 素材服务
@@ -40,6 +41,7 @@
 //! - `api_key_provider_service` - API Key Provider 服务
 //! - `provider_pool_service` - Provider 池服务
 //! - `token_cache_service` - Token 缓存服务
+//! - `provider_warmup_service` - Provider 启动预热服务
 
 // 无外部依赖的服务
 pub mod context_memory_service;
@@ -61,6 +63,7 @@ pub mod live_sync;
 pub mod machine_id_service;
 pub mod mcp_sync;
 pub mod prompt_sync;
+pub mod sync_guard;
 pub mod skill_service;
 
 // 依赖 database + models 的服务
@@ -91,4 +94,5 @@ pub mod kiro_event_service;
 pub mod api_key_provider_service;
 pub mod provider_pool_service;
 pub mod provider_type_mapping;
+pub mod provider_warmup_service;
 pub mod token_cache_service;
@@ -1,3 +1,5 @@
+use proxycast_core::config::load_config;
+pub use proxycast_core::config::ConflictPolicy;
 use proxycast_core::models::{AppType, Provider};
 use serde_json::{json, Value};
 use std::fs;
@@ -258,16 +260,35 @@ pub fn get_app_config_path(app_type: &AppType) -> Option<PathBuf> {
     }
 }
 
-/// Sync provider configuration to live config files
+/// 读取配置的 Provider 冲突策略，读取失败时使用默认策略
+pub fn configured_conflict_policy() -> ConflictPolicy {
+    load_config()
+        .map(|c| c.external_sync.provider_conflict_policy)
+        .unwrap_or_default()
+}
+
+/// 按配置的冲突策略同步 provider 配置到外部配置文件
 pub fn sync_to_live(
     app_type: &AppType,
     provider: &Provider,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<LiveSyncReport, Box<dyn std::error::Error + Send + Sync>> {
+    sync_to_live_with_policy(app_type, provider, configured_conflict_policy())
+}
+
+/// 按指定冲突策略同步 provider 配置到外部配置文件
+///
+/// Claude 配置会基于上次同步的快照做三方合并，外部并发修改不会被静默覆盖；
+/// `Manual` 策略下冲突字段保留外部值，并通过返回的报告交由 UI 处理。
+pub fn sync_to_live_with_policy(
+    app_type: &AppType,
+    provider: &Provider,
+    policy: ConflictPolicy,
+) -> Result<LiveSyncReport, Box<dyn std::error::Error + Send + Sync>> {
     match app_type {
-        AppType::Claude => sync_claude_settings(provider),
-        AppType::Codex => sync_codex_config(provider),
-        AppType::Gemini => sync_gemini_config(provider),
-        AppType::ProxyCast => Ok(()),
+        AppType::Claude => sync_claude_settings(provider, policy),
+        AppType::Codex => sync_codex_config(provider).map(|_| LiveSyncReport::default()),
+        AppType::Gemini => sync_gemini_config(provider).map(|_| LiveSyncReport::default()),
+        AppType::ProxyCast => Ok(LiveSyncReport::default()),
     }
 }
 
@@ -300,10 +321,49 @@ pub(crate) fn clean_claude_auth_conflict(settings: &mut Value) {
     }
 }
 
+/// 将 provider 的配置叠加到已有 Claude settings 上
+fn apply_provider_to_claude_settings(
+    mut settings: Value,
+    provider: &Provider,
+) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    // Merge env variables into settings
+    if let Some(env_obj) = provider
+        .settings_config
+        .get("env")
+        .and_then(|v| v.as_object())
+    {
+        if !settings.is_object() {
+            settings = json!({});
+        }
+        let settings_obj = settings.as_object_mut().ok_or("Invalid settings format")?;
+
+        // Ensure env object exists
+        if !settings_obj.contains_key("env") {
+            settings_obj.insert("env".to_string(), json!({}));
+        }
+
+        if let Some(target_env) = settings_obj.get_mut("env").and_then(|v| v.as_object_mut()) {
+            for (key, value) in env_obj {
+                target_env.insert(key.clone(), value.clone());
+                tracing::debug!("设置环境变量: {} = [MASKED]", key);
+            }
+        }
+    } else {
+        // If settings_config is the full settings object, use it directly
+        settings = provider.settings_config.clone();
+        tracing::debug!("使用完整配置对象");
+    }
+
+    // 清理冲突的认证环境变量
+    clean_claude_auth_conflict(&mut settings);
+    Ok(settings)
+}
+
 /// Sync Claude settings to ~/.claude/settings.json
 fn sync_claude_settings(
     provider: &Provider,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    policy: ConflictPolicy,
+) -> Result<LiveSyncReport, Box<dyn std::error::Error + Send + Sync>> {
     let home = dirs::home_dir().ok_or("Cannot find home directory")?;
     let claude_dir = home.join(".claude");
     let config_path = claude_dir.join("settings.json");
@@ -319,7 +379,7 @@ fn sync_claude_settings(
     }
 
     // Read existing settings to preserve other fields
-    let mut settings: Value = if config_path.exists() {
+    let remote: Value = if config_path.exists() {
         let content = std::fs::read_to_string(&config_path)?;
         match serde_json::from_str(&content) {
             Ok(v) => v,
@@ -332,32 +392,18 @@ fn sync_claude_settings(
         json!({})
     };
 
-    // Merge env variables into settings
-    if let Some(env_obj) = provider
-        .settings_config
-        .get("env")
-        .and_then(|v| v.as_object())
-    {
-        let settings_obj = settings.as_object_mut().ok_or("Invalid settings format")?;
-
-        // Ensure env object exists
-        if !settings_obj.contains_key("env") {
-            settings_obj.insert("env".to_string(), json!({}));
-        }
-
-        if let Some(target_env) = settings_obj.get_mut("env").and_then(|v| v.as_object_mut()) {
-            for (key, value) in env_obj {
-                target_env.insert(key.clone(), value.clone());
-                tracing::debug!("设置环境变量: {} = [MASKED]", key);
-            }
-        }
-    } else {
-        // If settings_config is the full settings object, use it directly
-        settings = provider.settings_config.clone();
-        tracing::debug!("使用完整配置对象");
-    }
-
-    // 清理冲突的认证环境变量（在收集环境变量之前）
+    // 三方合并：base 为上次同步写入的快照，没有快照时退化为直接覆盖
+    let base_path = sync_base_path(&AppType::Claude);
+    let base = base_path
+        .as_deref()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .unwrap_or_else(|| remote.clone());
+    let local = apply_provider_to_claude_settings(base.clone(), provider)?;
+    let report = three_way_merge(&base, &local, &remote, policy);
+    report.log("claude");
+
+    let mut settings = report.merged.clone();
     clean_claude_auth_conflict(&mut settings);
 
     // 收集环境变量用于写入 shell 配置（从清理后的 settings 中提取）
@@ -374,6 +420,15 @@ fn sync_claude_settings(
     write_json_file_atomic(&config_path, &settings)?;
     tracing::info!("Claude 配置文件同步完成: {}", config_path.display());
 
+    // 存在未解决的冲突时不更新快照，下次同步仍能检测到冲突
+    if report.conflicts.is_empty() {
+        if let Some(path) = &base_path {
+            if let Err(e) = write_json_file_atomic(path, &settings) {
+                tracing::warn!("保存同步快照失败: {}", e);
+            }
+        }
+    }
+
     // 同时写入 shell 配置文件（后台任务，避免阻塞切换响应）
     if !env_vars_for_shell.is_empty() {
         let env_vars_for_shell = env_vars_for_shell;
@@ -391,7 +446,7 @@ fn sync_claude_settings(
         );
     }
 
-    Ok(())
+    Ok(report)
 }
 
 /// Sync Codex config to ~/.codex/auth.json and ~/.codex/config.toml
@@ -583,6 +638,186 @@ pub struct SyncCheckResult {
     pub conflicts: Vec<ConfigConflict>,
 }
 
+/// 三方合并中无法自动解决的字段冲突
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncConflict {
+    /// 字段路径（如 `env.ANTHROPIC_BASE_URL`），`None` 值表示该侧删除了字段
+    pub field: String,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+}
+
+/// 合并时字段最终采用的一侧
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
+/// 单个字段的合并结果（用于同步日志）
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncResolution {
+    pub field: String,
+    pub winner: SyncSide,
+    /// 是否由冲突策略决定（否则为单侧修改的自动合并）
+    pub conflicted: bool,
+}
+
+/// 同步报告
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LiveSyncReport {
+    /// 合并后的配置
+    pub merged: Value,
+    /// 有变更的字段及胜出的一侧
+    pub resolutions: Vec<SyncResolution>,
+    /// `Manual` 策略下待用户解决的冲突
+    pub conflicts: Vec<SyncConflict>,
+}
+
+impl LiveSyncReport {
+    /// 将合并结果写入同步日志
+    fn log(&self, target: &str) {
+        for resolution in &self.resolutions {
+            tracing::info!(
+                "[LIVE_SYNC] {} field={} winner={:?} conflicted={}",
+                target,
+                resolution.field,
+                resolution.winner,
+                resolution.conflicted
+            );
+        }
+        for conflict in &self.conflicts {
+            tracing::warn!(
+                "[LIVE_SYNC] {} field={} 存在冲突，等待用户处理",
+                target,
+                conflict.field
+            );
+        }
+    }
+}
+
+/// 三方合并
+///
+/// - `base`: 上次同步时的配置快照
+/// - `local`: ProxyCast 期望写入的配置
+/// - `remote`: 当前外部文件中的配置
+///
+/// 只有一侧相对 `base` 修改的字段直接采用该侧；两侧都修改且结果不同的字段
+/// 按 `policy` 解决，`Manual` 时保留外部值并记录冲突。对象按字段递归合并。
+pub fn three_way_merge(
+    base: &Value,
+    local: &Value,
+    remote: &Value,
+    policy: ConflictPolicy,
+) -> LiveSyncReport {
+    let mut report = LiveSyncReport::default();
+    let merged = merge_value(
+        "",
+        Some(base),
+        Some(local),
+        Some(remote),
+        policy,
+        &mut report,
+    );
+    report.merged = merged.unwrap_or_else(|| json!({}));
+    report
+}
+
+fn merge_value(
+    path: &str,
+    base: Option<&Value>,
+    local: Option<&Value>,
+    remote: Option<&Value>,
+    policy: ConflictPolicy,
+    report: &mut LiveSyncReport,
+) -> Option<Value> {
+    if local == remote {
+        return local.cloned();
+    }
+    if local == base {
+        report.resolutions.push(SyncResolution {
+            field: path.to_string(),
+            winner: SyncSide::Remote,
+            conflicted: false,
+        });
+        return remote.cloned();
+    }
+    if remote == base {
+        report.resolutions.push(SyncResolution {
+            field: path.to_string(),
+            winner: SyncSide::Local,
+            conflicted: false,
+        });
+        return local.cloned();
+    }
+
+    // 两侧都是对象时逐字段合并
+    if let (Some(Value::Object(l)), Some(Value::Object(r))) = (local, remote) {
+        let empty = serde_json::Map::new();
+        let b = base.and_then(|v| v.as_object()).unwrap_or(&empty);
+        let mut keys: Vec<&String> = b.keys().chain(l.keys()).chain(r.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        let mut merged = serde_json::Map::new();
+        for key in keys {
+            let child_path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            if let Some(value) = merge_value(
+                &child_path,
+                b.get(key),
+                l.get(key),
+                r.get(key),
+                policy,
+                report,
+            ) {
+                merged.insert(key.clone(), value);
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+
+    // 真正的冲突
+    let winner = match policy {
+        ConflictPolicy::PreferLocal => SyncSide::Local,
+        ConflictPolicy::PreferRemote => SyncSide::Remote,
+        ConflictPolicy::Manual => {
+            report.conflicts.push(SyncConflict {
+                field: path.to_string(),
+                local: local.cloned(),
+                remote: remote.cloned(),
+            });
+            return remote.cloned();
+        }
+    };
+    report.resolutions.push(SyncResolution {
+        field: path.to_string(),
+        winner,
+        conflicted: true,
+    });
+    match winner {
+        SyncSide::Local => local.cloned(),
+        SyncSide::Remote => remote.cloned(),
+    }
+}
+
+/// 获取同步快照路径（~/.proxycast/live_sync/<app>.json）
+fn sync_base_path(app_type: &AppType) -> Option<PathBuf> {
+    let name = match app_type {
+        AppType::Claude => "claude_settings.json",
+        _ => return None,
+    };
+    Some(
+        dirs::home_dir()?
+            .join(".proxycast")
+            .join("live_sync")
+            .join(name),
+    )
+}
+
 /// 从外部配置文件解析当前生效的 provider
 pub fn parse_current_provider_from_live(
     app_type: &AppType,
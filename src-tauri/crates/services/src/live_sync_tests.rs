@@ -316,11 +316,173 @@ mod tests {
         }
     }
 
+    // ============================================================================
+    // 模块 4: 三方合并测试
+    // ============================================================================
+
+    #[cfg(test)]
+    mod three_way_merge_tests {
+        use super::*;
+
+        /// 上次同步快照
+        fn base() -> serde_json::Value {
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://old.example.com",
+                    "ANTHROPIC_AUTH_TOKEN": "token-old"
+                },
+                "model": "sonnet"
+            })
+        }
+
+        /// ProxyCast 切换 provider 后的期望值
+        fn local() -> serde_json::Value {
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://proxycast.local",
+                    "ANTHROPIC_AUTH_TOKEN": "token-old"
+                },
+                "model": "sonnet"
+            })
+        }
+
+        /// 外部同时修改了同一字段，并新增了其他字段
+        fn remote() -> serde_json::Value {
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://external.example.com",
+                    "ANTHROPIC_AUTH_TOKEN": "token-old"
+                },
+                "model": "sonnet",
+                "permissions": { "allow": ["Bash"] }
+            })
+        }
+
+        /// **Feature: three-way-merge, Property 1: 单侧修改自动合并**
+        #[test]
+        fn test_non_conflicting_edits_merge() {
+            let remote = json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://old.example.com",
+                    "ANTHROPIC_AUTH_TOKEN": "token-old"
+                },
+                "model": "opus"
+            });
+
+            let report = three_way_merge(&base(), &local(), &remote, ConflictPolicy::Manual);
+
+            assert!(report.conflicts.is_empty());
+            assert_eq!(
+                report.merged["env"]["ANTHROPIC_BASE_URL"],
+                "https://proxycast.local"
+            );
+            assert_eq!(report.merged["model"], "opus");
+            // 只有本地修改的子树整体采用本地值
+            assert!(report.resolutions.contains(&SyncResolution {
+                field: "env".to_string(),
+                winner: SyncSide::Local,
+                conflicted: false,
+            }));
+            assert!(report.resolutions.contains(&SyncResolution {
+                field: "model".to_string(),
+                winner: SyncSide::Remote,
+                conflicted: false,
+            }));
+        }
+
+        /// **Feature: three-way-merge, Property 2: PreferLocal 冲突以本地为准**
+        #[test]
+        fn test_prefer_local_policy() {
+            let report = three_way_merge(&base(), &local(), &remote(), ConflictPolicy::PreferLocal);
+
+            assert!(report.conflicts.is_empty());
+            assert_eq!(
+                report.merged["env"]["ANTHROPIC_BASE_URL"],
+                "https://proxycast.local"
+            );
+            // 外部新增的字段不会丢失
+            assert_eq!(report.merged["permissions"]["allow"][0], "Bash");
+            assert!(report.resolutions.contains(&SyncResolution {
+                field: "env.ANTHROPIC_BASE_URL".to_string(),
+                winner: SyncSide::Local,
+                conflicted: true,
+            }));
+        }
+
+        /// **Feature: three-way-merge, Property 3: PreferRemote 冲突以外部为准**
+        #[test]
+        fn test_prefer_remote_policy() {
+            let report =
+                three_way_merge(&base(), &local(), &remote(), ConflictPolicy::PreferRemote);
+
+            assert!(report.conflicts.is_empty());
+            assert_eq!(
+                report.merged["env"]["ANTHROPIC_BASE_URL"],
+                "https://external.example.com"
+            );
+            assert_eq!(report.merged["permissions"]["allow"][0], "Bash");
+            assert!(report.resolutions.contains(&SyncResolution {
+                field: "env.ANTHROPIC_BASE_URL".to_string(),
+                winner: SyncSide::Remote,
+                conflicted: true,
+            }));
+        }
+
+        /// **Feature: three-way-merge, Property 4: Manual 返回冲突列表且不覆盖外部值**
+        #[test]
+        fn test_manual_policy_surfaces_conflicts() {
+            let report = three_way_merge(&base(), &local(), &remote(), ConflictPolicy::Manual);
+
+            assert_eq!(
+                report.conflicts,
+                vec![SyncConflict {
+                    field: "env.ANTHROPIC_BASE_URL".to_string(),
+                    local: Some(json!("https://proxycast.local")),
+                    remote: Some(json!("https://external.example.com")),
+                }]
+            );
+            assert_eq!(
+                report.merged["env"]["ANTHROPIC_BASE_URL"],
+                "https://external.example.com"
+            );
+            assert_eq!(report.merged["permissions"]["allow"][0], "Bash");
+        }
+
+        /// **Feature: three-way-merge, Property 5: 删除与修改冲突**
+        #[test]
+        fn test_delete_vs_modify_conflict() {
+            let mut remote = base();
+            remote["env"]
+                .as_object_mut()
+                .unwrap()
+                .remove("ANTHROPIC_BASE_URL");
+
+            let report = three_way_merge(&base(), &local(), &remote, ConflictPolicy::Manual);
+            assert_eq!(report.conflicts.len(), 1);
+            assert_eq!(report.conflicts[0].remote, None);
+
+            let report = three_way_merge(&base(), &local(), &remote, ConflictPolicy::PreferRemote);
+            assert!(report.merged["env"].get("ANTHROPIC_BASE_URL").is_none());
+        }
+
+        /// **Feature: three-way-merge, Property 6: 无快照时与直接覆盖一致**
+        #[test]
+        fn test_no_base_behaves_like_overwrite() {
+            let remote = remote();
+            let mut local = remote.clone();
+            local["env"]["ANTHROPIC_BASE_URL"] = json!("https://proxycast.local");
+
+            let report = three_way_merge(&remote, &local, &remote, ConflictPolicy::Manual);
+            assert!(report.conflicts.is_empty());
+            assert_eq!(report.merged, local);
+        }
+    }
+
     // ============================================================================
     // 总结
     // ============================================================================
     //
-    // 本测试模块包含 3 个子模块，共 10 个单元测试：
+    // 本测试模块包含 4 个子模块，共 16 个单元测试：
     //
     // 1. **原子写入测试** (3 个测试)
     //    - 正常写入、备份创建、JSON 往返
@@ -331,6 +493,9 @@ mod tests {
     // 3. **Shell 配置写入测试** (1 个测试)
     //    - 特殊字符转义验证
     //
+    // 4. **三方合并测试** (6 个测试)
+    //    - 单侧修改、PreferLocal、PreferRemote、Manual、删除冲突、无快照
+    //
     // **注意**：由于 `sync_claude_settings`、`write_env_to_shell_config` 等函数
     // 依赖于真实的文件系统路径（如 ~/.claude、~/.zshrc），完整的集成测试
     // 应该在 `tests/` 目录下的集成测试中进行。
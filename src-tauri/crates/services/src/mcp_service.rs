@@ -1,4 +1,5 @@
 use crate::mcp_sync;
+use crate::sync_guard;
 use proxycast_core::database::dao::mcp::McpDao;
 use proxycast_core::database::DbConnection;
 use proxycast_core::models::mcp_model::ConfigValidationError;
@@ -64,14 +65,17 @@ impl McpService {
             return Err(format!("配置验证失败: {}", error_msgs.join("; ")));
         }
 
-        let conn = db.lock().map_err(|e| e.to_string())?;
-        McpDao::insert(&conn, &server).map_err(|e| e.to_string())?;
+        // 同步失败（含外部修改冲突）时回滚数据库变更
+        let mut conn = db.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        McpDao::insert(&tx, &server).map_err(|e| e.to_string())?;
 
         // Sync to enabled apps
-        let servers = McpDao::get_all(&conn).map_err(|e| e.to_string())?;
-        mcp_sync::sync_all_mcp_to_live(&servers).map_err(|e| e.to_string())?;
+        let servers = McpDao::get_all(&tx).map_err(|e| e.to_string())?;
+        mcp_sync::sync_all_mcp_to_live(&servers, sync_guard::configured_policy())
+            .map_err(|e| e.to_string())?;
 
-        Ok(())
+        tx.commit().map_err(|e| e.to_string())
     }
 
     pub fn update(db: &DbConnection, server: McpServer) -> Result<(), String> {
@@ -82,24 +86,28 @@ impl McpService {
             return Err(format!("配置验证失败: {}", error_msgs.join("; ")));
         }
 
-        let conn = db.lock().map_err(|e| e.to_string())?;
-        McpDao::update(&conn, &server).map_err(|e| e.to_string())?;
+        // 同步失败（含外部修改冲突）时回滚数据库变更
+        let mut conn = db.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        McpDao::update(&tx, &server).map_err(|e| e.to_string())?;
 
         // Sync to enabled apps
-        let servers = McpDao::get_all(&conn).map_err(|e| e.to_string())?;
-        mcp_sync::sync_all_mcp_to_live(&servers).map_err(|e| e.to_string())?;
+        let servers = McpDao::get_all(&tx).map_err(|e| e.to_string())?;
+        mcp_sync::sync_all_mcp_to_live(&servers, sync_guard::configured_policy())
+            .map_err(|e| e.to_string())?;
 
-        Ok(())
+        tx.commit().map_err(|e| e.to_string())
     }
 
     pub fn delete(db: &DbConnection, id: &str) -> Result<(), String> {
-        let conn = db.lock().map_err(|e| e.to_string())?;
-        McpDao::delete(&conn, id).map_err(|e| e.to_string())?;
+        let mut conn = db.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        McpDao::delete(&tx, id).map_err(|e| e.to_string())?;
 
         // Remove from all apps
         mcp_sync::remove_mcp_from_all_apps(id).map_err(|e| e.to_string())?;
 
-        Ok(())
+        tx.commit().map_err(|e| e.to_string())
     }
 
     pub fn toggle_enabled(
@@ -108,32 +116,40 @@ impl McpService {
         app_type: &str,
         enabled: bool,
     ) -> Result<(), String> {
-        let conn = db.lock().map_err(|e| e.to_string())?;
-        McpDao::toggle_enabled(&conn, id, app_type, enabled).map_err(|e| e.to_string())?;
+        // 同步失败（含外部修改冲突）时回滚数据库变更
+        let mut conn = db.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        McpDao::toggle_enabled(&tx, id, app_type, enabled).map_err(|e| e.to_string())?;
 
         // Get the server and sync
-        let servers = McpDao::get_all(&conn).map_err(|e| e.to_string())?;
+        let servers = McpDao::get_all(&tx).map_err(|e| e.to_string())?;
         let server = servers.iter().find(|s| s.id == id);
 
         if let Some(_server) = server {
             let app = app_type.parse::<AppType>().map_err(|e| e.to_string())?;
             if enabled {
                 // Sync server to the app
-                mcp_sync::sync_mcp_to_app(&app, &servers).map_err(|e| e.to_string())?;
+                let outcome =
+                    mcp_sync::sync_mcp_to_app(&app, &servers, sync_guard::configured_policy())
+                        .map_err(|e| e.to_string())?;
+                if let Some(message) = outcome.conflict_message(&format!("{app} MCP 配置")) {
+                    return Err(message);
+                }
             } else {
                 // Remove server from the app
                 mcp_sync::remove_mcp_from_app(&app, id).map_err(|e| e.to_string())?;
             }
         }
 
-        Ok(())
+        tx.commit().map_err(|e| e.to_string())
     }
 
     /// Sync all enabled MCP servers to all apps
     pub fn sync_all_to_live(db: &DbConnection) -> Result<(), String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
         let servers = McpDao::get_all(&conn).map_err(|e| e.to_string())?;
-        mcp_sync::sync_all_mcp_to_live(&servers).map_err(|e| e.to_string())
+        mcp_sync::sync_all_mcp_to_live(&servers, sync_guard::configured_policy())
+            .map_err(|e| e.to_string())
     }
 
     /// Import MCP servers from an app
@@ -1,6 +1,8 @@
+use crate::sync_guard::{self, SyncOutcome};
+use proxycast_core::config::ExternalSyncPolicy;
 use proxycast_core::models::{AppType, McpServer};
 use serde_json::{json, Map, Value};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// P0 安全修复：校验 TOML 键名是否合法（仅允许字母、数字、下划线和连字符）
 fn is_valid_toml_key(key: &str) -> bool {
@@ -20,7 +22,6 @@ fn escape_toml_string(s: &str) -> String {
 }
 
 /// Get the MCP config file path for an app type
-#[allow(dead_code)]
 pub fn get_mcp_config_path(app_type: &AppType) -> Option<PathBuf> {
     let home = dirs::home_dir()?;
     match app_type {
@@ -32,29 +33,67 @@ pub fn get_mcp_config_path(app_type: &AppType) -> Option<PathBuf> {
 }
 
 /// Sync all enabled MCP servers to their respective app configurations
+///
+/// All apps are conflict-checked before anything is written: if any app's config
+/// was edited externally and could not be merged, no app is synced and the
+/// conflicts are reported together in the returned error.
 pub fn sync_all_mcp_to_live(
     servers: &[McpServer],
+    policy: ExternalSyncPolicy,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Sync to Claude
-    let claude_servers: Vec<&McpServer> = servers.iter().filter(|s| s.enabled_claude).collect();
-    sync_mcp_to_claude(&claude_servers)?;
-
-    // Sync to Codex
-    let codex_servers: Vec<&McpServer> = servers.iter().filter(|s| s.enabled_codex).collect();
-    sync_mcp_to_codex(&codex_servers)?;
-
-    // Sync to Gemini
-    let gemini_servers: Vec<&McpServer> = servers.iter().filter(|s| s.enabled_gemini).collect();
-    sync_mcp_to_gemini(&gemini_servers)?;
-
-    Ok(())
+    let state_dir = sync_guard::default_state_dir().ok_or("Cannot find home directory")?;
+    let mut targets = Vec::new();
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+        if let Some(target) = prepare_mcp_sync(&app_type, servers)? {
+            targets.push(target);
+        }
+    }
+    sync_mcp_targets(&state_dir, targets, policy)
 }
 
 /// Sync MCP servers to a specific app
 pub fn sync_mcp_to_app(
     app_type: &AppType,
     servers: &[McpServer],
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    policy: ExternalSyncPolicy,
+) -> Result<SyncOutcome, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(target) = prepare_mcp_sync(app_type, servers)? else {
+        return Ok(SyncOutcome::Written);
+    };
+    let state_dir = sync_guard::default_state_dir().ok_or("Cannot find home directory")?;
+    let outcome = sync_guard::guarded_sync(
+        &state_dir,
+        &target.key,
+        target.current.as_ref(),
+        &target.desired,
+        policy,
+        target.write,
+    )?;
+    Ok(outcome)
+}
+
+/// 将最终 MCP 配置写回外部文件
+type McpWriter = Box<dyn FnOnce(&Value) -> Result<(), String>>;
+
+/// 单个应用的 MCP 同步目标
+struct McpSyncTarget {
+    /// 应用名称（用于冲突信息）
+    label: String,
+    /// 同步记录键
+    key: String,
+    /// 外部文件中当前的 MCP 配置，`None` 表示文件不存在
+    current: Option<Value>,
+    /// ProxyCast 期望写入的 MCP 配置
+    desired: Value,
+    /// 将最终内容写回外部文件
+    write: McpWriter,
+}
+
+/// 读取应用当前的 MCP 配置并计算期望内容（不写入）
+fn prepare_mcp_sync(
+    app_type: &AppType,
+    servers: &[McpServer],
+) -> Result<Option<McpSyncTarget>, Box<dyn std::error::Error + Send + Sync>> {
     let enabled_servers: Vec<&McpServer> = servers
         .iter()
         .filter(|s| match app_type {
@@ -65,31 +104,66 @@ pub fn sync_mcp_to_app(
         })
         .collect();
 
-    match app_type {
-        AppType::Claude => sync_mcp_to_claude(&enabled_servers),
-        AppType::Codex => sync_mcp_to_codex(&enabled_servers),
-        AppType::Gemini => sync_mcp_to_gemini(&enabled_servers),
-        AppType::ProxyCast => Ok(()),
-    }
+    let Some(config_path) = get_mcp_config_path(app_type) else {
+        return Ok(None);
+    };
+    let target = match app_type {
+        AppType::Codex => prepare_codex_config(&config_path, &enabled_servers)?,
+        _ => prepare_json_settings(app_type, &config_path, &enabled_servers)?,
+    };
+    Ok(Some(target))
 }
 
-/// Sync MCP servers to Claude's ~/.claude/settings.json
-/// Claude uses the mcpServers field in ~/.claude/settings.json
-fn sync_mcp_to_claude(
-    servers: &[&McpServer],
+/// 先对所有目标做冲突检测，全部可写时才依次写入
+///
+/// 任一目标冲突时不写入任何文件，避免部分应用已同步、部分未同步。
+fn sync_mcp_targets(
+    state_dir: &Path,
+    targets: Vec<McpSyncTarget>,
+    policy: ExternalSyncPolicy,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let home = dirs::home_dir().ok_or("Cannot find home directory")?;
-    let claude_dir = home.join(".claude");
-    let config_path = claude_dir.join("settings.json");
+    let mut conflicts = Vec::new();
+    for target in &targets {
+        let record = sync_guard::load_record(state_dir, &target.key);
+        let outcome = sync_guard::plan_sync(
+            record.as_ref(),
+            target.current.as_ref(),
+            &target.desired,
+            policy,
+        );
+        if let Some(message) = outcome.conflict_message(&format!("{} MCP 配置", target.label)) {
+            sync_guard::record_outcome(state_dir, &target.key, &outcome);
+            conflicts.push(message);
+        }
+    }
+    if !conflicts.is_empty() {
+        return Err(conflicts.join("; ").into());
+    }
 
-    // Ensure .claude directory exists
-    if !claude_dir.exists() {
-        std::fs::create_dir_all(&claude_dir)?;
+    for target in targets {
+        sync_guard::guarded_sync(
+            state_dir,
+            &target.key,
+            target.current.as_ref(),
+            &target.desired,
+            policy,
+            target.write,
+        )?;
     }
+    Ok(())
+}
 
+/// Prepare syncing MCP servers into the mcpServers field of a JSON settings file
+///
+/// Claude and Gemini both use the mcpServers field of their settings.json.
+fn prepare_json_settings(
+    app_type: &AppType,
+    settings_path: &Path,
+    servers: &[&McpServer],
+) -> Result<McpSyncTarget, Box<dyn std::error::Error + Send + Sync>> {
     // Read existing settings
-    let mut settings: Value = if config_path.exists() {
-        let content = std::fs::read_to_string(&config_path)?;
+    let mut settings: Value = if settings_path.exists() {
+        let content = std::fs::read_to_string(settings_path)?;
         serde_json::from_str(&content).unwrap_or_else(|_| json!({}))
     } else {
         json!({})
@@ -103,147 +177,189 @@ fn sync_mcp_to_claude(
         }
     }
 
-    // Update settings with mcpServers
-    if let Some(obj) = settings.as_object_mut() {
-        obj.insert("mcpServers".to_string(), Value::Object(mcp_servers));
-    }
+    let current = settings.get("mcpServers").cloned();
+    let settings_path = settings_path.to_path_buf();
+    Ok(McpSyncTarget {
+        label: app_type.to_string(),
+        key: mcp_sync_key(app_type),
+        current,
+        desired: Value::Object(mcp_servers),
+        write: Box::new(move |merged| {
+            if let Some(parent) = settings_path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
 
-    // Write settings
-    let content = serde_json::to_string_pretty(&settings)?;
-    std::fs::write(&config_path, content)?;
+            // Update settings with mcpServers
+            if let Some(obj) = settings.as_object_mut() {
+                obj.insert("mcpServers".to_string(), merged.clone());
+            }
 
-    Ok(())
+            // Write settings
+            let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+            std::fs::write(&settings_path, content).map_err(|e| e.to_string())
+        }),
+    })
 }
 
-/// Sync MCP servers to Codex's config.toml
-/// Codex uses [mcp_servers.*] sections in ~/.codex/config.toml
-fn sync_mcp_to_codex(
-    servers: &[&McpServer],
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let home = dirs::home_dir().ok_or("Cannot find home directory")?;
-    let codex_dir = home.join(".codex");
-    let config_path = codex_dir.join("config.toml");
-
-    // Create directory if not exists
-    std::fs::create_dir_all(&codex_dir)?;
+fn mcp_sync_key(app_type: &AppType) -> String {
+    format!("mcp_{}", app_type.to_string().to_lowercase())
+}
 
-    // Read existing config
-    let existing_content = if config_path.exists() {
-        std::fs::read_to_string(&config_path)?
-    } else {
-        String::new()
+/// Record the MCP servers after a targeted removal so they are not seen as an external edit
+fn record_mcp_servers(app_type: &AppType, mcp_servers: &Value) {
+    let Some(dir) = sync_guard::default_state_dir() else {
+        return;
     };
+    if let Err(e) = sync_guard::save_record(&dir, &mcp_sync_key(app_type), mcp_servers) {
+        tracing::warn!("[MCP Sync] 保存同步记录失败: {}", e);
+    }
+}
 
-    // Remove existing [mcp_servers.*] sections
-    let lines: Vec<&str> = existing_content.lines().collect();
-    let mut new_lines: Vec<String> = Vec::new();
-    let mut in_mcp_section = false;
+/// Split Codex's config.toml into non-MCP lines and [mcp_servers.*] sections
+///
+/// Sections are grouped by server name (including the `.env` sub-table); each
+/// server maps to its section text so it can be compared and merged per server.
+fn split_codex_config(content: &str) -> (Vec<String>, Map<String, Value>) {
+    let mut other_lines = Vec::new();
+    let mut sections: Map<String, Value> = Map::new();
+    let mut current: Option<String> = None;
 
-    for line in &lines {
+    for line in content.lines() {
         let trimmed = line.trim();
-        if trimmed.starts_with("[mcp_servers.") {
-            in_mcp_section = true;
-            continue;
+        if let Some(header) = trimmed
+            .strip_prefix("[mcp_servers.")
+            .and_then(|s| s.strip_suffix(']'))
+        {
+            let name = header.split('.').next().unwrap_or(header).to_string();
+            current = Some(name);
+        } else if trimmed.starts_with('[') {
+            current = None;
         }
-        if in_mcp_section && trimmed.starts_with('[') {
-            in_mcp_section = false;
+
+        match &current {
+            Some(name) => {
+                let entry = sections
+                    .entry(name.clone())
+                    .or_insert_with(|| Value::String(String::new()));
+                if let Value::String(text) = entry {
+                    if !text.is_empty() {
+                        text.push('\n');
+                    }
+                    text.push_str(line);
+                }
+            }
+            None => other_lines.push(line.to_string()),
         }
-        if !in_mcp_section {
-            new_lines.push(line.to_string());
+    }
+
+    while other_lines.last().is_some_and(|l| l.trim().is_empty()) {
+        other_lines.pop();
+    }
+    for section in sections.values_mut() {
+        if let Value::String(text) = section {
+            text.truncate(text.trim_end().len());
         }
     }
+    (other_lines, sections)
+}
 
-    // Add new MCP server sections - use name as key
-    for server in servers {
-        // P0 安全修复：校验 server.name 防止 TOML 注入
-        if !is_valid_toml_key(&server.name) {
-            tracing::warn!(
-                "[MCP Sync] 跳过无效的服务器名称: {} (仅允许字母、数字、下划线和连字符)",
-                server.name
-            );
-            continue;
+/// Render Codex's config.toml from non-MCP lines and [mcp_servers.*] sections
+fn render_codex_config(other_lines: &[String], sections: &Value) -> String {
+    let mut lines = other_lines.to_vec();
+    if let Some(sections) = sections.as_object() {
+        for text in sections.values().filter_map(|v| v.as_str()) {
+            lines.push(String::new());
+            lines.push(text.to_string());
         }
+    }
+    lines.join("\n")
+}
 
-        new_lines.push(String::new());
-        new_lines.push(format!("[mcp_servers.{}]", server.name));
+/// Render one server's [mcp_servers.*] section, `None` if the name is not a valid TOML key
+fn render_codex_server(server: &McpServer) -> Option<String> {
+    // P0 安全修复：校验 server.name 防止 TOML 注入
+    if !is_valid_toml_key(&server.name) {
+        tracing::warn!(
+            "[MCP Sync] 跳过无效的服务器名称: {} (仅允许字母、数字、下划线和连字符)",
+            server.name
+        );
+        return None;
+    }
 
-        if let Some(config) = server.server_config.as_object() {
-            // Convert JSON config to TOML format
-            if let Some(command) = config.get("command").and_then(|v| v.as_str()) {
-                // P0 安全修复：转义 TOML 字符串值
-                new_lines.push(format!("command = \"{}\"", escape_toml_string(command)));
-            }
+    let mut lines = vec![format!("[mcp_servers.{}]", server.name)];
 
-            if let Some(args) = config.get("args").and_then(|v| v.as_array()) {
-                let args_str: Vec<String> = args
-                    .iter()
-                    .filter_map(|a| a.as_str())
-                    .map(|s| format!("\"{}\"", escape_toml_string(s)))
-                    .collect();
-                new_lines.push(format!("args = [{}]", args_str.join(", ")));
-            }
+    if let Some(config) = server.server_config.as_object() {
+        // Convert JSON config to TOML format
+        if let Some(command) = config.get("command").and_then(|v| v.as_str()) {
+            // P0 安全修复：转义 TOML 字符串值
+            lines.push(format!("command = \"{}\"", escape_toml_string(command)));
+        }
 
-            if let Some(env) = config.get("env").and_then(|v| v.as_object()) {
-                new_lines.push("[mcp_servers.".to_string() + &server.name + ".env]");
-                for (key, value) in env {
-                    // P0 安全修复：校验 env key 并转义值
-                    if !is_valid_toml_key(key) {
-                        tracing::warn!("[MCP Sync] 跳过无效的环境变量名: {}", key);
-                        continue;
-                    }
-                    if let Some(val) = value.as_str() {
-                        new_lines.push(format!("{} = \"{}\"", key, escape_toml_string(val)));
-                    }
+        if let Some(args) = config.get("args").and_then(|v| v.as_array()) {
+            let args_str: Vec<String> = args
+                .iter()
+                .filter_map(|a| a.as_str())
+                .map(|s| format!("\"{}\"", escape_toml_string(s)))
+                .collect();
+            lines.push(format!("args = [{}]", args_str.join(", ")));
+        }
+
+        if let Some(env) = config.get("env").and_then(|v| v.as_object()) {
+            lines.push("[mcp_servers.".to_string() + &server.name + ".env]");
+            for (key, value) in env {
+                // P0 安全修复：校验 env key 并转义值
+                if !is_valid_toml_key(key) {
+                    tracing::warn!("[MCP Sync] 跳过无效的环境变量名: {}", key);
+                    continue;
+                }
+                if let Some(val) = value.as_str() {
+                    lines.push(format!("{} = \"{}\"", key, escape_toml_string(val)));
                 }
             }
         }
     }
 
-    // Write config
-    let content = new_lines.join("\n");
-    std::fs::write(&config_path, content)?;
-
-    Ok(())
+    Some(lines.join("\n"))
 }
 
-/// Sync MCP servers to Gemini's settings.json
-/// Gemini uses the mcpServers field in ~/.gemini/settings.json
-fn sync_mcp_to_gemini(
+/// Prepare syncing MCP servers to Codex's config.toml
+/// Codex uses [mcp_servers.*] sections in ~/.codex/config.toml
+///
+/// The sections are guarded per server like the JSON apps' mcpServers object.
+fn prepare_codex_config(
+    config_path: &Path,
     servers: &[&McpServer],
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let home = dirs::home_dir().ok_or("Cannot find home directory")?;
-    let gemini_dir = home.join(".gemini");
-    let settings_path = gemini_dir.join("settings.json");
-
-    // Create directory if not exists
-    std::fs::create_dir_all(&gemini_dir)?;
-
-    // Read existing settings
-    let mut settings: Value = if settings_path.exists() {
-        let content = std::fs::read_to_string(&settings_path)?;
-        serde_json::from_str(&content).unwrap_or_else(|_| json!({}))
+) -> Result<McpSyncTarget, Box<dyn std::error::Error + Send + Sync>> {
+    // Read existing config
+    let existing_content = if config_path.exists() {
+        Some(std::fs::read_to_string(config_path)?)
     } else {
-        json!({})
+        None
     };
+    let (other_lines, sections) = split_codex_config(existing_content.as_deref().unwrap_or(""));
 
-    // Build mcpServers object - use name as key
-    let mut mcp_servers = Map::new();
+    // Add new MCP server sections - use name as key
+    let mut desired = Map::new();
     for server in servers {
-        if let Some(config) = server.server_config.as_object() {
-            mcp_servers.insert(server.name.clone(), Value::Object(config.clone()));
+        if let Some(text) = render_codex_server(server) {
+            desired.insert(server.name.clone(), Value::String(text));
         }
     }
 
-    // Update settings with mcpServers
-    if let Some(obj) = settings.as_object_mut() {
-        obj.insert("mcpServers".to_string(), Value::Object(mcp_servers));
-    }
-
-    // Write settings
-    let content = serde_json::to_string_pretty(&settings)?;
-    std::fs::write(&settings_path, content)?;
-
-    Ok(())
+    let config_path = config_path.to_path_buf();
+    Ok(McpSyncTarget {
+        label: AppType::Codex.to_string(),
+        key: mcp_sync_key(&AppType::Codex),
+        current: existing_content.map(|_| Value::Object(sections)),
+        desired: Value::Object(desired),
+        write: Box::new(move |merged| {
+            if let Some(parent) = config_path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&config_path, render_codex_config(&other_lines, merged))
+                .map_err(|e| e.to_string())
+        }),
+    })
 }
 
 /// Remove a specific MCP server from an app's config
@@ -280,6 +396,9 @@ fn remove_mcp_from_claude(server_id: &str) -> Result<(), Box<dyn std::error::Err
 
     let content = serde_json::to_string_pretty(&settings)?;
     std::fs::write(&config_path, content)?;
+    if let Some(mcp_servers) = settings.get("mcpServers") {
+        record_mcp_servers(&AppType::Claude, mcp_servers);
+    }
 
     Ok(())
 }
@@ -293,33 +412,12 @@ fn remove_mcp_from_codex(server_id: &str) -> Result<(), Box<dyn std::error::Erro
     }
 
     let content = std::fs::read_to_string(&config_path)?;
-    let lines: Vec<&str> = content.lines().collect();
-    let mut new_lines: Vec<String> = Vec::new();
-    let section_header = format!("[mcp_servers.{server_id}]");
-    let env_header = format!("[mcp_servers.{server_id}.env]");
-    let mut skip_section = false;
+    let (other_lines, mut sections) = split_codex_config(&content);
+    sections.remove(server_id);
 
-    for line in &lines {
-        let trimmed = line.trim();
-
-        // Check if this is the section we want to remove
-        if trimmed == section_header || trimmed == env_header {
-            skip_section = true;
-            continue;
-        }
-
-        // Check if we've reached a new section
-        if skip_section && trimmed.starts_with('[') {
-            skip_section = false;
-        }
-
-        if !skip_section {
-            new_lines.push(line.to_string());
-        }
-    }
-
-    let content = new_lines.join("\n");
-    std::fs::write(&config_path, content)?;
+    let sections = Value::Object(sections);
+    std::fs::write(&config_path, render_codex_config(&other_lines, &sections))?;
+    record_mcp_servers(&AppType::Codex, &sections);
 
     Ok(())
 }
@@ -345,6 +443,9 @@ fn remove_mcp_from_gemini(server_id: &str) -> Result<(), Box<dyn std::error::Err
 
     let content = serde_json::to_string_pretty(&settings)?;
     std::fs::write(&settings_path, content)?;
+    if let Some(mcp_servers) = settings.get("mcpServers") {
+        record_mcp_servers(&AppType::Gemini, mcp_servers);
+    }
 
     Ok(())
 }
@@ -569,7 +670,120 @@ pub fn import_mcp_from_app(
 
 #[cfg(test)]
 mod tests {
-    use super::{escape_toml_string, is_valid_toml_key};
+    use super::*;
+    use tempfile::TempDir;
+
+    fn codex_server(name: &str, command: &str) -> McpServer {
+        McpServer {
+            id: name.to_string(),
+            name: name.to_string(),
+            server_config: json!({ "command": command, "args": ["-y"] }),
+            description: None,
+            enabled_proxycast: true,
+            enabled_claude: false,
+            enabled_codex: true,
+            enabled_gemini: false,
+            created_at: None,
+        }
+    }
+
+    fn sync_codex(dir: &TempDir, servers: &[McpServer]) -> Result<(), String> {
+        let enabled: Vec<&McpServer> = servers.iter().collect();
+        let target = prepare_codex_config(&dir.path().join("config.toml"), &enabled)
+            .map_err(|e| e.to_string())?;
+        sync_mcp_targets(
+            &dir.path().join("state"),
+            vec![target],
+            ExternalSyncPolicy::MergeNonOverlapping,
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_codex_config_round_trip_keeps_other_sections() {
+        let content = "model = \"o3\"\n\n[mcp_servers.fs]\ncommand = \"npx\"\n[mcp_servers.fs.env]\nA = \"1\"\n\n[profiles.dev]\nmodel = \"o4\"";
+        let (other, sections) = split_codex_config(content);
+        assert_eq!(
+            other,
+            vec!["model = \"o3\"", "", "[profiles.dev]", "model = \"o4\""]
+        );
+        assert_eq!(
+            sections["fs"],
+            json!("[mcp_servers.fs]\ncommand = \"npx\"\n[mcp_servers.fs.env]\nA = \"1\"")
+        );
+
+        let rendered = render_codex_config(&other, &Value::Object(sections.clone()));
+        let (other_again, sections_again) = split_codex_config(&rendered);
+        assert_eq!(other_again, other);
+        assert_eq!(sections_again, sections);
+    }
+
+    #[test]
+    fn test_codex_sync_detects_external_edit() {
+        let dir = TempDir::new().unwrap();
+        let config_path = dir.path().join("config.toml");
+        sync_codex(&dir, &[codex_server("fs", "npx")]).unwrap();
+
+        // 外部修改了同一个服务器
+        let edited = std::fs::read_to_string(&config_path)
+            .unwrap()
+            .replace("npx", "bunx");
+        std::fs::write(&config_path, &edited).unwrap();
+
+        let err = sync_codex(&dir, &[codex_server("fs", "uvx")]).unwrap_err();
+        assert!(err.contains("codex MCP 配置"));
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), edited);
+    }
+
+    #[test]
+    fn test_codex_sync_merges_non_overlapping_edit() {
+        let dir = TempDir::new().unwrap();
+        let config_path = dir.path().join("config.toml");
+        sync_codex(&dir, &[codex_server("fs", "npx")]).unwrap();
+
+        // 外部新增了另一个服务器
+        let mut edited = std::fs::read_to_string(&config_path).unwrap();
+        edited.push_str("\n\n[mcp_servers.git]\ncommand = \"git-mcp\"");
+        std::fs::write(&config_path, &edited).unwrap();
+
+        sync_codex(&dir, &[codex_server("fs", "uvx")]).unwrap();
+        let content = std::fs::read_to_string(&config_path).unwrap();
+        assert!(content.contains("command = \"uvx\""));
+        assert!(content.contains("[mcp_servers.git]"));
+    }
+
+    #[test]
+    fn test_conflict_in_one_target_skips_all_writes() {
+        let dir = TempDir::new().unwrap();
+        let state_dir = dir.path().join("state");
+        let settings_path = dir.path().join("settings.json");
+        let config_path = dir.path().join("config.toml");
+        let servers = [codex_server("fs", "npx")];
+        let enabled: Vec<&McpServer> = servers.iter().collect();
+        let policy = ExternalSyncPolicy::Abort;
+
+        let targets = vec![
+            prepare_json_settings(&AppType::Claude, &settings_path, &enabled).unwrap(),
+            prepare_codex_config(&config_path, &enabled).unwrap(),
+        ];
+        sync_mcp_targets(&state_dir, targets, policy).unwrap();
+
+        // Codex 配置被外部修改，Claude 未修改
+        std::fs::write(&config_path, "[mcp_servers.fs]\ncommand = \"bunx\"").unwrap();
+        let claude_before = std::fs::read_to_string(&settings_path).unwrap();
+
+        let servers = [codex_server("fs", "uvx")];
+        let enabled: Vec<&McpServer> = servers.iter().collect();
+        let targets = vec![
+            prepare_json_settings(&AppType::Claude, &settings_path, &enabled).unwrap(),
+            prepare_codex_config(&config_path, &enabled).unwrap(),
+        ];
+        assert!(sync_mcp_targets(&state_dir, targets, policy).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&settings_path).unwrap(),
+            claude_before
+        );
+    }
 
     #[test]
     fn test_valid_toml_key_accepts_alphanumeric() {
@@ -6,8 +6,9 @@
 use proxycast_core::database::dao::api_key_provider::ApiProviderType;
 use proxycast_core::database::DbConnection;
 use proxycast_core::models::model_registry::{
-    EnhancedModelMetadata, ModelCapabilities, ModelLimits, ModelPricing, ModelSource, ModelStatus,
-    ModelSyncState, ModelTier, ProviderAliasConfig, UserModelPreference,
+    EnhancedModelMetadata, LastKnownModels, ModelCapabilities, ModelLimits, ModelListSnapshot,
+    ModelPricing, ModelSource, ModelStatus, ModelSyncState, ModelTier, ProviderAliasConfig,
+    UserModelPreference,
 };
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
@@ -113,6 +114,8 @@ pub struct ModelRegistryService {
     aliases_cache: Arc<RwLock<HashMap<String, ProviderAliasConfig>>>,
     /// 同步状态
     sync_state: Arc<RwLock<ModelSyncState>>,
+    /// 最近一次成功读取的模型列表（注册表读取失败时降级使用）
+    last_known_models: Arc<LastKnownModels>,
     /// 资源目录路径
     resource_dir: Option<std::path::PathBuf>,
 }
@@ -125,6 +128,7 @@ impl ModelRegistryService {
             models_cache: Arc::new(RwLock::new(Vec::new())),
             aliases_cache: Arc::new(RwLock::new(HashMap::new())),
             sync_state: Arc::new(RwLock::new(ModelSyncState::default())),
+            last_known_models: Arc::new(LastKnownModels::new()),
             resource_dir: None,
         }
     }
@@ -178,6 +182,7 @@ impl ModelRegistryService {
             let mut cache = self.models_cache.write().await;
             *cache = models.clone();
         }
+        self.last_known_models.update(models.clone());
         {
             let mut cache = self.aliases_cache.write().await;
             *cache = aliases;
@@ -420,8 +425,7 @@ impl ModelRegistryService {
         }
     }
 
-    /// 从数据库加载模型（预留，将来实现从数据库加载自定义模型）
-    #[allow(dead_code)]
+    /// 从数据库加载模型
     async fn load_from_db(&self) -> Result<Vec<EnhancedModelMetadata>, String> {
         let (models, sync_rows) = {
             let conn = self.db.lock().map_err(|e| e.to_string())?;
@@ -570,6 +574,14 @@ impl ModelRegistryService {
         self.models_cache.read().await.clone()
     }
 
+    /// 从注册表数据库读取模型列表
+    ///
+    /// 读取失败（如数据库被短暂锁住）时返回最近一次成功读取的列表，并标记 `stale: true`
+    pub async fn list_models(&self) -> Result<ModelListSnapshot, String> {
+        let read = self.load_from_db().await;
+        self.last_known_models.resolve(read)
+    }
+
     /// 获取同步状态
     pub async fn get_sync_state(&self) -> ModelSyncState {
         self.sync_state.read().await.clone()
@@ -596,6 +608,7 @@ impl ModelRegistryService {
             let mut cache = self.models_cache.write().await;
             *cache = models.clone();
         }
+        self.last_known_models.update(models.clone());
         {
             let mut cache = self.aliases_cache.write().await;
             *cache = aliases;
@@ -7,6 +7,7 @@ use proxycast_core::database::DbConnection;
 use proxycast_core::models::provider_pool_model::{
     CredentialData, PoolProviderType, ProviderCredential,
 };
+use proxycast_providers::providers::openai_custom::OpenAICustomProvider;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -116,6 +117,17 @@ impl ModelService {
                 // Vertex AI 使用固定的模型列表
                 Ok(self.get_default_models_for_provider(&credential.provider_type))
             }
+            CredentialData::LocalOpenAI { base_url, api_key } => {
+                tracing::info!("[MODEL_SERVICE] 查询本地服务模型列表: {}", base_url);
+                OpenAICustomProvider::with_local_server(
+                    base_url.clone(),
+                    api_key.clone(),
+                    self.client.clone(),
+                )
+                .list_model_ids()
+                .await
+                .map_err(|e| format!("请求失败: {e}"))
+            }
         }
     }
 
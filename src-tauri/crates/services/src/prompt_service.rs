@@ -1,4 +1,5 @@
 use crate::prompt_sync;
+use crate::sync_guard;
 use proxycast_core::database::dao::prompts::PromptDao;
 use proxycast_core::database::DbConnection;
 use proxycast_core::models::{AppType, Prompt};
@@ -32,7 +33,7 @@ impl PromptService {
         // If this prompt is enabled, sync to live file
         if prompt.enabled {
             let app = app_type.parse::<AppType>().map_err(|e| e.to_string())?;
-            prompt_sync::write_live_prompt(&app, &prompt.content)?;
+            Self::sync_to_live(&app, &prompt.content)?;
         }
 
         Ok(())
@@ -56,12 +57,21 @@ impl PromptService {
                 .app_type
                 .parse::<AppType>()
                 .map_err(|e| e.to_string())?;
-            prompt_sync::write_live_prompt(&app, &prompt.content)?;
+            Self::sync_to_live(&app, &prompt.content)?;
         }
 
         Ok(())
     }
 
+    /// Sync prompt content to the live file without overwriting external edits
+    fn sync_to_live(app: &AppType, content: &str) -> Result<(), String> {
+        let outcome = prompt_sync::sync_live_prompt(app, content, sync_guard::configured_policy())?;
+        match outcome.conflict_message(&format!("{app} Prompt 文件")) {
+            Some(message) => Err(message),
+            None => Ok(()),
+        }
+    }
+
     /// Delete a prompt
     /// Cannot delete an enabled prompt
     pub fn delete(db: &DbConnection, app_type: &str, id: &str) -> Result<(), String> {
@@ -1,4 +1,7 @@
+use crate::sync_guard::{self, SyncOutcome};
+use proxycast_core::config::ExternalSyncPolicy;
 use proxycast_core::models::AppType;
+use serde_json::Value;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
@@ -64,9 +67,47 @@ pub fn write_live_prompt(app: &AppType, content: &str) -> Result<(), String> {
 
     fs::rename(&temp_path, &path).map_err(|e| format!("Failed to rename file: {e}"))?;
 
+    // Remember what we wrote so later syncs can detect out-of-band edits
+    if let Some(dir) = sync_guard::default_state_dir() {
+        if let Err(e) = sync_guard::save_record(
+            &dir,
+            &prompt_sync_key(app),
+            &Value::String(content.to_string()),
+        ) {
+            tracing::warn!("Failed to save prompt sync record: {}", e);
+        }
+    }
+
     Ok(())
 }
 
+/// Write content to the live prompt file, refusing to overwrite external edits
+///
+/// If the file changed since the last sync, the configured policy decides whether
+/// the write is aborted (reported as a conflict) or kept when only one side changed.
+pub fn sync_live_prompt(
+    app: &AppType,
+    content: &str,
+    policy: ExternalSyncPolicy,
+) -> Result<SyncOutcome, String> {
+    let dir = sync_guard::default_state_dir().ok_or("Cannot find home directory")?;
+    let current = read_live_prompt(app)?.map(Value::String);
+    let desired = Value::String(content.to_string());
+
+    sync_guard::guarded_sync(
+        &dir,
+        &prompt_sync_key(app),
+        current.as_ref(),
+        &desired,
+        policy,
+        |value| write_live_prompt(app, value.as_str().unwrap_or_default()),
+    )
+}
+
+fn prompt_sync_key(app: &AppType) -> String {
+    format!("prompt_{}", app.to_string().to_lowercase())
+}
+
 /// Delete the live prompt file
 #[allow(dead_code)]
 pub fn delete_live_prompt(app: &AppType) -> Result<(), String> {
@@ -7,20 +7,27 @@
 use crate::api_key_provider_service::ApiKeyProviderService;
 use crate::provider_type_mapping::{
     api_provider_type_to_pool_type, is_custom_provider_id, parse_pool_provider_type,
-    resolve_pool_provider_type_or_default,
+    resolve_fallback_pool_type,
 };
 use chrono::Utc;
+use proxycast_core::config::{CooldownRecoveryConfig, CredentialSelectionStrategy, FailoverChain};
+use proxycast_core::credential::{
+    existing_fingerprint, FilterReason, SelectionFactor, SelectionTrace,
+};
 use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
 use proxycast_core::database::DbConnection;
+use proxycast_core::middleware::request_lease;
 use proxycast_core::models::client_type::ClientType;
 use proxycast_core::models::provider_pool_model::{
-    get_default_check_model, get_oauth_creds_path, CredentialData, CredentialDisplay,
-    HealthCheckResult, OAuthStatus, PoolProviderType, PoolStats, ProviderCredential,
+    get_oauth_creds_path, resolve_check_model, CredentialData, CredentialDisplay,
+    HealthCheckResult, HealthReason, OAuthStatus, PoolProviderType, PoolStats, ProviderCredential,
     ProviderPoolOverview,
 };
 use proxycast_core::models::route_model::RouteInfo;
+use proxycast_credential::{HealthAlertMonitor, QuotaExceededRecord, QuotaManager};
 use proxycast_providers::providers::antigravity::TokenRefreshError;
 use proxycast_providers::providers::kiro::KiroProvider;
+use proxycast_providers::providers::openai_custom::OpenAICustomProvider;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
@@ -40,8 +47,9 @@ impl ProviderCredentialClientCompat for ProviderCredential {
         true
     }
 }
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// 凭证健康信息
@@ -78,6 +86,104 @@ pub enum SelectionError {
     ModelNotSupported { model: String },
 }
 
+/// 故障转移链中单个成员的调用结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainStep<T> {
+    /// 调用完成（成功或不需要换用成员的错误），结束遍历
+    Done(T),
+    /// 成员未被调用（如已熔断），换用下一个成员
+    Skipped(T),
+    /// 成员调用失败，记为不健康后换用下一个成员
+    Failed { result: T, error: String },
+}
+
+/// 延迟 EWMA 的平滑系数（新样本的权重）
+const LATENCY_EWMA_ALPHA: f64 = 0.3;
+
+/// 最快优先策略的探索间隔：每 N 次选择中有一次选用最久未使用的凭证，
+/// 使较慢凭证的延迟统计保持更新
+const FASTEST_EXPLORE_INTERVAL: usize = 10;
+
+/// 凭证轮换检查间隔
+pub const ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 启动凭证轮换检查后台任务
+///
+/// 按 `interval` 间隔将超过 `rotate_after_days` 的凭证标记为需要轮换，
+/// 被标记的凭证仍可继续使用，仅在凭证池概览和托盘中显示警告。
+pub fn spawn_rotation_check_task(
+    pool_service: Arc<ProviderPoolService>,
+    db: DbConnection,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match pool_service.flag_rotation_due(&db, Utc::now()) {
+                Ok(flagged) => {
+                    for cred in flagged {
+                        tracing::warn!(
+                            "[ROTATION] 凭证 {} ({}) 已超过 {} 天轮换周期，请尽快更换",
+                            cred.name.as_deref().unwrap_or(&cred.uuid),
+                            cred.provider_type,
+                            cred.rotate_after_days.unwrap_or_default()
+                        );
+                    }
+                }
+                Err(e) => tracing::warn!("[ROTATION] 检查凭证轮换失败: {}", e),
+            }
+        }
+    })
+}
+
+/// 冷却恢复检查间隔
+pub const COOLDOWN_RECOVERY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 启动冷却恢复后台任务
+///
+/// 按 `interval` 间隔恢复冷却期已过的不健康凭证，行为由
+/// [`ProviderPoolService::set_cooldown_recovery`] 设置的配置控制（支持热重载）。
+pub fn spawn_cooldown_recovery_task(
+    pool_service: Arc<ProviderPoolService>,
+    db: DbConnection,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let health_check = |cred: ProviderCredential| {
+                let pool_service = pool_service.clone();
+                let db = db.clone();
+                async move {
+                    pool_service
+                        .check_credential_health(&db, &cred.uuid)
+                        .await
+                        .is_ok_and(|result| result.success)
+                }
+            };
+            match pool_service
+                .recover_expired_cooldowns(&db, Utc::now(), health_check)
+                .await
+            {
+                Ok(recovered) => {
+                    for cred in recovered {
+                        tracing::info!(
+                            "[COOLDOWN] 凭证 {} ({}) 冷却结束，已恢复为可用",
+                            cred.name.as_deref().unwrap_or(&cred.uuid),
+                            cred.provider_type
+                        );
+                    }
+                }
+                Err(e) => tracing::warn!("[COOLDOWN] 恢复冷却凭证失败: {}", e),
+            }
+        }
+    })
+}
+
 /// 凭证池管理服务
 pub struct ProviderPoolService {
     /// HTTP 客户端（用于健康检测）
@@ -88,6 +194,48 @@ pub struct ProviderPoolService {
     max_error_count: u32,
     /// 健康检查超时时间
     health_check_timeout: Duration,
+    /// 配额管理器（跟踪每日 Token 用量）
+    quota_manager: Arc<QuotaManager>,
+    /// 健康告警（Provider 无健康凭证时通知）
+    health_alerts: Arc<HealthAlertMonitor>,
+    /// 按 Provider 类型配置的健康检查探测模型（来自配置 health_probe.models）
+    probe_models: std::sync::RwLock<HashMap<String, String>>,
+    /// 各凭证正在处理的请求数（仅跟踪设置了 max_concurrency 的凭证）
+    in_flight: Arc<std::sync::Mutex<HashMap<String, u32>>>,
+    /// 冷却恢复配置（来自配置 credentials.cooldown_recovery）
+    cooldown_recovery: std::sync::RwLock<CooldownRecoveryConfig>,
+    /// 凭证选择策略（来自配置 credentials.selection_strategy）
+    selection_strategy: std::sync::RwLock<CredentialSelectionStrategy>,
+    /// 各凭证的响应延迟 EWMA（毫秒）
+    latency_ewma: std::sync::RwLock<HashMap<String, f64>>,
+    /// 最快优先策略的选择次数（用于定期探索）
+    fastest_ticks: AtomicUsize,
+}
+
+/// 凭证并发名额
+///
+/// 释放时归还名额；由 [`ProviderPoolService::try_acquire_slot`] 创建。
+pub struct ConcurrencyPermit {
+    uuid: String,
+    in_flight: Arc<std::sync::Mutex<HashMap<String, u32>>>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            if let Some(count) = in_flight.get_mut(&self.uuid) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    in_flight.remove(&self.uuid);
+                }
+            }
+        }
+    }
+}
+
+/// 凭证并发名额在请求租约中的键
+fn concurrency_lease_key(uuid: &str) -> String {
+    format!("credential_concurrency:{uuid}")
 }
 
 impl Default for ProviderPoolService {
@@ -106,7 +254,164 @@ impl ProviderPoolService {
             round_robin_index: std::sync::RwLock::new(HashMap::new()),
             max_error_count: 3,
             health_check_timeout: Duration::from_secs(30),
+            quota_manager: Arc::new(QuotaManager::with_defaults()),
+            health_alerts: Arc::new(HealthAlertMonitor::default()),
+            probe_models: std::sync::RwLock::new(HashMap::new()),
+            in_flight: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            cooldown_recovery: std::sync::RwLock::new(CooldownRecoveryConfig::default()),
+            selection_strategy: std::sync::RwLock::new(CredentialSelectionStrategy::default()),
+            latency_ewma: std::sync::RwLock::new(HashMap::new()),
+            fastest_ticks: AtomicUsize::new(0),
+        }
+    }
+
+    /// 设置按 Provider 类型的健康检查探测模型
+    pub fn set_probe_models(&self, models: HashMap<String, String>) {
+        if let Ok(mut probe_models) = self.probe_models.write() {
+            *probe_models = models;
+        }
+    }
+
+    /// 设置冷却恢复配置
+    pub fn set_cooldown_recovery(&self, config: CooldownRecoveryConfig) {
+        if let Ok(mut cooldown_recovery) = self.cooldown_recovery.write() {
+            *cooldown_recovery = config;
+        }
+    }
+
+    /// 设置凭证选择策略
+    pub fn set_selection_strategy(&self, strategy: CredentialSelectionStrategy) {
+        if let Ok(mut selection_strategy) = self.selection_strategy.write() {
+            *selection_strategy = strategy;
+        }
+    }
+
+    /// 记录凭证一次成功请求的响应延迟，更新延迟 EWMA（第一个样本直接作为初始值）
+    pub fn record_latency(&self, uuid: &str, latency_ms: u64) {
+        if let Ok(mut latency_ewma) = self.latency_ewma.write() {
+            let sample = latency_ms as f64;
+            latency_ewma
+                .entry(uuid.to_string())
+                .and_modify(|ewma| *ewma += LATENCY_EWMA_ALPHA * (sample - *ewma))
+                .or_insert(sample);
+        }
+    }
+
+    /// 获取凭证的响应延迟 EWMA（毫秒），尚无样本时为 `None`
+    pub fn latency_ewma(&self, uuid: &str) -> Option<f64> {
+        self.latency_ewma
+            .read()
+            .ok()
+            .and_then(|latency_ewma| latency_ewma.get(uuid).copied())
+    }
+
+    /// 获取健康告警监视器
+    pub fn health_alerts(&self) -> &Arc<HealthAlertMonitor> {
+        &self.health_alerts
+    }
+
+    /// 凭证健康状态变化后，检查所属 Provider 类型是否还有健康凭证
+    fn observe_type_health(
+        &self,
+        conn: &rusqlite::Connection,
+        provider_type: &PoolProviderType,
+        reason: Option<&str>,
+    ) {
+        let credentials = match ProviderPoolDao::get_by_type(conn, provider_type) {
+            Ok(credentials) => credentials,
+            Err(e) => {
+                tracing::warn!("[HEALTH_ALERT] 读取凭证失败: {}", e);
+                return;
+            }
+        };
+        let enabled: Vec<_> = credentials.iter().filter(|c| !c.is_disabled).collect();
+        let healthy = enabled.iter().filter(|c| c.is_healthy).count();
+        self.health_alerts
+            .observe(&provider_type.to_string(), healthy, enabled.len(), reason);
+    }
+
+    /// 获取配额管理器
+    pub fn quota_manager(&self) -> &Arc<QuotaManager> {
+        &self.quota_manager
+    }
+
+    /// 凭证正在处理的请求数
+    pub fn in_flight(&self, uuid: &str) -> u32 {
+        self.in_flight
+            .lock()
+            .map(|in_flight| in_flight.get(uuid).copied().unwrap_or(0))
+            .unwrap_or(0)
+    }
+
+    /// 凭证是否已达并发上限（当前请求已占用该凭证的名额时不算）
+    fn is_at_capacity(&self, cred: &ProviderCredential) -> bool {
+        cred.max_concurrency.is_some_and(|max| {
+            !request_lease::is_held(&concurrency_lease_key(&cred.uuid))
+                && self.in_flight(&cred.uuid) >= max
+        })
+    }
+
+    /// 为凭证占用一个并发名额，已达上限时返回 None
+    pub fn try_acquire_slot(&self, cred: &ProviderCredential) -> Option<ConcurrencyPermit> {
+        let mut in_flight = self.in_flight.lock().ok()?;
+        let count = in_flight.entry(cred.uuid.clone()).or_insert(0);
+        if cred.max_concurrency.is_some_and(|max| *count >= max) {
+            if *count == 0 {
+                in_flight.remove(&cred.uuid);
+            }
+            return None;
         }
+        *count += 1;
+        Some(ConcurrencyPermit {
+            uuid: cred.uuid.clone(),
+            in_flight: self.in_flight.clone(),
+        })
+    }
+
+    /// 为选中的凭证占用并发名额，名额随当前请求结束释放；已达上限时返回 false
+    ///
+    /// 不在请求处理上下文中（如 WebSocket、批量任务）时无法跟踪请求何时结束，
+    /// 名额立即归还，此时并发上限只用于过滤。
+    fn reserve_slot(&self, cred: &ProviderCredential) -> bool {
+        let key = concurrency_lease_key(&cred.uuid);
+        if cred.max_concurrency.is_none() || request_lease::is_held(&key) {
+            return true;
+        }
+        match self.try_acquire_slot(cred) {
+            Some(permit) => {
+                let _ = request_lease::hold_for_request(&key, permit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 记录凭证消耗的 Token 数
+    ///
+    /// 按凭证配置的 `daily_token_limit` 累计当日用量，达到上限时返回超限记录，
+    /// 该凭证在下一个 UTC 日之前不会再被选中。
+    pub fn record_token_usage(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        tokens: u64,
+    ) -> Option<QuotaExceededRecord> {
+        let limit = {
+            let conn = proxycast_core::database::lock_db(db).ok()?;
+            ProviderPoolDao::get_by_uuid(&conn, uuid)
+                .ok()
+                .flatten()
+                .and_then(|c| c.daily_token_limit)
+        };
+        let record = self.quota_manager.record_daily_tokens(uuid, tokens, limit);
+        if let Some(record) = &record {
+            tracing::warn!(
+                "[DAILY_QUOTA] 凭证 {} 已达每日 Token 上限: {}",
+                uuid,
+                record.reason
+            );
+        }
+        record
     }
 
     /// 获取所有凭证概览
@@ -138,6 +443,21 @@ impl ProviderPoolService {
         Ok(overview)
     }
 
+    /// 获取所有凭证（附带 Token 缓存）
+    pub fn get_all_with_token_cache(
+        &self,
+        db: &DbConnection,
+    ) -> Result<Vec<ProviderCredential>, String> {
+        let conn = proxycast_core::database::lock_db(db)?;
+        let mut credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+        for cred in &mut credentials {
+            cred.cached_token = ProviderPoolDao::get_token_cache(&conn, &cred.uuid)
+                .ok()
+                .flatten();
+        }
+        Ok(credentials)
+    }
+
     /// 获取指定类型的凭证列表
     pub fn get_by_type(
         &self,
@@ -192,7 +512,10 @@ impl ProviderPoolService {
         check_health: Option<bool>,
         check_model_name: Option<String>,
         not_supported_models: Option<Vec<String>>,
+        preferred_models: Option<Vec<String>>,
         proxy_url: Option<String>,
+        rotate_after_days: Option<u32>,
+        max_concurrency: Option<u32>,
     ) -> Result<ProviderCredential, String> {
         let conn = proxycast_core::database::lock_db(db)?;
         let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
@@ -216,16 +539,62 @@ impl ProviderPoolService {
         if let Some(models) = not_supported_models {
             cred.not_supported_models = models;
         }
+        if let Some(models) = preferred_models {
+            cred.preferred_models = models;
+        }
         // 处理 proxy_url：空字符串表示清除，None 表示不修改
         if let Some(p) = proxy_url {
             cred.proxy_url = if p.is_empty() { None } else { Some(p) };
         }
+        // 处理 rotate_after_days：0 表示清除，None 表示不修改
+        if let Some(days) = rotate_after_days {
+            cred.rotate_after_days = (days > 0).then_some(days);
+            cred.needs_rotation = cred.is_rotation_due_at(Utc::now());
+        }
+        // 处理 max_concurrency：0 表示不限制，None 表示不修改
+        if let Some(max) = max_concurrency {
+            cred.max_concurrency = (max > 0).then_some(max);
+        }
         cred.updated_at = Utc::now();
 
         ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
         Ok(cred)
     }
 
+    /// 标记超过轮换周期的凭证
+    ///
+    /// 未禁用且超过 `rotate_after_days` 的凭证设置为需要轮换（仍可使用），
+    /// 周期被修改或清除后不再到期的凭证清除提醒。返回本次新标记的凭证。
+    pub fn flag_rotation_due(
+        &self,
+        db: &DbConnection,
+        now: chrono::DateTime<Utc>,
+    ) -> Result<Vec<ProviderCredential>, String> {
+        let conn = proxycast_core::database::lock_db(db)?;
+        let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+
+        let mut flagged = Vec::new();
+        for mut cred in credentials {
+            let due = !cred.is_disabled && cred.is_rotation_due_at(now);
+            if due == cred.needs_rotation {
+                continue;
+            }
+            ProviderPoolDao::set_needs_rotation(&conn, &cred.uuid, due)
+                .map_err(|e| e.to_string())?;
+            if due {
+                cred.needs_rotation = true;
+                flagged.push(cred);
+            }
+        }
+        Ok(flagged)
+    }
+
+    /// 记录凭证已轮换（重新授权或更换密钥），重置轮换计时
+    pub fn mark_rotated(&self, db: &DbConnection, uuid: &str) -> Result<(), String> {
+        let conn = proxycast_core::database::lock_db(db)?;
+        ProviderPoolDao::mark_rotated(&conn, uuid, Utc::now()).map_err(|e| e.to_string())
+    }
+
     /// 删除凭证
     pub fn delete_credential(&self, db: &DbConnection, uuid: &str) -> Result<bool, String> {
         let conn = proxycast_core::database::lock_db(db)?;
@@ -257,6 +626,59 @@ impl ProviderPoolService {
         provider_type: &str,
         model: Option<&str>,
         client_type: Option<&proxycast_core::models::client_type::ClientType>,
+    ) -> Result<Option<ProviderCredential>, String> {
+        self.select_credential_excluding(db, provider_type, model, client_type, &[])
+    }
+
+    /// 为重试选择凭证
+    ///
+    /// 优先选择 `tried` 之外的凭证，避免重复使用刚失败的账号；
+    /// 所有可用凭证都已尝试过时才重新使用已尝试的凭证。
+    pub fn select_credential_for_retry(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        model: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+        tried: &[&str],
+    ) -> Result<Option<ProviderCredential>, String> {
+        let fresh =
+            self.select_credential_excluding(db, provider_type, model, client_type, tried)?;
+        if fresh.is_some() || tried.is_empty() {
+            return Ok(fresh);
+        }
+        tracing::info!(
+            "[SELECT_CREDENTIAL] {} 的可用凭证均已尝试过（{} 个），重新使用已尝试的凭证",
+            provider_type,
+            tried.len()
+        );
+        self.select_credential_excluding(db, provider_type, model, client_type, &[])
+    }
+
+    /// 选择凭证并排除指定凭证
+    ///
+    /// 用于上游过载等场景下换用同类型的其他凭证，`excluded` 为需要跳过的凭证 UUID
+    pub fn select_credential_excluding(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        model: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+        excluded: &[&str],
+    ) -> Result<Option<ProviderCredential>, String> {
+        let mut trace = SelectionTrace::new(provider_type, false);
+        self.select_credential_traced(db, provider_type, model, client_type, excluded, &mut trace)
+    }
+
+    /// 选择凭证并记录选择过程（候选、过滤原因、选中凭证及决定因素）
+    pub fn select_credential_traced(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        model: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+        excluded: &[&str],
+        trace: &mut SelectionTrace,
     ) -> Result<Option<ProviderCredential>, String> {
         if is_custom_provider_id(provider_type) {
             eprintln!(
@@ -317,33 +739,34 @@ impl ProviderPoolService {
             model
         );
 
+        trace.consider(&credentials);
+        let mut available = credentials;
+        trace.retain(&mut available, FilterReason::Disabled, |c| !c.is_disabled);
+
         // 过滤可用的凭证
-        let mut available: Vec<_> = credentials
-            .into_iter()
-            .filter(|c| {
-                let is_avail = c.is_available();
-                if !is_avail {
-                    eprintln!(
-                        "[SELECT_CREDENTIAL] credential {} (type={}) is_available={} (is_healthy={}, is_disabled={}, error_count={}, last_error={:?})",
-                        c.name.as_deref().unwrap_or("unnamed"),
-                        c.provider_type,
-                        is_avail,
-                        c.is_healthy,
-                        c.is_disabled,
-                        c.error_count,
-                        c.last_error_message
-                    );
-                } else {
-                    eprintln!(
-                        "[SELECT_CREDENTIAL] credential {} (type={}) is_available={}",
-                        c.name.as_deref().unwrap_or("unnamed"),
-                        c.provider_type,
-                        is_avail
-                    );
-                }
-                is_avail
-            })
-            .collect();
+        trace.retain(&mut available, FilterReason::Cooldown, |c| {
+            let is_avail = c.is_available();
+            if !is_avail {
+                eprintln!(
+                    "[SELECT_CREDENTIAL] credential {} (type={}) is_available={} (is_healthy={}, is_disabled={}, error_count={}, last_error={:?})",
+                    c.name.as_deref().unwrap_or("unnamed"),
+                    c.provider_type,
+                    is_avail,
+                    c.is_healthy,
+                    c.is_disabled,
+                    c.error_count,
+                    c.last_error_message
+                );
+            } else {
+                eprintln!(
+                    "[SELECT_CREDENTIAL] credential {} (type={}) is_available={}",
+                    c.name.as_deref().unwrap_or("unnamed"),
+                    c.provider_type,
+                    is_avail
+                );
+            }
+            is_avail
+        });
 
         eprintln!(
             "[SELECT_CREDENTIAL] after is_available filter: {}",
@@ -352,7 +775,7 @@ impl ProviderPoolService {
 
         // 如果指定了模型，进一步过滤支持该模型的凭证
         if let Some(m) = model {
-            available.retain(|c| {
+            trace.retain(&mut available, FilterReason::ModelUnsupported, |c| {
                 let supports = c.supports_model(m);
                 eprintln!(
                     "[SELECT_CREDENTIAL] credential {} supports_model({})={}",
@@ -364,8 +787,27 @@ impl ProviderPoolService {
             });
         }
 
+        // 过滤已达每日 Token 上限的凭证
+        trace.retain(&mut available, FilterReason::Quota, |c| {
+            let within_quota = self.quota_manager.is_available(&c.uuid);
+            if !within_quota {
+                eprintln!(
+                    "[SELECT_CREDENTIAL] credential {} 已达每日 Token 上限",
+                    c.name.as_deref().unwrap_or("unnamed")
+                );
+            }
+            within_quota
+        });
+
+        // 排除指定的凭证
+        if !excluded.is_empty() {
+            trace.retain(&mut available, FilterReason::Excluded, |c| {
+                !excluded.contains(&c.uuid.as_str())
+            });
+        }
+
         // 过滤客户端兼容的凭证
-        available.retain(|c| {
+        trace.retain(&mut available, FilterReason::ClientIncompatible, |c| {
             let compatible = c.is_compatible_with_client(client_type);
             if !compatible {
                 eprintln!(
@@ -382,19 +824,129 @@ impl ProviderPoolService {
             available.len()
         );
 
-        if available.is_empty() {
-            return Ok(None);
+        // 过滤已达最大并发请求数的凭证
+        trace.retain(&mut available, FilterReason::Busy, |c| {
+            let busy = self.is_at_capacity(c);
+            if busy {
+                eprintln!(
+                    "[SELECT_CREDENTIAL] credential {} 已达最大并发请求数",
+                    c.name.as_deref().unwrap_or("unnamed")
+                );
+            }
+            !busy
+        });
+
+        // 存在偏好该模型的凭证时只在这些凭证中选择，否则使用任意可用凭证
+        if let Some(m) = model {
+            if available.iter().any(|c| c.prefers_model(m)) {
+                trace.retain(&mut available, FilterReason::NotPreferred, |c| {
+                    c.prefers_model(m)
+                });
+                eprintln!(
+                    "[SELECT_CREDENTIAL] {} 个凭证偏好模型 {}，优先选择",
+                    available.len(),
+                    m
+                );
+            }
         }
 
-        // 如果只有一个可用凭证，直接返回
-        if available.len() == 1 {
-            return Ok(Some(available.into_iter().next().unwrap()));
+        // 选中后占用并发名额；名额被其他请求抢先占满时重新选择
+        loop {
+            let (selected, factor) = match available.len() {
+                0 => return Ok(None),
+                // 如果只有一个可用凭证，直接返回
+                1 => (available[0].clone(), SelectionFactor::OnlyCandidate),
+                _ => match self.selection_strategy() {
+                    // 智能选择：基于权重分数选择最优凭证
+                    CredentialSelectionStrategy::Score => {
+                        let (selected, score) = self.select_best_credential_by_weight(&available);
+                        (selected, SelectionFactor::HighestScore { score })
+                    }
+                    CredentialSelectionStrategy::FastestFirst => self.select_fastest(&available),
+                },
+            };
+            if self.reserve_slot(&selected) {
+                trace.choose(&selected.uuid, factor);
+                return Ok(Some(selected));
+            }
+            trace.retain(&mut available, FilterReason::Busy, |c| {
+                c.uuid != selected.uuid
+            });
         }
+    }
 
-        // 智能选择：基于权重分数选择最优凭证
-        let selected = self.select_best_credential_by_weight(&available);
+    /// 按故障转移链选择凭证
+    ///
+    /// 按链中顺序返回第一个可用成员，跳过不健康、已禁用、已达每日 Token 上限、
+    /// 已达最大并发请求数、不支持该模型以及 `tried` 中已失败的成员。
+    /// 整条链都不可用时返回 `None`。
+    pub fn select_from_chain(
+        &self,
+        db: &DbConnection,
+        chain: &FailoverChain,
+        model: Option<&str>,
+        tried: &[&str],
+    ) -> Result<Option<ProviderCredential>, String> {
+        let credentials = {
+            let conn = proxycast_core::database::lock_db(db)?;
+            ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+        };
+        // 选中后占用并发名额；名额被其他请求抢先占满时选择下一个成员
+        let mut busy: Vec<&str> = Vec::new();
+        loop {
+            let selected = chain.first_available(&credentials, |c| {
+                !tried.contains(&c.uuid.as_str())
+                    && !busy.contains(&c.uuid.as_str())
+                    && self.quota_manager.is_available(&c.uuid)
+                    && model.is_none_or(|m| c.supports_model(m))
+                    && !self.is_at_capacity(c)
+            });
+            match selected {
+                Some(cred) if !self.reserve_slot(cred) => busy.push(&cred.uuid),
+                selected => return Ok(selected.cloned()),
+            }
+        }
+    }
 
-        Ok(Some(selected))
+    /// 按故障转移链依次调用成员凭证
+    ///
+    /// 按 [`Self::select_from_chain`] 的顺序选择成员并调用 `call`。失败的成员记为不健康，
+    /// 跳过或失败后换用链中下一个可用成员；`allow_failover` 为 false（请求禁用降级）时
+    /// 不换用，直接返回首个成员的结果。整条链都不可用时返回 `Ok(None)`。
+    pub async fn call_chain<T, F, Fut>(
+        &self,
+        db: &DbConnection,
+        chain: &FailoverChain,
+        model: Option<&str>,
+        allow_failover: bool,
+        call: F,
+    ) -> Result<Option<T>, String>
+    where
+        F: Fn(ProviderCredential) -> Fut,
+        Fut: std::future::Future<Output = ChainStep<T>>,
+    {
+        let mut tried: Vec<String> = Vec::new();
+        loop {
+            let tried_refs: Vec<&str> = tried.iter().map(String::as_str).collect();
+            let Some(cred) = self.select_from_chain(db, chain, model, &tried_refs)? else {
+                return Ok(None);
+            };
+            let uuid = cred.uuid.clone();
+            let result = match call(cred).await {
+                ChainStep::Done(result) => return Ok(Some(result)),
+                ChainStep::Skipped(result) => result,
+                ChainStep::Failed { result, error } => {
+                    if let Err(e) = self.mark_unhealthy(db, &uuid, Some(&error)) {
+                        tracing::warn!("[FAILOVER] 标记链 {} 的成员不健康失败: {}", chain.name, e);
+                    }
+                    result
+                }
+            };
+            if !allow_failover {
+                return Ok(Some(result));
+            }
+            tried.push(uuid);
+        }
     }
 
     /// 带智能降级的凭证选择
@@ -421,6 +973,36 @@ impl ProviderPoolService {
         model: Option<&str>,
         provider_id_hint: Option<&str>,
         client_type: Option<&proxycast_core::models::client_type::ClientType>,
+    ) -> Result<Option<ProviderCredential>, String> {
+        let mut trace = SelectionTrace::new(provider_type, false);
+        self.select_credential_with_fallback_traced(
+            db,
+            api_key_service,
+            provider_type,
+            None,
+            model,
+            provider_id_hint,
+            client_type,
+            &mut trace,
+        )
+        .await
+    }
+
+    /// 带智能降级的凭证选择，并记录选择过程
+    ///
+    /// `declared_protocol` 为路由层从 routing.provider_protocols 解析出的协议，
+    /// 智能降级时按该协议查找凭证。
+    #[allow(clippy::too_many_arguments)]
+    pub async fn select_credential_with_fallback_traced(
+        &self,
+        db: &DbConnection,
+        api_key_service: &ApiKeyProviderService,
+        provider_type: &str,
+        declared_protocol: Option<proxycast_core::ProviderType>,
+        model: Option<&str>,
+        provider_id_hint: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+        trace: &mut SelectionTrace,
     ) -> Result<Option<ProviderCredential>, String> {
         eprintln!(
             "[select_credential_with_fallback] 开始: provider_type={provider_type}, model={model:?}, provider_id_hint={provider_id_hint:?}"
@@ -428,7 +1010,7 @@ impl ProviderPoolService {
 
         // Step 1: 尝试从 Provider Pool 选择 (OAuth + API Key)
         if let Some(cred) =
-            self.select_credential_with_client_check(db, provider_type, model, client_type)?
+            self.select_credential_traced(db, provider_type, model, client_type, &[], trace)?
         {
             eprintln!(
                 "[select_credential_with_fallback] 从 Provider Pool 找到凭证: {:?}",
@@ -439,7 +1021,7 @@ impl ProviderPoolService {
         eprintln!("[select_credential_with_fallback] Provider Pool 未找到凭证，尝试智能降级");
 
         // Step 2: 智能降级到 API Key Provider
-        let mut pt = resolve_pool_provider_type_or_default(provider_type);
+        let mut pt = resolve_fallback_pool_type(provider_type, declared_protocol);
         let mut resolved_provider_id_hint = provider_id_hint;
 
         // 对 custom-* 场景优先查询真实 Provider 类型，避免默认按 OpenAI 协议处理
@@ -447,8 +1029,10 @@ impl ProviderPoolService {
             resolved_provider_id_hint = Some(provider_type);
         }
 
-        if let Some(custom_provider_id) =
-            resolved_provider_id_hint.filter(|id| is_custom_provider_id(id))
+        // 已在配置中声明协议时以声明为准
+        if let Some(custom_provider_id) = resolved_provider_id_hint
+            .filter(|id| is_custom_provider_id(id))
+            .filter(|_| declared_protocol.is_none())
         {
             match api_key_service.get_provider(db, custom_provider_id) {
                 Ok(Some(provider_with_keys)) => {
@@ -492,6 +1076,7 @@ impl ProviderPoolService {
                 "[select_credential_with_fallback] 智能降级成功: {:?}",
                 cred.name
             );
+            trace.choose(&cred.uuid, SelectionFactor::ApiKeyFallback);
             return Ok(Some(cred));
         }
 
@@ -524,11 +1109,58 @@ impl ProviderPoolService {
         .await
     }
 
-    /// 基于权重分数选择最优凭证
+    /// 当前凭证选择策略
+    fn selection_strategy(&self) -> CredentialSelectionStrategy {
+        self.selection_strategy
+            .read()
+            .map(|strategy| *strategy)
+            .unwrap_or_default()
+    }
+
+    /// 最快优先选择凭证
+    ///
+    /// - 尚无延迟样本的凭证优先被选中，以建立统计
+    /// - 每 [`FASTEST_EXPLORE_INTERVAL`] 次选择探索一次最久未使用的凭证
+    /// - 其余情况选择延迟 EWMA 最低的凭证
+    fn select_fastest(
+        &self,
+        credentials: &[ProviderCredential],
+    ) -> (ProviderCredential, SelectionFactor) {
+        let latencies: Vec<Option<f64>> = credentials
+            .iter()
+            .map(|c| self.latency_ewma(&c.uuid))
+            .collect();
+
+        if let Some(index) = latencies.iter().position(Option::is_none) {
+            return (credentials[index].clone(), SelectionFactor::Explore);
+        }
+
+        let tick = self
+            .fastest_ticks
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if tick % FASTEST_EXPLORE_INTERVAL == FASTEST_EXPLORE_INTERVAL - 1 {
+            if let Some(least_recent) = credentials.iter().min_by_key(|c| c.last_used) {
+                return (least_recent.clone(), SelectionFactor::Explore);
+            }
+        }
+
+        let (index, latency_ms) = latencies
+            .iter()
+            .map(|latency| latency.unwrap_or(f64::MAX))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap_or((0, f64::MAX));
+        (
+            credentials[index].clone(),
+            SelectionFactor::LowestLatency { latency_ms },
+        )
+    }
+
+    /// 基于权重分数选择最优凭证，返回凭证及其分数
     fn select_best_credential_by_weight(
         &self,
         credentials: &[ProviderCredential],
-    ) -> ProviderCredential {
+    ) -> (ProviderCredential, f64) {
         let now = chrono::Utc::now();
 
         let mut best_score = f64::MIN;
@@ -542,7 +1174,7 @@ impl ProviderPoolService {
             }
         }
 
-        best_credential.unwrap().clone()
+        (best_credential.unwrap().clone(), best_score)
     }
 
     /// 计算凭证的综合分数（分数越高越优先）
@@ -614,6 +1246,8 @@ impl ProviderPoolService {
     }
 
     /// 标记凭证为健康
+    ///
+    /// 更新最后成功时间，最后一次错误的时间和消息保留不变
     pub fn mark_healthy(
         &self,
         db: &DbConnection,
@@ -621,17 +1255,12 @@ impl ProviderPoolService {
         check_model: Option<&str>,
     ) -> Result<(), String> {
         let conn = proxycast_core::database::lock_db(db)?;
-        ProviderPoolDao::update_health_status(
-            &conn,
-            uuid,
-            true,
-            0,
-            None,
-            None,
-            Some(Utc::now()),
-            check_model,
-        )
-        .map_err(|e| e.to_string())
+        ProviderPoolDao::record_success(&conn, uuid, Utc::now(), check_model)
+            .map_err(|e| e.to_string())?;
+        if let Ok(Some(cred)) = ProviderPoolDao::get_by_uuid(&conn, uuid) {
+            self.observe_type_health(&conn, &cred.provider_type, None);
+        }
+        Ok(())
     }
 
     /// 标记凭证为不健康
@@ -659,7 +1288,89 @@ impl ProviderPoolService {
             None,
             None,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+        self.observe_type_health(&conn, &cred.provider_type, error_message);
+        Ok(())
+    }
+
+    /// 恢复冷却期已过的凭证
+    ///
+    /// 因连续错误被标记为不健康、且距最后一次错误已超过 `cooldown_secs` 的凭证恢复为健康；
+    /// 禁用或需要重新授权的凭证不自动恢复。启用 `health_check` 时先调用 `health_check`，
+    /// 未通过的凭证从 `now` 起重新冷却。返回本次恢复的凭证。
+    pub async fn recover_expired_cooldowns<F, Fut>(
+        &self,
+        db: &DbConnection,
+        now: chrono::DateTime<Utc>,
+        health_check: F,
+    ) -> Result<Vec<ProviderCredential>, String>
+    where
+        F: Fn(ProviderCredential) -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        let config = self
+            .cooldown_recovery
+            .read()
+            .map_err(|e| format!("读取冷却恢复配置失败: {e}"))?
+            .clone();
+        if !config.enabled {
+            return Ok(Vec::new());
+        }
+
+        let cooldown = chrono::Duration::seconds(config.cooldown_secs as i64);
+        let expired: Vec<ProviderCredential> = {
+            let conn = proxycast_core::database::lock_db(db)?;
+            ProviderPoolDao::get_all(&conn)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|c| {
+                    let requires_reauth = c
+                        .last_error_message
+                        .as_ref()
+                        .is_some_and(|e| e.contains("invalid_grant") || e.contains("重新授权"));
+                    !c.is_disabled
+                        && !c.is_healthy
+                        && !requires_reauth
+                        && c.last_error_time.is_none_or(|t| t + cooldown <= now)
+                })
+                .collect()
+        };
+
+        let mut recovered = Vec::new();
+        for mut cred in expired {
+            if config.health_check && !health_check(cred.clone()).await {
+                tracing::warn!(
+                    "[COOLDOWN] 凭证 {} ({}) 冷却结束后健康检查未通过，继续冷却",
+                    cred.name.as_deref().unwrap_or(&cred.uuid),
+                    cred.provider_type
+                );
+                let conn = proxycast_core::database::lock_db(db)?;
+                if let Some(current) =
+                    ProviderPoolDao::get_by_uuid(&conn, &cred.uuid).map_err(|e| e.to_string())?
+                {
+                    ProviderPoolDao::update_health_status(
+                        &conn,
+                        &current.uuid,
+                        false,
+                        current.error_count,
+                        Some(now),
+                        current.last_error_message.as_deref(),
+                        current.last_health_check_time,
+                        current.last_health_check_model.as_deref(),
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                continue;
+            }
+
+            let conn = proxycast_core::database::lock_db(db)?;
+            ProviderPoolDao::mark_recovered(&conn, &cred.uuid).map_err(|e| e.to_string())?;
+            self.observe_type_health(&conn, &cred.provider_type, None);
+            cred.is_healthy = true;
+            cred.error_count = 0;
+            recovered.push(cred);
+        }
+        Ok(recovered)
     }
 
     /// 重置凭证计数器
@@ -676,7 +1387,9 @@ impl ProviderPoolService {
     ) -> Result<usize, String> {
         let pt = parse_pool_provider_type(provider_type)?;
         let conn = proxycast_core::database::lock_db(db)?;
-        ProviderPoolDao::reset_health_by_type(&conn, &pt).map_err(|e| e.to_string())
+        let count = ProviderPoolDao::reset_health_by_type(&conn, &pt).map_err(|e| e.to_string())?;
+        self.observe_type_health(&conn, &pt, None);
+        Ok(count)
     }
 
     /// 获取凭证健康状态
@@ -773,7 +1486,9 @@ impl ProviderPoolService {
             None,
             None,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+        self.observe_type_health(&conn, &cred.provider_type, Some(&error_msg));
+        Ok(())
     }
 
     /// 选择一个健康的凭证
@@ -904,10 +1619,17 @@ impl ProviderPoolService {
                 .ok_or_else(|| format!("Credential not found: {uuid}"))?
         };
 
-        let check_model = cred
-            .check_model_name
-            .clone()
-            .unwrap_or_else(|| get_default_check_model(cred.provider_type).to_string());
+        let check_model = {
+            let probe_models = self
+                .probe_models
+                .read()
+                .map_err(|e| format!("读取探测模型配置失败: {e}"))?;
+            resolve_check_model(
+                cred.provider_type,
+                cred.check_model_name.as_deref(),
+                &probe_models,
+            )
+        };
 
         let start = std::time::Instant::now();
         let result = self
@@ -924,6 +1646,7 @@ impl ProviderPoolService {
                     model: Some(check_model),
                     message: Some("Health check passed".to_string()),
                     duration_ms,
+                    reason: None,
                 })
             }
             Err(e) => {
@@ -962,6 +1685,7 @@ impl ProviderPoolService {
                                             "Health check passed after token refresh".to_string(),
                                         ),
                                         duration_ms: duration_ms + retry_duration_ms,
+                                        reason: None,
                                     });
                                 }
                                 Err(retry_e) => {
@@ -971,6 +1695,7 @@ impl ProviderPoolService {
                                         uuid: uuid.to_string(),
                                         success: false,
                                         model: Some(check_model),
+                                        reason: Some(HealthReason::from_error(&retry_e)),
                                         message: Some(retry_e),
                                         duration_ms: duration_ms + retry_duration_ms,
                                     });
@@ -987,6 +1712,7 @@ impl ProviderPoolService {
                                 model: Some(check_model),
                                 message: Some(format!("{e} (Token 刷新失败: {refresh_err})")),
                                 duration_ms,
+                                reason: Some(HealthReason::AuthExpired),
                             });
                         }
                     }
@@ -997,6 +1723,7 @@ impl ProviderPoolService {
                     uuid: uuid.to_string(),
                     success: false,
                     model: Some(check_model),
+                    reason: Some(HealthReason::from_error(&e)),
                     message: Some(e),
                     duration_ms,
                 })
@@ -1089,6 +1816,10 @@ impl ProviderPoolService {
                 self.check_claude_health(api_key, base_url.as_deref(), model)
                     .await
             }
+            CredentialData::LocalOpenAI { base_url, api_key } => {
+                self.check_local_openai_health(base_url, api_key.as_deref())
+                    .await
+            }
         }
     }
 
@@ -1345,6 +2076,27 @@ impl ProviderPoolService {
         }
     }
 
+    // 本地 OpenAI 兼容服务健康检查
+    // 查询 /v1/models：不要求 API Key，也不需要加载模型进行推理
+    async fn check_local_openai_health(
+        &self,
+        base_url: &str,
+        api_key: Option<&str>,
+    ) -> Result<(), String> {
+        tracing::debug!("[HEALTH_CHECK] 本地服务 URL: {}", base_url);
+
+        let provider = OpenAICustomProvider::with_local_server(
+            base_url.to_string(),
+            api_key.map(str::to_string),
+            self.client.clone(),
+        );
+        tokio::time::timeout(self.health_check_timeout, provider.list_model_ids())
+            .await
+            .map_err(|_| "请求失败: 本地服务响应超时".to_string())?
+            .map(|_| ())
+            .map_err(|e| format!("请求失败: {e}"))
+    }
+
     // Claude API 健康检查
     // 与 ClaudeCustomProvider 保持一致的 URL 处理逻辑
     async fn check_claude_health(
@@ -1634,6 +2386,7 @@ impl ProviderPoolService {
         &self,
         db: &DbConnection,
         base_url: &str,
+        selector_prefix: &str,
     ) -> Result<Vec<RouteInfo>, String> {
         let conn = proxycast_core::database::lock_db(db)?;
         let grouped = ProviderPoolDao::get_grouped(&conn).map_err(|e| e.to_string())?;
@@ -1651,8 +2404,8 @@ impl ProviderPoolService {
             // Provider 类型路由 (轮询)
             let mut route = RouteInfo::new(provider_type.to_string(), provider_type.to_string());
             route.credential_count = available.len();
-            route.add_endpoint(base_url, "claude");
-            route.add_endpoint(base_url, "openai");
+            route.add_endpoint(base_url, selector_prefix, "claude");
+            route.add_endpoint(base_url, selector_prefix, "openai");
             route.tags.push("轮询".to_string());
             routes.push(route);
         }
@@ -1666,8 +2419,8 @@ impl ProviderPoolService {
                             RouteInfo::new(name.clone(), cred.provider_type.to_string());
                         route.credential_count = 1;
                         route.enabled = !cred.is_disabled;
-                        route.add_endpoint(base_url, "claude");
-                        route.add_endpoint(base_url, "openai");
+                        route.add_endpoint(base_url, selector_prefix, "claude");
+                        route.add_endpoint(base_url, selector_prefix, "openai");
                         route.tags.push("指定凭证".to_string());
                         routes.push(route);
                     }
@@ -2000,6 +2753,17 @@ impl ProviderPoolService {
         Ok(false)
     }
 
+    /// 凭证池中已有凭证的去重指纹（用于批量导入）
+    pub fn credential_fingerprints(&self, db: &DbConnection) -> Result<HashSet<String>, String> {
+        let conn = proxycast_core::database::lock_db(db)?;
+        let all_creds = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+
+        Ok(all_creds
+            .iter()
+            .filter_map(|cred| existing_fingerprint(&cred.credential))
+            .collect())
+    }
+
     /// 检查是否存在相同 API Key 的凭证
     fn credential_exists_by_api_key(
         &self,
@@ -2042,6 +2806,511 @@ mod tests {
     use super::*;
     use proxycast_core::database::dao::api_key_provider::ApiProviderType;
 
+    fn pool_db_with_openai_keys(count: usize) -> (DbConnection, Vec<String>) {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        proxycast_core::database::schema::create_tables(&conn).unwrap();
+        let uuids = (0..count)
+            .map(|i| {
+                let cred = ProviderCredential::new(
+                    PoolProviderType::OpenAI,
+                    CredentialData::OpenAIKey {
+                        api_key: format!("sk-test-{i}"),
+                        base_url: None,
+                    },
+                );
+                ProviderPoolDao::insert(&conn, &cred).unwrap();
+                cred.uuid
+            })
+            .collect();
+        (Arc::new(std::sync::Mutex::new(conn)), uuids)
+    }
+
+    #[test]
+    fn test_retry_selection_picks_distinct_credentials() {
+        let (db, uuids) = pool_db_with_openai_keys(3);
+        let service = ProviderPoolService::new();
+        let mut tried: Vec<String> = Vec::new();
+
+        for _ in 0..uuids.len() {
+            let excluded: Vec<&str> = tried.iter().map(String::as_str).collect();
+            let cred = service
+                .select_credential_for_retry(&db, "openai", None, None, &excluded)
+                .unwrap()
+                .expect("healthy credential available");
+            assert!(!tried.contains(&cred.uuid), "重试不应重复选择已尝试的凭证");
+            tried.push(cred.uuid);
+        }
+
+        tried.sort();
+        let mut expected = uuids.clone();
+        expected.sort();
+        assert_eq!(tried, expected);
+    }
+
+    #[tokio::test]
+    async fn test_call_chain_walks_members_and_marks_failures_unhealthy() {
+        let (db, uuids) = pool_db_with_openai_keys(3);
+        let service = ProviderPoolService::new();
+        let chain = FailoverChain {
+            name: "primary".to_string(),
+            members: uuids.clone(),
+        };
+        let called = std::sync::Mutex::new(Vec::new());
+        let call = |cred: ProviderCredential| {
+            called.lock().unwrap().push(cred.uuid.clone());
+            let step = if cred.uuid == uuids[0] {
+                ChainStep::Failed {
+                    result: 529,
+                    error: "HTTP 529".to_string(),
+                }
+            } else if cred.uuid == uuids[1] {
+                ChainStep::Skipped(503)
+            } else {
+                ChainStep::Done(200)
+            };
+            async move { step }
+        };
+
+        // 按链顺序调用，失败和跳过的成员换用下一个
+        let result = service.call_chain(&db, &chain, None, true, &call).await;
+        assert_eq!(result, Ok(Some(200)));
+        assert_eq!(*called.lock().unwrap(), uuids);
+        let errors = |uuid: &str| {
+            let conn = db.lock().unwrap();
+            ProviderPoolDao::get_by_uuid(&conn, uuid)
+                .unwrap()
+                .unwrap()
+                .error_count
+        };
+        assert_eq!(errors(&uuids[0]), 1, "失败的成员应记录错误");
+        assert_eq!(errors(&uuids[1]), 0, "跳过的成员不记录错误");
+
+        // 禁用降级时只调用首个可用成员
+        called.lock().unwrap().clear();
+        let result = service.call_chain(&db, &chain, None, false, &call).await;
+        assert_eq!(result, Ok(Some(529)));
+        assert_eq!(*called.lock().unwrap(), [uuids[0].clone()]);
+
+        // 整条链都不可用时返回 None
+        let result = service
+            .call_chain(&db, &chain, None, true, |_| async {
+                ChainStep::Skipped(503)
+            })
+            .await;
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn test_retry_selection_falls_back_when_exhausted() {
+        let (db, uuids) = pool_db_with_openai_keys(2);
+        let service = ProviderPoolService::new();
+        let tried: Vec<&str> = uuids.iter().map(String::as_str).collect();
+
+        // 全部尝试过时重新使用已尝试的凭证
+        let cred = service
+            .select_credential_for_retry(&db, "openai", None, None, &tried)
+            .unwrap()
+            .expect("falls back to a tried credential");
+        assert!(uuids.contains(&cred.uuid));
+
+        // 严格排除时没有可用凭证
+        assert!(service
+            .select_credential_excluding(&db, "openai", None, None, &tried)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_rotation_flag_set_after_window_and_cleared_on_rotation() {
+        let (db, uuids) = pool_db_with_openai_keys(2);
+        let service = ProviderPoolService::new();
+        service
+            .update_credential(
+                &db,
+                &uuids[0],
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(30),
+                None,
+            )
+            .unwrap();
+
+        // 轮换周期内不标记
+        let now = Utc::now();
+        assert!(service
+            .flag_rotation_due(&db, now + chrono::Duration::days(29))
+            .unwrap()
+            .is_empty());
+
+        // 超过轮换周期后标记并持久化，凭证仍可被选中
+        let flagged = service
+            .flag_rotation_due(&db, now + chrono::Duration::days(31))
+            .unwrap();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].uuid, uuids[0]);
+        let cred = service.get_by_uuid(&db, &uuids[0]).unwrap().unwrap();
+        assert!(cred.needs_rotation);
+        assert!(cred.is_available());
+        let overview = service.get_overview(&db).unwrap();
+        assert!(overview[0]
+            .credentials
+            .iter()
+            .any(|c| c.uuid == uuids[0] && c.needs_rotation));
+
+        // 已标记的凭证不会重复返回
+        assert!(service
+            .flag_rotation_due(&db, now + chrono::Duration::days(31))
+            .unwrap()
+            .is_empty());
+
+        // 轮换后清除标记并重新计时
+        service.mark_rotated(&db, &uuids[0]).unwrap();
+        let cred = service.get_by_uuid(&db, &uuids[0]).unwrap().unwrap();
+        assert!(!cred.needs_rotation);
+        assert!(cred.rotated_at.is_some());
+        assert!(service
+            .flag_rotation_due(&db, Utc::now() + chrono::Duration::days(29))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_credential_at_max_concurrency_is_skipped() {
+        let (db, uuids) = pool_db_with_openai_keys(2);
+        let service = ProviderPoolService::new();
+        let capped = service
+            .update_credential(
+                &db,
+                &uuids[0],
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(1),
+            )
+            .unwrap();
+        assert_eq!(capped.max_concurrency, Some(1));
+
+        // 占满名额后只选择另一个凭证，并记录过滤原因
+        let permit = service.try_acquire_slot(&capped).unwrap();
+        assert!(service.try_acquire_slot(&capped).is_none());
+        assert_eq!(service.in_flight(&uuids[0]), 1);
+        for _ in 0..3 {
+            let mut trace = SelectionTrace::new("openai", true);
+            let cred = service
+                .select_credential_traced(&db, "openai", None, None, &[], &mut trace)
+                .unwrap()
+                .unwrap();
+            assert_eq!(cred.uuid, uuids[1]);
+            assert_eq!(trace.reason_for(&uuids[0]), Some(FilterReason::Busy));
+        }
+
+        // 名额释放后重新可选；不在请求上下文中选择不会占用名额
+        drop(permit);
+        assert_eq!(service.in_flight(&uuids[0]), 0);
+        let tried = [uuids[1].as_str()];
+        let cred = service
+            .select_credential_excluding(&db, "openai", None, None, &tried)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cred.uuid, uuids[0]);
+        assert_eq!(service.in_flight(&uuids[0]), 0);
+    }
+
+    #[test]
+    fn test_selection_trace_lists_filtered_candidates_and_winner() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        proxycast_core::database::schema::create_tables(&conn).unwrap();
+        let credential = |name: &str| {
+            let mut cred = ProviderCredential::new(
+                PoolProviderType::OpenAI,
+                CredentialData::OpenAIKey {
+                    api_key: format!("sk-{name}"),
+                    base_url: None,
+                },
+            );
+            cred.name = Some(name.to_string());
+            cred
+        };
+        let mut disabled = credential("disabled");
+        disabled.is_disabled = true;
+        let mut cooling = credential("cooling");
+        cooling.is_healthy = false;
+        let mut over_quota = credential("over-quota");
+        over_quota.daily_token_limit = Some(100);
+        let mut busy = credential("busy");
+        busy.max_concurrency = Some(1);
+        let winner = credential("winner");
+        for cred in [&disabled, &cooling, &over_quota, &busy, &winner] {
+            ProviderPoolDao::insert(&conn, cred).unwrap();
+        }
+        let db: DbConnection = Arc::new(std::sync::Mutex::new(conn));
+
+        let service = ProviderPoolService::new();
+        assert!(service
+            .record_token_usage(&db, &over_quota.uuid, 500)
+            .is_some());
+        let _permit = service.try_acquire_slot(&busy).unwrap();
+
+        let mut trace = SelectionTrace::new("openai", true);
+        let selected = service
+            .select_credential_traced(&db, "openai", None, None, &[], &mut trace)
+            .unwrap()
+            .unwrap();
+        assert_eq!(selected.uuid, winner.uuid);
+
+        assert_eq!(trace.candidate_count, 5);
+        assert_eq!(trace.filtered_count, 4);
+        assert_eq!(
+            trace.reason_for(&disabled.uuid),
+            Some(FilterReason::Disabled)
+        );
+        assert_eq!(
+            trace.reason_for(&cooling.uuid),
+            Some(FilterReason::Cooldown)
+        );
+        assert_eq!(
+            trace.reason_for(&over_quota.uuid),
+            Some(FilterReason::Quota)
+        );
+        assert_eq!(trace.reason_for(&busy.uuid), Some(FilterReason::Busy));
+        assert_eq!(trace.reason_for(&winner.uuid), None);
+        assert_eq!(trace.chosen.as_deref(), Some(winner.uuid.as_str()));
+        assert_eq!(trace.factor, Some(SelectionFactor::OnlyCandidate));
+
+        let json = serde_json::to_value(&trace).unwrap();
+        assert_eq!(json["filtered"][0]["name"], "disabled");
+        assert_eq!(json["factor"]["type"], "only_candidate");
+    }
+
+    #[test]
+    fn test_fastest_first_prefers_low_latency_credential() {
+        let (db, uuids) = pool_db_with_openai_keys(3);
+        let service = ProviderPoolService::new();
+        service.set_selection_strategy(CredentialSelectionStrategy::FastestFirst);
+
+        // 尚无延迟样本的凭证先被选中
+        service.record_latency(&uuids[0], 800);
+        service.record_latency(&uuids[2], 300);
+        assert_eq!(
+            service
+                .select_credential(&db, "openai", None)
+                .unwrap()
+                .unwrap()
+                .uuid,
+            uuids[1]
+        );
+
+        for _ in 0..5 {
+            service.record_latency(&uuids[0], 800);
+            service.record_latency(&uuids[1], 50);
+            service.record_latency(&uuids[2], 300);
+        }
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for _ in 0..100 {
+            let cred = service
+                .select_credential(&db, "openai", None)
+                .unwrap()
+                .unwrap();
+            *counts.entry(cred.uuid).or_default() += 1;
+        }
+        // 最快的凭证占绝大多数，其余凭证仍会被探索
+        assert!(counts[&uuids[1]] >= 80, "{counts:?}");
+        assert!(counts.len() > 1, "{counts:?}");
+    }
+
+    #[test]
+    fn test_latency_ewma_smooths_samples() {
+        let service = ProviderPoolService::new();
+        assert!(service.latency_ewma("cred-1").is_none());
+        service.record_latency("cred-1", 100);
+        service.record_latency("cred-1", 200);
+        assert!((service.latency_ewma("cred-1").unwrap() - 130.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_request_outcomes_persist_recency() {
+        let (db, uuids) = pool_db_with_openai_keys(1);
+        let service = ProviderPoolService::new();
+        let uuid = &uuids[0];
+
+        service.mark_healthy(&db, uuid, Some("gpt-4o")).unwrap();
+        let first_success = service
+            .get_by_uuid(&db, uuid)
+            .unwrap()
+            .unwrap()
+            .last_success_time
+            .expect("成功后记录最后成功时间");
+
+        service
+            .mark_unhealthy(&db, uuid, Some("429 Too Many Requests"))
+            .unwrap();
+        let cred = service.get_by_uuid(&db, uuid).unwrap().unwrap();
+        let error_time = cred.last_error_time.expect("失败后记录最后错误时间");
+        assert!(error_time >= first_success);
+        assert_eq!(
+            cred.last_error_message.as_deref(),
+            Some("429 Too Many Requests")
+        );
+
+        service.mark_healthy(&db, uuid, None).unwrap();
+        let cred = service.get_by_uuid(&db, uuid).unwrap().unwrap();
+        assert!(cred.last_success_time.unwrap() >= error_time);
+        // 成功后仍保留最后一次错误，便于排查
+        assert_eq!(cred.last_error_time, Some(error_time));
+        assert_eq!(
+            cred.last_error_message.as_deref(),
+            Some("429 Too Many Requests")
+        );
+    }
+
+    /// 连续失败直到凭证被标记为不健康
+    fn fail_until_unhealthy(service: &ProviderPoolService, db: &DbConnection, uuid: &str) {
+        for _ in 0..service.max_error_count {
+            service
+                .mark_unhealthy(db, uuid, Some("503 overloaded"))
+                .unwrap();
+        }
+        assert!(!service.get_by_uuid(db, uuid).unwrap().unwrap().is_healthy);
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_recovery_reenables_after_window() {
+        let (db, uuids) = pool_db_with_openai_keys(2);
+        let service = ProviderPoolService::new();
+        fail_until_unhealthy(&service, &db, &uuids[0]);
+        fail_until_unhealthy(&service, &db, &uuids[1]);
+        service
+            .mark_unhealthy(&db, &uuids[1], Some("invalid_grant"))
+            .unwrap();
+
+        // 冷却期内不恢复
+        let now = Utc::now();
+        assert!(service
+            .recover_expired_cooldowns(&db, now + chrono::Duration::seconds(60), |_| async { true })
+            .await
+            .unwrap()
+            .is_empty());
+
+        // 冷却期结束后恢复，需要重新授权的凭证保持不健康
+        let recovered = service
+            .recover_expired_cooldowns(&db, now + chrono::Duration::seconds(301), |_| async {
+                true
+            })
+            .await
+            .unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].uuid, uuids[0]);
+        let cred = service.get_by_uuid(&db, &uuids[0]).unwrap().unwrap();
+        assert!(cred.is_available());
+        assert_eq!(cred.error_count, 0);
+        assert!(
+            !service
+                .get_by_uuid(&db, &uuids[1])
+                .unwrap()
+                .unwrap()
+                .is_healthy
+        );
+        assert_eq!(
+            service
+                .select_credential(&db, "openai", None)
+                .unwrap()
+                .unwrap()
+                .uuid,
+            uuids[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_recovery_failing_health_check_keeps_cooled() {
+        let (db, uuids) = pool_db_with_openai_keys(1);
+        let service = ProviderPoolService::new();
+        service.set_cooldown_recovery(CooldownRecoveryConfig {
+            health_check: true,
+            ..Default::default()
+        });
+        fail_until_unhealthy(&service, &db, &uuids[0]);
+
+        // 健康检查未通过：从当前时间重新冷却
+        let now = Utc::now() + chrono::Duration::seconds(301);
+        assert!(service
+            .recover_expired_cooldowns(&db, now, |_| async { false })
+            .await
+            .unwrap()
+            .is_empty());
+        let cred = service.get_by_uuid(&db, &uuids[0]).unwrap().unwrap();
+        assert!(!cred.is_healthy);
+        assert_eq!(
+            cred.last_error_time.map(|t| t.timestamp()),
+            Some(now.timestamp())
+        );
+        assert!(service
+            .recover_expired_cooldowns(&db, now + chrono::Duration::seconds(60), |_| async { true })
+            .await
+            .unwrap()
+            .is_empty());
+
+        // 新的冷却期结束且健康检查通过后恢复
+        let recovered = service
+            .recover_expired_cooldowns(&db, now + chrono::Duration::seconds(301), |_| async {
+                true
+            })
+            .await
+            .unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert!(
+            service
+                .get_by_uuid(&db, &uuids[0])
+                .unwrap()
+                .unwrap()
+                .is_healthy
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_recovery_disabled_requires_manual_reset() {
+        let (db, uuids) = pool_db_with_openai_keys(1);
+        let service = ProviderPoolService::new();
+        service.set_cooldown_recovery(CooldownRecoveryConfig {
+            enabled: false,
+            ..Default::default()
+        });
+        fail_until_unhealthy(&service, &db, &uuids[0]);
+
+        assert!(service
+            .recover_expired_cooldowns(&db, Utc::now() + chrono::Duration::days(1), |_| async {
+                true
+            })
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(
+            !service
+                .get_by_uuid(&db, &uuids[0])
+                .unwrap()
+                .unwrap()
+                .is_healthy
+        );
+
+        service.reset_counters(&db, &uuids[0]).unwrap();
+        assert!(
+            service
+                .get_by_uuid(&db, &uuids[0])
+                .unwrap()
+                .unwrap()
+                .is_healthy
+        );
+    }
+
     // ==================== Property 3: 不健康凭证排除 ====================
     // Feature: antigravity-token-refresh, Property 3: 不健康凭证排除
     // Validates: Requirements 2.4, 3.3
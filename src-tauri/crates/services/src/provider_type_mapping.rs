@@ -6,6 +6,7 @@
 use proxycast_core::database::dao::api_key_provider::ApiProviderType;
 use proxycast_core::models::provider_pool_model::PoolProviderType;
 use proxycast_core::models::provider_type::is_custom_provider_id as core_is_custom_provider_id;
+use proxycast_core::ProviderType;
 
 /// 是否为自定义 Provider ID（`custom-*`）
 pub(crate) fn is_custom_provider_id(provider_type: &str) -> bool {
@@ -22,6 +23,19 @@ pub(crate) fn resolve_pool_provider_type_or_default(provider_type: &str) -> Pool
     provider_type.parse().unwrap_or(PoolProviderType::OpenAI)
 }
 
+/// 解析智能降级使用的 PoolProviderType
+///
+/// 路由层为自定义 Provider 解析出的协议（routing.provider_protocols）优先，
+/// 否则按名称解析并回退到 OpenAI。
+pub(crate) fn resolve_fallback_pool_type(
+    provider_type: &str,
+    declared_protocol: Option<ProviderType>,
+) -> PoolProviderType {
+    declared_protocol
+        .and_then(|protocol| protocol.to_string().parse().ok())
+        .unwrap_or_else(|| resolve_pool_provider_type_or_default(provider_type))
+}
+
 /// ApiProviderType → PoolProviderType 映射
 pub(crate) fn api_provider_type_to_pool_type(api_type: ApiProviderType) -> PoolProviderType {
     match api_type {
@@ -69,10 +83,12 @@ pub(crate) fn pool_provider_type_to_api_type(
 mod tests {
     use super::{
         api_provider_type_to_pool_type, is_custom_provider_id, parse_pool_provider_type,
-        pool_provider_type_to_api_type, resolve_pool_provider_type_or_default,
+        pool_provider_type_to_api_type, resolve_fallback_pool_type,
+        resolve_pool_provider_type_or_default,
     };
     use proxycast_core::database::dao::api_key_provider::ApiProviderType;
     use proxycast_core::models::provider_pool_model::PoolProviderType;
+    use proxycast_core::ProviderType;
 
     #[test]
     fn test_api_provider_type_to_pool_type_mapping() {
@@ -123,6 +139,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_fallback_pool_type_prefers_declared_protocol() {
+        assert_eq!(
+            resolve_fallback_pool_type("my-vllm", Some(ProviderType::Anthropic)),
+            PoolProviderType::Anthropic
+        );
+        assert_eq!(
+            resolve_fallback_pool_type("my-vllm", Some(ProviderType::AnthropicCompatible)),
+            PoolProviderType::AnthropicCompatible
+        );
+        assert_eq!(
+            resolve_fallback_pool_type("ollama", None),
+            PoolProviderType::Ollama
+        );
+        assert_eq!(
+            resolve_fallback_pool_type("my-vllm", None),
+            PoolProviderType::OpenAI
+        );
+    }
+
     #[test]
     fn test_is_custom_provider_id() {
         assert!(is_custom_provider_id(
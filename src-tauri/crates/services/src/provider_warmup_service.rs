@@ -0,0 +1,540 @@
+//! Provider 启动预热服务
+//!
+//! 凭证加载完成后，对一部分健康凭证提前执行：
+//! - 刷新即将过期（或尚未缓存）的 OAuth Token
+//! - 为 Gemini/Antigravity 凭证解析并持久化 project id
+//!
+//! 这样重启后第一个真实请求不必承担 Token 刷新和项目发现的延迟。
+//! 预热失败只记录日志，不影响服务启动。
+//!
+//! 配置 `server.warmup.min_warm_per_provider` 后，后台任务还会定期检查每个 Provider
+//! 类型的预热凭证数（可用且 Token 新鲜），不足时主动刷新 Token 补足。
+
+use crate::provider_pool_service::ProviderPoolService;
+use crate::token_cache_service::TokenCacheService;
+use proxycast_core::config::WarmupConfig;
+use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::{lock_db, DbConnection};
+use proxycast_core::models::provider_pool_model::{
+    CachedTokenInfo, CredentialData, PoolProviderType, ProviderCredential,
+};
+use proxycast_providers::providers::antigravity::AntigravityProvider;
+use proxycast_providers::providers::gemini::GeminiProvider;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{RwLock, Semaphore};
+use tokio::task::JoinSet;
+
+/// 单个凭证的预热任务
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmupTask {
+    pub uuid: String,
+    pub provider_type: PoolProviderType,
+    /// 是否需要刷新 Token
+    pub refresh_token: bool,
+    /// 是否需要解析 project id
+    pub resolve_project: bool,
+}
+
+/// 预热结果汇总
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarmupSummary {
+    pub planned: usize,
+    pub refreshed: usize,
+    pub projects_resolved: usize,
+    pub failed: usize,
+}
+
+/// 生成预热计划
+///
+/// 跳过禁用或不健康的凭证；只有 OAuth 凭证会刷新 Token，
+/// 只有未配置 project id 的 Gemini/Antigravity 凭证会解析项目。
+/// 计划按输入顺序截断到 `config.max_credentials` 个。
+pub fn plan_warmup(
+    credentials: &[(ProviderCredential, Option<CachedTokenInfo>)],
+    config: &WarmupConfig,
+) -> Vec<WarmupTask> {
+    credentials
+        .iter()
+        .filter(|(cred, _)| !cred.is_disabled && cred.is_healthy)
+        .filter_map(|(cred, cache)| {
+            let refresh_token = supports_warmup_refresh(&cred.credential)
+                && match cache {
+                    Some(cache) => {
+                        !cache.is_valid()
+                            || cache.is_expiring_within_minutes(config.refresh_within_minutes)
+                    }
+                    None => true,
+                };
+            let resolve_project = matches!(
+                &cred.credential,
+                CredentialData::GeminiOAuth {
+                    project_id: None,
+                    ..
+                } | CredentialData::AntigravityOAuth {
+                    project_id: None,
+                    ..
+                }
+            );
+
+            (refresh_token || resolve_project).then(|| WarmupTask {
+                uuid: cred.uuid.clone(),
+                provider_type: cred.provider_type,
+                refresh_token,
+                resolve_project,
+            })
+        })
+        .take(config.max_credentials)
+        .collect()
+}
+
+/// 生成维持预热凭证数的刷新计划
+///
+/// 按 Provider 类型统计预热凭证（见 [`ProviderCredential::is_warm`]），
+/// 不足 `min_warm` 时从可用但 Token 不新鲜的 OAuth 凭证中按输入顺序补足差额，
+/// 已满足的 Provider 类型不做任何刷新。
+pub fn plan_warm_pool(
+    credentials: &[(ProviderCredential, Option<CachedTokenInfo>)],
+    min_warm: usize,
+) -> Vec<WarmupTask> {
+    let counts = warm_counts(credentials);
+    let mut missing: HashMap<PoolProviderType, usize> = HashMap::new();
+    let mut tasks = Vec::new();
+    for (cred, cache) in credentials {
+        if !cred.is_available()
+            || !cred.credential.uses_oauth_token()
+            || cred.is_warm(cache.as_ref())
+        {
+            continue;
+        }
+        let remaining = missing.entry(cred.provider_type).or_insert_with(|| {
+            let warm = counts.get(&cred.provider_type).copied().unwrap_or(0);
+            min_warm.saturating_sub(warm)
+        });
+        if *remaining == 0 {
+            continue;
+        }
+        *remaining -= 1;
+        tasks.push(WarmupTask {
+            uuid: cred.uuid.clone(),
+            provider_type: cred.provider_type,
+            refresh_token: true,
+            resolve_project: false,
+        });
+    }
+    tasks
+}
+
+/// 按 Provider 类型统计预热凭证数
+pub fn warm_counts(
+    credentials: &[(ProviderCredential, Option<CachedTokenInfo>)],
+) -> HashMap<PoolProviderType, usize> {
+    let mut counts = HashMap::new();
+    for (cred, cache) in credentials {
+        if cred.is_warm(cache.as_ref()) {
+            *counts.entry(cred.provider_type).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// 检查一次预热凭证数，不足时刷新 Token 补足
+pub async fn maintain_warm_pool(
+    db: &DbConnection,
+    token_cache: &TokenCacheService,
+    pool_service: &ProviderPoolService,
+    min_warm: usize,
+) -> WarmupSummary {
+    let credentials = match load_credentials(db) {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            tracing::warn!("[WARM_POOL] 读取凭证失败: {}", e);
+            return WarmupSummary::default();
+        }
+    };
+
+    let tasks = plan_warm_pool(&credentials, min_warm);
+    let mut summary = WarmupSummary {
+        planned: tasks.len(),
+        ..Default::default()
+    };
+    for task in &tasks {
+        let outcome = warm_credential(db, token_cache, pool_service, task).await;
+        summary.refreshed += outcome.refreshed as usize;
+        summary.failed += outcome.failed as usize;
+    }
+    if summary.planned > 0 {
+        tracing::info!(
+            "[WARM_POOL] 补足预热凭证: planned={}, refreshed={}, failed={}",
+            summary.planned,
+            summary.refreshed,
+            summary.failed
+        );
+    }
+    summary
+}
+
+/// 启动维持预热凭证数的后台任务
+///
+/// 每轮读取 `config` 的最新值（支持热重载）：按 `warm_pool_interval_secs` 间隔检查，
+/// `min_warm_per_provider` 为 0 时跳过。返回的句柄由调用方在停止服务时中止。
+pub fn spawn_warm_pool_maintainer(
+    db: DbConnection,
+    token_cache: Arc<TokenCacheService>,
+    pool_service: Arc<ProviderPoolService>,
+    config: Arc<RwLock<WarmupConfig>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let (min_warm, interval) = {
+                let config = config.read().await;
+                (
+                    config.min_warm_per_provider,
+                    Duration::from_secs(config.warm_pool_interval_secs.max(1)),
+                )
+            };
+            tokio::time::sleep(interval).await;
+            if min_warm > 0 {
+                maintain_warm_pool(&db, &token_cache, &pool_service, min_warm).await;
+            }
+        }
+    })
+}
+
+/// 是否支持在预热时刷新 Token
+fn supports_warmup_refresh(credential: &CredentialData) -> bool {
+    matches!(
+        credential,
+        CredentialData::KiroOAuth { .. }
+            | CredentialData::GeminiOAuth { .. }
+            | CredentialData::AntigravityOAuth { .. }
+    )
+}
+
+/// 执行启动预热
+///
+/// 按 `config.concurrency` 并发执行预热计划，返回汇总结果。
+pub async fn run_warmup(
+    db: DbConnection,
+    token_cache: Arc<TokenCacheService>,
+    pool_service: Arc<ProviderPoolService>,
+    config: &WarmupConfig,
+) -> WarmupSummary {
+    let credentials = match load_credentials(&db) {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            tracing::warn!("[WARMUP] 读取凭证失败，跳过预热: {}", e);
+            return WarmupSummary::default();
+        }
+    };
+
+    let tasks = plan_warmup(&credentials, config);
+    let mut summary = WarmupSummary {
+        planned: tasks.len(),
+        ..Default::default()
+    };
+    if tasks.is_empty() {
+        tracing::info!("[WARMUP] 没有需要预热的凭证");
+        return summary;
+    }
+    tracing::info!("[WARMUP] 开始预热 {} 个凭证", tasks.len());
+
+    let semaphore = Arc::new(Semaphore::new(config.concurrency.max(1)));
+    let mut join_set = JoinSet::new();
+    for task in tasks {
+        let db = db.clone();
+        let token_cache = token_cache.clone();
+        let pool_service = pool_service.clone();
+        let semaphore = semaphore.clone();
+        join_set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok();
+            warm_credential(&db, &token_cache, &pool_service, &task).await
+        });
+    }
+
+    while let Some(result) = join_set.join_next().await {
+        match result {
+            Ok(outcome) => {
+                summary.refreshed += outcome.refreshed as usize;
+                summary.projects_resolved += outcome.project_resolved as usize;
+                summary.failed += outcome.failed as usize;
+            }
+            Err(e) => {
+                tracing::warn!("[WARMUP] 预热任务异常退出: {}", e);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    tracing::info!(
+        "[WARMUP] 预热完成: planned={}, refreshed={}, projects={}, failed={}",
+        summary.planned,
+        summary.refreshed,
+        summary.projects_resolved,
+        summary.failed
+    );
+    summary
+}
+
+/// 读取所有凭证及其 Token 缓存
+fn load_credentials(
+    db: &DbConnection,
+) -> Result<Vec<(ProviderCredential, Option<CachedTokenInfo>)>, String> {
+    let conn = lock_db(db)?;
+    let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+    Ok(credentials
+        .into_iter()
+        .map(|cred| {
+            let cache = ProviderPoolDao::get_token_cache(&conn, &cred.uuid)
+                .ok()
+                .flatten();
+            (cred, cache)
+        })
+        .collect())
+}
+
+#[derive(Default)]
+struct WarmupOutcome {
+    refreshed: bool,
+    project_resolved: bool,
+    failed: bool,
+}
+
+/// 预热单个凭证
+async fn warm_credential(
+    db: &DbConnection,
+    token_cache: &TokenCacheService,
+    pool_service: &ProviderPoolService,
+    task: &WarmupTask,
+) -> WarmupOutcome {
+    let mut outcome = WarmupOutcome::default();
+    let short_id = &task.uuid[..task.uuid.len().min(8)];
+
+    if task.refresh_token {
+        let result = if TokenCacheService::supports_refresh(task.provider_type) {
+            token_cache.refresh_and_cache(db, &task.uuid, true).await
+        } else {
+            pool_service.refresh_credential_token(db, &task.uuid).await
+        };
+        match result {
+            Ok(_) => outcome.refreshed = true,
+            Err(e) => {
+                tracing::warn!("[WARMUP] 凭证 {} Token 刷新失败: {}", short_id, e);
+                outcome.failed = true;
+            }
+        }
+    }
+
+    if task.resolve_project {
+        match resolve_project_id(db, &task.uuid).await {
+            Ok(project_id) => {
+                tracing::info!("[WARMUP] 凭证 {} project id: {}", short_id, project_id);
+                outcome.project_resolved = true;
+            }
+            Err(e) => {
+                tracing::warn!("[WARMUP] 凭证 {} project id 解析失败: {}", short_id, e);
+                outcome.failed = true;
+            }
+        }
+    }
+
+    outcome
+}
+
+/// 解析 Gemini/Antigravity 凭证的 project id 并写回凭证池
+async fn resolve_project_id(db: &DbConnection, uuid: &str) -> Result<String, String> {
+    let (mut credential, cache) = {
+        let conn = lock_db(db)?;
+        let credential = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {uuid}"))?;
+        let cache = ProviderPoolDao::get_token_cache(&conn, uuid).map_err(|e| e.to_string())?;
+        (credential, cache)
+    };
+    let cached_token = cache.and_then(|c| c.access_token);
+
+    let project_id = match &mut credential.credential {
+        CredentialData::GeminiOAuth {
+            creds_file_path,
+            project_id,
+        } => {
+            let mut provider = GeminiProvider::new();
+            provider
+                .load_credentials_from_path(creds_file_path)
+                .await
+                .map_err(|e| e.to_string())?;
+            if cached_token.is_some() {
+                provider.credentials.access_token = cached_token;
+            }
+            let resolved = provider
+                .discover_project()
+                .await
+                .map_err(|e| e.to_string())?;
+            *project_id = Some(resolved.clone());
+            resolved
+        }
+        CredentialData::AntigravityOAuth {
+            creds_file_path,
+            project_id,
+        } => {
+            let mut provider = AntigravityProvider::new();
+            provider
+                .load_credentials_from_path(creds_file_path)
+                .await
+                .map_err(|e| e.to_string())?;
+            if cached_token.is_some() {
+                provider.credentials.access_token = cached_token;
+            }
+            let resolved = provider
+                .discover_project()
+                .await
+                .map_err(|e| e.to_string())?;
+            *project_id = Some(resolved.clone());
+            resolved
+        }
+        _ => return Err("此凭证类型不需要 project id".to_string()),
+    };
+
+    let conn = lock_db(db)?;
+    ProviderPoolDao::update(&conn, &credential).map_err(|e| e.to_string())?;
+    Ok(project_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn oauth(provider_type: PoolProviderType) -> ProviderCredential {
+        let credential = match provider_type {
+            PoolProviderType::Kiro => CredentialData::KiroOAuth {
+                creds_file_path: "/tmp/kiro.json".to_string(),
+            },
+            PoolProviderType::Gemini => CredentialData::GeminiOAuth {
+                creds_file_path: "/tmp/gemini.json".to_string(),
+                project_id: Some("proj".to_string()),
+            },
+            _ => CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        };
+        ProviderCredential::new(provider_type, credential)
+    }
+
+    fn token_expiring_in(minutes: i64) -> Option<CachedTokenInfo> {
+        Some(CachedTokenInfo {
+            access_token: Some("token".to_string()),
+            refresh_token: Some("refresh".to_string()),
+            expiry_time: Some(Utc::now() + Duration::minutes(minutes)),
+            last_refresh: None,
+            refresh_error_count: 0,
+            last_refresh_error: None,
+        })
+    }
+
+    #[test]
+    fn test_refreshes_near_expiry_and_skips_disabled() {
+        let near_expiry = oauth(PoolProviderType::Kiro);
+        let fresh = oauth(PoolProviderType::Kiro);
+        let mut disabled = oauth(PoolProviderType::Kiro);
+        disabled.is_disabled = true;
+        let api_key = oauth(PoolProviderType::OpenAI);
+
+        let credentials = vec![
+            (near_expiry.clone(), token_expiring_in(5)),
+            (fresh, token_expiring_in(240)),
+            (disabled, token_expiring_in(1)),
+            (api_key, None),
+        ];
+
+        let tasks = plan_warmup(&credentials, &WarmupConfig::default());
+        assert_eq!(
+            tasks,
+            vec![WarmupTask {
+                uuid: near_expiry.uuid,
+                provider_type: PoolProviderType::Kiro,
+                refresh_token: true,
+                resolve_project: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resolves_missing_project_id() {
+        let mut gemini = oauth(PoolProviderType::Gemini);
+        gemini.credential = CredentialData::GeminiOAuth {
+            creds_file_path: "/tmp/gemini.json".to_string(),
+            project_id: None,
+        };
+        let with_project = oauth(PoolProviderType::Gemini);
+
+        let credentials = vec![
+            (gemini.clone(), token_expiring_in(240)),
+            (with_project, token_expiring_in(240)),
+        ];
+
+        let tasks = plan_warmup(&credentials, &WarmupConfig::default());
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].uuid, gemini.uuid);
+        assert!(tasks[0].resolve_project);
+        assert!(!tasks[0].refresh_token);
+    }
+
+    #[test]
+    fn test_warm_pool_refreshes_up_to_minimum() {
+        let kiro: Vec<_> = (0..4).map(|_| oauth(PoolProviderType::Kiro)).collect();
+        let credentials = vec![
+            (kiro[0].clone(), token_expiring_in(240)),
+            (kiro[1].clone(), token_expiring_in(2)),
+            (kiro[2].clone(), None),
+            (kiro[3].clone(), None),
+            // API Key 凭证可用即视为预热，不需要刷新
+            (oauth(PoolProviderType::OpenAI), None),
+        ];
+
+        // 已有 1 个预热凭证，只需再刷新 2 个
+        let tasks = plan_warm_pool(&credentials, 3);
+        assert_eq!(
+            tasks.iter().map(|t| t.uuid.as_str()).collect::<Vec<_>>(),
+            vec![kiro[1].uuid.as_str(), kiro[2].uuid.as_str()]
+        );
+        assert!(tasks.iter().all(|t| t.refresh_token && !t.resolve_project));
+
+        let counts = warm_counts(&credentials);
+        assert_eq!(counts.get(&PoolProviderType::Kiro), Some(&1));
+        assert_eq!(counts.get(&PoolProviderType::OpenAI), Some(&1));
+    }
+
+    #[test]
+    fn test_warm_pool_skips_when_minimum_met() {
+        let mut unhealthy = oauth(PoolProviderType::Kiro);
+        unhealthy.is_healthy = false;
+        let credentials = vec![
+            (oauth(PoolProviderType::Kiro), token_expiring_in(240)),
+            (oauth(PoolProviderType::Kiro), token_expiring_in(120)),
+            (oauth(PoolProviderType::Kiro), token_expiring_in(1)),
+            (unhealthy, None),
+        ];
+
+        // 预热凭证数已满足，不做多余的刷新
+        assert!(plan_warm_pool(&credentials, 2).is_empty());
+        assert!(plan_warm_pool(&credentials, 0).is_empty());
+        // 不可用的凭证不参与补足
+        assert_eq!(plan_warm_pool(&credentials, 5).len(), 1);
+    }
+
+    #[test]
+    fn test_respects_max_credentials() {
+        let credentials: Vec<_> = (0..5)
+            .map(|_| (oauth(PoolProviderType::Kiro), None))
+            .collect();
+        let config = WarmupConfig {
+            max_credentials: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(plan_warmup(&credentials, &config).len(), 2);
+    }
+}
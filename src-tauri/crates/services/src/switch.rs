@@ -1,4 +1,4 @@
-use crate::live_sync;
+use crate::live_sync::{self, SyncConflict};
 use once_cell::sync::Lazy;
 use proxycast_core::database::dao::providers::ProviderDao;
 use proxycast_core::database::DbConnection;
@@ -30,7 +30,11 @@ impl SwitchService {
         ProviderDao::get_current(&conn, app_type).map_err(|e| e.to_string())
     }
 
-    pub fn add_provider(db: &DbConnection, provider: Provider) -> Result<(), String> {
+    /// 添加 Provider；若为首个 Provider 则同步到外部配置并返回待用户处理的冲突
+    pub fn add_provider(
+        db: &DbConnection,
+        provider: Provider,
+    ) -> Result<Vec<SyncConflict>, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
 
         // Check if this is the first provider for this app type
@@ -47,16 +51,21 @@ impl SwitchService {
 
             if let Ok(app_type_enum) = provider.app_type.parse::<AppType>() {
                 if app_type_enum != AppType::ProxyCast {
-                    live_sync::sync_to_live(&app_type_enum, &provider)
+                    let report = live_sync::sync_to_live(&app_type_enum, &provider)
                         .map_err(|e| format!("Failed to sync: {e}"))?;
+                    return Ok(report.conflicts);
                 }
             }
         }
 
-        Ok(())
+        Ok(Vec::new())
     }
 
-    pub fn update_provider(db: &DbConnection, provider: Provider) -> Result<(), String> {
+    /// 更新 Provider；若为当前 Provider 则同步到外部配置并返回待用户处理的冲突
+    pub fn update_provider(
+        db: &DbConnection,
+        provider: Provider,
+    ) -> Result<Vec<SyncConflict>, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
 
         // Check if this is the current provider
@@ -73,13 +82,14 @@ impl SwitchService {
         if is_current {
             if let Ok(app_type_enum) = provider.app_type.parse::<AppType>() {
                 if app_type_enum != AppType::ProxyCast {
-                    live_sync::sync_to_live(&app_type_enum, &provider)
+                    let report = live_sync::sync_to_live(&app_type_enum, &provider)
                         .map_err(|e| format!("Failed to sync: {e}"))?;
+                    return Ok(report.conflicts);
                 }
             }
         }
 
-        Ok(())
+        Ok(Vec::new())
     }
 
     pub fn delete_provider(db: &DbConnection, app_type: &str, id: &str) -> Result<(), String> {
@@ -96,7 +106,12 @@ impl SwitchService {
         ProviderDao::delete(&conn, app_type, id).map_err(|e| e.to_string())
     }
 
-    pub fn switch_provider(db: &DbConnection, app_type: &str, id: &str) -> Result<(), String> {
+    /// 切换 Provider，返回同步外部配置时待用户处理的冲突
+    pub fn switch_provider(
+        db: &DbConnection,
+        app_type: &str,
+        id: &str,
+    ) -> Result<Vec<SyncConflict>, String> {
         use tracing::{error, info, warn};
 
         info!("开始切换 {} 配置到 provider: {}", app_type, id);
@@ -130,6 +145,7 @@ impl SwitchService {
         };
 
         // 实施事务保护：先尝试同步，再更新数据库
+        let mut conflicts = Vec::new();
         if app_type_enum != AppType::ProxyCast {
             // Step 1: Backfill - 回填当前配置
             if let Some(ref current) = current_provider {
@@ -154,19 +170,23 @@ impl SwitchService {
 
             // Step 2: 尝试同步新配置（在更新数据库前验证）
             info!("验证目标配置可同步性");
-            if let Err(sync_error) = live_sync::sync_to_live(&app_type_enum, &target_provider) {
-                error!("配置同步失败: {}", sync_error);
+            match live_sync::sync_to_live(&app_type_enum, &target_provider) {
+                Ok(report) => conflicts = report.conflicts,
+                Err(sync_error) => {
+                    error!("配置同步失败: {}", sync_error);
 
-                // 尝试恢复原配置（如果有）
-                if let Some(ref current) = current_provider {
-                    warn!("尝试恢复原配置: {}", current.name);
-                    if let Err(restore_error) = live_sync::sync_to_live(&app_type_enum, current) {
-                        error!("恢复原配置失败: {}", restore_error);
-                        return Err(format!("切换失败且无法恢复原配置: {sync_error}"));
+                    // 尝试恢复原配置（如果有）
+                    if let Some(ref current) = current_provider {
+                        warn!("尝试恢复原配置: {}", current.name);
+                        if let Err(restore_error) = live_sync::sync_to_live(&app_type_enum, current)
+                        {
+                            error!("恢复原配置失败: {}", restore_error);
+                            return Err(format!("切换失败且无法恢复原配置: {sync_error}"));
+                        }
                     }
-                }
 
-                return Err(format!("配置同步失败: {sync_error}"));
+                    return Err(format!("配置同步失败: {sync_error}"));
+                }
             }
         }
 
@@ -189,7 +209,7 @@ impl SwitchService {
         }
 
         info!("配置切换成功: {} -> {}", app_type, target_provider.name);
-        Ok(())
+        Ok(conflicts)
     }
 
     /// 异步版本的 switch_provider，优化 Windows 性能
@@ -202,7 +222,7 @@ impl SwitchService {
         db: &DbConnection,
         app_type: &str,
         id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<Vec<SyncConflict>, String> {
         use tracing::{error, info, warn};
 
         info!("开始切换 {} 配置到 provider: {} (异步)", app_type, id);
@@ -247,6 +267,7 @@ impl SwitchService {
         };
 
         // Step 2: 执行文件 I/O（在后台线程，不持有锁）
+        let mut conflicts = Vec::new();
         if ctx.app_type_enum != AppType::ProxyCast {
             let current_for_backfill = ctx.current_provider.clone();
             let app_type_for_sync = ctx.app_type_enum.clone();
@@ -295,27 +316,27 @@ impl SwitchService {
             let current_for_restore = ctx.current_provider.clone();
             let app_type_for_sync = ctx.app_type_enum.clone();
 
-            tokio::task::spawn_blocking(move || {
+            conflicts = tokio::task::spawn_blocking(move || {
                 info!("验证目标配置可同步性");
-                if let Err(sync_error) =
-                    live_sync::sync_to_live(&app_type_for_sync, &target_for_sync)
-                {
-                    error!("配置同步失败: {}", sync_error);
-
-                    // 尝试恢复原配置（如果有）
-                    if let Some(ref current) = current_for_restore {
-                        warn!("尝试恢复原配置: {}", current.name);
-                        if let Err(restore_error) =
-                            live_sync::sync_to_live(&app_type_for_sync, current)
-                        {
-                            error!("恢复原配置失败: {}", restore_error);
-                            return Err(format!("切换失败且无法恢复原配置: {sync_error}"));
+                match live_sync::sync_to_live(&app_type_for_sync, &target_for_sync) {
+                    Ok(report) => Ok(report.conflicts),
+                    Err(sync_error) => {
+                        error!("配置同步失败: {}", sync_error);
+
+                        // 尝试恢复原配置（如果有）
+                        if let Some(ref current) = current_for_restore {
+                            warn!("尝试恢复原配置: {}", current.name);
+                            if let Err(restore_error) =
+                                live_sync::sync_to_live(&app_type_for_sync, current)
+                            {
+                                error!("恢复原配置失败: {}", restore_error);
+                                return Err(format!("切换失败且无法恢复原配置: {sync_error}"));
+                            }
                         }
-                    }
 
-                    return Err(format!("配置同步失败: {sync_error}"));
+                        Err(format!("配置同步失败: {sync_error}"))
+                    }
                 }
-                Ok(())
             })
             .await
             .map_err(|e| format!("后台任务失败: {e}"))??;
@@ -351,7 +372,7 @@ impl SwitchService {
         }
 
         info!("配置切换成功: {} -> {}", app_type, ctx.target_provider.name);
-        Ok(())
+        Ok(conflicts)
     }
 
     /// Import current live config as a default provider
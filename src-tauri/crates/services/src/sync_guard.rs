@@ -0,0 +1,325 @@
+//! 外部配置同步的带外修改检测
+//!
+//! Prompt / MCP 同步会改写外部应用的配置文件（如 `~/CLAUDE.md`、`~/.claude/settings.json`）。
+//! 每次写入后记录写入内容的哈希与快照；下次同步前先比对外部文件的当前内容，
+//! 若与上次写入时不一致，说明文件被用户手动修改过，此时按 [`ExternalSyncPolicy`]：
+//! - `Abort`: 放弃写入并报告冲突
+//! - `MergeNonOverlapping`: 以快照为基准三方合并，仅在同一条目两侧都修改时放弃写入
+//!
+//! 每次同步结果都会追加到 `sync_outcomes.jsonl` 中。
+
+use crate::live_sync::{three_way_merge, ConflictPolicy, SyncConflict, SyncResolution};
+use proxycast_core::config::{load_config, ExternalSyncPolicy};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 同步结果日志文件名
+const OUTCOME_LOG_FILE: &str = "sync_outcomes.jsonl";
+
+/// 上次同步写入的内容记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    /// 写入内容的 SHA-256
+    pub hash: String,
+    /// 写入内容的快照（三方合并的基准）
+    pub snapshot: Value,
+    /// 写入时间（RFC 3339）
+    pub synced_at: String,
+}
+
+/// 单次同步的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SyncOutcome {
+    /// 外部文件未被修改（或首次同步），直接写入
+    Written,
+    /// 外部修改与本地修改互不重叠，已合并写入
+    Merged {
+        merged: Value,
+        resolutions: Vec<SyncResolution>,
+    },
+    /// 检测到外部修改且无法自动合并，未写入
+    Conflict { conflicts: Vec<SyncConflict> },
+}
+
+impl SyncOutcome {
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, SyncOutcome::Conflict { .. })
+    }
+
+    /// 冲突描述（用于返回给前端的错误信息）
+    pub fn conflict_message(&self, target: &str) -> Option<String> {
+        match self {
+            SyncOutcome::Conflict { conflicts } => {
+                let fields: Vec<&str> = conflicts
+                    .iter()
+                    .map(|c| if c.field.is_empty() { "*" } else { &c.field })
+                    .collect();
+                Some(format!(
+                    "{target} 已被外部修改，为避免覆盖已取消同步（冲突: {}）",
+                    fields.join(", ")
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 计算内容哈希
+///
+/// 对 JSON 值按序列化结果计算，文本内容使用 `Value::String` 包装。
+pub fn content_hash(value: &Value) -> String {
+    let serialized = serde_json::to_string(value).unwrap_or_default();
+    format!("{:x}", Sha256::digest(serialized.as_bytes()))
+}
+
+/// 默认状态目录（~/.proxycast/live_sync）
+pub fn default_state_dir() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".proxycast").join("live_sync"))
+}
+
+/// 读取配置的冲突策略，读取失败时使用默认策略
+pub fn configured_policy() -> ExternalSyncPolicy {
+    load_config()
+        .map(|c| c.external_sync.conflict_policy)
+        .unwrap_or_default()
+}
+
+fn record_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{key}.sync.json"))
+}
+
+/// 读取上次同步记录
+pub fn load_record(dir: &Path, key: &str) -> Option<SyncRecord> {
+    let content = fs::read_to_string(record_path(dir, key)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 保存同步记录（临时文件 + 重命名）
+pub fn save_record(dir: &Path, key: &str, snapshot: &Value) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create sync state dir: {e}"))?;
+    let record = SyncRecord {
+        hash: content_hash(snapshot),
+        snapshot: snapshot.clone(),
+        synced_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let path = record_path(dir, key);
+    let temp_path = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(&record).map_err(|e| e.to_string())?;
+    fs::write(&temp_path, content).map_err(|e| format!("Failed to write sync record: {e}"))?;
+    fs::rename(&temp_path, &path).map_err(|e| format!("Failed to rename sync record: {e}"))
+}
+
+/// 计算同步方案
+///
+/// - `record`: 上次同步记录，`None` 表示首次同步
+/// - `current`: 外部文件当前内容，`None` 表示文件（或条目）不存在
+/// - `desired`: ProxyCast 期望写入的内容
+pub fn plan_sync(
+    record: Option<&SyncRecord>,
+    current: Option<&Value>,
+    desired: &Value,
+    policy: ExternalSyncPolicy,
+) -> SyncOutcome {
+    let (Some(record), Some(current)) = (record, current) else {
+        return SyncOutcome::Written;
+    };
+    if current == desired || content_hash(current) == record.hash {
+        return SyncOutcome::Written;
+    }
+
+    match policy {
+        ExternalSyncPolicy::Abort => SyncOutcome::Conflict {
+            conflicts: vec![SyncConflict {
+                field: String::new(),
+                local: Some(desired.clone()),
+                remote: Some(current.clone()),
+            }],
+        },
+        ExternalSyncPolicy::MergeNonOverlapping => {
+            let report =
+                three_way_merge(&record.snapshot, desired, current, ConflictPolicy::Manual);
+            if report.conflicts.is_empty() {
+                SyncOutcome::Merged {
+                    merged: report.merged,
+                    resolutions: report.resolutions,
+                }
+            } else {
+                SyncOutcome::Conflict {
+                    conflicts: report.conflicts,
+                }
+            }
+        }
+    }
+}
+
+/// 带冲突检测的同步
+///
+/// 按 [`plan_sync`] 的结果调用 `write` 写入最终内容并更新同步记录；
+/// 冲突时不写入、不更新记录，下次同步仍能检测到冲突。
+pub fn guarded_sync<F>(
+    dir: &Path,
+    key: &str,
+    current: Option<&Value>,
+    desired: &Value,
+    policy: ExternalSyncPolicy,
+    write: F,
+) -> Result<SyncOutcome, String>
+where
+    F: FnOnce(&Value) -> Result<(), String>,
+{
+    let record = load_record(dir, key);
+    let outcome = plan_sync(record.as_ref(), current, desired, policy);
+
+    let content = match &outcome {
+        SyncOutcome::Written => Some(desired),
+        SyncOutcome::Merged { merged, .. } => Some(merged),
+        SyncOutcome::Conflict { .. } => None,
+    };
+    if let Some(content) = content {
+        write(content)?;
+        save_record(dir, key, content)?;
+    }
+
+    record_outcome(dir, key, &outcome);
+    Ok(outcome)
+}
+
+/// 追加同步结果日志
+pub fn record_outcome(dir: &Path, key: &str, outcome: &SyncOutcome) {
+    match outcome {
+        SyncOutcome::Written => tracing::info!("[SYNC_GUARD] {} 已同步", key),
+        SyncOutcome::Merged { resolutions, .. } => tracing::info!(
+            "[SYNC_GUARD] {} 检测到外部修改，已合并 {} 处变更",
+            key,
+            resolutions.len()
+        ),
+        SyncOutcome::Conflict { conflicts } => tracing::warn!(
+            "[SYNC_GUARD] {} 检测到外部修改，{} 处冲突，已取消写入",
+            key,
+            conflicts.len()
+        ),
+    }
+
+    let entry = serde_json::json!({
+        "target": key,
+        "at": chrono::Utc::now().to_rfc3339(),
+        "outcome": outcome,
+    });
+    let result = fs::create_dir_all(dir).and_then(|_| {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(OUTCOME_LOG_FILE))?;
+        writeln!(file, "{entry}")
+    });
+    if let Err(e) = result {
+        tracing::warn!("[SYNC_GUARD] 写入同步日志失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn sync_to(
+        dir: &Path,
+        target: &mut Option<Value>,
+        desired: Value,
+        policy: ExternalSyncPolicy,
+    ) -> SyncOutcome {
+        let current = target.clone();
+        guarded_sync(dir, "target", current.as_ref(), &desired, policy, |v| {
+            *target = Some(v.clone());
+            Ok(())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_abort_policy_keeps_externally_edited_target() {
+        let dir = TempDir::new().unwrap();
+        let mut target = None;
+        let policy = ExternalSyncPolicy::Abort;
+
+        let first = sync_to(dir.path(), &mut target, json!("v1"), policy);
+        assert_eq!(first, SyncOutcome::Written);
+        // 未被外部修改时正常覆盖
+        let second = sync_to(dir.path(), &mut target, json!("v2"), policy);
+        assert_eq!(second, SyncOutcome::Written);
+
+        // 用户手动编辑了文件
+        target = Some(json!("edited by user"));
+        let outcome = sync_to(dir.path(), &mut target, json!("v3"), policy);
+        assert!(outcome.is_conflict());
+        assert_eq!(target, Some(json!("edited by user")));
+
+        // 冲突不更新记录，重试仍然冲突
+        let retry = sync_to(dir.path(), &mut target, json!("v3"), policy);
+        assert!(retry.is_conflict());
+
+        let log = fs::read_to_string(dir.path().join(OUTCOME_LOG_FILE)).unwrap();
+        assert_eq!(log.lines().count(), 4);
+        assert!(log.lines().last().unwrap().contains("\"conflict\""));
+    }
+
+    #[test]
+    fn test_merge_policy_merges_non_overlapping_entries() {
+        let dir = TempDir::new().unwrap();
+        let policy = ExternalSyncPolicy::MergeNonOverlapping;
+        let mut target = Some(json!({ "fs": { "command": "fs" } }));
+
+        sync_to(
+            dir.path(),
+            &mut target,
+            json!({ "fs": { "command": "fs" } }),
+            policy,
+        );
+
+        // 外部新增条目，本地修改另一条目
+        target = Some(json!({
+            "fs": { "command": "fs" },
+            "git": { "command": "git" }
+        }));
+        let outcome = sync_to(
+            dir.path(),
+            &mut target,
+            json!({ "fs": { "command": "fs-v2" } }),
+            policy,
+        );
+        assert!(matches!(outcome, SyncOutcome::Merged { .. }));
+        assert_eq!(
+            target,
+            Some(json!({
+                "fs": { "command": "fs-v2" },
+                "git": { "command": "git" }
+            }))
+        );
+
+        // 同一条目两侧都修改时放弃写入
+        target = Some(json!({
+            "fs": { "command": "fs-user" },
+            "git": { "command": "git" }
+        }));
+        let outcome = sync_to(
+            dir.path(),
+            &mut target,
+            json!({ "fs": { "command": "fs-v3" }, "git": { "command": "git" } }),
+            policy,
+        );
+        match outcome {
+            SyncOutcome::Conflict { conflicts } => {
+                assert_eq!(conflicts.len(), 1);
+                assert_eq!(conflicts[0].field, "fs.command");
+            }
+            other => panic!("expected conflict, got {other:?}"),
+        }
+        assert_eq!(target.unwrap()["fs"]["command"], "fs-user");
+    }
+}
@@ -10,7 +10,7 @@
 
 use crate::kiro_event_service::KiroEventService;
 use chrono::Utc;
-use dashmap::DashMap;
+use proxycast_core::credential::RefreshLocks;
 use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
 use proxycast_core::database::DbConnection;
 use proxycast_core::models::provider_pool_model::{
@@ -19,7 +19,6 @@ use proxycast_core::models::provider_pool_model::{
 use proxycast_providers::providers::gemini::GeminiProvider;
 use proxycast_providers::providers::kiro::KiroProvider;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 /// Token 刷新错误类型
 #[derive(Debug, Clone, PartialEq)]
@@ -56,7 +55,7 @@ pub struct RefreshErrorClassification {
 /// Token 缓存服务
 pub struct TokenCacheService {
     /// 每凭证一把锁，防止并发刷新
-    locks: DashMap<String, Arc<Mutex<()>>>,
+    locks: Arc<RefreshLocks>,
 }
 
 impl Default for TokenCacheService {
@@ -68,10 +67,15 @@ impl Default for TokenCacheService {
 impl TokenCacheService {
     pub fn new() -> Self {
         Self {
-            locks: DashMap::new(),
+            locks: Arc::new(RefreshLocks::new()),
         }
     }
 
+    /// 按凭证划分的刷新锁，供其他直接刷新凭证的路径共享
+    pub fn refresh_locks(&self) -> Arc<RefreshLocks> {
+        self.locks.clone()
+    }
+
     /// 获取有效的 Token（核心方法）
     ///
     /// 1. 检查数据库缓存是否有效
@@ -242,13 +246,7 @@ impl TokenCacheService {
         }
 
         // 获取该凭证的锁
-        let lock = self
-            .locks
-            .entry(uuid.to_string())
-            .or_insert_with(|| Arc::new(Mutex::new(())))
-            .clone();
-
-        let _guard = lock.lock().await;
+        let _guard = self.locks.lock(uuid).await;
 
         // 双重检查：可能其他线程已完成刷新
         if !force {
@@ -495,6 +493,17 @@ impl TokenCacheService {
                     last_refresh_error: None,
                 })
             }
+            CredentialData::LocalOpenAI { api_key, .. } => {
+                // 本地服务不需要刷新，API Key 可选
+                Ok(CachedTokenInfo {
+                    access_token: api_key.clone(),
+                    refresh_token: None,
+                    expiry_time: None, // 永不过期
+                    last_refresh: Some(Utc::now()),
+                    refresh_error_count: 0,
+                    last_refresh_error: None,
+                })
+            }
         }
     }
 
@@ -849,6 +858,14 @@ impl TokenCacheService {
                 refresh_error_count: 0,
                 last_refresh_error: None,
             }),
+            CredentialData::LocalOpenAI { api_key, .. } => Ok(CachedTokenInfo {
+                access_token: api_key.clone(),
+                refresh_token: None,
+                expiry_time: None,
+                last_refresh: None,
+                refresh_error_count: 0,
+                last_refresh_error: None,
+            }),
         }
     }
 
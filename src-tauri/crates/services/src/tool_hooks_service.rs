@@ -1,10 +1,16 @@
 //! 工具钩子管理服务
 //!
 //! 提供工具执行前后的钩子机制，用于自动化上下文记忆管理
+//!
+//! 除了基于规则的记忆钩子外，还支持按优先级排序的结构化过滤器：
+//! - 前置钩子（`PreToolHook`）可以改写或拒绝工具参数
+//! - 后置钩子（`PostToolHook`）可以转换工具结果
 
 use crate::context_memory_service::{ContextMemoryService, MemoryEntry, MemoryFileType};
+use proxycast_core::workspace::resolve_within;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tracing::{debug, error, info};
 
@@ -118,10 +124,111 @@ pub struct HookContext {
     pub metadata: HashMap<String, String>,
 }
 
+/// 前置钩子决策
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookDecision {
+    /// 保持参数不变继续执行
+    Proceed,
+    /// 使用改写后的参数继续执行
+    Modify(HashMap<String, String>),
+    /// 拒绝本次工具调用
+    Deny(String),
+}
+
+/// 工具前置钩子
+///
+/// 在工具执行前按优先级依次调用，`context.tool_parameters` 为前一个钩子处理后的参数。
+pub trait PreToolHook: Send + Sync {
+    /// 钩子名称（用于日志和拒绝信息）
+    fn name(&self) -> &str;
+
+    /// 决定是否继续执行工具
+    fn before_tool_use(&self, context: &HookContext) -> HookDecision;
+}
+
+/// 工具后置钩子
+///
+/// 在工具成功执行后按优先级依次调用，可以转换工具结果。
+pub trait PostToolHook: Send + Sync {
+    /// 钩子名称（用于日志）
+    fn name(&self) -> &str;
+
+    /// 转换工具结果
+    fn after_tool_use(&self, context: &HookContext, result: String) -> String;
+}
+
+/// 文件工具中表示路径的参数名
+const PATH_PARAMETER_KEYS: &[&str] = &["path", "file_path", "target_path", "directory"];
+
+/// Workspace 路径沙箱钩子
+///
+/// 将文件类工具的路径参数解析到 workspace 根目录内，
+/// 超出根目录（`..` 穿越或符号链接逃逸）的调用直接拒绝。
+pub struct WorkspaceSandboxHook {
+    root: PathBuf,
+}
+
+impl WorkspaceSandboxHook {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl PreToolHook for WorkspaceSandboxHook {
+    fn name(&self) -> &str {
+        "workspace-sandbox"
+    }
+
+    fn before_tool_use(&self, context: &HookContext) -> HookDecision {
+        let Some(params) = &context.tool_parameters else {
+            return HookDecision::Proceed;
+        };
+
+        let mut rewritten = params.clone();
+        for key in PATH_PARAMETER_KEYS {
+            let Some(value) = params.get(*key) else {
+                continue;
+            };
+            match resolve_within(&self.root, Path::new(value)) {
+                Ok(resolved) => {
+                    rewritten.insert(key.to_string(), resolved.to_string_lossy().to_string());
+                }
+                Err(e) => return HookDecision::Deny(e.to_string()),
+            }
+        }
+
+        if &rewritten == params {
+            HookDecision::Proceed
+        } else {
+            HookDecision::Modify(rewritten)
+        }
+    }
+}
+
+/// 按优先级排序的钩子列表
+type HookList<T> = Arc<Mutex<Vec<(u32, Arc<T>)>>>;
+
+/// 经过钩子处理的工具调用结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCallOutcome {
+    /// 实际执行时使用的参数
+    pub parameters: HashMap<String, String>,
+    /// 工具输出（出错时为错误信息）
+    pub output: String,
+    /// 是否为错误结果
+    pub is_error: bool,
+    /// 拒绝本次调用的前置钩子名称
+    pub denied_by: Option<String>,
+}
+
 /// 工具钩子管理器
 pub struct ToolHooksService {
     /// 钩子规则
     rules: Arc<Mutex<Vec<HookRule>>>,
+    /// 前置钩子（按优先级排序）
+    pre_hooks: HookList<dyn PreToolHook>,
+    /// 后置钩子（按优先级排序）
+    post_hooks: HookList<dyn PostToolHook>,
     /// 上下文记忆服务
     memory_service: Arc<ContextMemoryService>,
     /// 执行统计
@@ -148,6 +255,8 @@ impl ToolHooksService {
     pub fn new(memory_service: Arc<ContextMemoryService>) -> Self {
         let service = Self {
             rules: Arc::new(Mutex::new(Vec::new())),
+            pre_hooks: Arc::new(Mutex::new(Vec::new())),
+            post_hooks: Arc::new(Mutex::new(Vec::new())),
             memory_service,
             execution_stats: Arc::new(Mutex::new(HashMap::new())),
         };
@@ -284,6 +393,129 @@ impl ToolHooksService {
         Ok(())
     }
 
+    /// 注册前置钩子
+    ///
+    /// 数字越小越先执行，相同优先级按注册顺序执行。
+    pub fn register_pre_hook(&self, priority: u32, hook: Arc<dyn PreToolHook>) {
+        let mut hooks = self.pre_hooks.lock().unwrap();
+        let index = hooks.partition_point(|(p, _)| *p <= priority);
+        info!("已注册前置钩子: {} (优先级 {})", hook.name(), priority);
+        hooks.insert(index, (priority, hook));
+    }
+
+    /// 注册后置钩子
+    ///
+    /// 数字越小越先执行，相同优先级按注册顺序执行。
+    pub fn register_post_hook(&self, priority: u32, hook: Arc<dyn PostToolHook>) {
+        let mut hooks = self.post_hooks.lock().unwrap();
+        let index = hooks.partition_point(|(p, _)| *p <= priority);
+        info!("已注册后置钩子: {} (优先级 {})", hook.name(), priority);
+        hooks.insert(index, (priority, hook));
+    }
+
+    /// 经过钩子执行工具调用
+    ///
+    /// 依次执行 [`before_tool_call`](Self::before_tool_call)、工具本身和
+    /// [`after_tool_call`](Self::after_tool_call)；前置钩子拒绝时不执行工具。
+    pub fn execute_tool_call<F>(&self, context: HookContext, execute: F) -> ToolCallOutcome
+    where
+        F: FnOnce(&HashMap<String, String>) -> Result<String, String>,
+    {
+        let context = match self.before_tool_call(context) {
+            Ok(context) => context,
+            Err(denied) => return denied,
+        };
+        let result = execute(context.tool_parameters.as_ref().unwrap_or(&HashMap::new()));
+        self.after_tool_call(context, result)
+    }
+
+    /// 工具执行前调用
+    ///
+    /// 1. 依次执行前置钩子，`Modify` 改写 `context.tool_parameters`
+    /// 2. 触发 `PreToolUse` 规则钩子
+    ///
+    /// 返回用于执行工具的上下文；被前置钩子拒绝时返回 `Err`（错误结果，工具不应执行）。
+    pub fn before_tool_call(
+        &self,
+        mut context: HookContext,
+    ) -> Result<HookContext, ToolCallOutcome> {
+        let tool_name = context.tool_name.clone().unwrap_or_default();
+        let pre_hooks: Vec<_> = self
+            .pre_hooks
+            .lock()
+            .map(|hooks| hooks.iter().map(|(_, h)| h.clone()).collect())
+            .unwrap_or_default();
+
+        for hook in pre_hooks {
+            match hook.before_tool_use(&context) {
+                HookDecision::Proceed => {}
+                HookDecision::Modify(parameters) => {
+                    debug!("前置钩子 {} 改写了工具 {} 的参数", hook.name(), tool_name);
+                    context.tool_parameters = Some(parameters);
+                }
+                HookDecision::Deny(reason) => {
+                    info!(
+                        "前置钩子 {} 拒绝了工具调用 {}: {}",
+                        hook.name(),
+                        tool_name,
+                        reason
+                    );
+                    return Err(ToolCallOutcome {
+                        parameters: context.tool_parameters.unwrap_or_default(),
+                        output: format!("工具调用被拒绝 ({}): {}", hook.name(), reason),
+                        is_error: true,
+                        denied_by: Some(hook.name().to_string()),
+                    });
+                }
+            }
+        }
+
+        if let Err(e) = self.execute_hooks(HookTrigger::PreToolUse, &context) {
+            error!("执行 PreToolUse 钩子失败: {}", e);
+        }
+        Ok(context)
+    }
+
+    /// 工具执行后调用
+    ///
+    /// 1. 成功时依次执行后置钩子转换结果
+    /// 2. 触发 `PostToolUse` 规则钩子
+    pub fn after_tool_call(
+        &self,
+        mut context: HookContext,
+        result: Result<String, String>,
+    ) -> ToolCallOutcome {
+        let (output, is_error) = match result {
+            Ok(mut output) => {
+                let post_hooks: Vec<_> = self
+                    .post_hooks
+                    .lock()
+                    .map(|hooks| hooks.iter().map(|(_, h)| h.clone()).collect())
+                    .unwrap_or_default();
+                for hook in post_hooks {
+                    output = hook.after_tool_use(&context, output);
+                }
+                (output, false)
+            }
+            Err(e) => {
+                context.error_info = Some(e.clone());
+                (e, true)
+            }
+        };
+
+        context.tool_result = Some(output.clone());
+        if let Err(e) = self.execute_hooks(HookTrigger::PostToolUse, &context) {
+            error!("执行 PostToolUse 钩子失败: {}", e);
+        }
+
+        ToolCallOutcome {
+            parameters: context.tool_parameters.unwrap_or_default(),
+            output,
+            is_error,
+            denied_by: None,
+        }
+    }
+
     /// 评估钩子条件
     fn evaluate_conditions(&self, rule: &HookRule, context: &HookContext) -> bool {
         if rule.conditions.is_empty() {
@@ -685,6 +917,145 @@ mod tests {
         assert!(memories.iter().any(|m| m.title == "自定义发现"));
     }
 
+    fn tool_context(tool_name: &str, path: &str) -> HookContext {
+        HookContext {
+            session_id: "test-session".to_string(),
+            tool_name: Some(tool_name.to_string()),
+            tool_parameters: Some(HashMap::from([("path".to_string(), path.to_string())])),
+            tool_result: None,
+            message_content: None,
+            message_count: 0,
+            error_info: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// 将相对路径改写到工作区下
+    struct WorkspacePathHook;
+
+    impl PreToolHook for WorkspacePathHook {
+        fn name(&self) -> &str {
+            "workspace-path"
+        }
+
+        fn before_tool_use(&self, context: &HookContext) -> HookDecision {
+            let Some(mut params) = context.tool_parameters.clone() else {
+                return HookDecision::Proceed;
+            };
+            match params.get("path") {
+                Some(path) if !path.starts_with('/') => {
+                    let rewritten = format!("/workspace/{path}");
+                    params.insert("path".to_string(), rewritten);
+                    HookDecision::Modify(params)
+                }
+                _ => HookDecision::Proceed,
+            }
+        }
+    }
+
+    /// 拒绝写入工作区之外的路径
+    struct DenyOutsideWorkspaceHook;
+
+    impl PreToolHook for DenyOutsideWorkspaceHook {
+        fn name(&self) -> &str {
+            "deny-outside-workspace"
+        }
+
+        fn before_tool_use(&self, context: &HookContext) -> HookDecision {
+            let path = context
+                .tool_parameters
+                .as_ref()
+                .and_then(|p| p.get("path"))
+                .cloned()
+                .unwrap_or_default();
+            if context.tool_name.as_deref() == Some("write_file")
+                && !path.starts_with("/workspace/")
+            {
+                HookDecision::Deny(format!("禁止写入工作区之外的路径: {path}"))
+            } else {
+                HookDecision::Proceed
+            }
+        }
+    }
+
+    struct UppercaseResultHook;
+
+    impl PostToolHook for UppercaseResultHook {
+        fn name(&self) -> &str {
+            "uppercase"
+        }
+
+        fn after_tool_use(&self, _context: &HookContext, result: String) -> String {
+            result.to_uppercase()
+        }
+    }
+
+    #[test]
+    fn test_pre_hook_rewrites_path() {
+        let (_memory_service, hooks_service, _temp_dir) = create_test_services();
+        // 改写在前，拒绝检查在后，改写后的路径可以通过检查
+        hooks_service.register_pre_hook(20, Arc::new(DenyOutsideWorkspaceHook));
+        hooks_service.register_pre_hook(10, Arc::new(WorkspacePathHook));
+        hooks_service.register_post_hook(10, Arc::new(UppercaseResultHook));
+
+        let outcome = hooks_service
+            .execute_tool_call(tool_context("write_file", "notes.md"), |params| {
+                Ok(format!("wrote {}", params["path"]))
+            });
+
+        assert!(!outcome.is_error);
+        assert_eq!(outcome.denied_by, None);
+        assert_eq!(outcome.parameters["path"], "/workspace/notes.md");
+        assert_eq!(outcome.output, "WROTE /WORKSPACE/NOTES.MD");
+    }
+
+    #[test]
+    fn test_pre_hook_denies_call() {
+        let (_memory_service, hooks_service, _temp_dir) = create_test_services();
+        hooks_service.register_pre_hook(10, Arc::new(WorkspacePathHook));
+        hooks_service.register_pre_hook(20, Arc::new(DenyOutsideWorkspaceHook));
+
+        let mut executed = false;
+        let outcome =
+            hooks_service.execute_tool_call(tool_context("write_file", "/etc/passwd"), |_| {
+                executed = true;
+                Ok("ok".to_string())
+            });
+
+        assert!(!executed, "被拒绝的调用不应执行工具");
+        assert!(outcome.is_error);
+        assert_eq!(outcome.denied_by.as_deref(), Some("deny-outside-workspace"));
+        assert!(outcome.output.contains("/etc/passwd"));
+    }
+
+    #[test]
+    fn test_workspace_sandbox_hook() {
+        let (_memory_service, hooks_service, temp_dir) = create_test_services();
+        let root = temp_dir.path().join("workspace");
+        std::fs::create_dir_all(&root).unwrap();
+        hooks_service.register_pre_hook(0, Arc::new(WorkspaceSandboxHook::new(&root)));
+
+        let outcome = hooks_service
+            .execute_tool_call(tool_context("read_file", "notes.md"), |params| {
+                Ok(params["path"].clone())
+            });
+        assert!(!outcome.is_error);
+        assert_eq!(
+            outcome.output,
+            root.canonicalize()
+                .unwrap()
+                .join("notes.md")
+                .to_string_lossy()
+        );
+
+        let outcome = hooks_service
+            .execute_tool_call(tool_context("read_file", "../../.ssh/id_rsa"), |_| {
+                Ok("secret".to_string())
+            });
+        assert!(outcome.is_error);
+        assert_eq!(outcome.denied_by.as_deref(), Some("workspace-sandbox"));
+    }
+
     #[test]
     fn test_template_interpolation() {
         let (_memory_service, hooks_service, _temp_dir) = create_test_services();
@@ -177,7 +177,7 @@ impl ProxyCastLlmProvider {
         use proxycast_core::models::anthropic::AnthropicMessage;
         use proxycast_providers::converter::anthropic_to_openai::convert_anthropic_to_openai;
         use proxycast_providers::providers::traits::CredentialProvider;
-        use proxycast_server_utils::parse_cw_response;
+        use proxycast_server_utils::{parse_cw_response, CWParseError};
 
         let mut kiro = KiroProvider::new();
         kiro.load_credentials_from_path(creds_file_path)
@@ -195,7 +195,7 @@ impl ProxyCastLlmProvider {
         let request = AnthropicMessagesRequest {
             model: model.to_string(),
             max_tokens: Some(4096),
-            system: Some(serde_json::Value::String(system_prompt.to_string())),
+            system: Some(system_prompt.to_string().into()),
             messages: vec![AnthropicMessage {
                 role: "user".to_string(),
                 content: serde_json::Value::String(user_message.to_string()),
@@ -204,6 +204,8 @@ impl ProxyCastLlmProvider {
             temperature: None,
             tools: None,
             tool_choice: None,
+            thinking: None,
+            metadata: None,
         };
 
         // 转换为 OpenAI 格式并调用
@@ -227,7 +229,9 @@ impl ProxyCastLlmProvider {
             .await
             .map_err(|e| SkillError::ProviderError(format!("读取响应失败: {}", e)))?;
         let body = String::from_utf8_lossy(&bytes).to_string();
-        let parsed = parse_cw_response(&body);
+        let parsed = parse_cw_response(&body)
+            .or_else(CWParseError::recover)
+            .map_err(|e| SkillError::ProviderError(e.to_string()))?;
 
         Ok(parsed.content)
     }
@@ -250,7 +254,7 @@ impl ProxyCastLlmProvider {
         let request = AnthropicMessagesRequest {
             model: model.to_string(),
             max_tokens: Some(4096),
-            system: Some(serde_json::Value::String(system_prompt.to_string())),
+            system: Some(system_prompt.to_string().into()),
             messages: vec![AnthropicMessage {
                 role: "user".to_string(),
                 content: serde_json::Value::String(user_message.to_string()),
@@ -259,6 +263,8 @@ impl ProxyCastLlmProvider {
             temperature: None,
             tools: None,
             tool_choice: None,
+            thinking: None,
+            metadata: None,
         };
 
         let resp = claude
@@ -334,6 +340,11 @@ impl ProxyCastLlmProvider {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            n: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream_options: None,
         };
 
         let resp = openai
@@ -3,6 +3,7 @@
 //! 处理 WebSocket 连接和消息
 
 use super::{
+    api_key_identity,
     handlers::{parse_rpc_request, serialize_rpc_response, RpcHandler, RpcHandlerState},
     WsApiRequest, WsApiResponse, WsConfig, WsConnectionManager, WsEndpoint, WsError, WsMessage,
 };
@@ -89,15 +90,25 @@ pub async fn ws_handler(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    ws.on_upgrade(move |socket| handle_socket(socket, state, client_info))
+    let identity = api_key_identity(key);
+    ws.on_upgrade(move |socket| handle_socket(socket, state, client_info, identity))
 }
 
 /// 处理 WebSocket 连接
-async fn handle_socket(socket: WebSocket, state: WsHandlerState, client_info: Option<String>) {
+async fn handle_socket(
+    socket: WebSocket,
+    state: WsHandlerState,
+    client_info: Option<String>,
+    identity: String,
+) {
     let conn_id = uuid::Uuid::new_v4().to_string();
 
     // 注册连接
-    if let Err(e) = state.manager.register(conn_id.clone(), client_info.clone()) {
+    if let Err(e) =
+        state
+            .manager
+            .register_with_identity(conn_id.clone(), client_info.clone(), Some(identity))
+    {
         state.logs.write().await.add(
             "error",
             &format!("[WS] Failed to register connection: {}", e.message),
@@ -285,6 +296,8 @@ async fn handle_message(
         WsMessage::KiroCredentialEvent(_) => Some(WsMessage::Error(WsError::invalid_message(
             "KiroCredentialEvent messages are server-to-client only",
         ))),
+        // 升级时已通过请求头完成认证
+        WsMessage::Auth { .. } => None,
     }
 }
 
@@ -20,8 +20,9 @@ pub use processor::MessageProcessor;
 pub use protocol::{GatewayRpcRequest, GatewayRpcResponse, RpcError, RpcMethod};
 pub use proxycast_core::websocket::types;
 pub use proxycast_core::websocket::{
-    KiroTokenInfo, WsApiRequest, WsApiResponse, WsConfig, WsConnection, WsEndpoint, WsError,
-    WsKiroEvent, WsMessage, WsStats, WsStatsSnapshot, WsStreamChunk, WsStreamEnd,
+    api_key_identity, KiroTokenInfo, WsApiRequest, WsApiResponse, WsConfig, WsConnection,
+    WsEndpoint, WsError, WsKiroEvent, WsMessage, WsStats, WsStatsSnapshot, WsStreamChunk,
+    WsStreamEnd,
 };
 
 use dashmap::DashMap;
@@ -55,6 +56,16 @@ impl WsConnectionManager {
 
     /// 注册新连接
     pub fn register(&self, id: String, client_info: Option<String>) -> Result<(), WsError> {
+        self.register_with_identity(id, client_info, None)
+    }
+
+    /// 注册新连接并附带认证身份
+    pub fn register_with_identity(
+        &self,
+        id: String,
+        client_info: Option<String>,
+        identity: Option<String>,
+    ) -> Result<(), WsError> {
         if self.connections.len() >= self.config.max_connections {
             return Err(WsError::internal(
                 None,
@@ -64,7 +75,7 @@ impl WsConnectionManager {
                 ),
             ));
         }
-        let conn = WsConnection::new(id.clone(), client_info);
+        let conn = WsConnection::new(id.clone(), client_info).with_identity(identity);
         self.connections.insert(id, conn);
         self.stats.on_connect();
         Ok(())
@@ -84,6 +95,21 @@ impl WsConnectionManager {
         self.connections.get(id).map(|r| r.clone())
     }
 
+    /// 设置连接的认证身份（通过认证消息完成认证时使用）
+    pub fn set_identity(&self, id: &str, identity: String) {
+        if let Some(mut conn) = self.connections.get_mut(id) {
+            conn.identity = Some(identity);
+        }
+    }
+
+    /// 指定认证身份的活跃连接数
+    pub fn count_by_identity(&self, identity: &str) -> usize {
+        self.connections
+            .iter()
+            .filter(|r| r.identity.as_deref() == Some(identity))
+            .count()
+    }
+
     /// 更新连接请求计数
     pub fn increment_request_count(&self, id: &str) {
         if let Some(mut conn) = self.connections.get_mut(id) {
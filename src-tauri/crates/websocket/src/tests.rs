@@ -142,6 +142,27 @@ fn test_ws_connection_manager_register() {
     assert_eq!(manager.active_count(), 2);
 }
 
+#[test]
+fn test_ws_connection_manager_identity() {
+    let manager = WsConnectionManager::with_defaults();
+    let identity = api_key_identity("pc-test-api-key-123456");
+    assert!(!identity.contains("pc-test-api-key-123456"));
+
+    manager
+        .register_with_identity("conn-1".to_string(), None, Some(identity.clone()))
+        .unwrap();
+    manager.register("conn-2".to_string(), None).unwrap();
+    assert_eq!(
+        manager.get("conn-1").unwrap().identity,
+        Some(identity.clone())
+    );
+    assert_eq!(manager.count_by_identity(&identity), 1);
+
+    // 通过认证消息完成认证的连接
+    manager.set_identity("conn-2", identity.clone());
+    assert_eq!(manager.count_by_identity(&identity), 2);
+}
+
 #[test]
 fn test_ws_connection_manager_unregister() {
     let manager = WsConnectionManager::with_defaults();
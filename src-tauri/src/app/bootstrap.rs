@@ -86,9 +86,24 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
         &config.logging,
     )));
 
-    // 数据库
+    // 数据库（PROXYCAST_DB_PATH 环境变量优先于配置中的 database_path）
+    database::set_db_path_override(
+        config
+            .database_path
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+            .map(std::path::PathBuf::from),
+    );
     let db = database::init_database().map_err(|e| format!("数据库初始化失败: {e}"))?;
 
+    // 凭证密钥存储（按配置选择后端，并迁移已有密钥）
+    if let Err(e) = init_secret_store(&db, config) {
+        tracing::error!(
+            "[Bootstrap] 凭证密钥存储初始化失败，密钥位于外部存储的凭证将不可用: {}",
+            e
+        );
+    }
+
     // 初始化批量任务表
     if let Err(e) = proxycast_scheduler::BatchTaskDao::init_tables(&db) {
         tracing::warn!("[Bootstrap] 批量任务表初始化失败: {}", e);
@@ -99,6 +114,10 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     let skill_service_state = SkillServiceState(Arc::new(skill_service));
 
     let provider_pool_service = ProviderPoolService::new();
+    provider_pool_service
+        .health_alerts()
+        .set_config(config.health_alert.clone());
+    provider_pool_service.set_probe_models(config.health_probe.models.clone());
     let provider_pool_service_state = ProviderPoolServiceState(Arc::new(provider_pool_service));
 
     let api_key_provider_service = ApiKeyProviderService::new();
@@ -200,7 +219,9 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     let recording_service_state = create_recording_service_state();
 
     // 初始化 MCP 客户端管理器（延迟设置 AppHandle，在 setup hook 中完成）
-    let mcp_manager = crate::mcp::McpClientManager::new(None);
+    let mut mcp_manager = crate::mcp::McpClientManager::new(None);
+    mcp_manager
+        .set_credential_resolver(Arc::new(crate::mcp::DbCredentialResolver::new(db.clone())));
     let mcp_manager_state: McpManagerState = Arc::new(tokio::sync::Mutex::new(mcp_manager));
 
     Ok(AppStates {
@@ -237,6 +258,13 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     })
 }
 
+/// 初始化凭证密钥存储
+fn init_secret_store(db: &DbConnection, config: &Config) -> Result<(), String> {
+    let db_path = database::get_db_path()?;
+    let conn = database::lock_db(db)?;
+    database::secret_store::init_secret_store(&conn, &config.secret_storage, &db_path)
+}
+
 /// 初始化插件安装器
 fn init_plugin_installer() -> Result<PluginInstallerState, String> {
     let db_path = database::get_db_path().map_err(|e| format!("获取数据库路径失败: {e}"))?;
@@ -299,6 +327,7 @@ fn init_telemetry(
         retention_days: config.logging.retention_days,
         max_file_size: 10 * 1024 * 1024,
         enable_file_logging: config.logging.enabled,
+        sample_rate: config.telemetry.sample_rate,
     };
     let shared_logger = Arc::new(
         telemetry::RequestLogger::new(log_rotation)
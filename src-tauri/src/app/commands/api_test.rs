@@ -140,6 +140,11 @@ pub async fn check_api_compatibility(
                     }]),
                     tool_choice: None,
                     reasoning_effort: None,
+                    n: None,
+                    logprobs: None,
+                    top_logprobs: None,
+                    response_format: None,
+                    stream_options: None,
                 }
             }
             _ => {
@@ -162,6 +167,11 @@ pub async fn check_api_compatibility(
                     tools: None,
                     tool_choice: None,
                     reasoning_effort: None,
+                    n: None,
+                    logprobs: None,
+                    top_logprobs: None,
+                    response_format: None,
+                    stream_options: None,
                 }
             }
         };
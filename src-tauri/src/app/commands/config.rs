@@ -4,6 +4,7 @@
 
 use crate::app::types::{AppState, LogState};
 use crate::app::utils::is_valid_bind_host;
+use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
 use crate::config::{
     self,
     observer::{ConfigChangeEvent, RoutingChangeEvent},
@@ -17,10 +18,21 @@ pub async fn get_config(state: tauri::State<'_, AppState>) -> Result<config::Con
     Ok(s.config.clone())
 }
 
+/// 获取服务器实际生效的配置（含运行时修改，敏感信息已脱敏）
+#[tauri::command]
+pub async fn get_effective_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<config::Config, String> {
+    let s = state.read().await;
+    Ok(s.effective_config().await)
+}
+
 /// 保存配置
 #[tauri::command]
 pub async fn save_config(
     state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    pool_service: tauri::State<'_, ProviderPoolServiceState>,
     config: config::Config,
 ) -> Result<(), String> {
     let host = config.server.host.to_lowercase();
@@ -52,6 +64,16 @@ pub async fn save_config(
     match config::save_config(&config) {
         Ok(()) => {
             tracing::info!("[CONFIG] 配置保存成功: host={}", config.server.host);
+            // 日志脱敏规则立即生效，无需等待热重载
+            logs.write()
+                .await
+                .set_redactor(crate::logger::Redactor::from_config(
+                    &config.logging.redaction,
+                ));
+            // 健康检查探测模型同样立即生效
+            pool_service
+                .0
+                .set_probe_models(config.health_probe.models.clone());
             Ok(())
         }
         Err(e) => {
@@ -131,7 +153,8 @@ pub async fn get_endpoint_providers(
         "codex": ep.codex.clone(),
         "windsurf": ep.windsurf.clone(),
         "kiro": ep.kiro.clone(),
-        "other": ep.other.clone()
+        "other": ep.other.clone(),
+        "disabled": ep.disabled.clone()
     }))
 }
 
@@ -173,6 +196,7 @@ pub async fn set_endpoint_provider(
             windsurf: ep_config.windsurf.clone(),
             kiro: ep_config.kiro.clone(),
             other: ep_config.other.clone(),
+            disabled: ep_config.disabled.clone(),
             source: ConfigChangeSource::FrontendUI,
         },
     );
@@ -192,6 +216,170 @@ pub async fn set_endpoint_provider(
     Ok(provider_display.to_string())
 }
 
+/// 启用或停用端点 Provider
+///
+/// 停用后该客户端类型的聊天请求返回 503；配置会持久化，并通过观察者立即同步到运行中的服务器。
+#[tauri::command]
+pub async fn set_endpoint_enabled(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
+    endpoint: String,
+    enabled: bool,
+) -> Result<bool, String> {
+    let ep_config = {
+        let mut s = state.write().await;
+
+        if !s.config.endpoint_providers.set_enabled(&endpoint, enabled) {
+            return Err(format!("未知的客户端类型: {endpoint}"));
+        }
+
+        config::save_config(&s.config).map_err(|e| e.to_string())?;
+
+        s.config.endpoint_providers.clone()
+    };
+
+    let event = ConfigChangeEvent::EndpointProvidersChanged(
+        config::observer::EndpointProvidersChangeEvent {
+            cursor: ep_config.cursor.clone(),
+            claude_code: ep_config.claude_code.clone(),
+            codex: ep_config.codex.clone(),
+            windsurf: ep_config.windsurf.clone(),
+            kiro: ep_config.kiro.clone(),
+            other: ep_config.other.clone(),
+            disabled: ep_config.disabled.clone(),
+            source: ConfigChangeSource::FrontendUI,
+        },
+    );
+    config_manager.0.subject().notify_event(event).await;
+
+    let action = if enabled { "启用" } else { "停用" };
+    logs.write().await.add(
+        "info",
+        &format!("客户端 {endpoint} 的端点 Provider 已{action}"),
+    );
+
+    tracing::info!("[CONFIG] 端点 Provider 已{}: {}", action, endpoint);
+    Ok(enabled)
+}
+
+/// 获取配置档案列表
+#[tauri::command]
+pub async fn list_config_profiles(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let s = state.read().await;
+    let manager = config::ConfigManager::with_config(
+        s.config.clone(),
+        config::ConfigManager::default_config_path(),
+    );
+    Ok(serde_json::json!({
+        "profiles": manager.list_profiles(),
+        "active": manager.active_profile()
+    }))
+}
+
+/// 以当前设置创建配置档案
+#[tauri::command]
+pub async fn create_config_profile(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    name: String,
+) -> Result<(), String> {
+    let mut s = state.write().await;
+    let mut manager = config::ConfigManager::with_config(
+        s.config.clone(),
+        config::ConfigManager::default_config_path(),
+    );
+    manager.create_profile(&name).map_err(|e| e.to_string())?;
+    config::save_config(manager.config()).map_err(|e| e.to_string())?;
+    s.config = manager.config().clone();
+    drop(s);
+
+    logs.write()
+        .await
+        .add("info", &format!("已创建配置档案: {}", name.trim()));
+    Ok(())
+}
+
+/// 删除配置档案
+#[tauri::command]
+pub async fn delete_config_profile(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    name: String,
+) -> Result<(), String> {
+    let mut s = state.write().await;
+    let mut manager = config::ConfigManager::with_config(
+        s.config.clone(),
+        config::ConfigManager::default_config_path(),
+    );
+    manager.delete_profile(&name).map_err(|e| e.to_string())?;
+    config::save_config(manager.config()).map_err(|e| e.to_string())?;
+    s.config = manager.config().clone();
+    drop(s);
+
+    logs.write()
+        .await
+        .add("info", &format!("已删除配置档案: {name}"));
+    Ok(())
+}
+
+/// 切换配置档案
+///
+/// 切换后保存配置，并通过 GlobalConfigManager 通知观察者；运行中的服务器由配置文件
+/// 热重载更新处理器（路由、模型别名、注入规则）和附加 API 密钥等设置。
+/// 监听地址、端口和 `server.api_key` 不支持热重载，变更时记录需重启服务器的提示。
+#[tauri::command]
+pub async fn switch_config_profile(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
+    name: String,
+) -> Result<config::Config, String> {
+    let new_config = {
+        let mut s = state.write().await;
+        let mut manager = config::ConfigManager::with_config(
+            s.config.clone(),
+            config::ConfigManager::default_config_path(),
+        );
+        let new_config = manager
+            .switch_profile(&name)
+            .map_err(|e| e.to_string())?
+            .clone();
+        config::save_config(&new_config).map_err(|e| e.to_string())?;
+        let previous = std::mem::replace(&mut s.config, new_config.clone());
+        if s.running && requires_server_restart(&previous.server, &new_config.server) {
+            tracing::warn!(
+                "[CONFIG] 配置档案 {} 修改了监听地址、端口或 API 密钥，需重启服务器生效",
+                name
+            );
+            logs.write().await.add(
+                "warn",
+                &format!("配置档案 {name} 修改了监听地址、端口或 API 密钥，需重启服务器生效"),
+            );
+        }
+        new_config
+    };
+
+    // 释放锁后通知观察者
+    config_manager
+        .0
+        .update_config(new_config.clone(), ConfigChangeSource::FrontendUI)
+        .await;
+
+    logs.write()
+        .await
+        .add("info", &format!("已切换到配置档案: {name}"));
+    tracing::info!("[CONFIG] 已切换配置档案: {}", name);
+    Ok(new_config)
+}
+
+/// 服务器设置中不支持热重载的字段是否变更
+fn requires_server_restart(old: &config::ServerConfig, new: &config::ServerConfig) -> bool {
+    old.host != new.host || old.port != new.port || old.api_key != new.api_key
+}
+
 /// 根据 API Key Provider 更新环境变量
 ///
 /// 当用户在 API Server 页面选择一个 API Key Provider 时调用
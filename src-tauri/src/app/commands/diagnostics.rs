@@ -0,0 +1,75 @@
+//! 诊断命令
+//!
+//! 导出排障用的诊断包（脱敏配置、日志、凭证池健康、遥测摘要、版本与系统信息），
+//! 以及查看数据迁移报告和构建信息。
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::app::types::{AppState, LogState};
+use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
+use crate::commands::telemetry_cmd::TelemetryState;
+use crate::database::{lock_db, DbConnection};
+use proxycast_core::build_info::{build_info, BuildInfo};
+use proxycast_core::database::migration_report::{migrate, MigrationReport};
+use proxycast_core::diagnostics::{build_diagnostics_bundle, DiagnosticsInput};
+
+/// 诊断包导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsExportResult {
+    /// zip 内容（Base64 编码）
+    pub content: String,
+    /// 建议的文件名
+    pub suggested_filename: String,
+}
+
+/// 导出诊断包
+#[tauri::command]
+pub async fn export_diagnostics(
+    state: State<'_, AppState>,
+    logs: State<'_, LogState>,
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    telemetry: State<'_, TelemetryState>,
+) -> Result<DiagnosticsExportResult, String> {
+    let config = state.read().await.config.clone();
+    let logs = logs.read().await.get_logs();
+    let pool = pool_service.0.get_overview(&db).unwrap_or_else(|e| {
+        tracing::warn!("[DIAGNOSTICS] 获取凭证池概览失败: {}", e);
+        Vec::new()
+    });
+    let telemetry =
+        serde_json::to_value(telemetry.stats.read().summary(None)).map_err(|e| e.to_string())?;
+
+    let bytes = build_diagnostics_bundle(&DiagnosticsInput {
+        config: Some(&config),
+        logs: &logs,
+        pool: &pool,
+        telemetry,
+        app_version: env!("CARGO_PKG_VERSION"),
+    })
+    .map_err(|e| e.to_string())?;
+
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    Ok(DiagnosticsExportResult {
+        content: STANDARD.encode(bytes),
+        suggested_filename: format!("proxycast_diagnostics_{timestamp}.zip"),
+    })
+}
+
+/// 获取数据迁移报告
+///
+/// 始终在数据库副本上预演，列出待执行的迁移而不修改数据库；
+/// 实际迁移只在启动时由 `init_database` 执行。
+#[tauri::command]
+pub async fn get_migration_report(db: State<'_, DbConnection>) -> Result<MigrationReport, String> {
+    let conn = lock_db(&db)?;
+    migrate(&conn, true)
+}
+
+/// 获取构建信息（版本、git commit、构建时间、已启用的 features）
+#[tauri::command]
+pub fn get_build_info() -> BuildInfo {
+    build_info()
+}
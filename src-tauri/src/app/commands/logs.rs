@@ -1,6 +1,6 @@
 //! 日志命令
 //!
-//! 包含日志查询和清理命令。
+//! 包含日志查询、清理和运行时日志级别调整命令。
 
 use crate::app::types::LogState;
 use crate::logger;
@@ -17,3 +17,11 @@ pub async fn clear_logs(logs: tauri::State<'_, LogState>) -> Result<(), String>
     logs.write().await.clear();
     Ok(())
 }
+
+/// 运行时调整日志级别（不持久化）
+///
+/// `target` 为模块路径（如 `proxycast_server`），为空时调整默认级别。
+#[tauri::command]
+pub async fn set_log_level(target: String, level: String) -> Result<(), String> {
+    proxycast_core::log_filter::set_log_level(&target, &level)
+}
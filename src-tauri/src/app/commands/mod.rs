@@ -9,11 +9,13 @@
 //! - `gemini` - Gemini Provider 命令 (legacy)
 //! - `custom_providers` - 自定义 Provider 命令 (OpenAI/Claude Custom)
 //! - `logs` - 日志命令
+//! - `diagnostics` - 诊断包导出命令
 //! - `api_test` - API 测试和兼容性检查命令
 
 mod api_test;
 mod config;
 mod custom_providers;
+mod diagnostics;
 mod gemini;
 mod kiro;
 mod logs;
@@ -23,6 +25,7 @@ mod server;
 pub use api_test::*;
 pub use config::*;
 pub use custom_providers::*;
+pub use diagnostics::*;
 pub use gemini::*;
 pub use kiro::*;
 pub use logs::*;
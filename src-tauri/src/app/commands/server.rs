@@ -7,6 +7,7 @@ use crate::app::TokenCacheServiceState;
 use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
 use crate::commands::telemetry_cmd::TelemetryState;
 use crate::database;
+use proxycast_core::middleware::ShutdownReport;
 use proxycast_server as server;
 
 /// 启动服务器
@@ -39,15 +40,23 @@ pub async fn start_server(
 }
 
 /// 停止服务器
+///
+/// 等待在途请求排空后返回停机结果（排空/中止的请求数和耗时）
 #[tauri::command]
 pub async fn stop_server(
     state: tauri::State<'_, AppState>,
     logs: tauri::State<'_, LogState>,
-) -> Result<String, String> {
+) -> Result<ShutdownReport, String> {
     let mut s = state.write().await;
-    s.stop().await;
-    logs.write().await.add("info", "Server stopped");
-    Ok("Server stopped".to_string())
+    let report = s.stop().await;
+    logs.write().await.add(
+        "info",
+        &format!(
+            "Server stopped (drained {}, aborted {}, {}ms)",
+            report.drained_requests, report.aborted_requests, report.duration_ms
+        ),
+    );
+    Ok(report)
 }
 
 /// 获取服务器状态
@@ -24,7 +24,31 @@ use super::types::{AppState, TrayManagerState};
 /// 4. 注册所有 Tauri 命令
 /// 5. 启动应用
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
+/// 主 crate 编译时启用的 cargo features
+fn compiled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "custom-protocol") {
+        features.push("custom-protocol");
+    }
+    if cfg!(feature = "local-whisper") {
+        features.push("local-whisper");
+    }
+    if cfg!(feature = "notification") {
+        features.push("notification");
+    }
+    if cfg!(feature = "keychain") {
+        features.push("keychain");
+    }
+    features
+}
+
 pub fn run() {
+    // 初始化 tracing（带可运行时调整的日志级别过滤器，初始级别取自 RUST_LOG）
+    proxycast_core::log_filter::init_tracing(None);
+
+    // 登记主 crate 启用的 features，供版本信息端点返回
+    proxycast_core::build_info::register_features(&compiled_features());
+
     // 加载并验证配置
     let config = match bootstrap::load_and_validate_config() {
         Ok(cfg) => cfg,
@@ -35,6 +59,11 @@ pub fn run() {
         }
     };
 
+    // 未设置 RUST_LOG 时使用配置的日志级别
+    if let Err(e) = proxycast_core::log_filter::apply_config_level(&config.logging.level) {
+        tracing::warn!("[LOG_FILTER] 应用配置的日志级别失败: {}", e);
+    }
+
     // 初始化所有应用状态
     let states = match bootstrap::init_states(&config) {
         Ok(s) => s,
@@ -223,6 +252,17 @@ pub fn run() {
                 tracing::info!("[启动] MCP Manager 事件发射器已设置");
             }
 
+            // 设置凭证健康告警的事件发射器（用于发送 provider-health-alert 事件）
+            if let Some(pool_service) =
+                app.try_state::<commands::provider_pool_cmd::ProviderPoolServiceState>()
+            {
+                let emitter = proxycast_core::DynEmitter::new(crate::app::TauriEventEmitter(
+                    app.handle().clone(),
+                ));
+                pool_service.0.health_alerts().set_emitter(emitter);
+                tracing::info!("[启动] 凭证健康告警事件发射器已设置");
+            }
+
             // 初始化截图对话模块
             // _Requirements: 7.3_
             {
@@ -583,6 +623,17 @@ pub fn run() {
                 }
             });
 
+            // 定期同步凭证健康状态（含轮换提醒）到托盘
+            if let Some(tray_state) = app.try_state::<TrayManagerState<tauri::Wry>>() {
+                crate::tray::spawn_credential_health_sync(
+                    app.handle().clone(),
+                    tray_state.0.clone(),
+                    pool_service_clone.clone(),
+                    db_clone.clone(),
+                    std::time::Duration::from_secs(60),
+                );
+            }
+
             // 启动后台更新检查任务
             let app_handle_for_update = app.handle().clone();
             let update_service_for_task = update_check_service_clone.clone();
@@ -639,11 +690,17 @@ pub fn run() {
             app_commands::get_server_status,
             // Config commands (from app::commands)
             app_commands::get_config,
+            app_commands::get_effective_config,
             app_commands::save_config,
             app_commands::get_default_provider,
             app_commands::set_default_provider,
             app_commands::get_endpoint_providers,
             app_commands::set_endpoint_provider,
+            app_commands::set_endpoint_enabled,
+            app_commands::list_config_profiles,
+            app_commands::create_config_profile,
+            app_commands::delete_config_profile,
+            app_commands::switch_config_profile,
             app_commands::update_provider_env_vars,
             // Unified OAuth commands (new)
             commands::oauth_cmd::get_oauth_credentials,
@@ -676,6 +733,10 @@ pub fn run() {
             // Log commands (from app::commands)
             app_commands::get_logs,
             app_commands::clear_logs,
+            app_commands::set_log_level,
+            app_commands::export_diagnostics,
+            app_commands::get_migration_report,
+            app_commands::get_build_info,
             // API test commands (from app::commands)
             app_commands::test_api,
             app_commands::get_available_models,
@@ -734,6 +795,7 @@ pub fn run() {
             // MCP 资源管理命令
             commands::mcp_cmd::mcp_list_resources,
             commands::mcp_cmd::mcp_read_resource,
+            commands::mcp_cmd::mcp_read_resource_chunks,
             // Prompt commands
             commands::prompt_cmd::get_prompts,
             commands::prompt_cmd::upsert_prompt,
@@ -779,6 +841,7 @@ pub fn run() {
             commands::provider_pool_cmd::add_antigravity_oauth_credential,
             commands::provider_pool_cmd::add_openai_key_credential,
             commands::provider_pool_cmd::add_claude_key_credential,
+            commands::provider_pool_cmd::add_local_openai_credential,
             commands::provider_pool_cmd::add_gemini_api_key_credential,
             commands::provider_pool_cmd::add_codex_oauth_credential,
             commands::provider_pool_cmd::add_claude_oauth_credential,
@@ -787,6 +850,7 @@ pub fn run() {
             commands::provider_pool_cmd::debug_kiro_credentials,
             commands::provider_pool_cmd::test_user_credentials,
             commands::provider_pool_cmd::migrate_private_config_to_pool,
+            commands::provider_pool_cmd::import_credentials_from_dir,
             commands::provider_pool_cmd::start_antigravity_oauth_login,
             commands::provider_pool_cmd::get_antigravity_auth_url_and_wait,
             commands::provider_pool_cmd::get_codex_auth_url_and_wait,
@@ -862,6 +926,8 @@ pub fn run() {
             commands::telemetry_cmd::get_token_summary,
             commands::telemetry_cmd::get_token_stats_by_provider,
             commands::telemetry_cmd::get_token_stats_by_model,
+            commands::telemetry_cmd::get_token_stats_by_session,
+            commands::telemetry_cmd::get_token_stats_by_client,
             commands::telemetry_cmd::get_token_stats_by_day,
             // Injection commands
             commands::injection_cmd::get_injection_config,
@@ -870,6 +936,8 @@ pub fn run() {
             commands::injection_cmd::add_injection_rule,
             commands::injection_cmd::remove_injection_rule,
             commands::injection_cmd::update_injection_rule,
+            commands::injection_cmd::export_injection_rules,
+            commands::injection_cmd::import_injection_rules,
             // Usage commands
             commands::usage_cmd::get_kiro_usage,
             // Tray commands
@@ -1004,6 +1072,7 @@ pub fn run() {
             commands::connect_cmd::send_connect_callback,
             // Model Registry commands
             commands::model_registry_cmd::get_model_registry,
+            commands::model_registry_cmd::list_registry_models,
             commands::model_registry_cmd::get_model_registry_provider_ids,
             commands::model_registry_cmd::refresh_model_registry,
             commands::model_registry_cmd::get_model_host_alias_user_file_info,
@@ -1184,6 +1253,8 @@ pub fn run() {
             commands::template_cmd::delete_template,
             commands::template_cmd::set_default_template,
             commands::template_cmd::get_default_template,
+            // Batch commands
+            commands::batch_cmd::export_batch_results,
             // A2UI Form commands
             commands::a2ui_form_cmd::create_a2ui_form,
             commands::a2ui_form_cmd::get_a2ui_form,
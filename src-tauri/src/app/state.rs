@@ -190,6 +190,7 @@ pub fn init_telemetry_states(config: &Config) -> TelemetryStates {
         retention_days: config.logging.retention_days,
         max_file_size: 10 * 1024 * 1024,
         enable_file_logging: config.logging.enabled,
+        sample_rate: config.telemetry.sample_rate,
     };
     let shared_logger = Arc::new(
         telemetry::RequestLogger::new(log_rotation).expect("Failed to create RequestLogger"),
@@ -8,6 +8,7 @@ use crate::agent::aster_state::{ProviderConfig, SessionConfigBuilder};
 use crate::agent::{
     AsterAgentState, AsterAgentWrapper, SessionDetail, SessionInfo, TauriAgentEvent,
 };
+use crate::commands::tool_hooks::ToolHooksServiceState;
 use crate::database::dao::agent::AgentDao;
 use crate::database::DbConnection;
 use crate::mcp::{McpManagerState, McpServerConfig};
@@ -29,6 +30,9 @@ use async_trait::async_trait;
 use futures::StreamExt;
 use proxycast_agent::event_converter::convert_agent_event;
 use proxycast_services::mcp_service::McpService;
+use proxycast_services::tool_hooks_service::{
+    HookContext, HookDecision, PreToolHook, ToolHooksService, WorkspaceSandboxHook,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -220,15 +224,35 @@ pub struct AsterChatRequest {
 }
 
 /// 基于 aster::sandbox 的本地 bash 强隔离工具
-#[derive(Debug)]
+///
+/// 执行前后经过工具钩子：路径参数先由当前 workspace 的沙箱钩子约束到根目录内，
+/// 再经过全局前置钩子改写或拒绝，后置钩子可转换输出。
 struct WorkspaceSandboxedBashTool {
     delegate: BashTool,
     sandbox_type_name: String,
     base_sandbox_config: ProcessSandboxConfig,
+    workspace_hook: WorkspaceSandboxHook,
+    hooks: Arc<ToolHooksService>,
+    session_id: String,
+}
+
+impl std::fmt::Debug for WorkspaceSandboxedBashTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkspaceSandboxedBashTool")
+            .field("delegate", &self.delegate)
+            .field("sandbox_type_name", &self.sandbox_type_name)
+            .field("base_sandbox_config", &self.base_sandbox_config)
+            .field("session_id", &self.session_id)
+            .finish_non_exhaustive()
+    }
 }
 
 impl WorkspaceSandboxedBashTool {
-    fn new(workspace_root: &str) -> Result<Self, String> {
+    fn new(
+        workspace_root: &str,
+        hooks: Arc<ToolHooksService>,
+        session_id: &str,
+    ) -> Result<Self, String> {
         let workspace_root = workspace_root.trim();
         if workspace_root.is_empty() {
             return Err("workspace 根目录为空".to_string());
@@ -290,6 +314,9 @@ impl WorkspaceSandboxedBashTool {
             delegate: BashTool::new(),
             sandbox_type_name,
             base_sandbox_config,
+            workspace_hook: WorkspaceSandboxHook::new(workspace_root),
+            hooks,
+            session_id: session_id.to_string(),
         })
     }
 
@@ -327,6 +354,54 @@ impl WorkspaceSandboxedBashTool {
         config
     }
 
+    /// 构建工具钩子上下文（参数中的非字符串值按 JSON 文本传给钩子）
+    fn hook_context(&self, params: &serde_json::Value) -> HookContext {
+        let tool_parameters = params.as_object().map(|object| {
+            object
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect()
+        });
+        HookContext {
+            session_id: self.session_id.clone(),
+            tool_name: Some(self.name().to_string()),
+            tool_parameters,
+            tool_result: None,
+            message_content: None,
+            message_count: 0,
+            error_info: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// 将前置钩子改写后的参数写回 JSON 参数（原值不是字符串时按 JSON 解析）
+    fn apply_hook_parameters(
+        mut params: serde_json::Value,
+        hook_parameters: Option<&HashMap<String, String>>,
+    ) -> serde_json::Value {
+        let (Some(object), Some(hook_parameters)) = (params.as_object_mut(), hook_parameters)
+        else {
+            return params;
+        };
+        for (key, value) in hook_parameters {
+            let new_value = match object.get(key) {
+                Some(serde_json::Value::String(_)) | None => {
+                    serde_json::Value::String(value.clone())
+                }
+                Some(_) => serde_json::from_str(value)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.clone())),
+            };
+            object.insert(key.clone(), new_value);
+        }
+        params
+    }
+
     fn quote_shell(value: &str) -> String {
         format!("'{}'", value.replace('\'', "'\"'\"'"))
     }
@@ -423,6 +498,27 @@ impl Tool for WorkspaceSandboxedBashTool {
             return Err(ToolError::Cancelled);
         }
 
+        // workspace 沙箱钩子：路径参数必须解析到 workspace 根目录内
+        let mut hook_context = self.hook_context(&params);
+        match self.workspace_hook.before_tool_use(&hook_context) {
+            HookDecision::Proceed => {}
+            HookDecision::Modify(parameters) => hook_context.tool_parameters = Some(parameters),
+            HookDecision::Deny(reason) => {
+                return Err(ToolError::permission_denied(format!(
+                    "工具调用被拒绝 ({}): {}",
+                    self.workspace_hook.name(),
+                    reason
+                )));
+            }
+        }
+
+        // 工具前置钩子：可改写或拒绝参数
+        let hook_context = match self.hooks.before_tool_call(hook_context) {
+            Ok(hook_context) => hook_context,
+            Err(denied) => return Err(ToolError::permission_denied(denied.output)),
+        };
+        let params = Self::apply_hook_parameters(params, hook_context.tool_parameters.as_ref());
+
         let permission = self.check_permissions(&params, context).await;
         match permission.behavior {
             PermissionBehavior::Allow => {}
@@ -473,27 +569,27 @@ impl Tool for WorkspaceSandboxedBashTool {
         .map_err(|e| ToolError::execution_failed(format!("sandbox 执行失败: {e}")))?;
 
         let output = Self::format_output(&execution.stdout, &execution.stderr, execution.exit_code);
-        if execution.exit_code == 0 {
-            Ok(ToolResult::success(output)
-                .with_metadata("exit_code", serde_json::json!(execution.exit_code))
-                .with_metadata("stdout_length", serde_json::json!(execution.stdout.len()))
-                .with_metadata("stderr_length", serde_json::json!(execution.stderr.len()))
-                .with_metadata("sandboxed", serde_json::json!(execution.sandboxed))
-                .with_metadata(
-                    "sandbox_type",
-                    serde_json::json!(format!("{:?}", execution.sandbox_type)),
-                ))
+        let succeeded = execution.exit_code == 0;
+
+        // 工具后置钩子：成功时可转换输出
+        let outcome = self.hooks.after_tool_call(
+            hook_context,
+            if succeeded { Ok(output) } else { Err(output) },
+        );
+        let result = if succeeded {
+            ToolResult::success(outcome.output)
         } else {
-            Ok(ToolResult::error(output)
-                .with_metadata("exit_code", serde_json::json!(execution.exit_code))
-                .with_metadata("stdout_length", serde_json::json!(execution.stdout.len()))
-                .with_metadata("stderr_length", serde_json::json!(execution.stderr.len()))
-                .with_metadata("sandboxed", serde_json::json!(execution.sandboxed))
-                .with_metadata(
-                    "sandbox_type",
-                    serde_json::json!(format!("{:?}", execution.sandbox_type)),
-                ))
-        }
+            ToolResult::error(outcome.output)
+        };
+        Ok(result
+            .with_metadata("exit_code", serde_json::json!(execution.exit_code))
+            .with_metadata("stdout_length", serde_json::json!(execution.stdout.len()))
+            .with_metadata("stderr_length", serde_json::json!(execution.stderr.len()))
+            .with_metadata("sandboxed", serde_json::json!(execution.sandboxed))
+            .with_metadata(
+                "sandbox_type",
+                serde_json::json!(format!("{:?}", execution.sandbox_type)),
+            ))
     }
 }
 
@@ -501,6 +597,8 @@ impl Tool for WorkspaceSandboxedBashTool {
 async fn apply_workspace_sandbox_permissions(
     state: &AsterAgentState,
     workspace_root: &str,
+    hooks: Arc<ToolHooksService>,
+    session_id: &str,
 ) -> Result<(), String> {
     let workspace_root = workspace_root.trim();
     if workspace_root.is_empty() {
@@ -819,7 +917,7 @@ async fn apply_workspace_sandbox_permissions(
     }
     registry.set_permission_manager(Arc::new(permission_manager));
 
-    let workspace_bash_tool = WorkspaceSandboxedBashTool::new(workspace_root)?;
+    let workspace_bash_tool = WorkspaceSandboxedBashTool::new(workspace_root, hooks, session_id)?;
     let sandbox_type = workspace_bash_tool.sandbox_type().to_string();
     registry.register(Box::new(workspace_bash_tool));
 
@@ -847,6 +945,7 @@ pub async fn aster_agent_chat_stream(
     state: State<'_, AsterAgentState>,
     db: State<'_, DbConnection>,
     mcp_manager: State<'_, McpManagerState>,
+    tool_hooks: State<'_, ToolHooksServiceState>,
     request: AsterChatRequest,
 ) -> Result<(), String> {
     tracing::info!(
@@ -1023,7 +1122,7 @@ pub async fn aster_agent_chat_stream(
         return Err("Provider 未配置，请先调用 aster_agent_configure_provider".to_string());
     }
 
-    apply_workspace_sandbox_permissions(&state, &workspace_root)
+    apply_workspace_sandbox_permissions(&state, &workspace_root, tool_hooks.0.clone(), session_id)
         .await
         .map_err(|e| format!("注入本地 sandbox 失败: {e}"))?;
 
@@ -0,0 +1,32 @@
+//! 批量任务相关的 Tauri 命令
+//!
+//! 提供批量任务结果导出（CSV / JSONL）的前端 API。
+
+use tauri::State;
+use uuid::Uuid;
+
+use crate::database::DbConnection;
+use proxycast_scheduler::{BatchExportFormat, BatchTaskDao};
+
+/// 导出批量任务结果
+///
+/// 每个任务一行，包含任务 ID、状态、Token 用量、耗时和结果预览。
+///
+/// # 示例（前端调用）
+/// ```typescript
+/// const csv = await invoke('export_batch_results', {
+///   batchId: '...',
+///   format: 'csv',
+/// });
+/// ```
+#[tauri::command]
+pub async fn export_batch_results(
+    db: State<'_, DbConnection>,
+    batch_id: String,
+    format: BatchExportFormat,
+) -> Result<String, String> {
+    let id = Uuid::parse_str(&batch_id).map_err(|e| format!("无效的批量任务 ID: {e}"))?;
+    BatchTaskDao::export_batch_results(&db, &id, format)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("批量任务不存在: {batch_id}"))
+}
@@ -502,6 +502,11 @@ async fn generate_title_with_ai(prompt: &str, model: Option<&str>) -> Result<Str
         tools: None,
         tool_choice: None,
         reasoning_effort: None,
+        n: None,
+        logprobs: None,
+        top_logprobs: None,
+        response_format: None,
+        stream_options: None,
     };
 
     let resp = provider
@@ -1,7 +1,7 @@
 //! 参数注入相关命令
 
 use crate::config::{save_config, InjectionRuleConfig, InjectionSettings};
-use crate::injection::{InjectionMode, InjectionRule};
+use crate::injection::{export_rules, merge_rules, parse_bundle, InjectionMode, InjectionRule};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -176,3 +176,54 @@ pub async fn update_injection_rule(
     save_config(&s.config).map_err(|e| e.to_string())?;
     Ok(())
 }
+
+/// 导出注入规则包（JSON）
+#[tauri::command]
+pub async fn export_injection_rules(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let s = state.read().await;
+    let rules: Vec<InjectionRule> = s
+        .config
+        .injection
+        .rules
+        .iter()
+        .cloned()
+        .map(InjectionRule::from)
+        .collect();
+    Ok(export_rules(&rules))
+}
+
+/// 导入注入规则包
+///
+/// 规则包整体校验通过后才写入配置；`merge` 为 true 时按 ID 合并，否则替换全部规则。
+/// 返回导入的规则数。
+#[tauri::command]
+pub async fn import_injection_rules(
+    state: tauri::State<'_, AppState>,
+    json: String,
+    merge: bool,
+) -> Result<usize, String> {
+    let imported = parse_bundle(&json).map_err(|e| e.to_string())?;
+    let count = imported.len();
+
+    let mut s = state.write().await;
+    let current: Vec<InjectionRule> = s
+        .config
+        .injection
+        .rules
+        .iter()
+        .cloned()
+        .map(InjectionRule::from)
+        .collect();
+
+    // 先保存新配置，成功后再替换内存中的规则，避免半更新状态
+    let mut config = s.config.clone();
+    config.injection.rules = merge_rules(&current, imported, merge)
+        .iter()
+        .map(InjectionRuleConfig::from)
+        .collect();
+    save_config(&config).map_err(|e| e.to_string())?;
+    s.config = config;
+
+    tracing::info!("[INJECTION] 已导入 {} 条注入规则 (merge={})", count, merge);
+    Ok(count)
+}
@@ -33,6 +33,7 @@
 //! ## 资源管理命令
 //! - `mcp_list_resources`: 获取所有可用资源
 //! - `mcp_read_resource`: 读取资源内容
+//! - `mcp_read_resource_chunks`: 分块读取资源内容并以事件推送
 
 use crate::database::DbConnection;
 use crate::mcp::{
@@ -40,8 +41,9 @@ use crate::mcp::{
     McpResourceDefinition, McpServerConfig, McpServerInfo, McpToolDefinition, McpToolResult,
 };
 use crate::models::mcp_model::McpServer;
+use futures::StreamExt;
 use proxycast_services::mcp_service::McpService;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 use tracing::{debug, error, info};
 
 #[tauri::command]
@@ -511,3 +513,41 @@ pub async fn mcp_read_resource(
     info!(uri = %uri, "资源内容读取完成");
     Ok(result)
 }
+
+/// 分块读取资源内容
+///
+/// 不受缓冲上限约束，按块通过 `event_name` 事件推送 `McpResourceChunk`，
+/// 前端逐块拼接，避免大资源一次性经过 IPC。取到分块流后即释放管理器锁。
+///
+/// # Returns
+///
+/// 返回推送的分块数量。
+#[tauri::command]
+pub async fn mcp_read_resource_chunks(
+    app: AppHandle,
+    mcp_manager: State<'_, McpManagerState>,
+    uri: String,
+    event_name: String,
+) -> Result<usize, String> {
+    info!(uri = %uri, event = %event_name, "分块读取 MCP 资源内容命令");
+
+    let mut stream = {
+        let manager = mcp_manager.lock().await;
+        manager.read_resource_stream(&uri).await.map_err(|e| {
+            error!(uri = %uri, error = %e, "分块读取资源内容失败");
+            e.to_string()
+        })?
+    };
+
+    let mut count = 0;
+    while let Some(chunk) = stream.next().await {
+        app.emit(&event_name, &chunk).map_err(|e| {
+            error!(uri = %uri, error = %e, "推送资源分块失败");
+            e.to_string()
+        })?;
+        count += 1;
+    }
+
+    info!(uri = %uri, chunks = count, "资源内容分块推送完成");
+    Ok(count)
+}
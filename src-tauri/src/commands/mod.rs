@@ -4,6 +4,7 @@ pub mod api_key_provider_cmd;
 pub mod asr_cmd;
 pub mod aster_agent_cmd;
 pub mod auto_fix_cmd;
+pub mod batch_cmd;
 pub mod config_cmd;
 pub mod connect_cmd;
 pub mod connection_cmd;
@@ -3,7 +3,8 @@
 //! 提供模型注册表相关的前端 API
 
 use crate::models::model_registry::{
-    EnhancedModelMetadata, ModelSyncState, ModelTier, ProviderAliasConfig, UserModelPreference,
+    EnhancedModelMetadata, ModelListSnapshot, ModelSyncState, ModelTier, ProviderAliasConfig,
+    UserModelPreference,
 };
 use proxycast_services::model_registry_service::{FetchModelsResult, ModelRegistryService};
 use serde::Serialize;
@@ -34,6 +35,21 @@ pub async fn get_model_registry(
     Ok(service.get_all_models().await)
 }
 
+/// 从注册表数据库读取模型列表
+///
+/// 读取失败时返回最近一次成功读取的列表，并标记 `stale: true`
+#[tauri::command]
+pub async fn list_registry_models(
+    state: State<'_, ModelRegistryState>,
+) -> Result<ModelListSnapshot, String> {
+    let guard = state.read().await;
+    let service = guard
+        .as_ref()
+        .ok_or_else(|| "模型注册服务未初始化".to_string())?;
+
+    service.list_models().await
+}
+
 /// 获取模型注册表中所有 provider_id（去重且有序）
 ///
 /// provider_id 来源于 `src-tauri/resources/models/providers/*.json` 加载结果。
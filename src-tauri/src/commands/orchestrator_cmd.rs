@@ -202,8 +202,10 @@ pub async fn get_orchestrator_config() -> Result<OrchestratorConfig, String> {
 pub async fn update_orchestrator_config(config: OrchestratorConfig) -> Result<(), String> {
     let orchestrator = get_global_orchestrator().ok_or("编排器未初始化")?;
 
-    orchestrator.update_config(config).await;
-    Ok(())
+    orchestrator
+        .update_config(config)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 // ============================================================================
@@ -342,6 +344,15 @@ pub struct SelectionRequest {
     pub preferred_provider: Option<String>,
     pub excluded_models: Option<Vec<String>>,
     pub strategy_id: Option<String>,
+    /// 预估输入 tokens
+    #[serde(default)]
+    pub estimated_input_tokens: Option<u32>,
+    /// 预估输出 tokens
+    #[serde(default)]
+    pub estimated_output_tokens: Option<u32>,
+    /// 成本预算（美元）
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
 }
 
 /// 选择模型
@@ -383,6 +394,10 @@ pub async fn select_model(request: SelectionRequest) -> Result<SelectionResult,
         ctx.excluded_models = excluded;
     }
 
+    ctx.estimated_input_tokens = request.estimated_input_tokens;
+    ctx.estimated_output_tokens = request.estimated_output_tokens;
+    ctx.max_cost_usd = request.max_cost_usd;
+
     let result = if let Some(strategy_id) = &request.strategy_id {
         orchestrator.select_with_strategy(strategy_id, &ctx).await
     } else {
@@ -9,6 +9,7 @@ use crate::models::provider_pool_model::{
     PoolProviderType, ProviderCredential, ProviderPoolOverview, UpdateCredentialRequest,
 };
 use chrono::Utc;
+use proxycast_core::credential::{import_credential_dir, CredentialImportReport};
 use proxycast_credential::CredentialSyncService;
 use proxycast_services::provider_pool_service::ProviderPoolService;
 use std::fs;
@@ -401,7 +402,18 @@ pub fn update_provider_pool_credential(
         if let Some(not_supported_models) = request.not_supported_models {
             updated_cred.not_supported_models = not_supported_models;
         }
+        if let Some(preferred_models) = request.preferred_models {
+            updated_cred.preferred_models = preferred_models;
+        }
+        if let Some(days) = request.rotate_after_days {
+            updated_cred.rotate_after_days = (days > 0).then_some(days);
+        }
+        if let Some(max) = request.max_concurrency {
+            updated_cred.max_concurrency = (max > 0).then_some(max);
+        }
 
+        // 重新上传凭证文件视为一次轮换
+        updated_cred.mark_rotated(Utc::now());
         updated_cred.updated_at = Utc::now();
 
         // 保存到数据库
@@ -415,6 +427,11 @@ pub fn update_provider_pool_credential(
             .map_err(|e| e.to_string())?
             .ok_or_else(|| format!("凭证不存在: {uuid}"))?;
 
+        let request_rotates_key = request
+            .new_api_key
+            .as_deref()
+            .is_some_and(|key| !key.is_empty());
+
         // 更新 api_key 和 base_url
         match &mut current_credential.credential {
             CredentialData::OpenAIKey { api_key, base_url } => {
@@ -445,6 +462,20 @@ pub fn update_provider_pool_credential(
                     };
                 }
             }
+            CredentialData::LocalOpenAI { api_key, base_url } => {
+                if let Some(new_key) = request.new_api_key {
+                    *api_key = if new_key.is_empty() {
+                        None
+                    } else {
+                        Some(new_key)
+                    };
+                }
+                if let Some(new_url) = request.new_base_url {
+                    if !new_url.is_empty() {
+                        *base_url = new_url;
+                    }
+                }
+            }
             _ => {
                 return Err("只有 API Key 凭证支持修改 API Key 和 Base URL".to_string());
             }
@@ -472,7 +503,22 @@ pub fn update_provider_pool_credential(
         if let Some(not_supported_models) = request.not_supported_models {
             current_credential.not_supported_models = not_supported_models;
         }
+        if let Some(preferred_models) = request.preferred_models {
+            current_credential.preferred_models = preferred_models;
+        }
+        if let Some(days) = request.rotate_after_days {
+            current_credential.rotate_after_days = (days > 0).then_some(days);
+        }
+        if let Some(max) = request.max_concurrency {
+            current_credential.max_concurrency = (max > 0).then_some(max);
+        }
 
+        // 更换 API Key 视为一次轮换
+        if request_rotates_key {
+            current_credential.mark_rotated(Utc::now());
+        } else {
+            current_credential.needs_rotation = current_credential.is_rotation_due_at(Utc::now());
+        }
         current_credential.updated_at = Utc::now();
 
         // 保存到数据库
@@ -489,7 +535,10 @@ pub fn update_provider_pool_credential(
             request.check_health,
             request.check_model_name,
             request.not_supported_models,
+            request.preferred_models,
             request.new_proxy_url,
+            request.rotate_after_days,
+            request.max_concurrency,
         )?
     };
 
@@ -543,9 +592,19 @@ pub fn toggle_provider_pool_credential(
     uuid: String,
     is_disabled: bool,
 ) -> Result<ProviderCredential, String> {
-    pool_service
-        .0
-        .update_credential(&db, &uuid, None, Some(is_disabled), None, None, None, None)
+    pool_service.0.update_credential(
+        &db,
+        &uuid,
+        None,
+        Some(is_disabled),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
 }
 
 /// 重置凭证计数器
@@ -910,6 +969,28 @@ pub fn add_openai_key_credential(
     )
 }
 
+/// 添加本地 OpenAI 兼容服务凭证（Ollama、LM Studio、vLLM），API Key 可选
+#[tauri::command]
+pub fn add_local_openai_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    base_url: String,
+    api_key: Option<String>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    pool_service.0.add_credential(
+        &db,
+        "ollama",
+        CredentialData::LocalOpenAI {
+            base_url,
+            api_key: api_key.filter(|key| !key.is_empty()),
+        },
+        name,
+        Some(true),
+        None,
+    )
+}
+
 /// 添加 Claude API Key 凭证
 #[tauri::command]
 pub fn add_claude_key_credential(
@@ -1011,7 +1092,13 @@ pub async fn refresh_pool_credential_token(
     tracing::info!("[DEBUG] 开始刷新 Token for uuid: {}", uuid);
     let result = pool_service.0.refresh_credential_token(&db, &uuid).await;
     match &result {
-        Ok(msg) => tracing::info!("[DEBUG] Token 刷新成功: {}", msg),
+        Ok(msg) => {
+            tracing::info!("[DEBUG] Token 刷新成功: {}", msg);
+            // 手动刷新视为一次轮换（后台自动刷新不重置轮换计时）
+            if let Err(e) = pool_service.0.mark_rotated(&db, &uuid) {
+                tracing::warn!("[ROTATION] 重置凭证 {} 轮换计时失败: {}", uuid, e);
+            }
+        }
         Err(err) => tracing::error!("[DEBUG] Token 刷新失败: {}", err),
     }
     result
@@ -1242,6 +1329,50 @@ pub struct MigrationResultResponse {
     pub errors: Vec<String>,
 }
 
+/// 从 CLI 认证文件目录批量导入凭证
+///
+/// 扫描目录下的 JSON 文件（不递归），自动识别 Kiro / Gemini / Antigravity /
+/// Claude OAuth / Codex 凭证，复制到应用存储目录后加入凭证池；
+/// 凭证池中已存在的凭证（按刷新令牌指纹判断）会被跳过。
+#[tauri::command]
+pub fn import_credentials_from_dir(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    dir_path: String,
+) -> Result<CredentialImportReport, String> {
+    let dir = PathBuf::from(expand_tilde(&dir_path));
+    if !dir.is_dir() {
+        return Err(format!("目录不存在: {}", dir.display()));
+    }
+
+    let mut existing = pool_service.0.credential_fingerprints(&db)?;
+    let report = import_credential_dir(&dir, &mut existing, |detected| {
+        let provider_type = detected.provider_type.to_string();
+        let stored_file_path =
+            copy_and_rename_credential_file(&detected.path.to_string_lossy(), &provider_type)?;
+        pool_service
+            .0
+            .add_credential(
+                &db,
+                &provider_type,
+                detected.credential_data(stored_file_path),
+                None,
+                Some(true),
+                None,
+            )
+            .map(|_| ())
+    })?;
+
+    tracing::info!(
+        "[凭证导入] {}: 导入 {} 个，跳过 {} 个，失败 {} 个",
+        dir.display(),
+        report.imported,
+        report.skipped,
+        report.failed
+    );
+    Ok(report)
+}
+
 /// 获取 Antigravity OAuth 授权 URL 并等待回调（不自动打开浏览器）
 ///
 /// 启动服务器后通过事件发送授权 URL，然后等待回调
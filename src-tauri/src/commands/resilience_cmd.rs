@@ -1,6 +1,8 @@
 //! 容错配置相关 Tauri 命令
 
+use crate::config::JitterMode;
 use crate::resilience::{FailoverConfig, RetryConfig};
+use crate::AppState;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -38,6 +40,8 @@ pub struct RetryConfigDto {
     pub base_delay_ms: u64,
     pub max_delay_ms: u64,
     pub retryable_codes: Vec<u16>,
+    #[serde(default)]
+    pub jitter: JitterMode,
 }
 
 impl From<RetryConfig> for RetryConfigDto {
@@ -47,6 +51,7 @@ impl From<RetryConfig> for RetryConfigDto {
             base_delay_ms: config.base_delay_ms,
             max_delay_ms: config.max_delay_ms,
             retryable_codes: config.retryable_codes,
+            jitter: config.jitter,
         }
     }
 }
@@ -58,6 +63,7 @@ impl From<RetryConfigDto> for RetryConfig {
             base_delay_ms: dto.base_delay_ms,
             max_delay_ms: dto.max_delay_ms,
             retryable_codes: dto.retryable_codes,
+            jitter: dto.jitter,
         }
     }
 }
@@ -142,21 +148,38 @@ pub async fn update_failover_config(
 }
 
 /// 获取切换日志
+///
+/// 合并服务器熔断器的状态切换记录，`failure_type` 为 `circuit_<状态>`，
+/// 按时间先后排列。
 #[tauri::command]
 pub async fn get_switch_log(
     state: tauri::State<'_, ResilienceConfigState>,
+    app_state: tauri::State<'_, AppState>,
 ) -> Result<Vec<SwitchLogEntry>, String> {
-    let log = state.switch_log.read().await;
-    Ok(log.clone())
+    let mut entries = state.switch_log.read().await.clone();
+    if let Some(breaker) = app_state.read().await.circuit_breaker_ref.clone() {
+        entries.extend(breaker.transitions().into_iter().map(|t| SwitchLogEntry {
+            from_provider: t.provider.to_string(),
+            to_provider: t.provider.to_string(),
+            failure_type: format!("circuit_{}", t.to),
+            timestamp: t.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }));
+        entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    }
+    Ok(entries)
 }
 
 /// 清除切换日志
 #[tauri::command]
 pub async fn clear_switch_log(
     state: tauri::State<'_, ResilienceConfigState>,
+    app_state: tauri::State<'_, AppState>,
 ) -> Result<(), String> {
     let mut log = state.switch_log.write().await;
     log.clear();
+    if let Some(breaker) = &app_state.read().await.circuit_breaker_ref {
+        breaker.clear_transitions();
+    }
     Ok(())
 }
 
@@ -5,6 +5,7 @@ use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
 use crate::config;
 use crate::database::DbConnection;
 use crate::models::route_model::{RouteInfo, RouteListResponse};
+use proxycast_core::router::normalize_selector_prefix;
 
 /// 获取可访问的服务器地址
 ///
@@ -14,6 +15,11 @@ fn get_valid_base_url(config: &config::Config) -> String {
     get_accessible_url(&config.server.host, config.server.port)
 }
 
+/// 获取规范化后的选择器路由前缀（配置无效时回退为无前缀，与服务器启动时一致）
+fn get_selector_prefix(config: &config::Config) -> String {
+    normalize_selector_prefix(&config.server.selector_prefix).unwrap_or_default()
+}
+
 /// 获取所有可用的路由端点
 #[tauri::command]
 pub async fn get_available_routes(
@@ -23,11 +29,12 @@ pub async fn get_available_routes(
     // 获取配置中的服务器地址和默认 Provider
     let config = config::load_config().unwrap_or_default();
     let base_url = get_valid_base_url(&config);
+    let selector_prefix = get_selector_prefix(&config);
     let default_provider = config.default_provider.clone();
 
     let routes = pool_service
         .0
-        .get_available_routes(db.inner(), &base_url)
+        .get_available_routes(db.inner(), &base_url, &selector_prefix)
         .map_err(|e| e.to_string())?;
 
     // 添加默认路由，使用配置中的默认 Provider
@@ -49,6 +56,7 @@ pub async fn get_available_routes(
         ],
         tags: vec!["默认".to_string()],
         enabled: true,
+        capabilities: crate::models::route_model::provider_capabilities(&default_provider),
     }];
     all_routes.extend(routes);
 
@@ -68,11 +76,12 @@ pub async fn get_route_curl_examples(
 ) -> Result<Vec<crate::models::route_model::CurlExample>, String> {
     let config = config::load_config().unwrap_or_default();
     let base_url = get_valid_base_url(&config);
+    let selector_prefix = get_selector_prefix(&config);
     let default_provider = config.default_provider.clone();
 
     let routes = pool_service
         .0
-        .get_available_routes(db.inner(), &base_url)
+        .get_available_routes(db.inner(), &base_url, &selector_prefix)
         .map_err(|e| e.to_string())?;
 
     // 查找匹配的路由
@@ -86,8 +95,8 @@ pub async fn get_route_curl_examples(
         None => {
             // 生成默认路由的示例，使用配置中的默认 Provider
             let mut default_route = RouteInfo::new("default".to_string(), default_provider);
-            default_route.add_endpoint(&base_url, "claude");
-            default_route.add_endpoint(&base_url, "openai");
+            default_route.add_endpoint(&base_url, &selector_prefix, "claude");
+            default_route.add_endpoint(&base_url, &selector_prefix, "openai");
             Ok(default_route.generate_curl_examples(api_key))
         }
     }
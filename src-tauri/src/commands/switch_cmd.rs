@@ -1,7 +1,9 @@
 use crate::database::DbConnection;
 use crate::models::app_type::AppType;
 use crate::models::provider_model::Provider;
-use proxycast_services::live_sync::{check_config_sync, sync_from_external, SyncCheckResult};
+use proxycast_services::live_sync::{
+    check_config_sync, sync_from_external, SyncCheckResult, SyncConflict,
+};
 use proxycast_services::switch::SwitchService;
 use serde_json::Value;
 use tauri::State;
@@ -23,7 +25,10 @@ pub fn get_current_switch_provider(
 }
 
 #[tauri::command]
-pub fn add_switch_provider(db: State<'_, DbConnection>, provider: Provider) -> Result<(), String> {
+pub fn add_switch_provider(
+    db: State<'_, DbConnection>,
+    provider: Provider,
+) -> Result<Vec<SyncConflict>, String> {
     SwitchService::add_provider(&db, provider)
 }
 
@@ -31,7 +36,7 @@ pub fn add_switch_provider(db: State<'_, DbConnection>, provider: Provider) -> R
 pub fn update_switch_provider(
     db: State<'_, DbConnection>,
     provider: Provider,
-) -> Result<(), String> {
+) -> Result<Vec<SyncConflict>, String> {
     SwitchService::update_provider(&db, provider)
 }
 
@@ -50,7 +55,7 @@ pub async fn switch_provider(
     db: State<'_, DbConnection>,
     app_type: String,
     id: String,
-) -> Result<(), String> {
+) -> Result<Vec<SyncConflict>, String> {
     SwitchService::switch_provider_async(&db, &app_type, &id).await
 }
 
@@ -104,7 +109,15 @@ pub async fn sync_from_external_config(
         .map_err(|e| format!("Failed to sync from external: {e}"))?;
 
     // 切换到外部检测到的 provider
-    SwitchService::switch_provider_async(&db, &app_type, &external_provider).await?;
+    let conflicts =
+        SwitchService::switch_provider_async(&db, &app_type, &external_provider).await?;
+    if !conflicts.is_empty() {
+        let fields: Vec<&str> = conflicts.iter().map(|c| c.field.as_str()).collect();
+        return Ok(format!(
+            "已同步到外部配置的 provider: {external_provider}（以下字段存在冲突，已保留外部值: {}）",
+            fields.join(", ")
+        ));
+    }
 
     Ok(format!("已同步到外部配置的 provider: {external_provider}"))
 }
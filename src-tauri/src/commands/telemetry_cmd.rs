@@ -79,6 +79,7 @@ pub async fn get_request_logs(
     provider: Option<String>,
     model: Option<String>,
     status: Option<String>,
+    route: Option<String>,
     limit: Option<usize>,
 ) -> Result<Vec<RequestLog>, String> {
     let mut logs = state.logger.get_all();
@@ -107,6 +108,11 @@ pub async fn get_request_logs(
         logs.retain(|l| l.status == req_status);
     }
 
+    // 按路由过滤（`selector` 匹配所有选择器路由，`selector:<name>` 精确匹配）
+    if let Some(r) = route {
+        logs.retain(|l| l.matches_route(&r));
+    }
+
     // 按时间倒序排列
     logs.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
 
@@ -275,6 +281,46 @@ pub async fn get_token_stats_by_model(
     Ok(tokens.by_model(start, end))
 }
 
+/// 按会话分组 Token 统计
+#[tauri::command]
+pub async fn get_token_stats_by_session(
+    state: tauri::State<'_, TelemetryState>,
+    time_range: Option<TimeRangeParam>,
+) -> Result<HashMap<String, TokenStatsSummary>, String> {
+    let (start, end) = match time_range {
+        Some(r) => {
+            let range = r.to_time_range()?;
+            match range {
+                Some(tr) => (Some(tr.start), Some(tr.end)),
+                None => (None, None),
+            }
+        }
+        None => (None, None),
+    };
+    let tokens = state.tokens.read();
+    Ok(tokens.by_session(start, end))
+}
+
+/// 按客户端分组 Token 统计
+#[tauri::command]
+pub async fn get_token_stats_by_client(
+    state: tauri::State<'_, TelemetryState>,
+    time_range: Option<TimeRangeParam>,
+) -> Result<HashMap<String, TokenStatsSummary>, String> {
+    let (start, end) = match time_range {
+        Some(r) => {
+            let range = r.to_time_range()?;
+            match range {
+                Some(tr) => (Some(tr.start), Some(tr.end)),
+                None => (None, None),
+            }
+        }
+        None => (None, None),
+    };
+    let tokens = state.tokens.read();
+    Ok(tokens.by_client(start, end))
+}
+
 /// 按天汇总 Token 统计
 #[tauri::command]
 pub async fn get_token_stats_by_day(
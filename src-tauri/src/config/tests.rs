@@ -4,9 +4,10 @@
 
 use proptest::prelude::*;
 use proxycast_core::config::{
-    collapse_tilde, contains_tilde, expand_tilde, Config, ConfigManager, CustomProviderConfig,
-    HotReloadManager, InjectionSettings, LoggingConfig, ProviderConfig, ProvidersConfig,
-    ReloadResult, RetrySettings, RoutingConfig, ServerConfig, YamlService,
+    collapse_tilde, contains_tilde, expand_tilde, CircuitBreakerSettings, Config, ConfigManager,
+    CustomProviderConfig, HotReloadManager, InjectionSettings, JitterMode, LoggingConfig,
+    ProviderConfig, ProvidersConfig, ReloadResult, RetrySettings, RoutingConfig, ServerConfig,
+    YamlService,
 };
 use proxycast_core::config::{ContentCreatorConfig, NavigationConfig};
 use std::io::Write;
@@ -38,7 +39,31 @@ fn arb_server_config() -> impl Strategy<Value = ServerConfig> {
         host,
         port,
         api_key,
+        api_keys: Vec::new(),
         tls: proxycast_core::config::TlsConfig::default(),
+        upstream_pool: proxycast_core::config::UpstreamPoolConfig::default(),
+        validate_tools: true,
+        warmup: proxycast_core::config::WarmupConfig::default(),
+        session_quota: proxycast_core::config::SessionQuotaConfig::default(),
+        bind_interface: None,
+        route_aliases: std::collections::HashMap::new(),
+        stream_heartbeat_secs: 15,
+        emulate_multiple_choices: false,
+        max_concurrent_requests: 0,
+        concurrency_wait_ms: 5000,
+        coalesce_tool_calls: false,
+        provider_headers: std::collections::HashMap::new(),
+        response_post_processors: Vec::new(),
+        stream_idle_timeout_ms: 300_000,
+        strict_structured_output: false,
+        allow_upstream_override: false,
+        force_buffer_stream: false,
+        allow_self_upstream: false,
+        dedup_window_ms: 0,
+        stream_compat: proxycast_core::config::StreamCompatConfig::default(),
+        shutdown_drain_secs: 10,
+        selector_prefix: String::new(),
+        stream_coalesce: proxycast_core::config::StreamCoalesceConfig::default(),
     })
 }
 
@@ -117,6 +142,8 @@ fn arb_routing_config() -> impl Strategy<Value = RoutingConfig> {
         .prop_map(|(default_provider, model_aliases)| RoutingConfig {
             default_provider,
             model_aliases,
+            failover_chains: Vec::new(),
+            provider_protocols: std::collections::HashMap::new(),
         })
 }
 
@@ -133,7 +160,10 @@ fn arb_retry_settings() -> impl Strategy<Value = RetrySettings> {
                 max_retries,
                 base_delay_ms,
                 max_delay_ms,
+                jitter: JitterMode::default(),
                 auto_switch_provider,
+                provider_fallback: Default::default(),
+                circuit_breaker: CircuitBreakerSettings::default(),
             },
         )
 }
@@ -157,6 +187,7 @@ fn arb_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                redaction: proxycast_core::config::RedactionConfig::default(),
             },
         )
 }
@@ -177,14 +208,17 @@ fn arb_config() -> impl Strategy<Value = Config> {
             routing,
             retry,
             logging,
+            telemetry: proxycast_core::config::TelemetryConfig::default(),
             injection: InjectionSettings::default(),
             auth_dir: "~/.proxycast/auth".to_string(),
             credential_pool: proxycast_core::config::CredentialPoolConfig::default(),
+            credentials: proxycast_core::config::CredentialsConfig::default(),
             remote_management: proxycast_core::config::RemoteManagementConfig::default(),
             quota_exceeded: proxycast_core::config::QuotaExceededConfig::default(),
             proxy_url: None,
             ampcode: proxycast_core::config::AmpConfig::default(),
             endpoint_providers: proxycast_core::config::EndpointProvidersConfig::default(),
+            client_detection: proxycast_core::config::ClientDetectionConfig::default(),
             minimize_to_tray: true,
             models: proxycast_core::config::ModelsConfig::default(),
             agent: proxycast_core::config::NativeAgentConfig::default(),
@@ -198,6 +232,14 @@ fn arb_config() -> impl Strategy<Value = Config> {
             image_gen: proxycast_core::config::ImageGenConfig::default(),
             assistant: proxycast_core::config::AssistantConfig::default(),
             user_profile: proxycast_core::config::UserProfile::default(),
+            secret_storage: proxycast_core::config::SecretStorageConfig::default(),
+            profiles: std::collections::HashMap::new(),
+            active_profile: None,
+            database_path: None,
+            external_sync: proxycast_core::config::ExternalSyncConfig::default(),
+            health_alert: proxycast_core::config::HealthAlertConfig::default(),
+            health_probe: proxycast_core::config::HealthProbeConfig::default(),
+            anthropic: proxycast_core::config::AnthropicConfig::default(),
         })
 }
 
@@ -362,7 +404,31 @@ fn arb_valid_server_config() -> impl Strategy<Value = ServerConfig> {
         host,
         port,
         api_key,
+        api_keys: Vec::new(),
         tls: proxycast_core::config::TlsConfig::default(),
+        upstream_pool: proxycast_core::config::UpstreamPoolConfig::default(),
+        validate_tools: true,
+        warmup: proxycast_core::config::WarmupConfig::default(),
+        session_quota: proxycast_core::config::SessionQuotaConfig::default(),
+        bind_interface: None,
+        route_aliases: std::collections::HashMap::new(),
+        stream_heartbeat_secs: 15,
+        emulate_multiple_choices: false,
+        max_concurrent_requests: 0,
+        concurrency_wait_ms: 5000,
+        coalesce_tool_calls: false,
+        provider_headers: std::collections::HashMap::new(),
+        response_post_processors: Vec::new(),
+        stream_idle_timeout_ms: 300_000,
+        strict_structured_output: false,
+        allow_upstream_override: false,
+        force_buffer_stream: false,
+        allow_self_upstream: false,
+        dedup_window_ms: 0,
+        stream_compat: proxycast_core::config::StreamCompatConfig::default(),
+        shutdown_drain_secs: 10,
+        selector_prefix: String::new(),
+        stream_coalesce: proxycast_core::config::StreamCoalesceConfig::default(),
     })
 }
 
@@ -379,7 +445,10 @@ fn arb_valid_retry_settings() -> impl Strategy<Value = RetrySettings> {
                 max_retries,
                 base_delay_ms,
                 max_delay_ms,
+                jitter: JitterMode::default(),
                 auto_switch_provider,
+                provider_fallback: Default::default(),
+                circuit_breaker: CircuitBreakerSettings::default(),
             },
         )
 }
@@ -403,6 +472,7 @@ fn arb_valid_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                redaction: proxycast_core::config::RedactionConfig::default(),
             },
         )
 }
@@ -423,14 +493,17 @@ fn arb_valid_config() -> impl Strategy<Value = Config> {
             routing,
             retry,
             logging,
+            telemetry: proxycast_core::config::TelemetryConfig::default(),
             injection: InjectionSettings::default(),
             auth_dir: "~/.proxycast/auth".to_string(),
             credential_pool: proxycast_core::config::CredentialPoolConfig::default(),
+            credentials: proxycast_core::config::CredentialsConfig::default(),
             remote_management: proxycast_core::config::RemoteManagementConfig::default(),
             quota_exceeded: proxycast_core::config::QuotaExceededConfig::default(),
             proxy_url: None,
             ampcode: proxycast_core::config::AmpConfig::default(),
             endpoint_providers: proxycast_core::config::EndpointProvidersConfig::default(),
+            client_detection: proxycast_core::config::ClientDetectionConfig::default(),
             minimize_to_tray: true,
             models: proxycast_core::config::ModelsConfig::default(),
             agent: proxycast_core::config::NativeAgentConfig::default(),
@@ -444,6 +517,14 @@ fn arb_valid_config() -> impl Strategy<Value = Config> {
             image_gen: proxycast_core::config::ImageGenConfig::default(),
             assistant: proxycast_core::config::AssistantConfig::default(),
             user_profile: proxycast_core::config::UserProfile::default(),
+            secret_storage: proxycast_core::config::SecretStorageConfig::default(),
+            profiles: std::collections::HashMap::new(),
+            active_profile: None,
+            database_path: None,
+            external_sync: proxycast_core::config::ExternalSyncConfig::default(),
+            health_alert: proxycast_core::config::HealthAlertConfig::default(),
+            health_probe: proxycast_core::config::HealthProbeConfig::default(),
+            anthropic: proxycast_core::config::AnthropicConfig::default(),
         })
 }
 
@@ -480,14 +561,17 @@ fn arb_invalid_config() -> impl Strategy<Value = Config> {
                     routing,
                     retry,
                     logging,
+                    telemetry: proxycast_core::config::TelemetryConfig::default(),
                     injection: InjectionSettings::default(),
                     auth_dir: "~/.proxycast/auth".to_string(),
                     credential_pool: proxycast_core::config::CredentialPoolConfig::default(),
+                    credentials: proxycast_core::config::CredentialsConfig::default(),
                     remote_management: proxycast_core::config::RemoteManagementConfig::default(),
                     quota_exceeded: proxycast_core::config::QuotaExceededConfig::default(),
                     proxy_url: None,
                     ampcode: proxycast_core::config::AmpConfig::default(),
                     endpoint_providers: proxycast_core::config::EndpointProvidersConfig::default(),
+                    client_detection: proxycast_core::config::ClientDetectionConfig::default(),
                     minimize_to_tray: true,
                     models: proxycast_core::config::ModelsConfig::default(),
                     agent: proxycast_core::config::NativeAgentConfig::default(),
@@ -501,6 +585,14 @@ fn arb_invalid_config() -> impl Strategy<Value = Config> {
                     image_gen: proxycast_core::config::ImageGenConfig::default(),
                     assistant: proxycast_core::config::AssistantConfig::default(),
                     user_profile: proxycast_core::config::UserProfile::default(),
+                    secret_storage: proxycast_core::config::SecretStorageConfig::default(),
+                    profiles: std::collections::HashMap::new(),
+                    active_profile: None,
+                    database_path: None,
+                    external_sync: proxycast_core::config::ExternalSyncConfig::default(),
+                    health_alert: proxycast_core::config::HealthAlertConfig::default(),
+                    health_probe: proxycast_core::config::HealthProbeConfig::default(),
+                    anthropic: proxycast_core::config::AnthropicConfig::default(),
                 };
                 // 根据类型使配置无效
                 match invalid_type {
@@ -2401,6 +2493,7 @@ fn arb_endpoint_providers_config() -> impl Strategy<Value = EndpointProvidersCon
                 windsurf,
                 kiro,
                 other,
+                disabled: Vec::new(),
             }
         })
 }
@@ -166,6 +166,49 @@ async fn test_resolve_and_route() {
     assert_eq!(ctx.provider, None);
 }
 
+// ========== 配置档案测试 ==========
+
+#[tokio::test]
+async fn test_switch_profile_updates_processor() {
+    use crate::config::{Config, ConfigManager};
+
+    let pool_service = Arc::new(ProviderPoolService::new());
+    let processor = RequestProcessor::with_defaults(pool_service);
+
+    // 工作档案：Kiro；个人档案：Gemini + 模型别名
+    let mut config = Config::default();
+    config.routing.default_provider = "kiro".to_string();
+    let mut profiles =
+        ConfigManager::with_config(config, std::path::PathBuf::from("/tmp/test_config.yaml"));
+    profiles.create_profile("work").unwrap();
+    profiles.config_mut().routing.default_provider = "gemini".to_string();
+    profiles
+        .config_mut()
+        .routing
+        .model_aliases
+        .insert("fast".to_string(), "gemini-2.5-flash".to_string());
+    profiles.create_profile("personal").unwrap();
+
+    // 切换档案后走与热重载相同的处理器更新路径
+    let switched = profiles.switch_profile("work").unwrap().clone();
+    proxycast_server::update_processor_config(&processor, &switched).await;
+    let mut ctx = RequestContext::new("fast".to_string());
+    assert_eq!(
+        processor.resolve_and_route(&mut ctx).await,
+        Some(ProviderType::Kiro)
+    );
+    assert_eq!(ctx.resolved_model, "fast");
+
+    let switched = profiles.switch_profile("personal").unwrap().clone();
+    proxycast_server::update_processor_config(&processor, &switched).await;
+    let mut ctx = RequestContext::new("fast".to_string());
+    assert_eq!(
+        processor.resolve_and_route(&mut ctx).await,
+        Some(ProviderType::Gemini)
+    );
+    assert_eq!(ctx.resolved_model, "gemini-2.5-flash");
+}
+
 // ========== 属性测试 (Property-Based Tests) ==========
 
 use crate::telemetry::{RequestLog, RequestStatus};
@@ -272,7 +315,7 @@ proptest! {
 
         // 记录请求日志到 StatsAggregator
         {
-            let stats = processor.stats.write();
+            let stats = processor.stats.read();
             stats.record(log);
         }
 
@@ -10,7 +10,10 @@
 
 use super::state::{calculate_icon_status, CredentialHealth, TrayIconStatus, TrayStateSnapshot};
 use super::TrayManager;
+use crate::database::DbConnection;
+use proxycast_services::provider_pool_service::ProviderPoolService;
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::{AppHandle, Runtime};
 use tokio::sync::RwLock;
 use tracing::{debug, info};
@@ -229,3 +232,47 @@ impl<R: Runtime> TraySynchronizer<R> {
         Ok(())
     }
 }
+
+/// 启动凭证健康状态同步任务
+///
+/// 按 `interval` 间隔读取凭证池（不含已禁用的凭证），健康状态有变化时更新托盘，
+/// 超过轮换周期的凭证以警告状态显示。
+pub fn spawn_credential_health_sync<R: Runtime>(
+    app: AppHandle<R>,
+    tray_manager: Arc<RwLock<Option<TrayManager<R>>>>,
+    pool_service: Arc<ProviderPoolService>,
+    db: DbConnection,
+    interval: Duration,
+) -> tauri::async_runtime::JoinHandle<()> {
+    let synchronizer = TraySynchronizer::new(app, tray_manager);
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut last_summary = None;
+        loop {
+            ticker.tick().await;
+            let credentials = match pool_service.get_all_with_token_cache(&db) {
+                Ok(credentials) => credentials,
+                Err(e) => {
+                    debug!("读取凭证池失败，跳过托盘凭证状态同步: {}", e);
+                    continue;
+                }
+            };
+            let health: Vec<CredentialHealth> = credentials
+                .iter()
+                .filter(|c| !c.is_disabled)
+                .map(CredentialHealth::from_pool_credential)
+                .collect();
+            let summary = (
+                health.iter().filter(|c| c.is_valid).count(),
+                health.len(),
+                health.iter().filter(|c| c.has_warning()).count(),
+            );
+            if last_summary == Some(summary) {
+                continue;
+            }
+            if synchronizer.update_credential_health(&health).await.is_ok() {
+                last_summary = Some(summary);
+            }
+        }
+    })
+}
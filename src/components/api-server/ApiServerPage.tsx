@@ -441,9 +441,18 @@ export function ApiServerPage({ hideHeader = false }: ApiServerPageProps) {
   const handleStop = async () => {
     setLoading(true);
     try {
-      await stopServer();
+      const report = await stopServer();
       await fetchStatus();
-      setMessage({ type: "success", text: "服务已停止" });
+      const summary =
+        report.aborted_requests > 0
+          ? `，完成 ${report.drained_requests} 个请求，中止 ${report.aborted_requests} 个请求`
+          : report.drained_requests > 0
+            ? `，完成 ${report.drained_requests} 个请求`
+            : "";
+      setMessage({
+        type: "success",
+        text: `服务已停止（耗时 ${report.duration_ms}ms${summary}）`,
+      });
     } catch (e: unknown) {
       const errMsg = e instanceof Error ? e.message : String(e);
       setError(errMsg);
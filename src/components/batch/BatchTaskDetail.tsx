@@ -15,7 +15,11 @@ import {
   RefreshCw,
 } from "lucide-react";
 import type { BatchTaskDetail as BatchTaskDetailType } from "@/lib/api/batch";
-import { getBatchTask, cancelBatchTask } from "@/lib/api/batch";
+import {
+  getBatchTask,
+  cancelBatchTask,
+  subscribeBatchEvents,
+} from "@/lib/api/batch";
 
 interface Props {
   taskId: string;
@@ -209,12 +213,32 @@ export const BatchTaskDetail: React.FC<Props> = ({ taskId, onBack }) => {
     refresh();
   }, [refresh]);
 
-  // 自动刷新运行中的任务
+  // 运行中的任务通过进度事件实时刷新
+  const isActive =
+    detail?.batch_task.status === "running" ||
+    detail?.batch_task.status === "pending";
   useEffect(() => {
-    if (!detail || detail.batch_task.status !== "running") return;
-    const timer = setInterval(refresh, 2000);
-    return () => clearInterval(timer);
-  }, [detail, refresh]);
+    if (!isActive) return;
+    let unsubscribe: (() => void) | undefined;
+    let disposed = false;
+    subscribeBatchEvents(taskId, (event) => {
+      if (
+        event.event === "batch:task_completed" ||
+        event.event === "batch:finished"
+      ) {
+        refresh();
+      }
+    })
+      .then((close) => {
+        if (disposed) close();
+        else unsubscribe = close;
+      })
+      .catch((e) => console.error("[BatchTaskDetail] 订阅进度失败:", e));
+    return () => {
+      disposed = true;
+      unsubscribe?.();
+    };
+  }, [isActive, taskId, refresh]);
 
   const handleCancel = async () => {
     try {
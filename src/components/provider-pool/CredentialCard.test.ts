@@ -163,6 +163,7 @@ const oauthCredentialArbitrary: fc.Arbitrary<CredentialDisplay> = fc.record({
   usage_count: fc.nat({ max: 100000 }),
   error_count: fc.nat({ max: 10000 }),
   last_used: fc.option(validDateArbitrary, { nil: undefined }),
+  last_success_time: fc.option(validDateArbitrary, { nil: undefined }),
   last_error_time: fc.option(validDateArbitrary, { nil: undefined }),
   last_error_message: fc.option(fc.string({ minLength: 1, maxLength: 200 }), {
     nil: undefined,
@@ -26,6 +26,7 @@ import {
 import type {
   CredentialDisplay,
   CredentialSource,
+  HealthReason,
 } from "@/lib/api/providerPool";
 import {
   getKiroCredentialFingerprint,
@@ -37,6 +38,15 @@ import {
 import { usageApi, type UsageInfo } from "@/lib/api/usage";
 import { UsageDisplay } from "./UsageDisplay";
 
+const healthReasonLabels: Record<HealthReason, string> = {
+  auth_expired: "需要重新授权",
+  quota_exceeded: "额度耗尽或被限流",
+  network_error: "网络连接失败",
+  forbidden: "无权访问",
+  model_unavailable: "模型不可用",
+  unknown: "未知错误",
+};
+
 interface CredentialCardProps {
   credential: CredentialDisplay;
   onToggle: () => void;
@@ -291,7 +301,18 @@ export function CredentialCard({
 
   const isHealthy = credential.is_healthy && !credential.is_disabled;
   const hasError = credential.error_count > 0;
+  // 最后一次错误之后已有成功请求，错误仅作历史参考
+  const errorRecovered =
+    !!credential.last_error_time &&
+    !!credential.last_success_time &&
+    new Date(credential.last_success_time) >
+      new Date(credential.last_error_time);
   const isOAuth = credential.credential_type.includes("oauth");
+  const needsReauth =
+    credential.health_reason === "auth_expired" ||
+    !!credential.last_error_message?.includes("invalid_grant") ||
+    !!credential.last_error_message?.includes("重新授权") ||
+    !!credential.last_error_message?.includes("凭证已过期");
 
   return (
     <div
@@ -350,6 +371,18 @@ export function CredentialCard({
                 代理
               </span>
             )}
+            {credential.needs_rotation && (
+              <span
+                className="rounded-full px-2.5 py-1 text-xs font-medium inline-flex items-center gap-1.5 whitespace-nowrap bg-yellow-100 text-yellow-700 dark:bg-yellow-900/30 dark:text-yellow-400"
+                title={
+                  credential.rotation_due_at
+                    ? `已于 ${new Date(credential.rotation_due_at).toLocaleString()} 超过轮换周期`
+                    : undefined
+                }
+              >
+                需要轮换
+              </span>
+            )}
           </div>
         </div>
 
@@ -547,6 +580,9 @@ export function CredentialCard({
               <div className="font-medium text-sm">
                 {formatDate(credential.last_used)}
               </div>
+              <div className="text-xs text-muted-foreground">
+                最后成功 {formatDate(credential.last_success_time)}
+              </div>
             </div>
           </div>
 
@@ -667,6 +703,13 @@ export function CredentialCard({
             <span className="text-xs text-muted-foreground">最后使用:</span>
             <span className="text-sm">{formatDate(credential.last_used)}</span>
           </div>
+          <div className="flex items-center gap-2 col-span-2">
+            <Clock className="h-4 w-4 text-green-500" />
+            <span className="text-xs text-muted-foreground">最后成功:</span>
+            <span className="text-sm">
+              {formatDate(credential.last_success_time)}
+            </span>
+          </div>
         </div>
       </div>
 
@@ -674,29 +717,38 @@ export function CredentialCard({
       {credential.last_error_message && (
         <div
           className={`mx-4 mb-3 rounded-lg p-3 text-xs ${
-            credential.last_error_message.includes("invalid_grant") ||
-            credential.last_error_message.includes("重新授权") ||
-            credential.last_error_message.includes("凭证已过期")
-              ? "bg-amber-100 dark:bg-amber-900/30 border border-amber-300 dark:border-amber-700"
-              : "bg-red-100 dark:bg-red-900/30"
+            errorRecovered
+              ? "bg-muted/50"
+              : needsReauth
+                ? "bg-amber-100 dark:bg-amber-900/30 border border-amber-300 dark:border-amber-700"
+                : "bg-red-100 dark:bg-red-900/30"
           }`}
         >
+          {credential.last_error_time && (
+            <div className="mb-1 text-muted-foreground">
+              最后错误 {formatDate(credential.last_error_time)}
+              {errorRecovered && "（之后已恢复）"}
+              {!errorRecovered && credential.health_reason && (
+                <span className="ml-2 font-medium">
+                  · {healthReasonLabels[credential.health_reason]}
+                </span>
+              )}
+            </div>
+          )}
           <div
             className={`${
-              credential.last_error_message.includes("invalid_grant") ||
-              credential.last_error_message.includes("重新授权") ||
-              credential.last_error_message.includes("凭证已过期")
-                ? "text-amber-700 dark:text-amber-300"
-                : "text-red-700 dark:text-red-300"
+              errorRecovered
+                ? "text-muted-foreground"
+                : needsReauth
+                  ? "text-amber-700 dark:text-amber-300"
+                  : "text-red-700 dark:text-red-300"
             }`}
           >
             {credential.last_error_message.slice(0, 150)}
             {credential.last_error_message.length > 150 && "..."}
           </div>
           {/* 重新授权提示 */}
-          {(credential.last_error_message.includes("invalid_grant") ||
-            credential.last_error_message.includes("重新授权") ||
-            credential.last_error_message.includes("凭证已过期")) && (
+          {!errorRecovered && needsReauth && (
             <div className="mt-2 pt-2 border-t border-amber-300 dark:border-amber-700">
               <div className="flex items-center justify-between">
                 <span className="text-amber-600 dark:text-amber-400 font-medium">
@@ -66,6 +66,7 @@ const providerModels: Record<PoolProviderType, string[]> = {
     "gemini-2.5-flash-preview-09-2025",
     "gemini-3-pro-preview",
   ], // Gemini API Key
+  ollama: [], // 本地服务，模型从 /v1/models 发现
 };
 
 export function EditCredentialModal({
@@ -94,6 +95,12 @@ export function EditCredentialModal({
   const [proxyUrl, setProxyUrl] = useState("");
   const [proxyError, setProxyError] = useState<string | null>(null);
 
+  // 轮换周期（天），空字符串表示不提醒
+  const [rotateAfterDays, setRotateAfterDays] = useState("");
+
+  // 最大并发请求数，空字符串表示不限制
+  const [maxConcurrency, setMaxConcurrency] = useState("");
+
   // 初始化表单数据
   useEffect(() => {
     if (credential) {
@@ -119,6 +126,8 @@ export function EditCredentialModal({
       // 初始化代理 URL 为已保存的值
       setProxyUrl(credential.proxy_url || "");
       setProxyError(null);
+      setRotateAfterDays(credential.rotate_after_days?.toString() || "");
+      setMaxConcurrency(credential.max_concurrency?.toString() || "");
       setError(null);
     }
   }, [credential]);
@@ -218,6 +227,10 @@ export function EditCredentialModal({
         new_api_key: isApiKey ? newApiKey.trim() : undefined,
         // 代理 URL：始终传递当前值，空字符串表示清除代理
         new_proxy_url: proxyUrl.trim(),
+        // 轮换周期：始终传递当前值，0 表示清除
+        rotate_after_days: Math.max(0, parseInt(rotateAfterDays, 10) || 0),
+        // 最大并发数：始终传递当前值，0 表示不限制
+        max_concurrency: Math.max(0, parseInt(maxConcurrency, 10) || 0),
       };
 
       console.log("[EditCredentialModal] 提交更新请求:", updateRequest);
@@ -484,6 +497,43 @@ export function EditCredentialModal({
             </div>
           </div>
 
+          {/* 轮换提醒 */}
+          <div>
+            <label className="block text-sm font-medium mb-1.5">
+              轮换周期（天，可选）
+            </label>
+            <input
+              type="number"
+              min={0}
+              value={rotateAfterDays}
+              onChange={(e) => setRotateAfterDays(e.target.value)}
+              placeholder="例如: 30"
+              className="w-full rounded-lg border bg-background px-3 py-2 text-sm"
+            />
+            <p className="text-xs text-muted-foreground mt-1">
+              超过周期后提醒更换凭证（凭证仍可使用），重新上传凭证、更换 API Key
+              或手动刷新 Token 后重新计时。留空则不提醒
+            </p>
+          </div>
+
+          {/* 并发上限 */}
+          <div>
+            <label className="block text-sm font-medium mb-1.5">
+              最大并发请求数（可选）
+            </label>
+            <input
+              type="number"
+              min={0}
+              value={maxConcurrency}
+              onChange={(e) => setMaxConcurrency(e.target.value)}
+              placeholder="例如: 2"
+              className="w-full rounded-lg border bg-background px-3 py-2 text-sm"
+            />
+            <p className="text-xs text-muted-foreground mt-1">
+              同时处理的请求达到上限时暂不选择此凭证，请求结束后恢复。留空则不限制
+            </p>
+          </div>
+
           {/* 使用统计（只读） */}
           <div className="rounded-lg bg-muted/50 p-4">
             <label className="mb-3 block text-sm font-medium">使用统计</label>
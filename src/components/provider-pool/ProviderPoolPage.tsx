@@ -77,6 +77,7 @@ const providerLabels: Record<PoolProviderType, string> = {
   codex: "Codex (OpenAI)",
   claude_oauth: "Claude OAuth",
   gemini_api_key: "Gemini",
+  ollama: "Ollama",
 };
 
 // 判断是否为配置类型 tab
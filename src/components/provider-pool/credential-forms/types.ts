@@ -53,4 +53,5 @@ export const providerLabels: Record<PoolProviderType, string> = {
   codex: "Codex (OpenAI OAuth)",
   claude_oauth: "Claude OAuth",
   gemini_api_key: "Gemini API Key",
+  ollama: "Ollama / 本地服务",
 };
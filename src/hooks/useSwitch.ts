@@ -5,8 +5,18 @@ import {
   Provider,
   AppType,
   SyncCheckResult,
+  SyncConflict,
 } from "@/lib/api/switch";
 
+function warnSyncConflicts(conflicts: SyncConflict[]) {
+  if (conflicts.length === 0) return;
+  toast.warning(
+    `以下字段与外部配置冲突，已保留外部值: ${conflicts
+      .map((c) => c.field)
+      .join(", ")}`,
+  );
+}
+
 export function useSwitch(appType: AppType) {
   const [providers, setProviders] = useState<Provider[]>([]);
   const [currentProvider, setCurrentProvider] = useState<Provider | null>(null);
@@ -44,15 +54,17 @@ export function useSwitch(appType: AppType) {
       is_current: false,
       created_at: Date.now(),
     };
-    await switchApi.addProvider(newProvider);
+    const conflicts = await switchApi.addProvider(newProvider);
     await fetchProviders();
     toast.success("配置已添加");
+    warnSyncConflicts(conflicts);
   };
 
   const updateProvider = async (provider: Provider) => {
-    await switchApi.updateProvider(provider);
+    const conflicts = await switchApi.updateProvider(provider);
     await fetchProviders();
     toast.success("配置已更新");
+    warnSyncConflicts(conflicts);
   };
 
   const deleteProvider = async (id: string) => {
@@ -80,12 +92,13 @@ export function useSwitch(appType: AppType) {
       // 显示加载状态
       const loadingToast = toast.loading("正在切换配置...");
 
-      await switchApi.switchProvider(appType, id);
+      const conflicts = await switchApi.switchProvider(appType, id);
       void fetchProviders();
 
       // 关闭加载提示，显示成功消息
       toast.dismiss(loadingToast);
       toast.success("配置切换成功");
+      warnSyncConflicts(conflicts);
     } catch (e) {
       const errorMessage = e instanceof Error ? e.message : String(e);
       console.error("配置切换失败:", errorMessage);
@@ -16,11 +16,114 @@ export interface TlsConfig {
   key_path: string | null;
 }
 
+// Upstream HTTP Pool Configuration
+export interface UpstreamPoolConfig {
+  pool_max_idle_per_host: number;
+  pool_idle_timeout_secs: number;
+  tcp_keepalive_secs: number;
+}
+
+// Provider Warmup Configuration
+export interface WarmupConfig {
+  enabled: boolean;
+  max_credentials: number;
+  concurrency: number;
+  refresh_within_minutes: number;
+  /** 每个 Provider 类型至少保持预热的凭证数（0 表示不维持） */
+  min_warm_per_provider?: number;
+  /** 预热凭证数检查间隔（秒） */
+  warm_pool_interval_secs?: number;
+}
+
+export interface SessionQuotaConfig {
+  enabled: boolean;
+  max_requests: number;
+  max_tokens: number;
+  window_secs: number;
+}
+
+export interface StreamCompatConfig {
+  enabled: boolean;
+  padding_bytes: number;
+}
+
+export interface ServerApiKey {
+  key: string;
+  label: string;
+  enabled: boolean;
+}
+
+export interface StreamCoalesceConfig {
+  enabled: boolean;
+  max_bytes: number;
+  max_ms: number;
+}
+
+export interface SecretStorageConfig {
+  backend: "inline" | "sqlite" | "keychain";
+}
+
+export interface ExternalSyncConfig {
+  /** 外部配置文件被手动修改时：放弃写入，或合并互不重叠的条目 */
+  conflict_policy: "abort" | "merge_non_overlapping";
+  /** 切换 Provider 时字段冲突：以 ProxyCast 为准、以外部文件为准，或保留外部值交由用户处理 */
+  provider_conflict_policy?: "prefer_local" | "prefer_remote" | "manual";
+}
+
+export interface HealthAlertConfig {
+  /** 告警 Webhook 地址（为空时仅发送前端事件） */
+  webhook_url?: string;
+  /** 同一 Provider 两次通知之间的最小间隔（秒） */
+  debounce_secs: number;
+}
+
+export interface HealthProbeConfig {
+  /** Provider 类型到健康检查探测模型的映射（凭证自身的检查模型优先） */
+  models?: Record<string, string>;
+}
+
+export interface AnthropicConfig {
+  /** 是否把请求中的 metadata.user_id 转发给上游（关闭时剥离） */
+  forward_user_metadata?: boolean;
+  /** 转发前校验 user_id 是否为不透明标识，明显的个人信息会被拒绝 */
+  validate_user_metadata?: boolean;
+}
+
+/** 客户端识别规则：请求头包含特征字符串时识别为指定客户端 */
+export interface ClientSignatureRule {
+  id: string;
+  /** 匹配的请求头（默认 user-agent） */
+  header?: string;
+  /** 特征字符串（不区分大小写） */
+  pattern: string;
+  /** 用于选择端点 Provider 的客户端类型 */
+  client_type?: string;
+  /** 请求未指定模型时使用的模型 */
+  default_model?: string;
+  /** 仅应用的参数注入规则 ID */
+  injection_rules?: string[];
+}
+
+export interface ClientDetectionConfig {
+  rules?: ClientSignatureRule[];
+}
+
+export interface TelemetryConfig {
+  /** 成功请求写入详细日志的采样比例（0.0 - 1.0），失败请求始终记录 */
+  sample_rate: number;
+  /** 请求日志记录完整的凭证候选 / 过滤列表（默认只记录摘要） */
+  verbose_selection_trace?: boolean;
+}
+
 // Remote Management Configuration
 export interface RemoteManagementConfig {
   allow_remote: boolean;
   secret_key: string | null;
   disable_control_panel: boolean;
+  /** 是否要求 HMAC 请求签名（以 secret_key 为共享密钥） */
+  require_signature?: boolean;
+  /** 签名时间戳允许的最大偏差（秒） */
+  signature_max_skew_secs?: number;
 }
 
 // Quota Exceeded Configuration
@@ -100,6 +203,26 @@ export interface CredentialPoolConfig {
   iflow: IFlowCredentialEntry[];
 }
 
+// 凭证管理行为配置
+export interface CredentialsConfig {
+  /** 配置热重载时禁用已从凭证池配置中移除的凭证 */
+  prune_on_reload?: boolean;
+  /** 冷却恢复：因连续错误被标记为不健康的凭证在冷却期结束后自动恢复 */
+  cooldown_recovery?: CooldownRecoveryConfig;
+  /** 凭证选择策略：综合评分（默认）或最快优先 */
+  selection_strategy?: "score" | "fastest_first";
+}
+
+// 凭证冷却恢复配置
+export interface CooldownRecoveryConfig {
+  /** 是否自动恢复（关闭时需手动重置健康状态） */
+  enabled?: boolean;
+  /** 冷却时间（秒），从最后一次错误开始计算 */
+  cooldown_secs?: number;
+  /** 恢复前是否先通过健康检查，未通过时重新冷却 */
+  health_check?: boolean;
+}
+
 // API Key Entry
 export interface ApiKeyEntry {
   id: string;
@@ -264,12 +387,41 @@ export interface UserProfile {
   tags?: string[];
 }
 
+export type PostProcessorConfig =
+  | { type: "regex_redact"; pattern: string; replacement?: string }
+  | { type: "append_text"; text: string }
+  | { type: "strip_code_fences" };
+
 export interface Config {
   server: {
     host: string;
     port: number;
     api_key: string;
+    api_keys?: ServerApiKey[];
     tls: TlsConfig;
+    upstream_pool?: UpstreamPoolConfig;
+    validate_tools?: boolean;
+    warmup?: WarmupConfig;
+    session_quota?: SessionQuotaConfig;
+    bind_interface?: string;
+    route_aliases?: Record<string, string>;
+    stream_heartbeat_secs?: number;
+    emulate_multiple_choices?: boolean;
+    max_concurrent_requests?: number;
+    concurrency_wait_ms?: number;
+    coalesce_tool_calls?: boolean;
+    provider_headers?: Record<string, Record<string, string>>;
+    response_post_processors?: PostProcessorConfig[];
+    stream_idle_timeout_ms?: number;
+    strict_structured_output?: boolean;
+    allow_upstream_override?: boolean;
+    force_buffer_stream?: boolean;
+    allow_self_upstream?: boolean;
+    dedup_window_ms?: number;
+    stream_compat?: StreamCompatConfig;
+    shutdown_drain_secs?: number;
+    selector_prefix?: string;
+    stream_coalesce?: StreamCoalesceConfig;
   };
   providers: {
     kiro: {
@@ -301,6 +453,7 @@ export interface Config {
   quota_exceeded: QuotaExceededConfig;
   ampcode: AmpConfig;
   credential_pool: CredentialPoolConfig;
+  credentials?: CredentialsConfig;
   proxy_url: string | null;
   /** 关闭时最小化到托盘（而不是退出应用） */
   minimize_to_tray: boolean;
@@ -324,6 +477,39 @@ export interface Config {
   assistant?: AssistantConfig;
   /** 用户资料 */
   user_profile?: UserProfile;
+  /** 凭证密钥存储配置 */
+  secret_storage?: SecretStorageConfig;
+  /** 遥测配置 */
+  telemetry?: TelemetryConfig;
+  /** 命名配置档案 */
+  profiles?: Record<string, ConfigProfile>;
+  /** 当前激活的配置档案名称 */
+  active_profile?: string;
+  /** 数据库文件路径（修改后需重启） */
+  database_path?: string;
+  /** 外部应用配置（Prompt / MCP）同步配置 */
+  external_sync?: ExternalSyncConfig;
+  /** 凭证健康告警配置 */
+  health_alert?: HealthAlertConfig;
+  /** 健康检查探测模型配置 */
+  health_probe?: HealthProbeConfig;
+  anthropic?: AnthropicConfig;
+  /** 客户端识别配置 */
+  client_detection?: ClientDetectionConfig;
+}
+
+/** 配置档案：可切换的服务器、路由、注入和端点 Provider 设置，共享凭证池 */
+export interface ConfigProfile {
+  server: Config["server"];
+  default_provider: string;
+  routing: Record<string, unknown>;
+  injection: Record<string, unknown>;
+  endpoint_providers: EndpointProvidersConfig;
+}
+
+export interface ConfigProfilesInfo {
+  profiles: string[];
+  active: string | null;
 }
 
 export interface LogEntry {
@@ -336,7 +522,19 @@ export async function startServer(): Promise<string> {
   return safeInvoke("start_server");
 }
 
-export async function stopServer(): Promise<string> {
+/** 停机结果 */
+export interface ShutdownReport {
+  /** 是否执行了停止（服务器未运行时为 false） */
+  stopped: boolean;
+  /** 排空期间正常完成的请求数 */
+  drained_requests: number;
+  /** 排空期限到达时被中止的请求数 */
+  aborted_requests: number;
+  /** 停机耗时（毫秒） */
+  duration_ms: number;
+}
+
+export async function stopServer(): Promise<ShutdownReport> {
   return safeInvoke("stop_server");
 }
 
@@ -348,6 +546,11 @@ export async function getConfig(): Promise<Config> {
   return safeInvoke("get_config");
 }
 
+/** 获取服务器实际生效的配置（含运行时修改，敏感信息已脱敏） */
+export async function getEffectiveConfig(): Promise<Config> {
+  return safeInvoke("get_effective_config");
+}
+
 export async function saveConfig(config: Config): Promise<void> {
   return safeInvoke("save_config", { config });
 }
@@ -406,6 +609,67 @@ export async function clearLogs(): Promise<void> {
   }
 }
 
+/**
+ * 运行时调整日志级别（不持久化）
+ * @param target 模块路径，如 "proxycast_server"；为空时调整默认级别
+ * @param level trace | debug | info | warn | error | off
+ */
+export async function setLogLevel(target: string, level: string): Promise<void> {
+  await safeInvoke("set_log_level", { target, level });
+}
+
+export interface DiagnosticsExportResult {
+  /** zip 内容（Base64 编码） */
+  content: string;
+  suggested_filename: string;
+}
+
+/**
+ * 导出诊断包（脱敏配置、日志、凭证池健康、遥测摘要、版本与系统信息）
+ */
+export async function exportDiagnostics(): Promise<DiagnosticsExportResult> {
+  return safeInvoke("export_diagnostics");
+}
+
+export interface MigrationStep {
+  name: string;
+  description: string;
+  /** 是否执行（预演时表示将会执行） */
+  applied: boolean;
+  /** 影响的记录数 */
+  affected: number;
+  error?: string;
+}
+
+export interface MigrationReport {
+  dry_run: boolean;
+  steps: MigrationStep[];
+}
+
+/**
+ * 获取数据迁移报告（只预演，不修改数据库）
+ */
+export async function getMigrationReport(): Promise<MigrationReport> {
+  return safeInvoke("get_migration_report");
+}
+
+export interface BuildInfo {
+  version: string;
+  /** 构建时的 git commit，无法获取时为 "unknown" */
+  git_commit: string;
+  /** 构建时间（RFC 3339） */
+  build_timestamp: string;
+  /** 已启用的 cargo features */
+  features: string[];
+}
+
+/**
+ * 获取构建信息（版本、git commit、构建时间、已启用的 features）
+ */
+export async function getBuildInfo(): Promise<BuildInfo> {
+  return safeInvoke("get_build_info");
+}
+
 export interface TestResult {
   success: boolean;
   status: number;
@@ -644,6 +908,8 @@ export interface EndpointProvidersConfig {
   kiro?: string | null;
   /** 其他客户端使用的 Provider */
   other?: string | null;
+  /** 已停用的客户端类型 */
+  disabled?: string[];
 }
 
 /**
@@ -670,6 +936,48 @@ export async function setEndpointProvider(
   });
 }
 
+/**
+ * 启用或停用端点 Provider
+ * @param clientType 客户端类型 (cursor, claude_code, codex, windsurf, kiro, other)
+ * @param enabled 是否启用，停用后该客户端的请求返回 503
+ * @returns 设置后的启用状态
+ */
+export async function setEndpointEnabled(
+  clientType: string,
+  enabled: boolean,
+): Promise<boolean> {
+  return safeInvoke("set_endpoint_enabled", {
+    endpoint: clientType,
+    enabled,
+  });
+}
+
+// ============ 配置档案 ============
+
+export async function listConfigProfiles(): Promise<ConfigProfilesInfo> {
+  return safeInvoke("list_config_profiles");
+}
+
+/**
+ * 以当前设置创建配置档案
+ * @param name 档案名称
+ */
+export async function createConfigProfile(name: string): Promise<void> {
+  return safeInvoke("create_config_profile", { name });
+}
+
+export async function deleteConfigProfile(name: string): Promise<void> {
+  return safeInvoke("delete_config_profile", { name });
+}
+
+/**
+ * 切换配置档案，路由和注入设置立即生效
+ * @returns 切换后的配置
+ */
+export async function switchConfigProfile(name: string): Promise<Config> {
+  return safeInvoke("switch_config_profile", { name });
+}
+
 // Network Info
 export interface NetworkInfo {
   localhost: string;
@@ -34,9 +34,12 @@ export interface TaskDefinition {
   metadata?: Record<string, string>;
 }
 
+export type OnErrorPolicy = "stop_batch" | "continue_task" | "skip_remaining";
+
 export interface BatchOptions {
   concurrency?: number;
   continue_on_error?: boolean;
+  on_error?: OnErrorPolicy;
   retry_count?: number;
   timeout_seconds?: number;
 }
@@ -51,6 +54,8 @@ export interface TaskResult {
     completion_tokens: number;
     total_tokens: number;
   };
+  /** 完整响应（OpenAI 格式） */
+  response?: unknown;
   started_at: string;
   completed_at?: string;
 }
@@ -92,6 +97,19 @@ export interface BatchTaskDetail {
   statistics: BatchTaskStatistics;
 }
 
+export type BatchEvent =
+  | { event: "batch:task_started"; batch_id: string; task_id: string }
+  | { event: "batch:task_completed"; batch_id: string; result: TaskResult }
+  | { event: "batch:progress"; batch_id: string; done: number; total: number }
+  | { event: "batch:finished"; batch_id: string; status: BatchTask["status"] };
+
+const BATCH_EVENT_NAMES: BatchEvent["event"][] = [
+  "batch:task_started",
+  "batch:task_completed",
+  "batch:progress",
+  "batch:finished",
+];
+
 // ============================================================
 // API 方法
 // ============================================================
@@ -169,6 +187,23 @@ export async function getBatchTask(id: string): Promise<BatchTaskDetail> {
   return res.json();
 }
 
+export type BatchExportFormat = "csv" | "jsonl";
+
+/**
+ * 导出批量任务结果（每个任务一行）
+ */
+export async function exportBatchTask(
+  id: string,
+  format: BatchExportFormat = "csv",
+): Promise<string> {
+  const base = await getBaseUrl();
+  const res = await fetch(
+    `${base}/api/batch/tasks/${id}/export?format=${format}`,
+  );
+  if (!res.ok) throw new Error(await res.text());
+  return res.text();
+}
+
 export async function cancelBatchTask(id: string): Promise<void> {
   const base = await getBaseUrl();
   const res = await fetch(`${base}/api/batch/tasks/${id}`, {
@@ -176,3 +211,23 @@ export async function cancelBatchTask(id: string): Promise<void> {
   });
   if (!res.ok) throw new Error(await res.text());
 }
+
+/**
+ * 订阅批量任务进度事件（SSE）
+ *
+ * 收到 `batch:finished` 后自动关闭连接，返回取消订阅函数
+ */
+export async function subscribeBatchEvents(
+  id: string,
+  onEvent: (event: BatchEvent) => void,
+): Promise<() => void> {
+  const base = await getBaseUrl();
+  const source = new EventSource(`${base}/api/batch/tasks/${id}/events`);
+  const handler = (e: MessageEvent<string>) => {
+    const event = JSON.parse(e.data) as BatchEvent;
+    onEvent(event);
+    if (event.event === "batch:finished") source.close();
+  };
+  BATCH_EVENT_NAMES.forEach((name) => source.addEventListener(name, handler));
+  return () => source.close();
+}
@@ -49,4 +49,14 @@ export const injectionApi = {
   async getInjectionRules(): Promise<InjectionRule[]> {
     return safeInvoke("get_injection_rules");
   },
+
+  // Export injection rules as a JSON bundle
+  async exportInjectionRules(): Promise<string> {
+    return safeInvoke("export_injection_rules");
+  },
+
+  // Import injection rules from a JSON bundle (merge by id or replace all)
+  async importInjectionRules(json: string, merge: boolean): Promise<number> {
+    return safeInvoke("import_injection_rules", { json, merge });
+  },
 };
@@ -121,6 +121,16 @@ export interface McpResourceContent {
   blob?: string;
 }
 
+/** 资源内容分块（通过事件推送） */
+export interface McpResourceChunk {
+  uri: string;
+  mime_type?: string;
+  index: number;
+  text?: string;
+  blob?: string;
+  is_last: boolean;
+}
+
 // ============================================================================
 // API 封装
 // ============================================================================
@@ -210,4 +220,13 @@ export const mcpApi = {
   /** 读取资源内容 */
   readResource: (uri: string): Promise<McpResourceContent> =>
     safeInvoke("mcp_read_resource", { uri }),
+
+  /**
+   * 分块读取资源内容
+   *
+   * 分块通过 `eventName` 事件推送（用 `listen<McpResourceChunk>` 订阅），
+   * 返回分块总数。
+   */
+  readResourceChunks: (uri: string, eventName: string): Promise<number> =>
+    safeInvoke("mcp_read_resource_chunks", { uri, eventName }),
 };
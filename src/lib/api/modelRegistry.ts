@@ -7,6 +7,7 @@
 import { safeInvoke } from "@/lib/dev-bridge";
 import type {
   EnhancedModelMetadata,
+  ModelListSnapshot,
   ModelSyncState,
   ModelTier,
   ProviderAliasConfig,
@@ -20,6 +21,13 @@ export async function getModelRegistry(): Promise<EnhancedModelMetadata[]> {
   return safeInvoke("get_model_registry");
 }
 
+/**
+ * 从注册表数据库读取模型列表（读取失败时返回带 stale 标记的缓存）
+ */
+export async function listRegistryModels(): Promise<ModelListSnapshot> {
+  return safeInvoke("list_registry_models");
+}
+
 /**
  * 获取模型注册表中所有 provider_id
  */
@@ -100,6 +100,12 @@ export interface SelectionRequest {
   excluded_models?: string[];
   /** 策略 ID */
   strategy_id?: string;
+  /** 预估输入 tokens */
+  estimated_input_tokens?: number;
+  /** 预估输出 tokens */
+  estimated_output_tokens?: number;
+  /** 成本预算（美元），预估成本超出预算的模型不参与选择 */
+  max_cost_usd?: number;
 }
 
 /** 凭证信息请求 */
@@ -148,6 +154,18 @@ export interface TaskHintInfo {
   description: string;
 }
 
+/** 选择权重 */
+export interface SelectionWeights {
+  /** 成本权重（越便宜得分越高） */
+  cost: number;
+  /** 速度权重 */
+  latency: number;
+  /** 能力权重 */
+  capability: number;
+  /** 可用性权重（负载越低得分越高） */
+  availability: number;
+}
+
 /** 编排器配置 */
 export interface OrchestratorConfig {
   /** 默认服务等级 */
@@ -160,6 +178,8 @@ export interface OrchestratorConfig {
   load_balancing: boolean;
   /** 模型池刷新间隔（秒） */
   pool_refresh_interval: number;
+  /** 选择权重，设置后所有等级按加权评分选择模型 */
+  weights?: SelectionWeights | null;
 }
 
 // ============================================================================
@@ -9,7 +9,8 @@ export type PoolProviderType =
   | "claude"
   | "codex"
   | "claude_oauth"
-  | "gemini_api_key";
+  | "gemini_api_key"
+  | "ollama";
 
 // Credential data types
 export interface KiroOAuthCredential {
@@ -58,6 +59,13 @@ export interface ClaudeOAuthCredential {
   creds_file_path: string;
 }
 
+/** 本地 OpenAI 兼容服务（Ollama、LM Studio、vLLM），API Key 可选 */
+export interface LocalOpenAICredential {
+  type: "local_openai";
+  base_url: string;
+  api_key?: string;
+}
+
 export type CredentialData =
   | KiroOAuthCredential
   | GeminiOAuthCredential
@@ -66,7 +74,8 @@ export type CredentialData =
   | ClaudeKeyCredential
   | GeminiApiKeyCredential
   | CodexOAuthCredential
-  | ClaudeOAuthCredential;
+  | ClaudeOAuthCredential
+  | LocalOpenAICredential;
 
 // Provider credential
 export interface ProviderCredential {
@@ -79,9 +88,12 @@ export interface ProviderCredential {
   check_health: boolean;
   check_model_name?: string;
   not_supported_models: string[];
+  // 偏好的模型（支持通配符），请求匹配的模型时优先选择该凭证
+  preferred_models?: string[];
   usage_count: number;
   error_count: number;
   last_used?: string;
+  last_success_time?: string;
   last_error_time?: string;
   last_error_message?: string;
   last_health_check_time?: string;
@@ -93,6 +105,15 @@ export interface ProviderCredential {
 // Credential source type
 export type CredentialSource = "manual" | "imported" | "private";
 
+// Reason a credential is unhealthy
+export type HealthReason =
+  | "auth_expired"
+  | "quota_exceeded"
+  | "network_error"
+  | "forbidden"
+  | "model_unavailable"
+  | "unknown";
+
 // Credential display (for UI, hides sensitive data)
 export interface CredentialDisplay {
   uuid: string;
@@ -105,11 +126,16 @@ export interface CredentialDisplay {
   check_health: boolean;
   check_model_name?: string;
   not_supported_models: string[];
+  // 偏好的模型（支持通配符），请求匹配的模型时优先选择该凭证
+  preferred_models?: string[];
   usage_count: number;
   error_count: number;
   last_used?: string;
+  last_success_time?: string;
   last_error_time?: string;
   last_error_message?: string;
+  // 不健康的原因（由最后一次错误推断）
+  health_reason?: HealthReason;
   last_health_check_time?: string;
   last_health_check_model?: string;
   oauth_status?: OAuthStatus;
@@ -124,6 +150,14 @@ export interface CredentialDisplay {
   api_key?: string;
   // 凭证级代理 URL（可覆盖全局代理设置）
   proxy_url?: string;
+  // 轮换周期（天）
+  rotate_after_days?: number;
+  // 轮换到期时间
+  rotation_due_at?: string;
+  // 是否已超过轮换周期（仍可使用）
+  needs_rotation?: boolean;
+  // 最大并发请求数
+  max_concurrency?: number;
 }
 
 // Pool statistics
@@ -134,6 +168,8 @@ export interface PoolStats {
   disabled: number;
   total_usage: number;
   total_errors: number;
+  /** 预热凭证数（可用且 Token 新鲜） */
+  warm_count?: number;
 }
 
 // Provider pool overview
@@ -150,6 +186,7 @@ export interface HealthCheckResult {
   model?: string;
   message?: string;
   duration_ms: number;
+  reason?: HealthReason;
 }
 
 // OAuth status
@@ -187,6 +224,8 @@ export interface UpdateCredentialRequest {
   check_health?: boolean;
   check_model_name?: string;
   not_supported_models?: string[];
+  /// 偏好的模型（支持通配符），空数组表示清除
+  preferred_models?: string[];
   /// 新的凭证文件路径（仅适用于OAuth凭证，用于重新上传文件）
   new_creds_file_path?: string;
   /// OAuth相关：新的project_id（仅适用于Gemini）
@@ -197,6 +236,10 @@ export interface UpdateCredentialRequest {
   new_api_key?: string;
   /// 新的代理 URL（可覆盖全局代理设置）
   new_proxy_url?: string;
+  /// 轮换周期（天），0 表示清除
+  rotate_after_days?: number;
+  /// 最大并发请求数，0 表示不限制
+  max_concurrency?: number;
 }
 
 export const providerPoolApi = {
@@ -312,6 +355,14 @@ export const providerPoolApi = {
     return safeInvoke("add_claude_key_credential", { apiKey, baseUrl, name });
   },
 
+  async addLocalOpenAI(
+    baseUrl: string,
+    apiKey?: string,
+    name?: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("add_local_openai_credential", { baseUrl, apiKey, name });
+  },
+
   async addGeminiApiKey(
     apiKey: string,
     baseUrl?: string,
@@ -504,6 +555,13 @@ export const providerPoolApi = {
     return safeInvoke("migrate_private_config_to_pool", { config });
   },
 
+  // 从 CLI 认证文件目录批量导入凭证
+  async importCredentialsFromDir(
+    dirPath: string,
+  ): Promise<CredentialImportReport> {
+    return safeInvoke("import_credentials_from_dir", { dirPath });
+  },
+
   // 获取单个凭证的健康状态
   // Requirements: 4.4
   async getCredentialHealth(
@@ -560,6 +618,22 @@ export interface MigrationResult {
   errors: string[];
 }
 
+// 批量导入：单个文件的结果
+export interface FileImportResult {
+  path: string;
+  status: "imported" | "skipped" | "failed";
+  provider_type: PoolProviderType | null;
+  message: string | null;
+}
+
+// 批量导入汇总
+export interface CredentialImportReport {
+  imported: number;
+  skipped: number;
+  failed: number;
+  files: FileImportResult[];
+}
+
 // Kiro Builder ID 登录响应
 export interface KiroBuilderIdLoginResponse {
   success: boolean;
@@ -1,11 +1,15 @@
 import { safeInvoke } from "@/lib/dev-bridge";
 
+// Retry backoff jitter
+export type JitterMode = "additive" | "none" | "full" | "equal";
+
 // Retry configuration
 export interface RetryConfig {
   max_retries: number;
   base_delay_ms: number;
   max_delay_ms: number;
   retryable_codes: number[];
+  jitter?: JitterMode;
 }
 
 // Failover configuration
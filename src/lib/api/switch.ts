@@ -36,6 +36,13 @@ export interface SyncCheckResult {
   conflicts: ConfigConflict[];
 }
 
+// 同步到外部配置时待用户处理的字段冲突（manual 策略）
+export interface SyncConflict {
+  field: string;
+  local: unknown;
+  remote: unknown;
+}
+
 export const switchApi = {
   getProviders: (appType: AppType): Promise<Provider[]> =>
     safeInvoke("get_switch_providers", { appType }),
@@ -43,16 +50,16 @@ export const switchApi = {
   getCurrentProvider: (appType: AppType): Promise<Provider | null> =>
     safeInvoke("get_current_switch_provider", { appType }),
 
-  addProvider: (provider: Provider): Promise<void> =>
+  addProvider: (provider: Provider): Promise<SyncConflict[]> =>
     safeInvoke("add_switch_provider", { provider }),
 
-  updateProvider: (provider: Provider): Promise<void> =>
+  updateProvider: (provider: Provider): Promise<SyncConflict[]> =>
     safeInvoke("update_switch_provider", { provider }),
 
   deleteProvider: (appType: AppType, id: string): Promise<void> =>
     safeInvoke("delete_switch_provider", { appType, id }),
 
-  switchProvider: (appType: AppType, id: string): Promise<void> =>
+  switchProvider: (appType: AppType, id: string): Promise<SyncConflict[]> =>
     safeInvoke("switch_provider", { appType, id }),
 
   /** 读取当前生效的配置（从实际配置文件读取） */
@@ -24,6 +24,59 @@ export interface RequestLog {
   is_streaming: boolean;
   credential_id?: string;
   retry_count: number;
+  /** 分阶段耗时（毫秒） */
+  timing?: RequestTiming;
+  /** 识别到的客户端 ID */
+  client_id?: string;
+  /** 流式请求的首 token 耗时（毫秒） */
+  ttft_ms?: number;
+  /** 路由标签（default、selector:<name>、pool:<type>） */
+  route?: string;
+  /** 凭证选择追踪 */
+  selection_trace?: SelectionTrace;
+  /** 客户端使用的服务器 API 密钥标签 */
+  api_key_label?: string;
+}
+
+export type FilterReason =
+  | "disabled"
+  | "cooldown"
+  | "quota"
+  | "model_unsupported"
+  | "excluded"
+  | "client_incompatible"
+  | "not_preferred"
+  | "busy";
+
+export interface FilteredCandidate {
+  uuid: string;
+  name?: string;
+  reason: FilterReason;
+}
+
+export type SelectionFactor =
+  | { type: "only_candidate" }
+  | { type: "highest_score"; score: number }
+  | { type: "lowest_latency"; latency_ms: number }
+  | { type: "explore" }
+  | { type: "api_key_fallback" };
+
+/** 凭证选择追踪（candidates / filtered 仅在详细模式下记录） */
+export interface SelectionTrace {
+  provider_type: string;
+  candidate_count: number;
+  filtered_count: number;
+  candidates?: string[];
+  filtered?: FilteredCandidate[];
+  chosen?: string;
+  factor?: SelectionFactor;
+}
+
+export interface RequestTiming {
+  selection_ms: number;
+  refresh_ms: number;
+  upstream_ms: number;
+  total_ms: number;
 }
 
 export interface StatsSummary {
@@ -35,6 +88,10 @@ export interface StatsSummary {
   avg_latency_ms: number;
   min_latency_ms?: number;
   max_latency_ms?: number;
+  /** 平均首 token 耗时（毫秒） */
+  avg_ttft_ms?: number;
+  /** 首 token 耗时 P95（毫秒） */
+  p95_ttft_ms?: number;
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
@@ -50,6 +107,10 @@ export interface ProviderStats {
   avg_latency_ms: number;
   min_latency_ms?: number;
   max_latency_ms?: number;
+  /** 平均首 token 耗时（毫秒） */
+  avg_ttft_ms?: number;
+  /** 首 token 耗时 P95（毫秒） */
+  p95_ttft_ms?: number;
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
@@ -65,6 +126,10 @@ export interface ModelStats {
   avg_latency_ms: number;
   min_latency_ms?: number;
   max_latency_ms?: number;
+  /** 平均首 token 耗时（毫秒） */
+  avg_ttft_ms?: number;
+  /** 首 token 耗时 P95（毫秒） */
+  p95_ttft_ms?: number;
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
@@ -130,6 +195,8 @@ export async function getRequestLogs(params?: {
   provider?: string;
   model?: string;
   status?: RequestStatus;
+  /** 路由过滤（如 selector 或 selector:my-kiro） */
+  route?: string;
   limit?: number;
 }): Promise<RequestLog[]> {
   return safeInvoke("get_request_logs", params || {});
@@ -185,6 +252,18 @@ export async function getTokenStatsByModel(
   return safeInvoke("get_token_stats_by_model", { time_range: timeRange });
 }
 
+export async function getTokenStatsBySession(
+  timeRange?: TimeRangeParam,
+): Promise<Record<string, TokenStatsSummary>> {
+  return safeInvoke("get_token_stats_by_session", { time_range: timeRange });
+}
+
+export async function getTokenStatsByClient(
+  timeRange?: TimeRangeParam,
+): Promise<Record<string, TokenStatsSummary>> {
+  return safeInvoke("get_token_stats_by_client", { time_range: timeRange });
+}
+
 export async function getTokenStatsByDay(
   days?: number,
 ): Promise<PeriodTokenStats[]> {
@@ -49,6 +49,8 @@ const defaultMocks: Record<string, any> = {
       allow_remote: false,
       secret_key: null,
       disable_control_panel: false,
+      require_signature: false,
+      signature_max_skew_secs: 300,
     },
     quota_exceeded: {
       switch_project: true,
@@ -81,6 +83,7 @@ const defaultMocks: Record<string, any> = {
       },
     },
   }),
+  get_effective_config: () => defaultMocks.get_config(),
 
   save_config: (config: any) => {
     console.log("[Mock] Config saved:", config);
@@ -114,7 +117,12 @@ const defaultMocks: Record<string, any> = {
     uptime_secs: 0,
   }),
   start_server: () => "Server started (mock)",
-  stop_server: () => "Server stopped (mock)",
+  stop_server: () => ({
+    stopped: true,
+    drained_requests: 0,
+    aborted_requests: 0,
+    duration_ms: 0,
+  }),
 
   // 网络相关
   get_network_info: () => ({
@@ -231,6 +239,17 @@ const defaultMocks: Record<string, any> = {
   set_provider_ui_state: () => ({}),
   update_provider_sort_orders: () => ({ success: true }),
   export_api_key_providers: () => ({ config: "{}" }),
+  get_migration_report: () => ({
+    dry_run: true,
+    steps: [],
+  }),
+  get_build_info: () => ({
+    version: "0.0.0-mock",
+    git_commit: "unknown",
+    build_timestamp: "1970-01-01T00:00:00+00:00",
+    features: [],
+  }),
+  export_batch_results: () => "",
   import_api_key_providers: () => ({ success: true }),
   get_legacy_api_key_credentials: () => [],
   migrate_legacy_api_key_credentials: () => ({ success: true }),
@@ -244,6 +263,7 @@ const defaultMocks: Record<string, any> = {
   add_qwen_oauth_credential: () => ({ success: true }),
   add_openai_key_credential: () => ({ success: true }),
   add_claude_key_credential: () => ({ success: true }),
+  add_local_openai_credential: () => ({ success: true }),
   add_gemini_api_key_credential: () => ({ success: true }),
   add_antigravity_oauth_credential: () => ({ success: true }),
   add_codex_oauth_credential: () => ({ success: true }),
@@ -261,6 +281,12 @@ const defaultMocks: Record<string, any> = {
   refresh_pool_credential_token: () => ({ success: true }),
   get_pool_credential_oauth_status: () => ({ status: "unknown" }),
   migrate_private_config_to_pool: () => ({ success: true }),
+  import_credentials_from_dir: () => ({
+    imported: 0,
+    skipped: 0,
+    failed: 0,
+    files: [],
+  }),
   get_credential_health: () => ({ healthy: false }),
   get_all_credential_health: () => [],
   get_kiro_credential_fingerprint: () => ({ fingerprint: "" }),
@@ -291,6 +317,7 @@ const defaultMocks: Record<string, any> = {
 
   // 模型相关
   get_model_registry: () => [],
+  list_registry_models: () => ({ models: [], stale: false }),
   get_model_registry_provider_ids: () => [],
   refresh_model_registry: () => ({ success: true }),
   search_models: () => [],
@@ -337,9 +364,9 @@ const defaultMocks: Record<string, any> = {
 
   // Switch Provider 相关
   get_switch_providers: () => [],
-  add_switch_provider: () => ({ success: true }),
+  add_switch_provider: () => [],
   delete_switch_provider: () => ({ success: true }),
-  update_switch_provider: () => ({ success: true }),
+  update_switch_provider: () => [],
   get_current_switch_provider: () => null,
   read_live_provider_settings: () => ({}),
 
@@ -376,6 +403,8 @@ const defaultMocks: Record<string, any> = {
   get_token_summary: () => ({ summary: {} }),
   get_token_stats_by_provider: () => ({ stats: [] }),
   get_token_stats_by_model: () => ({ stats: [] }),
+  get_token_stats_by_session: () => ({}),
+  get_token_stats_by_client: () => ({}),
   get_token_stats_by_day: () => ({ stats: [] }),
 
   // Routes 相关
@@ -544,6 +573,7 @@ const defaultMocks: Record<string, any> = {
   // Endpoint Providers 相关
   get_endpoint_providers: () => ({}),
   set_endpoint_provider: () => ({ provider: "" }),
+  set_endpoint_enabled: () => true,
 
   // Experimental Features 相关
   get_experimental_config: () => ({
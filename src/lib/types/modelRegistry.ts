@@ -129,6 +129,13 @@ export interface ModelSyncState {
   last_error: string | null;
 }
 
+/** 模型列表读取结果 */
+export interface ModelListSnapshot {
+  models: EnhancedModelMetadata[];
+  /** 注册表读取失败时为 true，表示返回的是上次成功读取的缓存 */
+  stale: boolean;
+}
+
 /** 模型注册表状态 */
 export interface ModelRegistryState {
   /** 模型列表 */